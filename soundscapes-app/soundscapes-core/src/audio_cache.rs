@@ -0,0 +1,112 @@
+// Ambient audio cache - ambient files are decoded from bytes held in memory so playback never waits
+// on the disk. Hour-long ambiences made every cache entry (and every uncached play) pin the whole
+// file in RAM, so files at or above a size threshold are memory-mapped instead: the OS pages them
+// in as they play and can drop those pages again when memory is tight.
+use memmap2::Mmap;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::diagnostics::EngineStats;
+
+// Contents of an audio file, cheap to clone and hand to a decoder through a Cursor
+#[derive(Clone)]
+pub enum AudioBytes {
+    Memory(Arc<[u8]>),
+    Mapped(Arc<Mmap>),
+}
+
+impl AsRef<[u8]> for AudioBytes {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Memory(bytes) => bytes,
+            Self::Mapped(map) => map,
+        }
+    }
+}
+
+impl AudioBytes {
+    // Map the file if it is at least `mmap_threshold` bytes (0 = never), otherwise read it
+    pub fn read(path: impl AsRef<Path>, mmap_threshold: u64) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        if mmap_threshold > 0 && len >= mmap_threshold {
+            // Safety: the map is only read. A file truncated by another program while it plays makes
+            // reads past the new end fault, a risk taken for the library's own audio files.
+            let map = unsafe { Mmap::map(&file)? };
+            return Ok(Self::Mapped(Arc::new(map)));
+        }
+        let mut bytes = Vec::with_capacity(len as usize);
+        file.read_to_end(&mut bytes)?;
+        Ok(Self::Memory(Arc::from(bytes)))
+    }
+}
+
+pub struct AudioCache {
+    files: HashMap<String, AudioBytes>,
+    mmap_threshold: u64,
+}
+
+impl AudioCache {
+    pub fn new(mmap_threshold: u64) -> Self {
+        Self { files: HashMap::new(), mmap_threshold }
+    }
+
+    // Applies to files read from now on
+    pub fn set_mmap_threshold(&mut self, mmap_threshold: u64) {
+        self.mmap_threshold = mmap_threshold;
+    }
+
+    pub fn mmap_threshold(&self) -> u64 {
+        self.mmap_threshold
+    }
+
+    // Cached bytes of an ambient file; a miss is counted by whoever reads the file instead
+    pub fn cached(&self, path: &str, stats: &EngineStats) -> Option<AudioBytes> {
+        let bytes = self.files.get(path).cloned();
+        if bytes.is_some() {
+            stats.cache_hit();
+        }
+        bytes
+    }
+
+    // Cached bytes of an ambient file, or read from disk; both count towards the cache hit rate
+    pub fn get(&self, path: &str, stats: &EngineStats) -> Option<AudioBytes> {
+        if let Some(bytes) = self.cached(path, stats) {
+            return Some(bytes);
+        }
+        let started = Instant::now();
+        let bytes = AudioBytes::read(path, self.mmap_threshold).ok();
+        stats.cache_miss(started.elapsed());
+        bytes
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.files.contains_key(path)
+    }
+
+    // Preloaded bytes, read by a decode worker
+    pub fn insert(&mut self, path: String, bytes: AudioBytes) {
+        self.files.entry(path).or_insert(bytes);
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    // Bytes read into memory, and the number of mapped files (whose pages the OS manages)
+    pub fn usage(&self) -> (usize, usize) {
+        let mut bytes = 0;
+        let mut mapped = 0;
+        for file in self.files.values() {
+            match file {
+                AudioBytes::Memory(memory) => bytes += memory.len(),
+                AudioBytes::Mapped(_) => mapped += 1,
+            }
+        }
+        (bytes, mapped)
+    }
+}
@@ -0,0 +1,119 @@
+// Ambient decode workers - when an ambient sound moves on to its next file (A to B, B to A, after a
+// pause or a pitch change) the file was read and its decoder opened on the audio thread, stalling
+// fades and every other sound for as long as the disk took. Those reads and decodes now run on a few
+// worker threads and the finished decoders come back over a channel for the audio thread to queue.
+use parking_lot::Mutex;
+use rodio::Decoder;
+use std::io::Cursor;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use crate::audio_cache::AudioBytes;
+use crate::diagnostics::EngineStats;
+
+const WORKERS: usize = 2;
+
+pub type AmbientDecoder = Decoder<Cursor<AudioBytes>>;
+
+enum Job {
+    // Decode a file for an ambient sound; bytes are passed along when the cache already has them
+    Decode { ticket: u64, id: String, path: String, bytes: Option<AudioBytes>, mmap_threshold: u64 },
+    // Read a file for the cache
+    Read { path: String, mmap_threshold: u64 },
+}
+
+pub enum Done {
+    // source is None if the file couldn't be read or decoded
+    Decoded { ticket: u64, id: String, source: Option<AmbientDecoder> },
+    Read { path: String, bytes: AudioBytes },
+}
+
+pub struct DecodePool {
+    jobs: Sender<Job>,
+    done: Receiver<Done>,
+    next_ticket: u64,
+}
+
+impl DecodePool {
+    pub fn new(stats: Arc<EngineStats>) -> Self {
+        let (jobs, job_rx) = channel::<Job>();
+        let (done_tx, done) = channel();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+        for i in 0..WORKERS {
+            let job_rx = job_rx.clone();
+            let done_tx = done_tx.clone();
+            let stats = stats.clone();
+            thread::Builder::new()
+                .name(format!("ambient-decode-{}", i))
+                .spawn(move || loop {
+                    // The lock is only held while waiting, so the other workers pick up the next job
+                    let job = job_rx.lock().recv();
+                    let Ok(job) = job else { break };
+                    if let Some(done) = run(job, &stats) {
+                        if done_tx.send(done).is_err() {
+                            break;
+                        }
+                    }
+                })
+                .expect("failed to spawn ambient decode worker");
+        }
+        Self { jobs, done, next_ticket: 0 }
+    }
+
+    // Queue a decode and return its ticket; the audio thread only takes a result whose ticket the
+    // sound is still waiting for, so results for stopped or restarted sounds are dropped
+    pub fn decode(&mut self, id: &str, path: &str, bytes: Option<AudioBytes>, mmap_threshold: u64) -> u64 {
+        self.next_ticket += 1;
+        let ticket = self.next_ticket;
+        let _ = self.jobs.send(Job::Decode {
+            ticket,
+            id: id.to_string(),
+            path: path.to_string(),
+            bytes,
+            mmap_threshold,
+        });
+        ticket
+    }
+
+    pub fn read(&self, path: String, mmap_threshold: u64) {
+        let _ = self.jobs.send(Job::Read { path, mmap_threshold });
+    }
+
+    // Finished jobs, without waiting
+    pub fn finished(&self) -> impl Iterator<Item = Done> + '_ {
+        self.done.try_iter()
+    }
+}
+
+fn read(path: &str, mmap_threshold: u64, stats: &EngineStats) -> Option<AudioBytes> {
+    let started = Instant::now();
+    let bytes = AudioBytes::read(path, mmap_threshold).ok();
+    stats.cache_miss(started.elapsed());
+    bytes
+}
+
+fn run(job: Job, stats: &EngineStats) -> Option<Done> {
+    match job {
+        Job::Decode { ticket, id, path, bytes, mmap_threshold } => {
+            let source = bytes
+                .or_else(|| read(&path, mmap_threshold, stats))
+                .and_then(|bytes| stats.time_decode(|| Decoder::new(Cursor::new(bytes))).ok());
+            if source.is_none() {
+                log::warn!("Failed to decode ambient file {}", path);
+            }
+            Some(Done::Decoded { ticket, id, source })
+        }
+        Job::Read { path, mmap_threshold } => {
+            // A preload that fails is read again when the sound plays
+            match AudioBytes::read(&path, mmap_threshold) {
+                Ok(bytes) => Some(Done::Read { path, bytes }),
+                Err(e) => {
+                    log::warn!("Failed to preload ambient file {}: {}", path, e);
+                    None
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,300 @@
+// Troubleshooting data from the audio thread: snapshots of what it is actually holding, as opposed
+// to what the shared state says, so stuck fades and sounds nobody can stop show up in bug reports,
+// and performance counters for stutters
+use parking_lot::Mutex;
+use rodio::Sink;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::model::SchedulerState;
+
+// dump_debug_state result
+#[derive(Debug, Serialize)]
+pub struct DebugState {
+    #[serde(flatten)]
+    pub engine: EngineDebug,
+    pub scheduler: SchedulerState,
+    pub device: DeviceDebug,
+}
+
+// The part only the audio thread can fill in; it answers AudioCommand::DumpDebugState with this
+#[derive(Debug, Serialize)]
+pub struct EngineDebug {
+    pub music: MusicDebug,
+    pub ambients: Vec<AmbientDebug>,
+    // Fades and transitions for ids the audio thread has no sound for
+    pub orphan_fades: Vec<String>,
+    // Ids in get_active_ambients that the audio thread isn't playing, and the reverse
+    pub untracked_ambients: Vec<String>,
+    pub unreported_ambients: Vec<String>,
+    pub soundboard: Option<SinkDebug>,
+    pub ducking: DuckingDebug,
+    pub cache: CacheDebug,
+    pub scheduler_loop: SchedulerLoopDebug,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SinkDebug {
+    pub volume: f32,
+    pub paused: bool,
+    pub empty: bool,
+    // Sources queued in the sink, including the one playing
+    pub queued: usize,
+}
+
+impl SinkDebug {
+    pub fn of(sink: &Sink) -> Self {
+        Self { volume: sink.volume(), paused: sink.is_paused(), empty: sink.empty(), queued: sink.len() }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MusicDebug {
+    pub sink: Option<SinkDebug>,
+    // A skipped track still fading out
+    pub outgoing: Option<OutgoingDebug>,
+    pub track_duration: f64,
+    pub fade_out_active: bool,
+    // Seconds into the fade-in, and its length
+    pub fade_in: Option<(f32, f32)>,
+    pub crossfade_duration: f32,
+    pub pending_auto_advance: Option<String>,
+    pub loop_section: Option<(f64, f64)>,
+    pub track_gain: f32,
+    pub playlist_gain: f32,
+    pub volume: f32,
+    pub master_volume: f32,
+    pub muted: bool,
+    pub master_muted: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutgoingDebug {
+    pub sink: SinkDebug,
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AmbientDebug {
+    pub id: String,
+    pub file: String,
+    pub sink: SinkDebug,
+    pub loops_remaining: u32,
+    pub is_paused: bool,
+    pub pause_remaining: f64,
+    // Fade progress 0.0-1.0 (the scheduler's fades are the slow 2 second ones)
+    pub fading_in: Option<f32>,
+    pub fading_out: Option<f32>,
+    pub scheduler_fading_in: Option<f32>,
+    pub scheduler_fading_out: Option<f32>,
+    // (current, target) volume of a settings change in progress
+    pub volume_transition: Option<(f32, f32)>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuckingDebug {
+    pub progress: f32,
+    pub target: f32,
+    pub amount: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheDebug {
+    pub files: usize,
+    pub bytes: usize, // read into memory; mapped files aren't counted
+    pub mapped_files: usize,
+    pub loudness_entries: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SchedulerLoopDebug {
+    pub last_loaded_item: Option<usize>,
+    pub preset_pending: Option<String>,
+}
+
+// The backend always plays on the default device
+#[derive(Debug, Serialize)]
+pub struct DeviceDebug {
+    pub host: String,
+    pub default_output: Option<String>,
+}
+
+impl DeviceDebug {
+    pub fn current() -> Self {
+        use rodio::cpal::traits::{DeviceTrait, HostTrait};
+        let host = rodio::cpal::default_host();
+        Self {
+            host: host.id().name().to_string(),
+            default_output: host.default_output_device().and_then(|d| d.name().ok()),
+        }
+    }
+}
+
+// Audio thread performance counters for get_engine_stats, e.g. to put numbers on a stutter report.
+// The audio thread records into these as it runs; counting starts at launch or the last reset.
+pub struct EngineStats {
+    commands_sent: AtomicU64,
+    inner: Mutex<StatsInner>,
+}
+
+#[derive(Default)]
+struct StatsInner {
+    since: Option<Instant>,
+    commands_processed: u64,
+    max_queue_depth: u64,
+    loop_busy: Timing,
+    tick_interval: Timing,
+    late_ticks: u64,
+    last_tick: Option<Instant>,
+    decode: Timing,
+    disk_read: Timing,
+    cache_hits: u64,
+    cache_misses: u64,
+}
+
+// A tick half an interval later than scheduled is audible as a late fade step or a missed refill
+const LATE_TICK_FACTOR: f64 = 1.5;
+
+#[derive(Default, Clone, Copy)]
+struct Timing {
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl Timing {
+    fn record(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    fn view(&self) -> TimingStats {
+        TimingStats {
+            count: self.count,
+            avg_ms: if self.count > 0 { self.total.as_secs_f64() * 1000.0 / self.count as f64 } else { 0.0 },
+            max_ms: self.max.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+impl Default for EngineStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EngineStats {
+    pub fn new() -> Self {
+        Self {
+            commands_sent: AtomicU64::new(0),
+            inner: Mutex::new(StatsInner { since: Some(Instant::now()), ..Default::default() }),
+        }
+    }
+
+    pub fn command_sent(&self) {
+        self.commands_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Called as the audio thread takes a command off the queue
+    pub fn command_received(&self) {
+        let sent = self.commands_sent.load(Ordering::Relaxed);
+        let mut inner = self.inner.lock();
+        inner.max_queue_depth = inner.max_queue_depth.max(sent.saturating_sub(inner.commands_processed));
+        inner.commands_processed += 1;
+    }
+
+    // Time one loop iteration spent working, not waiting for commands
+    pub fn loop_busy(&self, duration: Duration) {
+        self.inner.lock().loop_busy.record(duration);
+    }
+
+    // A timed-out wait, i.e. a regular tick of the configured interval
+    pub fn tick(&self, expected: Duration) {
+        let now = Instant::now();
+        let mut inner = self.inner.lock();
+        if let Some(last) = inner.last_tick {
+            let interval = now - last;
+            inner.tick_interval.record(interval);
+            if interval > expected.mul_f64(LATE_TICK_FACTOR) {
+                inner.late_ticks += 1;
+            }
+        }
+        inner.last_tick = Some(now);
+    }
+
+    // Time probing a file and creating its decoder
+    pub fn time_decode<T>(&self, decode: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let decoder = decode();
+        self.inner.lock().decode.record(started.elapsed());
+        decoder
+    }
+
+    pub fn cache_hit(&self) {
+        self.inner.lock().cache_hits += 1;
+    }
+
+    pub fn cache_miss(&self, read: Duration) {
+        let mut inner = self.inner.lock();
+        inner.cache_misses += 1;
+        inner.disk_read.record(read);
+    }
+
+    pub fn reset(&self) {
+        // Commands still queued stay counted, so the depth stays right
+        let queued = self.commands_sent.load(Ordering::Relaxed).saturating_sub(self.inner.lock().commands_processed);
+        self.commands_sent.store(queued, Ordering::Relaxed);
+        *self.inner.lock() = StatsInner { since: Some(Instant::now()), ..Default::default() };
+    }
+
+    pub fn snapshot(&self) -> EngineStatsView {
+        let sent = self.commands_sent.load(Ordering::Relaxed);
+        let inner = self.inner.lock();
+        let lookups = inner.cache_hits + inner.cache_misses;
+        EngineStatsView {
+            seconds: inner.since.map(|s| s.elapsed().as_secs_f64()).unwrap_or(0.0),
+            loop_busy: inner.loop_busy.view(),
+            tick_interval: inner.tick_interval.view(),
+            late_ticks: inner.late_ticks,
+            commands_sent: sent,
+            commands_processed: inner.commands_processed,
+            queue_depth: sent.saturating_sub(inner.commands_processed),
+            max_queue_depth: inner.max_queue_depth,
+            decode: inner.decode.view(),
+            disk_read: inner.disk_read.view(),
+            cache_hits: inner.cache_hits,
+            cache_misses: inner.cache_misses,
+            cache_hit_rate: if lookups > 0 { inner.cache_hits as f64 / lookups as f64 } else { 0.0 },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimingStats {
+    pub count: u64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+// get_engine_stats result
+#[derive(Debug, Serialize)]
+pub struct EngineStatsView {
+    // Seconds covered, since launch or the last reset
+    pub seconds: f64,
+    pub loop_busy: TimingStats,
+    pub tick_interval: TimingStats,
+    pub late_ticks: u64,
+    pub commands_sent: u64,
+    pub commands_processed: u64,
+    pub queue_depth: u64,
+    pub max_queue_depth: u64,
+    pub decode: TimingStats,
+    // Ambient files read from disk because they weren't cached
+    pub disk_read: TimingStats,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub cache_hit_rate: f64,
+}
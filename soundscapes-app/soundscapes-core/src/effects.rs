@@ -0,0 +1,400 @@
+// Per-sound effect chain: rodio source wrappers for pan, low-pass and reverb
+use rodio::Source;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+// An effect setting that can be changed while the source plays; the source reads it once per frame
+#[derive(Clone)]
+pub struct EffectParam(Arc<AtomicU32>);
+
+impl EffectParam {
+    pub fn new(value: f32) -> Self {
+        Self(Arc::new(AtomicU32::new(value.to_bits())))
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, value: f32) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl From<f32> for EffectParam {
+    fn from(value: f32) -> Self {
+        Self::new(value)
+    }
+}
+
+// Pan, low-pass cutoff and reverb mix for one sound. Every source built from the same params follows
+// changes to them, so adjusting these doesn't need the sound restarted (pitch still does, since it
+// changes the playback speed).
+#[derive(Clone)]
+pub struct EffectParams {
+    pub pan: EffectParam,
+    pub low_pass_freq: EffectParam,
+    pub reverb_mix: EffectParam,
+}
+
+impl EffectParams {
+    pub fn new(pan: f32, low_pass_freq: f32, reverb_mix: f32) -> Self {
+        Self { pan: pan.into(), low_pass_freq: low_pass_freq.into(), reverb_mix: reverb_mix.into() }
+    }
+
+    pub fn set(&self, pan: f32, low_pass_freq: f32, reverb_mix: f32) {
+        self.pan.set(pan);
+        self.low_pass_freq.set(low_pass_freq);
+        self.reverb_mix.set(reverb_mix);
+    }
+
+    // Pan, then low-pass, then reverb
+    pub fn apply<S>(&self, source: S, sample_rate: u32) -> ReverbSource<LowPassSource<PannedSource<S>>>
+    where
+        S: Source<Item = f32>,
+    {
+        let source = PannedSource::new(source, self.pan.clone());
+        let source = LowPassSource::new(source, self.low_pass_freq.clone(), sample_rate);
+        ReverbSource::new(source, self.reverb_mix.clone(), sample_rate)
+    }
+}
+
+// Source wrapper for stereo panning (L/R balance)
+// pan: -1.0 = full left, 0.0 = center, 1.0 = full right
+pub struct PannedSource<S> {
+    inner: S,
+    param: EffectParam,
+    pan: f32,
+    channels: u16,
+    current_channel: u16,
+}
+
+impl<S> PannedSource<S>
+where
+    S: Source<Item = f32>,
+{
+    pub fn new(inner: S, pan: impl Into<EffectParam>) -> Self {
+        let channels = inner.channels();
+        let param = pan.into();
+        Self {
+            inner,
+            pan: param.get().clamp(-1.0, 1.0),
+            param,
+            channels,
+            current_channel: 0,
+        }
+    }
+}
+
+impl<S> Iterator for PannedSource<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.inner.next()?;
+        
+        // Only apply panning to stereo sources
+        if self.channels == 2 {
+            let channel = self.current_channel;
+            if channel == 0 {
+                self.pan = self.param.get().clamp(-1.0, 1.0);
+            }
+            self.current_channel = (self.current_channel + 1) % self.channels;
+            
+            // Calculate gain for this channel
+            // Left channel (0): full at pan=-1, half at pan=1
+            // Right channel (1): half at pan=-1, full at pan=1
+            let gain = if channel == 0 {
+                // Left channel: 1.0 when pan <= 0, decreases to 0 as pan -> 1
+                if self.pan <= 0.0 { 1.0 } else { 1.0 - self.pan }
+            } else {
+                // Right channel: 1.0 when pan >= 0, decreases to 0 as pan -> -1
+                if self.pan >= 0.0 { 1.0 } else { 1.0 + self.pan }
+            };
+            
+            Some(sample * gain)
+        } else {
+            Some(sample)
+        }
+    }
+}
+
+impl<S> Source for PannedSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.inner.total_duration()
+    }
+}
+
+// Source wrapper for low-pass filter (simple one-pole IIR filter)
+// cutoff_freq: 20 - 22000 Hz
+pub struct LowPassSource<S> {
+    inner: S,
+    param: EffectParam,
+    cutoff_freq: f32,
+    sample_rate: u32,
+    alpha: f32,
+    prev_samples: Vec<f32>, // One per channel
+    channels: u16,
+    current_channel: u16,
+}
+
+impl<S> LowPassSource<S>
+where
+    S: Source<Item = f32>,
+{
+    pub fn new(inner: S, cutoff_freq: impl Into<EffectParam>, sample_rate: u32) -> Self {
+        let channels = inner.channels();
+        let param = cutoff_freq.into();
+        let cutoff_freq = param.get();
+        
+        Self {
+            inner,
+            param,
+            cutoff_freq,
+            sample_rate,
+            alpha: low_pass_alpha(cutoff_freq, sample_rate),
+            prev_samples: vec![0.0; channels as usize],
+            channels,
+            current_channel: 0,
+        }
+    }
+}
+
+// Calculate filter coefficient using RC time constant approximation
+// alpha = dt / (RC + dt) where RC = 1 / (2 * pi * cutoff)
+fn low_pass_alpha(cutoff_freq: f32, sample_rate: u32) -> f32 {
+    let dt = 1.0 / sample_rate as f32;
+    let rc = 1.0 / (2.0 * std::f32::consts::PI * cutoff_freq.clamp(20.0, 22000.0));
+    dt / (rc + dt)
+}
+
+impl<S> Iterator for LowPassSource<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.inner.next()?;
+        let ch = self.current_channel as usize;
+        if ch == 0 {
+            let cutoff_freq = self.param.get();
+            if cutoff_freq != self.cutoff_freq {
+                // The filter state carries over, so the change doesn't click
+                self.cutoff_freq = cutoff_freq;
+                self.alpha = low_pass_alpha(cutoff_freq, self.sample_rate);
+            }
+        }
+        self.current_channel = (self.current_channel + 1) % self.channels;
+        
+        // One-pole low-pass: y[n] = alpha * x[n] + (1 - alpha) * y[n-1]
+        let filtered = self.alpha * sample + (1.0 - self.alpha) * self.prev_samples[ch];
+        self.prev_samples[ch] = filtered;
+        
+        Some(filtered)
+    }
+}
+
+impl<S> Source for LowPassSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.inner.total_duration()
+    }
+}
+
+// Source wrapper for algorithmic reverb (Schroeder-style with comb filters)
+// mix: 0.0 = dry only, 1.0 = full wet
+pub struct ReverbSource<S> {
+    inner: S,
+    param: EffectParam,
+    mix: f32,
+    channels: u16,
+    current_channel: u16,
+    // Delay lines for each channel (4 comb filters per channel)
+    comb_buffers: Vec<Vec<Vec<f32>>>, // [channel][comb_index][samples]
+    comb_positions: Vec<Vec<usize>>,   // [channel][comb_index]
+    // Allpass filters
+    allpass_buffers: Vec<Vec<Vec<f32>>>, // [channel][allpass_index][samples]
+    allpass_positions: Vec<Vec<usize>>,
+}
+
+impl<S> ReverbSource<S>
+where
+    S: Source<Item = f32>,
+{
+    pub fn new(inner: S, mix: impl Into<EffectParam>, sample_rate: u32) -> Self {
+        let channels = inner.channels() as usize;
+        let param = mix.into();
+        let mix = param.get().clamp(0.0, 1.0);
+        
+        // Comb filter delay times in samples (long delays for very spacious/echo-y reverb)
+        let comb_delays: [usize; 4] = [
+            (0.0797 * sample_rate as f32) as usize, // ~80ms
+            (0.0903 * sample_rate as f32) as usize, // ~90ms
+            (0.1100 * sample_rate as f32) as usize, // ~110ms
+            (0.1277 * sample_rate as f32) as usize, // ~128ms
+        ];
+        
+        // Allpass filter delay times (longer for more diffusion)
+        let allpass_delays: [usize; 2] = [
+            (0.0220 * sample_rate as f32) as usize, // ~22ms
+            (0.0074 * sample_rate as f32) as usize, // ~7.4ms
+        ];
+        
+        let mut comb_buffers = Vec::with_capacity(channels);
+        let mut comb_positions = Vec::with_capacity(channels);
+        let mut allpass_buffers = Vec::with_capacity(channels);
+        let mut allpass_positions = Vec::with_capacity(channels);
+        
+        for _ in 0..channels {
+            let mut ch_comb_buffers = Vec::with_capacity(4);
+            let mut ch_comb_positions = Vec::with_capacity(4);
+            for &delay in &comb_delays {
+                ch_comb_buffers.push(vec![0.0; delay.max(1)]);
+                ch_comb_positions.push(0);
+            }
+            comb_buffers.push(ch_comb_buffers);
+            comb_positions.push(ch_comb_positions);
+            
+            let mut ch_allpass_buffers = Vec::with_capacity(2);
+            let mut ch_allpass_positions = Vec::with_capacity(2);
+            for &delay in &allpass_delays {
+                ch_allpass_buffers.push(vec![0.0; delay.max(1)]);
+                ch_allpass_positions.push(0);
+            }
+            allpass_buffers.push(ch_allpass_buffers);
+            allpass_positions.push(ch_allpass_positions);
+        }
+        
+        Self {
+            inner,
+            param,
+            mix,
+            channels: channels as u16,
+            current_channel: 0,
+            comb_buffers,
+            comb_positions,
+            allpass_buffers,
+            allpass_positions,
+        }
+    }
+
+    fn clear_delay_lines(&mut self) {
+        for buffer in self.comb_buffers.iter_mut().chain(self.allpass_buffers.iter_mut()).flatten() {
+            buffer.fill(0.0);
+        }
+    }
+}
+
+impl<S> Iterator for ReverbSource<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.inner.next()?;
+        if self.current_channel == 0 {
+            let mix = self.param.get().clamp(0.0, 1.0);
+            if self.mix < 0.001 && mix >= 0.001 {
+                // The delay lines stopped updating while the mix was 0; don't replay what they held
+                self.clear_delay_lines();
+            }
+            self.mix = mix;
+        }
+        
+        // Skip processing if mix is 0
+        if self.mix < 0.001 {
+            self.current_channel = (self.current_channel + 1) % self.channels;
+            return Some(sample);
+        }
+        
+        let ch = self.current_channel as usize;
+        self.current_channel = (self.current_channel + 1) % self.channels;
+        
+        // Comb filter bank (parallel)
+        let feedback = 0.95; // Very high feedback for long echo-y decay
+        let mut comb_sum = 0.0;
+        
+        for i in 0..4 {
+            let buf = &mut self.comb_buffers[ch][i];
+            let pos = self.comb_positions[ch][i];
+            let delayed = buf[pos];
+            let new_val = sample + delayed * feedback;
+            buf[pos] = new_val;
+            self.comb_positions[ch][i] = (pos + 1) % buf.len();
+            comb_sum += delayed;
+        }
+        comb_sum *= 0.25; // Average the 4 comb outputs
+        
+        // Allpass filters (series)
+        let allpass_coeff = 0.7; // Higher coefficient for more diffusion
+        let mut allpass_out = comb_sum;
+        
+        for i in 0..2 {
+            let buf = &mut self.allpass_buffers[ch][i];
+            let pos = self.allpass_positions[ch][i];
+            let delayed = buf[pos];
+            let new_val = allpass_out + delayed * allpass_coeff;
+            allpass_out = delayed - allpass_coeff * new_val;
+            buf[pos] = new_val;
+            self.allpass_positions[ch][i] = (pos + 1) % buf.len();
+        }
+        
+        // Mix dry and wet - aggressive wet signal boost
+        let wet_gain = 2.5;
+        Some(sample * (1.0 - self.mix) + allpass_out * self.mix * wet_gain)
+    }
+}
+
+impl<S> Source for ReverbSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.inner.total_duration()
+    }
+}
@@ -0,0 +1,3815 @@
+// The audio engine - the audio thread that plays music, ambient sounds and the soundboard, and the
+// state it shares with whoever drives it. Commands go in as AudioCommands; what happens comes back
+// through the shared state and the Events sink (track changes, silence, band triggers, errors), so
+// the engine runs the same under the Tauri app, in headless mode or in a test.
+use parking_lot::Mutex;
+use rand::Rng;
+use rodio::{Decoder, Sink, Source};
+use rustfft::{num_complex::Complex, FftPlanner};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use crate::clock::{Clock, SystemClock};
+use crate::effects::EffectParams;
+use crate::events::Events;
+use crate::library::{get_album_tracks, pick_auto_dj_track};
+use crate::model::*;
+use crate::output::{self, OutputFactory};
+use crate::spectrum::{analyze_spectrum, bin_fft_magnitudes, frequency_bucket_ranges, smooth_bins, FrequencyScale, FFT_SIZE, FFT_WINDOW};
+use crate::{audio_cache, decode_pool, diagnostics, errors, mix_tap, tts, visualization};
+
+// Audio thread tick and the size from which ambient files are memory-mapped, until set otherwise
+pub const DEFAULT_AUDIO_TICK_MS: u32 = 10;
+pub const DEFAULT_MMAP_THRESHOLD_MB: u32 = 64;
+
+// Volume of one ambient category, on top of the ambient master
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct CategoryVolume {
+    pub volume: f32, // 0-100
+    pub muted: bool,
+}
+
+impl Default for CategoryVolume {
+    fn default() -> Self {
+        Self { volume: 100.0, muted: false }
+    }
+}
+
+impl CategoryVolume {
+    fn gain(&self) -> f32 {
+        if self.muted { 0.0 } else { self.volume.clamp(0.0, 100.0) / 100.0 }
+    }
+}
+
+// An ambient sound's category is the folder its files are in
+fn ambient_category(file: &str) -> String {
+    Path::new(file)
+        .parent()
+        .and_then(|dir| dir.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// Ambient sound settings matching the spec
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AmbientSettings {
+    pub volume: f32,           // 0.0 - 1.0
+    pub pitch: f32,            // 0.5 - 2.0 (playback speed)
+    pub pan: f32,              // -1.0 to 1.0 (L/R)
+    pub low_pass_freq: f32,    // 20 - 22000 Hz (cutoff frequency)
+    pub reverb_type: String,   // "off", "small-room", "large-hall", "cathedral"
+    pub algorithmic_reverb: f32, // 0.0 - 1.0 (only used when reverb_type is "off")
+    pub repeat_min: u32,       // Min A/B cycles before pause
+    pub repeat_max: u32,       // Max A/B cycles before pause
+    pub pause_min: u32,        // Min pause cycles
+    pub pause_max: u32,        // Max pause cycles
+    pub volume_variation: f32, // 0.0 - 0.5 (random ± per loop)
+    #[serde(default = "default_event_probability")]
+    pub event_probability: f32, // 0.0 - 1.0 (chance each A/B cycle plays rather than rests)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_group: Option<String>, // Sounds in the same group pause and start their cycles together
+    #[serde(default)]
+    pub random_start: bool,    // Start the first file at a random point instead of its beginning
+    #[serde(default)]
+    pub duck_music: bool,      // Lower the music while this sound is audible (spoken or story layers)
+}
+
+fn default_event_probability() -> f32 {
+    1.0
+}
+
+impl Default for AmbientSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pitch: 1.0,
+            pan: 0.0,
+            low_pass_freq: 22000.0, // Effectively off (above human hearing)
+            reverb_type: "off".to_string(),
+            algorithmic_reverb: 0.0,
+            repeat_min: 1,
+            repeat_max: 1,
+            pause_min: 0,
+            pause_max: 0,
+            volume_variation: 0.0,
+            event_probability: 1.0,
+            sync_group: None,
+            random_start: false,
+            duck_music: false,
+        }
+    }
+}
+
+// Audio Commands sent to the audio thread
+pub enum AudioCommand {
+    // Music commands
+    Play { file_path: String, track_info: CurrentTrackInfo },
+    Stop { fade_secs: Option<f32> }, // Fade the track out over fade_secs, or cut it
+    Pause,
+    Resume,
+    Seek(f64), // Seek to position in seconds
+    Restart, // Back to the start of the current track with a short fade
+    SetLoopSection(Option<(f64, f64)>), // A-B loop (start, end) in seconds, None to clear
+    SetVolume(f32),
+    SetMasterVolume(f32),
+    SetMuted(bool),
+    SetMasterMuted(bool),
+    SetCrossfadeDuration(f32),
+    SetSkipCrossfadeDuration(f32), // Shorter crossfade used for manual next/previous
+    SetPresetCrossfadeDuration(f32), // Fade used when ApplySounds switches the ambient set
+    SetLoudnessMatching(bool), // Match auto-advanced tracks to the current track's loudness
+    SetSkipOnSilence(bool), // Skip to the next track when the silence detector fires
+    SetTickInterval(u32), // Audio thread tick in ms, clamped to MIN/MAX_AUDIO_TICK_MS
+    SetRealtimePriority(bool, Sender<Result<(), String>>), // Promote/demote the audio thread itself
+    SetOutputDevice(Option<String>, Sender<Result<(), String>>), // None = the system default
+    SetSoundboardOutputDevice(Option<String>, Sender<Result<(), String>>), // None = the main output
+    SetPlaylistOverrides { crossfade_duration: Option<f32>, volume_offset_db: f32 }, // Per-playlist playback settings
+    // Visualization commands
+    SetFrequencyScale(FrequencyScale),
+    SetFftEventRate(u32), // playback://fft events per second, 0 disables
+    SetFftSmoothing { attack: f32, decay: f32 }, // Per-tick smoothing factors for frequency bins (0.0-1.0)
+    SetFftTriggers(Vec<FftTrigger>), // Replaces all band threshold triggers
+    // Soundboard commands
+    PlaySoundboard { file_path: String, volume: f32, trim: SoundTrim },
+    StopSoundboard { fade_secs: Option<f32> }, // Fade out over fade_secs, or cut it
+    SetSoundboardVolume(f32),
+    SetSoundboardMuted(bool),
+    SetDuckAmount(f32),
+    // Microphone monitoring; the source ends when its MicrophoneInput is dropped
+    StartMicrophone(output::BoxedSource),
+    // Ambient commands
+    PlayAmbient {
+        id: String,
+        file_a: String,
+        file_b: String,
+        settings: AmbientSettings,
+    },
+    StopAmbient { id: String, fade_secs: Option<f32> }, // FADE_SECS unless given
+    PreviewAmbient { file_path: String, seconds: f32 },
+    SwapAmbientFiles { id: String, file_a: String, file_b: String, crossfade_secs: f32 }, // Keeps settings and cycle
+    UpdateAmbientSettings { id: String, settings: AmbientSettings },
+    SetAmbientSyncGroup { id: String, group: Option<String> },
+    ApplySounds(Vec<ActiveAmbientInfo>), // Crossfade to these sounds, keeping the ones already playing
+    StopAllAmbient { fade_secs: Option<f32> }, // Stop all ambient sounds, over SCHEDULER_FADE_SECS unless given
+    PauseAllAmbient, // Freeze every ambient sound where it is
+    ResumeAllAmbient,
+    SetAmbientMasterVolume(f32),
+    SetAmbientMuted(bool),
+    SetCategoryVolumes(HashMap<String, CategoryVolume>), // Replaces every category's volume
+    PreloadAmbient(Vec<String>), // Preload audio files into memory cache
+    SetMmapThreshold(u64), // Ambient files of at least this many bytes are memory-mapped (0 = never)
+    // Scheduler-specific commands with longer fade times (2000ms)
+    PlayAmbientScheduler {
+        id: String,
+        file_a: String,
+        file_b: String,
+        settings: AmbientSettings,
+    },
+    StopAmbientScheduler(String),
+    UpdateAmbientSettingsScheduler { id: String, settings: AmbientSettings },
+    ApplyPreset(String), // Crossfade to a saved preset by id (same path as the scheduler)
+    // Diagnostics
+    DumpDebugState(Sender<diagnostics::EngineDebug>),
+}
+
+// Shared state for tracking active ambient sounds (queryable from outside audio thread); also what
+// apply_sounds takes for each sound
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ActiveAmbientInfo {
+    pub id: String,
+    pub file_a: String,
+    pub file_b: String,
+    pub settings: AmbientSettings,
+    // Only filled in by get_active_ambients, so the state broadcast doesn't fire on every countdown step
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<AmbientPhase>,
+}
+
+// Where an ambient sound is in its A/B cycle
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AmbientCycleState {
+    PlayingA,
+    PlayingB,
+    Paused, // Resting between cycles
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub struct AmbientPhase {
+    pub state: AmbientCycleState,
+    // Seconds of the pause left, while paused; None while playing, or while its sync group waits for
+    // the rest of the group to finish
+    pub next_cycle_secs: Option<f64>,
+}
+
+// Shared state for progress tracking (this is Send + Sync)
+#[derive(Clone)]
+pub struct AudioProgress {
+    pub current_time: f64,
+    pub duration: f64,
+    pub is_playing: bool,
+    pub is_finished: bool,
+    pub loop_section: Option<(f64, f64)>, // Active A-B loop (start, end) in seconds
+}
+
+// Event emitted by the audio thread with the latest visualization data
+const PLAYBACK_FFT_EVENT: &str = "playback://fft";
+// Analysis runs every 50ms whatever the tick interval, so events can't be emitted faster than this
+pub const MAX_FFT_EVENT_RATE: u32 = 20;
+const ANALYSIS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+// Range for the audio thread tick (ms)
+pub const MIN_AUDIO_TICK_MS: u32 = 5;
+pub const MAX_AUDIO_TICK_MS: u32 = 50;
+// Nominal rate for real-time scheduling requests; only the period (one tick) matters to the OS
+const REALTIME_SAMPLE_RATE: u32 = 48000;
+
+// Emitted by the audio thread as a track starts and ends, with the track's CurrentTrackInfo
+pub const TRACK_STARTED_EVENT: &str = "music://track-started";
+pub const TRACK_ENDED_EVENT: &str = "music://track-ended";
+
+// Silence/stall detection for the music bus
+const PLAYBACK_SILENCE_EVENT: &str = "playback://silence";
+const SILENCE_RMS_THRESHOLD: f32 = 0.0005; // ~-66 dBFS, before sink volume so muting doesn't count
+const SILENCE_TIMEOUT_SECS: f32 = 5.0;
+
+
+#[derive(Debug, Clone, Serialize)]
+struct SilenceEvent {
+    track_id: Option<String>,
+    current_time: f64,
+}
+
+// Event emitted when an FftTrigger fires
+const FFT_TRIGGER_EVENT: &str = "playback://trigger";
+
+#[derive(Debug, Clone, Serialize)]
+struct FftTriggerEvent {
+    name: String,
+    level: f32,
+}
+
+// Runtime state for one trigger: when the band went above the threshold, and whether it already fired
+struct FftTriggerState {
+    trigger: FftTrigger,
+    above_since: Option<Instant>,
+    fired: bool,
+}
+
+// Average level of the trigger's band, or None if the band is out of range
+fn trigger_band_level(trigger: &FftTrigger, bins: &[f32]) -> Option<f32> {
+    let end = trigger.band_end.min(bins.len().saturating_sub(1));
+    if trigger.band_start > end {
+        return None;
+    }
+    let band = &bins[trigger.band_start..=end];
+    Some(band.iter().sum::<f32>() / band.len() as f32)
+}
+
+// Playback state for visualization with FFT data
+#[derive(Clone)]
+pub struct PlaybackState {
+    pub music_playing: bool,
+    pub music_volume: f32,
+    pub ambient_count: u32,
+    pub ambient_volume: f32,
+    pub master_volume: f32,
+    pub is_muted: bool,
+    // FFT frequency data (0.0-1.0 for each bin), from the mid (L+R)/2 signal
+    pub frequencies: Vec<f32>,
+    // Per-channel FFT data for stereo visualizations
+    pub left_frequencies: Vec<f32>,
+    pub right_frequencies: Vec<f32>,
+    // Ambient amplitude data (0.0-1.0 for each bin) - derived from RMS tracking
+    pub ambient_frequencies: Vec<f32>,
+    pub soundboard_playing: bool,
+    pub soundboard_volume: f32,
+    // Soundboard RMS level after volume (0.0-1.0) and FFT data
+    pub soundboard_level: f32,
+    pub soundboard_frequencies: Vec<f32>,
+    // How far the music bus is currently ducked (0.0 = not at all, up to the duck amount)
+    pub duck_level: f32,
+}
+
+impl Default for PlaybackState {
+    fn default() -> Self {
+        Self {
+            music_playing: false,
+            music_volume: 0.0,
+            ambient_count: 0,
+            ambient_volume: 0.0,
+            master_volume: 1.0,
+            is_muted: false,
+            frequencies: vec![0.0; FFT_SIZE],
+            left_frequencies: vec![0.0; FFT_SIZE],
+            right_frequencies: vec![0.0; FFT_SIZE],
+            ambient_frequencies: vec![0.0; FFT_SIZE],
+            soundboard_playing: false,
+            soundboard_volume: 0.0,
+            soundboard_level: 0.0,
+            soundboard_frequencies: vec![0.0; FFT_SIZE],
+            duck_level: 0.0,
+        }
+    }
+}
+
+// Lock-free circular buffer for FFT samples - avoids mutex contention that causes static
+const FFT_BUFFER_SIZE: usize = 2048;
+
+// Stores frames (left/right pairs) so each channel can be analyzed separately
+struct FftSampleBuffer {
+    left: [std::sync::atomic::AtomicU32; FFT_BUFFER_SIZE],
+    right: [std::sync::atomic::AtomicU32; FFT_BUFFER_SIZE],
+    write_pos: std::sync::atomic::AtomicUsize,
+}
+
+impl FftSampleBuffer {
+    fn new() -> Self {
+        Self {
+            left: std::array::from_fn(|_| std::sync::atomic::AtomicU32::new(0)),
+            right: std::array::from_fn(|_| std::sync::atomic::AtomicU32::new(0)),
+            write_pos: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+    
+    fn push_frame(&self, left: f32, right: f32) {
+        let pos = self.write_pos.fetch_add(1, std::sync::atomic::Ordering::Relaxed) % FFT_BUFFER_SIZE;
+        self.left[pos].store(left.to_bits(), std::sync::atomic::Ordering::Relaxed);
+        self.right[pos].store(right.to_bits(), std::sync::atomic::Ordering::Relaxed);
+    }
+    
+    // Returns the latest `count` frames as (left, right)
+    fn get_latest(&self, count: usize) -> (Vec<f32>, Vec<f32>) {
+        let write_pos = self.write_pos.load(std::sync::atomic::Ordering::Relaxed);
+        let mut left = Vec::with_capacity(count);
+        let mut right = Vec::with_capacity(count);
+        for i in 0..count {
+            let pos = (write_pos + FFT_BUFFER_SIZE - count + i) % FFT_BUFFER_SIZE;
+            left.push(f32::from_bits(self.left[pos].load(std::sync::atomic::Ordering::Relaxed)));
+            right.push(f32::from_bits(self.right[pos].load(std::sync::atomic::Ordering::Relaxed)));
+        }
+        (left, right)
+    }
+    
+    fn clear(&self) {
+        self.write_pos.store(0, std::sync::atomic::Ordering::Relaxed);
+        for atom in self.left.iter().chain(self.right.iter()) {
+            atom.store(0, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+}
+
+// Lock-free buffer for ambient audio samples (for amplitude tracking)
+const AMBIENT_BUFFER_SIZE: usize = 2048;
+
+struct AmbientSampleBuffer {
+    buffer: [std::sync::atomic::AtomicU32; AMBIENT_BUFFER_SIZE],
+    write_pos: std::sync::atomic::AtomicUsize,
+}
+
+impl AmbientSampleBuffer {
+    fn new() -> Self {
+        Self {
+            buffer: std::array::from_fn(|_| std::sync::atomic::AtomicU32::new(0)),
+            write_pos: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+    
+    fn push(&self, sample: f32) {
+        let pos = self.write_pos.fetch_add(1, std::sync::atomic::Ordering::Relaxed) % AMBIENT_BUFFER_SIZE;
+        self.buffer[pos].store(sample.to_bits(), std::sync::atomic::Ordering::Relaxed);
+    }
+    
+    fn get_latest(&self, count: usize) -> Vec<f32> {
+        let write_pos = self.write_pos.load(std::sync::atomic::Ordering::Relaxed);
+        let mut result = Vec::with_capacity(count);
+        for i in 0..count {
+            let pos = (write_pos + AMBIENT_BUFFER_SIZE - count + i) % AMBIENT_BUFFER_SIZE;
+            let bits = self.buffer[pos].load(std::sync::atomic::Ordering::Relaxed);
+            result.push(f32::from_bits(bits));
+        }
+        result
+    }
+}
+
+// Source wrapper that copies samples for ambient amplitude analysis (lock-free)
+struct AmbientAnalyzingSource<S> {
+    inner: S,
+    sample_buffer: Arc<AmbientSampleBuffer>,
+}
+
+impl<S> AmbientAnalyzingSource<S> {
+    fn new(inner: S, sample_buffer: Arc<AmbientSampleBuffer>) -> Self {
+        Self { inner, sample_buffer }
+    }
+}
+
+impl<S> Iterator for AmbientAnalyzingSource<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.inner.next()?;
+        self.sample_buffer.push(sample);
+        Some(sample)
+    }
+}
+
+impl<S> Source for AmbientAnalyzingSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.inner.total_duration()
+    }
+}
+
+// Source wrapper that copies samples for FFT analysis (lock-free)
+// Splits interleaved samples into left/right frames; mono is duplicated, extra channels are ignored
+struct AnalyzingSource<S> {
+    inner: S,
+    sample_buffer: Arc<FftSampleBuffer>,
+    channels: usize,
+    channel: usize,
+    pending_left: f32,
+}
+
+impl<S> AnalyzingSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn new(inner: S, sample_buffer: Arc<FftSampleBuffer>) -> Self {
+        let channels = inner.channels().max(1) as usize;
+        Self { inner, sample_buffer, channels, channel: 0, pending_left: 0.0 }
+    }
+}
+
+impl<S> Iterator for AnalyzingSource<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.inner.next()?;
+        match self.channel {
+            0 if self.channels == 1 => self.sample_buffer.push_frame(sample, sample),
+            0 => self.pending_left = sample,
+            1 => self.sample_buffer.push_frame(self.pending_left, sample),
+            _ => {}
+        }
+        self.channel = (self.channel + 1) % self.channels;
+        Some(sample)
+    }
+}
+
+impl<S> Source for AnalyzingSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: std::time::Duration) -> Result<(), rodio::source::SeekError> {
+        self.inner.try_seek(pos)
+    }
+}
+
+// Loudness metering (ITU-R BS.1770 style K-weighted LUFS plus estimated true peak)
+const METER_FLUSH_SAMPLES: usize = 1024;
+const METER_SILENCE_DB: f32 = -70.0;
+const METER_MOMENTARY_TICKS: usize = 8; // 400ms of 50ms analysis passes
+const METER_SHORT_TERM_TICKS: usize = 60; // 3s of 50ms analysis passes
+const AMBIENT_LEVEL_SMOOTHING: f64 = 0.3; // per-tick smoothing for per-sound levels
+
+// Accumulates K-weighted energy and peak from the playback thread; drained by the audio loop each tick
+struct LevelMeter {
+    sum_squares: std::sync::atomic::AtomicU64, // f64 bits, sum over frames of per-channel squares
+    frames: std::sync::atomic::AtomicU64,
+    peak: std::sync::atomic::AtomicU32, // f32 bits, linear
+}
+
+impl LevelMeter {
+    fn new() -> Self {
+        Self {
+            sum_squares: std::sync::atomic::AtomicU64::new(0f64.to_bits()),
+            frames: std::sync::atomic::AtomicU64::new(0),
+            peak: std::sync::atomic::AtomicU32::new(0f32.to_bits()),
+        }
+    }
+    
+    fn add_block(&self, sum_squares: f64, frames: u64, peak: f32) {
+        use std::sync::atomic::Ordering;
+        let _ = self.sum_squares.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+            Some((f64::from_bits(bits) + sum_squares).to_bits())
+        });
+        self.frames.fetch_add(frames, Ordering::Relaxed);
+        let _ = self.peak.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+            if peak > f32::from_bits(bits) { Some(peak.to_bits()) } else { None }
+        });
+    }
+    
+    // Returns (sum_squares, frames, peak) since the last call and resets the accumulators
+    fn take(&self) -> (f64, u64, f32) {
+        use std::sync::atomic::Ordering;
+        let sum_squares = f64::from_bits(self.sum_squares.swap(0f64.to_bits(), Ordering::Relaxed));
+        let frames = self.frames.swap(0, Ordering::Relaxed);
+        let peak = f32::from_bits(self.peak.swap(0f32.to_bits(), Ordering::Relaxed));
+        (sum_squares, frames, peak)
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Biquad {
+    b0: f32, b1: f32, b2: f32,
+    a1: f32, a2: f32,
+    x1: f32, x2: f32,
+    y1: f32, y2: f32,
+}
+
+impl Biquad {
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2 - self.a1 * self.y1 - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+// K-weighting filter pair (high shelf + high pass) for the given sample rate
+fn k_weighting_filters(sample_rate: u32) -> (Biquad, Biquad) {
+    let fs = sample_rate.max(1) as f64;
+    
+    // Stage 1: high shelf (+4dB above ~1.7kHz)
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b0: ((vh + vb * k / q + k * k) / a0) as f32,
+        b1: (2.0 * (k * k - vh) / a0) as f32,
+        b2: ((vh - vb * k / q + k * k) / a0) as f32,
+        a1: (2.0 * (k * k - 1.0) / a0) as f32,
+        a2: ((1.0 - k / q + k * k) / a0) as f32,
+        ..Default::default()
+    };
+    
+    // Stage 2: high pass (~38Hz)
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b0: 1.0,
+        b1: -2.0,
+        b2: 1.0,
+        a1: (2.0 * (k * k - 1.0) / a0) as f32,
+        a2: ((1.0 - k / q + k * k) / a0) as f32,
+        ..Default::default()
+    };
+    
+    (shelf, high_pass)
+}
+
+// Estimate the inter-sample peak between p1 and p2 with 4x Catmull-Rom oversampling
+fn estimate_true_peak(p: &[f32; 4]) -> f32 {
+    let mut peak = p[2].abs();
+    for t in [0.25f32, 0.5, 0.75] {
+        let t2 = t * t;
+        let t3 = t2 * t;
+        let v = 0.5 * (2.0 * p[1]
+            + (-p[0] + p[2]) * t
+            + (2.0 * p[0] - 5.0 * p[1] + 4.0 * p[2] - p[3]) * t2
+            + (-p[0] + 3.0 * p[1] - 3.0 * p[2] + p[3]) * t3);
+        peak = peak.max(v.abs());
+    }
+    peak
+}
+
+// Source wrapper that feeds a LevelMeter (pre sink volume - the audio loop applies bus gain)
+struct MeteringSource<S> {
+    inner: S,
+    meter: Arc<LevelMeter>,
+    channels: usize,
+    channel: usize,
+    filters: Vec<(Biquad, Biquad)>,
+    history: Vec<[f32; 4]>,
+    sum_squares: f64,
+    samples: usize,
+    peak: f32,
+}
+
+impl<S> MeteringSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn new(inner: S, meter: Arc<LevelMeter>) -> Self {
+        let channels = inner.channels().max(1) as usize;
+        let filters = vec![k_weighting_filters(inner.sample_rate()); channels];
+        Self {
+            inner,
+            meter,
+            channels,
+            channel: 0,
+            filters,
+            history: vec![[0.0; 4]; channels],
+            sum_squares: 0.0,
+            samples: 0,
+            peak: 0.0,
+        }
+    }
+    
+    fn flush(&mut self) {
+        if self.samples > 0 {
+            let frames = (self.samples / self.channels).max(1) as u64;
+            self.meter.add_block(self.sum_squares, frames, self.peak);
+        }
+        self.sum_squares = 0.0;
+        self.samples = 0;
+        self.peak = 0.0;
+    }
+}
+
+impl<S> Iterator for MeteringSource<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = match self.inner.next() {
+            Some(s) => s,
+            None => {
+                self.flush();
+                return None;
+            }
+        };
+        
+        let ch = self.channel.min(self.channels - 1);
+        let (shelf, high_pass) = &mut self.filters[ch];
+        let weighted = high_pass.process(shelf.process(sample));
+        self.sum_squares += (weighted * weighted) as f64;
+        
+        let history = &mut self.history[ch];
+        history.rotate_left(1);
+        history[3] = sample;
+        self.peak = self.peak.max(estimate_true_peak(history));
+        
+        self.channel = (self.channel + 1) % self.channels;
+        self.samples += 1;
+        if self.samples >= METER_FLUSH_SAMPLES {
+            self.flush();
+        }
+        Some(sample)
+    }
+}
+
+impl<S> Source for MeteringSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: std::time::Duration) -> Result<(), rodio::source::SeekError> {
+        self.inner.try_seek(pos)
+    }
+}
+
+// The next track queued on the playing music sink for gapless playback. It marks when its first
+// sample is read, so the audio thread can move the track info over, and it can be withdrawn until then.
+struct GaplessSource<S> {
+    inner: S,
+    started: Arc<std::sync::atomic::AtomicBool>,
+    cancelled: Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl<S> Iterator for GaplessSource<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use std::sync::atomic::Ordering;
+        if !self.started.load(Ordering::Relaxed) {
+            if self.cancelled.load(Ordering::Relaxed) {
+                return None;
+            }
+            self.started.store(true, Ordering::Relaxed);
+        }
+        self.inner.next()
+    }
+}
+
+impl<S> Source for GaplessSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: std::time::Duration) -> Result<(), rodio::source::SeekError> {
+        self.inner.try_seek(pos)
+    }
+}
+
+// Rolling per-tick energy for one bus, used to derive momentary and short-term loudness
+struct BusLoudness {
+    history: std::collections::VecDeque<(f64, u64, f32)>, // (sum_squares, frames, peak) per tick, post bus gain
+}
+
+impl BusLoudness {
+    fn new() -> Self {
+        Self { history: std::collections::VecDeque::with_capacity(METER_SHORT_TERM_TICKS) }
+    }
+    
+    fn push(&mut self, reading: (f64, u64, f32), gain: f32) {
+        self.push_mix(&[(reading, gain)]);
+    }
+    
+    // Push one tick made of several simultaneously playing sources, each with its own gain
+    fn push_mix(&mut self, parts: &[((f64, u64, f32), f32)]) {
+        let frames = parts.iter().map(|&((_, n, _), _)| n).max().unwrap_or(0);
+        let mut power = 0.0f64;
+        let mut peak = 0.0f32;
+        for &((sum_squares, n, p), gain) in parts {
+            let gain = gain.max(0.0);
+            if n > 0 {
+                power += sum_squares / n as f64 * (gain * gain) as f64;
+            }
+            peak += p * gain;
+        }
+        self.history.push_back((power * frames as f64, frames, peak));
+        while self.history.len() > METER_SHORT_TERM_TICKS {
+            self.history.pop_front();
+        }
+    }
+    
+    // Mean K-weighted power (summed over channels) over the last `ticks` ticks
+    fn power(&self, ticks: usize) -> f64 {
+        let (sum, frames) = self.history.iter().rev().take(ticks)
+            .fold((0.0, 0u64), |(s, f), &(sq, n, _)| (s + sq, f + n));
+        if frames == 0 { 0.0 } else { sum / frames as f64 }
+    }
+    
+    fn peak(&self) -> f32 {
+        self.history.iter().fold(0.0f32, |p, &(_, _, peak)| p.max(peak))
+    }
+}
+
+fn power_to_lufs(power: f64) -> f32 {
+    if power <= 0.0 {
+        return METER_SILENCE_DB;
+    }
+    ((-0.691 + 10.0 * power.log10()) as f32).max(METER_SILENCE_DB)
+}
+
+fn power_to_db(power: f64) -> f32 {
+    if power <= 0.0 {
+        return METER_SILENCE_DB;
+    }
+    ((10.0 * power.log10()) as f32).max(METER_SILENCE_DB)
+}
+
+fn amplitude_to_db(amplitude: f32) -> f32 {
+    if amplitude <= 0.0 {
+        return METER_SILENCE_DB;
+    }
+    (20.0 * amplitude.log10()).max(METER_SILENCE_DB)
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct BusMeterReading {
+    pub momentary_lufs: f32,
+    pub short_term_lufs: f32,
+    pub true_peak_db: f32,
+}
+
+impl BusMeterReading {
+    fn silent() -> Self {
+        Self {
+            momentary_lufs: METER_SILENCE_DB,
+            short_term_lufs: METER_SILENCE_DB,
+            true_peak_db: METER_SILENCE_DB,
+        }
+    }
+    
+    fn from_powers(momentary: f64, short_term: f64, peak: f32) -> Self {
+        Self {
+            momentary_lufs: power_to_lufs(momentary),
+            short_term_lufs: power_to_lufs(short_term),
+            true_peak_db: amplitude_to_db(peak),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MeterReadings {
+    pub music: BusMeterReading,
+    pub ambient: BusMeterReading,
+    pub soundboard: BusMeterReading,
+    pub master: BusMeterReading,
+}
+
+impl Default for MeterReadings {
+    fn default() -> Self {
+        Self {
+            music: BusMeterReading::silent(),
+            ambient: BusMeterReading::silent(),
+            soundboard: BusMeterReading::silent(),
+            master: BusMeterReading::silent(),
+        }
+    }
+}
+
+// Seconds of audio decoded when estimating a track's loudness
+const LOUDNESS_ANALYSIS_SECS: u64 = 30;
+// Loudness matching never changes a track's gain by more than ~6 dB either way
+const LOUDNESS_MAX_GAIN: f32 = 2.0;
+
+// Estimate a track's loudness as the RMS of its opening LOUDNESS_ANALYSIS_SECS seconds
+fn analyze_track_loudness(file_path: &str) -> Option<f32> {
+    let file = File::open(file_path).ok()?;
+    let source = Decoder::new(BufReader::new(file)).ok()?;
+    let sample_count = source.sample_rate() as usize * source.channels() as usize * LOUDNESS_ANALYSIS_SECS as usize;
+    
+    let mut sum_squares = 0.0f64;
+    let mut count = 0usize;
+    for sample in source.convert_samples::<f32>().take(sample_count) {
+        sum_squares += (sample * sample) as f64;
+        count += 1;
+    }
+    if count == 0 {
+        return None;
+    }
+    
+    let rms = (sum_squares / count as f64).sqrt() as f32;
+    // Treat (near) silence as unknown rather than boosting it to the limit
+    if rms > 0.0001 { Some(rms) } else { None }
+}
+
+// When each one-shot event of the current item plays: at its offset, or at a random time in the item.
+// An offset past the end of the item never comes.
+fn plan_scheduled_events(sched: &mut SchedulerState) {
+    let length = sched.current_duration * 60;
+    let planned = sched.items[sched.current_item_index].events.iter()
+        .enumerate()
+        .map(|(index, event)| (event.offset_secs.unwrap_or_else(|| rand::thread_rng().gen_range(0..length.max(1))), index))
+        .collect();
+    sched.pending_events = planned;
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybackStateResponse {
+    music_playing: bool,
+    music_volume: f32,
+    ambient_count: u32,
+    ambient_volume: f32,
+    master_volume: f32,
+    is_muted: bool,
+    frequencies: Vec<f32>,
+    left_frequencies: Vec<f32>,
+    right_frequencies: Vec<f32>,
+    ambient_frequencies: Vec<f32>,
+    soundboard_playing: bool,
+    soundboard_volume: f32,
+    soundboard_level: f32,
+    soundboard_frequencies: Vec<f32>,
+}
+
+impl From<PlaybackState> for PlaybackStateResponse {
+    fn from(ps: PlaybackState) -> Self {
+        Self {
+            music_playing: ps.music_playing,
+            music_volume: ps.music_volume,
+            ambient_count: ps.ambient_count,
+            ambient_volume: ps.ambient_volume,
+            master_volume: ps.master_volume,
+            is_muted: ps.is_muted,
+            frequencies: ps.frequencies,
+            left_frequencies: ps.left_frequencies,
+            right_frequencies: ps.right_frequencies,
+            ambient_frequencies: ps.ambient_frequencies,
+            soundboard_playing: ps.soundboard_playing,
+            soundboard_volume: ps.soundboard_volume,
+            soundboard_level: ps.soundboard_level,
+            soundboard_frequencies: ps.soundboard_frequencies,
+        }
+    }
+}
+
+// Handle to the running engine: the command sender and the state the audio thread shares. The thread
+// runs until the process exits.
+pub struct Engine {
+    command_tx: Sender<AudioCommand>,
+    pub progress: Arc<Mutex<AudioProgress>>,
+    pub playback_state: Arc<Mutex<PlaybackState>>,
+    sample_buffer: Arc<FftSampleBuffer>,
+    ambient_sample_buffer: Arc<AmbientSampleBuffer>,
+    // Soundboard uses the same mono ring buffer type as ambient
+    soundboard_sample_buffer: Arc<AmbientSampleBuffer>,
+    pub active_ambients: Arc<Mutex<HashMap<String, ActiveAmbientInfo>>>,
+    pub current_track: Arc<Mutex<Option<CurrentTrackInfo>>>,
+    pub playlist_state: Arc<Mutex<PlaylistState>>,
+    pub playlists: Arc<Mutex<HashMap<String, MusicPlaylist>>>,
+    pub all_tracks: Arc<Mutex<Vec<PlaylistTrack>>>,
+    pub soundboard_playing: Arc<Mutex<bool>>,
+    pub scheduler_state: Arc<Mutex<SchedulerState>>,
+    pub presets_dir: Arc<Mutex<Option<PathBuf>>>,
+    pub current_preset_id: Arc<Mutex<Option<String>>>,
+    pub events: Events,
+    pub errors: errors::ErrorReporter,
+    pub engine_stats: Arc<diagnostics::EngineStats>,
+    pub visualization: visualization::VisualizationUsers,
+    pub meters: Arc<Mutex<MeterReadings>>,
+    pub ambient_levels: Arc<Mutex<HashMap<String, f32>>>,
+    pub ambient_phases: Arc<Mutex<HashMap<String, AmbientPhase>>>,
+    pub mix_tap: Arc<mix_tap::MixTap>,
+}
+
+impl Engine {
+    // Start the audio thread. `open_output` runs on it; pass output::default_output() for the sound
+    // card or a CollectingOutput factory to run the engine without audio hardware.
+    pub fn new(open_output: OutputFactory) -> Self {
+        Self::with_clock(open_output, Arc::new(SystemClock))
+    }
+
+    // Same, reading the time from `clock`; a test passes a ManualClock to step fades and the scheduler
+    pub fn with_clock(open_output: OutputFactory, clock: Arc<dyn Clock>) -> Self {
+        let (command_tx, command_rx) = channel::<AudioCommand>();
+        let progress = Arc::new(Mutex::new(AudioProgress {
+            current_time: 0.0,
+            duration: 0.0,
+            is_playing: false,
+            is_finished: true,
+            loop_section: None,
+        }));
+        let playback_state = Arc::new(Mutex::new(PlaybackState::default()));
+        let sample_buffer = Arc::new(FftSampleBuffer::new());
+        let ambient_sample_buffer = Arc::new(AmbientSampleBuffer::new());
+        let soundboard_sample_buffer = Arc::new(AmbientSampleBuffer::new());
+        let active_ambients: Arc<Mutex<HashMap<String, ActiveAmbientInfo>>> = Arc::new(Mutex::new(HashMap::new()));
+        let current_track = Arc::new(Mutex::new(None::<CurrentTrackInfo>));
+        let playlist_state = Arc::new(Mutex::new(PlaylistState::default()));
+        let playlists: Arc<Mutex<HashMap<String, MusicPlaylist>>> = Arc::new(Mutex::new(HashMap::new()));
+        let all_tracks: Arc<Mutex<Vec<PlaylistTrack>>> = Arc::new(Mutex::new(Vec::new()));
+        let soundboard_playing = Arc::new(Mutex::new(false));
+        let scheduler_state = Arc::new(Mutex::new(SchedulerState::default()));
+        let presets_dir: Arc<Mutex<Option<PathBuf>>> = Arc::new(Mutex::new(None));
+        let current_preset_id: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+        let events = Events::default();
+        let errors = errors::ErrorReporter::new(events.clone());
+        let meters = Arc::new(Mutex::new(MeterReadings::default()));
+        let ambient_levels: Arc<Mutex<HashMap<String, f32>>> = Arc::new(Mutex::new(HashMap::new()));
+        let ambient_phases: Arc<Mutex<HashMap<String, AmbientPhase>>> = Arc::new(Mutex::new(HashMap::new()));
+        let mix_tap = Arc::new(mix_tap::MixTap::new());
+        let engine_stats = Arc::new(diagnostics::EngineStats::new());
+        
+        let progress_clone = progress.clone();
+        let playback_state_clone = playback_state.clone();
+        let sample_buffer_clone = sample_buffer.clone();
+        let ambient_sample_buffer_clone = ambient_sample_buffer.clone();
+        let soundboard_sample_buffer_clone = soundboard_sample_buffer.clone();
+        let active_ambients_clone = active_ambients.clone();
+        let current_track_clone = current_track.clone();
+        let soundboard_playing_clone = soundboard_playing.clone();
+        let playlist_state_clone = playlist_state.clone();
+        let playlists_clone = playlists.clone();
+        let all_tracks_clone = all_tracks.clone();
+        let scheduler_state_clone = scheduler_state.clone();
+        let presets_dir_clone = presets_dir.clone();
+        let command_tx_clone = command_tx.clone();
+        let events_clone = events.clone();
+        let errors_clone = errors.clone();
+        let meters_clone = meters.clone();
+        let ambient_levels_clone = ambient_levels.clone();
+        let ambient_phases_clone = ambient_phases.clone();
+        let mix_tap_clone = mix_tap.clone();
+        let engine_stats_clone = engine_stats.clone();
+        let visualization = visualization::VisualizationUsers::default();
+        let visualization_active = visualization.active_flag();
+        
+        // Spawn audio thread
+        
+        thread::spawn(move || {
+            let output = match open_output() {
+                Ok(output) => output,
+                Err(e) => {
+                    errors_clone.report(errors::ErrorCode::AudioOutput, format!("Failed to create audio output: {}", e), None);
+                    return;
+                }
+            };
+            
+            let mut current_sink: Option<Sink> = None;
+            let mut music_volume: f32 = 1.0;
+            let mut master_volume: f32 = 1.0;
+            let mut is_muted = false;
+            let mut is_master_muted = false;
+            let mut track_start: Option<Instant> = None;
+            let mut track_duration: f64 = 0.0;
+            let mut pause_start: Option<Instant> = None;  // Track when pause started
+            let mut crossfade_duration: f32 = 3.0;  // Default 3 seconds (effective, includes playlist override)
+            let mut base_crossfade_duration: f32 = 3.0;  // Global setting, restored when the playlist has no override
+            let mut playlist_crossfade_override: Option<f32> = None;
+            let mut playlist_gain: f32 = 1.0;  // Linear gain from the current playlist's volume offset
+            // Fade states: fade_out for end of current track, fade_in for start of new track
+            let mut fade_out_active: bool = false;  // Currently fading out
+            let mut fade_in_progress: Option<(Instant, f32)> = None;  // (start_time, duration) for fade-in
+            // Manual skips overlap the old track's fade-out with the new track's fade-in
+            let mut skip_crossfade_duration: f32 = 0.5;
+            let mut outgoing_sink: Option<(Sink, Instant, f32, f32)> = None;  // (sink, start_time, duration, start_volume)
+            const RESTART_FADE_SECS: f32 = 0.15; // Fade across a restart, long enough not to click
+            // Loudness matching: per-file RMS estimates (filled by background analysis) and the gain applied to the current track
+            let loudness_cache: Arc<Mutex<HashMap<String, f32>>> = Arc::new(Mutex::new(HashMap::new()));
+            let mut loudness_matching: bool = true;
+            let mut current_track_gain: f32 = 1.0;
+            // A-B section looping: (start, end) in seconds, plus a flag so we only queue one seek per pass
+            let mut loop_section: Option<(f64, f64)> = None;
+            let mut loop_seek_pending: bool = false;
+            
+            // FFT setup
+            let mut fft_planner = FftPlanner::<f32>::new();
+            let fft = fft_planner.plan_fft_forward(FFT_WINDOW);
+            let mut fft_buffer: Vec<Complex<f32>> = vec![Complex::new(0.0, 0.0); FFT_WINDOW];
+            let mut frequency_ranges = frequency_bucket_ranges(FrequencyScale::Linear);
+            // Push visualization data to the windows instead of having them poll
+            let mut fft_event_interval: Option<std::time::Duration> = Some(std::time::Duration::from_millis(50));
+            let mut last_fft_event = Instant::now();
+            let mut fft_attack: f32 = 0.7;
+            let mut fft_decay: f32 = 0.3;
+            let mut fft_triggers: Vec<FftTriggerState> = Vec::new();
+            
+            // Silence detector: when music output went quiet, and whether we already reported it
+            let mut silence_start: Option<Instant> = None;
+            let mut silence_reported = false;
+            let mut skip_on_silence = false;
+            
+            // Per-bus loudness meters (fed by MeteringSource, drained every tick)
+            let music_meter = Arc::new(LevelMeter::new());
+            let soundboard_meter = Arc::new(LevelMeter::new());
+            let mut music_loudness = BusLoudness::new();
+            let mut ambient_loudness = BusLoudness::new();
+            let mut soundboard_loudness = BusLoudness::new();
+            // Smoothed per-ambient power (id -> K-weighted mean square after sink volume)
+            let mut ambient_level_power: HashMap<String, f64> = HashMap::new();
+            
+            // Ambient sounds state - A/B crossfade system
+            struct AmbientState {
+                sink: Sink,
+                file_a: String,
+                file_b: String,
+                category: String,        // folder of the files, for its category volume
+                settings: AmbientSettings,
+                is_playing_a: bool,      // true = A, false = B
+                loops_remaining: u32,    // A/B cycles before pause
+                pause_remaining: f64,    // seconds of pause remaining
+                is_paused: bool,         // in pause state
+                meter: Arc<LevelMeter>,  // level of this sound alone (pre sink volume)
+                effects: EffectParams,   // pan, low-pass and reverb, adjustable while playing
+                decoding: Option<u64>,   // ticket of the next file's decode, while a worker has it
+            }
+            let mut ambient_states: HashMap<String, AmbientState> = HashMap::new();
+            // Sync groups that are pausing together (group -> seconds of pause remaining)
+            let mut sync_pauses: HashMap<String, f64> = HashMap::new();
+            // When the whole ambient engine was paused; sounds started meanwhile wait for the resume
+            let mut ambient_paused_at: Option<Instant> = None;
+            let mut ambient_master_volume: f32 = 1.0;
+            let mut is_ambient_muted = false;
+            let mut category_volumes: HashMap<String, CategoryVolume> = HashMap::new();
+            fn category_gain(volumes: &HashMap<String, CategoryVolume>, category: &str) -> f32 {
+                volumes.get(category).map_or(1.0, CategoryVolume::gain)
+            }
+            
+            // Audio file cache - stores file bytes in memory to avoid disk I/O during playback
+            // Shared slices, so a cache hit hands the decoder the same bytes instead of copying them;
+            // very large files are memory-mapped rather than read
+            let mut audio_cache = audio_cache::AudioCache::new(DEFAULT_MMAP_THRESHOLD_MB as u64 * 1024 * 1024);
+            // Files for A/B swaps, pitch changes and preloads are read and decoded off this thread
+            let mut decode_pool = decode_pool::DecodePool::new(engine_stats_clone.clone());
+            
+            // Track sounds that are fading out before stop (id -> (when the fade started, seconds))
+            let mut fading_out: HashMap<String, (Instant, f32)> = HashMap::new();
+            // Track sounds that are fading in after start (id -> when the fade started)
+            let mut fading_in: HashMap<String, Instant> = HashMap::new();
+            // Track volume transitions for smooth settings changes (id -> (current_vol, target_vol))
+            let mut volume_transitions: HashMap<String, (f32, f32)> = HashMap::new();
+            // Sounds crossfading to new files (id -> (old sink, when the crossfade started, seconds)); the
+            // crossfade starts once the new file has decoded, and until then the old sink plays on
+            let mut swap_fades: HashMap<String, (Sink, Option<Instant>, f32)> = HashMap::new();
+            // Fade progress comes from the start time, so a fade lasts its full duration however late
+            // or irregular the ticks are
+            const FADE_SECS: f32 = 0.2;
+            const VOLUME_TRANSITION_SPEED: f32 = 1.6; // Volume change per second (~600ms full transition)
+            
+            // Scheduler-specific fades with longer duration (2000ms); preset switches share them
+            // with their own duration, so each fade keeps the seconds it started with
+            let mut scheduler_fading_out: HashMap<String, (Instant, f32)> = HashMap::new();
+            let mut scheduler_fading_in: HashMap<String, (Instant, f32)> = HashMap::new();
+            // (current_vol, target_vol, change per second)
+            let mut scheduler_volume_transitions: HashMap<String, (f32, f32, f32)> = HashMap::new();
+            const SCHEDULER_FADE_SECS: f32 = 2.0;
+            const SCHEDULER_VOLUME_TRANSITION_SPEED: f32 = 0.5; // ~2000ms full transition
+            let mut preset_crossfade_duration: f32 = 2.0;
+            fn fade_progress(clock: &dyn Clock, started: &Instant, duration_secs: f32) -> f32 {
+                clock.since(*started).as_secs_f32() / duration_secs
+            }
+            
+            // Soundboard state
+            let mut soundboard_sink: Option<Sink> = None;
+            let mut soundboard_outgoing: Option<(Sink, Instant, f32, f32)> = None;  // stopped with a fade: (sink, start_time, duration, start_volume)
+            let mut soundboard_volume: f32 = 1.0; // Soundboard volume (0-1)
+            let mut soundboard_muted: bool = false; // Soundboard mute state
+            let mut microphone_sink: Option<Sink> = None;
+            // Ambient file being auditioned; not on a bus, so recordings and the stream don't get it
+            let mut preview_sink: Option<Sink> = None;
+            const PREVIEW_VOLUME: f32 = 0.4;
+            let mut duck_amount: f32 = 0.5; // Default 50% ducking
+            let mut duck_progress: f32 = 0.0; // 0.0 = no ducking, 1.0 = fully ducked
+            let mut duck_target: f32 = 0.0; // Target duck level (0.0 or 1.0)
+            const DUCK_FADE_SECS: f32 = 0.3; // Full duck fade
+            // Music-only duck while an ambient sound flagged duck_music is audible
+            let mut story_duck_progress: f32 = 0.0;
+            let mut story_duck_target: f32 = 0.0;
+            let mut last_duck_update = clock.now();
+            
+            // Auto-advance state for playlist
+            let mut was_playing: bool = false;
+            let mut pending_auto_advance: Option<(String, CurrentTrackInfo, Option<PrebufferedSource>)> = None; // (file_path, track_info, prebuffered)
+            // The track music://track-started was last sent for, so each one gets exactly one music://track-ended
+            let mut started_track: Option<CurrentTrackInfo> = None;
+            fn announce_track(events: &Events, started: &mut Option<CurrentTrackInfo>, next: Option<&CurrentTrackInfo>) {
+                if !events.has_sink() {
+                    return;
+                }
+                if let Some(ended) = started.take() {
+                    events.emit(TRACK_ENDED_EVENT, &ended);
+                }
+                if let Some(next) = next {
+                    events.emit(TRACK_STARTED_EVENT, next);
+                    *started = Some(next.clone());
+                }
+            }
+            // Next playlist track, picked and decoded in the background shortly before the current one ends
+            struct PrebufferedTrack {
+                file_path: String,
+                track_info: CurrentTrackInfo,
+                next_index: Option<usize>,
+                // Playlist position it was picked from; if that changed since, it's picked again
+                picked_from: (Option<String>, i32),
+                source: PrebufferedSource,
+            }
+            type PrebufferedSource = Receiver<Option<Box<dyn Source<Item = i16> + Send>>>;
+            let mut prebuffered: Option<PrebufferedTrack> = None;
+            let mut prebuffer_started = false; // Only pick once per track, even if there is no next one
+            const PREBUFFER_LEAD_SECS: f64 = 5.0; // Seconds before the fade-out starts
+            // With no crossfade, the prebuffered track is queued on the playing sink so there's no gap
+            struct GaplessNext {
+                track_info: CurrentTrackInfo,
+                next_index: Option<usize>,
+                duration: f64,
+                gain: f32,
+                started: Arc<std::sync::atomic::AtomicBool>,
+                cancelled: Arc<std::sync::atomic::AtomicBool>,
+            }
+            let mut gapless_next: Option<GaplessNext> = None;
+            // Withdraw a queued track that hasn't started; true if there was one to withdraw
+            fn cancel_gapless(gapless_next: &mut Option<GaplessNext>) -> bool {
+                let Some(next) = gapless_next.take() else { return false };
+                next.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+                true
+            }
+            
+            // Measure a track's loudness on a worker thread, unless it's cached already
+            fn measure_loudness(cache: &Arc<Mutex<HashMap<String, f32>>>, path: &str) {
+                if cache.lock().contains_key(path) {
+                    return;
+                }
+                let cache = cache.clone();
+                let path = path.to_string();
+                thread::spawn(move || {
+                    if let Some(rms) = analyze_track_loudness(&path) {
+                        cache.lock().insert(path, rms);
+                    }
+                });
+            }
+            
+            // Gain that matches the next track's loudness to the previous one's (path and gain). Only
+            // cached loudness is used: a track that hasn't been measured plays at unity gain while it's
+            // measured in the background.
+            fn matched_gain(cache: &Arc<Mutex<HashMap<String, f32>>>, previous: Option<(String, f32)>, next_path: &str) -> f32 {
+                let reference = previous.and_then(|(path, gain)| cache.lock().get(&path).map(|rms| rms * gain));
+                let next_loudness = cache.lock().get(next_path).copied();
+                if next_loudness.is_none() {
+                    measure_loudness(cache, next_path);
+                }
+                match (reference, next_loudness) {
+                    (Some(reference), Some(next_loudness)) => (reference / next_loudness).clamp(1.0 / LOUDNESS_MAX_GAIN, LOUDNESS_MAX_GAIN),
+                    _ => 1.0,
+                }
+            }
+            
+            // The track auto-advance plays after the current one, and the playlist index to move to
+            // (None for auto-DJ, which picks by mood and energy instead)
+            fn pick_next_track(
+                ps: &PlaylistState,
+                all_tracks: &[PlaylistTrack],
+                playlists: &HashMap<String, MusicPlaylist>,
+                current_id: Option<&str>,
+            ) -> Option<(Option<usize>, String, CurrentTrackInfo)> {
+                let track_info = |track: &PlaylistTrack| {
+                    let info = track.track_info();
+                    (info.file_path.clone(), info)
+                };
+                let playlist_id = ps.current_playlist_id.as_deref()?;
+                if playlist_id == AUTO_DJ_PLAYLIST_ID {
+                    // Auto-DJ: pick the next track from the target mood/energy band
+                    let target = ps.auto_dj.as_ref()?;
+                    let next_track = pick_auto_dj_track(all_tracks, target, current_id)?;
+                    let (file_path, info) = track_info(&next_track);
+                    return Some((None, file_path, info));
+                }
+                
+                // Get tracks for current playlist
+                let tracks: Vec<PlaylistTrack> = if playlist_id.starts_with("album-") {
+                    // Album playlist - filter all_tracks by album name
+                    let album_name = playlist_id.strip_prefix("album-").unwrap_or("");
+                    get_album_tracks(all_tracks, album_name)
+                } else if playlist_id == "all-music" {
+                    all_tracks.to_vec()
+                } else if playlist_id == "favorites" {
+                    all_tracks.iter()
+                        .filter(|t| ps.favorites.contains(&t.id))
+                        .cloned()
+                        .collect()
+                } else {
+                    // Custom playlist
+                    playlists.get(playlist_id)?.tracks.clone()
+                };
+                if tracks.is_empty() {
+                    return None;
+                }
+                
+                // Calculate next index
+                let current_idx = ps.current_index as usize;
+                let next_idx = if ps.is_shuffled {
+                    // Random next track
+                    rand::random::<usize>() % tracks.len()
+                } else {
+                    // Sequential
+                    let next = current_idx + 1;
+                    if next >= tracks.len() {
+                        if ps.is_looping { 0 } else { return None } // Stop if not looping
+                    } else {
+                        next
+                    }
+                };
+                let (file_path, info) = track_info(&tracks[next_idx]);
+                Some((Some(next_idx), file_path, info))
+            }
+            
+            // Scheduler runs once a second, analysis (FFT, meters, fft events) every ANALYSIS_INTERVAL;
+            // fades and refills run on the finer tick, which commands don't push back
+            let mut last_scheduler_tick = clock.now();
+            let mut last_analysis = Instant::now();
+            let mut tick_interval = std::time::Duration::from_millis(DEFAULT_AUDIO_TICK_MS as u64);
+            let mut next_tick = Instant::now() + tick_interval;
+            // Audio time of the last tick, for volume transitions and ambient pauses
+            let mut last_step = clock.now();
+            // Held while the thread has real-time priority; dropping it doesn't demote the thread
+            let mut realtime_priority: Option<audio_thread_priority::RtPriorityHandle> = None;
+            // Device the output plays on (None = the system default)
+            let mut output_device: Option<String> = None;
+            // The soundboard's own stream on another device (None = it plays on the main output)
+            let mut soundboard_output: Option<Box<dyn output::OutputBackend>> = None;
+            let mut soundboard_device: Option<String> = None;
+            
+            // Helper to calculate effective volume with variation and ducking
+            fn calc_ambient_volume(
+                settings: &AmbientSettings,
+                ambient_master: f32,
+                category: f32,
+                master: f32,
+                is_ambient_muted: bool,
+                is_master_muted: bool,
+                duck_progress: f32,
+                duck_amount: f32,
+            ) -> f32 {
+                if is_ambient_muted || is_master_muted {
+                    0.0
+                } else {
+                    let variation = if settings.volume_variation > 0.0 {
+                        let var = (rand::random::<f32>() - 0.5) * 2.0 * settings.volume_variation;
+                        (1.0 + var).max(0.0).min(2.0)
+                    } else {
+                        1.0
+                    };
+                    let base_vol = settings.volume * ambient_master * category * master * variation;
+                    // Apply gradual ducking based on duck_progress (0.0 = none, 1.0 = full)
+                    base_vol * (1.0 - duck_progress * duck_amount)
+                }
+            }
+            
+            // State for a new ambient sound: a paused sink with file A queued through its effects at
+            // volume 0, ready for the caller to start and fade in
+            fn open_ambient(
+                output: &dyn output::OutputBackend,
+                mix_tap: &Arc<mix_tap::MixTap>,
+                cache: &audio_cache::AudioCache,
+                stats: &diagnostics::EngineStats,
+                sample_buffer: &Arc<AmbientSampleBuffer>,
+                info: &ActiveAmbientInfo,
+            ) -> Result<AmbientState, (errors::ErrorCode, String, Option<String>)> {
+                let sink = mix_tap::new_tapped_sink(output, mix_tap, mix_tap::Bus::Ambient)
+                    .map_err(|e| (errors::ErrorCode::Sink, format!("Failed to create ambient sink: {}", e), None))?;
+                sink.pause();
+                let bytes = cache.get(&info.file_a, stats)
+                    .ok_or_else(|| (errors::ErrorCode::FileOpen, "Failed to read ambient sound".to_string(), Some(info.file_a.clone())))?;
+                let mut source = stats.time_decode(|| Decoder::new(Cursor::new(bytes)))
+                    .map_err(|_| (errors::ErrorCode::Decode, "Failed to decode ambient sound".to_string(), Some(info.file_a.clone())))?;
+                
+                // Start somewhere inside the file, so sounds started together aren't in step. Files the
+                // decoder can't tell the length of, or can't seek in, start at the beginning.
+                let settings = &info.settings;
+                if settings.random_start {
+                    if let Some(total) = source.total_duration() {
+                        let offset = total.mul_f32(rand::thread_rng().gen_range(0.0..1.0));
+                        if let Err(e) = source.try_seek(offset) {
+                            log::debug!("Can't start {} at a random point: {}", info.file_a, e);
+                        }
+                    }
+                }
+                
+                // Apply pitch, pan, low-pass filter
+                let sample_rate = source.sample_rate();
+                let source = source.speed(settings.pitch).convert_samples::<f32>();
+                let effects = EffectParams::new(settings.pan, settings.low_pass_freq, settings.algorithmic_reverb);
+                let source = effects.apply(source, sample_rate);
+                
+                // Start at 0 volume for fade-in
+                sink.set_volume(0.0);
+                
+                // Wrap with amplitude tracking
+                let meter = Arc::new(LevelMeter::new());
+                let source = AmbientAnalyzingSource::new(MeteringSource::new(source, meter.clone()), sample_buffer.clone());
+                sink.append(source);
+                
+                // Determine initial loop count
+                let loops = rand::thread_rng().gen_range(settings.repeat_min..=settings.repeat_max);
+                Ok(AmbientState {
+                    sink,
+                    file_a: info.file_a.clone(),
+                    file_b: info.file_b.clone(),
+                    category: ambient_category(&info.file_a),
+                    settings: settings.clone(),
+                    is_playing_a: true,
+                    loops_remaining: loops,
+                    pause_remaining: 0.0,
+                    is_paused: false,
+                    meter,
+                    effects,
+                    decoding: None,
+                })
+            }
+            
+            // Track last loaded scheduler item to detect changes
+            let mut last_scheduler_item_index: Option<usize> = None;
+            let mut scheduler_preset_pending: Option<String> = None; // preset_id to load
+            
+            loop {
+                let iteration_start = Instant::now();
+                
+                // Handle scheduler tick (every 1 second)
+                if clock.since(last_scheduler_tick) >= std::time::Duration::from_secs(1) {
+                    last_scheduler_tick = clock.now();
+                    
+                    let mut sched = scheduler_state_clone.lock();
+                    if sched.is_playing && !sched.items.is_empty() {
+                        // Check if this is the first tick or if we advanced to a new item
+                        let current_idx = sched.current_item_index;
+                        let should_load_preset = last_scheduler_item_index != Some(current_idx);
+                        
+                        if should_load_preset {
+                            last_scheduler_item_index = Some(current_idx);
+                            let preset_id = sched.items[current_idx].preset_id.clone();
+                            log::debug!("[Scheduler] Queued preset load: {}", preset_id);
+                            scheduler_preset_pending = Some(preset_id);
+                            if let Some(text) = sched.items[current_idx].announcement.clone().filter(|t| !t.trim().is_empty()) {
+                                tts::announce(command_tx_clone.clone(), errors_clone.clone(), text, None);
+                            }
+                            plan_scheduled_events(&mut sched);
+                        }
+                        
+                        sched.time_remaining -= 1;
+                        // The transition sound, its lead-in before the switch (or at it); an item
+                        // shorter than the lead-in gets it as soon as it starts
+                        if let Some(chime) = &sched.transition {
+                            let lead_in = (chime.lead_in_secs as i32).min(sched.current_duration as i32 * 60 - 1).max(0);
+                            if let Some((file, trim)) = sched.sound_files.get(&chime.sound_id).filter(|_| sched.time_remaining == lead_in) {
+                                let _ = command_tx_clone.send(AudioCommand::PlaySoundboard {
+                                    file_path: file.clone(),
+                                    volume: chime.volume.min(100) as f32 / 100.0,
+                                    trim: *trim,
+                                });
+                            }
+                        }
+                        // One-shot sounds of this item that are due
+                        let elapsed = (sched.current_duration * 60) as i32 - sched.time_remaining;
+                        for &(_, index) in sched.pending_events.iter().filter(|(at, _)| *at as i32 <= elapsed) {
+                            let event = &sched.items[current_idx].events[index];
+                            if let Some((file, trim)) = sched.sound_files.get(&event.sound_id) {
+                                let _ = command_tx_clone.send(AudioCommand::PlaySoundboard {
+                                    file_path: file.clone(),
+                                    volume: event.volume.min(100) as f32 / 100.0,
+                                    trim: *trim,
+                                });
+                            }
+                        }
+                        sched.pending_events.retain(|(at, _)| *at as i32 > elapsed);
+                        // Log every 10 seconds to avoid spam
+                        if sched.time_remaining % 10 == 0 {
+                            log::debug!("[Scheduler] Tick: item {}/{}, time_remaining={}", 
+                                current_idx, sched.items.len(), sched.time_remaining);
+                        }
+                        
+                        if sched.time_remaining <= 0 {
+                            // Advance to next item
+                            log::debug!("[Scheduler] Time expired, advancing to next item");
+                            let next_index = (sched.current_item_index + 1) % sched.items.len();
+                            
+                            // Clone values before mutating sched
+                            let next_preset_id = sched.items[next_index].preset_id.clone();
+                            let min = sched.items[next_index].min_minutes.min(sched.items[next_index].max_minutes);
+                            let max = sched.items[next_index].min_minutes.max(sched.items[next_index].max_minutes);
+                            let duration = if min == max {
+                                min
+                            } else {
+                                min + (rand::random::<u32>() % (max - min + 1))
+                            };
+                            
+                            sched.current_item_index = next_index;
+                            sched.current_duration = duration;
+                            sched.time_remaining = (duration * 60) as i32;
+                            plan_scheduled_events(&mut sched);
+                            
+                            // Queue the next preset to load
+                            scheduler_preset_pending = Some(next_preset_id);
+                            last_scheduler_item_index = Some(next_index);
+                            if let Some(text) = sched.items[next_index].announcement.clone().filter(|t| !t.trim().is_empty()) {
+                                tts::announce(command_tx_clone.clone(), errors_clone.clone(), text, None);
+                            }
+                        }
+                    } else if !sched.is_playing {
+                        last_scheduler_item_index = None;
+                    }
+                }
+                
+                // Handle pending scheduler preset load
+                if let Some(preset_id) = scheduler_preset_pending.take() {
+                    log::debug!("[Scheduler] Loading preset: {}", preset_id);
+                    let presets_path_opt = presets_dir_clone.lock().clone();
+                    if presets_path_opt.is_none() {
+                        log::error!("[Scheduler] presets_dir is not set");
+                    }
+                    if let Some(presets_path) = presets_path_opt {
+                        let preset_path = presets_path.join(format!("{}.soundscape", &preset_id));
+                        log::debug!("[Scheduler] Preset path: {:?}, exists: {}", preset_path, preset_path.exists());
+                        if preset_path.exists() {
+                            if let Ok(content) = fs::read_to_string(&preset_path) {
+                                match serde_json::from_str::<SoundscapePreset>(&content) {
+                                    Ok(preset) => {
+                                        log::debug!("[Scheduler] Loaded preset with {} sounds", preset.sounds.len());
+                                    // Get current active ambient IDs
+                                    let current_ids: std::collections::HashSet<String> = {
+                                        active_ambients_clone.lock().keys().cloned().collect()
+                                    };
+                                    
+                                    // Get new preset sound IDs
+                                    let new_ids: std::collections::HashSet<String> = preset.sounds
+                                        .iter()
+                                        .filter(|s| s.enabled)
+                                        .map(|s| s.sound_id.clone())
+                                        .collect();
+                                    
+                                    // Stop sounds not in new preset (with scheduler fade)
+                                    for id in current_ids.difference(&new_ids) {
+                                        log::debug!("[Scheduler] Fading out removed sound: {}", id);
+                                        scheduler_fading_out.insert(id.clone(), (clock.now(), SCHEDULER_FADE_SECS));
+                                    }
+                                    
+                                    // Start or update sounds in new preset
+                                    for sound in &preset.sounds {
+                                        if !sound.enabled {
+                                            continue;
+                                        }
+                                        
+                                        let new_settings = AmbientSettings {
+                                            volume: sound.volume as f32 / 100.0,
+                                            pitch: sound.pitch,
+                                            pan: sound.pan as f32 / 100.0,
+                                            low_pass_freq: sound.low_pass_freq as f32,
+                                            reverb_type: "off".to_string(),
+                                            algorithmic_reverb: sound.algorithmic_reverb as f32 / 100.0,
+                                            repeat_min: sound.repeat_range_min,
+                                            repeat_max: sound.repeat_range_max,
+                                            pause_min: sound.pause_range_min,
+                                            pause_max: sound.pause_range_max,
+                                            volume_variation: sound.volume_variation as f32 / 100.0,
+                                            event_probability: sound.event_probability as f32 / 100.0,
+                                            sync_group: sound.sync_group.clone(),
+                                            random_start: sound.random_start,
+                                            duck_music: sound.duck_music,
+                                        };
+                                        
+                                        let id = sound.sound_id.clone();
+                                        // Construct full path from category_path + filename
+                                        let file_a = if sound.files_a.is_empty() {
+                                            String::new()
+                                        } else {
+                                            content_path(&sound.category_path, &sound.files_a).to_string_lossy().to_string()
+                                        };
+                                        let file_b = if sound.files_b.is_empty() {
+                                            String::new()
+                                        } else {
+                                            content_path(&sound.category_path, &sound.files_b).to_string_lossy().to_string()
+                                        };
+                                        
+                                        // Check if already playing and if settings changed
+                                        let (already_playing, settings_changed) = {
+                                            let active = active_ambients_clone.lock();
+                                            if let Some(info) = active.get(&id) {
+                                                // Only a pitch change needs a restart; pan, low-pass and reverb adjust in place
+                                                let changed = (info.settings.pitch - new_settings.pitch).abs() > 0.001;
+                                                (true, changed)
+                                            } else {
+                                                (false, false)
+                                            }
+                                        };
+                                        
+                                        if already_playing && settings_changed {
+                                            // Pitch changed - immediately stop old and start new with fade-in
+                                            log::debug!("[Scheduler] Pitch changed for {}, restarting with new settings", id);
+                                            // Immediately stop the old sound (don't use fade-out queue since we'll reuse the ID)
+                                            if let Some(state) = ambient_states.remove(&id) {
+                                                state.sink.stop();
+                                            }
+                                            active_ambients_clone.lock().remove(&id);
+                                            // Remove from fade-out queue in case it's there
+                                            scheduler_fading_out.remove(&id);
+                                            // Queue the new sound to start with fade-in
+                                            engine_stats_clone.command_sent();
+                                            let _ = command_tx_clone.send(AudioCommand::PlayAmbientScheduler {
+                                                id,
+                                                file_a,
+                                                file_b,
+                                                settings: new_settings,
+                                            });
+                                        } else if already_playing {
+                                            // Keep playing and move to the new volume and effects
+                                            log::debug!("[Scheduler] Keeping sound {} (same pitch)", id);
+                                            engine_stats_clone.command_sent();
+                                            let _ = command_tx_clone.send(AudioCommand::UpdateAmbientSettingsScheduler {
+                                                id,
+                                                settings: new_settings,
+                                            });
+                                        } else {
+                                            // New sound - start it
+                                            log::debug!("[Scheduler] Starting new sound: {}", id);
+                                            engine_stats_clone.command_sent();
+                                            let _ = command_tx_clone.send(AudioCommand::PlayAmbientScheduler {
+                                                id,
+                                                file_a,
+                                                file_b,
+                                                settings: new_settings,
+                                            });
+                                        }
+                                    }
+                                    }
+                                    Err(e) => {
+                                        log::error!("[Scheduler] Failed to parse preset: {}", e);
+                                    }
+                                }
+                            } else {
+                                log::error!("[Scheduler] Failed to read preset file");
+                            }
+                        }
+                    }
+                }
+                
+                // Handle pending auto-advance (play next track in playlist)
+                if let Some((file_path, track_info, prebuffered_source)) = pending_auto_advance.take() {
+                    // Match the next track's gain to the loudness of the track that just finished
+                    current_track_gain = if loudness_matching {
+                        let previous_path = current_track_clone.lock().as_ref().map(|t| t.file_path.clone());
+                        matched_gain(&loudness_cache, previous_path.map(|path| (path, current_track_gain)), &file_path)
+                    } else {
+                        1.0
+                    };
+                    
+                    // Reset fade states for new track
+                    fade_out_active = false;
+                    prebuffer_started = false;
+                    loop_section = None;
+                    loop_seek_pending = false;
+                    progress_clone.lock().loop_section = None;
+                    sample_buffer_clone.clear();
+                    announce_track(&events_clone, &mut started_track, Some(&track_info));
+                    *current_track_clone.lock() = Some(track_info);
+                    
+                    // Prebuffered if it finished decoding in time, otherwise open it now
+                    let decoded = match prebuffered_source.and_then(|rx| rx.try_recv().ok().flatten()) {
+                        Some(source) => Some(source),
+                        None => match File::open(&file_path) {
+                            Ok(file) => match engine_stats_clone.time_decode(|| Decoder::new(BufReader::new(file))) {
+                                Ok(source) => Some(Box::new(source) as Box<dyn Source<Item = i16> + Send>),
+                                Err(e) => {
+                                    errors_clone.report(errors::ErrorCode::Decode, format!("Auto-advance: Failed to decode audio: {}", e), Some(file_path.clone()));
+                                    None
+                                }
+                            },
+                            Err(e) => {
+                                errors_clone.report(errors::ErrorCode::FileOpen, format!("Auto-advance: Failed to open file: {}", e), Some(file_path.clone()));
+                                None
+                            }
+                        },
+                    };
+                    if let Some(source) = decoded {
+                        let duration = source.total_duration()
+                            .map(|d| d.as_secs_f64())
+                            .unwrap_or(0.0);
+                        
+                        let source_f32 = source.convert_samples::<f32>().amplify(current_track_gain * playlist_gain);
+                        let analyzing_source = AnalyzingSource::new(
+                            MeteringSource::new(source_f32, music_meter.clone()),
+                            sample_buffer_clone.clone()
+                        );
+                        
+                        match mix_tap::new_tapped_sink(output.as_ref(), &mix_tap_clone, mix_tap::Bus::Music) {
+                            Ok(sink) => {
+                                let start_vol = if crossfade_duration > 0.0 {
+                                    fade_in_progress = Some((clock.now(), crossfade_duration));
+                                    0.0
+                                } else if is_muted || is_master_muted {
+                                    0.0
+                                } else {
+                                    music_volume * master_volume
+                                };
+                                sink.set_volume(start_vol);
+                                sink.append(analyzing_source);
+                                
+                                track_start = Some(clock.now());
+                                track_duration = duration;
+                                current_sink = Some(sink);
+                                
+                                let mut prog = progress_clone.lock();
+                                prog.current_time = 0.0;
+                                prog.duration = duration;
+                                prog.is_playing = true;
+                                prog.is_finished = false;
+                            }
+                            Err(e) => errors_clone.report(errors::ErrorCode::Sink, format!("Auto-advance: Failed to create sink: {}", e), None),
+                        }
+                    }
+                }
+                
+                // Check if soundboard finished playing
+                if let Some(ref sink) = soundboard_sink {
+                    if sink.empty() {
+                        duck_target = 0.0; // Start fading out ducking
+                        soundboard_sink = None;
+                        *soundboard_playing_clone.lock() = false;
+                    }
+                }
+                
+                // Fade out a soundboard sound stopped with a fade
+                let soundboard_outgoing_done = if let Some((ref sink, fade_start, fade_duration, start_vol)) = soundboard_outgoing {
+                    let progress = (clock.since(fade_start).as_secs_f32() / fade_duration).clamp(0.0, 1.0);
+                    sink.set_volume(start_vol * (1.0 - progress));
+                    progress >= 1.0 || sink.empty()
+                } else {
+                    false
+                };
+                if soundboard_outgoing_done {
+                    if let Some((sink, _, _, _)) = soundboard_outgoing.take() {
+                        sink.stop();
+                    }
+                }
+                
+                // Smoothly fade duck_progress toward duck_target. Whether anything was ducked before this
+                // step, so the step that ends a duck still restores the volumes.
+                let music_was_ducked = duck_progress.max(story_duck_progress) > 0.0;
+                let ambients_were_ducked = duck_progress > 0.0;
+                let duck_step = clock.since(last_duck_update).as_secs_f32() / DUCK_FADE_SECS;
+                last_duck_update = clock.now();
+                if duck_progress < duck_target {
+                    duck_progress = (duck_progress + duck_step).min(duck_target);
+                } else if duck_progress > duck_target {
+                    duck_progress = (duck_progress - duck_step).max(duck_target);
+                }
+                
+                // Story sounds duck the music with the same envelope while they're audible
+                // (not resting between cycles, not muted, engine not paused)
+                story_duck_target = if ambient_paused_at.is_none() && ambient_states.values().any(|s| {
+                    s.settings.duck_music && !s.is_paused && !s.sink.empty() && s.sink.volume() > 0.0
+                }) { 1.0 } else { 0.0 };
+                if story_duck_progress < story_duck_target {
+                    story_duck_progress = (story_duck_progress + duck_step).min(story_duck_target);
+                } else if story_duck_progress > story_duck_target {
+                    story_duck_progress = (story_duck_progress - duck_step).max(story_duck_target);
+                }
+                // The music follows whichever duck is deeper; ambients only duck for the soundboard
+                let music_duck = duck_progress.max(story_duck_progress);
+                
+                // Apply ducking to music volume (gradual)
+                let target_vol = if is_muted || is_master_muted {
+                    0.0
+                } else {
+                    let base_vol = music_volume * master_volume;
+                    // Apply gradual ducking based on duck_progress
+                    base_vol * (1.0 - music_duck * duck_amount)
+                };
+                
+                // Update music sink volume during ducking transitions
+                if music_was_ducked || music_duck > 0.0 || duck_target != duck_progress || story_duck_target != story_duck_progress {
+                    if let Some(ref sink) = current_sink {
+                        if fade_in_progress.is_none() {
+                            sink.set_volume(target_vol);
+                        }
+                    }
+                }
+                if ambients_were_ducked || duck_progress > 0.0 || duck_target != duck_progress {
+                    // Update ambient volumes during ducking transitions
+                    for state in ambient_states.values() {
+                        let vol = calc_ambient_volume(
+                            &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                            is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                        );
+                        state.sink.set_volume(vol);
+                    }
+                }
+                
+                // Fade out the previous track after a manual skip (overlaps the new track's fade-in)
+                let outgoing_done = if let Some((ref sink, fade_start, fade_duration, start_vol)) = outgoing_sink {
+                    let progress = (clock.since(fade_start).as_secs_f32() / fade_duration).clamp(0.0, 1.0);
+                    sink.set_volume(start_vol * (1.0 - progress));
+                    progress >= 1.0 || sink.empty()
+                } else {
+                    false
+                };
+                if outgoing_done {
+                    if let Some((sink, _, _, _)) = outgoing_sink.take() {
+                        sink.stop();
+                    }
+                }
+                
+                // Handle fade-in for new tracks
+                if let Some((fade_start, fade_duration)) = fade_in_progress {
+                    let elapsed = clock.since(fade_start).as_secs_f32();
+                    let progress = (elapsed / fade_duration).clamp(0.0, 1.0);
+                    
+                    if let Some(ref sink) = current_sink {
+                        sink.set_volume(target_vol * progress);
+                    }
+                    
+                    // Fade-in complete
+                    if progress >= 1.0 {
+                        fade_in_progress = None;
+                        if let Some(ref sink) = current_sink {
+                            sink.set_volume(target_vol);
+                        }
+                    }
+                }
+                
+                // Handle A-B section looping - jump back to the loop start once we pass the end
+                if let (Some((loop_start, loop_end)), Some(start), Some(ref sink)) = (loop_section, track_start, &current_sink) {
+                    if !loop_seek_pending && !sink.is_paused() && !sink.empty()
+                        && clock.since(start).as_secs_f64() >= loop_end
+                    {
+                        loop_seek_pending = true;
+                        engine_stats_clone.command_sent();
+                        let _ = command_tx_clone.send(AudioCommand::Seek(loop_start));
+                    }
+                }
+                
+                // Handle automatic fade-out near end of track (not while looping a section, nor with a
+                // gapless track queued behind it, which would start faded out)
+                if crossfade_duration > 0.0 && !fade_out_active && loop_section.is_none() && gapless_next.is_none() {
+                    if let (Some(start), Some(ref sink)) = (track_start, &current_sink) {
+                        if !sink.is_paused() && !sink.empty() {
+                            let current_time = clock.since(start).as_secs_f64();
+                            let time_remaining = track_duration - current_time;
+                            
+                            // Start fade-out when we're within crossfade_duration of the end
+                            if time_remaining > 0.0 && time_remaining <= crossfade_duration as f64 {
+                                fade_out_active = true;
+                            }
+                        }
+                    }
+                }
+                
+                // Pick the next playlist track a few seconds before the fade-out starts and decode it in
+                // the background, so a slow disk doesn't leave a gap when this one ends
+                if !prebuffer_started && loop_section.is_none() {
+                    if let (Some(start), Some(ref sink)) = (track_start, &current_sink) {
+                        let time_remaining = track_duration - clock.since(start).as_secs_f64();
+                        if !sink.is_paused() && !sink.empty() && time_remaining > 0.0
+                            && time_remaining <= crossfade_duration as f64 + PREBUFFER_LEAD_SECS
+                        {
+                            prebuffer_started = true;
+                            let ps = playlist_state_clone.lock().clone();
+                            let current_id = current_track_clone.lock().as_ref().map(|t| t.id.clone());
+                            let next = pick_next_track(&ps, &all_tracks_clone.lock(), &playlists_clone.lock(), current_id.as_deref());
+                            if let Some((next_index, file_path, track_info)) = next {
+                                log::debug!("Prebuffering next track: {}", file_path);
+                                let (source_tx, source_rx) = channel();
+                                let path = file_path.clone();
+                                let stats = engine_stats_clone.clone();
+                                // Loudness matching needs the next track's loudness too; measure it here,
+                                // before handing the source over, so it's cached by the time the track is
+                                // queued or starts and the audio thread never decodes for it
+                                let cache = loudness_matching.then(|| loudness_cache.clone());
+                                thread::spawn(move || {
+                                    if let Some(cache) = cache {
+                                        if !cache.lock().contains_key(&path) {
+                                            if let Some(rms) = analyze_track_loudness(&path) {
+                                                cache.lock().insert(path.clone(), rms);
+                                            }
+                                        }
+                                    }
+                                    let source = fs::read(&path).ok()
+                                        .and_then(|bytes| stats.time_decode(|| Decoder::new(Cursor::new(bytes))).ok())
+                                        .map(|source| Box::new(source) as Box<dyn Source<Item = i16> + Send>);
+                                    let _ = source_tx.send(source);
+                                });
+                                prebuffered = Some(PrebufferedTrack {
+                                    file_path,
+                                    track_info,
+                                    next_index,
+                                    picked_from: (ps.current_playlist_id.clone(), ps.current_index),
+                                    source: source_rx,
+                                });
+                            }
+                        }
+                    }
+                }
+                
+                // Gapless: with no crossfade, queue the prebuffered track on the playing sink as soon as it
+                // has decoded, so it follows this one sample for sample instead of waiting for a new sink
+                if crossfade_duration == 0.0 && gapless_next.is_none() && loop_section.is_none() && prebuffered.is_some() {
+                    if let Some(ref sink) = current_sink {
+                        let picked_from = {
+                            let ps = playlist_state_clone.lock();
+                            (ps.current_playlist_id.clone(), ps.current_index)
+                        };
+                        // A pick the playlist has moved on from is dropped; auto-advance picks again
+                        if let Some(p) = prebuffered.take().filter(|p| p.picked_from == picked_from) {
+                            match p.source.try_recv() {
+                                Ok(Some(source)) => {
+                                    let duration = source.total_duration().map(|d| d.as_secs_f64()).unwrap_or(0.0);
+                                    let gain = if loudness_matching {
+                                        let current_path = current_track_clone.lock().as_ref().map(|t| t.file_path.clone());
+                                        matched_gain(&loudness_cache, current_path.map(|path| (path, current_track_gain)), &p.file_path)
+                                    } else {
+                                        1.0
+                                    };
+                                    let started = Arc::new(std::sync::atomic::AtomicBool::new(false));
+                                    let cancelled = Arc::new(std::sync::atomic::AtomicBool::new(false));
+                                    let source_f32 = source.convert_samples::<f32>().amplify(gain * playlist_gain);
+                                    sink.append(GaplessSource {
+                                        inner: AnalyzingSource::new(
+                                            MeteringSource::new(source_f32, music_meter.clone()),
+                                            sample_buffer_clone.clone()
+                                        ),
+                                        started: started.clone(),
+                                        cancelled: cancelled.clone(),
+                                    });
+                                    log::debug!("Queued next track gapless: {}", p.file_path);
+                                    gapless_next = Some(GaplessNext {
+                                        track_info: p.track_info,
+                                        next_index: p.next_index,
+                                        duration,
+                                        gain,
+                                        started,
+                                        cancelled,
+                                    });
+                                }
+                                // Still decoding
+                                Err(std::sync::mpsc::TryRecvError::Empty) => prebuffered = Some(p),
+                                // Failed; auto-advance opens it again and reports why
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                
+                // The queued track has started: it's the current track from here, without a new sink
+                if gapless_next.as_ref().is_some_and(|next| next.started.load(std::sync::atomic::Ordering::Relaxed)) {
+                    if let Some(next) = gapless_next.take() {
+                        if let Some(next_index) = next.next_index {
+                            playlist_state_clone.lock().current_index = next_index as i32;
+                        }
+                        current_track_gain = next.gain;
+                        fade_out_active = false;
+                        prebuffer_started = false;
+                        track_start = Some(clock.now());
+                        track_duration = next.duration;
+                        announce_track(&events_clone, &mut started_track, Some(&next.track_info));
+                        *current_track_clone.lock() = Some(next.track_info);
+                        let mut prog = progress_clone.lock();
+                        prog.current_time = 0.0;
+                        prog.duration = next.duration;
+                    }
+                }
+                
+                // Apply fade-out volume
+                if fade_out_active {
+                    if let (Some(start), Some(ref sink)) = (track_start, &current_sink) {
+                        let current_time = clock.since(start).as_secs_f64();
+                        let time_remaining = (track_duration - current_time).max(0.0);
+                        let fade_progress = 1.0 - (time_remaining / crossfade_duration as f64).clamp(0.0, 1.0);
+                        
+                        // Only apply fade-out if we're not also fading in (which takes precedence)
+                        if fade_in_progress.is_none() {
+                            sink.set_volume(target_vol * (1.0 - fade_progress as f32));
+                        }
+                    }
+                }
+                
+                // Update progress and handle auto-advance
+                if let Some(ref sink) = current_sink {
+                    let is_empty = sink.empty();
+                    let is_paused = sink.is_paused();
+                    
+                    let mut prog = progress_clone.lock();
+                    prog.is_finished = is_empty;
+                    prog.is_playing = !is_empty && !is_paused;
+                    prog.duration = track_duration;
+                    if let Some(start) = track_start {
+                        if !is_paused {
+                            prog.current_time = clock.since(start).as_secs_f64();
+                        }
+                    }
+                    
+                    // A-B loop reaching the real end of the file - restart the section instead of advancing
+                    if was_playing && is_empty && !loop_seek_pending {
+                        if let Some((loop_start, _)) = loop_section {
+                            loop_seek_pending = true;
+                            engine_stats_clone.command_sent();
+                            let _ = command_tx_clone.send(AudioCommand::Seek(loop_start));
+                        }
+                    }
+                    
+                    // Auto-advance: if we were playing and track just finished, queue next track
+                    if was_playing && is_empty && pending_auto_advance.is_none() && loop_section.is_none() {
+                        announce_track(&events_clone, &mut started_track, None);
+                        let ps = playlist_state_clone.lock().clone();
+                        // Use the prebuffered track unless the playlist moved on since it was picked
+                        let picked_from = (ps.current_playlist_id.clone(), ps.current_index);
+                        let next = match prebuffered.take().filter(|p| p.picked_from == picked_from) {
+                            Some(p) => Some((p.next_index, p.file_path, p.track_info, Some(p.source))),
+                            None => {
+                                let current_id = current_track_clone.lock().as_ref().map(|t| t.id.clone());
+                                pick_next_track(&ps, &all_tracks_clone.lock(), &playlists_clone.lock(), current_id.as_deref())
+                                    .map(|(next_index, file_path, track_info)| (next_index, file_path, track_info, None))
+                            }
+                        };
+                        if let Some((next_index, file_path, track_info, source)) = next {
+                            // Update playlist state
+                            if let Some(next_index) = next_index {
+                                playlist_state_clone.lock().current_index = next_index as i32;
+                            }
+                            pending_auto_advance = Some((file_path, track_info, source));
+                        }
+                    }
+                    was_playing = !is_empty && !is_paused;
+                }
+                
+                // Update playback state for visualization with FFT
+                if last_analysis.elapsed() >= ANALYSIS_INTERVAL {
+                    last_analysis = Instant::now();
+                    let music_playing = current_sink.as_ref()
+                        .map(|s| !s.empty() && !s.is_paused())
+                        .unwrap_or(false);
+                    
+                    let active_ambient_count = ambient_states.values()
+                        .filter(|s| !s.is_paused && !s.sink.empty())
+                        .count() as u32;
+                    
+                    let effective_music_vol = if is_muted || is_master_muted { 0.0 } else { music_volume * master_volume };
+                    let effective_ambient_vol = if is_ambient_muted || is_master_muted { 0.0 } else { ambient_master_volume * master_volume };
+                    
+                    // Perform FFT on sample buffer (lock-free read)
+                    // Analyze each channel separately plus the mid (L+R)/2 signal, so stereo content isn't smeared
+                    let (left_samples, right_samples) = sample_buffer_clone.get_latest(FFT_WINDOW);
+                    let mid_samples: Vec<f32> = left_samples.iter().zip(right_samples.iter())
+                        .map(|(l, r)| (l + r) * 0.5)
+                        .collect();
+                    // Spectrum analysis only while something uses it (a visualizer, lighting sync or band
+                    // triggers); otherwise the bins decay to zero
+                    let spectrum_wanted = visualization_active.load(std::sync::atomic::Ordering::Relaxed) || !fft_triggers.is_empty();
+                    let (frequencies, left_frequencies, right_frequencies) = if spectrum_wanted {
+                        (
+                            analyze_spectrum(fft.as_ref(), &mut fft_buffer, &mid_samples, &frequency_ranges),
+                            analyze_spectrum(fft.as_ref(), &mut fft_buffer, &left_samples, &frequency_ranges),
+                            analyze_spectrum(fft.as_ref(), &mut fft_buffer, &right_samples, &frequency_ranges),
+                        )
+                    } else {
+                        (vec![0.0f32; FFT_SIZE], vec![0.0f32; FFT_SIZE], vec![0.0f32; FFT_SIZE])
+                    };
+                    
+                    // Detect a playing track that produces no sound (bad file, decoder stall)
+                    let music_rms = (mid_samples.iter().map(|s| s * s).sum::<f32>() / mid_samples.len().max(1) as f32).sqrt();
+                    if music_playing && music_rms < SILENCE_RMS_THRESHOLD {
+                        let since = *silence_start.get_or_insert_with(|| clock.now());
+                        if !silence_reported && clock.since(since).as_secs_f32() >= SILENCE_TIMEOUT_SECS {
+                            silence_reported = true;
+                            events_clone.emit(PLAYBACK_SILENCE_EVENT, SilenceEvent {
+                                track_id: current_track_clone.lock().as_ref().map(|t| t.id.clone()),
+                                current_time: progress_clone.lock().current_time,
+                            });
+                            // Stopping the sink lets the normal auto-advance pick the next track
+                            if skip_on_silence {
+                                if let Some(ref sink) = current_sink {
+                                    sink.stop();
+                                }
+                            }
+                        }
+                    } else {
+                        silence_start = None;
+                        silence_reported = false;
+                    }
+                    
+                    // Compute ambient frequencies from ambient sample buffer (same FFT approach)
+                    let mut ambient_frequencies = vec![0.0f32; FFT_SIZE];
+                    if spectrum_wanted && active_ambient_count > 0 {
+                        let ambient_samples = ambient_sample_buffer_clone.get_latest(1024);
+                        if ambient_samples.len() >= 1024 {
+                            let mut planner = FftPlanner::new();
+                            let fft = planner.plan_fft_forward(1024);
+                            let mut ambient_fft_buffer: Vec<Complex<f32>> = ambient_samples.iter()
+                                .take(1024)
+                                .map(|&s| Complex::new(s, 0.0))
+                                .collect();
+                            fft.process(&mut ambient_fft_buffer);
+                            
+                            // Convert to frequency bins (same logic as music FFT)
+                            ambient_frequencies = bin_fft_magnitudes(&ambient_fft_buffer, &frequency_ranges);
+                        }
+                    }
+                    
+                    // Soundboard level and frequencies (only while a sound is playing)
+                    let soundboard_active = soundboard_sink.as_ref()
+                        .map(|s| !s.empty() && !s.is_paused())
+                        .unwrap_or(false);
+                    let effective_soundboard_vol = soundboard_sink.as_ref().map(|s| s.volume()).unwrap_or(0.0);
+                    let mut soundboard_frequencies = vec![0.0f32; FFT_SIZE];
+                    let mut soundboard_level = 0.0f32;
+                    if soundboard_active {
+                        let soundboard_samples = soundboard_sample_buffer_clone.get_latest(FFT_WINDOW);
+                        let rms = (soundboard_samples.iter().map(|s| s * s).sum::<f32>() / FFT_WINDOW as f32).sqrt();
+                        soundboard_level = (rms * effective_soundboard_vol).clamp(0.0, 1.0);
+                        if spectrum_wanted {
+                            soundboard_frequencies = analyze_spectrum(fft.as_ref(), &mut fft_buffer, &soundboard_samples, &frequency_ranges);
+                        }
+                    }
+                    
+                    // Drain bus meters, applying each sink's output gain
+                    music_loudness.push(music_meter.take(), current_sink.as_ref().map(|s| s.volume()).unwrap_or(0.0));
+                    {
+                        let parts: Vec<((f64, u64, f32), f32)> = ambient_states.values()
+                            .map(|s| (s.meter.take(), s.sink.volume()))
+                            .collect();
+                        let mut levels = HashMap::new();
+                        for (id, &((sum_squares, frames, _), gain)) in ambient_states.keys().zip(parts.iter()) {
+                            let power = if frames > 0 { sum_squares / frames as f64 * (gain * gain) as f64 } else { 0.0 };
+                            let previous = ambient_level_power.get(id).copied().unwrap_or(power);
+                            let smoothed = previous + (power - previous) * AMBIENT_LEVEL_SMOOTHING;
+                            levels.insert(id.clone(), smoothed);
+                        }
+                        ambient_level_power = levels;
+                        *ambient_levels_clone.lock() = ambient_level_power.iter()
+                            .map(|(id, &power)| (id.clone(), power_to_db(power)))
+                            .collect();
+                        ambient_loudness.push_mix(&parts);
+                    }
+                    // Cycle phase of each sound, for get_active_ambients
+                    *ambient_phases_clone.lock() = ambient_states.iter().map(|(id, s)| {
+                        let phase = if s.is_paused {
+                            let next_cycle_secs = match &s.settings.sync_group {
+                                Some(group) => sync_pauses.get(group).map(|secs| secs.max(0.0)),
+                                None => Some(s.pause_remaining.max(0.0)),
+                            };
+                            AmbientPhase { state: AmbientCycleState::Paused, next_cycle_secs }
+                        } else if s.is_playing_a {
+                            AmbientPhase { state: AmbientCycleState::PlayingA, next_cycle_secs: None }
+                        } else {
+                            AmbientPhase { state: AmbientCycleState::PlayingB, next_cycle_secs: None }
+                        };
+                        (id.clone(), phase)
+                    }).collect();
+                    soundboard_loudness.push(soundboard_meter.take(), effective_soundboard_vol);
+                    {
+                        let buses = [&music_loudness, &ambient_loudness, &soundboard_loudness];
+                        // Master assumes uncorrelated buses (powers add); peak is the worst case sum
+                        let master_momentary: f64 = buses.iter().map(|b| b.power(METER_MOMENTARY_TICKS)).sum();
+                        let master_short_term: f64 = buses.iter().map(|b| b.power(METER_SHORT_TERM_TICKS)).sum();
+                        let master_peak: f32 = buses.iter().map(|b| b.peak()).sum();
+                        let reading = |b: &BusLoudness| BusMeterReading::from_powers(
+                            b.power(METER_MOMENTARY_TICKS),
+                            b.power(METER_SHORT_TERM_TICKS),
+                            b.peak(),
+                        );
+                        *meters_clone.lock() = MeterReadings {
+                            music: reading(&music_loudness),
+                            ambient: reading(&ambient_loudness),
+                            soundboard: reading(&soundboard_loudness),
+                            master: BusMeterReading::from_powers(master_momentary, master_short_term, master_peak),
+                        };
+                    }
+                    
+                    let mut state = playback_state_clone.lock();
+                    state.music_playing = music_playing;
+                    state.music_volume = effective_music_vol;
+                    state.ambient_count = active_ambient_count;
+                    state.ambient_volume = effective_ambient_vol;
+                    state.master_volume = master_volume;
+                    state.is_muted = is_master_muted;
+                    // Smooth against the previous tick so bars rise quickly and fall gently
+                    smooth_bins(&mut state.frequencies, &frequencies, fft_attack, fft_decay);
+                    smooth_bins(&mut state.left_frequencies, &left_frequencies, fft_attack, fft_decay);
+                    smooth_bins(&mut state.right_frequencies, &right_frequencies, fft_attack, fft_decay);
+                    smooth_bins(&mut state.ambient_frequencies, &ambient_frequencies, fft_attack, fft_decay);
+                    state.soundboard_playing = soundboard_active;
+                    state.soundboard_volume = effective_soundboard_vol;
+                    state.soundboard_level = soundboard_level;
+                    smooth_bins(&mut state.soundboard_frequencies, &soundboard_frequencies, fft_attack, fft_decay);
+                    state.duck_level = duck_progress.max(story_duck_progress) * duck_amount;
+                    
+                    // Evaluate band triggers; each fires once per crossing and re-arms when the band drops
+                    for trigger_state in fft_triggers.iter_mut() {
+                        let bins = match trigger_state.trigger.source.as_str() {
+                            "ambient" => &state.ambient_frequencies,
+                            "soundboard" => &state.soundboard_frequencies,
+                            _ => &state.frequencies,
+                        };
+                        let level = trigger_band_level(&trigger_state.trigger, bins).unwrap_or(0.0);
+                        if level <= trigger_state.trigger.threshold {
+                            trigger_state.above_since = None;
+                            trigger_state.fired = false;
+                            continue;
+                        }
+                        let since = *trigger_state.above_since.get_or_insert_with(|| clock.now());
+                        let hold = std::time::Duration::from_millis(trigger_state.trigger.hold_ms as u64);
+                        if !trigger_state.fired && clock.since(since) >= hold {
+                            trigger_state.fired = true;
+                            events_clone.emit(FFT_TRIGGER_EVENT, FftTriggerEvent {
+                                name: trigger_state.trigger.name.clone(),
+                                level,
+                            });
+                        }
+                    }
+                    
+                    if let Some(interval) = fft_event_interval.filter(|_| spectrum_wanted) {
+                        if last_fft_event.elapsed() >= interval {
+                            last_fft_event = Instant::now();
+                            events_clone.emit(PLAYBACK_FFT_EVENT, PlaybackStateResponse::from(state.clone()));
+                        }
+                    }
+                }
+                
+                // Check for commands until the next tick is due. An overdue tick runs before any queued
+                // command, so a burst of commands can't hold fades back
+                let wait_start = Instant::now();
+                let received = if next_tick <= wait_start {
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout)
+                } else {
+                    command_rx.recv_timeout(next_tick - wait_start)
+                };
+                let waited = wait_start.elapsed();
+                match &received {
+                    Ok(_) => engine_stats_clone.command_received(),
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => engine_stats_clone.tick(tick_interval),
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {}
+                }
+                match received {
+                    Ok(cmd) => match cmd {
+                        AudioCommand::Play { file_path, track_info } => {
+                            silence_start = None;
+                            silence_reported = false;
+                            // Manual skip: hand the old track to the outgoing fade instead of cutting it off
+                            let mut fade_in_duration = crossfade_duration;
+                            cancel_gapless(&mut gapless_next);
+                            if let Some((sink, _, _, _)) = outgoing_sink.take() {
+                                sink.stop();
+                            }
+                            if let Some(old_sink) = current_sink.take() {
+                                if skip_crossfade_duration > 0.0 && !old_sink.empty() && !old_sink.is_paused() {
+                                    let start_vol = old_sink.volume();
+                                    outgoing_sink = Some((old_sink, clock.now(), skip_crossfade_duration, start_vol));
+                                    fade_in_duration = skip_crossfade_duration;
+                                } else {
+                                    old_sink.stop();
+                                }
+                            }
+                            
+                            // Reset fade states for new track
+                            fade_out_active = false;
+                            prebuffered = None;
+                            prebuffer_started = false;
+                            
+                            // Manually started tracks play at unity gain and become the loudness reference
+                            current_track_gain = 1.0;
+                            if loudness_matching {
+                                measure_loudness(&loudness_cache, &file_path);
+                            }
+                            
+                            // A-B loop belongs to the previous track
+                            loop_section = None;
+                            loop_seek_pending = false;
+                            progress_clone.lock().loop_section = None;
+                            
+                            // Clear sample buffer for new track
+                            sample_buffer_clone.clear();
+                            
+                            // Store current track info
+                            announce_track(&events_clone, &mut started_track, Some(&track_info));
+                            *current_track_clone.lock() = Some(track_info);
+                            
+                            // Load and play new file
+                            match File::open(&file_path) {
+                                Ok(file) => {
+                                    let reader = BufReader::new(file);
+                                    match engine_stats_clone.time_decode(|| Decoder::new(reader)) {
+                                        Ok(source) => {
+                                            let duration = source.total_duration()
+                                                .map(|d| d.as_secs_f64())
+                                                .unwrap_or(0.0);
+                                            
+                                            // Convert to f32 samples and wrap with AnalyzingSource for FFT
+                                            let source_f32 = source.convert_samples::<f32>().amplify(playlist_gain);
+                                            let analyzing_source = AnalyzingSource::new(
+                                                MeteringSource::new(source_f32, music_meter.clone()),
+                                                sample_buffer_clone.clone()
+                                            );
+                                            
+                                            match mix_tap::new_tapped_sink(output.as_ref(), &mix_tap_clone, mix_tap::Bus::Music) {
+                                                Ok(sink) => {
+                                                    // Start at 0 volume and fade in if crossfade enabled
+                                                    let start_vol = if fade_in_duration > 0.0 {
+                                                        fade_in_progress = Some((clock.now(), fade_in_duration));
+                                                        0.0
+                                                    } else if is_muted || is_master_muted {
+                                                        0.0
+                                                    } else {
+                                                        music_volume * master_volume
+                                                    };
+                                                    sink.set_volume(start_vol);
+                                                    sink.append(analyzing_source);
+                                                    
+                                                    track_start = Some(clock.now());
+                                                    track_duration = duration;
+                                                    current_sink = Some(sink);
+                                                    
+                                                    let mut prog = progress_clone.lock();
+                                                    prog.current_time = 0.0;
+                                                    prog.duration = duration;
+                                                    prog.is_playing = true;
+                                                    prog.is_finished = false;
+                                                }
+                                                Err(e) => errors_clone.report(errors::ErrorCode::Sink, format!("Failed to create sink: {}", e), None),
+                                            }
+                                        }
+                                        Err(e) => errors_clone.report(errors::ErrorCode::Decode, format!("Failed to decode audio: {}", e), Some(file_path.clone())),
+                                    }
+                                }
+                                Err(e) => errors_clone.report(errors::ErrorCode::FileOpen, format!("Failed to open file: {}", e), Some(file_path.clone())),
+                            }
+                        }
+                        AudioCommand::Stop { fade_secs } => {
+                            cancel_gapless(&mut gapless_next);
+                            if let Some((sink, _, _, _)) = outgoing_sink.take() {
+                                sink.stop();
+                            }
+                            if let Some(sink) = current_sink.take() {
+                                // A fading stop lets the track tail off like the old one after a skip
+                                match fade_secs {
+                                    Some(secs) if secs > 0.0 && !sink.is_paused() && !sink.empty() => {
+                                        let start_vol = sink.volume();
+                                        outgoing_sink = Some((sink, clock.now(), secs, start_vol));
+                                    }
+                                    _ => sink.stop(),
+                                }
+                            }
+                            track_start = None;
+                            loop_section = None;
+                            loop_seek_pending = false;
+                            prebuffered = None;
+                            prebuffer_started = false;
+                            *current_track_clone.lock() = None;
+                            announce_track(&events_clone, &mut started_track, None);
+                            let mut prog = progress_clone.lock();
+                            prog.is_playing = false;
+                            prog.is_finished = true;
+                            prog.loop_section = None;
+                        }
+                        AudioCommand::Pause => {
+                            if let Some((sink, _, _, _)) = outgoing_sink.take() {
+                                sink.stop();
+                            }
+                            if let Some(ref sink) = current_sink {
+                                sink.pause();
+                                pause_start = Some(clock.now());
+                            }
+                        }
+                        AudioCommand::Resume => {
+                            if let Some(ref sink) = current_sink {
+                                sink.play();
+                                // Adjust track_start to account for pause duration
+                                if let (Some(ps), Some(ts)) = (pause_start.take(), track_start) {
+                                    let pause_duration = clock.since(ps);
+                                    track_start = Some(ts + pause_duration);
+                                }
+                            }
+                        }
+                        AudioCommand::Seek(position) => {
+                            loop_seek_pending = false;
+                            // Seek the decoder on the playing sink, so the rest of the file isn't decoded
+                            // to get there. A paused track stays paused, a fade-in or a manual skip's
+                            // fade-out carries on as it was, and a gapless next track stays queued.
+                            let target = if track_duration > 0.0 { position.min(track_duration) } else { position }.max(0.0);
+                            let seeked = current_track_clone.lock().is_some() && output.plays_in_real_time() && current_sink.as_ref()
+                                .filter(|sink| !sink.empty())
+                                .is_some_and(|sink| match sink.try_seek(std::time::Duration::from_secs_f64(target)) {
+                                    Ok(()) => true,
+                                    Err(e) => {
+                                        log::debug!("Can't seek the playing track, reopening it: {}", e);
+                                        false
+                                    }
+                                });
+                            if seeked {
+                                if let Some(ref sink) = current_sink {
+                                    sample_buffer_clone.clear();
+                                    let was_paused = sink.is_paused();
+                                    // An end-of-track fade-out starts again once the new position reaches it
+                                    if fade_out_active {
+                                        fade_out_active = false;
+                                        let mut effective_vol = if is_muted || is_master_muted {
+                                            0.0
+                                        } else {
+                                            music_volume * master_volume * (1.0 - duck_progress.max(story_duck_progress) * duck_amount)
+                                        };
+                                        if let Some((fade_start, fade_duration)) = fade_in_progress {
+                                            effective_vol *= (clock.since(fade_start).as_secs_f32() / fade_duration).clamp(0.0, 1.0);
+                                        }
+                                        sink.set_volume(effective_vol);
+                                    }
+                                    let now = clock.now();
+                                    track_start = Some(now - std::time::Duration::from_secs_f64(target));
+                                    // Resume moves track_start on by the time since this
+                                    pause_start = was_paused.then_some(now);
+                                    let mut prog = progress_clone.lock();
+                                    prog.current_time = target;
+                                    prog.is_playing = !was_paused;
+                                    prog.is_finished = false;
+                                }
+                            // Otherwise reload the file at the position
+                            } else if let Some(track_info) = current_track_clone.lock().clone() {
+                                // The queued next track goes with the old sink; it's prebuffered again
+                                if cancel_gapless(&mut gapless_next) {
+                                    prebuffer_started = false;
+                                }
+                                let was_paused = current_sink.as_ref().is_some_and(|s| s.is_paused());
+                                if let Some(old_sink) = current_sink.take() {
+                                    old_sink.stop();
+                                }
+                                sample_buffer_clone.clear();
+                                
+                                if let Ok(file) = File::open(&track_info.file_path) {
+                                    let reader = BufReader::new(file);
+                                    if let Ok(mut source) = engine_stats_clone.time_decode(|| Decoder::new(reader)) {
+                                        let duration = source.total_duration()
+                                            .map(|d| d.as_secs_f64())
+                                            .unwrap_or(0.0);
+                                        
+                                        // Seek to the desired position; files that can't seek are decoded up to it
+                                        let skip_duration = std::time::Duration::from_secs_f64(position.min(duration).max(0.0));
+                                        let mut skip = std::time::Duration::ZERO;
+                                        if let Err(e) = source.try_seek(skip_duration) {
+                                            log::debug!("Can't seek in {}, skipping to the position: {}", track_info.file_path, e);
+                                            skip = skip_duration;
+                                        }
+                                        let source_f32 = source.convert_samples::<f32>();
+                                        let skipped_source = source_f32.skip_duration(skip).amplify(current_track_gain * playlist_gain);
+                                        let analyzing_source = AnalyzingSource::new(
+                                            MeteringSource::new(skipped_source, music_meter.clone()),
+                                            sample_buffer_clone.clone()
+                                        );
+                                        
+                                        if let Ok(sink) = mix_tap::new_tapped_sink(output.as_ref(), &mix_tap_clone, mix_tap::Bus::Music) {
+                                            let mut effective_vol = if is_muted || is_master_muted {
+                                                0.0
+                                            } else {
+                                                music_volume * master_volume * (1.0 - duck_progress.max(story_duck_progress) * duck_amount)
+                                            };
+                                            if let Some((fade_start, fade_duration)) = fade_in_progress {
+                                                effective_vol *= (clock.since(fade_start).as_secs_f32() / fade_duration).clamp(0.0, 1.0);
+                                            }
+                                            sink.set_volume(effective_vol);
+                                            if was_paused {
+                                                sink.pause();
+                                            }
+                                            sink.append(analyzing_source);
+                                            
+                                            let now = clock.now();
+                                            track_start = Some(now - skip_duration);
+                                            // Resume moves track_start on by the time since this
+                                            pause_start = was_paused.then_some(now);
+                                            track_duration = duration;
+                                            current_sink = Some(sink);
+                                            // The end-of-track fade-out starts again once the new position
+                                            // reaches it
+                                            fade_out_active = false;
+                                            
+                                            let mut prog = progress_clone.lock();
+                                            prog.current_time = position;
+                                            prog.duration = duration;
+                                            prog.is_playing = !was_paused;
+                                            prog.is_finished = false;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        AudioCommand::Restart => {
+                            // Unlike Play, the track keeps its loudness gain, A-B loop and prebuffered next
+                            // track; the old position fades out under the start, like a manual skip
+                            if let Some(track_info) = current_track_clone.lock().clone() {
+                                let was_paused = current_sink.as_ref().is_some_and(|s| s.is_paused());
+                                // A gapless next track queued on the old sink is prebuffered again
+                                if cancel_gapless(&mut gapless_next) {
+                                    prebuffer_started = false;
+                                }
+                                if let Some((sink, _, _, _)) = outgoing_sink.take() {
+                                    sink.stop();
+                                }
+                                if let Some(old_sink) = current_sink.take() {
+                                    if !was_paused && !old_sink.empty() {
+                                        let start_vol = old_sink.volume();
+                                        outgoing_sink = Some((old_sink, clock.now(), RESTART_FADE_SECS, start_vol));
+                                    } else {
+                                        old_sink.stop();
+                                    }
+                                }
+                                sample_buffer_clone.clear();
+                                
+                                match File::open(&track_info.file_path) {
+                                    Ok(file) => {
+                                        let reader = BufReader::new(file);
+                                        match engine_stats_clone.time_decode(|| Decoder::new(reader)) {
+                                            Ok(source) => {
+                                                let duration = source.total_duration()
+                                                    .map(|d| d.as_secs_f64())
+                                                    .unwrap_or(0.0);
+                                                let source_f32 = source.convert_samples::<f32>().amplify(current_track_gain * playlist_gain);
+                                                let analyzing_source = AnalyzingSource::new(
+                                                    MeteringSource::new(source_f32, music_meter.clone()),
+                                                    sample_buffer_clone.clone()
+                                                );
+                                                
+                                                match mix_tap::new_tapped_sink(output.as_ref(), &mix_tap_clone, mix_tap::Bus::Music) {
+                                                    Ok(sink) => {
+                                                        // A paused track stays paused at the start, at full volume for
+                                                        // when it resumes
+                                                        if was_paused {
+                                                            fade_in_progress = None;
+                                                            sink.set_volume(if is_muted || is_master_muted {
+                                                                0.0
+                                                            } else {
+                                                                music_volume * master_volume * (1.0 - duck_progress.max(story_duck_progress) * duck_amount)
+                                                            });
+                                                            sink.pause();
+                                                        } else {
+                                                            fade_in_progress = Some((clock.now(), RESTART_FADE_SECS));
+                                                            sink.set_volume(0.0);
+                                                        }
+                                                        sink.append(analyzing_source);
+                                                        
+                                                        let now = clock.now();
+                                                        track_start = Some(now);
+                                                        pause_start = was_paused.then_some(now);
+                                                        track_duration = duration;
+                                                        current_sink = Some(sink);
+                                                        fade_out_active = false;
+                                                        
+                                                        let mut prog = progress_clone.lock();
+                                                        prog.current_time = 0.0;
+                                                        prog.duration = duration;
+                                                        prog.is_playing = !was_paused;
+                                                        prog.is_finished = false;
+                                                    }
+                                                    Err(e) => errors_clone.report(errors::ErrorCode::Sink, format!("Failed to create sink: {}", e), None),
+                                                }
+                                            }
+                                            Err(e) => errors_clone.report(errors::ErrorCode::Decode, format!("Failed to decode audio: {}", e), Some(track_info.file_path.clone())),
+                                        }
+                                    }
+                                    Err(e) => errors_clone.report(errors::ErrorCode::FileOpen, format!("Failed to open file: {}", e), Some(track_info.file_path.clone())),
+                                }
+                            }
+                        }
+                        AudioCommand::SetLoopSection(section) => {
+                            // Clamp the end to the track length so the loop can always be reached
+                            loop_section = section.map(|(start, end)| {
+                                let end = if track_duration > 0.0 { end.min(track_duration) } else { end };
+                                (start.max(0.0), end)
+                            }).filter(|(start, end)| end > start);
+                            loop_seek_pending = false;
+                            progress_clone.lock().loop_section = loop_section;
+                            // The loop keeps the track playing, so a queued next track mustn't follow it
+                            if loop_section.is_some() && cancel_gapless(&mut gapless_next) {
+                                prebuffer_started = false;
+                            }
+                        }
+                        AudioCommand::SetVolume(vol) => {
+                            music_volume = vol;
+                            if let Some(ref sink) = current_sink {
+                                let effective_vol = if is_muted || is_master_muted {
+                                    0.0
+                                } else {
+                                    music_volume * master_volume
+                                };
+                                sink.set_volume(effective_vol);
+                            }
+                        }
+                        AudioCommand::SetMasterVolume(vol) => {
+                            master_volume = vol;
+                            // Update music volume
+                            if let Some(ref sink) = current_sink {
+                                let effective_vol = if is_muted || is_master_muted {
+                                    0.0
+                                } else {
+                                    music_volume * master_volume
+                                };
+                                sink.set_volume(effective_vol);
+                            }
+                            // Update ambient volumes
+                            for state in ambient_states.values() {
+                                let effective_vol = calc_ambient_volume(
+                                    &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                );
+                                state.sink.set_volume(effective_vol);
+                            }
+                            // Update soundboard volume
+                            if let Some(ref sink) = soundboard_sink {
+                                let effective_vol = if soundboard_muted || is_master_muted { 0.0 } else { soundboard_volume * master_volume };
+                                sink.set_volume(effective_vol);
+                            }
+                        }
+                        AudioCommand::SetMuted(muted) => {
+                            is_muted = muted;
+                            if let Some(ref sink) = current_sink {
+                                let effective_vol = if is_muted || is_master_muted {
+                                    0.0
+                                } else {
+                                    music_volume * master_volume
+                                };
+                                sink.set_volume(effective_vol);
+                            }
+                        }
+                        AudioCommand::SetMasterMuted(muted) => {
+                            is_master_muted = muted;
+                            // Update music volume
+                            if let Some(ref sink) = current_sink {
+                                let effective_vol = if is_muted || is_master_muted {
+                                    0.0
+                                } else {
+                                    music_volume * master_volume
+                                };
+                                sink.set_volume(effective_vol);
+                            }
+                            // Update ambient volumes
+                            for state in ambient_states.values() {
+                                let effective_vol = calc_ambient_volume(
+                                    &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                );
+                                state.sink.set_volume(effective_vol);
+                            }
+                            // Update soundboard volume
+                            if let Some(ref sink) = soundboard_sink {
+                                let effective_vol = if soundboard_muted || is_master_muted { 0.0 } else { soundboard_volume * master_volume };
+                                sink.set_volume(effective_vol);
+                            }
+                        }
+                        AudioCommand::SetCrossfadeDuration(duration) => {
+                            base_crossfade_duration = duration;
+                            crossfade_duration = playlist_crossfade_override.unwrap_or(base_crossfade_duration);
+                        }
+                        AudioCommand::SetFrequencyScale(scale) => {
+                            frequency_ranges = frequency_bucket_ranges(scale);
+                        }
+                        AudioCommand::SetFftSmoothing { attack, decay } => {
+                            // Zero would freeze the bins, so keep a small minimum
+                            fft_attack = attack.clamp(0.01, 1.0);
+                            fft_decay = decay.clamp(0.01, 1.0);
+                        }
+                        AudioCommand::SetFftTriggers(triggers) => {
+                            fft_triggers = triggers.into_iter()
+                                .map(|trigger| FftTriggerState { trigger, above_since: None, fired: false })
+                                .collect();
+                        }
+                        AudioCommand::SetTickInterval(ms) => {
+                            tick_interval = std::time::Duration::from_millis(ms.clamp(MIN_AUDIO_TICK_MS, MAX_AUDIO_TICK_MS) as u64);
+                            next_tick = next_tick.min(Instant::now() + tick_interval);
+                        }
+                        AudioCommand::SetRealtimePriority(enabled, reply) => {
+                            let result = match (enabled, realtime_priority.take()) {
+                                (true, None) => {
+                                    // Tell the scheduler the thread needs to run once per tick
+                                    let frames = (REALTIME_SAMPLE_RATE as u128 * tick_interval.as_millis() / 1000) as u32;
+                                    audio_thread_priority::promote_current_thread_to_real_time(frames, REALTIME_SAMPLE_RATE)
+                                        .map(|handle| realtime_priority = Some(handle))
+                                        .map_err(|e| e.to_string())
+                                }
+                                (false, Some(handle)) => {
+                                    audio_thread_priority::demote_current_thread_from_real_time(handle).map_err(|e| e.to_string())
+                                }
+                                (true, Some(handle)) => {
+                                    realtime_priority = Some(handle);
+                                    Ok(())
+                                }
+                                (false, None) => Ok(()),
+                            };
+                            match &result {
+                                Ok(()) => log::info!("Audio thread real-time priority {}", if realtime_priority.is_some() { "on" } else { "off" }),
+                                Err(e) => log::warn!("Audio thread real-time priority not changed: {}", e),
+                            }
+                            let _ = reply.send(result);
+                        }
+                        AudioCommand::SetOutputDevice(device, reply) => {
+                            // Settings are applied at every load; reopening the same device would only glitch
+                            let result = if device == output_device { Ok(()) } else { output.set_device(device.as_deref()) };
+                            match &result {
+                                Ok(()) if device != output_device => {
+                                    log::info!("Output device: {}", device.as_deref().unwrap_or("system default"));
+                                    output_device = device;
+                                }
+                                Ok(()) => {}
+                                Err(e) => log::warn!("Output device not changed: {}", e),
+                            }
+                            let _ = reply.send(result);
+                        }
+                        AudioCommand::SetSoundboardOutputDevice(device, reply) => {
+                            let result = if device == soundboard_device {
+                                Ok(())
+                            } else {
+                                match &device {
+                                    None => Ok(None),
+                                    Some(name) => output.open_device(Some(name)).map(Some),
+                                }
+                                .map(|new_output| {
+                                    // A sound playing on the old stream can't move; it stops with it
+                                    if let Some((sink, ..)) = soundboard_outgoing.take() {
+                                        sink.stop();
+                                    }
+                                    if let Some(sink) = soundboard_sink.take() {
+                                        sink.stop();
+                                        duck_target = 0.0;
+                                        *soundboard_playing_clone.lock() = false;
+                                    }
+                                    soundboard_output = new_output;
+                                    log::info!("Soundboard output device: {}", device.as_deref().unwrap_or("main output"));
+                                    soundboard_device = device;
+                                })
+                            };
+                            if let Err(e) = &result {
+                                log::warn!("Soundboard output device not changed: {}", e);
+                            }
+                            let _ = reply.send(result);
+                        }
+                        AudioCommand::SetFftEventRate(rate) => {
+                            fft_event_interval = if rate == 0 {
+                                None
+                            } else {
+                                Some(std::time::Duration::from_secs_f32(1.0 / rate.min(MAX_FFT_EVENT_RATE) as f32))
+                            };
+                        }
+                        AudioCommand::SetPlaylistOverrides { crossfade_duration: override_duration, volume_offset_db } => {
+                            playlist_crossfade_override = override_duration.map(|d| d.max(0.0));
+                            crossfade_duration = playlist_crossfade_override.unwrap_or(base_crossfade_duration);
+                            // Takes effect from the next track started in this playlist
+                            playlist_gain = 10f32.powf(volume_offset_db / 20.0);
+                        }
+                        AudioCommand::SetSkipCrossfadeDuration(duration) => {
+                            skip_crossfade_duration = duration.max(0.0);
+                        }
+                        AudioCommand::SetPresetCrossfadeDuration(duration) => {
+                            // Fades divide by it, so it never reaches zero
+                            preset_crossfade_duration = duration.max(FADE_SECS);
+                        }
+                        AudioCommand::SetLoudnessMatching(enabled) => {
+                            loudness_matching = enabled;
+                        }
+                        AudioCommand::SetSkipOnSilence(enabled) => {
+                            skip_on_silence = enabled;
+                        }
+                        // Soundboard commands
+                        AudioCommand::PlaySoundboard { file_path, volume, trim } => {
+                            // Stop any current soundboard sound
+                            if let Some(old_sink) = soundboard_sink.take() {
+                                old_sink.stop();
+                            }
+                            
+                            // Start ducking (gradual fade handled by main loop)
+                            duck_target = 1.0;
+                            
+                            // Load and play soundboard sound
+                            match File::open(&file_path) {
+                                Ok(file) => {
+                                    let reader = BufReader::new(file);
+                                    match engine_stats_clone.time_decode(|| Decoder::new(reader)) {
+                                        Ok(mut source) => {
+                                            // A trimmed sound seeks to its start; files that can't seek are
+                                            // decoded up to it instead
+                                            let mut skip = std::time::Duration::ZERO;
+                                            if !trim.start().is_zero() {
+                                                if let Err(e) = source.try_seek(trim.start()) {
+                                                    log::debug!("Can't seek in {}, skipping to the trim start: {}", file_path, e);
+                                                    skip = trim.start();
+                                                }
+                                            }
+                                            let source = source
+                                                .skip_duration(skip)
+                                                .take_duration(trim.length().unwrap_or(std::time::Duration::MAX));
+                                            let bus_output = soundboard_output.as_deref().unwrap_or(output.as_ref());
+                                            match mix_tap::new_tapped_sink(bus_output, &mix_tap_clone, mix_tap::Bus::Soundboard) {
+                                                Ok(sink) => {
+                                                    // Use stored soundboard volume/mute state
+                                                    let effective_vol = if soundboard_muted || is_master_muted {
+                                                        0.0
+                                                    } else {
+                                                        soundboard_volume * master_volume
+                                                    };
+                                                    sink.set_volume(effective_vol);
+                                                    // The sound's own volume, under the bus volume
+                                                    let source = MeteringSource::new(source.convert_samples::<f32>().amplify(volume), soundboard_meter.clone());
+                                                    sink.append(AmbientAnalyzingSource::new(source, soundboard_sample_buffer_clone.clone()));
+                                                    soundboard_sink = Some(sink);
+                                                    *soundboard_playing_clone.lock() = true;
+                                                }
+                                                Err(e) => errors_clone.report(errors::ErrorCode::Sink, format!("Failed to create soundboard sink: {}", e), None),
+                                            }
+                                        }
+                                        Err(e) => errors_clone.report(errors::ErrorCode::Decode, format!("Failed to decode soundboard file: {}", e), Some(file_path.clone())),
+                                    }
+                                }
+                                Err(e) => errors_clone.report(errors::ErrorCode::FileOpen, format!("Failed to open soundboard file: {}", e), Some(file_path.clone())),
+                            }
+                        }
+                        AudioCommand::StopSoundboard { fade_secs } => {
+                            if let Some((sink, _, _, _)) = soundboard_outgoing.take() {
+                                sink.stop();
+                            }
+                            if let Some(sink) = soundboard_sink.take() {
+                                match fade_secs {
+                                    Some(secs) if secs > 0.0 && !sink.empty() => {
+                                        let start_vol = sink.volume();
+                                        soundboard_outgoing = Some((sink, clock.now(), secs, start_vol));
+                                    }
+                                    _ => sink.stop(),
+                                }
+                            }
+                            duck_target = 0.0; // Start fading out ducking (gradual restore handled by main loop)
+                            *soundboard_playing_clone.lock() = false;
+                        }
+                        AudioCommand::SetDuckAmount(amount) => {
+                            duck_amount = amount;
+                        }
+                        AudioCommand::StartMicrophone(source) => {
+                            // Its own bus, so recordings and the stream get the voice too
+                            match mix_tap::new_tapped_sink(output.as_ref(), &mix_tap_clone, mix_tap::Bus::Microphone) {
+                                Ok(sink) => {
+                                    sink.append(source);
+                                    if let Some(old_sink) = microphone_sink.replace(sink) {
+                                        old_sink.stop();
+                                    }
+                                }
+                                Err(e) => errors_clone.report(errors::ErrorCode::Sink, format!("Failed to create microphone sink: {}", e), None),
+                            }
+                        }
+                        AudioCommand::SetSoundboardVolume(volume) => {
+                            soundboard_volume = volume;
+                            // Apply to currently playing soundboard
+                            if let Some(ref sink) = soundboard_sink {
+                                let effective_vol = if soundboard_muted || is_master_muted { 0.0 } else { soundboard_volume * master_volume };
+                                sink.set_volume(effective_vol);
+                            }
+                        }
+                        AudioCommand::SetSoundboardMuted(muted) => {
+                            soundboard_muted = muted;
+                            // Apply to currently playing soundboard
+                            if let Some(ref sink) = soundboard_sink {
+                                let effective_vol = if soundboard_muted || is_master_muted { 0.0 } else { soundboard_volume * master_volume };
+                                sink.set_volume(effective_vol);
+                            }
+                        }
+                        // Ambient sound commands with A/B crossfade
+                        AudioCommand::PlayAmbient { id, file_a, file_b, settings } => {
+                            // Stop existing ambient sound with this ID if any
+                            if let Some(old_state) = ambient_states.remove(&id) {
+                                old_state.sink.stop();
+                            }
+                            if let Some((old_sink, ..)) = swap_fades.remove(&id) {
+                                old_sink.stop();
+                            }
+                            
+                            let info = ActiveAmbientInfo { id, file_a, file_b, settings, phase: None };
+                            match open_ambient(output.as_ref(), &mix_tap_clone, &audio_cache, &engine_stats_clone, &ambient_sample_buffer_clone, &info) {
+                                Ok(state) => {
+                                    if ambient_paused_at.is_none() {
+                                        state.sink.play();
+                                    }
+                                    fading_in.insert(info.id.clone(), clock.now());
+                                    ambient_states.insert(info.id.clone(), state);
+                                    // Track in shared state for querying
+                                    active_ambients_clone.lock().insert(info.id.clone(), info);
+                                }
+                                Err((code, message, context)) => errors_clone.report(code, message, context),
+                            }
+                        }
+                        AudioCommand::ApplySounds(sounds) => {
+                            // Sounds that aren't part of the new set fade out over the preset crossfade
+                            for id in ambient_states.keys() {
+                                if !sounds.iter().any(|s| &s.id == id) && !fading_out.contains_key(id) && !scheduler_fading_out.contains_key(id) {
+                                    scheduler_fading_out.insert(id.clone(), (clock.now(), preset_crossfade_duration));
+                                }
+                            }
+                            
+                            // Open and decode every sound before starting any, so they all start together
+                            let mut opened = Vec::new();
+                            for info in sounds {
+                                // A sound that is already playing the same files at the same pitch keeps
+                                // playing and moves to its new volume and effects, as the scheduler does
+                                let keep = !fading_out.contains_key(&info.id)
+                                    && !scheduler_fading_out.contains_key(&info.id)
+                                    && !swap_fades.contains_key(&info.id);
+                                if let Some(state) = ambient_states.get_mut(&info.id).filter(|state| {
+                                    keep && state.file_a == info.file_a && state.file_b == info.file_b
+                                        && (state.settings.pitch - info.settings.pitch).abs() <= 0.001
+                                }) {
+                                    state.effects.set(info.settings.pan, info.settings.low_pass_freq, info.settings.algorithmic_reverb);
+                                    state.settings = info.settings.clone();
+                                    let target_vol = calc_ambient_volume(
+                                        &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                        is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                    );
+                                    let current_vol = volume_transitions.remove(&info.id).map(|(c, _)| c)
+                                        .or_else(|| scheduler_volume_transitions.get(&info.id).map(|(c, ..)| *c))
+                                        .unwrap_or_else(|| state.sink.volume());
+                                    scheduler_volume_transitions.insert(info.id.clone(), (current_vol, target_vol, 1.0 / preset_crossfade_duration));
+                                    active_ambients_clone.lock().insert(info.id.clone(), info);
+                                    continue;
+                                }
+                                match open_ambient(output.as_ref(), &mix_tap_clone, &audio_cache, &engine_stats_clone, &ambient_sample_buffer_clone, &info) {
+                                    Ok(state) => opened.push((info, state)),
+                                    Err((code, message, context)) => errors_clone.report(code, message, context),
+                                }
+                            }
+                            for (info, state) in opened {
+                                // A sound that was already playing starts over, so drop its old sink and fades
+                                if let Some(old_state) = ambient_states.remove(&info.id) {
+                                    old_state.sink.stop();
+                                }
+                                if let Some((old_sink, ..)) = swap_fades.remove(&info.id) {
+                                    old_sink.stop();
+                                }
+                                fading_out.remove(&info.id);
+                                scheduler_fading_out.remove(&info.id);
+                                scheduler_fading_in.remove(&info.id);
+                                volume_transitions.remove(&info.id);
+                                scheduler_volume_transitions.remove(&info.id);
+                                
+                                if ambient_paused_at.is_none() {
+                                    state.sink.play();
+                                }
+                                fading_in.remove(&info.id);
+                                scheduler_fading_in.insert(info.id.clone(), (clock.now(), preset_crossfade_duration));
+                                ambient_states.insert(info.id.clone(), state);
+                                active_ambients_clone.lock().insert(info.id.clone(), info);
+                            }
+                        }
+                        AudioCommand::StopAmbient { id, fade_secs } => {
+                            // Start fade-out instead of immediate stop; a quicker stop during a fade-out
+                            // carries on from where that fade has got to
+                            let fade_secs = fade_secs.unwrap_or(FADE_SECS);
+                            if ambient_states.contains_key(&id) {
+                                match fading_out.get_mut(&id) {
+                                    None => {
+                                        fading_out.insert(id, (clock.now(), fade_secs));
+                                    }
+                                    Some((started, secs)) if fade_secs < *secs => {
+                                        let progress = fade_progress(&*clock, started, *secs).min(1.0);
+                                        let now = clock.now();
+                                        *started = now.checked_sub(std::time::Duration::from_secs_f32(progress * fade_secs)).unwrap_or(now);
+                                        *secs = fade_secs;
+                                    }
+                                    Some(_) => {}
+                                }
+                            }
+                        }
+                        AudioCommand::StopAllAmbient { fade_secs } => {
+                            // Stop all ambient sounds with fade-out; a cut still takes one tick
+                            let secs = fade_secs.unwrap_or(SCHEDULER_FADE_SECS).max(0.001);
+                            let ids: Vec<String> = ambient_states.keys().cloned().collect();
+                            for id in ids {
+                                if !fading_out.contains_key(&id) && !scheduler_fading_out.contains_key(&id) {
+                                    // Use scheduler fade for smoother transition
+                                    scheduler_fading_out.insert(id, (clock.now(), secs));
+                                }
+                            }
+                        }
+                        AudioCommand::PauseAllAmbient => {
+                            if ambient_paused_at.is_none() {
+                                ambient_paused_at = Some(clock.now());
+                                for state in ambient_states.values() {
+                                    state.sink.pause();
+                                }
+                                for (old_sink, ..) in swap_fades.values() {
+                                    old_sink.pause();
+                                }
+                            }
+                        }
+                        AudioCommand::ResumeAllAmbient => {
+                            if let Some(paused_at) = ambient_paused_at.take() {
+                                // Fades are timed from when they started, so move those clocks past the pause
+                                let now = clock.now();
+                                let paused = now - paused_at;
+                                let shift = |started: &mut Instant| *started = (*started + paused).min(now);
+                                fading_in.values_mut().for_each(shift);
+                                fading_out.values_mut().chain(scheduler_fading_in.values_mut()).chain(scheduler_fading_out.values_mut())
+                                    .for_each(|(started, _)| shift(started));
+                                for (old_sink, started, _) in swap_fades.values_mut() {
+                                    started.iter_mut().for_each(shift);
+                                    old_sink.play();
+                                }
+                                for state in ambient_states.values() {
+                                    state.sink.play();
+                                }
+                            }
+                        }
+                        AudioCommand::PreviewAmbient { file_path, seconds } => {
+                            if let Some(old_sink) = preview_sink.take() {
+                                old_sink.stop();
+                            }
+                            let decoded = File::open(&file_path)
+                                .map_err(|e| (errors::ErrorCode::FileOpen, format!("Failed to open preview file: {}", e)))
+                                .and_then(|file| {
+                                    engine_stats_clone.time_decode(|| Decoder::new(BufReader::new(file)))
+                                        .map_err(|e| (errors::ErrorCode::Decode, format!("Failed to decode preview file: {}", e)))
+                                });
+                            match decoded {
+                                Ok(source) => {
+                                    let (sink, queue) = Sink::new_idle();
+                                    match output.play(Box::new(queue)) {
+                                        Ok(()) => {
+                                            sink.set_volume(if is_master_muted { 0.0 } else { PREVIEW_VOLUME * master_volume });
+                                            // Fade the cut at the end so it doesn't click
+                                            let mut source = source.convert_samples::<f32>()
+                                                .take_duration(std::time::Duration::from_secs_f32(seconds));
+                                            source.set_filter_fadeout();
+                                            sink.append(source.fade_in(std::time::Duration::from_millis(20)));
+                                            preview_sink = Some(sink);
+                                        }
+                                        Err(e) => errors_clone.report(errors::ErrorCode::Sink, format!("Failed to create preview sink: {}", e), None),
+                                    }
+                                }
+                                Err((code, message)) => errors_clone.report(code, message, Some(file_path)),
+                            }
+                        }
+                        AudioCommand::SwapAmbientFiles { id, file_a, file_b, crossfade_secs } => {
+                            if let Some(state) = ambient_states.get_mut(&id) {
+                                state.file_a = file_a.clone();
+                                state.file_b = file_b.clone();
+                                state.category = ambient_category(&state.file_a);
+                                if let Some(info) = active_ambients_clone.lock().get_mut(&id) {
+                                    info.file_a = file_a;
+                                    info.file_b = file_b;
+                                }
+                                // A sound resting between cycles simply starts its next one on the new files;
+                                // otherwise the file it is on moves to a new sink and crossfades over
+                                let resting = state.is_paused && state.sink.empty() && state.decoding.is_none();
+                                if !resting {
+                                    match mix_tap::new_tapped_sink(output.as_ref(), &mix_tap_clone, mix_tap::Bus::Ambient) {
+                                        Ok(new_sink) => {
+                                            new_sink.set_volume(0.0);
+                                            let old_sink = std::mem::replace(&mut state.sink, new_sink);
+                                            // Swapped again mid-crossfade: the oldest sink goes now
+                                            if let Some((previous, ..)) = swap_fades.insert(id.clone(), (old_sink, None, crossfade_secs)) {
+                                                previous.stop();
+                                            }
+                                            let file_path = if state.is_playing_a { &state.file_a } else { &state.file_b };
+                                            let bytes = audio_cache.cached(file_path, &engine_stats_clone);
+                                            state.decoding = Some(decode_pool.decode(&id, file_path, bytes, audio_cache.mmap_threshold()));
+                                        }
+                                        Err(e) => errors_clone.report(errors::ErrorCode::Sink, format!("Failed to create ambient sink: {}", e), None),
+                                    }
+                                }
+                            }
+                        }
+                        AudioCommand::SetAmbientSyncGroup { id, group } => {
+                            // Takes effect when the sound finishes its current cycle
+                            if let Some(state) = ambient_states.get_mut(&id) {
+                                state.settings.sync_group = group.clone();
+                                if let Some(info) = active_ambients_clone.lock().get_mut(&id) {
+                                    info.settings.sync_group = group;
+                                }
+                            }
+                        }
+                        AudioCommand::UpdateAmbientSettings { id, settings } => {
+                            if let Some(state) = ambient_states.get_mut(&id) {
+                                // Pitch is a speed change and needs the file restarted; the playing source
+                                // picks up pan, low-pass and reverb as they are
+                                let pitch_changed = (state.settings.pitch - settings.pitch).abs() > 0.001;
+                                state.effects.set(settings.pan, settings.low_pass_freq, settings.algorithmic_reverb);
+                                state.settings = settings.clone();
+                                
+                                // Update shared state with new settings
+                                {
+                                    let mut active = active_ambients_clone.lock();
+                                    if let Some(info) = active.get_mut(&id) {
+                                        info.settings = settings;
+                                    }
+                                }
+                                
+                                // If pitch changed, restart current file with new settings
+                                if pitch_changed {
+                                    state.sink.stop();
+                                    // Create new sink; the file restarts on it once a decode worker has it
+                                    if let Ok(new_sink) = mix_tap::new_tapped_sink(output.as_ref(), &mix_tap_clone, mix_tap::Bus::Ambient) {
+                                        let file_path = if state.is_playing_a {
+                                            &state.file_a
+                                        } else {
+                                            &state.file_b
+                                        };
+                                        let bytes = audio_cache.cached(file_path, &engine_stats_clone);
+                                        state.decoding = Some(decode_pool.decode(&id, file_path, bytes, audio_cache.mmap_threshold()));
+                                        state.sink = new_sink;
+                                    }
+                                } else {
+                                    // Smooth volume transition - set target and let the loop interpolate
+                                    let target_vol = calc_ambient_volume(
+                                        &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                        is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                    );
+                                    // Get current volume (or use sink's current if not transitioning)
+                                    let current_vol = volume_transitions.get(&id)
+                                        .map(|(c, _)| *c)
+                                        .unwrap_or_else(|| state.sink.volume());
+                                    volume_transitions.insert(id.clone(), (current_vol, target_vol));
+                                }
+                            }
+                        }
+                        AudioCommand::SetAmbientMasterVolume(vol) => {
+                            ambient_master_volume = vol;
+                            for state in ambient_states.values() {
+                                let effective_vol = calc_ambient_volume(
+                                    &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                );
+                                state.sink.set_volume(effective_vol);
+                            }
+                        }
+                        AudioCommand::SetCategoryVolumes(volumes) => {
+                            category_volumes = volumes;
+                            for state in ambient_states.values() {
+                                let effective_vol = calc_ambient_volume(
+                                    &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                );
+                                state.sink.set_volume(effective_vol);
+                            }
+                        }
+                        AudioCommand::SetAmbientMuted(muted) => {
+                            is_ambient_muted = muted;
+                            for state in ambient_states.values() {
+                                let effective_vol = calc_ambient_volume(
+                                    &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                );
+                                state.sink.set_volume(effective_vol);
+                            }
+                        }
+                        AudioCommand::SetMmapThreshold(bytes) => {
+                            audio_cache.set_mmap_threshold(bytes);
+                        }
+                        AudioCommand::PreloadAmbient(paths) => {
+                            // Preload audio files into memory cache to avoid disk I/O during playback
+                            for path in paths {
+                                if !audio_cache.contains(&path) {
+                                    decode_pool.read(path, audio_cache.mmap_threshold());
+                                }
+                            }
+                        }
+                        // Scheduler-specific commands with 2000ms fade times
+                        AudioCommand::PlayAmbientScheduler { id, file_a, file_b, settings } => {
+                            log::debug!("[Scheduler] PlayAmbientScheduler: id={}, file_a={}", id, file_a);
+                            // Stop existing ambient sound with this ID if any (with scheduler fade)
+                            if ambient_states.contains_key(&id) && !scheduler_fading_out.contains_key(&id) {
+                                scheduler_fading_out.insert(id.clone(), (clock.now(), SCHEDULER_FADE_SECS));
+                            }
+                            
+                            let info = ActiveAmbientInfo { id, file_a, file_b, settings, phase: None };
+                            match open_ambient(output.as_ref(), &mix_tap_clone, &audio_cache, &engine_stats_clone, &ambient_sample_buffer_clone, &info) {
+                                Ok(state) => {
+                                    if ambient_paused_at.is_none() {
+                                        state.sink.play();
+                                    }
+                                    // Start scheduler fade-in (2000ms)
+                                    scheduler_fading_in.insert(info.id.clone(), (clock.now(), SCHEDULER_FADE_SECS));
+                                    ambient_states.insert(info.id.clone(), state);
+                                    if let Some((old_sink, ..)) = swap_fades.remove(&info.id) {
+                                        old_sink.stop();
+                                    }
+                                    active_ambients_clone.lock().insert(info.id.clone(), info);
+                                }
+                                Err((code, message, context)) => errors_clone.report(code, message, context),
+                            }
+                        }
+                        AudioCommand::ApplyPreset(preset_id) => {
+                            scheduler_preset_pending = Some(preset_id);
+                        }
+                        AudioCommand::DumpDebugState(reply) => {
+                            use diagnostics::*;
+                            let ambients: Vec<AmbientDebug> = ambient_states.iter().map(|(id, state)| AmbientDebug {
+                                id: id.clone(),
+                                file: if state.is_playing_a { state.file_a.clone() } else { state.file_b.clone() },
+                                sink: SinkDebug::of(&state.sink),
+                                loops_remaining: state.loops_remaining,
+                                is_paused: state.is_paused,
+                                pause_remaining: state.pause_remaining,
+                                fading_in: fading_in.get(id).map(|t| fade_progress(&*clock, t, FADE_SECS).min(1.0)),
+                                fading_out: fading_out.get(id).map(|(t, secs)| fade_progress(&*clock, t, *secs).min(1.0)),
+                                scheduler_fading_in: scheduler_fading_in.get(id).map(|(t, secs)| fade_progress(&*clock, t, *secs).min(1.0)),
+                                scheduler_fading_out: scheduler_fading_out.get(id).map(|(t, secs)| fade_progress(&*clock, t, *secs).min(1.0)),
+                                volume_transition: volume_transitions.get(id).copied()
+                                    .or_else(|| scheduler_volume_transitions.get(id).map(|&(current, target, _)| (current, target))),
+                            }).collect();
+                            let mut orphan_fades: Vec<String> = fading_in.keys()
+                                .chain(fading_out.keys())
+                                .chain(scheduler_fading_in.keys())
+                                .chain(scheduler_fading_out.keys())
+                                .chain(volume_transitions.keys())
+                                .chain(scheduler_volume_transitions.keys())
+                                .filter(|id| !ambient_states.contains_key(*id))
+                                .cloned()
+                                .collect();
+                            orphan_fades.sort();
+                            orphan_fades.dedup();
+                            let (untracked_ambients, unreported_ambients) = {
+                                let active = active_ambients_clone.lock();
+                                (
+                                    active.keys().filter(|id| !ambient_states.contains_key(*id)).cloned().collect(),
+                                    ambient_states.keys().filter(|id| !active.contains_key(*id)).cloned().collect(),
+                                )
+                            };
+                            let _ = reply.send(EngineDebug {
+                                music: MusicDebug {
+                                    sink: current_sink.as_ref().map(SinkDebug::of),
+                                    outgoing: outgoing_sink.as_ref().map(|(sink, start, duration, _)| OutgoingDebug {
+                                        sink: SinkDebug::of(sink),
+                                        elapsed: clock.since(*start).as_secs_f32(),
+                                        duration: *duration,
+                                    }),
+                                    track_duration,
+                                    fade_out_active,
+                                    fade_in: fade_in_progress.map(|(start, duration)| (clock.since(start).as_secs_f32(), duration)),
+                                    crossfade_duration,
+                                    pending_auto_advance: pending_auto_advance.as_ref().map(|(file_path, ..)| file_path.clone()),
+                                    loop_section,
+                                    track_gain: current_track_gain,
+                                    playlist_gain,
+                                    volume: music_volume,
+                                    master_volume,
+                                    muted: is_muted,
+                                    master_muted: is_master_muted,
+                                },
+                                ambients,
+                                orphan_fades,
+                                untracked_ambients,
+                                unreported_ambients,
+                                soundboard: soundboard_sink.as_ref().map(SinkDebug::of),
+                                ducking: DuckingDebug { progress: duck_progress, target: duck_target, amount: duck_amount },
+                                cache: CacheDebug {
+                                    files: audio_cache.file_count(),
+                                    bytes: audio_cache.usage().0,
+                                    mapped_files: audio_cache.usage().1,
+                                    loudness_entries: loudness_cache.lock().len(),
+                                },
+                                scheduler_loop: SchedulerLoopDebug {
+                                    last_loaded_item: last_scheduler_item_index,
+                                    preset_pending: scheduler_preset_pending.clone(),
+                                },
+                            });
+                        }
+                        AudioCommand::StopAmbientScheduler(id) => {
+                            // Start scheduler fade-out (2000ms) instead of immediate stop
+                            if ambient_states.contains_key(&id) && !scheduler_fading_out.contains_key(&id) {
+                                // Remove from regular fading if present
+                                fading_out.remove(&id);
+                                scheduler_fading_out.insert(id, (clock.now(), SCHEDULER_FADE_SECS));
+                            }
+                        }
+                        AudioCommand::UpdateAmbientSettingsScheduler { id, settings } => {
+                            if let Some(state) = ambient_states.get_mut(&id) {
+                                // Pitch is a speed change and needs the file restarted; the playing source
+                                // picks up pan, low-pass and reverb as they are
+                                let pitch_changed = (state.settings.pitch - settings.pitch).abs() > 0.001;
+                                state.effects.set(settings.pan, settings.low_pass_freq, settings.algorithmic_reverb);
+                                state.settings = settings.clone();
+                                
+                                {
+                                    let mut active = active_ambients_clone.lock();
+                                    if let Some(info) = active.get_mut(&id) {
+                                        info.settings = settings;
+                                    }
+                                }
+                                
+                                if pitch_changed {
+                                    state.sink.stop();
+                                    if let Ok(new_sink) = mix_tap::new_tapped_sink(output.as_ref(), &mix_tap_clone, mix_tap::Bus::Ambient) {
+                                        let file_path = if state.is_playing_a {
+                                            &state.file_a
+                                        } else {
+                                            &state.file_b
+                                        };
+                                        let bytes = audio_cache.cached(file_path, &engine_stats_clone);
+                                        state.decoding = Some(decode_pool.decode(&id, file_path, bytes, audio_cache.mmap_threshold()));
+                                        state.sink = new_sink;
+                                    }
+                                } else {
+                                    // Smooth volume transition with scheduler timing (2000ms)
+                                    let target_vol = calc_ambient_volume(
+                                        &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                        is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                    );
+                                    let current_vol = scheduler_volume_transitions.get(&id)
+                                        .map(|(c, ..)| *c)
+                                        .unwrap_or_else(|| state.sink.volume());
+                                    scheduler_volume_transitions.insert(id.clone(), (current_vol, target_vol, SCHEDULER_VOLUME_TRANSITION_SPEED));
+                                }
+                            }
+                        }
+                    },
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) if ambient_paused_at.is_some() => {
+                        // Ambient engine paused: no fades, refills or pause timers until it resumes
+                        let now = Instant::now();
+                        next_tick = now + tick_interval;
+                        last_step = clock.now();
+                        // Sounds stopped while paused are silent already, so they go without a fade
+                        let stopped: Vec<String> = fading_out.keys().chain(scheduler_fading_out.keys()).cloned().collect();
+                        for id in stopped {
+                            fading_out.remove(&id);
+                            scheduler_fading_out.remove(&id);
+                            if let Some(state) = ambient_states.remove(&id) {
+                                state.sink.stop();
+                            }
+                            if let Some((old_sink, ..)) = swap_fades.remove(&id) {
+                                old_sink.stop();
+                            }
+                            active_ambients_clone.lock().remove(&id);
+                        }
+                    }
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                        // Schedule from the deadline rather than from now, so slow ticks don't drift;
+                        // if the thread fell behind, start over instead of firing a burst of ticks
+                        let now = Instant::now();
+                        next_tick += tick_interval;
+                        if next_tick <= now {
+                            next_tick = now + tick_interval;
+                        }
+                        // Seconds since the last tick, for volume transitions and ambient pauses
+                        let elapsed = clock.since(last_step).as_secs_f32();
+                        last_step = clock.now();
+                        
+                        // Process fade-outs for sounds being stopped
+                        let mut completed_fades: Vec<String> = Vec::new();
+                        for (id, (started, secs)) in fading_out.iter() {
+                            let progress = if *secs > 0.0 { fade_progress(&*clock, started, *secs) } else { 1.0 };
+                            if let Some(state) = ambient_states.get(id) {
+                                // Calculate faded volume (linear fade to 0)
+                                let fade_multiplier = (1.0 - progress).max(0.0);
+                                let base_vol = calc_ambient_volume(
+                                    &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                );
+                                state.sink.set_volume(base_vol * fade_multiplier);
+                            }
+                            if progress >= 1.0 {
+                                completed_fades.push(id.clone());
+                            }
+                        }
+                        // Remove completed fades and stop their sinks
+                        for id in completed_fades {
+                            fading_out.remove(&id);
+                            if let Some(state) = ambient_states.remove(&id) {
+                                state.sink.stop();
+                            }
+                            // Remove from shared state
+                            {
+                                let mut active = active_ambients_clone.lock();
+                                active.remove(&id);
+                            }
+                        }
+                        
+                        // Process fade-ins for newly started sounds
+                        let mut completed_fade_ins: Vec<String> = Vec::new();
+                        for (id, started) in fading_in.iter() {
+                            let progress = fade_progress(&*clock, started, FADE_SECS);
+                            if let Some(state) = ambient_states.get(id) {
+                                // Calculate faded volume (linear fade from 0 to target)
+                                let fade_multiplier = progress.min(1.0);
+                                let target_vol = calc_ambient_volume(
+                                    &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                );
+                                state.sink.set_volume(target_vol * fade_multiplier);
+                            }
+                            if progress >= 1.0 {
+                                completed_fade_ins.push(id.clone());
+                            }
+                        }
+                        // Remove completed fade-ins
+                        for id in completed_fade_ins {
+                            fading_in.remove(&id);
+                        }
+                        
+                        // Process smooth volume transitions for settings changes
+                        let mut completed_transitions: Vec<String> = Vec::new();
+                        for (id, (current_vol, target_vol)) in volume_transitions.iter_mut() {
+                            // Skip if sound is fading in (fade-in takes precedence)
+                            if fading_in.contains_key(id) {
+                                continue;
+                            }
+                            
+                            if let Some(state) = ambient_states.get(id) {
+                                // Interpolate toward target
+                                let diff = *target_vol - *current_vol;
+                                if diff.abs() < 0.01 {
+                                    // Close enough, snap to target
+                                    *current_vol = *target_vol;
+                                    state.sink.set_volume(*target_vol);
+                                    completed_transitions.push(id.clone());
+                                } else {
+                                    // Move toward target
+                                    *current_vol += diff.signum() * (VOLUME_TRANSITION_SPEED * elapsed).min(diff.abs());
+                                    state.sink.set_volume(*current_vol);
+                                }
+                            } else {
+                                completed_transitions.push(id.clone());
+                            }
+                        }
+                        // Remove completed transitions
+                        for id in completed_transitions {
+                            volume_transitions.remove(&id);
+                        }
+                        
+                        // Process SCHEDULER fade-outs (2000ms)
+                        let mut completed_scheduler_fades: Vec<String> = Vec::new();
+                        for (id, (started, secs)) in scheduler_fading_out.iter() {
+                            let progress = fade_progress(&*clock, started, *secs);
+                            if let Some(state) = ambient_states.get(id) {
+                                let fade_multiplier = (1.0 - progress).max(0.0);
+                                let base_vol = calc_ambient_volume(
+                                    &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                );
+                                state.sink.set_volume(base_vol * fade_multiplier);
+                            }
+                            if progress >= 1.0 {
+                                completed_scheduler_fades.push(id.clone());
+                            }
+                        }
+                        for id in completed_scheduler_fades {
+                            scheduler_fading_out.remove(&id);
+                            if let Some(state) = ambient_states.remove(&id) {
+                                state.sink.stop();
+                            }
+                            {
+                                let mut active = active_ambients_clone.lock();
+                                active.remove(&id);
+                            }
+                        }
+                        
+                        // Process SCHEDULER fade-ins (2000ms)
+                        let mut completed_scheduler_fade_ins: Vec<String> = Vec::new();
+                        for (id, (started, secs)) in scheduler_fading_in.iter() {
+                            let progress = fade_progress(&*clock, started, *secs);
+                            if let Some(state) = ambient_states.get(id) {
+                                let fade_multiplier = progress.min(1.0);
+                                let target_vol = calc_ambient_volume(
+                                    &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                );
+                                let final_vol = target_vol * fade_multiplier;
+                                state.sink.set_volume(final_vol);
+                                // Log first fade-in step only
+                                if progress < 0.1 {
+                                    log::trace!("[Scheduler] Fade-in {}: progress={:.2}, target_vol={:.3}, final_vol={:.3}, ambient_master={:.2}, master={:.2}", 
+                                        id, progress, target_vol, final_vol, ambient_master_volume, master_volume);
+                                }
+                            } else {
+                                log::warn!("[Scheduler] Fade-in {} not found in ambient_states!", id);
+                            }
+                            if progress >= 1.0 {
+                                completed_scheduler_fade_ins.push(id.clone());
+                            }
+                        }
+                        for id in completed_scheduler_fade_ins {
+                            scheduler_fading_in.remove(&id);
+                        }
+                        
+                        // Process SCHEDULER volume transitions (2000ms)
+                        let mut completed_scheduler_transitions: Vec<String> = Vec::new();
+                        for (id, (current_vol, target_vol, speed)) in scheduler_volume_transitions.iter_mut() {
+                            if scheduler_fading_in.contains_key(id) {
+                                continue;
+                            }
+                            
+                            if let Some(state) = ambient_states.get(id) {
+                                let diff = *target_vol - *current_vol;
+                                if diff.abs() < 0.01 {
+                                    *current_vol = *target_vol;
+                                    state.sink.set_volume(*target_vol);
+                                    completed_scheduler_transitions.push(id.clone());
+                                } else {
+                                    *current_vol += diff.signum() * (*speed * elapsed).min(diff.abs());
+                                    state.sink.set_volume(*current_vol);
+                                }
+                            } else {
+                                completed_scheduler_transitions.push(id.clone());
+                            }
+                        }
+                        for id in completed_scheduler_transitions {
+                            scheduler_volume_transitions.remove(&id);
+                        }
+                        
+                        // Queue the files decode workers have finished
+                        for done in decode_pool.finished() {
+                            match done {
+                                decode_pool::Done::Read { path, bytes } => audio_cache.insert(path, bytes),
+                                decode_pool::Done::Decoded { ticket, id, source } => {
+                                    let Some(state) = ambient_states.get_mut(&id) else { continue };
+                                    if state.decoding != Some(ticket) {
+                                        continue;
+                                    }
+                                    state.decoding = None;
+                                    // A file that failed leaves the sink empty, so the sound moves on to its next one
+                                    if let Some(source) = source {
+                                        let sample_rate = source.sample_rate();
+                                        let source = source.speed(state.settings.pitch).convert_samples::<f32>();
+                                        let source = state.effects.apply(source, sample_rate);
+                                        let effective_vol = calc_ambient_volume(
+                                            &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                            is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                                        );
+                                        // A swapped-in file starts silent; its crossfade takes it up from there
+                                        let swapping = swap_fades.get(&id).is_some_and(|(_, started, _)| started.is_none());
+                                        state.sink.set_volume(if swapping { 0.0 } else { effective_vol });
+                                        let source = AmbientAnalyzingSource::new(MeteringSource::new(source, state.meter.clone()), ambient_sample_buffer_clone.clone());
+                                        state.sink.append(source);
+                                    }
+                                }
+                            }
+                        }
+                        
+                        // Crossfade sounds that moved to new files; start and stop fades keep the new
+                        // sink's volume while they run
+                        swap_fades.retain(|id, (old_sink, started, secs)| {
+                            let Some(state) = ambient_states.get(id) else {
+                                old_sink.stop();
+                                return false;
+                            };
+                            if started.is_none() {
+                                if state.decoding.is_some() {
+                                    return true;
+                                }
+                                *started = Some(clock.now());
+                            }
+                            let progress = match started {
+                                Some(started) if *secs > 0.0 => fade_progress(&*clock, started, *secs).min(1.0),
+                                _ => 1.0,
+                            };
+                            let base_vol = calc_ambient_volume(
+                                &state.settings, ambient_master_volume, category_gain(&category_volumes, &state.category), master_volume,
+                                is_ambient_muted, is_master_muted, duck_progress, duck_amount
+                            );
+                            old_sink.set_volume(base_vol * (1.0 - progress));
+                            let fading = fading_in.contains_key(id) || fading_out.contains_key(id)
+                                || scheduler_fading_in.contains_key(id) || scheduler_fading_out.contains_key(id);
+                            if !fading {
+                                state.sink.set_volume(base_vol * progress);
+                            }
+                            if progress >= 1.0 {
+                                old_sink.stop();
+                                return false;
+                            }
+                            true
+                        });
+                        
+                        // A/B crossfade state machine - check each ambient sound
+                        let mut rng = rand::thread_rng();
+                        let mut decode = |id: &str, path: &str| {
+                            let bytes = audio_cache.cached(path, &engine_stats_clone);
+                            Some(decode_pool.decode(id, path, bytes, audio_cache.mmap_threshold()))
+                        };
+                        for (id, state) in ambient_states.iter_mut() {
+                            // Next file still decoding
+                            if state.decoding.is_some() {
+                                continue;
+                            }
+                            // Check if current file finished playing
+                            if state.sink.empty() {
+                                if state.is_paused && state.settings.sync_group.is_some() {
+                                    // Waiting for the rest of its sync group, below
+                                } else if state.is_paused {
+                                    // In pause state, decrement pause time
+                                    state.pause_remaining -= elapsed as f64;
+                                    if state.pause_remaining <= 0.0 && rng.gen::<f32>() >= state.settings.event_probability {
+                                        // The cycle doesn't come up this time; rest another loop
+                                        state.pause_remaining = 5.0;
+                                    } else if state.pause_remaining <= 0.0 {
+                                        state.is_paused = false;
+                                        // Start new cycle
+                                        state.loops_remaining = rng.gen_range(
+                                            state.settings.repeat_min..=state.settings.repeat_max
+                                        );
+                                        state.is_playing_a = true;
+                                        state.decoding = decode(id, &state.file_a);
+                                    }
+                                } else if state.is_playing_a {
+                                    // A finished, play B
+                                    state.is_playing_a = false;
+                                    state.decoding = decode(id, &state.file_b);
+                                } else {
+                                    // B finished, one A/B loop complete
+                                    state.loops_remaining = state.loops_remaining.saturating_sub(1);
+                                    
+                                    if state.loops_remaining == 0 && state.settings.sync_group.is_some() {
+                                        // Cycle done; the group's clock decides when it plays again
+                                        state.is_paused = true;
+                                        state.pause_remaining = 0.0;
+                                    } else if state.loops_remaining == 0 {
+                                        // Check if we need to pause
+                                        let pause_loops = rng.gen_range(
+                                            state.settings.pause_min..=state.settings.pause_max
+                                        );
+                                        if pause_loops > 0 {
+                                            // Calculate pause duration (estimate based on file lengths)
+                                            state.is_paused = true;
+                                            state.pause_remaining = pause_loops as f64 * 5.0; // ~5s per loop estimate
+                                        } else if rng.gen::<f32>() >= state.settings.event_probability {
+                                            // Rest a loop and roll again
+                                            state.is_paused = true;
+                                            state.pause_remaining = 5.0;
+                                        } else {
+                                            // No pause, start new cycle
+                                            state.loops_remaining = rng.gen_range(
+                                                state.settings.repeat_min..=state.settings.repeat_max
+                                            );
+                                            state.is_playing_a = true;
+                                            state.decoding = decode(id, &state.file_a);
+                                        }
+                                    } else {
+                                        // More loops to go, play A again
+                                        state.is_playing_a = true;
+                                        state.decoding = decode(id, &state.file_a);
+                                    }
+                                }
+                            }
+                        }
+                        
+                        // Sync groups run on one clock: once every sound in a group has finished its
+                        // cycle, the group pauses, then all of them start the next cycle together with
+                        // the same repeat count. The first sound by id sets the ranges and probability.
+                        let mut sync_groups: HashMap<String, Vec<String>> = HashMap::new();
+                        for (id, state) in &ambient_states {
+                            if let Some(group) = &state.settings.sync_group {
+                                sync_groups.entry(group.clone()).or_default().push(id.clone());
+                            }
+                        }
+                        sync_pauses.retain(|group, _| sync_groups.contains_key(group));
+                        for (group, mut ids) in sync_groups {
+                            let waiting = ids.iter().all(|id| {
+                                let state = &ambient_states[id];
+                                state.is_paused && state.sink.empty() && state.decoding.is_none()
+                            });
+                            if !waiting {
+                                // A sound joined mid-pause; the group waits for it and rolls a new pause
+                                sync_pauses.remove(&group);
+                                continue;
+                            }
+                            ids.sort();
+                            let leader = ambient_states[&ids[0]].settings.clone();
+                            let pause_remaining = sync_pauses
+                                .entry(group.clone())
+                                .or_insert_with(|| rng.gen_range(leader.pause_min..=leader.pause_max) as f64 * 5.0); // ~5s per loop estimate
+                            *pause_remaining -= elapsed as f64;
+                            if *pause_remaining > 0.0 {
+                                continue;
+                            }
+                            if rng.gen::<f32>() >= leader.event_probability {
+                                // The cycle doesn't come up this time; rest another loop
+                                *pause_remaining = 5.0;
+                                continue;
+                            }
+                            sync_pauses.remove(&group);
+                            let loops = rng.gen_range(leader.repeat_min..=leader.repeat_max);
+                            for id in &ids {
+                                if let Some(state) = ambient_states.get_mut(id) {
+                                    state.is_paused = false;
+                                    state.loops_remaining = loops;
+                                    state.is_playing_a = true;
+                                    state.decoding = decode(id, &state.file_a);
+                                }
+                            }
+                        }
+                    }
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                        // Channel closed, exit thread
+                        break;
+                    }
+                }
+                engine_stats_clone.loop_busy(iteration_start.elapsed().saturating_sub(waited));
+            }
+        });
+        
+        Self {
+            command_tx,
+            progress,
+            playback_state,
+            sample_buffer,
+            ambient_sample_buffer,
+            soundboard_sample_buffer,
+            active_ambients,
+            current_track,
+            playlist_state,
+            playlists,
+            all_tracks,
+            soundboard_playing,
+            scheduler_state,
+            presets_dir,
+            current_preset_id,
+            events,
+            errors,
+            engine_stats,
+            visualization,
+            meters,
+            ambient_levels,
+            ambient_phases,
+            mix_tap,
+        }
+    }
+    
+    pub fn set_presets_dir(&self, path: PathBuf) {
+        *self.presets_dir.lock() = Some(path);
+    }
+    
+    pub fn send(&self, cmd: AudioCommand) {
+        self.engine_stats.command_sent();
+        let _ = self.command_tx.send(cmd);
+    }
+    
+    pub fn get_progress(&self) -> AudioProgress {
+        self.progress.lock().clone()
+    }
+    
+    pub fn get_playback_state(&self) -> PlaybackState {
+        self.playback_state.lock().clone()
+    }
+    
+    pub fn get_meters(&self) -> MeterReadings {
+        self.meters.lock().clone()
+    }
+    
+    pub fn get_current_track(&self) -> Option<CurrentTrackInfo> {
+        self.current_track.lock().clone()
+    }
+    
+    pub fn get_playlist_state(&self) -> PlaylistState {
+        self.playlist_state.lock().clone()
+    }
+    
+    // Speak `text` on the soundboard bus
+    pub fn announce(&self, text: String, voice: Option<String>) {
+        tts::announce(self.command_tx.clone(), self.errors.clone(), text, voice);
+    }
+    
+    // Start running a schedule from its first item. `sound_files` has the file and trim of every
+    // transition and event sound the items use, so the scheduler tick doesn't scan the soundboard.
+    pub fn start_schedule(
+        &self,
+        items: Vec<ScheduledItem>,
+        schedule_id: Option<String>,
+        transition: Option<TransitionChime>,
+        sound_files: HashMap<String, (String, SoundTrim)>,
+    ) {
+        let mut sched = self.scheduler_state.lock();
+        
+        let first_item = &items[0];
+        let min = first_item.min_minutes.min(first_item.max_minutes);
+        let max = first_item.min_minutes.max(first_item.max_minutes);
+        let duration = if min == max {
+            min
+        } else {
+            min + (rand::random::<u32>() % (max - min + 1))
+        };
+        
+        sched.items = items;
+        sched.current_schedule_id = schedule_id;
+        sched.transition = transition;
+        sched.sound_files = sound_files;
+        sched.pending_events.clear(); // Planned when the scheduler tick starts the first item
+        sched.is_playing = true;
+        sched.current_item_index = 0;
+        sched.current_duration = duration;
+        sched.time_remaining = (duration * 60) as i32;
+    }
+    
+    pub fn stop_schedule(&self) {
+        let mut sched = self.scheduler_state.lock();
+        sched.is_playing = false;
+        sched.current_item_index = 0;
+        sched.current_duration = 0;
+        sched.time_remaining = 0;
+        sched.pending_events.clear();
+        // Also stop all ambient sounds
+        self.send(AudioCommand::StopAllAmbient { fade_secs: None });
+    }
+}
@@ -0,0 +1,71 @@
+// Error reporting - failures on the audio thread and in background work used to only reach stderr,
+// so a button that failed looked like it did nothing. Each one is now sent to the frontend as an
+// app://error event and kept in a short history for get_recent_errors.
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crate::events::Events;
+
+pub const ERROR_EVENT: &str = "app://error";
+const MAX_RECENT: usize = 50;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    AudioOutput,  // no output device could be opened
+    FileOpen,     // a sound or track file is missing or unreadable
+    Decode,       // the file isn't audio rodio can decode
+    Sink,         // a playback sink couldn't be created
+    Announcement, // text-to-speech failed
+    Recording,
+    Render,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorEvent {
+    pub code: ErrorCode,
+    pub message: String,
+    pub context: Option<String>, // what it was working on, usually a file path
+    pub timestamp: String,       // RFC 3339
+}
+
+#[derive(Clone)]
+pub struct ErrorReporter {
+    // Errors before the frontend sets its event sink are only kept in the history
+    events: Events,
+    recent: Arc<Mutex<VecDeque<ErrorEvent>>>,
+}
+
+impl ErrorReporter {
+    pub fn new(events: Events) -> Self {
+        Self { events, recent: Arc::new(Mutex::new(VecDeque::new())) }
+    }
+
+    pub fn report(&self, code: ErrorCode, message: impl Into<String>, context: Option<String>) {
+        let event = ErrorEvent {
+            code,
+            message: message.into(),
+            context,
+            timestamp: chrono::Local::now().to_rfc3339(),
+        };
+        match &event.context {
+            Some(context) => log::error!("{} ({})", event.message, context),
+            None => log::error!("{}", event.message),
+        }
+        {
+            let mut recent = self.recent.lock();
+            if recent.len() == MAX_RECENT {
+                recent.pop_front();
+            }
+            recent.push_back(event.clone());
+        }
+        self.events.emit(ERROR_EVENT, event);
+    }
+
+    // Oldest first
+    pub fn recent(&self) -> Vec<ErrorEvent> {
+        self.recent.lock().iter().cloned().collect()
+    }
+}
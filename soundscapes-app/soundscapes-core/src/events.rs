@@ -0,0 +1,36 @@
+// Event sink - how the engine tells its frontend what happened (track changes, silence, band
+// triggers, errors). The app forwards events to its windows; tests and other frontends can collect
+// or ignore them. Payloads arrive as JSON, the same shape the windows receive.
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::sync::Arc;
+
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: &str, payload: serde_json::Value);
+}
+
+// Shared handle to the frontend's sink. Set once the frontend is ready; events emitted before then
+// are dropped.
+#[derive(Clone, Default)]
+pub struct Events {
+    sink: Arc<Mutex<Option<Arc<dyn EventSink>>>>,
+}
+
+impl Events {
+    pub fn set_sink(&self, sink: Arc<dyn EventSink>) {
+        *self.sink.lock() = Some(sink);
+    }
+
+    pub fn has_sink(&self) -> bool {
+        self.sink.lock().is_some()
+    }
+
+    pub fn emit<T: Serialize>(&self, event: &str, payload: T) {
+        // Cloned out so a sink that takes a while doesn't hold the lock
+        let Some(sink) = self.sink.lock().clone() else { return };
+        match serde_json::to_value(payload) {
+            Ok(payload) => sink.emit(event, payload),
+            Err(e) => log::warn!("Failed to serialize {}: {}", event, e),
+        }
+    }
+}
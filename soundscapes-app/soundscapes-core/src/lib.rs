@@ -0,0 +1,21 @@
+// The Soundscapes audio engine, independent of Tauri, so other frontends (and tests) can run it
+// without a window: the engine and its audio thread, the shared data model, track selection, the
+// per-sound effect chain, the analysis used by meters, exports and visualizers, and the output
+// backends. The engine reports back through an EventSink the frontend implements, and reads the time
+// from a Clock.
+pub mod audio_cache;
+pub mod clock;
+pub mod decode_pool;
+pub mod diagnostics;
+pub mod effects;
+pub mod engine;
+pub mod errors;
+pub mod events;
+pub mod library;
+pub mod loudness;
+pub mod mix_tap;
+pub mod model;
+pub mod output;
+pub mod spectrum;
+pub mod tts;
+pub mod visualization;
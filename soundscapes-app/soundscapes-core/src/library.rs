@@ -0,0 +1,50 @@
+// Track selection over the scanned music library: album playlists and the auto-DJ
+use crate::model::{AutoDjTarget, PlaylistTrack, ENERGY_MAX, ENERGY_MIN};
+
+// Tracks belonging to an album, in library order (backs the "album-<name>" playlist ids)
+pub fn get_album_tracks(all_tracks: &[PlaylistTrack], album_name: &str) -> Vec<PlaylistTrack> {
+    all_tracks.iter()
+        .filter(|t| t.album == album_name)
+        .cloned()
+        .collect()
+}
+
+// Whether a track falls inside the auto-DJ target band
+pub fn matches_auto_dj_target(track: &PlaylistTrack, target: &AutoDjTarget) -> bool {
+    let mood_matches = target.moods.is_empty() || track.mood.as_ref()
+        .map(|mood| target.moods.iter().any(|m| m.eq_ignore_ascii_case(mood)))
+        .unwrap_or(false);
+    // Untagged energy only matches a band that covers the whole scale
+    let energy_matches = match track.energy {
+        Some(energy) => energy >= target.energy_min && energy <= target.energy_max,
+        None => target.energy_min <= ENERGY_MIN && target.energy_max >= ENERGY_MAX,
+    };
+    mood_matches && energy_matches
+}
+
+// Pick the next auto-DJ track: never the current one, preferring a similar energy so transitions stay smooth
+pub fn pick_auto_dj_track(all_tracks: &[PlaylistTrack], target: &AutoDjTarget, current_id: Option<&str>) -> Option<PlaylistTrack> {
+    let candidates: Vec<&PlaylistTrack> = all_tracks.iter()
+        .filter(|t| matches_auto_dj_target(t, target))
+        .filter(|t| Some(t.id.as_str()) != current_id)
+        .collect();
+    if candidates.is_empty() {
+        // Only the current track matches - repeat it rather than stopping
+        return all_tracks.iter()
+            .find(|t| Some(t.id.as_str()) == current_id && matches_auto_dj_target(t, target))
+            .cloned();
+    }
+    
+    let current_energy = current_id
+        .and_then(|id| all_tracks.iter().find(|t| t.id == id))
+        .and_then(|t| t.energy);
+    let close: Vec<&PlaylistTrack> = match current_energy {
+        Some(energy) => candidates.iter()
+            .filter(|t| t.energy.map(|e| e.abs_diff(energy) <= 2).unwrap_or(false))
+            .copied()
+            .collect(),
+        None => Vec::new(),
+    };
+    let pool = if close.is_empty() { &candidates } else { &close };
+    Some(pool[rand::random::<usize>() % pool.len()].clone())
+}
@@ -0,0 +1,115 @@
+// Integrated loudness (LUFS) per ITU-R BS.1770-4: K-weighting, 400 ms blocks with 75% overlap,
+// absolute gate at -70 LUFS and relative gate 10 LU below the ungated level
+use std::f64::consts::PI;
+
+const ABSOLUTE_GATE: f64 = -70.0;
+const RELATIVE_GATE: f64 = -10.0;
+
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 3],
+    // Direct form II state per channel
+    z: [[f64; 2]; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, channel: usize, x: f64) -> f64 {
+        let z = &mut self.z[channel];
+        let w = x - self.a[1] * z[0] - self.a[2] * z[1];
+        let y = self.b[0] * w + self.b[1] * z[0] + self.b[2] * z[1];
+        z[1] = z[0];
+        z[0] = w;
+        y
+    }
+}
+
+// Measures interleaved stereo as it's written
+pub struct LoudnessMeter {
+    shelf: Biquad,
+    high_pass: Biquad,
+    hop_frames: usize,
+    hop_position: usize,
+    hop_energy: f64,
+    // Mean square of each 100 ms hop; four consecutive hops make a 400 ms gating block
+    hops: Vec<f64>,
+    peak: f32,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32) -> Self {
+        let fs = sample_rate as f64;
+
+        // Stage 1: high shelf modelling the head
+        let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (PI * f0 / fs).tan();
+        let vh = 10f64.powf(gain / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad {
+            b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+            a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            z: [[0.0; 2]; 2],
+        };
+
+        // Stage 2: RLB high-pass
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (PI * f0 / fs).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad {
+            b: [1.0, -2.0, 1.0],
+            a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            z: [[0.0; 2]; 2],
+        };
+
+        Self {
+            shelf,
+            high_pass,
+            hop_frames: (sample_rate / 10) as usize,
+            hop_position: 0,
+            hop_energy: 0.0,
+            hops: Vec::new(),
+            peak: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(2) {
+            for (channel, sample) in frame.iter().enumerate() {
+                self.peak = self.peak.max(sample.abs());
+                let weighted = self.high_pass.process(channel, self.shelf.process(channel, *sample as f64));
+                self.hop_energy += weighted * weighted;
+            }
+            self.hop_position += 1;
+            if self.hop_position == self.hop_frames {
+                self.hops.push(self.hop_energy / self.hop_frames as f64);
+                self.hop_energy = 0.0;
+                self.hop_position = 0;
+            }
+        }
+    }
+
+    // Highest absolute sample value seen
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    // None for silence or audio shorter than one gating block
+    pub fn integrated(&self) -> Option<f64> {
+        let blocks: Vec<f64> = self.hops.windows(4).map(|w| w.iter().sum::<f64>() / 4.0).collect();
+        let audible: Vec<f64> = blocks.into_iter().filter(|e| loudness(*e) > ABSOLUTE_GATE).collect();
+        if audible.is_empty() {
+            return None;
+        }
+        let threshold = loudness(mean(&audible)) + RELATIVE_GATE;
+        let gated: Vec<f64> = audible.into_iter().filter(|e| loudness(*e) > threshold).collect();
+        (!gated.is_empty()).then(|| loudness(mean(&gated)))
+    }
+}
+
+fn loudness(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.max(1e-12).log10()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
@@ -0,0 +1,327 @@
+// Mix tap - copies every sink's output (after volume) to whoever is listening. A pump thread mixes
+// each bus in real time and hands the blocks to every subscriber (the streamer, recordings, the
+// replay buffer), so they don't compete for samples. Sinks only copy while someone subscribes.
+use parking_lot::Mutex;
+use rodio::{Sink, Source};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::output::OutputBackend;
+
+// The mix is resampled to a fixed stereo rate before encoding
+pub const TAP_RATE: u32 = 44100;
+pub const TAP_CHANNELS: usize = 2;
+// Samples are handed to the mixer in blocks, and each sink buffers at most one second
+const TAP_BLOCK_FRAMES: usize = 1024;
+const MAX_QUEUED_SAMPLES: usize = TAP_RATE as usize * TAP_CHANNELS;
+// Mixing runs this far behind real time so every sink has delivered its samples
+const MIX_LATENCY: Duration = Duration::from_millis(250);
+pub const MIX_INTERVAL: Duration = Duration::from_millis(50);
+// Blocks a subscriber may fall behind before the oldest are dropped (10 seconds)
+const MAX_QUEUED_BLOCKS: usize = 200;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Bus {
+    Music,
+    Ambient,
+    Soundboard,
+    Microphone,
+}
+
+impl Bus {
+    pub const ALL: [Bus; 4] = [Bus::Music, Bus::Ambient, Bus::Soundboard, Bus::Microphone];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Bus::Music => "music",
+            Bus::Ambient => "ambient",
+            Bus::Soundboard => "soundboard",
+            Bus::Microphone => "microphone",
+        }
+    }
+}
+
+// One pump interval of audio, interleaved stereo at TAP_RATE, indexed like Bus::ALL
+pub struct MixBlock {
+    pub buses: [Vec<f32>; 4],
+}
+
+impl MixBlock {
+    pub fn bus(&self, bus: Bus) -> &[f32] {
+        &self.buses[bus as usize]
+    }
+
+    // All buses summed
+    pub fn mix(&self) -> Vec<f32> {
+        let mut out = self.buses[0].clone();
+        for bus in &self.buses[1..] {
+            for (sample, value) in out.iter_mut().zip(bus) {
+                *sample += value;
+            }
+        }
+        out
+    }
+}
+
+// Shared mix point; sinks only copy samples while at least one subscriber exists
+pub struct MixTap {
+    users: AtomicUsize,
+    inputs: Mutex<Vec<Arc<TapInput>>>,
+    subscribers: Mutex<Vec<Arc<Subscriber>>>,
+    pump_started: std::sync::Once,
+}
+
+struct TapInput {
+    bus: Bus,
+    queue: Mutex<VecDeque<f32>>, // interleaved stereo at TAP_RATE
+    closed: AtomicBool,
+}
+
+struct Subscriber {
+    blocks: Mutex<VecDeque<Arc<MixBlock>>>,
+}
+
+// Receives every block the pump mixes; dropping it unsubscribes
+pub struct Subscription {
+    tap: Arc<MixTap>,
+    subscriber: Arc<Subscriber>,
+}
+
+impl Subscription {
+    // Blocks mixed since the last call
+    pub fn take(&self) -> Vec<Arc<MixBlock>> {
+        self.subscriber.blocks.lock().drain(..).collect()
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.tap.subscribers.lock().retain(|s| !Arc::ptr_eq(s, &self.subscriber));
+        self.tap.release();
+    }
+}
+
+impl Default for MixTap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MixTap {
+    pub fn new() -> Self {
+        Self {
+            users: AtomicUsize::new(0),
+            inputs: Mutex::new(Vec::new()),
+            subscribers: Mutex::new(Vec::new()),
+            pump_started: std::sync::Once::new(),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.users.load(Ordering::Relaxed) > 0
+    }
+
+    pub fn subscribe(self: &Arc<Self>) -> Subscription {
+        let subscriber = Arc::new(Subscriber { blocks: Mutex::new(VecDeque::new()) });
+        self.subscribers.lock().push(subscriber.clone());
+        self.users.fetch_add(1, Ordering::Relaxed);
+        // The first subscriber starts the pump, which idles while nobody is subscribed
+        self.pump_started.call_once(|| {
+            let tap = self.clone();
+            thread::spawn(move || tap.pump());
+        });
+        Subscription { tap: self.clone(), subscriber }
+    }
+
+    fn release(&self) {
+        if self.users.fetch_sub(1, Ordering::Relaxed) == 1 {
+            self.inputs.lock().clear();
+        }
+    }
+
+    fn register(&self, bus: Bus) -> Arc<TapInput> {
+        let input = Arc::new(TapInput {
+            bus,
+            queue: Mutex::new(VecDeque::new()),
+            closed: AtomicBool::new(false),
+        });
+        self.inputs.lock().push(input.clone());
+        input
+    }
+
+    // Mix in real time, a little behind, whenever someone is subscribed
+    fn pump(&self) {
+        let mut started = Instant::now() + MIX_LATENCY;
+        let mut frames_sent: u64 = 0;
+        loop {
+            thread::sleep(MIX_INTERVAL);
+            if !self.is_enabled() {
+                started = Instant::now() + MIX_LATENCY;
+                frames_sent = 0;
+                continue;
+            }
+            let Some(elapsed) = Instant::now().checked_duration_since(started) else {
+                continue;
+            };
+            let frames_due = (elapsed.as_secs_f64() * TAP_RATE as f64) as u64;
+            if frames_due <= frames_sent {
+                continue;
+            }
+            let block = Arc::new(self.mix((frames_due - frames_sent) as usize));
+            frames_sent = frames_due;
+            for subscriber in self.subscribers.lock().iter() {
+                let mut blocks = subscriber.blocks.lock();
+                blocks.push_back(block.clone());
+                let excess = blocks.len().saturating_sub(MAX_QUEUED_BLOCKS);
+                blocks.drain(..excess);
+            }
+        }
+    }
+
+    // Sum `frames` stereo frames per bus; sinks that fall behind contribute silence
+    fn mix(&self, frames: usize) -> MixBlock {
+        let mut block = MixBlock { buses: std::array::from_fn(|_| vec![0.0f32; frames * TAP_CHANNELS]) };
+        let mut inputs = self.inputs.lock();
+        inputs.retain(|input| !(input.closed.load(Ordering::Relaxed) && input.queue.lock().is_empty()));
+        for input in inputs.iter() {
+            let out = &mut block.buses[input.bus as usize];
+            let mut queue = input.queue.lock();
+            let count = out.len().min(queue.len());
+            for (sample, value) in out.iter_mut().zip(queue.drain(..count)) {
+                *sample += value;
+            }
+        }
+        block
+    }
+}
+
+// Create a sink whose output passes through the mix tap on its way to the output
+// (same as Sink::try_new, which plays the sink's queue on the stream)
+pub fn new_tapped_sink(output: &dyn OutputBackend, tap: &Arc<MixTap>, bus: Bus) -> Result<Sink, String> {
+    let (sink, queue) = Sink::new_idle();
+    output.play(Box::new(TapSource::new(queue, tap.clone(), bus)))?;
+    Ok(sink)
+}
+
+struct TapSource<S: Source<Item = f32>> {
+    inner: S,
+    tap: Arc<MixTap>,
+    bus: Bus,
+    input: Option<Arc<TapInput>>,
+    // Current input frame
+    channel: u16,
+    frame_channels: u16,
+    frame_rate: u32,
+    frame: [f32; 2],
+    // Linear resampler state: previous frame and position of the next output frame after it
+    previous: [f32; 2],
+    position: f64,
+    pending: Vec<f32>,
+}
+
+impl<S: Source<Item = f32>> TapSource<S> {
+    fn new(inner: S, tap: Arc<MixTap>, bus: Bus) -> Self {
+        Self {
+            inner,
+            tap,
+            bus,
+            input: None,
+            channel: 0,
+            frame_channels: 1,
+            frame_rate: TAP_RATE,
+            frame: [0.0; 2],
+            previous: [0.0; 2],
+            position: 0.0,
+            pending: Vec::with_capacity(TAP_BLOCK_FRAMES * TAP_CHANNELS),
+        }
+    }
+
+    fn push_frame(&mut self) {
+        let current = if self.frame_channels == 1 { [self.frame[0], self.frame[0]] } else { self.frame };
+        let step = self.frame_rate as f64 / TAP_RATE as f64;
+        while self.position <= 1.0 {
+            let t = self.position as f32;
+            self.pending.push(self.previous[0] + (current[0] - self.previous[0]) * t);
+            self.pending.push(self.previous[1] + (current[1] - self.previous[1]) * t);
+            self.position += step;
+        }
+        self.position -= 1.0;
+        self.previous = current;
+        if self.pending.len() >= TAP_BLOCK_FRAMES * TAP_CHANNELS {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(input) = &self.input {
+            let mut queue = input.queue.lock();
+            queue.extend(self.pending.drain(..));
+            let excess = queue.len().saturating_sub(MAX_QUEUED_SAMPLES);
+            queue.drain(..excess);
+        }
+        self.pending.clear();
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for TapSource<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            if self.tap.is_enabled() {
+                if self.input.is_none() {
+                    self.input = Some(self.tap.register(self.bus));
+                }
+            } else if self.input.take().is_some() {
+                self.pending.clear();
+            }
+            self.frame_channels = self.inner.channels().max(1);
+            self.frame_rate = self.inner.sample_rate().max(1);
+        }
+
+        let sample = self.inner.next()?;
+        if self.input.is_some() && (self.channel as usize) < TAP_CHANNELS {
+            self.frame[self.channel as usize] = sample;
+        }
+        self.channel += 1;
+        if self.channel >= self.frame_channels {
+            self.channel = 0;
+            if self.input.is_some() {
+                self.push_frame();
+            }
+        }
+        Some(sample)
+    }
+}
+
+impl<S: Source<Item = f32>> Source for TapSource<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+impl<S: Source<Item = f32>> Drop for TapSource<S> {
+    fn drop(&mut self) {
+        self.flush();
+        if let Some(input) = &self.input {
+            input.closed.store(true, Ordering::Relaxed);
+        }
+    }
+}
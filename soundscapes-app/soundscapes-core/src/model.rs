@@ -0,0 +1,513 @@
+// Library, preset, schedule and playlist types shared by the engine and its frontends; these are
+// the shapes stored in the data folder and sent over IPC
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MusicTrack {
+    pub id: String,
+    pub file: String,
+    pub title: String,
+    pub artist: String,
+    #[serde(default)]
+    pub mood: Option<String>,
+    #[serde(default)]
+    pub energy: Option<u32>, // 1 (calm) - 10 (intense)
+}
+
+// Current track info for cross-window communication
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CurrentTrackInfo {
+    pub id: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub file_path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MusicAlbum {
+    pub name: String,
+    pub artist: String,
+    pub tracks: Vec<MusicTrack>,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AmbientSoundDefaults {
+    pub volume: Option<u32>,
+    pub pitch: Option<f32>,
+    pub pan: Option<i32>,
+    #[serde(rename = "lowPassFreq")]
+    pub low_pass_freq: Option<u32>,
+    #[serde(rename = "reverbType")]
+    pub reverb_type: Option<String>,
+    #[serde(rename = "algorithmicReverb")]
+    pub algorithmic_reverb: Option<u32>,
+    #[serde(rename = "repeatRangeMin")]
+    pub repeat_range_min: Option<u32>,
+    #[serde(rename = "repeatRangeMax")]
+    pub repeat_range_max: Option<u32>,
+    #[serde(rename = "pauseRangeMin")]
+    pub pause_range_min: Option<u32>,
+    #[serde(rename = "pauseRangeMax")]
+    pub pause_range_max: Option<u32>,
+    #[serde(rename = "volumeVariation")]
+    pub volume_variation: Option<u32>,
+    #[serde(rename = "eventProbability")]
+    pub event_probability: Option<u32>,
+    #[serde(rename = "intensityMacro")]
+    pub intensity_macro: Option<IntensityMacro>,
+}
+
+// One setting's value at intensity 0 and at intensity 100
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct IntensityRange {
+    pub low: f32,
+    pub high: f32,
+}
+
+impl IntensityRange {
+    pub const fn new(low: f32, high: f32) -> Self {
+        Self { low, high }
+    }
+}
+
+// How a sound's intensity fader (0-100) maps onto its settings. Settings left out aren't touched.
+// Volume and event probability are percentages, the ranges are A/B cycles.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IntensityMacro {
+    // Shape of the fader: 1 is linear, above 1 changes little at first and more near the top,
+    // below 1 the other way round
+    #[serde(default = "default_intensity_curve")]
+    pub curve: f32,
+    pub volume: Option<IntensityRange>,
+    #[serde(rename = "repeatRangeMin")]
+    pub repeat_range_min: Option<IntensityRange>,
+    #[serde(rename = "repeatRangeMax")]
+    pub repeat_range_max: Option<IntensityRange>,
+    #[serde(rename = "pauseRangeMin")]
+    pub pause_range_min: Option<IntensityRange>,
+    #[serde(rename = "pauseRangeMax")]
+    pub pause_range_max: Option<IntensityRange>,
+    #[serde(rename = "eventProbability")]
+    pub event_probability: Option<IntensityRange>,
+}
+
+fn default_intensity_curve() -> f32 {
+    1.0
+}
+
+// Used for sounds whose metadata doesn't define a macro: from a faint, rare sound to a loud one
+// that plays back to back
+impl Default for IntensityMacro {
+    fn default() -> Self {
+        Self {
+            curve: default_intensity_curve(),
+            volume: Some(IntensityRange::new(15.0, 100.0)),
+            repeat_range_min: Some(IntensityRange::new(1.0, 2.0)),
+            repeat_range_max: Some(IntensityRange::new(1.0, 4.0)),
+            pause_range_min: Some(IntensityRange::new(3.0, 0.0)),
+            pause_range_max: Some(IntensityRange::new(8.0, 0.0)),
+            event_probability: Some(IntensityRange::new(30.0, 100.0)),
+        }
+    }
+}
+
+// Settings an intensity maps to; None where the macro leaves a setting alone
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IntensityValues {
+    pub volume: Option<f32>,
+    pub repeat_range_min: Option<u32>,
+    pub repeat_range_max: Option<u32>,
+    pub pause_range_min: Option<u32>,
+    pub pause_range_max: Option<u32>,
+    pub event_probability: Option<f32>,
+}
+
+impl IntensityMacro {
+    pub fn at(&self, intensity: u32) -> IntensityValues {
+        let t = (intensity.min(100) as f32 / 100.0).powf(self.curve.max(0.01));
+        let value = |range: &Option<IntensityRange>| range.map(|r| r.low + (r.high - r.low) * t);
+        let cycles = |range: &Option<IntensityRange>| value(range).map(|v| v.max(0.0).round() as u32);
+        let repeat_range_min = cycles(&self.repeat_range_min);
+        let pause_range_min = cycles(&self.pause_range_min);
+        IntensityValues {
+            volume: value(&self.volume).map(|v| v.clamp(0.0, 100.0)),
+            // A range's max never ends up below its min, or the engine couldn't pick from it
+            repeat_range_min,
+            repeat_range_max: cycles(&self.repeat_range_max).map(|max| max.max(repeat_range_min.unwrap_or(0))),
+            pause_range_min,
+            pause_range_max: cycles(&self.pause_range_max).map(|max| max.max(pause_range_min.unwrap_or(0))),
+            event_probability: value(&self.event_probability).map(|v| v.clamp(0.0, 100.0)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AmbientSoundFiles {
+    pub a: String,
+    pub b: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AmbientSoundDef {
+    pub id: String,
+    pub name: String,
+    pub files: AmbientSoundFiles,
+    pub defaults: Option<AmbientSoundDefaults>,
+    // Free-form labels ("rain", "night", ...) the random soundscape generator can filter on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u32>, // 1 - 5 stars; higher rated sounds are picked more often
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AmbientCategory {
+    pub name: String,
+    pub icon: Option<String>,
+    pub sounds: Vec<AmbientSoundDef>,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SoundboardSound {
+    pub id: String,
+    pub name: String,
+    pub file: String,
+    pub volume: Option<u32>,
+    pub hotkey: Option<String>,
+    pub color: Option<String>,
+    #[serde(flatten)]
+    pub trim: SoundTrim,
+}
+
+// The part of a soundboard file that plays, so a long recording can be a button without cutting it
+// in an audio editor first
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct SoundTrim {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_ms: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_ms: Option<u32>, // None = to the end of the file
+}
+
+impl SoundTrim {
+    pub fn start(&self) -> Duration {
+        Duration::from_millis(self.start_ms.unwrap_or(0) as u64)
+    }
+
+    // How long the trimmed sound plays, if it ends before the file does. An end that isn't after the
+    // start is ignored.
+    pub fn length(&self) -> Option<Duration> {
+        let start = self.start_ms.unwrap_or(0);
+        self.end_ms.filter(|&end| end > start).map(|end| Duration::from_millis((end - start) as u64))
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        match (self.start_ms, self.end_ms) {
+            (Some(start), Some(end)) if end <= start => Err(format!("Trim end ({} ms) must be after its start ({} ms)", end, start)),
+            (None, Some(0)) => Err("Trim end must be after the start of the file".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SoundboardData {
+    pub sounds: Vec<SoundboardSound>,
+    pub path: String,
+}
+
+// Soundscape preset types
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PresetSound {
+    #[serde(rename = "categoryId")]
+    pub category_id: String,
+    #[serde(rename = "categoryPath")]
+    pub category_path: String,
+    #[serde(rename = "soundId")]
+    pub sound_id: String,
+    pub name: String,
+    #[serde(rename = "filesA")]
+    pub files_a: String,
+    #[serde(rename = "filesB")]
+    pub files_b: String,
+    pub enabled: bool,
+    pub volume: u32,
+    pub pitch: f32,
+    pub pan: i32,
+    #[serde(rename = "lowPassFreq")]
+    pub low_pass_freq: u32,
+    #[serde(rename = "algorithmicReverb")]
+    pub algorithmic_reverb: u32,
+    #[serde(rename = "repeatRangeMin")]
+    pub repeat_range_min: u32,
+    #[serde(rename = "repeatRangeMax")]
+    pub repeat_range_max: u32,
+    #[serde(rename = "pauseRangeMin")]
+    pub pause_range_min: u32,
+    #[serde(rename = "pauseRangeMax")]
+    pub pause_range_max: u32,
+    #[serde(rename = "volumeVariation")]
+    pub volume_variation: u32,
+    #[serde(rename = "eventProbability", default = "default_event_probability")]
+    pub event_probability: u32,
+    // Intensity fader position the settings above came from, if it was used
+    #[serde(default)]
+    pub intensity: Option<u32>,
+    // Sounds with the same sync group pause and start their cycles together
+    #[serde(rename = "syncGroup", default, skip_serializing_if = "Option::is_none")]
+    pub sync_group: Option<String>,
+    // Start the first file at a random point, so sounds starting together aren't in step
+    #[serde(rename = "randomStart", default)]
+    pub random_start: bool,
+    // Lower the music while this sound is audible, for spoken or story layers
+    #[serde(rename = "duckMusic", default)]
+    pub duck_music: bool,
+}
+
+fn default_event_probability() -> u32 {
+    100
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SoundscapePreset {
+    pub id: String,
+    pub name: String,
+    pub created: String,
+    pub modified: String,
+    pub sounds: Vec<PresetSound>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PresetInfo {
+    pub id: String,
+    pub name: String,
+    pub created: String,
+    pub modified: String,
+    #[serde(rename = "soundCount")]
+    pub sound_count: usize,
+    pub etag: String, // hash of the file, changes with every write
+}
+
+// Schedule types for the Soundscapes Scheduler
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduledItem {
+    pub id: String,
+    #[serde(rename = "presetId")]
+    pub preset_id: String,
+    #[serde(rename = "presetName")]
+    pub preset_name: String,
+    #[serde(rename = "minMinutes")]
+    pub min_minutes: u32,
+    #[serde(rename = "maxMinutes")]
+    pub max_minutes: u32,
+    pub order: u32,
+    // Spoken when the schedule moves to this item, e.g. "Break time"
+    #[serde(default)]
+    pub announcement: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<ScheduledEvent>,
+}
+
+// Soundboard sound played once during an item, e.g. a bell toll sometime during "village night"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduledEvent {
+    #[serde(rename = "soundId")]
+    pub sound_id: String,
+    #[serde(default = "default_chime_volume")]
+    pub volume: u32, // 0-100
+    // Seconds after the item starts; None = a random time during the item
+    #[serde(rename = "offsetSecs", default, skip_serializing_if = "Option::is_none")]
+    pub offset_secs: Option<u32>,
+}
+
+// Soundboard sound played as the schedule moves on, e.g. a chime 10 seconds before the switch
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransitionChime {
+    #[serde(rename = "soundId")]
+    pub sound_id: String,
+    #[serde(default = "default_chime_volume")]
+    pub volume: u32, // 0-100
+    #[serde(rename = "leadInSecs", default)]
+    pub lead_in_secs: u32, // how long before the switch it plays; 0 = at the switch
+}
+
+fn default_chime_volume() -> u32 {
+    100
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SchedulePreset {
+    pub id: String,
+    pub name: String,
+    pub created: String,
+    pub modified: String,
+    pub items: Vec<ScheduledItem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition: Option<TransitionChime>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SchedulePresetInfo {
+    pub id: String,
+    pub name: String,
+    pub created: String,
+    pub modified: String,
+    #[serde(rename = "itemCount")]
+    pub item_count: usize,
+    pub etag: String,
+}
+
+// Music Playlist types
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlaylistTrack {
+    pub id: String,
+    pub file: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    #[serde(rename = "albumPath")]
+    pub album_path: String,
+    #[serde(default)]
+    pub mood: Option<String>,
+    #[serde(default)]
+    pub energy: Option<u32>,
+}
+
+impl PlaylistTrack {
+    pub fn file_path(&self) -> String {
+        content_path(&self.album_path, &self.file).to_string_lossy().to_string()
+    }
+
+    // What the engine announces while this track plays
+    pub fn track_info(&self) -> CurrentTrackInfo {
+        CurrentTrackInfo {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            artist: self.artist.clone(),
+            album: self.album.clone(),
+            file_path: self.file_path(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MusicPlaylist {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "isAuto")]
+    pub is_auto: bool,  // true for "All Music" and "Favorites"
+    pub tracks: Vec<PlaylistTrack>,
+    // Optional playback overrides applied when this playlist is selected
+    #[serde(rename = "crossfadeDuration", default)]
+    pub crossfade_duration: Option<f32>,
+    #[serde(rename = "shuffleDefault", default)]
+    pub shuffle_default: Option<bool>,
+    #[serde(rename = "volumeOffset", default)]
+    pub volume_offset: Option<f32>, // dB, applied on top of the music volume
+}
+
+// Auto-DJ target band - the next track is picked from tracks matching these tags
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AutoDjTarget {
+    pub moods: Vec<String>, // Empty = any mood
+    #[serde(rename = "energyMin")]
+    pub energy_min: u32,
+    #[serde(rename = "energyMax")]
+    pub energy_max: u32,
+}
+
+// Playlist id used while the auto-DJ is choosing tracks
+pub const AUTO_DJ_PLAYLIST_ID: &str = "auto-dj";
+// Track energy scale used by mood/energy tagging
+pub const ENERGY_MIN: u32 = 1;
+pub const ENERGY_MAX: u32 = 10;
+
+// Playlist playback state (shared across windows)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlaylistState {
+    #[serde(rename = "currentPlaylistId")]
+    pub current_playlist_id: Option<String>,
+    #[serde(rename = "currentIndex")]
+    pub current_index: i32,
+    #[serde(rename = "isShuffled")]
+    pub is_shuffled: bool,
+    #[serde(rename = "isLooping")]
+    pub is_looping: bool,
+    pub favorites: Vec<String>,  // Track IDs that are favorited
+    #[serde(rename = "interruptedIndex")]
+    pub interrupted_index: Option<i32>,  // For resuming after Play Now
+    #[serde(rename = "autoDj", default)]
+    pub auto_dj: Option<AutoDjTarget>,  // Target band while the auto-dj playlist is active
+}
+
+impl Default for PlaylistState {
+    fn default() -> Self {
+        Self {
+            current_playlist_id: None,
+            current_index: 0,
+            is_shuffled: false,
+            is_looping: true, // Loop enabled by default
+            favorites: Vec::new(),
+            interrupted_index: None,
+            auto_dj: None,
+        }
+    }
+}
+
+// Scheduler playback state (shared across windows)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SchedulerState {
+    #[serde(rename = "isPlaying")]
+    pub is_playing: bool,
+    #[serde(rename = "currentItemIndex")]
+    pub current_item_index: usize,
+    #[serde(rename = "currentDuration")]
+    pub current_duration: u32, // minutes
+    #[serde(rename = "timeRemaining")]
+    pub time_remaining: i32, // seconds
+    pub items: Vec<ScheduledItem>,
+    #[serde(rename = "currentScheduleId")]
+    pub current_schedule_id: Option<String>,
+    #[serde(default)]
+    pub transition: Option<TransitionChime>,
+    // Files of the transition and event sounds (sound id -> file, trim), found when the schedule starts
+    #[serde(skip)]
+    pub sound_files: HashMap<String, (String, SoundTrim)>,
+    // Events of the current item still to play (seconds into the item, index in its events)
+    #[serde(skip)]
+    pub pending_events: Vec<(u32, usize)>,
+}
+
+// Fires a named event when a frequency band stays above a threshold, e.g. "bass > 0.7 for 100ms"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FftTrigger {
+    pub name: String,
+    #[serde(default = "default_trigger_source")]
+    pub source: String, // "music", "ambient" or "soundboard"
+    pub band_start: usize, // first bucket (0-63)
+    pub band_end: usize,   // last bucket, inclusive
+    pub threshold: f32,    // 0.0-1.0 average level of the band
+    #[serde(default)]
+    pub hold_ms: u32,      // how long the band must stay above the threshold
+}
+
+fn default_trigger_source() -> String {
+    "music".to_string()
+}
+
+// A file named in a metadata.json, inside the folder it's in. File names may use either slash; on
+// Windows both already work, elsewhere backslashes are turned into '/' so metadata written on
+// Windows still finds files in subfolders.
+pub fn content_path(folder: impl AsRef<Path>, file: &str) -> PathBuf {
+    if cfg!(windows) {
+        folder.as_ref().join(file)
+    } else {
+        folder.as_ref().join(file.replace('\\', "/"))
+    }
+}
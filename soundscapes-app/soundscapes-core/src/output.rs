@@ -0,0 +1,224 @@
+// Output backends - where the engine's sinks play. The audio thread only ever hands finished sink
+// queues to an OutputBackend, so tests can swap the sound card for CollectingOutput and decide
+// exactly how much audio time passes.
+use parking_lot::Mutex;
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::dynamic_mixer::{self, DynamicMixer, DynamicMixerController};
+use rodio::source::UniformSourceIterator;
+use rodio::{OutputStream, Source};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub type BoxedSource = Box<dyn Source<Item = f32> + Send>;
+
+pub trait OutputBackend {
+    // Start playing a source; it plays until it ends
+    fn play(&self, source: BoxedSource) -> Result<(), String>;
+
+    // Carry on playing everything on another device (None = the system default)
+    fn set_device(&self, _device: Option<&str>) -> Result<(), String> {
+        Err("This output can't change devices".to_string())
+    }
+
+    // A second output of the same kind on another device, for a bus that plays somewhere else
+    fn open_device(&self, _device: Option<&str>) -> Result<Box<dyn OutputBackend>, String> {
+        Err("This output can't open other devices".to_string())
+    }
+
+    // Whether something pulls the sources on its own. Sink::try_seek waits for the source to be
+    // pulled, so the engine only seeks a sink in place on an output that does.
+    fn plays_in_real_time(&self) -> bool {
+        true
+    }
+}
+
+// Opens the backend on the audio thread (a cpal stream can't be moved between threads)
+pub type OutputFactory = Box<dyn FnOnce() -> Result<Box<dyn OutputBackend>, String> + Send>;
+
+const MIX_CHANNELS: u16 = 2;
+// Frames the device stream takes from the mixer at a time
+const FEED_FRAMES: usize = 256;
+
+// An output device through rodio. Sources play into one mixer and the mixer plays on the device, so
+// switching devices only moves the mixer to a new stream and everything playing carries on.
+pub struct RodioOutput {
+    mixer: Arc<DynamicMixerController<f32>>,
+    feed: Arc<MixerFeed>,
+    stream: RefCell<OutputStream>,
+}
+
+// The mixer's output, read by whichever stream has the current generation
+struct MixerFeed {
+    mixer: Mutex<DynamicMixer<f32>>,
+    rate: u32,
+    generation: AtomicU64,
+}
+
+impl RodioOutput {
+    pub fn open_default() -> Result<Self, String> {
+        Self::open(None)
+    }
+
+    pub fn open(device: Option<&str>) -> Result<Self, String> {
+        let device = find_device(device)?;
+        // Mix at the first device's rate; a later device with another rate converts once more
+        let rate = device.default_output_config().map(|config| config.sample_rate().0).unwrap_or(COLLECT_RATE);
+        let (mixer, source) = dynamic_mixer::mixer(MIX_CHANNELS, rate);
+        let feed = Arc::new(MixerFeed { mixer: Mutex::new(source), rate, generation: AtomicU64::new(0) });
+        let stream = open_feed(&device, &feed)?;
+        Ok(Self { mixer, feed, stream: RefCell::new(stream) })
+    }
+}
+
+impl OutputBackend for RodioOutput {
+    fn play(&self, source: BoxedSource) -> Result<(), String> {
+        self.mixer.add(source);
+        Ok(())
+    }
+
+    fn set_device(&self, device: Option<&str>) -> Result<(), String> {
+        let device = find_device(device)?;
+        let stream = open_feed(&device, &self.feed)?;
+        // The old stream stops reading when it's dropped
+        *self.stream.borrow_mut() = stream;
+        Ok(())
+    }
+
+    fn open_device(&self, device: Option<&str>) -> Result<Box<dyn OutputBackend>, String> {
+        Ok(Box::new(RodioOutput::open(device)?))
+    }
+}
+
+fn find_device(name: Option<&str>) -> Result<rodio::Device, String> {
+    let host = rodio::cpal::default_host();
+    match name {
+        None => host.default_output_device().ok_or_else(|| "No output device".to_string()),
+        Some(name) => host
+            .output_devices()
+            .map_err(|e| format!("Failed to enumerate devices: {}", e))?
+            .find(|device| device.name().is_ok_and(|n| n == name))
+            .ok_or_else(|| format!("Output device not found: {}", name)),
+    }
+}
+
+// Open a stream on `device` that reads the mixer from now on; a stream reading it before gets
+// silence from here
+fn open_feed(device: &rodio::Device, feed: &Arc<MixerFeed>) -> Result<OutputStream, String> {
+    let (stream, handle) = OutputStream::try_from_device(device).map_err(|e| e.to_string())?;
+    let generation = feed.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let reader = FeedSource { feed: feed.clone(), generation, buffer: Vec::new(), position: 0 };
+    handle.play_raw(reader).map_err(|e| e.to_string())?;
+    Ok(stream)
+}
+
+struct FeedSource {
+    feed: Arc<MixerFeed>,
+    generation: u64,
+    // Read a block at a time, so the device callback doesn't lock for every sample
+    buffer: Vec<f32>,
+    position: usize,
+}
+
+impl Iterator for FeedSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position == self.buffer.len() {
+            if self.feed.generation.load(Ordering::Relaxed) != self.generation {
+                return None;
+            }
+            let mut mixer = self.feed.mixer.lock();
+            self.buffer.clear();
+            // The mixer has nothing to say when nothing is playing; the stream keeps going
+            self.buffer.extend((0..FEED_FRAMES * MIX_CHANNELS as usize).map(|_| mixer.next().unwrap_or(0.0)));
+            self.position = 0;
+        }
+        let sample = self.buffer[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for FeedSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        MIX_CHANNELS
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.feed.rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+pub fn default_output() -> OutputFactory {
+    Box::new(|| RodioOutput::open_default().map(|output| Box::new(output) as Box<dyn OutputBackend>))
+}
+
+pub const COLLECT_RATE: u32 = 44100;
+pub const COLLECT_CHANNELS: u16 = 2;
+
+type UniformSource = UniformSourceIterator<BoxedSource, f32>;
+
+// Silent backend for tests: nothing reaches a device, and played sources only advance when pull()
+// mixes them. With a ManualClock driving the engine, fades, ducking and auto-advance can be stepped
+// deterministically. A seek reopens the file instead of seeking the sink in place.
+#[derive(Clone, Default)]
+pub struct CollectingOutput {
+    sources: Arc<Mutex<Vec<UniformSource>>>,
+}
+
+impl CollectingOutput {
+    // Factory for Engine::new; keep a clone of this to pull from
+    pub fn factory(&self) -> OutputFactory {
+        let output = self.clone();
+        Box::new(move || Ok(Box::new(output) as Box<dyn OutputBackend>))
+    }
+
+    // Mix the next `frames` frames of everything playing (interleaved stereo at COLLECT_RATE);
+    // sources that end are dropped
+    pub fn pull(&self, frames: usize) -> Vec<f32> {
+        let mut mix = vec![0.0; frames * COLLECT_CHANNELS as usize];
+        let mut sources = self.sources.lock();
+        sources.retain_mut(|source| {
+            for slot in mix.iter_mut() {
+                match source.next() {
+                    Some(sample) => *slot += sample,
+                    None => return false,
+                }
+            }
+            true
+        });
+        mix
+    }
+
+    // Sources currently playing (each sink holds one)
+    pub fn active(&self) -> usize {
+        self.sources.lock().len()
+    }
+}
+
+impl OutputBackend for CollectingOutput {
+    fn play(&self, source: BoxedSource) -> Result<(), String> {
+        self.sources.lock().push(UniformSourceIterator::new(source, COLLECT_CHANNELS, COLLECT_RATE));
+        Ok(())
+    }
+
+    // Every device is the same collector, so pull() hears all buses wherever they're routed
+    fn open_device(&self, _device: Option<&str>) -> Result<Box<dyn OutputBackend>, String> {
+        Ok(Box::new(self.clone()))
+    }
+
+    // Nothing pulls between a test's pull() calls
+    fn plays_in_real_time(&self) -> bool {
+        false
+    }
+}
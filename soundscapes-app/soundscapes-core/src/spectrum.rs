@@ -0,0 +1,101 @@
+// Spectrum analysis for the visualizers: FFT bins grouped into FFT_SIZE buckets on a linear, log
+// or mel scale
+use rustfft::num_complex::Complex;
+
+// Number of FFT frequency bins to send to frontend
+pub const FFT_SIZE: usize = 64;
+
+// FFT window length and the number of usable (positive frequency) bins
+pub const FFT_WINDOW: usize = 1024;
+pub const FFT_HALF: usize = FFT_WINDOW / 2;
+// Nominal output rate used to place mel bucket edges (sample buffers don't carry a rate)
+pub const FFT_NOMINAL_SAMPLE_RATE: f32 = 44100.0;
+
+// How FFT bins are grouped into the FFT_SIZE buckets sent to the frontend
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FrequencyScale {
+    Linear, // Equal-width buckets (most musical content lands in the first few)
+    Log,    // Logarithmically spaced buckets
+    Mel,    // Mel-scale buckets (perceptual)
+}
+
+impl FrequencyScale {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(Self::Linear),
+            "log" => Some(Self::Log),
+            "mel" => Some(Self::Mel),
+            _ => None,
+        }
+    }
+}
+
+// Compute the [start, end) FFT bin range covered by each of the FFT_SIZE buckets
+pub fn frequency_bucket_ranges(scale: FrequencyScale) -> Vec<(usize, usize)> {
+    // Bucket edges expressed as (fractional) FFT bin positions
+    let edges: Vec<f32> = (0..=FFT_SIZE).map(|i| {
+        let t = i as f32 / FFT_SIZE as f32;
+        match scale {
+            FrequencyScale::Linear => t * FFT_HALF as f32,
+            // Bin 1 up to the last bin, evenly spaced in log space (skip the DC bin)
+            FrequencyScale::Log => (FFT_HALF as f32).powf(t),
+            FrequencyScale::Mel => {
+                let hz_to_mel = |hz: f32| 2595.0 * (1.0 + hz / 700.0).log10();
+                let max_mel = hz_to_mel(FFT_NOMINAL_SAMPLE_RATE / 2.0);
+                let hz = 700.0 * (10f32.powf(t * max_mel / 2595.0) - 1.0);
+                hz / (FFT_NOMINAL_SAMPLE_RATE / FFT_WINDOW as f32)
+            }
+        }
+    }).collect();
+    
+    // Every bucket gets at least one bin and buckets never overlap
+    let mut ranges = Vec::with_capacity(FFT_SIZE);
+    let mut prev_end = 0;
+    for i in 0..FFT_SIZE {
+        let start = (edges[i] as usize).max(prev_end).min(FFT_HALF - 1);
+        let end = (edges[i + 1] as usize).max(start + 1).min(FFT_HALF);
+        ranges.push((start, end));
+        prev_end = end;
+    }
+    ranges
+}
+
+// Hann-window the samples, run the FFT and bin the result
+pub fn analyze_spectrum(
+    fft: &dyn rustfft::Fft<f32>,
+    fft_buffer: &mut [Complex<f32>],
+    samples: &[f32],
+    ranges: &[(usize, usize)],
+) -> Vec<f32> {
+    let last = (fft_buffer.len() - 1).max(1) as f32;
+    for (i, slot) in fft_buffer.iter_mut().enumerate() {
+        let sample = samples.get(i).copied().unwrap_or(0.0);
+        let window = 0.5 * (1.0 - (2.0 * std::f32::consts::PI * i as f32 / last).cos());
+        *slot = Complex::new(sample * window, 0.0);
+    }
+    fft.process(fft_buffer);
+    bin_fft_magnitudes(fft_buffer, ranges)
+}
+
+// Move the previous bins toward the new ones: `attack` when rising, `decay` when falling (1.0 = no smoothing)
+pub fn smooth_bins(previous: &mut Vec<f32>, current: &[f32], attack: f32, decay: f32) {
+    if previous.len() != current.len() {
+        *previous = current.to_vec();
+        return;
+    }
+    for (prev, &cur) in previous.iter_mut().zip(current.iter()) {
+        let factor = if cur > *prev { attack } else { decay };
+        *prev += (cur - *prev) * factor;
+    }
+}
+
+// Average FFT magnitudes into buckets and map them to 0.0-1.0
+pub fn bin_fft_magnitudes(fft_buffer: &[Complex<f32>], ranges: &[(usize, usize)]) -> Vec<f32> {
+    ranges.iter().map(|&(start, end)| {
+        let sum: f32 = fft_buffer[start..end].iter().map(|c| c.norm()).sum();
+        let mag = sum / (end - start) as f32;
+        // Use log scale for better dynamic range
+        let log_mag = (1.0 + mag * 50.0).ln() / 5.0;
+        log_mag.clamp(0.0, 1.0)
+    }).collect()
+}
@@ -0,0 +1,175 @@
+// Text-to-speech announcements - the OS speech engine renders to a WAV file, which then plays on the
+// soundboard bus so music and ambience duck under it like any other soundboard sound
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::errors::{ErrorCode, ErrorReporter};
+use crate::engine::AudioCommand;
+
+const FILE_PREFIX: &str = "soundscapes-announce-";
+// Rendered announcements are deleted once they're this old
+const FILE_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+
+// Render and play an announcement in the background; rendering takes about a second
+pub fn announce(commands: Sender<AudioCommand>, errors: ErrorReporter, text: String, voice: Option<String>) {
+    thread::spawn(move || {
+        remove_old_files();
+        match render(&text, voice.as_deref()) {
+            Ok(path) => {
+                let _ = commands.send(AudioCommand::PlaySoundboard {
+                    file_path: path.to_string_lossy().to_string(),
+                    volume: 1.0,
+                    trim: Default::default(),
+                });
+            }
+            Err(e) => errors.report(ErrorCode::Announcement, format!("Announcement failed: {}", e), Some(text)),
+        }
+    });
+}
+
+fn render(text: &str, voice: Option<&str>) -> Result<PathBuf, String> {
+    if text.trim().is_empty() {
+        return Err("Announcement text is empty".to_string());
+    }
+    let stamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+    let path = std::env::temp_dir().join(format!("{}{}.wav", FILE_PREFIX, stamp));
+    render_to(text, voice, &path)?;
+    if !path.exists() {
+        return Err("Speech engine produced no audio".to_string());
+    }
+    Ok(path)
+}
+
+// Windows: System.Speech via PowerShell. Text and voice go through the environment to avoid quoting issues.
+#[cfg(windows)]
+fn render_to(text: &str, voice: Option<&str>, path: &std::path::Path) -> Result<(), String> {
+    const SCRIPT: &str = "Add-Type -AssemblyName System.Speech; \
+        $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+        if ($env:SOUNDSCAPES_TTS_VOICE) { $s.SelectVoice($env:SOUNDSCAPES_TTS_VOICE) }; \
+        $s.SetOutputToWaveFile($env:SOUNDSCAPES_TTS_OUT); \
+        $s.Speak($env:SOUNDSCAPES_TTS_TEXT); \
+        $s.Dispose()";
+    let mut command = powershell(SCRIPT);
+    command
+        .env("SOUNDSCAPES_TTS_TEXT", text)
+        .env("SOUNDSCAPES_TTS_VOICE", voice.unwrap_or_default())
+        .env("SOUNDSCAPES_TTS_OUT", path);
+    run(command, None).map(|_| ())
+}
+
+// macOS: `say`, reading the text from stdin
+#[cfg(target_os = "macos")]
+fn render_to(text: &str, voice: Option<&str>, path: &std::path::Path) -> Result<(), String> {
+    let mut command = Command::new("say");
+    command.arg("--data-format=LEI16@22050").arg("-o").arg(path);
+    if let Some(voice) = voice {
+        command.args(["-v", voice]);
+    }
+    run(command, Some(text)).map(|_| ())
+}
+
+// Linux: espeak-ng, falling back to the older espeak
+#[cfg(not(any(windows, target_os = "macos")))]
+fn render_to(text: &str, voice: Option<&str>, path: &std::path::Path) -> Result<(), String> {
+    let mut last_error = String::new();
+    for program in ["espeak-ng", "espeak"] {
+        let mut command = Command::new(program);
+        command.arg("--stdin").arg("-w").arg(path);
+        if let Some(voice) = voice {
+            command.args(["-v", voice]);
+        }
+        match run(command, Some(text)) {
+            Ok(_) => return Ok(()),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(format!("{} (install espeak-ng for announcements)", last_error))
+}
+
+// Names accepted as the `voice` argument
+pub fn list_voices() -> Result<Vec<String>, String> {
+    #[cfg(windows)]
+    let voices = run(
+        powershell("Add-Type -AssemblyName System.Speech; \
+            (New-Object System.Speech.Synthesis.SpeechSynthesizer).GetInstalledVoices() | ForEach-Object { $_.VoiceInfo.Name }"),
+        None,
+    )?
+    .lines()
+    .map(|line| line.trim().to_string())
+    .collect::<Vec<_>>();
+
+    // `say -v ?` lines look like "Alex                en_US    # Most people recognize me by my voice."
+    #[cfg(target_os = "macos")]
+    let voices = {
+        let mut command = Command::new("say");
+        command.args(["-v", "?"]);
+        run(command, None)?
+            .lines()
+            .filter_map(|line| line.split("  ").next())
+            .map(|name| name.trim().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // `espeak-ng --voices` prints a table; the fourth column is the voice name
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let voices = {
+        let output = ["espeak-ng", "espeak"].iter().find_map(|program| {
+            let mut command = Command::new(program);
+            command.arg("--voices");
+            run(command, None).ok()
+        });
+        output.ok_or("espeak-ng is not installed")?
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split_whitespace().nth(3))
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    Ok(voices.into_iter().filter(|v| !v.is_empty()).collect())
+}
+
+#[cfg(windows)]
+fn powershell(script: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", script]).creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+// Run a speech command, optionally feeding it stdin, and return its stdout
+fn run(mut command: Command, input: Option<&str>) -> Result<String, String> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let mut child = command
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes()).map_err(|e| format!("Failed to send text to {}: {}", program, e))?;
+    }
+    let output = child.wait_with_output().map_err(|e| format!("{} failed: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn remove_old_files() {
+    let Ok(entries) = std::fs::read_dir(std::env::temp_dir()) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let is_announcement = entry.file_name().to_string_lossy().starts_with(FILE_PREFIX);
+        let age = entry.metadata().and_then(|m| m.modified()).ok().and_then(|t| t.elapsed().ok());
+        if is_announcement && age.map(|a| a > FILE_MAX_AGE).unwrap_or(false) {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// Visualization consumers - the audio thread runs FFTs on music, ambient and soundboard audio every
+// 50ms for the visualizers, lighting sync and band triggers. That work was done even with no
+// visualizer open, so windows now report when they show one (counted per window, since a window
+// can show several) and the audio thread skips spectrum analysis while nothing uses it.
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+#[derive(Default)]
+pub struct VisualizationUsers {
+    // Window label -> number of active visualizers in it
+    windows: Mutex<HashMap<String, u32>>,
+    lighting: AtomicBool,
+    // Read by the audio thread each analysis pass
+    active: Arc<AtomicBool>,
+}
+
+impl VisualizationUsers {
+    pub fn active_flag(&self) -> Arc<AtomicBool> {
+        self.active.clone()
+    }
+
+    pub fn set_window(&self, label: &str, active: bool) {
+        let mut windows = self.windows.lock();
+        let count = windows.entry(label.to_string()).or_insert(0);
+        if active {
+            *count += 1;
+        } else {
+            *count = count.saturating_sub(1);
+        }
+        if *count == 0 {
+            windows.remove(label);
+        }
+        self.update(&windows);
+    }
+
+    // A closed window can't say it stopped
+    pub fn remove_window(&self, label: &str) {
+        let mut windows = self.windows.lock();
+        windows.remove(label);
+        self.update(&windows);
+    }
+
+    pub fn set_lighting(&self, running: bool) {
+        self.lighting.store(running, Ordering::Relaxed);
+        self.update(&self.windows.lock());
+    }
+
+    fn update(&self, windows: &HashMap<String, u32>) {
+        let active = !windows.is_empty() || self.lighting.load(Ordering::Relaxed);
+        self.active.store(active, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,339 @@
+// Engine behaviour over time, run without audio hardware: CollectingOutput stands in for the sound
+// card and a ManualClock for the time, so each test decides exactly how much audio is played and how
+// much time passes. Audio files are short tones written to a temporary folder.
+use parking_lot::Mutex;
+use soundscapes_core::clock::ManualClock;
+use soundscapes_core::diagnostics::EngineDebug;
+use soundscapes_core::engine::*;
+use soundscapes_core::events::EventSink;
+use soundscapes_core::model::*;
+use soundscapes_core::output::{CollectingOutput, COLLECT_CHANNELS, COLLECT_RATE};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// How long to wait for the audio thread to get somewhere before failing
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Harness {
+    engine: Engine,
+    clock: Arc<ManualClock>,
+    output: CollectingOutput,
+    events: Arc<CollectedEvents>,
+    dir: PathBuf,
+}
+
+#[derive(Default)]
+struct CollectedEvents(Mutex<Vec<(String, serde_json::Value)>>);
+
+impl EventSink for CollectedEvents {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        self.0.lock().push((event.to_string(), payload));
+    }
+}
+
+impl Harness {
+    fn start(name: &str) -> Self {
+        let clock = Arc::new(ManualClock::new());
+        let output = CollectingOutput::default();
+        let engine = Engine::with_clock(output.factory(), clock.clone());
+        let events = Arc::new(CollectedEvents::default());
+        engine.events.set_sink(events.clone());
+        let dir = std::env::temp_dir().join(format!("soundscapes-core-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let harness = Self { engine, clock, output, events, dir };
+        // Once it answers, the audio thread has read the clock, so moving it counts from here
+        harness.debug();
+        harness
+    }
+
+    fn advance(&self, secs: f32) {
+        self.clock.advance(Duration::from_secs_f32(secs));
+    }
+
+    // Play `secs` of audio through everything the engine has playing
+    fn pull(&self, secs: f32) {
+        self.output.pull((secs * COLLECT_RATE as f32) as usize);
+    }
+
+    fn debug(&self) -> EngineDebug {
+        let (reply, response) = channel();
+        self.engine.send(AudioCommand::DumpDebugState(reply));
+        response.recv_timeout(TIMEOUT).expect("The audio thread did not respond")
+    }
+
+    // Poll the audio thread until `done` holds; the clock stands still meanwhile, so a fade that got
+    // there stays there
+    fn wait_for(&self, what: &str, done: impl Fn(&EngineDebug) -> bool) -> EngineDebug {
+        let started = Instant::now();
+        loop {
+            let debug = self.debug();
+            if done(&debug) {
+                return debug;
+            }
+            assert!(started.elapsed() < TIMEOUT, "Timed out waiting for {}: {:?}", what, debug);
+            std::thread::sleep(Duration::from_millis(2));
+        }
+    }
+
+    fn wait_until(&self, what: &str, done: impl Fn() -> bool) {
+        let started = Instant::now();
+        while !done() {
+            assert!(started.elapsed() < TIMEOUT, "Timed out waiting for {}", what);
+            std::thread::sleep(Duration::from_millis(2));
+        }
+    }
+
+    fn event_ids(&self, event: &str) -> Vec<String> {
+        self.events.0.lock().iter()
+            .filter(|(name, _)| name == event)
+            .filter_map(|(_, payload)| payload["id"].as_str().map(str::to_string))
+            .collect()
+    }
+
+    // A 440 Hz stereo tone at COLLECT_RATE, as 16-bit PCM WAV
+    fn write_tone(&self, name: &str, secs: f32) -> String {
+        let frames = (secs * COLLECT_RATE as f32) as u32;
+        let block_align = COLLECT_CHANNELS as u32 * 2;
+        let data_len = frames * block_align;
+        let mut bytes = Vec::with_capacity(44 + data_len as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&COLLECT_CHANNELS.to_le_bytes());
+        bytes.extend_from_slice(&COLLECT_RATE.to_le_bytes());
+        bytes.extend_from_slice(&(COLLECT_RATE * block_align).to_le_bytes());
+        bytes.extend_from_slice(&(block_align as u16).to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for frame in 0..frames {
+            let phase = frame as f32 * 440.0 * std::f32::consts::TAU / COLLECT_RATE as f32;
+            let sample = (phase.sin() * 0.5 * i16::MAX as f32) as i16;
+            for _ in 0..COLLECT_CHANNELS {
+                bytes.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+        let path = self.dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().to_string()
+    }
+}
+
+fn near(value: f32, expected: f32) -> bool {
+    (value - expected).abs() < 1e-3
+}
+
+fn music_volume(debug: &EngineDebug) -> Option<f32> {
+    debug.music.sink.as_ref().map(|sink| sink.volume)
+}
+
+fn track(dir: &Path, id: &str, file: &str) -> PlaylistTrack {
+    PlaylistTrack {
+        id: id.to_string(),
+        file: file.to_string(),
+        title: id.to_uppercase(),
+        artist: "Test".to_string(),
+        album: "Tones".to_string(),
+        album_path: dir.to_string_lossy().to_string(),
+        mood: None,
+        energy: None,
+    }
+}
+
+#[test]
+fn ambient_fades_follow_the_clock() {
+    let h = Harness::start("ambient-fades");
+    let file = h.write_tone("rain.wav", 2.0);
+    h.engine.send(AudioCommand::PlayAmbient {
+        id: "rain".to_string(),
+        file_a: file.clone(),
+        file_b: file,
+        settings: AmbientSettings::default(),
+    });
+    let rain = |debug: &EngineDebug| debug.ambients.iter().find(|a| a.id == "rain").map(|a| (a.sink.volume, a.fading_in, a.fading_out));
+
+    // The fade-in is 0.2 seconds, and doesn't move until the clock does
+    h.wait_for("the sound to start", |d| rain(d).is_some_and(|(volume, fading_in, _)| volume == 0.0 && fading_in == Some(0.0)));
+    h.advance(0.1);
+    h.wait_for("half the fade-in", |d| rain(d).is_some_and(|(volume, _, _)| near(volume, 0.5)));
+    h.advance(0.1);
+    h.wait_for("the end of the fade-in", |d| rain(d).is_some_and(|(volume, fading_in, _)| near(volume, 1.0) && fading_in.is_none()));
+
+    h.engine.send(AudioCommand::StopAmbient { id: "rain".to_string(), fade_secs: Some(1.0) });
+    h.wait_for("the fade-out to start", |d| rain(d).is_some_and(|(_, _, fading_out)| fading_out == Some(0.0)));
+    h.advance(0.5);
+    h.wait_for("half the fade-out", |d| rain(d).is_some_and(|(volume, _, _)| near(volume, 0.5)));
+    h.advance(0.5);
+    h.wait_for("the sound to stop", |d| rain(d).is_none());
+    assert!(h.engine.active_ambients.lock().is_empty());
+}
+
+#[test]
+fn soundboard_ducks_the_music() {
+    let h = Harness::start("ducking");
+    let music = h.write_tone("music.wav", 10.0);
+    let chime = h.write_tone("chime.wav", 1.0);
+    h.engine.send(AudioCommand::SetCrossfadeDuration(0.0));
+    h.engine.send(AudioCommand::Play {
+        file_path: music.clone(),
+        track_info: CurrentTrackInfo { id: "music".to_string(), file_path: music, ..Default::default() },
+    });
+    h.wait_for("the music to start", |d| music_volume(d) == Some(1.0));
+
+    // 50% ducking by default, over 0.3 seconds
+    h.engine.send(AudioCommand::PlaySoundboard { file_path: chime, volume: 1.0, trim: SoundTrim::default() });
+    h.wait_for("the duck to start", |d| d.ducking.target == 1.0 && d.ducking.progress == 0.0);
+    h.advance(0.15);
+    h.wait_for("half the duck", |d| near(d.ducking.progress, 0.5) && music_volume(d).is_some_and(|v| near(v, 0.75)));
+    h.advance(0.15);
+    h.wait_for("the full duck", |d| near(d.ducking.progress, 1.0) && music_volume(d).is_some_and(|v| near(v, 0.5)));
+
+    // The chime ends once it has been played, and the music comes back up
+    h.pull(1.5);
+    h.wait_for("the chime to end", |d| d.ducking.target == 0.0);
+    assert!(!*h.engine.soundboard_playing.lock());
+    h.advance(0.3);
+    h.wait_for("the music to come back", |d| d.ducking.progress == 0.0 && music_volume(d).is_some_and(|v| near(v, 1.0)));
+}
+
+#[test]
+fn playlist_advances_with_a_crossfade() {
+    let h = Harness::start("auto-advance");
+    h.write_tone("a.wav", 3.0);
+    h.write_tone("b.wav", 3.0);
+    let (a, b) = (track(&h.dir, "a", "a.wav"), track(&h.dir, "b", "b.wav"));
+    h.engine.playlists.lock().insert("tones".to_string(), MusicPlaylist {
+        id: "tones".to_string(),
+        name: "Tones".to_string(),
+        is_auto: false,
+        tracks: vec![a.clone(), b.clone()],
+        crossfade_duration: None,
+        shuffle_default: None,
+        volume_offset: None,
+    });
+    {
+        let mut state = h.engine.playlist_state.lock();
+        state.current_playlist_id = Some("tones".to_string());
+        state.current_index = 0;
+    }
+    h.engine.send(AudioCommand::SetCrossfadeDuration(1.0));
+    h.engine.send(AudioCommand::Play { file_path: a.file_path(), track_info: a.track_info() });
+
+    h.wait_for("the first track to fade in", |d| d.music.fade_in == Some((0.0, 1.0)) && music_volume(d) == Some(0.0));
+    h.advance(1.0);
+    h.wait_for("the fade-in to finish", |d| d.music.fade_in.is_none() && music_volume(d).is_some_and(|v| near(v, 1.0)));
+    // Half a second from the end of a 3 second track, halfway through the 1 second fade-out
+    h.advance(1.5);
+    h.wait_for("the fade-out", |d| d.music.fade_out_active && music_volume(d).is_some_and(|v| near(v, 0.5)));
+
+    // Once the first track has been played to the end, the second takes over with its own fade-in
+    h.pull(3.0);
+    h.wait_for("the second track", |d| d.music.fade_in == Some((0.0, 1.0)) && !d.music.fade_out_active);
+    assert_eq!(h.engine.get_current_track().map(|t| t.id), Some("b".to_string()));
+    assert_eq!(h.engine.get_playlist_state().current_index, 1);
+    assert_eq!(h.event_ids(TRACK_STARTED_EVENT), ["a", "b"]);
+    assert_eq!(h.event_ids(TRACK_ENDED_EVENT), ["a"]);
+}
+
+#[test]
+fn seek_reopens_the_track_on_a_collecting_output() {
+    let h = Harness::start("seek");
+    let music = h.write_tone("music.wav", 5.0);
+    h.engine.send(AudioCommand::SetCrossfadeDuration(0.0));
+    h.engine.send(AudioCommand::Play {
+        file_path: music.clone(),
+        track_info: CurrentTrackInfo { id: "music".to_string(), file_path: music, ..Default::default() },
+    });
+    h.wait_for("the music to start", |d| music_volume(d) == Some(1.0));
+
+    // Nothing pulls the sink between pulls, so seeking it in place would never return
+    h.engine.send(AudioCommand::Seek(2.0));
+    h.debug();
+    let progress = h.engine.get_progress();
+    assert!(near(progress.current_time as f32, 2.0), "Seeked to {}", progress.current_time);
+    assert!(!progress.is_finished);
+}
+
+#[test]
+fn schedule_moves_to_the_next_preset() {
+    let h = Harness::start("scheduler");
+    let presets = h.dir.join("presets");
+    fs::create_dir_all(&presets).unwrap();
+    for (preset, sound) in [("forest", "birds"), ("storm", "rain")] {
+        h.write_tone(&format!("ambient/{}.wav", sound), 2.0);
+        let content = serde_json::json!({
+            "id": preset,
+            "name": preset,
+            "created": "",
+            "modified": "",
+            "sounds": [{
+                "categoryId": "ambient",
+                "categoryPath": h.dir.join("ambient"),
+                "soundId": sound,
+                "name": sound,
+                "filesA": format!("{}.wav", sound),
+                "filesB": format!("{}.wav", sound),
+                "enabled": true,
+                "volume": 100,
+                "pitch": 1.0,
+                "pan": 0,
+                "lowPassFreq": 22000,
+                "algorithmicReverb": 0,
+                "repeatRangeMin": 1,
+                "repeatRangeMax": 1,
+                "pauseRangeMin": 0,
+                "pauseRangeMax": 0,
+                "volumeVariation": 0,
+            }],
+        });
+        fs::write(presets.join(format!("{}.soundscape", preset)), content.to_string()).unwrap();
+    }
+    h.engine.set_presets_dir(presets);
+    let item = |preset: &str, order: u32| ScheduledItem {
+        id: preset.to_string(),
+        preset_id: preset.to_string(),
+        preset_name: preset.to_string(),
+        min_minutes: 1,
+        max_minutes: 1,
+        order,
+        announcement: None,
+        events: Vec::new(),
+    };
+    h.engine.start_schedule(vec![item("forest", 0), item("storm", 1)], None, None, HashMap::new());
+    let playing = |id: &str| h.engine.active_ambients.lock().contains_key(id);
+    let at = |index: usize, remaining: i32| {
+        let state = h.engine.scheduler_state.lock();
+        state.current_item_index == index && state.time_remaining == remaining
+    };
+
+    // The first tick starts the first preset
+    h.advance(1.0);
+    h.wait_until("the first preset", || playing("birds") && at(0, 59));
+
+    // One tick per second of clock; the last one moves to the next item
+    for second in 2..60 {
+        h.advance(1.0);
+        h.wait_until(&format!("second {}", second), || at(0, 60 - second));
+    }
+    h.advance(1.0);
+    h.wait_until("the second item", || at(1, 60));
+    h.wait_until("the second preset", || playing("rain"));
+    h.wait_for("the first preset to fade out", |d| {
+        d.ambients.iter().any(|a| a.id == "birds" && a.scheduler_fading_out == Some(0.0))
+    });
+
+    // The scheduler fade is 2 seconds
+    h.advance(2.0);
+    h.wait_until("the first preset to stop", || !playing("birds"));
+    assert!(playing("rain"));
+}
@@ -0,0 +1,128 @@
+// Album import - an album is a folder with a metadata.json listing its tracks, which meant writing
+// that file by hand for every existing music folder. create_album_metadata builds it from the audio
+// files' tags instead, falling back to the file names where a file has none.
+use lofty::prelude::{Accessor, TaggedFileExt};
+use lofty::tag::ItemKey;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::{MusicMetadata, MusicTrack};
+
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "wav", "flac", "ogg"];
+
+struct FileInfo {
+    file: String,
+    title: String,
+    artist: Option<String>,
+    album: Option<String>,
+    album_artist: Option<String>,
+    disc: u32,
+    track: Option<u32>,
+}
+
+// Leading track number of a file name like "01 - Intro" or "1. Intro", and the rest of it
+fn split_track_number(stem: &str) -> (Option<u32>, &str) {
+    let digits = stem.len() - stem.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 || digits > 3 {
+        return (None, stem);
+    }
+    let rest = stem[digits..].trim_start_matches([' ', '-', '.', '_']);
+    if rest.is_empty() {
+        return (None, stem);
+    }
+    (stem[..digits].parse().ok(), rest)
+}
+
+fn read_file(path: &Path) -> FileInfo {
+    let file = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let (number, name) = split_track_number(&stem);
+    let mut info = FileInfo {
+        file,
+        title: name.to_string(),
+        artist: None,
+        album: None,
+        album_artist: None,
+        disc: 1,
+        track: number,
+    };
+
+    let tagged = match lofty::read_from_path(path) {
+        Ok(tagged) => tagged,
+        Err(e) => {
+            log::debug!("No tags read from {}: {}", path.display(), e);
+            return info;
+        }
+    };
+    let Some(tag) = tagged.primary_tag().or_else(|| tagged.first_tag()) else { return info };
+    if let Some(title) = text(tag.title()) {
+        info.title = title;
+    }
+    info.artist = text(tag.artist());
+    info.album = text(tag.album());
+    info.album_artist = text(tag.get_string(&ItemKey::AlbumArtist).map(Cow::from));
+    info.disc = tag.disk().unwrap_or(1);
+    info.track = tag.track().or(info.track);
+    info
+}
+
+fn text(value: Option<Cow<str>>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+// The value most files agree on, if any file has one
+fn most_common<'a>(values: impl Iterator<Item = &'a str>) -> Option<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for value in values {
+        *counts.entry(value).or_insert(0) += 1;
+    }
+    counts.into_iter().max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0))).map(|(value, _)| value.to_string())
+}
+
+// Album metadata for the audio files directly in `folder`, in disc and track order. Files without a
+// track number come after the numbered ones, by name.
+pub fn build(folder: &Path) -> Result<MusicMetadata, String> {
+    let entries = fs::read_dir(folder).map_err(|e| format!("Failed to read folder: {}", e))?;
+    let mut files: Vec<FileInfo> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        })
+        .map(|path| read_file(&path))
+        .collect();
+    if files.is_empty() {
+        return Err("No audio files in folder".to_string());
+    }
+    files.sort_by_cached_key(|f| (f.disc, f.track.is_none(), f.track, f.file.to_lowercase()));
+
+    let folder_name = folder.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let name = most_common(files.iter().filter_map(|f| f.album.as_deref())).unwrap_or(folder_name);
+    let artist = most_common(files.iter().filter_map(|f| f.album_artist.as_deref())).unwrap_or_else(|| {
+        let mut artists = files.iter().filter_map(|f| f.artist.as_deref());
+        match artists.next() {
+            Some(first) if artists.all(|a| a == first) => first.to_string(),
+            _ => "Various Artists".to_string(),
+        }
+    });
+
+    let tracks = files
+        .into_iter()
+        .enumerate()
+        .map(|(i, f)| MusicTrack {
+            id: format!("track-{:03}", i + 1),
+            file: f.file,
+            title: f.title,
+            artist: f.artist.unwrap_or_else(|| artist.clone()),
+            mood: None,
+            energy: None,
+        })
+        .collect();
+    Ok(MusicMetadata { name, artist, tracks })
+}
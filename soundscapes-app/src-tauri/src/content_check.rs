@@ -0,0 +1,161 @@
+// Content validation - album, ambient category and soundboard metadata.json files are written by
+// hand, and a mistake in one only showed up as a library that failed to load or a sound that did
+// nothing when clicked. validate_content_folders reads all of them and lists every problem found,
+// with the file it is in, instead of stopping at the first one.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::{content_path, AmbientMetadata, MusicMetadata, SoundboardMetadata};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentIssueKind {
+    Unreadable,  // metadata.json couldn't be read
+    Schema,      // not valid JSON, a field missing or of the wrong type, or an empty id
+    MissingFile, // a track or sound file that doesn't exist
+    DuplicateId, // an id used twice where it has to be unique
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentIssue {
+    pub kind: ContentIssueKind,
+    pub library: &'static str, // "music", "ambient" or "soundboard"
+    pub path: String,          // the metadata.json
+    pub id: Option<String>,    // track or sound the issue is about
+    pub message: String,
+}
+
+// validate_content_folders result
+#[derive(Debug, Serialize, Default)]
+pub struct ContentValidation {
+    pub files: usize, // metadata.json files checked
+    pub issues: Vec<ContentIssue>,
+}
+
+impl ContentValidation {
+    fn add(&mut self, kind: ContentIssueKind, library: &'static str, path: &Path, id: Option<&str>, message: String) {
+        self.issues.push(ContentIssue {
+            kind,
+            library,
+            path: path.to_string_lossy().to_string(),
+            id: id.map(str::to_string),
+            message,
+        });
+    }
+
+    fn read<M: DeserializeOwned>(&mut self, library: &'static str, path: &Path) -> Option<M> {
+        self.files += 1;
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                self.add(ContentIssueKind::Unreadable, library, path, None, format!("Failed to read metadata: {}", e));
+                return None;
+            }
+        };
+        match serde_json::from_str(&content) {
+            Ok(metadata) => Some(metadata),
+            Err(e) => {
+                self.add(ContentIssueKind::Schema, library, path, None, format!("Invalid metadata: {}", e));
+                None
+            }
+        }
+    }
+
+    // Checks a track or sound's id against the others in `seen` and that its file exists
+    fn check_entry(
+        &mut self,
+        library: &'static str,
+        path: &Path,
+        seen: &mut HashSet<String>,
+        id: &str,
+        folder: &Path,
+        files: &[&str],
+    ) {
+        if id.is_empty() {
+            self.add(ContentIssueKind::Schema, library, path, None, "An entry has an empty id".to_string());
+        } else if !seen.insert(id.to_string()) {
+            self.add(ContentIssueKind::DuplicateId, library, path, Some(id), format!("Id \"{}\" is used more than once", id));
+        }
+        for file in files {
+            if file.is_empty() || !content_path(folder, file).is_file() {
+                self.add(ContentIssueKind::MissingFile, library, path, Some(id), format!("File not found: {}", file));
+            }
+        }
+    }
+}
+
+// Subfolders of `folder` that have a metadata.json, like the library scan reads
+fn metadata_files(folder: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let mut files: Vec<(PathBuf, PathBuf)> = WalkDir::new(folder)
+        .min_depth(1)
+        .max_depth(1)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_dir())
+        .map(|entry| {
+            let dir = entry.into_path();
+            (dir.join("metadata.json"), dir)
+        })
+        .filter(|(path, _)| path.is_file())
+        .collect();
+    files.sort();
+    files
+}
+
+pub fn validate(music: &Path, ambient: &Path, soundboard: &Path) -> ContentValidation {
+    let mut result = ContentValidation::default();
+
+    // Track ids only have to be unique within their album
+    for (path, dir) in metadata_files(music) {
+        let Some(metadata) = result.read::<MusicMetadata>("music", &path) else { continue };
+        let mut seen = HashSet::new();
+        for track in &metadata.tracks {
+            result.check_entry("music", &path, &mut seen, &track.id, &dir, &[&track.file]);
+        }
+    }
+
+    // Playing ambient sounds are keyed by sound id alone, so ids have to be unique across categories
+    let mut ambient_ids: HashMap<String, PathBuf> = HashMap::new();
+    for (path, dir) in metadata_files(ambient) {
+        let Some(metadata) = result.read::<AmbientMetadata>("ambient", &path) else { continue };
+        let mut seen = HashSet::new();
+        for sound in &metadata.sounds {
+            result.check_entry("ambient", &path, &mut seen, &sound.id, &dir, &[&sound.files.a, &sound.files.b]);
+            if sound.id.is_empty() {
+                continue;
+            }
+            match ambient_ids.get(&sound.id) {
+                Some(other) if other != &path => result.add(
+                    ContentIssueKind::DuplicateId,
+                    "ambient",
+                    &path,
+                    Some(&sound.id),
+                    format!("Id \"{}\" is also used in {}", sound.id, other.display()),
+                ),
+                Some(_) => {}
+                None => {
+                    ambient_ids.insert(sound.id.clone(), path.clone());
+                }
+            }
+        }
+    }
+
+    let path = soundboard.join("metadata.json");
+    if path.is_file() {
+        if let Some(metadata) = result.read::<SoundboardMetadata>("soundboard", &path) {
+            let mut seen = HashSet::new();
+            for sound in &metadata.sounds {
+                result.check_entry("soundboard", &path, &mut seen, &sound.id, soundboard, &[&sound.file]);
+                if let Err(e) = sound.trim.check() {
+                    result.add(ContentIssueKind::Schema, "soundboard", &path, Some(&sound.id), e);
+                }
+            }
+        }
+    }
+
+    result
+}
@@ -0,0 +1,207 @@
+// Sync-friendly data files - app data may live in a folder synced between machines (Syncthing,
+// Dropbox, ...). Writes to presets, schedules, playlists and favorites are checked against the
+// version this app last read, so a file changed on the other machine isn't silently clobbered,
+// and a watcher tells the windows to reload when files change underneath them.
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::{Emitter, Manager};
+
+use crate::errors::AppError;
+use crate::persist;
+use crate::AudioController;
+
+pub const CHANGED_EVENT: &str = "data://changed";
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    // Refuse the write with a conflict error; the window reloads the other machine's version
+    #[default]
+    Reload,
+    // Last writer wins
+    Overwrite,
+    // Keep the other machine's version as a "conflict" copy, then write ours
+    Duplicate,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SyncConfig {
+    pub on_conflict: ConflictPolicy,
+}
+
+// data://changed payload; kind is "presets", "schedules", "playlists" or "favorites" ("soundboard"
+// and "ambient" only come from undo/redo)
+#[derive(Clone, Serialize)]
+pub struct DataChanged {
+    pub kind: &'static str,
+    pub path: String,
+}
+
+// Short content hash; equal ETags mean equal files
+pub fn etag(content: &[u8]) -> String {
+    Sha256::digest(content).iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Default)]
+pub struct DataSync {
+    // ETag of each file as this app last read or wrote it
+    known: Mutex<HashMap<PathBuf, String>>,
+    policy: Mutex<ConflictPolicy>,
+}
+
+impl DataSync {
+    pub fn set_policy(&self, policy: ConflictPolicy) {
+        *self.policy.lock() = policy;
+    }
+
+    // Read a file and remember its version as the base for the next write
+    pub fn read(&self, path: &Path) -> Result<String, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        self.known.lock().insert(path.to_path_buf(), etag(content.as_bytes()));
+        Ok(content)
+    }
+
+    // Write a file unless it changed on disk since we last read it; what happens then depends on
+    // the conflict policy. Files we haven't read this session are written as before.
+    pub fn write(&self, path: &Path, content: &str) -> Result<(), AppError> {
+        let base = self.known.lock().get(path).cloned();
+        let on_disk = fs::read(path).ok().map(|c| etag(&c));
+        if let (Some(base), Some(on_disk)) = (base, on_disk) {
+            if base != on_disk {
+                match *self.policy.lock() {
+                    ConflictPolicy::Reload => {
+                        return Err(AppError::conflict(format!(
+                            "{} was changed on another device; reload it before saving",
+                            display_name(path)
+                        )));
+                    }
+                    ConflictPolicy::Overwrite => log::info!("Overwriting {} changed on another device", path.display()),
+                    ConflictPolicy::Duplicate => {
+                        let copy = keep_conflict_copy(path)?;
+                        log::info!("Kept the other device's {} as {}", display_name(path), copy.display());
+                    }
+                }
+            }
+        }
+        persist::write_atomic(path, content).map_err(|e| AppError::io(path, format!("Failed to write {}: {}", display_name(path), e)))?;
+        self.known.lock().insert(path.to_path_buf(), etag(content.as_bytes()));
+        Ok(())
+    }
+
+    // Delete a file we manage; the watcher won't report it
+    pub fn remove(&self, path: &Path) -> Result<(), AppError> {
+        self.forget(path);
+        fs::remove_file(path).map_err(|e| AppError::io(path, format!("Failed to delete {}: {}", display_name(path), e)))
+    }
+
+    // Returns whether the file was known
+    fn forget(&self, path: &Path) -> bool {
+        self.known.lock().remove(path).is_some()
+    }
+
+    // Whether the file on disk is the version we last read or wrote
+    fn is_known(&self, path: &Path, etag: &str) -> bool {
+        self.known.lock().get(path).map(|known| known == etag).unwrap_or(false)
+    }
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.display().to_string())
+}
+
+// Copy the file on disk to "<id>-conflict-<time>.<ext>"; presets, schedules and playlists also get
+// a new id and a "(conflict ...)" name so both versions show up in the lists
+fn keep_conflict_copy(path: &Path) -> Result<PathBuf, AppError> {
+    let content = fs::read_to_string(path).map_err(|e| AppError::io(path, format!("Failed to read: {}", e)))?;
+    let now = chrono::Local::now();
+    let stem = format!(
+        "{}-conflict-{}",
+        path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+        now.format("%Y%m%d-%H%M%S")
+    );
+    let copy = path.with_file_name(match path.extension() {
+        Some(ext) => format!("{}.{}", stem, ext.to_string_lossy()),
+        None => stem.clone(),
+    });
+
+    let content = match serde_json::from_str::<serde_json::Value>(&content) {
+        Ok(serde_json::Value::Object(mut object)) if object.contains_key("id") => {
+            object.insert("id".to_string(), stem.into());
+            if let Some(serde_json::Value::String(name)) = object.get_mut("name") {
+                name.push_str(&format!(" (conflict {})", now.format("%Y-%m-%d %H:%M")));
+            }
+            serde_json::to_string_pretty(&object).unwrap_or(content)
+        }
+        _ => content,
+    };
+    persist::write_atomic(&copy, content).map_err(|e| AppError::io(&copy, format!("Failed to write conflict copy: {}", e)))?;
+    Ok(copy)
+}
+
+// Files in the app data folder that other machines may change, with their data://changed kind
+fn watched_files(app_data: &Path) -> Vec<(&'static str, PathBuf)> {
+    let mut files = Vec::new();
+    for (kind, dir, extension) in [
+        ("presets", "presets", "soundscape"),
+        ("schedules", "Schedules", "schedule"),
+        ("playlists", "playlists", "playlist"),
+    ] {
+        let Ok(entries) = fs::read_dir(app_data.join(dir)) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map(|e| e == extension).unwrap_or(false) {
+                files.push((kind, path));
+            }
+        }
+    }
+    let favorites = app_data.join("favorites.json");
+    if favorites.exists() {
+        files.push(("favorites", favorites));
+    }
+    files
+}
+
+// Poll the data files and emit data://changed when one is added, changed or removed by something
+// other than this app. Writes of our own are recognised by their ETag and not reported.
+pub fn watch(app: tauri::AppHandle) {
+    let Ok(app_data) = app.path().app_data_dir() else { return };
+    thread::spawn(move || {
+        let mut seen: HashMap<PathBuf, (&'static str, Option<SystemTime>, u64)> = HashMap::new();
+        let mut first = true;
+        loop {
+            let controller = app.state::<Arc<AudioController>>();
+            let mut current = HashMap::new();
+            for (kind, path) in watched_files(&app_data) {
+                let metadata = fs::metadata(&path).ok();
+                let stamp = (kind, metadata.as_ref().and_then(|m| m.modified().ok()), metadata.map(|m| m.len()).unwrap_or(0));
+                let changed = seen.get(&path).map(|s| *s != stamp).unwrap_or(true);
+                if changed && !first {
+                    let own = fs::read(&path).map(|c| controller.data_sync.is_known(&path, &etag(&c))).unwrap_or(false);
+                    if !own {
+                        log::debug!("{} changed outside the app", path.display());
+                        let _ = app.emit(CHANGED_EVENT, DataChanged { kind, path: path.to_string_lossy().to_string() });
+                    }
+                }
+                current.insert(path, stamp);
+            }
+            // Files removed by remove() were already forgotten
+            for (path, (kind, _, _)) in &seen {
+                if !current.contains_key(path) && controller.data_sync.forget(path) {
+                    let _ = app.emit(CHANGED_EVENT, DataChanged { kind: *kind, path: path.to_string_lossy().to_string() });
+                }
+            }
+            seen = current;
+            first = false;
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
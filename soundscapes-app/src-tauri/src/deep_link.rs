@@ -0,0 +1,116 @@
+// soundscapes:// links - launch presets, tracks and sounds from browsers, notes apps or VTT modules
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::remote::{self, RemoteAction};
+use crate::AudioController;
+
+const SCHEME: &str = "soundscapes://";
+// Links that launched the app wait for the frontend to initialize audio before running
+const STARTUP_WAIT: Duration = Duration::from_secs(15);
+const STARTUP_POLL: Duration = Duration::from_millis(100);
+
+pub fn is_deep_link(arg: &str) -> bool {
+    arg.starts_with(SCHEME)
+}
+
+// Supported links:
+//   soundscapes://preset/<id or name>     soundscapes://play/<track id or title>
+//   soundscapes://playlist/<id>           soundscapes://sound/<id or name>
+//   soundscapes://announce/<text>
+//   soundscapes://pause | resume | toggle | next | previous | stop
+pub fn parse(url: &str) -> Result<RemoteAction, String> {
+    let path = url.strip_prefix(SCHEME)
+        .ok_or_else(|| format!("Not a soundscapes link: {}", url))?;
+    let path = path.split(['?', '#']).next().unwrap_or("");
+    let (command, target) = match path.trim_end_matches('/').split_once('/') {
+        Some((command, target)) => (command, percent_decode(target)),
+        None => (path.trim_end_matches('/'), String::new()),
+    };
+
+    let action = match command {
+        "preset" => RemoteAction::ApplyPreset { id: target },
+        "play" if !target.is_empty() => RemoteAction::PlayTrack { id: target },
+        "play" | "resume" => RemoteAction::Resume,
+        "playlist" => RemoteAction::PlayPlaylist { id: target },
+        "sound" => RemoteAction::PlaySoundboard { id: target },
+        "announce" => RemoteAction::Announce { text: target, voice: None },
+        "pause" => RemoteAction::Pause,
+        "toggle" => RemoteAction::TogglePlayback,
+        "next" => RemoteAction::Next,
+        "previous" => RemoteAction::Previous,
+        "stop" => RemoteAction::Stop,
+        _ => return Err(format!("Unsupported link: {}", url)),
+    };
+    match &action {
+        RemoteAction::ApplyPreset { id }
+        | RemoteAction::PlayPlaylist { id }
+        | RemoteAction::PlaySoundboard { id }
+        | RemoteAction::Announce { text: id, .. } if id.is_empty() => Err(format!("Link is missing an id: {}", url)),
+        _ => Ok(action),
+    }
+}
+
+// Link targets are path segments, so names with spaces arrive as %20
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = value.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn open(app: &tauri::AppHandle, url: &str) {
+    if let Err(e) = parse(url).and_then(|action| remote::apply_action(app, action)) {
+        log::warn!("Deep link failed: {}", e);
+    }
+}
+
+// Register the scheme and handle links, including the one the app may have been launched with
+pub fn start(app: &tauri::AppHandle) {
+    // Installed builds register the scheme from the bundle; this covers Linux and dev builds
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    if let Err(e) = app.deep_link().register_all() {
+        log::warn!("Failed to register soundscapes:// links: {}", e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            open(&handle, url.as_str());
+        }
+    });
+
+    let startup_urls: Vec<String> = app.deep_link().get_current().ok().flatten()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|url| url.to_string())
+        .collect();
+    if startup_urls.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        let controller = app.state::<Arc<AudioController>>();
+        let started = Instant::now();
+        while !controller.events.has_sink() && started.elapsed() < STARTUP_WAIT {
+            thread::sleep(STARTUP_POLL);
+        }
+        for url in startup_urls {
+            open(&app, &url);
+        }
+    });
+}
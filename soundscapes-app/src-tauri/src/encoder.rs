@@ -0,0 +1,513 @@
+// Audio file writers for recordings and offline renders. Input is always interleaved stereo f32 at
+// 44.1 kHz (the mix tap's format); compressed formats keep multi-hour sessions to a manageable size.
+use mp3lame_encoder::{max_required_buffer_size, FlushNoGap, InterleavedPcm, Quality};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::num::{NonZeroU32, NonZeroU8};
+use std::path::Path;
+
+use soundscapes_core::loudness::LoudnessMeter;
+use crate::streaming::{self, TAP_CHANNELS, TAP_RATE};
+
+const CHANNELS: u16 = TAP_CHANNELS as u16;
+// Opus only runs at 48 kHz, encoded in 20 ms frames
+const OPUS_RATE: u32 = 48000;
+const OPUS_FRAME: usize = 960;
+const OPUS_MAX_PACKET: usize = 4000;
+// Normalization never pushes sample peaks above -1 dBFS
+const NORMALIZE_PEAK_CEILING: f32 = 0.891;
+const WAV_HEADER_BYTES: u64 = 44;
+
+pub const FORMATS: [&str; 4] = ["wav", "mp3", "ogg", "opus"];
+
+// Format implied by a file name, for renders where the user picks the path
+pub fn format_for_path(path: &str) -> &'static str {
+    let extension = Path::new(path).extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    FORMATS.iter().copied().find(|f| *f == extension).unwrap_or("wav")
+}
+
+// Metadata written into finished files (RIFF INFO, ID3v2 or Vorbis comments); empty fields are skipped
+#[derive(Debug, Clone, Default)]
+pub struct Tags {
+    pub title: String,
+    pub artist: String,
+    pub album: String, // the preset or schedule the audio came from
+    pub date: String,
+}
+
+impl Tags {
+    pub fn new(title: impl Into<String>, album: Option<String>) -> Self {
+        Self {
+            title: title.into(),
+            artist: "Soundscapes".to_string(),
+            album: album.unwrap_or_default(),
+            date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    // Vorbis comment names, also used by Opus
+    fn comments(&self) -> Vec<(&'static str, &str)> {
+        [("TITLE", &self.title), ("ARTIST", &self.artist), ("ALBUM", &self.album), ("DATE", &self.date)]
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(name, value)| (name, value.as_str()))
+            .collect()
+    }
+
+    // LIST/INFO chunk appended after the WAV data; empty when there's nothing to write
+    fn riff_info(&self) -> Vec<u8> {
+        let mut body = b"INFO".to_vec();
+        for (id, value) in [(b"INAM", &self.title), (b"IART", &self.artist), (b"IPRD", &self.album), (b"ICRD", &self.date)] {
+            if value.is_empty() {
+                continue;
+            }
+            let mut text = value.as_bytes().to_vec();
+            text.push(0);
+            body.extend_from_slice(id);
+            body.extend_from_slice(&(text.len() as u32).to_le_bytes());
+            if text.len() % 2 == 1 {
+                text.push(0);
+            }
+            body.extend_from_slice(&text);
+        }
+        if body.len() == 4 {
+            return Vec::new();
+        }
+        let mut chunk = b"LIST".to_vec();
+        chunk.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(&body);
+        chunk
+    }
+
+    // ID3v2.4 tag with UTF-8 text frames, written before the MP3 frames
+    fn id3(&self) -> Vec<u8> {
+        let mut frames = Vec::new();
+        for (id, value) in [(b"TIT2", &self.title), (b"TPE1", &self.artist), (b"TALB", &self.album), (b"TDRC", &self.date)] {
+            if value.is_empty() {
+                continue;
+            }
+            let mut text = vec![3u8]; // UTF-8
+            text.extend_from_slice(value.as_bytes());
+            frames.extend_from_slice(id);
+            frames.extend_from_slice(&syncsafe(text.len()));
+            frames.extend_from_slice(&[0, 0]); // flags
+            frames.extend_from_slice(&text);
+        }
+        if frames.is_empty() {
+            return Vec::new();
+        }
+        let mut tag = b"ID3".to_vec();
+        tag.extend_from_slice(&[4, 0, 0]); // version 2.4, no flags
+        tag.extend_from_slice(&syncsafe(frames.len()));
+        tag.extend_from_slice(&frames);
+        tag
+    }
+}
+
+// ID3v2 sizes use 7 bits per byte
+fn syncsafe(size: usize) -> [u8; 4] {
+    [(size >> 21) as u8 & 0x7f, (size >> 14) as u8 & 0x7f, (size >> 7) as u8 & 0x7f, size as u8 & 0x7f]
+}
+
+pub enum AudioFileWriter {
+    Wav(WavWriter),
+    Mp3(Mp3Writer),
+    Ogg(OggWriter),
+    Opus(OpusWriter),
+    Normalized(Box<NormalizingWriter>),
+}
+
+impl AudioFileWriter {
+    // `bitrate` (kbps) only applies to the compressed formats
+    pub fn create(path: &str, format: &str, bitrate: u32, tags: &Tags) -> Result<Self, String> {
+        Ok(match format {
+            "wav" => Self::Wav(WavWriter::create(path, tags)?),
+            "mp3" => Self::Mp3(Mp3Writer::create(path, bitrate, tags)?),
+            "ogg" => Self::Ogg(OggWriter::create(path, bitrate, tags)?),
+            "opus" => Self::Opus(OpusWriter::create(path, bitrate, tags)?),
+            _ => return Err(format!("Unsupported audio format: {}", format)),
+        })
+    }
+
+    // Like create(), but the finished file is normalized to `target_lufs` when given
+    pub fn create_normalized(
+        path: &str,
+        format: &str,
+        bitrate: u32,
+        tags: &Tags,
+        target_lufs: Option<f64>,
+    ) -> Result<Self, String> {
+        match target_lufs {
+            Some(target) => Ok(Self::Normalized(Box::new(NormalizingWriter::create(path, format, bitrate, tags, target)?))),
+            None => Self::create(path, format, bitrate, tags),
+        }
+    }
+
+    // The file actually growing on disk, which is a temporary WAV while normalizing
+    pub fn disk_path<'a>(&'a self, path: &'a str) -> &'a str {
+        match self {
+            Self::Normalized(w) => &w.temp_path,
+            _ => path,
+        }
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        match self {
+            Self::Wav(w) => w.write(samples),
+            Self::Mp3(w) => w.write(samples),
+            Self::Ogg(w) => w.write(samples),
+            Self::Opus(w) => w.write(samples),
+            Self::Normalized(w) => w.write(samples),
+        }
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        match self {
+            Self::Wav(w) => w.finish(),
+            Self::Mp3(w) => w.finish(),
+            Self::Ogg(w) => w.finish(),
+            Self::Opus(w) => w.finish(),
+            Self::Normalized(w) => w.finish(),
+        }
+    }
+}
+
+fn create_file(path: &str) -> Result<BufWriter<File>, String> {
+    File::create(path).map(BufWriter::new).map_err(|e| format!("Failed to create {}: {}", path, e))
+}
+
+fn write_error(e: impl std::fmt::Display) -> String {
+    format!("Failed to write audio: {}", e)
+}
+
+// 16-bit PCM WAV; the header sizes are patched and the tags appended in finish()
+pub struct WavWriter {
+    out: BufWriter<File>,
+    data_bytes: u64,
+    info: Vec<u8>,
+}
+
+impl WavWriter {
+    pub fn create(path: &str, tags: &Tags) -> Result<Self, String> {
+        let mut writer = Self { out: create_file(path)?, data_bytes: 0, info: tags.riff_info() };
+        writer.write_header().map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        Ok(writer)
+    }
+
+    fn write_header(&mut self) -> std::io::Result<()> {
+        // WAV sizes are 32-bit; very long files are clamped rather than wrapping
+        let data = self.data_bytes.min(u32::MAX as u64 - 36 - self.info.len() as u64) as u32;
+        let block_align = CHANNELS * 2;
+        self.out.write_all(b"RIFF")?;
+        self.out.write_all(&(36 + data + self.info.len() as u32).to_le_bytes())?;
+        self.out.write_all(b"WAVEfmt ")?;
+        self.out.write_all(&16u32.to_le_bytes())?;
+        self.out.write_all(&1u16.to_le_bytes())?;
+        self.out.write_all(&CHANNELS.to_le_bytes())?;
+        self.out.write_all(&TAP_RATE.to_le_bytes())?;
+        self.out.write_all(&(TAP_RATE * block_align as u32).to_le_bytes())?;
+        self.out.write_all(&block_align.to_le_bytes())?;
+        self.out.write_all(&16u16.to_le_bytes())?;
+        self.out.write_all(b"data")?;
+        self.out.write_all(&data.to_le_bytes())
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        for sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.out.write_all(&value.to_le_bytes()).map_err(write_error)?;
+        }
+        self.data_bytes += samples.len() as u64 * 2;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        self.out.write_all(&self.info)
+            .and_then(|_| self.out.seek(SeekFrom::Start(0)))
+            .and_then(|_| self.write_header())
+            .and_then(|_| self.out.flush())
+            .map_err(|e| format!("Failed to finalize audio file: {}", e))
+    }
+}
+
+// Writes a temporary WAV while measuring loudness, then encodes the real file with the gain that
+// reaches the target loudness
+pub struct NormalizingWriter {
+    temp: WavWriter,
+    temp_path: String,
+    path: String,
+    format: String,
+    bitrate: u32,
+    tags: Tags,
+    target_lufs: f64,
+    meter: LoudnessMeter,
+}
+
+impl NormalizingWriter {
+    pub fn create(path: &str, format: &str, bitrate: u32, tags: &Tags, target_lufs: f64) -> Result<Self, String> {
+        if !FORMATS.contains(&format) {
+            return Err(format!("Unsupported audio format: {}", format));
+        }
+        let temp_path = format!("{}.normalizing.wav", path);
+        Ok(Self {
+            // Untagged, so copy_with_gain() can read everything after the header as audio
+            temp: WavWriter::create(&temp_path, &Tags::default())?,
+            temp_path,
+            path: path.to_string(),
+            format: format.to_string(),
+            bitrate,
+            tags: tags.clone(),
+            target_lufs,
+            meter: LoudnessMeter::new(TAP_RATE),
+        })
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        self.meter.push(samples);
+        self.temp.write(samples)
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        let result = self.temp.finish().and_then(|_| {
+            // Silence (or anything too short to measure) is copied unchanged
+            let mut gain = match self.meter.integrated() {
+                Some(measured) => 10f32.powf(((self.target_lufs - measured) / 20.0) as f32),
+                None => 1.0,
+            };
+            if self.meter.peak() > 0.0 {
+                gain = gain.min(NORMALIZE_PEAK_CEILING / self.meter.peak());
+            }
+            copy_with_gain(&self.temp_path, &self.path, &self.format, self.bitrate, &self.tags, gain)
+        });
+        let _ = std::fs::remove_file(&self.temp_path);
+        result
+    }
+}
+
+// Re-encode one of our 16-bit WAV files with a gain applied
+fn copy_with_gain(wav_path: &str, path: &str, format: &str, bitrate: u32, tags: &Tags, gain: f32) -> Result<(), String> {
+    let file = File::open(wav_path).map_err(|e| format!("Failed to open {}: {}", wav_path, e))?;
+    let mut reader = BufReader::new(file);
+    reader.seek(SeekFrom::Start(WAV_HEADER_BYTES)).map_err(|e| format!("Failed to read {}: {}", wav_path, e))?;
+    let mut writer = AudioFileWriter::create(path, format, bitrate, tags)?;
+    // One second at a time
+    let mut bytes = vec![0u8; TAP_RATE as usize * TAP_CHANNELS * 2];
+    loop {
+        let mut filled = 0;
+        while filled < bytes.len() {
+            match reader.read(&mut bytes[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) => return Err(format!("Failed to read {}: {}", wav_path, e)),
+            }
+        }
+        // Whole stereo frames only
+        let usable = filled - filled % (TAP_CHANNELS * 2);
+        if usable == 0 {
+            break;
+        }
+        let samples: Vec<f32> = bytes[..usable]
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / i16::MAX as f32 * gain)
+            .collect();
+        writer.write(&samples)?;
+        if filled < bytes.len() {
+            break;
+        }
+    }
+    writer.finish()
+}
+
+// Constant bitrate MP3 through LAME, like the live stream
+pub struct Mp3Writer {
+    out: BufWriter<File>,
+    encoder: mp3lame_encoder::Encoder,
+}
+
+impl Mp3Writer {
+    pub fn create(path: &str, bitrate: u32, tags: &Tags) -> Result<Self, String> {
+        let mut builder = mp3lame_encoder::Builder::new().ok_or_else(|| "Failed to create MP3 encoder".to_string())?;
+        builder.set_num_channels(CHANNELS as u8).map_err(|e| format!("MP3 encoder: {:?}", e))?;
+        builder.set_sample_rate(TAP_RATE).map_err(|e| format!("MP3 encoder: {:?}", e))?;
+        builder.set_brate(streaming::bitrate_for(bitrate)?).map_err(|e| format!("MP3 encoder: {:?}", e))?;
+        builder.set_quality(Quality::Good).map_err(|e| format!("MP3 encoder: {:?}", e))?;
+        let encoder = builder.build().map_err(|e| format!("MP3 encoder: {:?}", e))?;
+        let mut out = create_file(path)?;
+        out.write_all(&tags.id3()).map_err(write_error)?;
+        Ok(Self { out, encoder })
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        let pcm: Vec<i16> = samples.iter().map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).collect();
+        let mut mp3 = Vec::with_capacity(max_required_buffer_size(pcm.len()));
+        let size = self.encoder.encode(InterleavedPcm(&pcm), mp3.spare_capacity_mut())
+            .map_err(|e| format!("MP3 encoding failed: {:?}", e))?;
+        // SAFETY: encode() initialized the first `size` bytes of the spare capacity
+        unsafe {
+            mp3.set_len(size);
+        }
+        self.out.write_all(&mp3).map_err(write_error)
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        // LAME needs at least 7200 bytes to flush its last frames
+        let mut mp3 = Vec::with_capacity(7200);
+        let size = self.encoder.flush::<FlushNoGap>(mp3.spare_capacity_mut())
+            .map_err(|e| format!("MP3 encoding failed: {:?}", e))?;
+        // SAFETY: flush() initialized the first `size` bytes of the spare capacity
+        unsafe {
+            mp3.set_len(size);
+        }
+        self.out.write_all(&mp3).and_then(|_| self.out.flush()).map_err(write_error)
+    }
+}
+
+// Ogg Vorbis at an average bitrate
+pub struct OggWriter {
+    encoder: vorbis_rs::VorbisEncoder<BufWriter<File>>,
+}
+
+impl OggWriter {
+    pub fn create(path: &str, bitrate: u32, tags: &Tags) -> Result<Self, String> {
+        let average_bitrate = NonZeroU32::new(bitrate * 1000).ok_or("Bitrate must be more than 0")?;
+        let mut builder = vorbis_rs::VorbisEncoderBuilder::new(
+            NonZeroU32::new(TAP_RATE).expect("sample rate is non-zero"),
+            NonZeroU8::new(CHANNELS as u8).expect("channel count is non-zero"),
+            create_file(path)?,
+        )
+        .map_err(|e| format!("Vorbis encoder: {}", e))?;
+        builder.bitrate_management_strategy(vorbis_rs::VorbisBitrateManagementStrategy::Abr { average_bitrate });
+        for (name, value) in tags.comments() {
+            builder.comment_tag(name, value).map_err(|e| format!("Vorbis encoder: {}", e))?;
+        }
+        let encoder = builder.build().map_err(|e| format!("Vorbis encoder: {}", e))?;
+        Ok(Self { encoder })
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        // Vorbis takes one slice per channel
+        let left: Vec<f32> = samples.iter().step_by(2).copied().collect();
+        let right: Vec<f32> = samples.iter().skip(1).step_by(2).copied().collect();
+        self.encoder.encode_audio_block([left, right]).map_err(|e| format!("Vorbis encoding failed: {}", e))
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        let mut out = self.encoder.finish().map_err(|e| format!("Vorbis encoding failed: {}", e))?;
+        out.flush().map_err(write_error)
+    }
+}
+
+// Opus in an Ogg container (.opus). The 44.1 kHz input is resampled to 48 kHz first.
+pub struct OpusWriter {
+    packets: ogg::PacketWriter<'static, BufWriter<File>>,
+    encoder: opus::Encoder,
+    serial: u32,
+    pre_skip: u64,
+    // Input frames resampled so far (at 48 kHz), and the ones not yet encoded
+    resampled_frames: u64,
+    pending: Vec<f32>,
+    // Linear resampler state: previous input frame and position of the next output frame after it
+    previous: [f32; 2],
+    position: f64,
+    // Held back one packet so the last one can be marked as the end of the stream
+    held: Option<Vec<u8>>,
+    encoded_frames: u64,
+}
+
+impl OpusWriter {
+    pub fn create(path: &str, bitrate: u32, tags: &Tags) -> Result<Self, String> {
+        let mut encoder = opus::Encoder::new(OPUS_RATE, opus::Channels::Stereo, opus::Application::Audio)
+            .map_err(|e| format!("Opus encoder: {}", e))?;
+        encoder.set_bitrate(opus::Bitrate::Bits(bitrate as i32 * 1000)).map_err(|e| format!("Opus encoder: {}", e))?;
+        let pre_skip = encoder.get_lookahead().map_err(|e| format!("Opus encoder: {}", e))?.max(0) as u64;
+
+        let mut writer = Self {
+            packets: ogg::PacketWriter::new(create_file(path)?),
+            encoder,
+            serial: rand::random(),
+            pre_skip,
+            resampled_frames: 0,
+            pending: Vec::with_capacity(OPUS_FRAME * 2 * 2),
+            previous: [0.0; 2],
+            position: 0.0,
+            held: None,
+            encoded_frames: 0,
+        };
+        writer.write_headers(tags).map_err(write_error)?;
+        Ok(writer)
+    }
+
+    // OpusHead and OpusTags, each on its own page (RFC 7845)
+    fn write_headers(&mut self, tags: &Tags) -> std::io::Result<()> {
+        let mut head = b"OpusHead".to_vec();
+        head.push(1); // version
+        head.push(CHANNELS as u8);
+        head.extend_from_slice(&(self.pre_skip as u16).to_le_bytes());
+        head.extend_from_slice(&TAP_RATE.to_le_bytes()); // original input rate, informational
+        head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+        head.push(0); // mapping family: mono/stereo
+        self.packets.write_packet(head, self.serial, ogg::PacketWriteEndInfo::EndPage, 0)?;
+
+        let vendor = b"Soundscapes";
+        let comments = tags.comments();
+        let mut packet = b"OpusTags".to_vec();
+        packet.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        packet.extend_from_slice(vendor);
+        packet.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+        for (name, value) in comments {
+            let comment = format!("{}={}", name, value);
+            packet.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            packet.extend_from_slice(comment.as_bytes());
+        }
+        self.packets.write_packet(packet, self.serial, ogg::PacketWriteEndInfo::EndPage, 0)
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        let step = TAP_RATE as f64 / OPUS_RATE as f64;
+        for frame in samples.chunks_exact(2) {
+            let current = [frame[0], frame[1]];
+            while self.position <= 1.0 {
+                let t = self.position as f32;
+                self.pending.push(self.previous[0] + (current[0] - self.previous[0]) * t);
+                self.pending.push(self.previous[1] + (current[1] - self.previous[1]) * t);
+                self.resampled_frames += 1;
+                self.position += step;
+            }
+            self.position -= 1.0;
+            self.previous = current;
+        }
+        while self.pending.len() >= OPUS_FRAME * 2 {
+            let frame: Vec<f32> = self.pending.drain(..OPUS_FRAME * 2).collect();
+            self.encode_frame(&frame)?;
+        }
+        Ok(())
+    }
+
+    fn encode_frame(&mut self, frame: &[f32]) -> Result<(), String> {
+        let mut packet = vec![0u8; OPUS_MAX_PACKET];
+        let size = self.encoder.encode_float(frame, &mut packet).map_err(|e| format!("Opus encoding failed: {}", e))?;
+        packet.truncate(size);
+        if let Some(previous) = self.held.replace(packet) {
+            self.encoded_frames += OPUS_FRAME as u64;
+            let granule = self.pre_skip + self.encoded_frames;
+            self.packets.write_packet(previous, self.serial, ogg::PacketWriteEndInfo::NormalPacket, granule)
+                .map_err(write_error)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        // Pad the last frame with silence; the final granule position trims the padding on playback
+        if !self.pending.is_empty() {
+            let mut frame = std::mem::take(&mut self.pending);
+            frame.resize(OPUS_FRAME * 2, 0.0);
+            self.encode_frame(&frame)?;
+        }
+        if let Some(last) = self.held.take() {
+            let granule = self.pre_skip + self.resampled_frames;
+            self.packets.write_packet(last, self.serial, ogg::PacketWriteEndInfo::EndStream, granule)
+                .map_err(write_error)?;
+        }
+        self.packets.into_inner().flush().map_err(write_error)
+    }
+}
@@ -0,0 +1,87 @@
+// Errors returned by commands. What the engine reports while it runs (ErrorReporter, app://error)
+// lives in soundscapes-core and is re-exported here.
+use serde::Serialize;
+use std::path::Path;
+
+pub use soundscapes_core::errors::{ErrorCode, ErrorEvent, ErrorReporter};
+
+// Error returned by commands, serialized as {"kind": "not_found", "message": "...", ...} so the
+// frontend can choose a message or retry by kind and fall back to `message` for the rest
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppError {
+    NotFound { message: String },
+    // A file that exists but isn't valid audio / JSON
+    DecodeError { path: String, message: String },
+    IoError { path: String, message: String },
+    InvalidArgument { message: String },
+    // Not possible in the current state, e.g. already recording
+    Conflict { message: String },
+    // A feature that's turned off or a device that isn't there
+    Unavailable { message: String },
+    Other { message: String },
+}
+
+impl AppError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound { message: message.into() }
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::InvalidArgument { message: message.into() }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict { message: message.into() }
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::Unavailable { message: message.into() }
+    }
+
+    pub fn io(path: impl AsRef<Path>, message: impl Into<String>) -> Self {
+        Self::IoError { path: path.as_ref().display().to_string(), message: message.into() }
+    }
+
+    pub fn decode(path: impl AsRef<Path>, message: impl Into<String>) -> Self {
+        Self::DecodeError { path: path.as_ref().display().to_string(), message: message.into() }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotFound { message }
+            | Self::DecodeError { message, .. }
+            | Self::IoError { message, .. }
+            | Self::InvalidArgument { message }
+            | Self::Conflict { message }
+            | Self::Unavailable { message }
+            | Self::Other { message } => message,
+        }
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+// Helpers still return String errors; those arrive as Other
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::Other { message }
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        Self::Other { message: message.to_string() }
+    }
+}
+
+// Lets String-returning code (remote API, IPC, tray) call commands with `?`
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
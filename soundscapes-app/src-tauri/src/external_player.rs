@@ -0,0 +1,408 @@
+// External-player mode - the music bus drives Spotify or MPD instead of local files, while ambient
+// and soundboard keep playing here. Soundboard ducking is applied through the player's own volume.
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+use crate::PlaybackState;
+
+// Spotify may rotate the refresh token; the frontend stores the new one in settings
+const SPOTIFY_TOKEN_EVENT: &str = "external-player://spotify-token";
+const SPOTIFY_API: &str = "https://api.spotify.com/v1/me/player";
+const SPOTIFY_TOKEN_URL: &str = "https://accounts.spotify.com/api/token";
+const TICK: Duration = Duration::from_millis(100);
+const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+// Volume changes are rate limited so a duck fade doesn't flood the player's API
+const VOLUME_INTERVAL: Duration = Duration::from_millis(250);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ExternalPlayerConfig {
+    pub enabled: bool,
+    pub player: String, // "mpd" or "spotify"
+    pub mpd_host: String,
+    pub mpd_port: u16,
+    pub mpd_password: String,
+    pub spotify_client_id: String,
+    pub spotify_refresh_token: String,
+}
+
+impl Default for ExternalPlayerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            player: "mpd".to_string(),
+            mpd_host: "127.0.0.1".to_string(),
+            mpd_port: 6600,
+            mpd_password: String::new(),
+            spotify_client_id: String::new(),
+            spotify_refresh_token: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ExternalPlayerStatus {
+    pub connected: bool,
+    pub is_playing: bool,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub volume: Option<u8>, // 0-100, as last sent to the player
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Transport {
+    Play,
+    Pause,
+    Toggle,
+    Stop,
+    Next,
+    Previous,
+}
+
+// Running external player; dropping it closes the command channel and stops the thread
+pub struct ExternalPlayer {
+    config: ExternalPlayerConfig,
+    // Latest Spotify refresh token, which may have been rotated since start
+    refresh_token: Arc<Mutex<String>>,
+    commands: Sender<Transport>,
+    status: Arc<Mutex<ExternalPlayerStatus>>,
+}
+
+impl ExternalPlayer {
+    pub fn start(app: tauri::AppHandle, config: ExternalPlayerConfig, playback_state: Arc<Mutex<PlaybackState>>) -> Result<Self, String> {
+        match config.player.as_str() {
+            "mpd" if config.mpd_host.trim().is_empty() => return Err("MPD host is required".to_string()),
+            "mpd" => {}
+            "spotify" if config.spotify_client_id.trim().is_empty() || config.spotify_refresh_token.trim().is_empty() => {
+                return Err("Spotify needs a client id and refresh token".to_string());
+            }
+            "spotify" => {}
+            other => return Err(format!("Unknown external player: {}", other)),
+        }
+
+        let (commands, command_rx) = channel::<Transport>();
+        let status = Arc::new(Mutex::new(ExternalPlayerStatus::default()));
+        let status_clone = status.clone();
+        let refresh_token = Arc::new(Mutex::new(config.spotify_refresh_token.clone()));
+        let refresh_token_clone = refresh_token.clone();
+        let thread_config = config.clone();
+
+        thread::spawn(move || {
+            let config = thread_config;
+            let mut backend: Option<Backend> = None;
+            let mut retry_at = Instant::now();
+            let mut last_status = Instant::now() - STATUS_INTERVAL;
+            let mut last_volume_update = Instant::now() - VOLUME_INTERVAL;
+            let mut sent_volume: Option<u8> = None;
+
+            loop {
+                let command = match command_rx.recv_timeout(TICK) {
+                    Ok(command) => Some(command),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+
+                // Back off after a failure; commands sent meanwhile are dropped
+                if Instant::now() < retry_at {
+                    continue;
+                }
+                if backend.is_none() {
+                    match Backend::connect(&app, &config, &refresh_token_clone) {
+                        Ok(connected) => {
+                            backend = Some(connected);
+                            sent_volume = None;
+                            last_status = Instant::now() - STATUS_INTERVAL;
+                        }
+                        Err(e) => {
+                            *status_clone.lock() = ExternalPlayerStatus { error: Some(e), ..Default::default() };
+                            retry_at = Instant::now() + RECONNECT_INTERVAL;
+                            continue;
+                        }
+                    }
+                }
+                let Some(player) = backend.as_mut() else { continue };
+
+                let mut result = Ok(());
+                if let Some(command) = command {
+                    let is_playing = status_clone.lock().is_playing;
+                    result = player.transport(command, is_playing);
+                    // Show the new play state right away rather than on the next poll
+                    last_status = Instant::now() - STATUS_INTERVAL;
+                }
+
+                // Follow the music bus volume, reduced by however far the soundboard is ducking it
+                let volume = {
+                    let state = playback_state.lock();
+                    (state.music_volume * (1.0 - state.duck_level) * 100.0).round().clamp(0.0, 100.0) as u8
+                };
+                if result.is_ok() && sent_volume != Some(volume) && last_volume_update.elapsed() >= VOLUME_INTERVAL {
+                    last_volume_update = Instant::now();
+                    result = player.set_volume(volume);
+                    if result.is_ok() {
+                        sent_volume = Some(volume);
+                    }
+                }
+
+                if result.is_ok() && last_status.elapsed() >= STATUS_INTERVAL {
+                    last_status = Instant::now();
+                    match player.status() {
+                        Ok(mut status) => {
+                            status.volume = sent_volume;
+                            *status_clone.lock() = status;
+                        }
+                        Err(e) => result = Err(e),
+                    }
+                }
+
+                if let Err(e) = result {
+                    log::warn!("External player: {}", e);
+                    *status_clone.lock() = ExternalPlayerStatus { error: Some(e), ..Default::default() };
+                    // Spotify's API is stateless (and keeps its access token), but the MPD socket is probably gone
+                    if let Some(Backend::Mpd(_)) = backend {
+                        backend = None;
+                    }
+                    retry_at = Instant::now() + RECONNECT_INTERVAL;
+                }
+            }
+        });
+
+        Ok(Self { config, refresh_token, commands, status })
+    }
+
+    // Saving a rotated Spotify token updates the settings; that alone shouldn't restart the player
+    pub fn is_running_with(&self, config: &ExternalPlayerConfig) -> bool {
+        let current = ExternalPlayerConfig { spotify_refresh_token: self.refresh_token.lock().clone(), ..self.config.clone() };
+        &current == config
+    }
+
+    pub fn transport(&self, command: Transport) {
+        let _ = self.commands.send(command);
+    }
+
+    pub fn status(&self) -> ExternalPlayerStatus {
+        self.status.lock().clone()
+    }
+}
+
+enum Backend {
+    Mpd(MpdConnection),
+    Spotify(SpotifyClient),
+}
+
+impl Backend {
+    fn connect(app: &tauri::AppHandle, config: &ExternalPlayerConfig, refresh_token: &Arc<Mutex<String>>) -> Result<Self, String> {
+        if config.player == "spotify" {
+            let mut client = SpotifyClient {
+                app: app.clone(),
+                client_id: config.spotify_client_id.clone(),
+                refresh_token: refresh_token.clone(),
+                access_token: String::new(),
+                expires_at: Instant::now(),
+            };
+            // Sign in up front so a bad token shows as a connection error
+            client.refresh()?;
+            Ok(Backend::Spotify(client))
+        } else {
+            MpdConnection::connect(&config.mpd_host, config.mpd_port, &config.mpd_password).map(Backend::Mpd)
+        }
+    }
+
+    fn transport(&mut self, command: Transport, is_playing: bool) -> Result<(), String> {
+        let command = match command {
+            Transport::Toggle if is_playing => Transport::Pause,
+            Transport::Toggle => Transport::Play,
+            other => other,
+        };
+        match self {
+            Backend::Mpd(mpd) => {
+                let line = match command {
+                    Transport::Play | Transport::Toggle => "play",
+                    Transport::Pause => "pause 1",
+                    Transport::Stop => "stop",
+                    Transport::Next => "next",
+                    Transport::Previous => "previous",
+                };
+                mpd.command(line).map(|_| ())
+            }
+            Backend::Spotify(spotify) => {
+                // Spotify has no stop, so stopping just pauses
+                let (method, path) = match command {
+                    Transport::Play | Transport::Toggle => ("PUT", "/play"),
+                    Transport::Pause | Transport::Stop => ("PUT", "/pause"),
+                    Transport::Next => ("POST", "/next"),
+                    Transport::Previous => ("POST", "/previous"),
+                };
+                spotify.request(method, path).map(|_| ())
+            }
+        }
+    }
+
+    fn set_volume(&mut self, volume: u8) -> Result<(), String> {
+        match self {
+            Backend::Mpd(mpd) => mpd.command(&format!("setvol {}", volume)).map(|_| ()),
+            Backend::Spotify(spotify) => spotify.request("PUT", &format!("/volume?volume_percent={}", volume)).map(|_| ()),
+        }
+    }
+
+    fn status(&mut self) -> Result<ExternalPlayerStatus, String> {
+        match self {
+            Backend::Mpd(mpd) => {
+                let status = mpd.command("status")?;
+                let song = mpd.command("currentsong")?;
+                let field = |lines: &[(String, String)], key: &str| {
+                    lines.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+                };
+                Ok(ExternalPlayerStatus {
+                    connected: true,
+                    is_playing: field(&status, "state").as_deref() == Some("play"),
+                    // Untagged files only have a path
+                    title: field(&song, "Title").or_else(|| field(&song, "file")),
+                    artist: field(&song, "Artist"),
+                    ..Default::default()
+                })
+            }
+            Backend::Spotify(spotify) => {
+                // No body means there's no active Spotify device
+                let player = spotify.request("GET", "")?;
+                let item = &player["item"];
+                let artists: Vec<&str> = item["artists"].as_array()
+                    .map(|a| a.iter().filter_map(|artist| artist["name"].as_str()).collect())
+                    .unwrap_or_default();
+                Ok(ExternalPlayerStatus {
+                    connected: true,
+                    is_playing: player["is_playing"].as_bool().unwrap_or(false),
+                    title: item["name"].as_str().map(|s| s.to_string()),
+                    artist: if artists.is_empty() { None } else { Some(artists.join(", ")) },
+                    ..Default::default()
+                })
+            }
+        }
+    }
+}
+
+// MPD text protocol: one command per line, answered by "key: value" lines and then OK or ACK
+struct MpdConnection {
+    reader: BufReader<TcpStream>,
+}
+
+impl MpdConnection {
+    fn connect(host: &str, port: u16, password: &str) -> Result<Self, String> {
+        let address = (host, port).to_socket_addrs()
+            .map_err(|e| format!("Invalid MPD address {}:{}: {}", host, port, e))?
+            .next()
+            .ok_or_else(|| format!("Could not resolve MPD host: {}", host))?;
+        let stream = TcpStream::connect_timeout(&address, NETWORK_TIMEOUT)
+            .map_err(|e| format!("Failed to connect to MPD at {}:{}: {}", host, port, e))?;
+        let _ = stream.set_read_timeout(Some(NETWORK_TIMEOUT));
+        let _ = stream.set_write_timeout(Some(NETWORK_TIMEOUT));
+
+        let mut connection = Self { reader: BufReader::new(stream) };
+        let greeting = connection.read_line()?;
+        if !greeting.starts_with("OK MPD") {
+            return Err(format!("Not an MPD server: {}", greeting));
+        }
+        if !password.is_empty() {
+            connection.command(&format!("password {}", quote(password)))?;
+        }
+        Ok(connection)
+    }
+
+    fn command(&mut self, command: &str) -> Result<Vec<(String, String)>, String> {
+        self.reader.get_mut().write_all(format!("{}\n", command).as_bytes())
+            .map_err(|e| format!("Failed to send to MPD: {}", e))?;
+        let mut fields = Vec::new();
+        loop {
+            let line = self.read_line()?;
+            if line == "OK" {
+                return Ok(fields);
+            }
+            if let Some(error) = line.strip_prefix("ACK ") {
+                return Err(format!("MPD: {}", error));
+            }
+            if let Some((key, value)) = line.split_once(": ") {
+                fields.push((key.to_string(), value.to_string()));
+            }
+        }
+    }
+
+    fn read_line(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Err("MPD closed the connection".to_string()),
+            Ok(_) => Ok(line.trim_end().to_string()),
+            Err(e) => Err(format!("Failed to read from MPD: {}", e)),
+        }
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Spotify Web API; playback control needs a Premium account and an active device
+struct SpotifyClient {
+    app: tauri::AppHandle,
+    client_id: String,
+    refresh_token: Arc<Mutex<String>>,
+    access_token: String,
+    expires_at: Instant,
+}
+
+impl SpotifyClient {
+    // Refresh tokens from the PKCE flow don't need the client secret
+    fn refresh(&mut self) -> Result<(), String> {
+        let refresh_token = self.refresh_token.lock().clone();
+        let response: Value = ureq::post(SPOTIFY_TOKEN_URL)
+            .timeout(NETWORK_TIMEOUT)
+            .send_form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", &refresh_token),
+                ("client_id", &self.client_id),
+            ])
+            .map_err(|e| format!("Spotify sign-in failed: {}", e))?
+            .into_json()
+            .map_err(|e| format!("Invalid Spotify token response: {}", e))?;
+
+        self.access_token = response["access_token"].as_str()
+            .ok_or("Spotify did not return an access token")?
+            .to_string();
+        // Refresh a minute early so requests never race the expiry
+        let expires_in = response["expires_in"].as_u64().unwrap_or(3600).saturating_sub(60);
+        self.expires_at = Instant::now() + Duration::from_secs(expires_in);
+        if let Some(token) = response["refresh_token"].as_str() {
+            if token != refresh_token {
+                *self.refresh_token.lock() = token.to_string();
+                let _ = self.app.emit(SPOTIFY_TOKEN_EVENT, token);
+            }
+        }
+        Ok(())
+    }
+
+    fn request(&mut self, method: &str, path: &str) -> Result<Value, String> {
+        if Instant::now() >= self.expires_at {
+            self.refresh()?;
+        }
+        let request = ureq::request(method, &format!("{}{}", SPOTIFY_API, path))
+            .timeout(NETWORK_TIMEOUT)
+            .set("Authorization", &format!("Bearer {}", self.access_token));
+        let response = if method == "GET" { request.call() } else { request.send_string("") };
+        match response {
+            Ok(response) if response.status() == 204 => Ok(Value::Null),
+            Ok(response) => Ok(response.into_json().unwrap_or(Value::Null)),
+            Err(ureq::Error::Status(404, _)) => Err("No active Spotify device - start playback in Spotify first".to_string()),
+            Err(ureq::Error::Status(403, _)) => Err("Spotify playback control requires Premium".to_string()),
+            Err(e) => Err(format!("Spotify request failed: {}", e)),
+        }
+    }
+}
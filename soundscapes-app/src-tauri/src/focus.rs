@@ -0,0 +1,199 @@
+// Background audio - what happens to the sound while the app is in the background. Some people keep
+// listening with every window minimized, others want it to go quiet or stop. The backend follows the
+// window events, so every window counts the same, and undoes the policy as soon as one comes back.
+// Ducking and silencing go through a gate on every source like the panic mute, so recordings and
+// the live stream carry on untouched; pausing also pauses the music so it picks up where it was.
+use parking_lot::Mutex;
+use rodio::Source;
+use serde::{Deserialize, Serialize};
+use soundscapes_core::output::{BoxedSource, OutputBackend};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::AudioController;
+
+const FADE_SECS: f32 = 0.5;
+// -12 dB
+const DUCK_GAIN: f32 = 0.25;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundPolicy {
+    #[default]
+    KeepPlaying,
+    Duck,
+    Pause,
+}
+
+// When the app counts as in the background
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundTrigger {
+    // Every window is minimized or hidden
+    #[default]
+    Minimized,
+    // No window has the focus
+    Unfocused,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct BackgroundAudioConfig {
+    pub policy: BackgroundPolicy,
+    pub trigger: BackgroundTrigger,
+}
+
+pub struct Focus {
+    config: Mutex<BackgroundAudioConfig>,
+    background: AtomicBool,
+    // Gain the gate moves to, as f32 bits
+    gain: AtomicU32,
+    // The music was playing when the policy paused it, so it resumes on the way back
+    paused_music: AtomicBool,
+}
+
+impl Default for Focus {
+    fn default() -> Self {
+        Self {
+            config: Mutex::new(BackgroundAudioConfig::default()),
+            background: AtomicBool::new(false),
+            gain: AtomicU32::new(1.0f32.to_bits()),
+            paused_music: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Focus {
+    fn gain(&self) -> f32 {
+        f32::from_bits(self.gain.load(Ordering::Relaxed))
+    }
+}
+
+pub fn configure(app: &tauri::AppHandle, config: BackgroundAudioConfig) {
+    let controller = app.state::<Arc<AudioController>>();
+    let changed = std::mem::replace(&mut *controller.focus.config.lock(), config) != config;
+    if changed {
+        // Undo whatever the old policy did, then apply the new one if the app is in the background
+        controller.focus.background.store(false, Ordering::Relaxed);
+        leave_background(app, &controller);
+        update(app, None);
+    }
+}
+
+// Called on window events; `closing` is a window being destroyed, which no longer counts
+pub fn update(app: &tauri::AppHandle, closing: Option<&str>) {
+    let controller = app.state::<Arc<AudioController>>();
+    let BackgroundAudioConfig { policy, trigger } = *controller.focus.config.lock();
+    let windows: Vec<_> = app.webview_windows().into_values().filter(|w| Some(w.label()) != closing).collect();
+    let background = policy != BackgroundPolicy::KeepPlaying
+        && !windows.is_empty()
+        && match trigger {
+            BackgroundTrigger::Minimized => windows
+                .iter()
+                .all(|w| w.is_minimized().unwrap_or(false) || !w.is_visible().unwrap_or(true)),
+            BackgroundTrigger::Unfocused => !windows.iter().any(|w| w.is_focused().unwrap_or(false)),
+        };
+    if controller.focus.background.swap(background, Ordering::Relaxed) == background {
+        return;
+    }
+    if background {
+        log::info!("In the background: {:?}", policy);
+        let gain = if policy == BackgroundPolicy::Duck { DUCK_GAIN } else { 0.0 };
+        controller.focus.gain.store(gain.to_bits(), Ordering::Relaxed);
+        if policy == BackgroundPolicy::Pause && controller.progress.lock().is_playing {
+            controller.focus.paused_music.store(true, Ordering::Relaxed);
+            let _ = crate::pause_music(app.state());
+        }
+    } else {
+        log::info!("Back in the foreground");
+        leave_background(app, &controller);
+    }
+}
+
+fn leave_background(app: &tauri::AppHandle, controller: &AudioController) {
+    controller.focus.gain.store(1.0f32.to_bits(), Ordering::Relaxed);
+    // Only if nothing else paused or stopped it since
+    if controller.focus.paused_music.swap(false, Ordering::Relaxed) && controller.current_track.lock().is_some() {
+        let _ = crate::resume_music(app.state());
+    }
+}
+
+// Output backend that plays every source through the background gate
+pub struct FocusOutput {
+    inner: Box<dyn OutputBackend>,
+    focus: Arc<Focus>,
+}
+
+impl FocusOutput {
+    pub fn new(inner: Box<dyn OutputBackend>, focus: Arc<Focus>) -> Self {
+        Self { inner, focus }
+    }
+}
+
+impl OutputBackend for FocusOutput {
+    fn play(&self, source: BoxedSource) -> Result<(), String> {
+        // A sound started in the background starts at the background gain
+        let gain = self.focus.gain();
+        self.inner.play(Box::new(Gate { inner: source, focus: self.focus.clone(), gain, channel: 0 }))
+    }
+
+    fn set_device(&self, device: Option<&str>) -> Result<(), String> {
+        self.inner.set_device(device)
+    }
+
+    fn open_device(&self, device: Option<&str>) -> Result<Box<dyn OutputBackend>, String> {
+        Ok(Box::new(FocusOutput::new(self.inner.open_device(device)?, self.focus.clone())))
+    }
+
+    fn plays_in_real_time(&self) -> bool {
+        self.inner.plays_in_real_time()
+    }
+}
+
+struct Gate {
+    inner: BoxedSource,
+    focus: Arc<Focus>,
+    gain: f32,
+    channel: u16,
+}
+
+impl Iterator for Gate {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        // Step the gain once per frame, so every channel gets the same one
+        if self.channel == 0 {
+            let target = self.focus.gain();
+            if self.gain != target {
+                let step = 1.0 / (FADE_SECS * self.inner.sample_rate().max(1) as f32);
+                self.gain = if target > self.gain { (self.gain + step).min(target) } else { (self.gain - step).max(target) };
+            }
+        }
+        let sample = self.inner.next()?;
+        self.channel += 1;
+        if self.channel >= self.inner.channels().max(1) {
+            self.channel = 0;
+        }
+        Some(sample * self.gain)
+    }
+}
+
+impl Source for Gate {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
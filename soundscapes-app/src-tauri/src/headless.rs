@@ -0,0 +1,173 @@
+// Headless daemon mode - `soundscapes --headless` runs the engine, scheduler and remote API with
+// no windows, e.g. on a Raspberry Pi that is controlled from a phone. The startup work the main
+// window normally does (pointing the audio thread at the presets, loading the music library,
+// applying the saved settings and starting the control servers) happens here instead.
+use std::sync::Arc;
+use tauri::{Listener, Manager};
+
+use crate::remote::{self, RemoteAction};
+use crate::settings_watch::{self, SettingsChanged};
+use crate::{AppSettings, AudioController, PlaylistTrack};
+
+const USAGE: &str = "Usage: soundscapes --headless [--preset <id or name>] [--schedule <id or name>]
+
+Runs the audio engine, scheduler and remote API without opening a window.
+The remote API is always started, with the port and token from the saved settings.";
+
+#[derive(Default)]
+pub struct HeadlessOptions {
+    // Applied once everything has started
+    pub preset: Option<String>,
+    pub schedule: Option<String>,
+}
+
+pub fn parse_args(args: &[String]) -> Result<HeadlessOptions, String> {
+    let mut options = HeadlessOptions::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("Missing value for {}\n\n{}", flag, USAGE));
+        match flag.as_str() {
+            "--preset" => options.preset = Some(value()?),
+            "--schedule" => options.schedule = Some(value()?),
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            other => return Err(format!("Unknown option: {}\n\n{}", other, USAGE)),
+        }
+    }
+    Ok(options)
+}
+
+pub fn start(app: &tauri::AppHandle, options: &HeadlessOptions) -> Result<(), String> {
+    let controller = app.state::<Arc<AudioController>>();
+    controller.set_presets_dir(crate::get_presets_dir(app)?);
+    controller.set_app_handle(app.clone());
+
+    let settings = crate::get_settings()?;
+    apply_settings(app, &settings)?;
+    load_library(app, &settings.music_folder_path)?;
+    start_servers(app, &settings)?;
+
+    // There's no window to pick up edits to settings.json, so do its part here
+    let handle = app.clone();
+    app.listen(settings_watch::CHANGED_EVENT, move |event| {
+        if let Ok(change) = serde_json::from_str::<SettingsChanged>(event.payload()) {
+            if let Err(e) = reload(&handle, &change) {
+                log::warn!("Headless: failed to apply changed settings: {}", e);
+            }
+        }
+    });
+
+    if let Some(preset) = &options.preset {
+        remote::apply_action(app, RemoteAction::ApplyPreset { id: preset.clone() })?;
+    }
+    if let Some(schedule) = &options.schedule {
+        start_schedule(app, schedule)?;
+    }
+    Ok(())
+}
+
+// Without a window the remote API is the only way in, so it runs whatever the saved setting says
+fn start_servers(app: &tauri::AppHandle, settings: &AppSettings) -> Result<(), String> {
+    crate::set_remote_api(
+        app.clone(),
+        app.state(),
+        true,
+        settings.remote_api_port,
+        settings.remote_api_token.clone(),
+        settings.remote_api_allow_lan,
+    )?;
+    log::info!("Headless: remote API listening on port {}", settings.remote_api_port);
+    if !settings.remote_api_allow_lan {
+        log::warn!("Headless: the remote API only accepts connections from this machine; enable LAN access in the settings to reach it from a phone");
+    }
+    crate::set_osc(app.clone(), app.state(), settings.osc_enabled, settings.osc_port, settings.osc_feedback_host.clone(), settings.osc_feedback_port, settings.remote_api_allow_lan)?;
+    crate::set_streamdeck(app.clone(), app.state(), settings.streamdeck_enabled, settings.streamdeck_port)?;
+    Ok(())
+}
+
+// settings.json was edited while running; the settings watcher has already applied the engine ones
+fn reload(app: &tauri::AppHandle, change: &SettingsChanged) -> Result<(), String> {
+    let changed = |prefixes: &[&str]| change.changed.iter().any(|key| prefixes.iter().any(|p| key.starts_with(p)));
+    if changed(&["external_player", "webhooks", "microphone"]) {
+        apply_settings(app, &change.settings)?;
+    }
+    if changed(&["music_folder_path"]) {
+        load_library(app, &change.settings.music_folder_path)?;
+    }
+    if changed(&["remote_api_", "osc_", "streamdeck_"]) {
+        start_servers(app, &change.settings)?;
+    }
+    Ok(())
+}
+
+// The engine settings the main window sends when it loads
+fn apply_settings(app: &tauri::AppHandle, settings: &AppSettings) -> Result<(), String> {
+    crate::set_master_volume(app.state(), settings.master_volume / 100.0)?;
+    crate::set_music_volume(app.state(), settings.music_volume / 100.0)?;
+    crate::set_ambient_master_volume(app.state(), settings.ambient_volume / 100.0)?;
+    crate::set_soundboard_volume(app.state(), settings.soundboard_volume / 100.0)?;
+    crate::set_category_volumes(app.state(), settings.ambient_category_volumes.clone())?;
+    crate::set_crossfade_duration(app.state(), settings.music_crossfade_duration)?;
+    crate::set_skip_crossfade_duration(app.state(), settings.music_skip_crossfade_duration)?;
+    crate::set_preset_crossfade_duration(app.state(), settings.ambient_preset_crossfade_duration)?;
+    crate::set_duck_amount(app.state(), settings.soundboard_duck_amount)?;
+    crate::set_loudness_matching(app.state(), settings.music_loudness_matching)?;
+    crate::set_skip_on_silence(app.state(), settings.music_skip_on_silence)?;
+    crate::set_audio_tick_interval(app.state(), settings.audio_tick_ms)?;
+    crate::set_mmap_threshold(app.state(), settings.ambient_mmap_threshold_mb)?;
+    // Not being allowed real-time priority shouldn't keep the daemon from starting
+    if let Err(e) = crate::set_realtime_priority(app.state(), settings.audio_realtime_priority) {
+        log::warn!("{}", e);
+    }
+    // A missing device (e.g. unplugged headphones) leaves the daemon on the default one
+    if let Err(e) = crate::set_output_device(app.state(), settings.output_device.clone()) {
+        log::warn!("{}", e);
+    }
+    if let Err(e) = crate::set_soundboard_output_device(app.state(), settings.soundboard_output_device.clone()) {
+        log::warn!("{}", e);
+    }
+    crate::set_night_mode(app.state(), settings.night_mode)?;
+    // A daemon without a microphone still runs
+    if let Err(e) = crate::set_microphone(app.state(), settings.microphone.clone()) {
+        log::warn!("{}", e);
+    }
+    crate::set_data_sync(app.state(), settings.data_sync.clone());
+    crate::set_external_player(app.clone(), app.state(), settings.external_player.clone())?;
+    let webhooks = settings.webhooks.iter().filter(|w| !w.url.trim().is_empty()).cloned().collect();
+    crate::set_webhooks(app.clone(), app.state(), webhooks)?;
+    Ok(())
+}
+
+// Saved playlists, favorites and the track list that playlists and Auto DJ pick from
+fn load_library(app: &tauri::AppHandle, music_folder: &str) -> Result<(), String> {
+    crate::load_saved_playlists_and_favorites(app.clone(), app.state())?;
+    let tracks: Vec<PlaylistTrack> = crate::scan_music_folder(app.state(), music_folder.to_string())?
+        .into_iter()
+        .flat_map(|album| {
+            album.tracks.into_iter().map(move |track| PlaylistTrack {
+                id: track.id,
+                file: track.file,
+                title: track.title,
+                artist: track.artist,
+                album: album.name.clone(),
+                album_path: album.path.clone(),
+                mood: track.mood,
+                energy: track.energy,
+            })
+        })
+        .collect();
+    log::info!("Headless: {} tracks in {}", tracks.len(), music_folder);
+    crate::set_all_tracks(app.state(), tracks)?;
+    Ok(())
+}
+
+// Schedules may be given by id or name, like presets
+fn start_schedule(app: &tauri::AppHandle, id: &str) -> Result<(), String> {
+    let info = crate::list_schedules(app.clone())?
+        .into_iter()
+        .find(|s| s.id == id || s.name.eq_ignore_ascii_case(id))
+        .ok_or_else(|| format!("Schedule not found: {}", id))?;
+    let schedule = crate::load_schedule(app.clone(), info.id.clone())?;
+    crate::start_scheduler_playback(app.state(), schedule.items, Some(schedule.id), schedule.transition)?;
+    log::info!("Headless: started schedule {}", schedule.name);
+    Ok(())
+}
@@ -0,0 +1,123 @@
+// Undo/redo for edits to presets, playlists, soundboard sounds and ambient categories. Each edit
+// records the file's contents before and after, so undoing puts the old file back (or removes one
+// the edit created) and redoing writes the new one again. Writes go through DataSync, so undoing
+// over a file another machine has since changed is a conflict like any other save.
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::data_sync::DataSync;
+use crate::errors::AppError;
+
+const MAX_EDITS: usize = 50;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EditKind {
+    Preset,
+    Playlist,
+    Soundboard,
+    Ambient,
+}
+
+impl EditKind {
+    // data://changed kind for windows to reload
+    pub fn data_kind(self) -> &'static str {
+        match self {
+            EditKind::Preset => "presets",
+            EditKind::Playlist => "playlists",
+            EditKind::Soundboard => "soundboard",
+            EditKind::Ambient => "ambient",
+        }
+    }
+}
+
+struct Edit {
+    kind: EditKind,
+    label: String,
+    path: PathBuf,
+    // None = the file didn't exist
+    before: Option<String>,
+    after: Option<String>,
+}
+
+// What undo and redo would do next, for menus and tooltips
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct HistoryState {
+    pub undo: Option<String>,
+    pub redo: Option<String>,
+}
+
+// Result of undo()/redo()
+#[derive(Debug, Serialize, Clone)]
+pub struct UndoneEdit {
+    pub kind: EditKind,
+    pub label: String,
+    pub path: String,
+}
+
+#[derive(Default)]
+pub struct History {
+    undo: Mutex<Vec<Edit>>,
+    redo: Mutex<Vec<Edit>>,
+}
+
+impl History {
+    // Record an edit after it was written; a new edit clears the redo list
+    pub fn record(&self, kind: EditKind, label: impl Into<String>, path: &Path, before: Option<String>, after: Option<String>) {
+        if before == after {
+            return;
+        }
+        let mut undo = self.undo.lock();
+        undo.push(Edit { kind, label: label.into(), path: path.to_path_buf(), before, after });
+        if undo.len() > MAX_EDITS {
+            undo.remove(0);
+        }
+        self.redo.lock().clear();
+    }
+
+    pub fn state(&self) -> HistoryState {
+        HistoryState {
+            undo: self.undo.lock().last().map(|e| e.label.clone()),
+            redo: self.redo.lock().last().map(|e| e.label.clone()),
+        }
+    }
+
+    // Undo the latest edit; None when there is nothing to undo
+    pub fn undo(&self, sync: &DataSync) -> Result<Option<UndoneEdit>, AppError> {
+        step(&self.undo, &self.redo, sync, |edit| &edit.before)
+    }
+
+    pub fn redo(&self, sync: &DataSync) -> Result<Option<UndoneEdit>, AppError> {
+        step(&self.redo, &self.undo, sync, |edit| &edit.after)
+    }
+}
+
+// Move the latest edit from one list to the other, putting the file in the chosen state. A failed
+// write leaves the edit where it was so it can be retried.
+fn step(
+    from: &Mutex<Vec<Edit>>,
+    to: &Mutex<Vec<Edit>>,
+    sync: &DataSync,
+    content: impl Fn(&Edit) -> &Option<String>,
+) -> Result<Option<UndoneEdit>, AppError> {
+    let Some(edit) = from.lock().pop() else { return Ok(None) };
+    let result = match content(&edit) {
+        Some(content) => sync.write(&edit.path, content),
+        None if edit.path.exists() => sync.remove(&edit.path),
+        None => Ok(()),
+    };
+    if let Err(e) = result {
+        from.lock().push(edit);
+        return Err(e);
+    }
+    let undone = UndoneEdit { kind: edit.kind, label: edit.label.clone(), path: edit.path.to_string_lossy().to_string() };
+    to.lock().push(edit);
+    Ok(Some(undone))
+}
+
+// The file as it is before an edit, to pass to record()
+pub fn snapshot(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok()
+}
@@ -0,0 +1,336 @@
+// Local IPC endpoint (unix socket / named pipe) and the CLI that talks to it,
+// e.g. `soundscapes play-preset rain` or `soundscapes next` against the running app
+use interprocess::local_socket::{prelude::*, ListenerOptions, Name, Stream};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::thread;
+
+use crate::remote::{self, RemoteAction};
+
+#[cfg(windows)]
+const PIPE_NAME: &str = "soundscapes-ipc";
+
+const USAGE: &str = "Usage: soundscapes <command>
+
+Controls the running Soundscapes app.
+
+Commands:
+  play | pause | toggle | stop | next | previous
+  seek <seconds>
+  volume <master|music|ambient|soundboard> <0-100>
+  mute <bus> | unmute <bus>
+  play-preset <id or name>
+  play-playlist <id>
+  sound <id or name> | stop-sound
+  announce <text>
+  status
+
+  --render <preset> <minutes> <output>   render a preset to a file without the app running
+  --headless [--preset <p>] [--schedule <s>]   run without windows, controlled through the remote API";
+
+// One request per connection: a remote action, or a query ({"type": "status"} / {"type": "focus"})
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum IpcRequest {
+    Action(RemoteAction),
+    Query(IpcQuery),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum IpcQuery {
+    Status,
+    // A second launch asks the running instance to show its window
+    Focus,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IpcResponse {
+    ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    state: Option<serde_json::Value>,
+}
+
+// Windows uses a named pipe; elsewhere a socket file in the per-user runtime directory
+#[cfg(windows)]
+fn socket_name() -> std::io::Result<Name<'static>> {
+    use interprocess::local_socket::GenericNamespaced;
+    PIPE_NAME.to_ns_name::<GenericNamespaced>()
+}
+
+#[cfg(not(windows))]
+fn socket_name() -> std::io::Result<Name<'static>> {
+    use interprocess::local_socket::GenericFilePath;
+    socket_path().to_fs_name::<GenericFilePath>()
+}
+
+#[cfg(not(windows))]
+fn socket_path() -> std::path::PathBuf {
+    let dir = dirs::runtime_dir().unwrap_or_else(std::env::temp_dir);
+    let user = std::env::var("USER").unwrap_or_default();
+    dir.join(format!("soundscapes-{}.sock", user))
+}
+
+// Start listening for CLI requests; runs for the lifetime of the app. Owning the endpoint is what
+// makes this the single instance - if another instance already answers, it gets focus and this
+// process exits before it opens a second audio engine.
+pub fn start(app: tauri::AppHandle) {
+    let name = match socket_name() {
+        Ok(name) => name,
+        Err(e) => {
+            log::error!("Invalid IPC socket name: {}", e);
+            return;
+        }
+    };
+    // main() already checked, but two launches at once can both get this far
+    if focus_running_instance() {
+        log::info!("Soundscapes is already running; handed over to it");
+        std::process::exit(0);
+    }
+    // Nothing answered, so a socket file left behind by a crash is stale and would block binding
+    #[cfg(not(windows))]
+    let _ = std::fs::remove_file(socket_path());
+    let listener = match ListenerOptions::new().name(name).create_sync() {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Failed to start IPC endpoint: {}", e);
+            return;
+        }
+    };
+
+    thread::spawn(move || {
+        for connection in listener.incoming().filter_map(|c| c.ok()) {
+            let app = app.clone();
+            thread::spawn(move || handle_connection(&app, connection));
+        }
+    });
+}
+
+fn handle_connection(app: &tauri::AppHandle, connection: Stream) {
+    let mut reader = BufReader::new(connection);
+    let mut line = String::new();
+    if reader.read_line(&mut line).is_err() {
+        return;
+    }
+
+    let response = match serde_json::from_str::<IpcRequest>(&line) {
+        Ok(IpcRequest::Action(action)) => match remote::apply_action(app, action) {
+            Ok(()) => IpcResponse { ok: true, error: None, state: None },
+            Err(e) => IpcResponse { ok: false, error: Some(e), state: None },
+        },
+        Ok(IpcRequest::Query(IpcQuery::Status)) => match remote::get_state(app) {
+            Ok(state) => IpcResponse { ok: true, error: None, state: serde_json::to_value(&state).ok() },
+            Err(e) => IpcResponse { ok: false, error: Some(e), state: None },
+        },
+        Ok(IpcRequest::Query(IpcQuery::Focus)) => {
+            // Headless instances have no window; the new process still leaves
+            crate::tray::show_main_window(app);
+            IpcResponse { ok: true, error: None, state: None }
+        }
+        Err(e) => IpcResponse { ok: false, error: Some(format!("Invalid request: {}", e)), state: None },
+    };
+
+    let mut connection = reader.into_inner();
+    let mut data = serde_json::to_vec(&response).unwrap_or_default();
+    data.push(b'\n');
+    let _ = connection.write_all(&data);
+}
+
+// Entry point for `soundscapes <command>`; returns the process exit code
+pub fn run_cli(args: &[String]) -> i32 {
+    attach_parent_console();
+
+    // Offline renders run in this process and don't need the app to be running
+    if args.first().map(|s| s.as_str()) == Some("--render") {
+        return crate::render::run_cli(&args[1..]);
+    }
+
+    let request = match parse_args(args) {
+        Ok(request) => request,
+        Err(message) => {
+            eprintln!("{}", message);
+            return 2;
+        }
+    };
+    match send_request(&request) {
+        Ok(response) if response.ok => {
+            if let Some(state) = response.state {
+                print_status(&state);
+            }
+            0
+        }
+        Ok(response) => {
+            eprintln!("Error: {}", response.error.unwrap_or_default());
+            1
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+// On Windows and Linux a clicked soundscapes:// link starts a new process; hand the link to the
+// running instance if there is one. Returns false when no instance answered, so the app should start.
+pub fn forward_deep_link(url: &str) -> bool {
+    let action = match crate::deep_link::parse(url) {
+        Ok(action) => action,
+        Err(e) => {
+            eprintln!("{}", e);
+            return false;
+        }
+    };
+    let connection = match connect() {
+        Ok(connection) => connection,
+        Err(_) => return false,
+    };
+    match exchange(connection, &IpcRequest::Action(action)) {
+        Ok(response) if !response.ok => eprintln!("Error: {}", response.error.unwrap_or_default()),
+        Err(e) => eprintln!("{}", e),
+        Ok(_) => {}
+    }
+    true
+}
+
+// A plain second launch: bring the running instance's window forward instead of starting another
+// audio engine. Returns false when no instance answered, so the app should start.
+pub fn focus_running_instance() -> bool {
+    let Ok(connection) = connect() else { return false };
+    if let Err(e) = exchange(connection, &IpcRequest::Query(IpcQuery::Focus)) {
+        log::warn!("Failed to focus the running instance: {}", e);
+    }
+    true
+}
+
+pub fn is_running() -> bool {
+    connect().is_ok()
+}
+
+fn parse_args(args: &[String]) -> Result<IpcRequest, String> {
+    let command = args.first().map(|s| s.as_str()).unwrap_or("help");
+    let arg = |i: usize| args.get(i).cloned().ok_or_else(|| format!("Missing argument for {}\n\n{}", command, USAGE));
+
+    let action = match command {
+        "play" | "resume" => RemoteAction::Resume,
+        "pause" => RemoteAction::Pause,
+        "toggle" => RemoteAction::TogglePlayback,
+        "stop" => RemoteAction::Stop,
+        "next" => RemoteAction::Next,
+        "previous" | "prev" => RemoteAction::Previous,
+        "seek" => RemoteAction::Seek {
+            position: arg(1)?.parse().map_err(|_| "Seek position must be a number of seconds".to_string())?,
+        },
+        "volume" => RemoteAction::SetVolume {
+            bus: arg(1)?,
+            volume: arg(2)?.parse().map_err(|_| "Volume must be a number from 0 to 100".to_string())?,
+        },
+        "mute" => RemoteAction::SetMuted { bus: arg(1)?, muted: true },
+        "unmute" => RemoteAction::SetMuted { bus: arg(1)?, muted: false },
+        "play-preset" | "preset" => RemoteAction::ApplyPreset { id: args[1..].join(" ") },
+        "play-playlist" | "playlist" => RemoteAction::PlayPlaylist { id: arg(1)? },
+        "sound" => RemoteAction::PlaySoundboard { id: args[1..].join(" ") },
+        "stop-sound" => RemoteAction::StopSoundboard,
+        "announce" | "say" => RemoteAction::Announce { text: args[1..].join(" "), voice: None },
+        "status" => return Ok(IpcRequest::Query(IpcQuery::Status)),
+        "help" | "--help" | "-h" => return Err(USAGE.to_string()),
+        other => return Err(format!("Unknown command: {}\n\n{}", other, USAGE)),
+    };
+    match &action {
+        RemoteAction::ApplyPreset { id: text }
+        | RemoteAction::PlaySoundboard { id: text }
+        | RemoteAction::Announce { text, .. } if text.trim().is_empty() => {
+            Err(format!("Missing argument for {}\n\n{}", command, USAGE))
+        }
+        _ => Ok(IpcRequest::Action(action)),
+    }
+}
+
+fn connect() -> Result<Stream, String> {
+    let name = socket_name().map_err(|e| format!("Invalid IPC socket name: {}", e))?;
+    Stream::connect(name).map_err(|_| "Soundscapes is not running".to_string())
+}
+
+fn send_request(request: &IpcRequest) -> Result<IpcResponse, String> {
+    exchange(connect()?, request)
+}
+
+fn exchange(mut connection: Stream, request: &IpcRequest) -> Result<IpcResponse, String> {
+    let mut data = serde_json::to_vec(request).map_err(|e| e.to_string())?;
+    data.push(b'\n');
+    connection.write_all(&data)
+        .map_err(|e| format!("Failed to send command: {}", e))?;
+
+    let mut line = String::new();
+    BufReader::new(connection).read_line(&mut line)
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    serde_json::from_str(&line).map_err(|e| format!("Invalid response: {}", e))
+}
+
+fn print_status(value: &serde_json::Value) {
+    let Ok(state) = serde_json::from_value::<StatusView>(value.clone()) else {
+        println!("{}", value);
+        return;
+    };
+    match state.track {
+        Some(track) => println!(
+            "{}: {} - {} ({:.0}/{:.0}s)",
+            if state.progress.is_playing { "Playing" } else { "Paused" },
+            track.title, track.artist, state.progress.current_time, state.progress.duration
+        ),
+        None => println!("Nothing playing"),
+    }
+    println!(
+        "Volume: master {:.0}, music {:.0}, ambient {:.0}, soundboard {:.0}",
+        state.volumes.master, state.volumes.music, state.volumes.ambient, state.volumes.soundboard
+    );
+    println!("Preset: {}", state.current_preset_id.as_deref().unwrap_or("none"));
+    println!("Active ambients: {}", state.active_ambients.len());
+}
+
+// The parts of RemoteState the status command prints
+#[derive(Deserialize)]
+struct StatusView {
+    track: Option<StatusTrack>,
+    progress: StatusProgress,
+    volumes: StatusVolumes,
+    current_preset_id: Option<String>,
+    active_ambients: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct StatusTrack {
+    title: String,
+    artist: String,
+}
+
+#[derive(Deserialize)]
+struct StatusProgress {
+    current_time: f64,
+    duration: f64,
+    is_playing: bool,
+}
+
+#[derive(Deserialize)]
+struct StatusVolumes {
+    master: f32,
+    music: f32,
+    ambient: f32,
+    soundboard: f32,
+}
+
+// Release builds use the Windows GUI subsystem, so output only shows up after attaching to the calling console
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
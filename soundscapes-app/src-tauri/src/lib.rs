@@ -1,287 +1,240 @@
 use serde::{Deserialize, Serialize};
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 use std::collections::HashMap;
 use std::fs;
-use std::fs::File;
-use std::io::{BufReader, Cursor, Read};
-use std::path::PathBuf;
-use std::sync::mpsc::{channel, Sender};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
 use std::sync::Arc;
 use std::thread;
-use std::time::Instant;
 use parking_lot::Mutex;
 use rand::Rng;
-use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
 use rodio::cpal::traits::{DeviceTrait, HostTrait};
-use walkdir::WalkDir;
-use rustfft::{FftPlanner, num_complex::Complex};
+use errors::AppError;
+use soundscapes_core::events::EventSink;
+use soundscapes_core::library::{get_album_tracks, pick_auto_dj_track};
+use soundscapes_core::output::{self, OutputFactory};
+use soundscapes_core::spectrum::{FrequencyScale, FFT_SIZE};
+use soundscapes_core::{audio_cache, diagnostics, tts};
+
+pub use soundscapes_core::model::*;
+// The engine (AudioCommand, the shared state types and the audio thread) lives in soundscapes-core
+use soundscapes_core::engine::*;
+
+mod album_import;
+mod data_sync;
+mod deep_link;
+mod content_check;
+mod encoder;
+mod errors;
+mod external_player;
+mod focus;
+mod headless;
+mod history;
+mod ipc;
+mod library_scan;
+mod lighting;
+mod logging;
+mod media_controls;
+mod microphone;
+mod night_mode;
+mod obs;
+mod osc;
+mod panic_mute;
+mod persist;
+mod random_soundscape;
+mod recording;
+mod remote;
+mod render;
+mod replay;
+mod settings_check;
+mod settings_watch;
+mod settings_writer;
+mod state_events;
+mod streamdeck;
+mod streaming;
+mod test_tone;
+mod tray;
+mod url_import;
+mod vtt;
+mod webhooks;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct MusicTrack {
-    pub id: String,
-    pub file: String,
-    pub title: String,
-    pub artist: String,
-}
-
-// Current track info for cross-window communication
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct CurrentTrackInfo {
-    pub id: String,
-    pub title: String,
-    pub artist: String,
-    pub album: String,
-    pub file_path: String,
-}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct MusicAlbum {
-    pub name: String,
-    pub artist: String,
-    pub tracks: Vec<MusicTrack>,
-    pub path: String,
+pub struct AppSettings {
+    pub music_folder_path: String,
+    pub ambient_folder_path: String,
+    pub soundboard_folder_path: String,
+    pub presets_folder_path: String,
+    pub music_crossfade_duration: f32,
+    pub soundboard_duck_amount: f32,
+    #[serde(default = "default_visualization")]
+    pub visualization_type: String,
+    #[serde(default = "default_volume")]
+    pub master_volume: f32,
+    #[serde(default = "default_volume")]
+    pub music_volume: f32,
+    #[serde(default = "default_volume")]
+    pub ambient_volume: f32,
+    #[serde(default = "default_volume")]
+    pub soundboard_volume: f32,
+    #[serde(default = "default_skip_crossfade")]
+    pub music_skip_crossfade_duration: f32,
+    #[serde(default = "default_preset_crossfade")]
+    pub ambient_preset_crossfade_duration: f32,
+    #[serde(default = "default_true")]
+    pub music_loudness_matching: bool,
+    #[serde(default)]
+    pub music_skip_on_silence: bool,
+    #[serde(default = "default_frequency_scale")]
+    pub visualization_frequency_scale: String, // "linear", "log" or "mel"
+    #[serde(default = "default_fft_event_rate")]
+    pub visualization_event_rate: u32, // playback://fft events per second (0 = disabled)
+    #[serde(default = "default_fft_attack")]
+    pub visualization_attack: f32, // 0.0-1.0, 1.0 = bins jump straight up
+    #[serde(default = "default_fft_decay")]
+    pub visualization_decay: f32, // 0.0-1.0, 1.0 = bins drop straight down
+    #[serde(default)]
+    pub fft_triggers: Vec<FftTrigger>,
+    #[serde(default)]
+    pub remote_api_enabled: bool,
+    #[serde(default = "default_remote_api_port")]
+    pub remote_api_port: u16,
+    #[serde(default = "generate_remote_api_token")]
+    pub remote_api_token: String,
+    #[serde(default)]
+    pub remote_api_allow_lan: bool, // Remote API and OSC listen on all interfaces instead of localhost only
+    #[serde(default)]
+    pub osc_enabled: bool,
+    #[serde(default = "default_osc_port")]
+    pub osc_port: u16,
+    #[serde(default)]
+    pub osc_feedback_host: String, // Empty = don't send feedback
+    #[serde(default = "default_osc_feedback_port")]
+    pub osc_feedback_port: u16,
+    #[serde(default)]
+    pub streamdeck_enabled: bool,
+    #[serde(default = "default_streamdeck_port")]
+    pub streamdeck_port: u16,
+    #[serde(default)]
+    pub obs_enabled: bool,
+    #[serde(default = "default_obs_url")]
+    pub obs_url: String,
+    #[serde(default)]
+    pub obs_password: String,
+    #[serde(default)]
+    pub obs_scene_mappings: Vec<obs::ObsSceneMapping>,
+    #[serde(default)]
+    pub streaming: streaming::StreamingConfig,
+    #[serde(default)]
+    pub recording: recording::RecordingConfig,
+    #[serde(default)]
+    pub replay_buffer: replay::ReplayConfig,
+    #[serde(default)]
+    pub external_player: external_player::ExternalPlayerConfig,
+    #[serde(default)]
+    pub microphone: microphone::MicrophoneConfig,
+    #[serde(default)]
+    pub background_audio: focus::BackgroundAudioConfig, // What the audio does while every window is minimized
+    #[serde(default)]
+    pub webhooks: Vec<webhooks::Webhook>,
+    #[serde(default = "default_audio_tick_ms")]
+    pub audio_tick_ms: u32, // Audio thread tick for fades, ducking and ambient refills
+    #[serde(default)]
+    pub audio_realtime_priority: bool, // Ask the OS to schedule the audio thread as real-time
+    #[serde(default)]
+    pub output_device: String, // Output device name from get_output_devices; empty = the system default
+    #[serde(default)]
+    pub soundboard_output_device: String, // Device for the soundboard alone; empty = wherever the rest plays
+    #[serde(default = "default_mmap_threshold_mb")]
+    pub ambient_mmap_threshold_mb: u32, // Ambient files this large are memory-mapped (0 = never)
+    #[serde(default = "default_panic_mute_shortcut")]
+    pub panic_mute_shortcut: String, // Global shortcut that mutes everything; empty = none
+    #[serde(default)]
+    pub night_mode: bool, // Compress and cap the output so loud moments stay quiet
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    #[serde(default)]
+    pub data_sync: data_sync::SyncConfig,
+    #[serde(default)]
+    pub ambient_category_volumes: HashMap<String, CategoryVolume>, // Category folder name -> volume
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct AmbientSoundDefaults {
-    pub volume: Option<u32>,
-    pub pitch: Option<f32>,
-    pub pan: Option<i32>,
-    #[serde(rename = "lowPassFreq")]
-    pub low_pass_freq: Option<u32>,
-    #[serde(rename = "reverbType")]
-    pub reverb_type: Option<String>,
-    #[serde(rename = "algorithmicReverb")]
-    pub algorithmic_reverb: Option<u32>,
-    #[serde(rename = "repeatRangeMin")]
-    pub repeat_range_min: Option<u32>,
-    #[serde(rename = "repeatRangeMax")]
-    pub repeat_range_max: Option<u32>,
-    #[serde(rename = "pauseRangeMin")]
-    pub pause_range_min: Option<u32>,
-    #[serde(rename = "pauseRangeMax")]
-    pub pause_range_max: Option<u32>,
-    #[serde(rename = "volumeVariation")]
-    pub volume_variation: Option<u32>,
+
+fn default_volume() -> f32 {
+    50.0
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct AmbientSoundFiles {
-    pub a: String,
-    pub b: String,
+fn default_visualization() -> String {
+    "orb".to_string()
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct AmbientSoundDef {
-    pub id: String,
-    pub name: String,
-    pub files: AmbientSoundFiles,
-    pub defaults: Option<AmbientSoundDefaults>,
+fn default_skip_crossfade() -> f32 {
+    0.5
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct AmbientCategory {
-    pub name: String,
-    pub icon: Option<String>,
-    pub sounds: Vec<AmbientSoundDef>,
-    pub path: String,
+fn default_preset_crossfade() -> f32 {
+    2.0
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SoundboardSound {
-    pub id: String,
-    pub name: String,
-    pub file: String,
-    pub volume: Option<u32>,
-    pub hotkey: Option<String>,
-    pub color: Option<String>,
+fn default_true() -> bool {
+    true
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SoundboardData {
-    pub sounds: Vec<SoundboardSound>,
-    pub path: String,
+fn default_frequency_scale() -> String {
+    "linear".to_string()
 }
 
-// Soundscape preset types
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct PresetSound {
-    #[serde(rename = "categoryId")]
-    pub category_id: String,
-    #[serde(rename = "categoryPath")]
-    pub category_path: String,
-    #[serde(rename = "soundId")]
-    pub sound_id: String,
-    pub name: String,
-    #[serde(rename = "filesA")]
-    pub files_a: String,
-    #[serde(rename = "filesB")]
-    pub files_b: String,
-    pub enabled: bool,
-    pub volume: u32,
-    pub pitch: f32,
-    pub pan: i32,
-    #[serde(rename = "lowPassFreq")]
-    pub low_pass_freq: u32,
-    #[serde(rename = "algorithmicReverb")]
-    pub algorithmic_reverb: u32,
-    #[serde(rename = "repeatRangeMin")]
-    pub repeat_range_min: u32,
-    #[serde(rename = "repeatRangeMax")]
-    pub repeat_range_max: u32,
-    #[serde(rename = "pauseRangeMin")]
-    pub pause_range_min: u32,
-    #[serde(rename = "pauseRangeMax")]
-    pub pause_range_max: u32,
-    #[serde(rename = "volumeVariation")]
-    pub volume_variation: u32,
+fn default_fft_event_rate() -> u32 {
+    20
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SoundscapePreset {
-    pub id: String,
-    pub name: String,
-    pub created: String,
-    pub modified: String,
-    pub sounds: Vec<PresetSound>,
+fn default_audio_tick_ms() -> u32 {
+    DEFAULT_AUDIO_TICK_MS
 }
 
-#[derive(Debug, Serialize, Clone)]
-pub struct PresetInfo {
-    pub id: String,
-    pub name: String,
-    pub created: String,
-    pub modified: String,
-    #[serde(rename = "soundCount")]
-    pub sound_count: usize,
+fn default_mmap_threshold_mb() -> u32 {
+    DEFAULT_MMAP_THRESHOLD_MB
 }
 
-// Schedule types for the Soundscapes Scheduler
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ScheduledItem {
-    pub id: String,
-    #[serde(rename = "presetId")]
-    pub preset_id: String,
-    #[serde(rename = "presetName")]
-    pub preset_name: String,
-    #[serde(rename = "minMinutes")]
-    pub min_minutes: u32,
-    #[serde(rename = "maxMinutes")]
-    pub max_minutes: u32,
-    pub order: u32,
+fn default_remote_api_port() -> u16 {
+    7483
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SchedulePreset {
-    pub id: String,
-    pub name: String,
-    pub created: String,
-    pub modified: String,
-    pub items: Vec<ScheduledItem>,
+fn default_osc_port() -> u16 {
+    9000
 }
 
-#[derive(Debug, Serialize, Clone)]
-pub struct SchedulePresetInfo {
-    pub id: String,
-    pub name: String,
-    pub created: String,
-    pub modified: String,
-    #[serde(rename = "itemCount")]
-    pub item_count: usize,
+fn default_osc_feedback_port() -> u16 {
+    9001
 }
 
-// Music Playlist types
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct PlaylistTrack {
-    pub id: String,
-    pub file: String,
-    pub title: String,
-    pub artist: String,
-    pub album: String,
-    #[serde(rename = "albumPath")]
-    pub album_path: String,
+fn default_streamdeck_port() -> u16 {
+    7490
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct MusicPlaylist {
-    pub id: String,
-    pub name: String,
-    #[serde(rename = "isAuto")]
-    pub is_auto: bool,  // true for "All Music" and "Favorites"
-    pub tracks: Vec<PlaylistTrack>,
+fn default_obs_url() -> String {
+    "ws://127.0.0.1:4455".to_string()
 }
 
-// Playlist playback state (shared across windows)
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct PlaylistState {
-    #[serde(rename = "currentPlaylistId")]
-    pub current_playlist_id: Option<String>,
-    #[serde(rename = "currentIndex")]
-    pub current_index: i32,
-    #[serde(rename = "isShuffled")]
-    pub is_shuffled: bool,
-    #[serde(rename = "isLooping")]
-    pub is_looping: bool,
-    pub favorites: Vec<String>,  // Track IDs that are favorited
-    #[serde(rename = "interruptedIndex")]
-    pub interrupted_index: Option<i32>,  // For resuming after Play Now
-}
-
-impl Default for PlaylistState {
-    fn default() -> Self {
-        Self {
-            current_playlist_id: None,
-            current_index: 0,
-            is_shuffled: false,
-            is_looping: true, // Loop enabled by default
-            favorites: Vec::new(),
-            interrupted_index: None,
-        }
-    }
+fn default_panic_mute_shortcut() -> String {
+    "CmdOrCtrl+Alt+M".to_string()
 }
 
-// Scheduler playback state (shared across windows)
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct SchedulerState {
-    #[serde(rename = "isPlaying")]
-    pub is_playing: bool,
-    #[serde(rename = "currentItemIndex")]
-    pub current_item_index: usize,
-    #[serde(rename = "currentDuration")]
-    pub current_duration: u32, // minutes
-    #[serde(rename = "timeRemaining")]
-    pub time_remaining: i32, // seconds
-    pub items: Vec<ScheduledItem>,
-    #[serde(rename = "currentScheduleId")]
-    pub current_schedule_id: Option<String>,
+fn default_log_level() -> String {
+    "info".to_string()
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct AppSettings {
-    pub music_folder_path: String,
-    pub ambient_folder_path: String,
-    pub soundboard_folder_path: String,
-    pub presets_folder_path: String,
-    pub music_crossfade_duration: f32,
-    pub soundboard_duck_amount: f32,
-    #[serde(default = "default_visualization")]
-    pub visualization_type: String,
-    #[serde(default = "default_volume")]
-    pub master_volume: f32,
-    #[serde(default = "default_volume")]
-    pub music_volume: f32,
-    #[serde(default = "default_volume")]
-    pub ambient_volume: f32,
-    #[serde(default = "default_volume")]
-    pub soundboard_volume: f32,
+fn generate_remote_api_token() -> String {
+    let mut rng = rand::thread_rng();
+    (0..32).map(|_| format!("{:x}", rng.gen_range(0..16u8))).collect()
 }
 
-fn default_volume() -> f32 {
-    50.0
+fn default_fft_attack() -> f32 {
+    0.7
 }
 
-fn default_visualization() -> String {
-    "orb".to_string()
+fn default_fft_decay() -> f32 {
+    0.3
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -303,2946 +256,1970 @@ struct SoundboardMetadata {
     sounds: Vec<SoundboardSound>,
 }
 
-// Ambient sound settings matching the spec
-#[derive(Clone, Serialize)]
-struct AmbientSettings {
-    volume: f32,           // 0.0 - 1.0
-    pitch: f32,            // 0.5 - 2.0 (playback speed)
-    pan: f32,              // -1.0 to 1.0 (L/R)
-    low_pass_freq: f32,    // 20 - 22000 Hz (cutoff frequency)
-    reverb_type: String,   // "off", "small-room", "large-hall", "cathedral"
-    algorithmic_reverb: f32, // 0.0 - 1.0 (only used when reverb_type is "off")
-    repeat_min: u32,       // Min A/B cycles before pause
-    repeat_max: u32,       // Max A/B cycles before pause
-    pause_min: u32,        // Min pause cycles
-    pause_max: u32,        // Max pause cycles
-    volume_variation: f32, // 0.0 - 0.5 (random ± per loop)
-}
-
-impl Default for AmbientSettings {
-    fn default() -> Self {
-        Self {
-            volume: 1.0,
-            pitch: 1.0,
-            pan: 0.0,
-            low_pass_freq: 22000.0, // Effectively off (above human hearing)
-            reverb_type: "off".to_string(),
-            algorithmic_reverb: 0.0,
-            repeat_min: 1,
-            repeat_max: 1,
-            pause_min: 0,
-            pause_max: 0,
-            volume_variation: 0.0,
-        }
-    }
-}
-
-// Audio Commands sent to the audio thread
-enum AudioCommand {
-    // Music commands
-    Play { file_path: String, track_info: CurrentTrackInfo },
-    Stop,
-    Pause,
-    Resume,
-    Seek(f64), // Seek to position in seconds
-    SetVolume(f32),
-    SetMasterVolume(f32),
-    SetMuted(bool),
-    SetMasterMuted(bool),
-    SetCrossfadeDuration(f32),
-    // Soundboard commands
-    PlaySoundboard { file_path: String, volume: f32 },
-    StopSoundboard,
-    SetSoundboardVolume(f32),
-    SetSoundboardMuted(bool),
-    SetDuckAmount(f32),
-    // Ambient commands
-    PlayAmbient {
-        id: String,
-        file_a: String,
-        file_b: String,
-        settings: AmbientSettings,
-    },
-    StopAmbient(String),
-    UpdateAmbientSettings { id: String, settings: AmbientSettings },
-    StopAllAmbient, // Stop all ambient sounds
-    SetAmbientMasterVolume(f32),
-    SetAmbientMuted(bool),
-    PreloadAmbient(Vec<String>), // Preload audio files into memory cache
-    // Scheduler-specific commands with longer fade times (2000ms)
-    PlayAmbientScheduler {
-        id: String,
-        file_a: String,
-        file_b: String,
-        settings: AmbientSettings,
-    },
-    StopAmbientScheduler(String),
-    UpdateAmbientSettingsScheduler { id: String, settings: AmbientSettings },
-}
-
-// Shared state for tracking active ambient sounds (queryable from outside audio thread)
-#[derive(Clone, Serialize)]
-struct ActiveAmbientInfo {
-    id: String,
-    file_a: String,
-    file_b: String,
-    settings: AmbientSettings,
-}
+// Longest ambient preview
+const MAX_PREVIEW_SECS: f32 = 30.0;
+// Longest crossfade when an ambient sound swaps files
+const MAX_SWAP_CROSSFADE_MS: u32 = 30_000;
+// Longest fade-out a stop command can ask for
+const MAX_STOP_FADE_MS: u32 = 60_000;
+// Limits of a playlist's own playback settings
+const MAX_PLAYLIST_VOLUME_OFFSET_DB: f32 = 12.0;
+const MAX_PLAYLIST_CROSSFADE_SECS: f32 = 30.0;
 
-// Shared state for progress tracking (this is Send + Sync)
-#[derive(Clone)]
-struct AudioProgress {
-    current_time: f64,
-    duration: f64,
-    is_playing: bool,
-    is_finished: bool,
+// What stop_all silenced, so restore_last_stopped can bring it back
+struct StoppedState {
+    ambients: Vec<ActiveAmbientInfo>,
+    music: Option<StoppedMusic>,
 }
 
-// Number of FFT frequency bins to send to frontend
-const FFT_SIZE: usize = 64;
-
-// Playback state for visualization with FFT data
-#[derive(Clone)]
-struct PlaybackState {
-    music_playing: bool,
-    music_volume: f32,
-    ambient_count: u32,
-    ambient_volume: f32,
-    master_volume: f32,
-    is_muted: bool,
-    // FFT frequency data (0.0-1.0 for each bin)
-    frequencies: Vec<f32>,
-    // Ambient amplitude data (0.0-1.0 for each bin) - derived from RMS tracking
-    ambient_frequencies: Vec<f32>,
+struct StoppedMusic {
+    track: CurrentTrackInfo,
+    position: f64,
+    paused: bool,
 }
 
-impl Default for PlaybackState {
-    fn default() -> Self {
-        Self {
-            music_playing: false,
-            music_volume: 0.0,
-            ambient_count: 0,
-            ambient_volume: 0.0,
-            master_volume: 1.0,
-            is_muted: false,
-            frequencies: vec![0.0; FFT_SIZE],
-            ambient_frequencies: vec![0.0; FFT_SIZE],
-        }
+// The engine plus everything the app runs around it. Derefs to the Engine, so commands reach its
+// shared state and send() directly.
+struct AudioController {
+    engine: Engine,
+    data_sync: data_sync::DataSync,
+    history: history::History,
+    panic_mute: Arc<panic_mute::PanicMute>,
+    night_mode: Arc<night_mode::NightMode>,
+    focus: Arc<focus::Focus>,
+    settings_watch: settings_watch::SettingsWatch,
+    settings_writer: settings_writer::SettingsWriter,
+    category_volumes: Mutex<HashMap<String, CategoryVolume>>,
+    library_cache: library_scan::LibraryCache,
+    lighting: Mutex<Option<lighting::LightingSync>>,
+    remote_server: Mutex<Option<remote::RemoteServer>>,
+    osc_server: Mutex<Option<osc::OscServer>>,
+    streamdeck_server: Mutex<Option<streamdeck::StreamDeckServer>>,
+    obs_client: Mutex<Option<obs::ObsClient>>,
+    streamer: Mutex<Option<streaming::Streamer>>,
+    recorder: Mutex<Option<recording::Recorder>>,
+    replay_buffer: Mutex<Option<replay::ReplayBuffer>>,
+    external_player: Mutex<Option<external_player::ExternalPlayer>>,
+    microphone: Mutex<Option<microphone::MicrophoneInput>>,
+    webhooks: Mutex<Option<webhooks::WebhookDispatcher>>,
+    // Cancel flag of the offline render in progress, if any
+    render_job: Mutex<Option<Arc<std::sync::atomic::AtomicBool>>>,
+    last_stopped: Mutex<Option<StoppedState>>,
+}
+
+impl std::ops::Deref for AudioController {
+    type Target = Engine;
+
+    fn deref(&self) -> &Engine {
+        &self.engine
     }
 }
 
-// Lock-free circular buffer for FFT samples - avoids mutex contention that causes static
-const FFT_BUFFER_SIZE: usize = 2048;
-
-struct FftSampleBuffer {
-    buffer: [std::sync::atomic::AtomicU32; FFT_BUFFER_SIZE],
-    write_pos: std::sync::atomic::AtomicUsize,
-}
-
-impl FftSampleBuffer {
-    fn new() -> Self {
+impl AudioController {
+    // `open_output` runs on the audio thread; pass output::default_output() for the sound card.
+    // Everything plays through the panic mute gate, the background gate and the night mode compressor.
+    fn new(open_output: OutputFactory) -> Self {
+        let panic_mute = Arc::new(panic_mute::PanicMute::default());
+        let night_mode = Arc::new(night_mode::NightMode::default());
+        let focus = Arc::new(focus::Focus::default());
+        let gates = (panic_mute.clone(), night_mode.clone(), focus.clone());
+        let open_output: OutputFactory = Box::new(move || {
+            let (panic_mute, night_mode, focus) = gates;
+            let output = Box::new(night_mode::NightModeOutput::new(open_output()?, night_mode));
+            let output = Box::new(focus::FocusOutput::new(output, focus));
+            Ok(Box::new(panic_mute::GatedOutput::new(output, panic_mute)) as Box<dyn output::OutputBackend>)
+        });
+        
         Self {
-            buffer: std::array::from_fn(|_| std::sync::atomic::AtomicU32::new(0)),
-            write_pos: std::sync::atomic::AtomicUsize::new(0),
-        }
-    }
-    
-    fn push(&self, sample: f32) {
-        let pos = self.write_pos.fetch_add(1, std::sync::atomic::Ordering::Relaxed) % FFT_BUFFER_SIZE;
-        self.buffer[pos].store(sample.to_bits(), std::sync::atomic::Ordering::Relaxed);
-    }
-    
-    fn get_latest(&self, count: usize) -> Vec<f32> {
-        let write_pos = self.write_pos.load(std::sync::atomic::Ordering::Relaxed);
-        let mut result = Vec::with_capacity(count);
-        for i in 0..count {
-            let pos = (write_pos + FFT_BUFFER_SIZE - count + i) % FFT_BUFFER_SIZE;
-            let bits = self.buffer[pos].load(std::sync::atomic::Ordering::Relaxed);
-            result.push(f32::from_bits(bits));
+            engine: Engine::new(open_output),
+            data_sync: data_sync::DataSync::default(),
+            history: history::History::default(),
+            panic_mute,
+            night_mode,
+            focus,
+            settings_watch: settings_watch::SettingsWatch::default(),
+            settings_writer: settings_writer::SettingsWriter::default(),
+            category_volumes: Mutex::new(HashMap::new()),
+            library_cache: library_scan::LibraryCache::default(),
+            lighting: Mutex::new(None),
+            remote_server: Mutex::new(None),
+            osc_server: Mutex::new(None),
+            streamdeck_server: Mutex::new(None),
+            obs_client: Mutex::new(None),
+            streamer: Mutex::new(None),
+            recorder: Mutex::new(None),
+            replay_buffer: Mutex::new(None),
+            external_player: Mutex::new(None),
+            microphone: Mutex::new(None),
+            webhooks: Mutex::new(None),
+            render_job: Mutex::new(None),
+            last_stopped: Mutex::new(None),
         }
-        result
     }
     
-    fn clear(&self) {
-        self.write_pos.store(0, std::sync::atomic::Ordering::Relaxed);
-        for atom in &self.buffer {
-            atom.store(0, std::sync::atomic::Ordering::Relaxed);
-        }
+    // Engine events go to every window from here on
+    fn set_app_handle(&self, app: tauri::AppHandle) {
+        self.events.set_sink(Arc::new(WindowEvents(app)));
     }
 }
 
-// Lock-free buffer for ambient audio samples (for amplitude tracking)
-const AMBIENT_BUFFER_SIZE: usize = 2048;
+struct WindowEvents(tauri::AppHandle);
 
-struct AmbientSampleBuffer {
-    buffer: [std::sync::atomic::AtomicU32; AMBIENT_BUFFER_SIZE],
-    write_pos: std::sync::atomic::AtomicUsize,
+impl EventSink for WindowEvents {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        let _ = self.0.emit(event, payload);
+    }
 }
 
-impl AmbientSampleBuffer {
-    fn new() -> Self {
-        Self {
-            buffer: std::array::from_fn(|_| std::sync::atomic::AtomicU32::new(0)),
-            write_pos: std::sync::atomic::AtomicUsize::new(0),
-        }
-    }
-    
-    fn push(&self, sample: f32) {
-        let pos = self.write_pos.fetch_add(1, std::sync::atomic::Ordering::Relaxed) % AMBIENT_BUFFER_SIZE;
-        self.buffer[pos].store(sample.to_bits(), std::sync::atomic::Ordering::Relaxed);
-    }
+fn get_default_settings() -> AppSettings {
+    let exe_path = std::env::current_exe().ok();
+    let base_path = exe_path
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+        .map(|p| p.join("sample-content"))
+        .unwrap_or_else(|| {
+            dirs::document_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("Soundscapes")
+        });
     
-    fn get_latest(&self, count: usize) -> Vec<f32> {
-        let write_pos = self.write_pos.load(std::sync::atomic::Ordering::Relaxed);
-        let mut result = Vec::with_capacity(count);
-        for i in 0..count {
-            let pos = (write_pos + AMBIENT_BUFFER_SIZE - count + i) % AMBIENT_BUFFER_SIZE;
-            let bits = self.buffer[pos].load(std::sync::atomic::Ordering::Relaxed);
-            result.push(f32::from_bits(bits));
-        }
-        result
+    AppSettings {
+        music_folder_path: base_path.join("Music").to_string_lossy().to_string(),
+        ambient_folder_path: base_path.join("Ambient").to_string_lossy().to_string(),
+        soundboard_folder_path: base_path.join("Soundboard").to_string_lossy().to_string(),
+        presets_folder_path: base_path.join("Presets").to_string_lossy().to_string(),
+        music_crossfade_duration: 3.0,
+        soundboard_duck_amount: 0.3,
+        visualization_type: default_visualization(),
+        master_volume: default_volume(),
+        music_volume: default_volume(),
+        ambient_volume: default_volume(),
+        soundboard_volume: default_volume(),
+        music_skip_crossfade_duration: default_skip_crossfade(),
+        ambient_preset_crossfade_duration: default_preset_crossfade(),
+        music_loudness_matching: true,
+        music_skip_on_silence: false,
+        visualization_frequency_scale: default_frequency_scale(),
+        visualization_event_rate: default_fft_event_rate(),
+        visualization_attack: default_fft_attack(),
+        visualization_decay: default_fft_decay(),
+        fft_triggers: Vec::new(),
+        remote_api_enabled: false,
+        remote_api_port: default_remote_api_port(),
+        remote_api_token: generate_remote_api_token(),
+        remote_api_allow_lan: false,
+        osc_enabled: false,
+        osc_port: default_osc_port(),
+        osc_feedback_host: String::new(),
+        osc_feedback_port: default_osc_feedback_port(),
+        streamdeck_enabled: false,
+        streamdeck_port: default_streamdeck_port(),
+        obs_enabled: false,
+        obs_url: default_obs_url(),
+        obs_password: String::new(),
+        obs_scene_mappings: Vec::new(),
+        streaming: streaming::StreamingConfig::default(),
+        recording: recording::RecordingConfig::default(),
+        replay_buffer: replay::ReplayConfig::default(),
+        external_player: external_player::ExternalPlayerConfig::default(),
+        microphone: microphone::MicrophoneConfig::default(),
+        background_audio: focus::BackgroundAudioConfig::default(),
+        webhooks: Vec::new(),
+        audio_tick_ms: default_audio_tick_ms(),
+        audio_realtime_priority: false,
+        output_device: String::new(),
+        soundboard_output_device: String::new(),
+        panic_mute_shortcut: default_panic_mute_shortcut(),
+        night_mode: false,
+        ambient_mmap_threshold_mb: default_mmap_threshold_mb(),
+        log_level: default_log_level(),
+        data_sync: data_sync::SyncConfig::default(),
+        ambient_category_volumes: HashMap::new(),
     }
 }
 
-// Source wrapper that copies samples for ambient amplitude analysis (lock-free)
-struct AmbientAnalyzingSource<S> {
-    inner: S,
-    sample_buffer: Arc<AmbientSampleBuffer>,
-}
-
-impl<S> AmbientAnalyzingSource<S> {
-    fn new(inner: S, sample_buffer: Arc<AmbientSampleBuffer>) -> Self {
-        Self { inner, sample_buffer }
-    }
+fn get_settings_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("Soundscapes")
+        .join("settings.json")
 }
 
-impl<S> Iterator for AmbientAnalyzingSource<S>
-where
-    S: Source<Item = f32>,
-{
-    type Item = f32;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let sample = self.inner.next()?;
-        self.sample_buffer.push(sample);
-        Some(sample)
+#[tauri::command]
+fn get_settings() -> Result<AppSettings, AppError> {
+    let settings_path = get_settings_path();
+    
+    if settings_path.exists() {
+        let content = fs::read_to_string(&settings_path)
+            .map_err(|e| AppError::io(&settings_path, format!("Failed to read settings: {}", e)))?;
+        let (settings, issues) = settings_check::parse(&content)
+            .map_err(|e| AppError::decode(&settings_path, format!("Failed to parse settings: {}", e)))?;
+        settings_check::log_fixed(&issues);
+        Ok(settings)
+    } else {
+        Ok(get_default_settings())
     }
 }
 
-impl<S> Source for AmbientAnalyzingSource<S>
-where
-    S: Source<Item = f32>,
-{
-    fn current_frame_len(&self) -> Option<usize> {
-        self.inner.current_frame_len()
-    }
-
-    fn channels(&self) -> u16 {
-        self.inner.channels()
-    }
-
-    fn sample_rate(&self) -> u32 {
-        self.inner.sample_rate()
-    }
-
-    fn total_duration(&self) -> Option<std::time::Duration> {
-        self.inner.total_duration()
+#[tauri::command]
+fn save_settings(state: tauri::State<Arc<AudioController>>, settings: AppSettings) -> Result<(), AppError> {
+    let settings_path = get_settings_path();
+    
+    if let Some(parent) = settings_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io(parent, format!("Failed to create settings directory: {}", e)))?;
     }
+    
+    // Volume changes still waiting to be written go out with this save
+    let mut settings = settings;
+    state.settings_writer.take_into(&mut settings);
+    let validation = settings_check::validate(settings);
+    settings_check::log_fixed(&validation.issues);
+    write_settings(&state, &validation.settings)
 }
 
-// Source wrapper that copies samples for FFT analysis (lock-free)
-struct AnalyzingSource<S> {
-    inner: S,
-    sample_buffer: Arc<FftSampleBuffer>,
+// Check settings without saving them; the result has out-of-range values fixed and lists what was
+// changed, plus warnings such as folders that don't exist
+#[tauri::command]
+fn validate_settings(settings: AppSettings) -> settings_check::SettingsValidation {
+    settings_check::validate(settings)
 }
 
-impl<S> AnalyzingSource<S> {
-    fn new(inner: S, sample_buffer: Arc<FftSampleBuffer>) -> Self {
-        Self { inner, sample_buffer }
-    }
+// Check every music, ambient and soundboard metadata.json in the folders from the settings. Unlike a
+// scan, which fails on the first bad file, this lists every problem it finds.
+#[tauri::command]
+fn validate_content_folders() -> Result<content_check::ContentValidation, AppError> {
+    let settings = get_settings()?;
+    let validation = content_check::validate(
+        Path::new(&settings.music_folder_path),
+        Path::new(&settings.ambient_folder_path),
+        Path::new(&settings.soundboard_folder_path),
+    );
+    log::info!("Checked {} metadata files, {} issues", validation.files, validation.issues.len());
+    Ok(validation)
 }
 
-impl<S> Iterator for AnalyzingSource<S>
-where
-    S: Source<Item = f32>,
-{
-    type Item = f32;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let sample = self.inner.next()?;
-        self.sample_buffer.push(sample);
-        Some(sample)
-    }
+// Our own writes are remembered so the settings watcher doesn't report them back
+fn write_settings(state: &AudioController, settings: &AppSettings) -> Result<(), AppError> {
+    let settings_path = get_settings_path();
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    
+    state.settings_watch.wrote(&content);
+    persist::write_atomic(&settings_path, content)
+        .map_err(|e| AppError::io(&settings_path, format!("Failed to write settings: {}", e)))
 }
 
-impl<S> Source for AnalyzingSource<S>
-where
-    S: Source<Item = f32>,
-{
-    fn current_frame_len(&self) -> Option<usize> {
-        self.inner.current_frame_len()
-    }
-
-    fn channels(&self) -> u16 {
-        self.inner.channels()
-    }
-
-    fn sample_rate(&self) -> u32 {
-        self.inner.sample_rate()
-    }
-
-    fn total_duration(&self) -> Option<std::time::Duration> {
-        self.inner.total_duration()
+// Called continuously while a volume slider is dragged, so the value is only queued here; the
+// settings writer saves it once the slider stops moving
+#[tauri::command]
+fn save_volume_setting(state: tauri::State<Arc<AudioController>>, key: String, value: f32) -> Result<(), AppError> {
+    if !settings_writer::VOLUME_KEYS.contains(&key.as_str()) {
+        return Err(AppError::invalid(format!("Unknown volume key: {}", key)));
     }
+    let value = settings_check::clamp(&key, value as f64) as f32;
+    state.settings_writer.set_volume(&key, value);
+    Ok(())
 }
 
-// Source wrapper for stereo panning (L/R balance)
-// pan: -1.0 = full left, 0.0 = center, 1.0 = full right
-struct PannedSource<S> {
-    inner: S,
-    pan: f32,
-    channels: u16,
-    current_channel: u16,
+// Album folders that haven't changed since the last scan come from the library cache
+#[tauri::command]
+fn scan_music_folder(state: tauri::State<Arc<AudioController>>, folder_path: String) -> Result<Vec<MusicAlbum>, AppError> {
+    let (albums, _) = state.library_cache.scan_music(Path::new(&folder_path), true)?;
+    Ok(albums)
 }
 
-impl<S> PannedSource<S>
-where
-    S: Source<Item = f32>,
-{
-    fn new(inner: S, pan: f32) -> Self {
-        let channels = inner.channels();
-        Self {
-            inner,
-            pan: pan.clamp(-1.0, 1.0),
-            channels,
-            current_channel: 0,
-        }
+// Write a metadata.json for a music folder that doesn't have one yet, from its files' tags, so the
+// folder shows up as an album
+#[tauri::command]
+fn create_album_metadata(folder: String) -> Result<MusicAlbum, AppError> {
+    let folder_path = PathBuf::from(&folder);
+    if !folder_path.is_dir() {
+        return Err(AppError::not_found(format!("Folder not found: {}", folder)));
     }
-}
-
-impl<S> Iterator for PannedSource<S>
-where
-    S: Source<Item = f32>,
-{
-    type Item = f32;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let sample = self.inner.next()?;
-        
-        // Only apply panning to stereo sources
-        if self.channels == 2 {
-            let channel = self.current_channel;
-            self.current_channel = (self.current_channel + 1) % self.channels;
-            
-            // Calculate gain for this channel
-            // Left channel (0): full at pan=-1, half at pan=1
-            // Right channel (1): half at pan=-1, full at pan=1
-            let gain = if channel == 0 {
-                // Left channel: 1.0 when pan <= 0, decreases to 0 as pan -> 1
-                if self.pan <= 0.0 { 1.0 } else { 1.0 - self.pan }
-            } else {
-                // Right channel: 1.0 when pan >= 0, decreases to 0 as pan -> -1
-                if self.pan >= 0.0 { 1.0 } else { 1.0 + self.pan }
-            };
-            
-            Some(sample * gain)
-        } else {
-            Some(sample)
-        }
+    let metadata_path = folder_path.join("metadata.json");
+    if metadata_path.exists() {
+        return Err(AppError::conflict("The folder already has a metadata.json"));
     }
+    let metadata = album_import::build(&folder_path).map_err(AppError::invalid)?;
+    let content = serde_json::to_string_pretty(&metadata)
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    persist::write_atomic(&metadata_path, &content)
+        .map_err(|e| AppError::io(&metadata_path, format!("Failed to write metadata: {}", e)))?;
+    log::info!("Created album metadata for {} with {} tracks", folder, metadata.tracks.len());
+    Ok(MusicAlbum {
+        name: metadata.name,
+        artist: metadata.artist,
+        tracks: metadata.tracks,
+        path: folder,
+    })
 }
 
-impl<S> Source for PannedSource<S>
-where
-    S: Source<Item = f32>,
-{
-    fn current_frame_len(&self) -> Option<usize> {
-        self.inner.current_frame_len()
-    }
-
-    fn channels(&self) -> u16 {
-        self.inner.channels()
-    }
-
-    fn sample_rate(&self) -> u32 {
-        self.inner.sample_rate()
-    }
-
-    fn total_duration(&self) -> Option<std::time::Duration> {
-        self.inner.total_duration()
-    }
+#[tauri::command]
+fn scan_ambient_folder(state: tauri::State<Arc<AudioController>>, folder_path: String) -> Result<Vec<AmbientCategory>, AppError> {
+    let (categories, _) = state.library_cache.scan_ambient(Path::new(&folder_path), true)?;
+    Ok(categories)
 }
 
-// Source wrapper for low-pass filter (simple one-pole IIR filter)
-// cutoff_freq: 20 - 22000 Hz
-struct LowPassSource<S> {
-    inner: S,
-    alpha: f32,
-    prev_samples: Vec<f32>, // One per channel
-    channels: u16,
-    current_channel: u16,
+// Rescan the music, ambient and soundboard folders from the settings. With `incremental`, only
+// folders whose metadata.json changed are read again; otherwise everything is.
+#[tauri::command]
+fn rescan(state: tauri::State<Arc<AudioController>>, incremental: bool) -> Result<library_scan::LibraryScan, AppError> {
+    let settings = get_settings()?;
+    let (albums, music_stats) = state.library_cache.scan_music(Path::new(&settings.music_folder_path), incremental)?;
+    let (categories, ambient_stats) = state.library_cache.scan_ambient(Path::new(&settings.ambient_folder_path), incremental)?;
+    log::info!(
+        "Rescanned library: {} of {} album folders and {} of {} ambient folders read",
+        music_stats.reread, music_stats.folders, ambient_stats.reread, ambient_stats.folders
+    );
+    Ok(library_scan::LibraryScan {
+        albums,
+        categories,
+        soundboard: scan_soundboard_folder(settings.soundboard_folder_path)?,
+        music_stats,
+        ambient_stats,
+    })
 }
 
-impl<S> LowPassSource<S>
-where
-    S: Source<Item = f32>,
-{
-    fn new(inner: S, cutoff_freq: f32, sample_rate: u32) -> Self {
-        let channels = inner.channels();
-        // Calculate filter coefficient using RC time constant approximation
-        // alpha = dt / (RC + dt) where RC = 1 / (2 * pi * cutoff)
-        let dt = 1.0 / sample_rate as f32;
-        let rc = 1.0 / (2.0 * std::f32::consts::PI * cutoff_freq.clamp(20.0, 22000.0));
-        let alpha = dt / (rc + dt);
-        
-        Self {
-            inner,
-            alpha,
-            prev_samples: vec![0.0; channels as usize],
-            channels,
-            current_channel: 0,
-        }
+#[tauri::command]
+fn scan_soundboard_folder(folder_path: String) -> Result<SoundboardData, AppError> {
+    let path = PathBuf::from(&folder_path);
+    
+    if !path.exists() {
+        return Ok(SoundboardData {
+            sounds: Vec::new(),
+            path: folder_path,
+        });
     }
-}
-
-impl<S> Iterator for LowPassSource<S>
-where
-    S: Source<Item = f32>,
-{
-    type Item = f32;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let sample = self.inner.next()?;
-        let ch = self.current_channel as usize;
-        self.current_channel = (self.current_channel + 1) % self.channels;
+    let path = library_scan::canonical(&path);
+    let folder_path = path.to_string_lossy().to_string();
+    
+    let metadata_path = path.join("metadata.json");
+    
+    if metadata_path.exists() {
+        let content = fs::read_to_string(&metadata_path)
+            .map_err(|e| format!("Failed to read metadata: {}", e))?;
         
-        // One-pole low-pass: y[n] = alpha * x[n] + (1 - alpha) * y[n-1]
-        let filtered = self.alpha * sample + (1.0 - self.alpha) * self.prev_samples[ch];
-        self.prev_samples[ch] = filtered;
+        let metadata: SoundboardMetadata = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse metadata: {}", e))?;
         
-        Some(filtered)
+        Ok(SoundboardData {
+            sounds: metadata.sounds,
+            path: folder_path,
+        })
+    } else {
+        Ok(SoundboardData {
+            sounds: Vec::new(),
+            path: folder_path,
+        })
     }
 }
 
-impl<S> Source for LowPassSource<S>
-where
-    S: Source<Item = f32>,
-{
-    fn current_frame_len(&self) -> Option<usize> {
-        self.inner.current_frame_len()
-    }
-
-    fn channels(&self) -> u16 {
-        self.inner.channels()
-    }
-
-    fn sample_rate(&self) -> u32 {
-        self.inner.sample_rate()
-    }
+#[tauri::command]
+fn update_soundboard_sound(
+    state: tauri::State<Arc<AudioController>>,
+    folder_path: String,
+    sound_id: String,
+    name: Option<String>,
+    hotkey: Option<String>,
+    color: Option<String>,
+    volume: Option<u32>,
+    trim: Option<SoundTrim>, // Replaces the whole trim; an empty one plays the full file again
+) -> Result<(), AppError> {
+    if let Some(trim) = &trim {
+        trim.check().map_err(AppError::invalid)?;
+    }
+    let path = PathBuf::from(&folder_path);
+    let metadata_path = path.join("metadata.json");
+    
+    if !metadata_path.exists() {
+        return Err(AppError::not_found("Metadata file not found"));
+    }
+    
+    // Read existing metadata
+    let content = fs::read_to_string(&metadata_path)
+        .map_err(|e| format!("Failed to read metadata: {}", e))?;
+    
+    let mut metadata: SoundboardMetadata = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse metadata: {}", e))?;
+    
+    let before = content;
+    
+    // Find and update the sound
+    let sound = metadata.sounds.iter_mut().find(|s| s.id == sound_id);
+    let label = if let Some(sound) = sound {
+        if let Some(new_name) = name {
+            sound.name = new_name;
+        }
+        if hotkey.is_some() {
+            sound.hotkey = hotkey;
+        }
+        if let Some(new_color) = color {
+            sound.color = Some(new_color);
+        }
+        if let Some(new_volume) = volume {
+            sound.volume = Some(new_volume);
+        }
+        if let Some(new_trim) = trim {
+            sound.trim = new_trim;
+        }
+        format!("Edit sound \"{}\"", sound.name)
+    } else {
+        return Err(AppError::not_found(format!("Sound with id {} not found", sound_id)));
+    };
+    
+    // Write back to file
+    let content = serde_json::to_string_pretty(&metadata)
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    
+    persist::write_atomic(&metadata_path, &content)
+        .map_err(|e| AppError::io(&metadata_path, format!("Failed to write metadata: {}", e)))?;
+    state.history.record(history::EditKind::Soundboard, label, &metadata_path, Some(before), Some(content));
+    Ok(())
+}
+
+// Ambient category editing - a category is a folder with a metadata.json listing its sounds, like an
+// album. These commands keep that file up to date. Audio files picked from elsewhere are copied into
+// the category folder, since the metadata names them relative to it.
+
+// Folder and sound ids are made from names the same way preset ids are
+fn id_from_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c.to_ascii_lowercase() } else { '-' })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string()
+}
 
-    fn total_duration(&self) -> Option<std::time::Duration> {
-        self.inner.total_duration()
+fn read_ambient_metadata(category_path: &str) -> Result<(PathBuf, String, AmbientMetadata), AppError> {
+    let metadata_path = PathBuf::from(category_path).join("metadata.json");
+    if !metadata_path.exists() {
+        return Err(AppError::not_found("Metadata file not found"));
     }
+    let content = fs::read_to_string(&metadata_path)
+        .map_err(|e| AppError::io(&metadata_path, format!("Failed to read metadata: {}", e)))?;
+    let metadata: AmbientMetadata = serde_json::from_str(&content)
+        .map_err(|e| AppError::decode(&metadata_path, format!("Failed to parse metadata: {}", e)))?;
+    Ok((metadata_path, content, metadata))
+}
+
+fn write_ambient_metadata(
+    state: &AudioController,
+    metadata_path: &Path,
+    metadata: &AmbientMetadata,
+    before: Option<String>,
+    label: String,
+) -> Result<(), AppError> {
+    let content = serde_json::to_string_pretty(metadata)
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    persist::write_atomic(metadata_path, &content)
+        .map_err(|e| AppError::io(metadata_path, format!("Failed to write metadata: {}", e)))?;
+    state.history.record(history::EditKind::Ambient, label, metadata_path, before, Some(content));
+    Ok(())
 }
 
-// Source wrapper for algorithmic reverb (Schroeder-style with comb filters)
-// mix: 0.0 = dry only, 1.0 = full wet
-struct ReverbSource<S> {
-    inner: S,
-    mix: f32,
-    channels: u16,
-    current_channel: u16,
-    // Delay lines for each channel (4 comb filters per channel)
-    comb_buffers: Vec<Vec<Vec<f32>>>, // [channel][comb_index][samples]
-    comb_positions: Vec<Vec<usize>>,   // [channel][comb_index]
-    // Allpass filters
-    allpass_buffers: Vec<Vec<Vec<f32>>>, // [channel][allpass_index][samples]
-    allpass_positions: Vec<Vec<usize>>,
+// Ranges match the per-sound controls
+fn check_ambient_defaults(defaults: &AmbientSoundDefaults) -> Result<(), AppError> {
+    let out_of_range = |what: &str| Err(AppError::invalid(format!("Default {} is out of range", what)));
+    if defaults.volume.is_some_and(|v| v > 100) {
+        return out_of_range("volume");
+    }
+    if defaults.pitch.is_some_and(|p| !(0.5..=2.0).contains(&p)) {
+        return out_of_range("pitch");
+    }
+    if defaults.pan.is_some_and(|p| !(-100..=100).contains(&p)) {
+        return out_of_range("pan");
+    }
+    if defaults.low_pass_freq.is_some_and(|f| !(20..=22000).contains(&f)) {
+        return out_of_range("low-pass frequency");
+    }
+    if defaults.reverb_type.as_deref().is_some_and(|r| !["off", "small-room", "large-hall", "cathedral"].contains(&r)) {
+        return out_of_range("reverb type");
+    }
+    if defaults.algorithmic_reverb.is_some_and(|r| r > 100) {
+        return out_of_range("algorithmic reverb");
+    }
+    if [defaults.repeat_range_min, defaults.repeat_range_max].iter().flatten().any(|r| !(1..=10).contains(r)) {
+        return out_of_range("repeat range");
+    }
+    if [defaults.pause_range_min, defaults.pause_range_max].iter().flatten().any(|p| *p > 10) {
+        return out_of_range("pause range");
+    }
+    if defaults.volume_variation.is_some_and(|v| v > 50) {
+        return out_of_range("volume variation");
+    }
+    if defaults.event_probability.is_some_and(|p| p > 100) {
+        return out_of_range("event probability");
+    }
+    if defaults.intensity_macro.as_ref().is_some_and(|m| !(m.curve > 0.0 && m.curve <= 10.0)) {
+        return out_of_range("intensity curve");
+    }
+    if let (Some(min), Some(max)) = (defaults.repeat_range_min, defaults.repeat_range_max) {
+        if min > max {
+            return out_of_range("repeat range");
+        }
+    }
+    if let (Some(min), Some(max)) = (defaults.pause_range_min, defaults.pause_range_max) {
+        if min > max {
+            return out_of_range("pause range");
+        }
+    }
+    Ok(())
 }
 
-impl<S> ReverbSource<S>
-where
-    S: Source<Item = f32>,
-{
-    fn new(inner: S, mix: f32, sample_rate: u32) -> Self {
-        let channels = inner.channels() as usize;
-        let mix = mix.clamp(0.0, 1.0);
-        
-        // Comb filter delay times in samples (long delays for very spacious/echo-y reverb)
-        let comb_delays: [usize; 4] = [
-            (0.0797 * sample_rate as f32) as usize, // ~80ms
-            (0.0903 * sample_rate as f32) as usize, // ~90ms
-            (0.1100 * sample_rate as f32) as usize, // ~110ms
-            (0.1277 * sample_rate as f32) as usize, // ~128ms
-        ];
-        
-        // Allpass filter delay times (longer for more diffusion)
-        let allpass_delays: [usize; 2] = [
-            (0.0220 * sample_rate as f32) as usize, // ~22ms
-            (0.0074 * sample_rate as f32) as usize, // ~7.4ms
-        ];
-        
-        let mut comb_buffers = Vec::with_capacity(channels);
-        let mut comb_positions = Vec::with_capacity(channels);
-        let mut allpass_buffers = Vec::with_capacity(channels);
-        let mut allpass_positions = Vec::with_capacity(channels);
-        
-        for _ in 0..channels {
-            let mut ch_comb_buffers = Vec::with_capacity(4);
-            let mut ch_comb_positions = Vec::with_capacity(4);
-            for &delay in &comb_delays {
-                ch_comb_buffers.push(vec![0.0; delay.max(1)]);
-                ch_comb_positions.push(0);
-            }
-            comb_buffers.push(ch_comb_buffers);
-            comb_positions.push(ch_comb_positions);
-            
-            let mut ch_allpass_buffers = Vec::with_capacity(2);
-            let mut ch_allpass_positions = Vec::with_capacity(2);
-            for &delay in &allpass_delays {
-                ch_allpass_buffers.push(vec![0.0; delay.max(1)]);
-                ch_allpass_positions.push(0);
-            }
-            allpass_buffers.push(ch_allpass_buffers);
-            allpass_positions.push(ch_allpass_positions);
+// The file's name relative to the category folder, copying it in if it is somewhere else
+fn import_ambient_file(folder: &Path, file: &str) -> Result<String, AppError> {
+    let source = content_path(folder, file);
+    if !source.is_file() {
+        return Err(AppError::not_found(format!("Audio file not found: {}", file)));
+    }
+    if let Ok(relative) = source.strip_prefix(folder) {
+        return Ok(relative.to_string_lossy().replace('\\', "/"));
+    }
+    let name = source.file_name()
+        .ok_or_else(|| AppError::invalid(format!("Not a file: {}", file)))?;
+    let target = folder.join(name);
+    if target.exists() {
+        return Err(AppError::conflict(format!("{} already exists in the category folder", name.to_string_lossy())));
+    }
+    fs::copy(&source, &target)
+        .map_err(|e| AppError::io(&target, format!("Failed to copy audio file: {}", e)))?;
+    Ok(name.to_string_lossy().to_string())
+}
+
+// Playing sounds are keyed by sound id alone, so a new id must not be used by any category
+fn unique_ambient_sound_id(state: &AudioController, metadata: &AmbientMetadata, name: &str) -> Result<String, AppError> {
+    let settings = get_settings()?;
+    let (categories, _) = state.library_cache.scan_ambient(Path::new(&settings.ambient_folder_path), true)?;
+    let taken: std::collections::HashSet<&str> = categories.iter()
+        .flat_map(|c| c.sounds.iter())
+        .chain(metadata.sounds.iter())
+        .map(|s| s.id.as_str())
+        .collect();
+    let base = id_from_name(name);
+    if base.is_empty() {
+        return Err(AppError::invalid("Sound name needs a letter or number"));
+    }
+    let mut id = base.clone();
+    let mut n = 2;
+    while taken.contains(id.as_str()) {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    Ok(id)
+}
+
+// Create a category folder named after `name` in the ambient folder from the settings
+#[tauri::command]
+fn create_ambient_category(
+    state: tauri::State<Arc<AudioController>>,
+    name: String,
+    icon: Option<String>,
+) -> Result<AmbientCategory, AppError> {
+    let name = name.trim().to_string();
+    let folder_name = id_from_name(&name);
+    if folder_name.is_empty() {
+        return Err(AppError::invalid("Category name needs a letter or number"));
+    }
+    let settings = get_settings()?;
+    let folder = PathBuf::from(&settings.ambient_folder_path).join(&folder_name);
+    let metadata_path = folder.join("metadata.json");
+    if metadata_path.exists() {
+        return Err(AppError::conflict(format!("A category folder named {} already exists", folder_name)));
+    }
+    fs::create_dir_all(&folder)
+        .map_err(|e| AppError::io(&folder, format!("Failed to create category folder: {}", e)))?;
+    
+    let metadata = AmbientMetadata { name: name.clone(), icon, sounds: Vec::new() };
+    write_ambient_metadata(&state, &metadata_path, &metadata, None, format!("Create category \"{}\"", name))?;
+    Ok(AmbientCategory {
+        name: metadata.name,
+        icon: metadata.icon,
+        sounds: metadata.sounds,
+        path: folder.to_string_lossy().to_string(),
+    })
+}
+
+#[tauri::command]
+fn add_ambient_sound(
+    state: tauri::State<Arc<AudioController>>,
+    category_path: String,
+    name: String,
+    file_a: String,
+    file_b: String,
+    defaults: Option<AmbientSoundDefaults>,
+) -> Result<AmbientSoundDef, AppError> {
+    let name = name.trim().to_string();
+    if let Some(defaults) = &defaults {
+        check_ambient_defaults(defaults)?;
+    }
+    let (metadata_path, before, mut metadata) = read_ambient_metadata(&category_path)?;
+    let id = unique_ambient_sound_id(&state, &metadata, &name)?;
+    let folder = PathBuf::from(&category_path);
+    let files = AmbientSoundFiles {
+        a: import_ambient_file(&folder, &file_a)?,
+        b: import_ambient_file(&folder, &file_b)?,
+    };
+    
+    let sound = AmbientSoundDef { id, name, files, defaults, tags: Vec::new(), rating: None };
+    metadata.sounds.push(sound.clone());
+    let label = format!("Add sound \"{}\"", sound.name);
+    write_ambient_metadata(&state, &metadata_path, &metadata, Some(before), label)?;
+    Ok(sound)
+}
+
+// Fields left out keep their value; the id never changes, so presets that use the sound keep working
+#[tauri::command]
+fn update_ambient_sound(
+    state: tauri::State<Arc<AudioController>>,
+    category_path: String,
+    sound_id: String,
+    name: Option<String>,
+    file_a: Option<String>,
+    file_b: Option<String>,
+    defaults: Option<AmbientSoundDefaults>,
+    tags: Option<Vec<String>>,
+    rating: Option<u32>,
+) -> Result<AmbientSoundDef, AppError> {
+    if let Some(defaults) = &defaults {
+        check_ambient_defaults(defaults)?;
+    }
+    // 0 clears the rating
+    if rating.is_some_and(|r| r > 5) {
+        return Err(AppError::invalid("Rating must be 1 - 5 stars"));
+    }
+    let (metadata_path, before, mut metadata) = read_ambient_metadata(&category_path)?;
+    let folder = PathBuf::from(&category_path);
+    let sound = metadata.sounds.iter_mut().find(|s| s.id == sound_id)
+        .ok_or_else(|| AppError::not_found(format!("Sound with id {} not found", sound_id)))?;
+    
+    if let Some(name) = name {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::invalid("Sound name can't be empty"));
         }
-        
-        Self {
-            inner,
-            mix,
-            channels: channels as u16,
-            current_channel: 0,
-            comb_buffers,
-            comb_positions,
-            allpass_buffers,
-            allpass_positions,
+        sound.name = name;
+    }
+    if let Some(file) = file_a {
+        sound.files.a = import_ambient_file(&folder, &file)?;
+    }
+    if let Some(file) = file_b {
+        sound.files.b = import_ambient_file(&folder, &file)?;
+    }
+    if defaults.is_some() {
+        sound.defaults = defaults;
+    }
+    if let Some(tags) = tags {
+        sound.tags = tags.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+    }
+    if let Some(rating) = rating {
+        sound.rating = (rating > 0).then_some(rating);
+    }
+    
+    let sound = sound.clone();
+    let label = format!("Edit sound \"{}\"", sound.name);
+    write_ambient_metadata(&state, &metadata_path, &metadata, Some(before), label)?;
+    Ok(sound)
+}
+
+// Removes the sound from the category; its audio files stay in the folder
+#[tauri::command]
+fn delete_ambient_sound(
+    state: tauri::State<Arc<AudioController>>,
+    category_path: String,
+    sound_id: String,
+) -> Result<(), AppError> {
+    let (metadata_path, before, mut metadata) = read_ambient_metadata(&category_path)?;
+    let index = metadata.sounds.iter().position(|s| s.id == sound_id)
+        .ok_or_else(|| AppError::not_found(format!("Sound with id {} not found", sound_id)))?;
+    let sound = metadata.sounds.remove(index);
+    let label = format!("Delete sound \"{}\"", sound.name);
+    write_ambient_metadata(&state, &metadata_path, &metadata, Some(before), label)
+}
+
+// Audio Commands - using thread-safe AudioController
+#[tauri::command]
+fn init_audio(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    // Set the presets directory for the audio thread to use
+    let presets_dir = get_presets_dir(&app)?;
+    state.set_presets_dir(presets_dir);
+    // Let the audio thread emit visualization events
+    state.set_app_handle(app);
+    Ok(())
+}
+
+#[tauri::command]
+fn play_music(
+    state: tauri::State<Arc<AudioController>>,
+    file_path: String,
+    id: String,
+    title: String,
+    artist: String,
+    album: String,
+) -> Result<(), AppError> {
+    let track_info = CurrentTrackInfo {
+        id,
+        title,
+        artist,
+        album,
+        file_path: file_path.clone(),
+    };
+    state.send(AudioCommand::Play { file_path, track_info });
+    Ok(())
+}
+
+// Start a library track, announcing it with its playlist info
+fn play_track(state: &AudioController, track: &PlaylistTrack) {
+    let track_info = track.track_info();
+    state.send(AudioCommand::Play { file_path: track_info.file_path.clone(), track_info });
+}
+
+#[tauri::command]
+fn get_current_track(state: tauri::State<Arc<AudioController>>) -> Result<Option<CurrentTrackInfo>, AppError> {
+    Ok(state.get_current_track())
+}
+
+// Scheduler management commands
+#[tauri::command]
+fn get_scheduler_state(state: tauri::State<Arc<AudioController>>) -> Result<SchedulerState, AppError> {
+    Ok(state.scheduler_state.lock().clone())
+}
+
+#[tauri::command]
+fn start_scheduler_playback(
+    state: tauri::State<Arc<AudioController>>,
+    items: Vec<ScheduledItem>,
+    schedule_id: Option<String>,
+    transition: Option<TransitionChime>,
+) -> Result<(), AppError> {
+    if items.is_empty() {
+        return Err(AppError::invalid("No items to schedule"));
+    }
+    check_event_volumes(&items)?;
+    // Find the transition and event sounds' files now, so the scheduler tick doesn't scan the soundboard
+    let sound_ids: std::collections::HashSet<&str> = transition.iter()
+        .map(|chime| chime.sound_id.as_str())
+        .chain(items.iter().flat_map(|item| item.events.iter().map(|event| event.sound_id.as_str())))
+        .collect();
+    let mut sound_files = HashMap::new();
+    if !sound_ids.is_empty() {
+        let settings = get_settings()?;
+        let sounds = scan_soundboard_folder(settings.soundboard_folder_path.clone())?.sounds;
+        for id in sound_ids {
+            let sound = sounds.iter()
+                .find(|s| s.id == id)
+                .ok_or_else(|| AppError::not_found(format!("Soundboard sound not found: {}", id)))?;
+            let file = content_path(&settings.soundboard_folder_path, &sound.file).to_string_lossy().to_string();
+            sound_files.insert(id.to_string(), (file, sound.trim));
         }
     }
+
+    state.start_schedule(items, schedule_id, transition, sound_files);
+    Ok(())
 }
 
-impl<S> Iterator for ReverbSource<S>
-where
-    S: Source<Item = f32>,
-{
-    type Item = f32;
+fn check_event_volumes(items: &[ScheduledItem]) -> Result<(), AppError> {
+    if items.iter().flat_map(|item| &item.events).any(|event| event.volume > 100) {
+        return Err(AppError::invalid("Event volume must be 0-100"));
+    }
+    Ok(())
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let sample = self.inner.next()?;
-        
-        // Skip processing if mix is 0
-        if self.mix < 0.001 {
-            self.current_channel = (self.current_channel + 1) % self.channels;
-            return Some(sample);
+#[tauri::command]
+fn stop_scheduler_playback(state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    state.stop_schedule();
+    Ok(())
+}
+
+// Playlist management commands
+#[tauri::command]
+fn get_playlist_state(state: tauri::State<Arc<AudioController>>) -> Result<PlaylistState, AppError> {
+    Ok(state.get_playlist_state())
+}
+
+#[tauri::command]
+fn load_saved_playlists_and_favorites(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    // Load favorites from disk
+    let favorites = load_favorites_from_disk(&app)?;
+    state.playlist_state.lock().favorites = favorites;
+    
+    // Load playlists from disk
+    let playlists = load_playlists_from_disk(&app)?;
+    let mut playlist_map = state.playlists.lock();
+    // Replace rather than merge, so a reload drops playlists deleted on another machine
+    playlist_map.clear();
+    for playlist in playlists {
+        playlist_map.insert(playlist.id.clone(), playlist);
+    }
+    
+    Ok(())
+}
+
+#[tauri::command]
+fn set_playlist_shuffle(state: tauri::State<Arc<AudioController>>, shuffled: bool) -> Result<(), AppError> {
+    state.playlist_state.lock().is_shuffled = shuffled;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_playlist_loop(state: tauri::State<Arc<AudioController>>, looping: bool) -> Result<(), AppError> {
+    state.playlist_state.lock().is_looping = looping;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_current_playlist(state: tauri::State<Arc<AudioController>>, playlist_id: Option<String>) -> Result<(), AppError> {
+    // Apply the playlist's own playback settings (auto playlists and albums have none)
+    let playlist = playlist_id.as_ref().and_then(|id| state.playlists.lock().get(id).cloned());
+    let (crossfade_duration, shuffle_default, volume_offset) = playlist
+        .map(|p| (p.crossfade_duration, p.shuffle_default, p.volume_offset))
+        .unwrap_or((None, None, None));
+    state.send(AudioCommand::SetPlaylistOverrides {
+        crossfade_duration,
+        volume_offset_db: volume_offset.unwrap_or(0.0),
+    });
+    
+    let mut ps = state.playlist_state.lock();
+    ps.current_playlist_id = playlist_id;
+    ps.current_index = 0;
+    ps.interrupted_index = None;
+    if let Some(shuffled) = shuffle_default {
+        ps.is_shuffled = shuffled;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn set_playlist_index(state: tauri::State<Arc<AudioController>>, index: i32) -> Result<(), AppError> {
+    state.playlist_state.lock().current_index = index;
+    Ok(())
+}
+
+#[tauri::command]
+fn play_next_track(state: tauri::State<Arc<AudioController>>) -> Result<bool, AppError> {
+    if external_transport(&state, external_player::Transport::Next) {
+        return Ok(true);
+    }
+    
+    // Get current playlist state
+    let ps = state.playlist_state.lock().clone();
+    
+    // Auto-DJ picks from its target band rather than stepping through a list
+    if ps.current_playlist_id.as_deref() == Some(AUTO_DJ_PLAYLIST_ID) {
+        let Some(target) = ps.auto_dj else {
+            return Ok(false);
+        };
+        let current_id = state.get_current_track().map(|t| t.id);
+        let Some(track) = pick_auto_dj_track(&state.all_tracks.lock(), &target, current_id.as_deref()) else {
+            return Ok(false);
+        };
+        play_track(&state, &track);
+        return Ok(true);
+    }
+    let all_tracks = state.all_tracks.lock().clone();
+    let playlists = state.playlists.lock().clone();
+    
+    // Determine which tracks to use
+    let tracks: Vec<PlaylistTrack> = if let Some(ref playlist_id) = ps.current_playlist_id {
+        if playlist_id == "all-music" {
+            all_tracks.clone()
+        } else if playlist_id == "favorites" {
+            all_tracks.iter().filter(|t| ps.favorites.contains(&t.id)).cloned().collect()
+        } else if playlist_id.starts_with("album-") {
+            // Filter tracks by album name
+            let album_name = playlist_id.strip_prefix("album-").unwrap_or("");
+            get_album_tracks(&all_tracks, album_name)
+        } else if let Some(playlist) = playlists.get(playlist_id) {
+            playlist.tracks.clone()
+        } else {
+            // Unknown playlist, return empty
+            Vec::new()
         }
-        
-        let ch = self.current_channel as usize;
-        self.current_channel = (self.current_channel + 1) % self.channels;
-        
-        // Comb filter bank (parallel)
-        let feedback = 0.95; // Very high feedback for long echo-y decay
-        let mut comb_sum = 0.0;
-        
-        for i in 0..4 {
-            let buf = &mut self.comb_buffers[ch][i];
-            let pos = self.comb_positions[ch][i];
-            let delayed = buf[pos];
-            let new_val = sample + delayed * feedback;
-            buf[pos] = new_val;
-            self.comb_positions[ch][i] = (pos + 1) % buf.len();
-            comb_sum += delayed;
+    } else {
+        return Ok(false); // No playlist selected
+    };
+    
+    if tracks.is_empty() {
+        return Ok(false);
+    }
+    
+    // Calculate next index
+    let next_index: i32 = if ps.is_shuffled {
+        ((rand::random::<usize>()) % tracks.len()) as i32
+    } else {
+        let next = ps.current_index + 1;
+        if next >= tracks.len() as i32 {
+            if ps.is_looping {
+                0
+            } else {
+                return Ok(false); // Playlist finished, not looping
+            }
+        } else {
+            next
         }
-        comb_sum *= 0.25; // Average the 4 comb outputs
-        
-        // Allpass filters (series)
-        let allpass_coeff = 0.7; // Higher coefficient for more diffusion
-        let mut allpass_out = comb_sum;
-        
-        for i in 0..2 {
-            let buf = &mut self.allpass_buffers[ch][i];
-            let pos = self.allpass_positions[ch][i];
-            let delayed = buf[pos];
-            let new_val = allpass_out + delayed * allpass_coeff;
-            allpass_out = delayed - allpass_coeff * new_val;
-            buf[pos] = new_val;
-            self.allpass_positions[ch][i] = (pos + 1) % buf.len();
+    };
+    
+    // Update state
+    {
+        let mut ps_lock = state.playlist_state.lock();
+        ps_lock.current_index = next_index;
+    }
+    
+    // Get the track and play it
+    let track = &tracks[next_index as usize];
+    play_track(&state, track);
+    Ok(true)
+}
+
+#[tauri::command]
+fn play_album(
+    state: tauri::State<Arc<AudioController>>,
+    album_name: String,
+    start_track_id: Option<String>,
+) -> Result<(), AppError> {
+    let tracks = get_album_tracks(&state.all_tracks.lock(), &album_name);
+    if tracks.is_empty() {
+        return Err(AppError::not_found(format!("Album '{}' not found", album_name)));
+    }
+    
+    let index = match start_track_id {
+        Some(track_id) => tracks.iter().position(|t| t.id == track_id)
+            .ok_or_else(|| AppError::not_found(format!("Track '{}' not found in album '{}'", track_id, album_name)))?,
+        None => 0,
+    };
+    
+    // Albums have no per-playlist overrides, so reset any left over from a custom playlist
+    state.send(AudioCommand::SetPlaylistOverrides {
+        crossfade_duration: None,
+        volume_offset_db: 0.0,
+    });
+    
+    {
+        let mut ps = state.playlist_state.lock();
+        ps.current_playlist_id = Some(format!("album-{}", album_name));
+        ps.current_index = index as i32;
+        ps.interrupted_index = None;
+    }
+    
+    let track = &tracks[index];
+    play_track(&state, track);
+    Ok(())
+}
+
+#[tauri::command]
+fn start_auto_dj(
+    state: tauri::State<Arc<AudioController>>,
+    moods: Vec<String>,
+    energy_min: Option<u32>,
+    energy_max: Option<u32>,
+) -> Result<bool, AppError> {
+    let target = AutoDjTarget {
+        moods,
+        energy_min: energy_min.unwrap_or(ENERGY_MIN).clamp(ENERGY_MIN, ENERGY_MAX),
+        energy_max: energy_max.unwrap_or(ENERGY_MAX).clamp(ENERGY_MIN, ENERGY_MAX),
+    };
+    if target.energy_min > target.energy_max {
+        return Err(AppError::invalid(format!("Invalid energy range: {} - {}", target.energy_min, target.energy_max)));
+    }
+    
+    let current_id = state.get_current_track().map(|t| t.id);
+    let Some(track) = pick_auto_dj_track(&state.all_tracks.lock(), &target, current_id.as_deref()) else {
+        return Ok(false); // Nothing in the library matches this band
+    };
+    
+    state.send(AudioCommand::SetPlaylistOverrides {
+        crossfade_duration: None,
+        volume_offset_db: 0.0,
+    });
+    {
+        let mut ps = state.playlist_state.lock();
+        ps.current_playlist_id = Some(AUTO_DJ_PLAYLIST_ID.to_string());
+        ps.current_index = 0;
+        ps.interrupted_index = None;
+        ps.auto_dj = Some(target);
+    }
+    
+    play_track(&state, &track);
+    Ok(true)
+}
+
+#[tauri::command]
+fn set_track_tags(
+    state: tauri::State<Arc<AudioController>>,
+    album_path: String,
+    track_id: String,
+    mood: Option<String>,
+    energy: Option<u32>,
+) -> Result<(), AppError> {
+    let energy = energy.map(|e| e.clamp(ENERGY_MIN, ENERGY_MAX));
+    let mood = mood.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+    let metadata_path = PathBuf::from(&album_path).join("metadata.json");
+    
+    if !metadata_path.exists() {
+        return Err(AppError::not_found("Metadata file not found"));
+    }
+    
+    let content = fs::read_to_string(&metadata_path)
+        .map_err(|e| format!("Failed to read metadata: {}", e))?;
+    
+    // Edited as plain JSON so fields this version doesn't know about survive the write
+    let mut metadata: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse metadata: {}", e))?;
+    
+    let track = metadata.get_mut("tracks")
+        .and_then(|tracks| tracks.as_array_mut())
+        .and_then(|tracks| tracks.iter_mut().find(|t| t.get("id").and_then(|id| id.as_str()) == Some(track_id.as_str())))
+        .and_then(|track| track.as_object_mut())
+        .ok_or_else(|| AppError::not_found(format!("Track with id {} not found", track_id)))?;
+    match &mood {
+        Some(mood) => track.insert("mood".to_string(), serde_json::Value::from(mood.clone())),
+        None => track.remove("mood"),
+    };
+    match energy {
+        Some(energy) => track.insert("energy".to_string(), serde_json::Value::from(energy)),
+        None => track.remove("energy"),
+    };
+    
+    let content = serde_json::to_string_pretty(&metadata)
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    
+    persist::write_atomic(&metadata_path, content)
+        .map_err(|e| format!("Failed to write metadata: {}", e))?;
+    
+    // Keep the in-memory library in sync so the auto-DJ sees the new tags
+    for t in state.all_tracks.lock().iter_mut().filter(|t| t.id == track_id && t.album_path == album_path) {
+        t.mood = mood.clone();
+        t.energy = energy;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn play_previous_track(state: tauri::State<Arc<AudioController>>) -> Result<bool, AppError> {
+    if external_transport(&state, external_player::Transport::Previous) {
+        return Ok(true);
+    }
+    
+    // Get current playlist state
+    let ps = state.playlist_state.lock().clone();
+    let all_tracks = state.all_tracks.lock().clone();
+    let playlists = state.playlists.lock().clone();
+    
+    // Determine which tracks to use
+    let tracks: Vec<PlaylistTrack> = if let Some(ref playlist_id) = ps.current_playlist_id {
+        if playlist_id == "all-music" {
+            all_tracks.clone()
+        } else if playlist_id == "favorites" {
+            all_tracks.iter().filter(|t| ps.favorites.contains(&t.id)).cloned().collect()
+        } else if playlist_id.starts_with("album-") {
+            // Filter tracks by album name
+            let album_name = playlist_id.strip_prefix("album-").unwrap_or("");
+            get_album_tracks(&all_tracks, album_name)
+        } else if let Some(playlist) = playlists.get(playlist_id) {
+            playlist.tracks.clone()
+        } else {
+            Vec::new()
         }
-        
-        // Mix dry and wet - aggressive wet signal boost
-        let wet_gain = 2.5;
-        Some(sample * (1.0 - self.mix) + allpass_out * self.mix * wet_gain)
+    } else {
+        return Ok(false);
+    };
+    
+    if tracks.is_empty() {
+        return Ok(false);
     }
+    
+    // Calculate previous index
+    let prev_index = if ps.current_index <= 0 {
+        (tracks.len() - 1) as i32
+    } else {
+        ps.current_index - 1
+    };
+    
+    // Update state
+    {
+        let mut ps_lock = state.playlist_state.lock();
+        ps_lock.current_index = prev_index;
+    }
+    
+    // Get the track and play it
+    let track = &tracks[prev_index as usize];
+    play_track(&state, track);
+    Ok(true)
+}
+
+#[tauri::command]
+fn toggle_favorite(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>, track_id: String) -> Result<bool, AppError> {
+    let mut ps = state.playlist_state.lock();
+    let is_favorite = if let Some(pos) = ps.favorites.iter().position(|id| id == &track_id) {
+        ps.favorites.remove(pos);
+        false
+    } else {
+        ps.favorites.push(track_id);
+        true
+    };
+    
+    // Persist favorites to disk
+    save_favorites_to_disk(&app, &ps.favorites)?;
+    
+    Ok(is_favorite)
+}
+
+#[tauri::command]
+fn set_crossfade_duration(state: tauri::State<Arc<AudioController>>, duration: f32) -> Result<(), AppError> {
+    let duration = settings_check::clamp("music_crossfade_duration", duration as f64) as f32;
+    state.send(AudioCommand::SetCrossfadeDuration(duration));
+    Ok(())
+}
+
+#[tauri::command]
+fn set_skip_crossfade_duration(state: tauri::State<Arc<AudioController>>, duration: f32) -> Result<(), AppError> {
+    let duration = settings_check::clamp("music_skip_crossfade_duration", duration as f64) as f32;
+    state.send(AudioCommand::SetSkipCrossfadeDuration(duration));
+    Ok(())
+}
+
+// How long a preset switch takes to fade sounds in and out and move shared sounds to their new volume
+#[tauri::command]
+fn set_preset_crossfade_duration(state: tauri::State<Arc<AudioController>>, duration: f32) -> Result<(), AppError> {
+    let duration = settings_check::clamp("ambient_preset_crossfade_duration", duration as f64) as f32;
+    state.send(AudioCommand::SetPresetCrossfadeDuration(duration));
+    Ok(())
+}
+
+#[tauri::command]
+fn set_skip_on_silence(state: tauri::State<Arc<AudioController>>, enabled: bool) -> Result<(), AppError> {
+    state.send(AudioCommand::SetSkipOnSilence(enabled));
+    Ok(())
+}
+
+#[tauri::command]
+fn set_audio_tick_interval(state: tauri::State<Arc<AudioController>>, ms: u32) -> Result<(), AppError> {
+    state.send(AudioCommand::SetTickInterval(ms));
+    Ok(())
 }
 
-impl<S> Source for ReverbSource<S>
-where
-    S: Source<Item = f32>,
-{
-    fn current_frame_len(&self) -> Option<usize> {
-        self.inner.current_frame_len()
-    }
+#[tauri::command]
+fn set_mmap_threshold(state: tauri::State<Arc<AudioController>>, mb: u32) -> Result<(), AppError> {
+    state.send(AudioCommand::SetMmapThreshold(mb as u64 * 1024 * 1024));
+    Ok(())
+}
 
-    fn channels(&self) -> u16 {
-        self.inner.channels()
-    }
+// Fails if the OS won't allow it, e.g. on Linux without rtkit or an rtprio limit for the user
+#[tauri::command]
+fn set_realtime_priority(state: tauri::State<Arc<AudioController>>, enabled: bool) -> Result<(), AppError> {
+    let (reply, response) = channel();
+    state.send(AudioCommand::SetRealtimePriority(enabled, reply));
+    response.recv_timeout(std::time::Duration::from_secs(2))
+        .map_err(|_| AppError::unavailable("The audio thread did not respond"))?
+        .map_err(|e| AppError::unavailable(format!("Real-time priority not available: {}", e)))
+}
 
-    fn sample_rate(&self) -> u32 {
-        self.inner.sample_rate()
+// Move all playback to another output device, by its id from get_output_devices (empty = the system
+// default). Music, ambient sounds and the soundboard carry on where they are on the new device; a
+// soundboard routed to a device of its own stays there.
+#[tauri::command]
+fn set_output_device(state: tauri::State<Arc<AudioController>>, device_id: String) -> Result<(), AppError> {
+    let device = Some(device_id).filter(|id| !id.is_empty());
+    if let Some(id) = &device {
+        if !get_output_devices()?.iter().any(|d| &d.id == id) {
+            return Err(AppError::not_found(format!("Output device not found: {}", id)));
+        }
     }
+    let (reply, response) = channel();
+    state.send(AudioCommand::SetOutputDevice(device, reply));
+    response.recv_timeout(std::time::Duration::from_secs(2))
+        .map_err(|_| AppError::unavailable("The audio thread did not respond"))?
+        .map_err(|e| AppError::unavailable(format!("Failed to open output device: {}", e)))
+}
 
-    fn total_duration(&self) -> Option<std::time::Duration> {
-        self.inner.total_duration()
+// Play the soundboard on its own device, e.g. a virtual cable for a stream, while music and ambient
+// sounds stay on the output device (empty = back on the output device). A soundboard sound that is
+// playing stops.
+#[tauri::command]
+fn set_soundboard_output_device(state: tauri::State<Arc<AudioController>>, device_id: String) -> Result<(), AppError> {
+    let device = Some(device_id).filter(|id| !id.is_empty());
+    if let Some(id) = &device {
+        if !get_output_devices()?.iter().any(|d| &d.id == id) {
+            return Err(AppError::not_found(format!("Output device not found: {}", id)));
+        }
     }
+    let (reply, response) = channel();
+    state.send(AudioCommand::SetSoundboardOutputDevice(device, reply));
+    response.recv_timeout(std::time::Duration::from_secs(2))
+        .map_err(|_| AppError::unavailable("The audio thread did not respond"))?
+        .map_err(|e| AppError::unavailable(format!("Failed to open soundboard output device: {}", e)))
 }
 
-struct AudioController {
-    command_tx: Sender<AudioCommand>,
-    progress: Arc<Mutex<AudioProgress>>,
-    playback_state: Arc<Mutex<PlaybackState>>,
-    sample_buffer: Arc<FftSampleBuffer>,
-    ambient_sample_buffer: Arc<AmbientSampleBuffer>,
-    active_ambients: Arc<Mutex<HashMap<String, ActiveAmbientInfo>>>,
-    current_track: Arc<Mutex<Option<CurrentTrackInfo>>>,
-    playlist_state: Arc<Mutex<PlaylistState>>,
-    playlists: Arc<Mutex<HashMap<String, MusicPlaylist>>>,
-    all_tracks: Arc<Mutex<Vec<PlaylistTrack>>>,
-    soundboard_playing: Arc<Mutex<bool>>,
-    scheduler_state: Arc<Mutex<SchedulerState>>,
-    presets_dir: Arc<Mutex<Option<PathBuf>>>,
-    current_preset_id: Arc<Mutex<Option<String>>>,
+#[tauri::command]
+fn set_loudness_matching(state: tauri::State<Arc<AudioController>>, enabled: bool) -> Result<(), AppError> {
+    state.send(AudioCommand::SetLoudnessMatching(enabled));
+    Ok(())
 }
 
-impl AudioController {
-    fn new() -> Self {
-        let (command_tx, command_rx) = channel::<AudioCommand>();
-        let progress = Arc::new(Mutex::new(AudioProgress {
-            current_time: 0.0,
-            duration: 0.0,
-            is_playing: false,
-            is_finished: true,
-        }));
-        let playback_state = Arc::new(Mutex::new(PlaybackState::default()));
-        let sample_buffer = Arc::new(FftSampleBuffer::new());
-        let ambient_sample_buffer = Arc::new(AmbientSampleBuffer::new());
-        let active_ambients: Arc<Mutex<HashMap<String, ActiveAmbientInfo>>> = Arc::new(Mutex::new(HashMap::new()));
-        let current_track = Arc::new(Mutex::new(None::<CurrentTrackInfo>));
-        let playlist_state = Arc::new(Mutex::new(PlaylistState::default()));
-        let playlists: Arc<Mutex<HashMap<String, MusicPlaylist>>> = Arc::new(Mutex::new(HashMap::new()));
-        let all_tracks: Arc<Mutex<Vec<PlaylistTrack>>> = Arc::new(Mutex::new(Vec::new()));
-        let soundboard_playing = Arc::new(Mutex::new(false));
-        let scheduler_state = Arc::new(Mutex::new(SchedulerState::default()));
-        let presets_dir: Arc<Mutex<Option<PathBuf>>> = Arc::new(Mutex::new(None));
-        let current_preset_id: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
-        
-        let progress_clone = progress.clone();
-        let playback_state_clone = playback_state.clone();
-        let sample_buffer_clone = sample_buffer.clone();
-        let ambient_sample_buffer_clone = ambient_sample_buffer.clone();
-        let active_ambients_clone = active_ambients.clone();
-        let current_track_clone = current_track.clone();
-        let soundboard_playing_clone = soundboard_playing.clone();
-        let playlist_state_clone = playlist_state.clone();
-        let playlists_clone = playlists.clone();
-        let all_tracks_clone = all_tracks.clone();
-        let scheduler_state_clone = scheduler_state.clone();
-        let presets_dir_clone = presets_dir.clone();
-        let command_tx_clone = command_tx.clone();
-        
-        // Spawn audio thread
-        thread::spawn(move || {
-            let (_stream, stream_handle) = match OutputStream::try_default() {
-                Ok(s) => s,
-                Err(e) => {
-                    eprintln!("Failed to create audio output: {}", e);
-                    return;
-                }
-            };
-            
-            let mut current_sink: Option<Sink> = None;
-            let mut music_volume: f32 = 1.0;
-            let mut master_volume: f32 = 1.0;
-            let mut is_muted = false;
-            let mut is_master_muted = false;
-            let mut track_start: Option<Instant> = None;
-            let mut track_duration: f64 = 0.0;
-            let mut pause_start: Option<Instant> = None;  // Track when pause started
-            let mut crossfade_duration: f32 = 3.0;  // Default 3 seconds
-            // Fade states: fade_out for end of current track, fade_in for start of new track
-            let mut fade_out_active: bool = false;  // Currently fading out
-            let mut fade_in_progress: Option<(Instant, f32)> = None;  // (start_time, duration) for fade-in
-            
-            // FFT setup
-            let mut fft_planner = FftPlanner::<f32>::new();
-            let fft = fft_planner.plan_fft_forward(1024);
-            let mut fft_buffer: Vec<Complex<f32>> = vec![Complex::new(0.0, 0.0); 1024];
-            
-            // Ambient sounds state - A/B crossfade system
-            struct AmbientState {
-                sink: Sink,
-                file_a: String,
-                file_b: String,
-                settings: AmbientSettings,
-                is_playing_a: bool,      // true = A, false = B
-                loops_remaining: u32,    // A/B cycles before pause
-                pause_remaining: f64,    // seconds of pause remaining
-                is_paused: bool,         // in pause state
-            }
-            let mut ambient_states: HashMap<String, AmbientState> = HashMap::new();
-            let mut ambient_master_volume: f32 = 1.0;
-            let mut is_ambient_muted = false;
-            
-            // Audio file cache - stores file bytes in memory to avoid disk I/O during playback
-            let mut audio_cache: HashMap<String, Vec<u8>> = HashMap::new();
-            
-            // Track sounds that are fading out before stop (id -> fade progress 0.0-1.0)
-            let mut fading_out: HashMap<String, f32> = HashMap::new();
-            // Track sounds that are fading in after start (id -> fade progress 0.0-1.0)
-            let mut fading_in: HashMap<String, f32> = HashMap::new();
-            // Track volume transitions for smooth settings changes (id -> (current_vol, target_vol))
-            let mut volume_transitions: HashMap<String, (f32, f32)> = HashMap::new();
-            const FADE_STEPS: f32 = 4.0; // ~200ms fade (4 steps × 50ms loop)
-            const VOLUME_TRANSITION_SPEED: f32 = 0.08; // Volume change per loop iteration (~400ms full transition)
-            
-            // Scheduler-specific fades with longer duration (2000ms)
-            let mut scheduler_fading_out: HashMap<String, f32> = HashMap::new();
-            let mut scheduler_fading_in: HashMap<String, f32> = HashMap::new();
-            let mut scheduler_volume_transitions: HashMap<String, (f32, f32)> = HashMap::new();
-            const SCHEDULER_FADE_STEPS: f32 = 40.0; // ~2000ms fade (40 steps × 50ms loop)
-            const SCHEDULER_VOLUME_TRANSITION_SPEED: f32 = 0.025; // ~2000ms full transition
-            
-            // Soundboard state
-            let mut soundboard_sink: Option<Sink> = None;
-            let mut soundboard_volume: f32 = 1.0; // Soundboard volume (0-1)
-            let mut soundboard_muted: bool = false; // Soundboard mute state
-            let mut duck_amount: f32 = 0.5; // Default 50% ducking
-            let mut duck_progress: f32 = 0.0; // 0.0 = no ducking, 1.0 = fully ducked
-            let mut duck_target: f32 = 0.0; // Target duck level (0.0 or 1.0)
-            const DUCK_FADE_SPEED: f32 = 0.15; // How fast to fade ducking per loop iteration (~300ms full fade)
-            
-            // Auto-advance state for playlist
-            let mut was_playing: bool = false;
-            let mut pending_auto_advance: Option<(String, CurrentTrackInfo)> = None; // (file_path, track_info)
-            
-            // Scheduler tick counter (loop runs every 50ms, so 20 iterations = 1 second)
-            let mut scheduler_tick_counter: u32 = 0;
-            const SCHEDULER_TICKS_PER_SECOND: u32 = 20;
-            
-            // Helper to calculate effective volume with variation and ducking
-            fn calc_ambient_volume(
-                settings: &AmbientSettings,
-                ambient_master: f32,
-                master: f32,
-                is_ambient_muted: bool,
-                is_master_muted: bool,
-                duck_progress: f32,
-                duck_amount: f32,
-            ) -> f32 {
-                if is_ambient_muted || is_master_muted {
-                    0.0
-                } else {
-                    let variation = if settings.volume_variation > 0.0 {
-                        let var = (rand::random::<f32>() - 0.5) * 2.0 * settings.volume_variation;
-                        (1.0 + var).max(0.0).min(2.0)
-                    } else {
-                        1.0
-                    };
-                    let base_vol = settings.volume * ambient_master * master * variation;
-                    // Apply gradual ducking based on duck_progress (0.0 = none, 1.0 = full)
-                    base_vol * (1.0 - duck_progress * duck_amount)
-                }
-            }
-            
-            // Track last loaded scheduler item to detect changes
-            let mut last_scheduler_item_index: Option<usize> = None;
-            let mut scheduler_preset_pending: Option<String> = None; // preset_id to load
-            
-            loop {
-                // Handle scheduler tick (every 1 second)
-                scheduler_tick_counter += 1;
-                if scheduler_tick_counter >= SCHEDULER_TICKS_PER_SECOND {
-                    scheduler_tick_counter = 0;
-                    
-                    let mut sched = scheduler_state_clone.lock();
-                    if sched.is_playing && !sched.items.is_empty() {
-                        // Check if this is the first tick or if we advanced to a new item
-                        let current_idx = sched.current_item_index;
-                        let should_load_preset = last_scheduler_item_index != Some(current_idx);
-                        
-                        if should_load_preset {
-                            last_scheduler_item_index = Some(current_idx);
-                            let preset_id = sched.items[current_idx].preset_id.clone();
-                            println!("[Scheduler] Queued preset load: {}", preset_id);
-                            scheduler_preset_pending = Some(preset_id);
-                        }
-                        
-                        sched.time_remaining -= 1;
-                        // Log every 10 seconds to avoid spam
-                        if sched.time_remaining % 10 == 0 {
-                            println!("[Scheduler] Tick: item {}/{}, time_remaining={}", 
-                                current_idx, sched.items.len(), sched.time_remaining);
-                        }
-                        
-                        if sched.time_remaining <= 0 {
-                            // Advance to next item
-                            println!("[Scheduler] Time expired, advancing to next item");
-                            let next_index = (sched.current_item_index + 1) % sched.items.len();
-                            
-                            // Clone values before mutating sched
-                            let next_preset_id = sched.items[next_index].preset_id.clone();
-                            let min = sched.items[next_index].min_minutes.min(sched.items[next_index].max_minutes);
-                            let max = sched.items[next_index].min_minutes.max(sched.items[next_index].max_minutes);
-                            let duration = if min == max {
-                                min
-                            } else {
-                                min + (rand::random::<u32>() % (max - min + 1))
-                            };
-                            
-                            sched.current_item_index = next_index;
-                            sched.current_duration = duration;
-                            sched.time_remaining = (duration * 60) as i32;
-                            
-                            // Queue the next preset to load
-                            scheduler_preset_pending = Some(next_preset_id);
-                            last_scheduler_item_index = Some(next_index);
-                        }
-                    } else if !sched.is_playing {
-                        last_scheduler_item_index = None;
-                    }
-                }
-                
-                // Handle pending scheduler preset load
-                if let Some(preset_id) = scheduler_preset_pending.take() {
-                    println!("[Scheduler] Loading preset: {}", preset_id);
-                    let presets_path_opt = presets_dir_clone.lock().clone();
-                    if presets_path_opt.is_none() {
-                        println!("[Scheduler] ERROR: presets_dir is None!");
-                    }
-                    if let Some(presets_path) = presets_path_opt {
-                        let preset_path = presets_path.join(format!("{}.soundscape", &preset_id));
-                        println!("[Scheduler] Preset path: {:?}, exists: {}", preset_path, preset_path.exists());
-                        if preset_path.exists() {
-                            if let Ok(content) = fs::read_to_string(&preset_path) {
-                                match serde_json::from_str::<SoundscapePreset>(&content) {
-                                    Ok(preset) => {
-                                        println!("[Scheduler] Loaded preset with {} sounds", preset.sounds.len());
-                                    // Get current active ambient IDs
-                                    let current_ids: std::collections::HashSet<String> = {
-                                        active_ambients_clone.lock().keys().cloned().collect()
-                                    };
-                                    
-                                    // Get new preset sound IDs
-                                    let new_ids: std::collections::HashSet<String> = preset.sounds
-                                        .iter()
-                                        .filter(|s| s.enabled)
-                                        .map(|s| s.sound_id.clone())
-                                        .collect();
-                                    
-                                    // Stop sounds not in new preset (with scheduler fade)
-                                    for id in current_ids.difference(&new_ids) {
-                                        println!("[Scheduler] Fading out removed sound: {}", id);
-                                        scheduler_fading_out.insert(id.clone(), 0.0);
-                                    }
-                                    
-                                    // Start or update sounds in new preset
-                                    for sound in &preset.sounds {
-                                        if !sound.enabled {
-                                            continue;
-                                        }
-                                        
-                                        let new_settings = AmbientSettings {
-                                            volume: sound.volume as f32 / 100.0,
-                                            pitch: sound.pitch,
-                                            pan: sound.pan as f32 / 100.0,
-                                            low_pass_freq: sound.low_pass_freq as f32,
-                                            reverb_type: "off".to_string(),
-                                            algorithmic_reverb: sound.algorithmic_reverb as f32 / 100.0,
-                                            repeat_min: sound.repeat_range_min,
-                                            repeat_max: sound.repeat_range_max,
-                                            pause_min: sound.pause_range_min,
-                                            pause_max: sound.pause_range_max,
-                                            volume_variation: sound.volume_variation as f32 / 100.0,
-                                        };
-                                        
-                                        let id = sound.sound_id.clone();
-                                        // Construct full path from category_path + filename
-                                        let file_a = if sound.files_a.is_empty() {
-                                            String::new()
-                                        } else {
-                                            let base_path = std::path::Path::new(&sound.category_path);
-                                            base_path.join(&sound.files_a).to_string_lossy().to_string()
-                                        };
-                                        let file_b = if sound.files_b.is_empty() {
-                                            String::new()
-                                        } else {
-                                            let base_path = std::path::Path::new(&sound.category_path);
-                                            base_path.join(&sound.files_b).to_string_lossy().to_string()
-                                        };
-                                        
-                                        // Check if already playing and if settings changed
-                                        let (already_playing, settings_changed) = {
-                                            let active = active_ambients_clone.lock();
-                                            if let Some(info) = active.get(&id) {
-                                                // Check if audio-affecting settings changed (pitch, pan, low_pass, reverb)
-                                                let old = &info.settings;
-                                                let changed = (old.pitch - new_settings.pitch).abs() > 0.001
-                                                    || (old.pan - new_settings.pan).abs() > 0.001
-                                                    || (old.low_pass_freq - new_settings.low_pass_freq).abs() > 1.0
-                                                    || (old.algorithmic_reverb - new_settings.algorithmic_reverb).abs() > 0.001;
-                                                (true, changed)
-                                            } else {
-                                                (false, false)
-                                            }
-                                        };
-                                        
-                                        if already_playing && settings_changed {
-                                            // Settings changed - immediately stop old and start new with fade-in
-                                            println!("[Scheduler] Settings changed for {}, restarting with new settings", id);
-                                            // Immediately stop the old sound (don't use fade-out queue since we'll reuse the ID)
-                                            if let Some(state) = ambient_states.remove(&id) {
-                                                state.sink.stop();
-                                            }
-                                            active_ambients_clone.lock().remove(&id);
-                                            // Remove from fade-out queue in case it's there
-                                            scheduler_fading_out.remove(&id);
-                                            // Queue the new sound to start with fade-in
-                                            let _ = command_tx_clone.send(AudioCommand::PlayAmbientScheduler {
-                                                id,
-                                                file_a,
-                                                file_b,
-                                                settings: new_settings,
-                                            });
-                                        } else if already_playing {
-                                            // Same settings - just update volume-related settings
-                                            println!("[Scheduler] Keeping sound {} (same settings)", id);
-                                            if let Some(state) = active_ambients_clone.lock().get_mut(&id) {
-                                                state.settings.volume = new_settings.volume;
-                                                state.settings.volume_variation = new_settings.volume_variation;
-                                            }
-                                        } else {
-                                            // New sound - start it
-                                            println!("[Scheduler] Starting new sound: {}", id);
-                                            let _ = command_tx_clone.send(AudioCommand::PlayAmbientScheduler {
-                                                id,
-                                                file_a,
-                                                file_b,
-                                                settings: new_settings,
-                                            });
-                                        }
-                                    }
-                                    }
-                                    Err(e) => {
-                                        println!("[Scheduler] ERROR parsing preset: {}", e);
-                                    }
-                                }
-                            } else {
-                                println!("[Scheduler] ERROR reading preset file");
-                            }
-                        }
-                    }
-                }
-                
-                // Handle pending auto-advance (play next track in playlist)
-                if let Some((file_path, track_info)) = pending_auto_advance.take() {
-                    // Reset fade states for new track
-                    fade_out_active = false;
-                    sample_buffer_clone.clear();
-                    *current_track_clone.lock() = Some(track_info);
-                    
-                    match File::open(&file_path) {
-                        Ok(file) => {
-                            let reader = BufReader::new(file);
-                            match Decoder::new(reader) {
-                                Ok(source) => {
-                                    let duration = source.total_duration()
-                                        .map(|d| d.as_secs_f64())
-                                        .unwrap_or(0.0);
-                                    
-                                    let source_f32 = source.convert_samples::<f32>();
-                                    let analyzing_source = AnalyzingSource::new(
-                                        source_f32,
-                                        sample_buffer_clone.clone()
-                                    );
-                                    
-                                    match Sink::try_new(&stream_handle) {
-                                        Ok(sink) => {
-                                            let start_vol = if crossfade_duration > 0.0 {
-                                                fade_in_progress = Some((Instant::now(), crossfade_duration));
-                                                0.0
-                                            } else if is_muted || is_master_muted {
-                                                0.0
-                                            } else {
-                                                music_volume * master_volume
-                                            };
-                                            sink.set_volume(start_vol);
-                                            sink.append(analyzing_source);
-                                            
-                                            track_start = Some(Instant::now());
-                                            track_duration = duration;
-                                            current_sink = Some(sink);
-                                            
-                                            let mut prog = progress_clone.lock();
-                                            prog.current_time = 0.0;
-                                            prog.duration = duration;
-                                            prog.is_playing = true;
-                                            prog.is_finished = false;
-                                        }
-                                        Err(e) => eprintln!("Auto-advance: Failed to create sink: {}", e),
-                                    }
-                                }
-                                Err(e) => eprintln!("Auto-advance: Failed to decode audio: {}", e),
-                            }
-                        }
-                        Err(e) => eprintln!("Auto-advance: Failed to open file {}: {}", file_path, e),
-                    }
-                }
-                
-                // Check if soundboard finished playing
-                if let Some(ref sink) = soundboard_sink {
-                    if sink.empty() {
-                        duck_target = 0.0; // Start fading out ducking
-                        soundboard_sink = None;
-                        *soundboard_playing_clone.lock() = false;
-                    }
-                }
-                
-                // Smoothly fade duck_progress toward duck_target
-                if duck_progress < duck_target {
-                    duck_progress = (duck_progress + DUCK_FADE_SPEED).min(duck_target);
-                } else if duck_progress > duck_target {
-                    duck_progress = (duck_progress - DUCK_FADE_SPEED).max(duck_target);
-                }
-                
-                // Apply ducking to music volume (gradual)
-                let target_vol = if is_muted || is_master_muted {
-                    0.0
-                } else {
-                    let base_vol = music_volume * master_volume;
-                    // Apply gradual ducking based on duck_progress
-                    base_vol * (1.0 - duck_progress * duck_amount)
-                };
-                
-                // Update music sink volume during ducking transitions
-                if duck_progress > 0.0 || duck_target != duck_progress {
-                    if let Some(ref sink) = current_sink {
-                        if fade_in_progress.is_none() {
-                            sink.set_volume(target_vol);
-                        }
-                    }
-                    // Update ambient volumes during ducking transitions
-                    for state in ambient_states.values() {
-                        let vol = calc_ambient_volume(
-                            &state.settings, ambient_master_volume, master_volume,
-                            is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                        );
-                        state.sink.set_volume(vol);
-                    }
-                }
-                
-                // Handle fade-in for new tracks
-                if let Some((fade_start, fade_duration)) = fade_in_progress {
-                    let elapsed = fade_start.elapsed().as_secs_f32();
-                    let progress = (elapsed / fade_duration).clamp(0.0, 1.0);
-                    
-                    if let Some(ref sink) = current_sink {
-                        sink.set_volume(target_vol * progress);
-                    }
-                    
-                    // Fade-in complete
-                    if progress >= 1.0 {
-                        fade_in_progress = None;
-                        if let Some(ref sink) = current_sink {
-                            sink.set_volume(target_vol);
-                        }
-                    }
-                }
-                
-                // Handle automatic fade-out near end of track
-                if crossfade_duration > 0.0 && !fade_out_active {
-                    if let (Some(start), Some(ref sink)) = (track_start, &current_sink) {
-                        if !sink.is_paused() && !sink.empty() {
-                            let current_time = start.elapsed().as_secs_f64();
-                            let time_remaining = track_duration - current_time;
-                            
-                            // Start fade-out when we're within crossfade_duration of the end
-                            if time_remaining > 0.0 && time_remaining <= crossfade_duration as f64 {
-                                fade_out_active = true;
-                            }
-                        }
-                    }
-                }
-                
-                // Apply fade-out volume
-                if fade_out_active {
-                    if let (Some(start), Some(ref sink)) = (track_start, &current_sink) {
-                        let current_time = start.elapsed().as_secs_f64();
-                        let time_remaining = (track_duration - current_time).max(0.0);
-                        let fade_progress = 1.0 - (time_remaining / crossfade_duration as f64).clamp(0.0, 1.0);
-                        
-                        // Only apply fade-out if we're not also fading in (which takes precedence)
-                        if fade_in_progress.is_none() {
-                            sink.set_volume(target_vol * (1.0 - fade_progress as f32));
-                        }
-                    }
-                }
-                
-                // Update progress and handle auto-advance
-                if let Some(ref sink) = current_sink {
-                    let is_empty = sink.empty();
-                    let is_paused = sink.is_paused();
-                    
-                    let mut prog = progress_clone.lock();
-                    prog.is_finished = is_empty;
-                    prog.is_playing = !is_empty && !is_paused;
-                    prog.duration = track_duration;
-                    if let Some(start) = track_start {
-                        if !is_paused {
-                            prog.current_time = start.elapsed().as_secs_f64();
-                        }
-                    }
-                    
-                    // Auto-advance: if we were playing and track just finished, queue next track
-                    if was_playing && is_empty && pending_auto_advance.is_none() {
-                        // Get playlist state and determine next track
-                        let ps = playlist_state_clone.lock().clone();
-                        if let Some(ref playlist_id) = ps.current_playlist_id {
-                            let all_tracks = all_tracks_clone.lock();
-                            let playlists = playlists_clone.lock();
-                            
-                            // Get tracks for current playlist
-                            let tracks: Option<Vec<PlaylistTrack>> = if playlist_id.starts_with("album-") {
-                                // Album playlist - filter all_tracks by album name
-                                let album_name = playlist_id.strip_prefix("album-").unwrap_or("");
-                                let album_tracks: Vec<PlaylistTrack> = all_tracks.iter()
-                                    .filter(|t| t.album == album_name)
-                                    .cloned()
-                                    .collect();
-                                if !album_tracks.is_empty() { Some(album_tracks) } else { None }
-                            } else if playlist_id == "all-music" {
-                                Some(all_tracks.clone())
-                            } else if playlist_id == "favorites" {
-                                let fav_tracks: Vec<PlaylistTrack> = all_tracks.iter()
-                                    .filter(|t| ps.favorites.contains(&t.id))
-                                    .cloned()
-                                    .collect();
-                                if !fav_tracks.is_empty() { Some(fav_tracks) } else { None }
-                            } else {
-                                // Custom playlist
-                                playlists.get(playlist_id).map(|p| p.tracks.clone())
-                            };
-                            
-                            if let Some(tracks) = tracks {
-                                if !tracks.is_empty() {
-                                    // Calculate next index
-                                    let current_idx = ps.current_index as usize;
-                                    let next_idx = if ps.is_shuffled {
-                                        // Random next track
-                                        rand::random::<usize>() % tracks.len()
-                                    } else {
-                                        // Sequential
-                                        let next = current_idx + 1;
-                                        if next >= tracks.len() {
-                                            if ps.is_looping { 0 } else { tracks.len() } // Stop if not looping
-                                        } else {
-                                            next
-                                        }
-                                    };
-                                    
-                                    if next_idx < tracks.len() {
-                                        let next_track = &tracks[next_idx];
-                                        let file_path = format!("{}/{}", next_track.album_path, next_track.file);
-                                        let track_info = CurrentTrackInfo {
-                                            id: next_track.id.clone(),
-                                            title: next_track.title.clone(),
-                                            artist: next_track.artist.clone(),
-                                            album: next_track.album.clone(),
-                                            file_path: file_path.clone(),
-                                        };
-                                        
-                                        // Update playlist state
-                                        drop(all_tracks);
-                                        drop(playlists);
-                                        playlist_state_clone.lock().current_index = next_idx as i32;
-                                        
-                                        pending_auto_advance = Some((file_path, track_info));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    was_playing = !is_empty && !is_paused;
-                }
-                
-                // Update playback state for visualization with FFT
-                {
-                    let music_playing = current_sink.as_ref()
-                        .map(|s| !s.empty() && !s.is_paused())
-                        .unwrap_or(false);
-                    
-                    let active_ambient_count = ambient_states.values()
-                        .filter(|s| !s.is_paused && !s.sink.empty())
-                        .count() as u32;
-                    
-                    let effective_music_vol = if is_muted || is_master_muted { 0.0 } else { music_volume * master_volume };
-                    let effective_ambient_vol = if is_ambient_muted || is_master_muted { 0.0 } else { ambient_master_volume * master_volume };
-                    
-                    // Perform FFT on sample buffer (lock-free read)
-                    let mut frequencies = vec![0.0f32; FFT_SIZE];
-                    {
-                        let samples = sample_buffer_clone.get_latest(1024);
-                        // Copy samples to FFT buffer with Hann window
-                        for (i, &sample) in samples.iter().enumerate() {
-                            let window = 0.5 * (1.0 - (2.0 * std::f32::consts::PI * i as f32 / 1023.0).cos());
-                            fft_buffer[i] = Complex::new(sample * window, 0.0);
-                        }
-                        
-                        // Run FFT
-                        fft.process(&mut fft_buffer);
-                        
-                        // Convert to magnitudes and bin into FFT_SIZE buckets
-                        let bins_per_bucket = 512 / FFT_SIZE; // Only use first half (positive frequencies)
-                        
-                        for i in 0..FFT_SIZE {
-                            let mut sum = 0.0f32;
-                            for j in 0..bins_per_bucket {
-                                let idx = i * bins_per_bucket + j;
-                                if idx < 512 {
-                                    sum += fft_buffer[idx].norm();
-                                }
-                            }
-                            // Average the bin values
-                            let mag = sum / bins_per_bucket as f32;
-                            // Use log scale for better dynamic range
-                            let log_mag = (1.0 + mag * 50.0).ln() / 5.0;
-                            frequencies[i] = log_mag.clamp(0.0, 1.0);
-                        }
-                    }
-                    
-                    // Compute ambient frequencies from ambient sample buffer (same FFT approach)
-                    let mut ambient_frequencies = vec![0.0f32; FFT_SIZE];
-                    if active_ambient_count > 0 {
-                        let ambient_samples = ambient_sample_buffer_clone.get_latest(1024);
-                        if ambient_samples.len() >= 1024 {
-                            let mut planner = FftPlanner::new();
-                            let fft = planner.plan_fft_forward(1024);
-                            let mut ambient_fft_buffer: Vec<Complex<f32>> = ambient_samples.iter()
-                                .take(1024)
-                                .map(|&s| Complex::new(s, 0.0))
-                                .collect();
-                            fft.process(&mut ambient_fft_buffer);
-                            
-                            // Convert to frequency bins (same logic as music FFT)
-                            let bins_per_bucket = 512 / FFT_SIZE;
-                            for i in 0..FFT_SIZE {
-                                let mut sum = 0.0f32;
-                                for j in 0..bins_per_bucket {
-                                    let idx = i * bins_per_bucket + j;
-                                    if idx < 512 {
-                                        sum += ambient_fft_buffer[idx].norm();
-                                    }
-                                }
-                                let mag = sum / bins_per_bucket as f32;
-                                let log_mag = (1.0 + mag * 50.0).ln() / 5.0;
-                                ambient_frequencies[i] = log_mag.clamp(0.0, 1.0);
-                            }
-                        }
-                    }
-                    
-                    let mut state = playback_state_clone.lock();
-                    state.music_playing = music_playing;
-                    state.music_volume = effective_music_vol;
-                    state.ambient_count = active_ambient_count;
-                    state.ambient_volume = effective_ambient_vol;
-                    state.master_volume = master_volume;
-                    state.is_muted = is_master_muted;
-                    state.frequencies = frequencies;
-                    state.ambient_frequencies = ambient_frequencies;
-                }
-                
-                // Check for commands (non-blocking with timeout)
-                match command_rx.recv_timeout(std::time::Duration::from_millis(50)) {
-                    Ok(cmd) => match cmd {
-                        AudioCommand::Play { file_path, track_info } => {
-                            // Stop current track immediately (fade-out already happened or manual skip)
-                            if let Some(old_sink) = current_sink.take() {
-                                old_sink.stop();
-                            }
-                            
-                            // Reset fade states for new track
-                            fade_out_active = false;
-                            
-                            // Clear sample buffer for new track
-                            sample_buffer_clone.clear();
-                            
-                            // Store current track info
-                            *current_track_clone.lock() = Some(track_info);
-                            
-                            // Load and play new file
-                            match File::open(&file_path) {
-                                Ok(file) => {
-                                    let reader = BufReader::new(file);
-                                    match Decoder::new(reader) {
-                                        Ok(source) => {
-                                            let duration = source.total_duration()
-                                                .map(|d| d.as_secs_f64())
-                                                .unwrap_or(0.0);
-                                            
-                                            // Convert to f32 samples and wrap with AnalyzingSource for FFT
-                                            let source_f32 = source.convert_samples::<f32>();
-                                            let analyzing_source = AnalyzingSource::new(
-                                                source_f32,
-                                                sample_buffer_clone.clone()
-                                            );
-                                            
-                                            match Sink::try_new(&stream_handle) {
-                                                Ok(sink) => {
-                                                    // Start at 0 volume and fade in if crossfade enabled
-                                                    let start_vol = if crossfade_duration > 0.0 {
-                                                        fade_in_progress = Some((Instant::now(), crossfade_duration));
-                                                        0.0
-                                                    } else if is_muted || is_master_muted {
-                                                        0.0
-                                                    } else {
-                                                        music_volume * master_volume
-                                                    };
-                                                    sink.set_volume(start_vol);
-                                                    sink.append(analyzing_source);
-                                                    
-                                                    track_start = Some(Instant::now());
-                                                    track_duration = duration;
-                                                    current_sink = Some(sink);
-                                                    
-                                                    let mut prog = progress_clone.lock();
-                                                    prog.current_time = 0.0;
-                                                    prog.duration = duration;
-                                                    prog.is_playing = true;
-                                                    prog.is_finished = false;
-                                                }
-                                                Err(e) => eprintln!("Failed to create sink: {}", e),
-                                            }
-                                        }
-                                        Err(e) => eprintln!("Failed to decode audio: {}", e),
-                                    }
-                                }
-                                Err(e) => eprintln!("Failed to open file {}: {}", file_path, e),
-                            }
-                        }
-                        AudioCommand::Stop => {
-                            if let Some(sink) = current_sink.take() {
-                                sink.stop();
-                            }
-                            track_start = None;
-                            *current_track_clone.lock() = None;
-                            let mut prog = progress_clone.lock();
-                            prog.is_playing = false;
-                            prog.is_finished = true;
-                        }
-                        AudioCommand::Pause => {
-                            if let Some(ref sink) = current_sink {
-                                sink.pause();
-                                pause_start = Some(Instant::now());
-                            }
-                        }
-                        AudioCommand::Resume => {
-                            if let Some(ref sink) = current_sink {
-                                sink.play();
-                                // Adjust track_start to account for pause duration
-                                if let (Some(ps), Some(ts)) = (pause_start.take(), track_start) {
-                                    let pause_duration = ps.elapsed();
-                                    track_start = Some(ts + pause_duration);
-                                }
-                            }
-                        }
-                        AudioCommand::Seek(position) => {
-                            // Seeking requires reloading the file and skipping to position
-                            if let Some(track_info) = current_track_clone.lock().clone() {
-                                if let Some(old_sink) = current_sink.take() {
-                                    old_sink.stop();
-                                }
-                                sample_buffer_clone.clear();
-                                
-                                if let Ok(file) = File::open(&track_info.file_path) {
-                                    let reader = BufReader::new(file);
-                                    if let Ok(source) = Decoder::new(reader) {
-                                        let duration = source.total_duration()
-                                            .map(|d| d.as_secs_f64())
-                                            .unwrap_or(0.0);
-                                        
-                                        // Skip to the desired position
-                                        let skip_duration = std::time::Duration::from_secs_f64(position.min(duration).max(0.0));
-                                        let source_f32 = source.convert_samples::<f32>();
-                                        let skipped_source = source_f32.skip_duration(skip_duration);
-                                        let analyzing_source = AnalyzingSource::new(
-                                            skipped_source,
-                                            sample_buffer_clone.clone()
-                                        );
-                                        
-                                        if let Ok(sink) = Sink::try_new(&stream_handle) {
-                                            let effective_vol = if is_muted || is_master_muted {
-                                                0.0
-                                            } else {
-                                                music_volume * master_volume
-                                            };
-                                            sink.set_volume(effective_vol);
-                                            sink.append(analyzing_source);
-                                            
-                                            track_start = Some(Instant::now() - skip_duration);
-                                            track_duration = duration;
-                                            current_sink = Some(sink);
-                                            
-                                            let mut prog = progress_clone.lock();
-                                            prog.current_time = position;
-                                            prog.duration = duration;
-                                            prog.is_playing = true;
-                                            prog.is_finished = false;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        AudioCommand::SetVolume(vol) => {
-                            music_volume = vol;
-                            if let Some(ref sink) = current_sink {
-                                let effective_vol = if is_muted || is_master_muted {
-                                    0.0
-                                } else {
-                                    music_volume * master_volume
-                                };
-                                sink.set_volume(effective_vol);
-                            }
-                        }
-                        AudioCommand::SetMasterVolume(vol) => {
-                            master_volume = vol;
-                            // Update music volume
-                            if let Some(ref sink) = current_sink {
-                                let effective_vol = if is_muted || is_master_muted {
-                                    0.0
-                                } else {
-                                    music_volume * master_volume
-                                };
-                                sink.set_volume(effective_vol);
-                            }
-                            // Update ambient volumes
-                            for state in ambient_states.values() {
-                                let effective_vol = calc_ambient_volume(
-                                    &state.settings, ambient_master_volume, master_volume,
-                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                );
-                                state.sink.set_volume(effective_vol);
-                            }
-                            // Update soundboard volume
-                            if let Some(ref sink) = soundboard_sink {
-                                let effective_vol = if soundboard_muted || is_master_muted { 0.0 } else { soundboard_volume * master_volume };
-                                sink.set_volume(effective_vol);
-                            }
-                        }
-                        AudioCommand::SetMuted(muted) => {
-                            is_muted = muted;
-                            if let Some(ref sink) = current_sink {
-                                let effective_vol = if is_muted || is_master_muted {
-                                    0.0
-                                } else {
-                                    music_volume * master_volume
-                                };
-                                sink.set_volume(effective_vol);
-                            }
-                        }
-                        AudioCommand::SetMasterMuted(muted) => {
-                            is_master_muted = muted;
-                            // Update music volume
-                            if let Some(ref sink) = current_sink {
-                                let effective_vol = if is_muted || is_master_muted {
-                                    0.0
-                                } else {
-                                    music_volume * master_volume
-                                };
-                                sink.set_volume(effective_vol);
-                            }
-                            // Update ambient volumes
-                            for state in ambient_states.values() {
-                                let effective_vol = calc_ambient_volume(
-                                    &state.settings, ambient_master_volume, master_volume,
-                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                );
-                                state.sink.set_volume(effective_vol);
-                            }
-                            // Update soundboard volume
-                            if let Some(ref sink) = soundboard_sink {
-                                let effective_vol = if soundboard_muted || is_master_muted { 0.0 } else { soundboard_volume * master_volume };
-                                sink.set_volume(effective_vol);
-                            }
-                        }
-                        AudioCommand::SetCrossfadeDuration(duration) => {
-                            crossfade_duration = duration;
-                        }
-                        // Soundboard commands
-                        AudioCommand::PlaySoundboard { file_path, volume: _ } => {
-                            // Stop any current soundboard sound
-                            if let Some(old_sink) = soundboard_sink.take() {
-                                old_sink.stop();
-                            }
-                            
-                            // Start ducking (gradual fade handled by main loop)
-                            duck_target = 1.0;
-                            
-                            // Load and play soundboard sound
-                            match File::open(&file_path) {
-                                Ok(file) => {
-                                    let reader = BufReader::new(file);
-                                    match Decoder::new(reader) {
-                                        Ok(source) => {
-                                            match Sink::try_new(&stream_handle) {
-                                                Ok(sink) => {
-                                                    // Use stored soundboard volume/mute state
-                                                    let effective_vol = if soundboard_muted || is_master_muted {
-                                                        0.0
-                                                    } else {
-                                                        soundboard_volume * master_volume
-                                                    };
-                                                    sink.set_volume(effective_vol);
-                                                    sink.append(source.convert_samples::<f32>());
-                                                    soundboard_sink = Some(sink);
-                                                    *soundboard_playing_clone.lock() = true;
-                                                }
-                                                Err(e) => eprintln!("Failed to create soundboard sink: {}", e),
-                                            }
-                                        }
-                                        Err(e) => eprintln!("Failed to decode soundboard file: {}", e),
-                                    }
-                                }
-                                Err(e) => eprintln!("Failed to open soundboard file {}: {}", file_path, e),
-                            }
-                        }
-                        AudioCommand::StopSoundboard => {
-                            if let Some(sink) = soundboard_sink.take() {
-                                sink.stop();
-                            }
-                            duck_target = 0.0; // Start fading out ducking (gradual restore handled by main loop)
-                            *soundboard_playing_clone.lock() = false;
-                        }
-                        AudioCommand::SetDuckAmount(amount) => {
-                            duck_amount = amount;
-                        }
-                        AudioCommand::SetSoundboardVolume(volume) => {
-                            soundboard_volume = volume;
-                            // Apply to currently playing soundboard
-                            if let Some(ref sink) = soundboard_sink {
-                                let effective_vol = if soundboard_muted || is_master_muted { 0.0 } else { soundboard_volume * master_volume };
-                                sink.set_volume(effective_vol);
-                            }
-                        }
-                        AudioCommand::SetSoundboardMuted(muted) => {
-                            soundboard_muted = muted;
-                            // Apply to currently playing soundboard
-                            if let Some(ref sink) = soundboard_sink {
-                                let effective_vol = if soundboard_muted || is_master_muted { 0.0 } else { soundboard_volume * master_volume };
-                                sink.set_volume(effective_vol);
-                            }
-                        }
-                        // Ambient sound commands with A/B crossfade
-                        AudioCommand::PlayAmbient { id, file_a, file_b, settings } => {
-                            // Stop existing ambient sound with this ID if any
-                            if let Some(old_state) = ambient_states.remove(&id) {
-                                old_state.sink.stop();
-                            }
-                            
-                            // Create sink and start with file A
-                            match Sink::try_new(&stream_handle) {
-                                Ok(sink) => {
-                                    // Try to load from cache first, fall back to disk (read into memory)
-                                    let bytes = if let Some(cached_bytes) = audio_cache.get(&file_a) {
-                                        Some(cached_bytes.clone())
-                                    } else {
-                                        // Fall back to disk read into memory
-                                        File::open(&file_a).ok().and_then(|mut f| {
-                                            let mut bytes = Vec::new();
-                                            f.read_to_end(&mut bytes).ok().map(|_| bytes)
-                                        })
-                                    };
-                                    
-                                    if let Some(bytes) = bytes {
-                                    if let Ok(source) = Decoder::new(Cursor::new(bytes)) {
-                                        // Apply pitch, pan, low-pass filter
-                                        let sample_rate = source.sample_rate();
-                                        let source = source.speed(settings.pitch).convert_samples::<f32>();
-                                        let source = PannedSource::new(source, settings.pan);
-                                        let source = LowPassSource::new(source, settings.low_pass_freq, sample_rate);
-                                        
-                                        // Start at 0 volume for fade-in
-                                        sink.set_volume(0.0);
-                                        
-                                        // Apply reverb then wrap with amplitude tracking
-                                        let source = ReverbSource::new(source, settings.algorithmic_reverb, sample_rate);
-                                        let source = AmbientAnalyzingSource::new(source, ambient_sample_buffer_clone.clone());
-                                        sink.append(source);
-                                        
-                                        // Start fade-in
-                                        fading_in.insert(id.clone(), 0.0);
-                                        
-                                        // Determine initial loop count
-                                        let mut rng = rand::thread_rng();
-                                        let loops = rng.gen_range(settings.repeat_min..=settings.repeat_max);
-                                        
-                                        ambient_states.insert(id.clone(), AmbientState {
-                                            sink,
-                                            file_a: file_a.clone(),
-                                            file_b: file_b.clone(),
-                                            settings: settings.clone(),
-                                            is_playing_a: true,
-                                            loops_remaining: loops,
-                                            pause_remaining: 0.0,
-                                            is_paused: false,
-                                        });
-                                        
-                                        // Track in shared state for querying
-                                        {
-                                            let mut active = active_ambients_clone.lock();
-                                            active.insert(id.clone(), ActiveAmbientInfo {
-                                                id,
-                                                file_a,
-                                                file_b,
-                                                settings,
-                                            });
-                                        }
-                                    }
-                                    }
-                                }
-                                Err(e) => eprintln!("Failed to create ambient sink: {}", e),
-                            }
-                        }
-                        AudioCommand::StopAmbient(id) => {
-                            // Start fade-out instead of immediate stop
-                            if ambient_states.contains_key(&id) && !fading_out.contains_key(&id) {
-                                fading_out.insert(id, 0.0);
-                            }
-                        }
-                        AudioCommand::StopAllAmbient => {
-                            // Stop all ambient sounds with fade-out
-                            let ids: Vec<String> = ambient_states.keys().cloned().collect();
-                            for id in ids {
-                                if !fading_out.contains_key(&id) && !scheduler_fading_out.contains_key(&id) {
-                                    // Use scheduler fade for smoother transition
-                                    scheduler_fading_out.insert(id, 0.0);
-                                }
-                            }
-                        }
-                        AudioCommand::UpdateAmbientSettings { id, settings } => {
-                            if let Some(state) = ambient_states.get_mut(&id) {
-                                let pitch_changed = (state.settings.pitch - settings.pitch).abs() > 0.001;
-                                let pan_changed = (state.settings.pan - settings.pan).abs() > 0.001;
-                                let low_pass_changed = (state.settings.low_pass_freq - settings.low_pass_freq).abs() > 1.0;
-                                let reverb_changed = (state.settings.algorithmic_reverb - settings.algorithmic_reverb).abs() > 0.001
-                                    || state.settings.reverb_type != settings.reverb_type;
-                                state.settings = settings.clone();
-                                
-                                // Update shared state with new settings
-                                {
-                                    let mut active = active_ambients_clone.lock();
-                                    if let Some(info) = active.get_mut(&id) {
-                                        info.settings = settings;
-                                    }
-                                }
-                                
-                                // If pitch, pan, low-pass, or reverb changed, restart current file with new settings
-                                if pitch_changed || pan_changed || low_pass_changed || reverb_changed {
-                                    state.sink.stop();
-                                    // Create new sink
-                                    if let Ok(new_sink) = Sink::try_new(&stream_handle) {
-                                        let file_path = if state.is_playing_a {
-                                            &state.file_a
-                                        } else {
-                                            &state.file_b
-                                        };
-                                        // Try cache first, fall back to disk read into memory
-                                        let bytes = if let Some(cached) = audio_cache.get(file_path) {
-                                            Some(cached.clone())
-                                        } else {
-                                            File::open(file_path).ok().and_then(|mut f| {
-                                                let mut b = Vec::new();
-                                                f.read_to_end(&mut b).ok().map(|_| b)
-                                            })
-                                        };
-                                        if let Some(bytes) = bytes {
-                                        if let Ok(source) = Decoder::new(Cursor::new(bytes)) {
-                                            let sample_rate = source.sample_rate();
-                                            let source = source.speed(state.settings.pitch).convert_samples::<f32>();
-                                            let source = PannedSource::new(source, state.settings.pan);
-                                            let source = LowPassSource::new(source, state.settings.low_pass_freq, sample_rate);
-                                            let effective_vol = calc_ambient_volume(
-                                                &state.settings, ambient_master_volume, master_volume,
-                                                is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                            );
-                                            new_sink.set_volume(effective_vol);
-                                            let source = ReverbSource::new(source, state.settings.algorithmic_reverb, sample_rate);
-                                            let source = AmbientAnalyzingSource::new(source, ambient_sample_buffer_clone.clone());
-                                            new_sink.append(source);
-                                            state.sink = new_sink;
-                                        }
-                                        }
-                                    }
-                                } else {
-                                    // Smooth volume transition - set target and let the loop interpolate
-                                    let target_vol = calc_ambient_volume(
-                                        &state.settings, ambient_master_volume, master_volume,
-                                        is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                    );
-                                    // Get current volume (or use sink's current if not transitioning)
-                                    let current_vol = volume_transitions.get(&id)
-                                        .map(|(c, _)| *c)
-                                        .unwrap_or_else(|| state.sink.volume());
-                                    volume_transitions.insert(id.clone(), (current_vol, target_vol));
-                                }
-                            }
-                        }
-                        AudioCommand::SetAmbientMasterVolume(vol) => {
-                            ambient_master_volume = vol;
-                            for state in ambient_states.values() {
-                                let effective_vol = calc_ambient_volume(
-                                    &state.settings, ambient_master_volume, master_volume,
-                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                );
-                                state.sink.set_volume(effective_vol);
-                            }
-                        }
-                        AudioCommand::SetAmbientMuted(muted) => {
-                            is_ambient_muted = muted;
-                            for state in ambient_states.values() {
-                                let effective_vol = calc_ambient_volume(
-                                    &state.settings, ambient_master_volume, master_volume,
-                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                );
-                                state.sink.set_volume(effective_vol);
-                            }
-                        }
-                        AudioCommand::PreloadAmbient(paths) => {
-                            // Preload audio files into memory cache to avoid disk I/O during playback
-                            for path in paths {
-                                if !audio_cache.contains_key(&path) {
-                                    if let Ok(mut file) = File::open(&path) {
-                                        let mut bytes = Vec::new();
-                                        if file.read_to_end(&mut bytes).is_ok() {
-                                            audio_cache.insert(path, bytes);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        // Scheduler-specific commands with 2000ms fade times
-                        AudioCommand::PlayAmbientScheduler { id, file_a, file_b, settings } => {
-                            println!("[Scheduler] PlayAmbientScheduler: id={}, file_a={}", id, file_a);
-                            // Stop existing ambient sound with this ID if any (with scheduler fade)
-                            if ambient_states.contains_key(&id) && !scheduler_fading_out.contains_key(&id) {
-                                scheduler_fading_out.insert(id.clone(), 0.0);
-                            }
-                            
-                            // Create sink and start with file A
-                            match Sink::try_new(&stream_handle) {
-                                Ok(sink) => {
-                                    let bytes = if let Some(cached_bytes) = audio_cache.get(&file_a) {
-                                        println!("[Scheduler] Using cached audio for {}", id);
-                                        Some(cached_bytes.clone())
-                                    } else {
-                                        println!("[Scheduler] Loading audio file: {}", file_a);
-                                        File::open(&file_a).ok().and_then(|mut f| {
-                                            let mut bytes = Vec::new();
-                                            f.read_to_end(&mut bytes).ok().map(|_| bytes)
-                                        })
-                                    };
-                                    
-                                    if let Some(bytes) = bytes {
-                                        println!("[Scheduler] Audio loaded, {} bytes", bytes.len());
-                                    if let Ok(source) = Decoder::new(Cursor::new(bytes)) {
-                                        let sample_rate = source.sample_rate();
-                                        let source = source.speed(settings.pitch).convert_samples::<f32>();
-                                        let source = PannedSource::new(source, settings.pan);
-                                        let source = LowPassSource::new(source, settings.low_pass_freq, sample_rate);
-                                        
-                                        // Start at 0 volume for scheduler fade-in (2000ms)
-                                        sink.set_volume(0.0);
-                                        
-                                        let source = ReverbSource::new(source, settings.algorithmic_reverb, sample_rate);
-                                        let source = AmbientAnalyzingSource::new(source, ambient_sample_buffer_clone.clone());
-                                        sink.append(source);
-                                        
-                                        // Start scheduler fade-in (2000ms)
-                                        scheduler_fading_in.insert(id.clone(), 0.0);
-                                        
-                                        let mut rng = rand::thread_rng();
-                                        let loops = rng.gen_range(settings.repeat_min..=settings.repeat_max);
-                                        
-                                        ambient_states.insert(id.clone(), AmbientState {
-                                            sink,
-                                            file_a: file_a.clone(),
-                                            file_b: file_b.clone(),
-                                            settings: settings.clone(),
-                                            is_playing_a: true,
-                                            loops_remaining: loops,
-                                            pause_remaining: 0.0,
-                                            is_paused: false,
-                                        });
-                                        
-                                        {
-                                            let mut active = active_ambients_clone.lock();
-                                            active.insert(id.clone(), ActiveAmbientInfo {
-                                                id,
-                                                file_a,
-                                                file_b,
-                                                settings,
-                                            });
-                                        }
-                                    }
-                                    }
-                                }
-                                Err(e) => eprintln!("Failed to create ambient sink: {}", e),
-                            }
-                        }
-                        AudioCommand::StopAmbientScheduler(id) => {
-                            // Start scheduler fade-out (2000ms) instead of immediate stop
-                            if ambient_states.contains_key(&id) && !scheduler_fading_out.contains_key(&id) {
-                                // Remove from regular fading if present
-                                fading_out.remove(&id);
-                                scheduler_fading_out.insert(id, 0.0);
-                            }
-                        }
-                        AudioCommand::UpdateAmbientSettingsScheduler { id, settings } => {
-                            if let Some(state) = ambient_states.get_mut(&id) {
-                                let pitch_changed = (state.settings.pitch - settings.pitch).abs() > 0.001;
-                                let pan_changed = (state.settings.pan - settings.pan).abs() > 0.001;
-                                let low_pass_changed = (state.settings.low_pass_freq - settings.low_pass_freq).abs() > 1.0;
-                                let reverb_changed = (state.settings.algorithmic_reverb - settings.algorithmic_reverb).abs() > 0.001
-                                    || state.settings.reverb_type != settings.reverb_type;
-                                state.settings = settings.clone();
-                                
-                                {
-                                    let mut active = active_ambients_clone.lock();
-                                    if let Some(info) = active.get_mut(&id) {
-                                        info.settings = settings;
-                                    }
-                                }
-                                
-                                if pitch_changed || pan_changed || low_pass_changed || reverb_changed {
-                                    state.sink.stop();
-                                    if let Ok(new_sink) = Sink::try_new(&stream_handle) {
-                                        let file_path = if state.is_playing_a {
-                                            &state.file_a
-                                        } else {
-                                            &state.file_b
-                                        };
-                                        let bytes = if let Some(cached) = audio_cache.get(file_path) {
-                                            Some(cached.clone())
-                                        } else {
-                                            File::open(file_path).ok().and_then(|mut f| {
-                                                let mut b = Vec::new();
-                                                f.read_to_end(&mut b).ok().map(|_| b)
-                                            })
-                                        };
-                                        if let Some(bytes) = bytes {
-                                        if let Ok(source) = Decoder::new(Cursor::new(bytes)) {
-                                            let sample_rate = source.sample_rate();
-                                            let source = source.speed(state.settings.pitch).convert_samples::<f32>();
-                                            let source = PannedSource::new(source, state.settings.pan);
-                                            let source = LowPassSource::new(source, state.settings.low_pass_freq, sample_rate);
-                                            let effective_vol = calc_ambient_volume(
-                                                &state.settings, ambient_master_volume, master_volume,
-                                                is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                            );
-                                            new_sink.set_volume(effective_vol);
-                                            let source = ReverbSource::new(source, state.settings.algorithmic_reverb, sample_rate);
-                                            let source = AmbientAnalyzingSource::new(source, ambient_sample_buffer_clone.clone());
-                                            new_sink.append(source);
-                                            state.sink = new_sink;
-                                        }
-                                        }
-                                    }
-                                } else {
-                                    // Smooth volume transition with scheduler timing (2000ms)
-                                    let target_vol = calc_ambient_volume(
-                                        &state.settings, ambient_master_volume, master_volume,
-                                        is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                    );
-                                    let current_vol = scheduler_volume_transitions.get(&id)
-                                        .map(|(c, _)| *c)
-                                        .unwrap_or_else(|| state.sink.volume());
-                                    scheduler_volume_transitions.insert(id.clone(), (current_vol, target_vol));
-                                }
-                            }
-                        }
-                    },
-                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
-                        // Process fade-outs for sounds being stopped
-                        let mut completed_fades: Vec<String> = Vec::new();
-                        for (id, progress) in fading_out.iter_mut() {
-                            *progress += 1.0 / FADE_STEPS;
-                            if let Some(state) = ambient_states.get(id) {
-                                // Calculate faded volume (linear fade to 0)
-                                let fade_multiplier = (1.0 - *progress).max(0.0);
-                                let base_vol = calc_ambient_volume(
-                                    &state.settings, ambient_master_volume, master_volume,
-                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                );
-                                state.sink.set_volume(base_vol * fade_multiplier);
-                            }
-                            if *progress >= 1.0 {
-                                completed_fades.push(id.clone());
-                            }
-                        }
-                        // Remove completed fades and stop their sinks
-                        for id in completed_fades {
-                            fading_out.remove(&id);
-                            if let Some(state) = ambient_states.remove(&id) {
-                                state.sink.stop();
-                            }
-                            // Remove from shared state
-                            {
-                                let mut active = active_ambients_clone.lock();
-                                active.remove(&id);
-                            }
-                        }
-                        
-                        // Process fade-ins for newly started sounds
-                        let mut completed_fade_ins: Vec<String> = Vec::new();
-                        for (id, progress) in fading_in.iter_mut() {
-                            *progress += 1.0 / FADE_STEPS;
-                            if let Some(state) = ambient_states.get(id) {
-                                // Calculate faded volume (linear fade from 0 to target)
-                                let fade_multiplier = (*progress).min(1.0);
-                                let target_vol = calc_ambient_volume(
-                                    &state.settings, ambient_master_volume, master_volume,
-                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                );
-                                state.sink.set_volume(target_vol * fade_multiplier);
-                            }
-                            if *progress >= 1.0 {
-                                completed_fade_ins.push(id.clone());
-                            }
-                        }
-                        // Remove completed fade-ins
-                        for id in completed_fade_ins {
-                            fading_in.remove(&id);
-                        }
-                        
-                        // Process smooth volume transitions for settings changes
-                        let mut completed_transitions: Vec<String> = Vec::new();
-                        for (id, (current_vol, target_vol)) in volume_transitions.iter_mut() {
-                            // Skip if sound is fading in (fade-in takes precedence)
-                            if fading_in.contains_key(id) {
-                                continue;
-                            }
-                            
-                            if let Some(state) = ambient_states.get(id) {
-                                // Interpolate toward target
-                                let diff = *target_vol - *current_vol;
-                                if diff.abs() < 0.01 {
-                                    // Close enough, snap to target
-                                    *current_vol = *target_vol;
-                                    state.sink.set_volume(*target_vol);
-                                    completed_transitions.push(id.clone());
-                                } else {
-                                    // Move toward target
-                                    *current_vol += diff.signum() * VOLUME_TRANSITION_SPEED.min(diff.abs());
-                                    state.sink.set_volume(*current_vol);
-                                }
-                            } else {
-                                completed_transitions.push(id.clone());
-                            }
-                        }
-                        // Remove completed transitions
-                        for id in completed_transitions {
-                            volume_transitions.remove(&id);
-                        }
-                        
-                        // Process SCHEDULER fade-outs (2000ms)
-                        let mut completed_scheduler_fades: Vec<String> = Vec::new();
-                        for (id, progress) in scheduler_fading_out.iter_mut() {
-                            *progress += 1.0 / SCHEDULER_FADE_STEPS;
-                            if let Some(state) = ambient_states.get(id) {
-                                let fade_multiplier = (1.0 - *progress).max(0.0);
-                                let base_vol = calc_ambient_volume(
-                                    &state.settings, ambient_master_volume, master_volume,
-                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                );
-                                state.sink.set_volume(base_vol * fade_multiplier);
-                            }
-                            if *progress >= 1.0 {
-                                completed_scheduler_fades.push(id.clone());
-                            }
-                        }
-                        for id in completed_scheduler_fades {
-                            scheduler_fading_out.remove(&id);
-                            if let Some(state) = ambient_states.remove(&id) {
-                                state.sink.stop();
-                            }
-                            {
-                                let mut active = active_ambients_clone.lock();
-                                active.remove(&id);
-                            }
-                        }
-                        
-                        // Process SCHEDULER fade-ins (2000ms)
-                        let mut completed_scheduler_fade_ins: Vec<String> = Vec::new();
-                        for (id, progress) in scheduler_fading_in.iter_mut() {
-                            *progress += 1.0 / SCHEDULER_FADE_STEPS;
-                            if let Some(state) = ambient_states.get(id) {
-                                let fade_multiplier = (*progress).min(1.0);
-                                let target_vol = calc_ambient_volume(
-                                    &state.settings, ambient_master_volume, master_volume,
-                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                );
-                                let final_vol = target_vol * fade_multiplier;
-                                state.sink.set_volume(final_vol);
-                                // Log first fade-in step only
-                                if *progress < 0.1 {
-                                    println!("[Scheduler] Fade-in {}: progress={:.2}, target_vol={:.3}, final_vol={:.3}, ambient_master={:.2}, master={:.2}", 
-                                        id, progress, target_vol, final_vol, ambient_master_volume, master_volume);
-                                }
-                            } else {
-                                println!("[Scheduler] WARNING: Fade-in {} not found in ambient_states!", id);
-                            }
-                            if *progress >= 1.0 {
-                                completed_scheduler_fade_ins.push(id.clone());
-                            }
-                        }
-                        for id in completed_scheduler_fade_ins {
-                            scheduler_fading_in.remove(&id);
-                        }
-                        
-                        // Process SCHEDULER volume transitions (2000ms)
-                        let mut completed_scheduler_transitions: Vec<String> = Vec::new();
-                        for (id, (current_vol, target_vol)) in scheduler_volume_transitions.iter_mut() {
-                            if scheduler_fading_in.contains_key(id) {
-                                continue;
-                            }
-                            
-                            if let Some(state) = ambient_states.get(id) {
-                                let diff = *target_vol - *current_vol;
-                                if diff.abs() < 0.01 {
-                                    *current_vol = *target_vol;
-                                    state.sink.set_volume(*target_vol);
-                                    completed_scheduler_transitions.push(id.clone());
-                                } else {
-                                    *current_vol += diff.signum() * SCHEDULER_VOLUME_TRANSITION_SPEED.min(diff.abs());
-                                    state.sink.set_volume(*current_vol);
-                                }
-                            } else {
-                                completed_scheduler_transitions.push(id.clone());
-                            }
-                        }
-                        for id in completed_scheduler_transitions {
-                            scheduler_volume_transitions.remove(&id);
-                        }
-                        
-                        // A/B crossfade state machine - check each ambient sound
-                        let mut rng = rand::thread_rng();
-                        for state in ambient_states.values_mut() {
-                            // Check if current file finished playing
-                            if state.sink.empty() {
-                                if state.is_paused {
-                                    // In pause state, decrement pause time
-                                    state.pause_remaining -= 0.05; // 50ms per loop iteration
-                                    if state.pause_remaining <= 0.0 {
-                                        state.is_paused = false;
-                                        // Start new cycle
-                                        state.loops_remaining = rng.gen_range(
-                                            state.settings.repeat_min..=state.settings.repeat_max
-                                        );
-                                        state.is_playing_a = true;
-                                        // Play A (try cache first)
-                                        let bytes = if let Some(cached) = audio_cache.get(&state.file_a) {
-                                            Some(cached.clone())
-                                        } else {
-                                            File::open(&state.file_a).ok().and_then(|mut f| {
-                                                let mut b = Vec::new();
-                                                f.read_to_end(&mut b).ok().map(|_| b)
-                                            })
-                                        };
-                                        if let Some(bytes) = bytes {
-                                        if let Ok(source) = Decoder::new(Cursor::new(bytes)) {
-                                            let sample_rate = source.sample_rate();
-                                            let source = source.speed(state.settings.pitch).convert_samples::<f32>();
-                                            let source = PannedSource::new(source, state.settings.pan);
-                                            let source = LowPassSource::new(source, state.settings.low_pass_freq, sample_rate);
-                                            let effective_vol = calc_ambient_volume(
-                                                &state.settings, ambient_master_volume, master_volume,
-                                                is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                            );
-                                            state.sink.set_volume(effective_vol);
-                                            let source = ReverbSource::new(source, state.settings.algorithmic_reverb, sample_rate);
-                                            let source = AmbientAnalyzingSource::new(source, ambient_sample_buffer_clone.clone());
-                                            state.sink.append(source);
-                                        }
-                                        }
-                                    }
-                                } else if state.is_playing_a {
-                                    // A finished, play B (try cache first)
-                                    state.is_playing_a = false;
-                                    let bytes = if let Some(cached) = audio_cache.get(&state.file_b) {
-                                        Some(cached.clone())
-                                    } else {
-                                        File::open(&state.file_b).ok().and_then(|mut f| {
-                                            let mut b = Vec::new();
-                                            f.read_to_end(&mut b).ok().map(|_| b)
-                                        })
-                                    };
-                                    if let Some(bytes) = bytes {
-                                    if let Ok(source) = Decoder::new(Cursor::new(bytes)) {
-                                        let sample_rate = source.sample_rate();
-                                        let source = source.speed(state.settings.pitch).convert_samples::<f32>();
-                                        let source = PannedSource::new(source, state.settings.pan);
-                                        let source = LowPassSource::new(source, state.settings.low_pass_freq, sample_rate);
-                                        let effective_vol = calc_ambient_volume(
-                                            &state.settings, ambient_master_volume, master_volume,
-                                            is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                        );
-                                        state.sink.set_volume(effective_vol);
-                                        let source = ReverbSource::new(source, state.settings.algorithmic_reverb, sample_rate);
-                                        let source = AmbientAnalyzingSource::new(source, ambient_sample_buffer_clone.clone());
-                                        state.sink.append(source);
-                                    }
-                                    }
-                                } else {
-                                    // B finished, one A/B loop complete
-                                    state.loops_remaining = state.loops_remaining.saturating_sub(1);
-                                    
-                                    if state.loops_remaining == 0 {
-                                        // Check if we need to pause
-                                        let pause_loops = rng.gen_range(
-                                            state.settings.pause_min..=state.settings.pause_max
-                                        );
-                                        if pause_loops > 0 {
-                                            // Calculate pause duration (estimate based on file lengths)
-                                            state.is_paused = true;
-                                            state.pause_remaining = pause_loops as f64 * 5.0; // ~5s per loop estimate
-                                        } else {
-                                            // No pause, start new cycle
-                                            state.loops_remaining = rng.gen_range(
-                                                state.settings.repeat_min..=state.settings.repeat_max
-                                            );
-                                            state.is_playing_a = true;
-                                            let bytes = if let Some(cached) = audio_cache.get(&state.file_a) {
-                                                Some(cached.clone())
-                                            } else {
-                                                File::open(&state.file_a).ok().and_then(|mut f| {
-                                                    let mut b = Vec::new();
-                                                    f.read_to_end(&mut b).ok().map(|_| b)
-                                                })
-                                            };
-                                            if let Some(bytes) = bytes {
-                                            if let Ok(source) = Decoder::new(Cursor::new(bytes)) {
-                                                let sample_rate = source.sample_rate();
-                                                let source = source.speed(state.settings.pitch).convert_samples::<f32>();
-                                                let source = PannedSource::new(source, state.settings.pan);
-                                                let source = LowPassSource::new(source, state.settings.low_pass_freq, sample_rate);
-                                                let effective_vol = calc_ambient_volume(
-                                                    &state.settings, ambient_master_volume, master_volume,
-                                                    is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                                );
-                                                state.sink.set_volume(effective_vol);
-                                                let source = ReverbSource::new(source, state.settings.algorithmic_reverb, sample_rate);
-                                                let source = AmbientAnalyzingSource::new(source, ambient_sample_buffer_clone.clone());
-                                                state.sink.append(source);
-                                            }
-                                            }
-                                        }
-                                    } else {
-                                        // More loops to go, play A again
-                                        state.is_playing_a = true;
-                                        let bytes = if let Some(cached) = audio_cache.get(&state.file_a) {
-                                            Some(cached.clone())
-                                        } else {
-                                            File::open(&state.file_a).ok().and_then(|mut f| {
-                                                let mut b = Vec::new();
-                                                f.read_to_end(&mut b).ok().map(|_| b)
-                                            })
-                                        };
-                                        if let Some(bytes) = bytes {
-                                        if let Ok(source) = Decoder::new(Cursor::new(bytes)) {
-                                            let sample_rate = source.sample_rate();
-                                            let source = source.speed(state.settings.pitch).convert_samples::<f32>();
-                                            let source = PannedSource::new(source, state.settings.pan);
-                                            let source = LowPassSource::new(source, state.settings.low_pass_freq, sample_rate);
-                                            let effective_vol = calc_ambient_volume(
-                                                &state.settings, ambient_master_volume, master_volume,
-                                                is_ambient_muted, is_master_muted, duck_progress, duck_amount
-                                            );
-                                            state.sink.set_volume(effective_vol);
-                                            let source = ReverbSource::new(source, state.settings.algorithmic_reverb, sample_rate);
-                                            let source = AmbientAnalyzingSource::new(source, ambient_sample_buffer_clone.clone());
-                                            state.sink.append(source);
-                                        }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
-                        // Channel closed, exit thread
-                        break;
-                    }
-                }
-            }
-        });
-        
-        Self { 
-            command_tx, 
-            progress, 
-            playback_state, 
-            sample_buffer, 
-            ambient_sample_buffer, 
-            active_ambients, 
-            current_track,
-            playlist_state,
-            playlists,
-            all_tracks,
-            soundboard_playing,
-            scheduler_state,
-            presets_dir,
-            current_preset_id,
-        }
+#[tauri::command]
+fn get_playlists(state: tauri::State<Arc<AudioController>>) -> Result<Vec<MusicPlaylist>, AppError> {
+    let playlists = state.playlists.lock();
+    Ok(playlists.values().cloned().collect())
+}
+
+#[tauri::command]
+fn save_playlist(
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AudioController>>,
+    id: String,
+    name: String,
+    tracks: Vec<PlaylistTrack>,
+) -> Result<(), AppError> {
+    // Don't allow overwriting auto playlists
+    if id == "all-music" || id == "favorites" {
+        return Err(AppError::conflict("Cannot modify auto playlists"));
     }
     
-    fn set_presets_dir(&self, path: PathBuf) {
-        *self.presets_dir.lock() = Some(path);
-    }
+    // Keep any playback overrides already configured for this playlist
+    let existing = state.playlists.lock().get(&id).cloned();
+    let playlist = MusicPlaylist {
+        id: id.clone(),
+        name,
+        is_auto: false,
+        tracks,
+        crossfade_duration: existing.as_ref().and_then(|p| p.crossfade_duration),
+        shuffle_default: existing.as_ref().and_then(|p| p.shuffle_default),
+        volume_offset: existing.as_ref().and_then(|p| p.volume_offset),
+    };
     
-    fn send(&self, cmd: AudioCommand) {
-        let _ = self.command_tx.send(cmd);
-    }
+    // Persist to disk
+    save_playlist_to_disk(&app, &playlist, format!("Edit playlist \"{}\"", playlist.name))?;
     
-    fn get_progress(&self) -> AudioProgress {
-        self.progress.lock().clone()
+    state.playlists.lock().insert(id, playlist);
+    Ok(())
+}
+
+#[tauri::command]
+fn update_playlist_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AudioController>>,
+    id: String,
+    crossfade_duration: Option<f32>,
+    shuffle_default: Option<bool>,
+    volume_offset: Option<f32>,
+) -> Result<(), AppError> {
+    if let Some(offset) = volume_offset {
+        if !(-MAX_PLAYLIST_VOLUME_OFFSET_DB..=MAX_PLAYLIST_VOLUME_OFFSET_DB).contains(&offset) {
+            return Err(AppError::invalid(format!("Volume offset must be within ±{} dB", MAX_PLAYLIST_VOLUME_OFFSET_DB)));
+        }
     }
-    
-    fn get_playback_state(&self) -> PlaybackState {
-        self.playback_state.lock().clone()
+    if let Some(secs) = crossfade_duration {
+        if !(0.0..=MAX_PLAYLIST_CROSSFADE_SECS).contains(&secs) {
+            return Err(AppError::invalid(format!("Crossfade must be between 0 and {} seconds", MAX_PLAYLIST_CROSSFADE_SECS)));
+        }
     }
+    let playlist = {
+        let mut playlists = state.playlists.lock();
+        let playlist = playlists.get_mut(&id)
+            .ok_or_else(|| AppError::not_found(format!("Playlist '{}' not found", id)))?;
+        playlist.crossfade_duration = crossfade_duration;
+        playlist.shuffle_default = shuffle_default;
+        playlist.volume_offset = volume_offset;
+        playlist.clone()
+    };
     
-    fn get_current_track(&self) -> Option<CurrentTrackInfo> {
-        self.current_track.lock().clone()
-    }
+    save_playlist_to_disk(&app, &playlist, format!("Change playback settings of \"{}\"", playlist.name))?;
     
-    fn get_playlist_state(&self) -> PlaylistState {
-        self.playlist_state.lock().clone()
+    // Re-apply immediately if this playlist is the one currently selected
+    if state.playlist_state.lock().current_playlist_id.as_deref() == Some(id.as_str()) {
+        state.send(AudioCommand::SetPlaylistOverrides {
+            crossfade_duration,
+            volume_offset_db: volume_offset.unwrap_or(0.0),
+        });
     }
+    Ok(())
 }
 
-fn get_default_settings() -> AppSettings {
-    let exe_path = std::env::current_exe().ok();
-    let base_path = exe_path
-        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-        .map(|p| p.join("sample-content"))
-        .unwrap_or_else(|| {
-            dirs::document_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join("Soundscapes")
-        });
-    
-    AppSettings {
-        music_folder_path: base_path.join("Music").to_string_lossy().to_string(),
-        ambient_folder_path: base_path.join("Ambient").to_string_lossy().to_string(),
-        soundboard_folder_path: base_path.join("Soundboard").to_string_lossy().to_string(),
-        presets_folder_path: base_path.join("Presets").to_string_lossy().to_string(),
-        music_crossfade_duration: 3.0,
-        soundboard_duck_amount: 0.3,
-        visualization_type: default_visualization(),
-        master_volume: default_volume(),
-        music_volume: default_volume(),
-        ambient_volume: default_volume(),
-        soundboard_volume: default_volume(),
+#[tauri::command]
+fn delete_playlist(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>, id: String) -> Result<(), AppError> {
+    // Don't allow deleting auto playlists
+    if id == "all-music" || id == "favorites" {
+        return Err(AppError::conflict("Cannot delete auto playlists"));
     }
+    
+    // Delete from disk
+    let name = state.playlists.lock().get(&id).map(|p| p.name.clone()).unwrap_or_else(|| id.clone());
+    delete_playlist_from_disk(&app, &id, format!("Delete playlist \"{}\"", name))?;
+    
+    state.playlists.lock().remove(&id);
+    Ok(())
 }
 
-fn get_settings_path() -> PathBuf {
-    dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("Soundscapes")
-        .join("settings.json")
+#[tauri::command]
+fn set_all_tracks(state: tauri::State<Arc<AudioController>>, tracks: Vec<PlaylistTrack>) -> Result<(), AppError> {
+    *state.all_tracks.lock() = tracks;
+    Ok(())
 }
 
 #[tauri::command]
-fn get_settings() -> Result<AppSettings, String> {
-    let settings_path = get_settings_path();
-    
-    if settings_path.exists() {
-        let content = fs::read_to_string(&settings_path)
-            .map_err(|e| format!("Failed to read settings: {}", e))?;
-        serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse settings: {}", e))
-    } else {
-        Ok(get_default_settings())
+fn get_all_tracks(state: tauri::State<Arc<AudioController>>) -> Result<Vec<PlaylistTrack>, AppError> {
+    Ok(state.all_tracks.lock().clone())
+}
+
+// A stop fade in ms, if one was asked for
+fn stop_fade_secs(fade_ms: Option<u32>) -> Result<Option<f32>, AppError> {
+    match fade_ms {
+        Some(ms) if ms > MAX_STOP_FADE_MS => Err(AppError::invalid(format!("Fade must be at most {} ms", MAX_STOP_FADE_MS))),
+        _ => Ok(fade_ms.map(|ms| ms as f32 / 1000.0)),
     }
 }
 
+// Stops right away unless `fade_ms` is given
 #[tauri::command]
-fn save_settings(settings: AppSettings) -> Result<(), String> {
-    let settings_path = get_settings_path();
-    
-    if let Some(parent) = settings_path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+fn stop_music(state: tauri::State<Arc<AudioController>>, fade_ms: Option<u32>) -> Result<(), AppError> {
+    let fade_secs = stop_fade_secs(fade_ms)?;
+    if external_transport(&state, external_player::Transport::Stop) {
+        return Ok(());
     }
-    
-    let content = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-    
-    fs::write(&settings_path, content)
-        .map_err(|e| format!("Failed to write settings: {}", e))
+    state.send(AudioCommand::Stop { fade_secs });
+    Ok(())
 }
 
 #[tauri::command]
-fn save_volume_setting(key: String, value: f32) -> Result<(), String> {
-    let settings_path = get_settings_path();
-    
-    // Load current settings
-    let mut settings: AppSettings = if settings_path.exists() {
-        let content = fs::read_to_string(&settings_path)
-            .map_err(|e| format!("Failed to read settings: {}", e))?;
-        serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse settings: {}", e))?
-    } else {
-        return Err("Settings file not found".to_string());
-    };
-    
-    // Update the specific volume field
-    match key.as_str() {
-        "master_volume" => settings.master_volume = value,
-        "music_volume" => settings.music_volume = value,
-        "ambient_volume" => settings.ambient_volume = value,
-        "soundboard_volume" => settings.soundboard_volume = value,
-        _ => return Err(format!("Unknown volume key: {}", key)),
+fn pause_music(state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    if external_transport(&state, external_player::Transport::Pause) {
+        return Ok(());
     }
-    
-    // Save updated settings
-    let content = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-    
-    fs::write(&settings_path, content)
-        .map_err(|e| format!("Failed to write settings: {}", e))
+    state.send(AudioCommand::Pause);
+    Ok(())
 }
 
 #[tauri::command]
-fn scan_music_folder(folder_path: String) -> Result<Vec<MusicAlbum>, String> {
-    let path = PathBuf::from(&folder_path);
-    let mut albums = Vec::new();
-    
-    if !path.exists() {
-        return Ok(albums);
-    }
-    
-    for entry in WalkDir::new(&path).min_depth(1).max_depth(1) {
-        let entry = entry.map_err(|e| format!("Failed to read directory: {}", e))?;
-        
-        if entry.file_type().is_dir() {
-            let metadata_path = entry.path().join("metadata.json");
-            
-            if metadata_path.exists() {
-                let content = fs::read_to_string(&metadata_path)
-                    .map_err(|e| format!("Failed to read metadata: {}", e))?;
-                
-                let metadata: MusicMetadata = serde_json::from_str(&content)
-                    .map_err(|e| format!("Failed to parse metadata: {}", e))?;
-                
-                albums.push(MusicAlbum {
-                    name: metadata.name,
-                    artist: metadata.artist,
-                    tracks: metadata.tracks,
-                    path: entry.path().to_string_lossy().to_string(),
-                });
-            }
-        }
+fn resume_music(state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    if external_transport(&state, external_player::Transport::Play) {
+        return Ok(());
     }
-    
-    Ok(albums)
+    state.send(AudioCommand::Resume);
+    Ok(())
 }
 
 #[tauri::command]
-fn scan_ambient_folder(folder_path: String) -> Result<Vec<AmbientCategory>, String> {
-    let path = PathBuf::from(&folder_path);
-    let mut categories = Vec::new();
-    
-    if !path.exists() {
-        return Ok(categories);
-    }
-    
-    for entry in WalkDir::new(&path).min_depth(1).max_depth(1) {
-        let entry = entry.map_err(|e| format!("Failed to read directory: {}", e))?;
-        
-        if entry.file_type().is_dir() {
-            let metadata_path = entry.path().join("metadata.json");
-            
-            if metadata_path.exists() {
-                let content = fs::read_to_string(&metadata_path)
-                    .map_err(|e| format!("Failed to read metadata: {}", e))?;
-                
-                let metadata: AmbientMetadata = serde_json::from_str(&content)
-                    .map_err(|e| format!("Failed to parse metadata: {}", e))?;
-                
-                categories.push(AmbientCategory {
-                    name: metadata.name,
-                    icon: metadata.icon,
-                    sounds: metadata.sounds,
-                    path: entry.path().to_string_lossy().to_string(),
-                });
-            }
-        }
-    }
-    
-    Ok(categories)
+fn seek_music(state: tauri::State<Arc<AudioController>>, position: f64) -> Result<(), AppError> {
+    state.send(AudioCommand::Seek(position));
+    Ok(())
 }
 
+// Play the current track from the start without stopping it first, so the rewind fades instead of
+// clicking and the track keeps its crossfade and loudness state
 #[tauri::command]
-fn scan_soundboard_folder(folder_path: String) -> Result<SoundboardData, String> {
-    let path = PathBuf::from(&folder_path);
-    
-    if !path.exists() {
-        return Ok(SoundboardData {
-            sounds: Vec::new(),
-            path: folder_path,
-        });
+fn restart_track(state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    // Players like Spotify go back to the start of the track on previous once it's playing a while
+    if external_transport(&state, external_player::Transport::Previous) {
+        return Ok(());
     }
-    
-    let metadata_path = path.join("metadata.json");
-    
-    if metadata_path.exists() {
-        let content = fs::read_to_string(&metadata_path)
-            .map_err(|e| format!("Failed to read metadata: {}", e))?;
-        
-        let metadata: SoundboardMetadata = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse metadata: {}", e))?;
-        
-        Ok(SoundboardData {
-            sounds: metadata.sounds,
-            path: folder_path,
-        })
-    } else {
-        Ok(SoundboardData {
-            sounds: Vec::new(),
-            path: folder_path,
-        })
+    if state.current_track.lock().is_none() {
+        return Err(AppError::not_found("No track is playing"));
     }
+    state.send(AudioCommand::Restart);
+    Ok(())
 }
 
 #[tauri::command]
-fn update_soundboard_sound(
-    folder_path: String,
-    sound_id: String,
-    name: Option<String>,
-    hotkey: Option<String>,
-    color: Option<String>,
-    volume: Option<u32>,
-) -> Result<(), String> {
-    let path = PathBuf::from(&folder_path);
-    let metadata_path = path.join("metadata.json");
-    
-    if !metadata_path.exists() {
-        return Err("Metadata file not found".to_string());
-    }
-    
-    // Read existing metadata
-    let content = fs::read_to_string(&metadata_path)
-        .map_err(|e| format!("Failed to read metadata: {}", e))?;
-    
-    let mut metadata: SoundboardMetadata = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse metadata: {}", e))?;
-    
-    // Find and update the sound
-    let sound = metadata.sounds.iter_mut().find(|s| s.id == sound_id);
-    if let Some(sound) = sound {
-        if let Some(new_name) = name {
-            sound.name = new_name;
-        }
-        if hotkey.is_some() {
-            sound.hotkey = hotkey;
-        }
-        if let Some(new_color) = color {
-            sound.color = Some(new_color);
-        }
-        if let Some(new_volume) = volume {
-            sound.volume = Some(new_volume);
-        }
-    } else {
-        return Err(format!("Sound with id {} not found", sound_id));
+fn set_loop_section(state: tauri::State<Arc<AudioController>>, start_secs: f64, end_secs: f64) -> Result<(), AppError> {
+    if start_secs < 0.0 || end_secs <= start_secs {
+        return Err(AppError::invalid(format!("Invalid loop section: {} - {}", start_secs, end_secs)));
     }
-    
-    // Write back to file
-    let content = serde_json::to_string_pretty(&metadata)
-        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
-    
-    fs::write(&metadata_path, content)
-        .map_err(|e| format!("Failed to write metadata: {}", e))
+    state.send(AudioCommand::SetLoopSection(Some((start_secs, end_secs))));
+    Ok(())
 }
 
-// Audio Commands - using thread-safe AudioController
 #[tauri::command]
-fn init_audio(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>) -> Result<(), String> {
-    // Set the presets directory for the audio thread to use
-    let presets_dir = get_presets_dir(&app)?;
-    state.set_presets_dir(presets_dir);
+fn clear_loop_section(state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    state.send(AudioCommand::SetLoopSection(None));
     Ok(())
 }
 
 #[tauri::command]
-fn play_music(
+fn play_soundboard(
     state: tauri::State<Arc<AudioController>>,
     file_path: String,
-    id: String,
-    title: String,
-    artist: String,
-    album: String,
-) -> Result<(), String> {
-    let track_info = CurrentTrackInfo {
-        id,
-        title,
-        artist,
-        album,
-        file_path: file_path.clone(),
-    };
-    state.send(AudioCommand::Play { file_path, track_info });
+    volume: f32,
+    trim: Option<SoundTrim>,
+) -> Result<(), AppError> {
+    let trim = trim.unwrap_or_default();
+    trim.check().map_err(AppError::invalid)?;
+    state.send(AudioCommand::PlaySoundboard { file_path, volume, trim });
     Ok(())
 }
 
+// Speak text through the soundboard bus (ducking music and ambience), e.g. "Break time"
 #[tauri::command]
-fn get_current_track(state: tauri::State<Arc<AudioController>>) -> Result<Option<CurrentTrackInfo>, String> {
-    Ok(state.get_current_track())
+fn announce(state: tauri::State<Arc<AudioController>>, text: String, voice: Option<String>) -> Result<(), AppError> {
+    if text.trim().is_empty() {
+        return Err(AppError::invalid("Announcement text is empty"));
+    }
+    let voice = voice.filter(|v| !v.trim().is_empty());
+    state.announce(text, voice);
+    Ok(())
 }
 
-// Scheduler management commands
 #[tauri::command]
-fn get_scheduler_state(state: tauri::State<Arc<AudioController>>) -> Result<SchedulerState, String> {
-    Ok(state.scheduler_state.lock().clone())
+fn get_tts_voices() -> Result<Vec<String>, AppError> {
+    Ok(tts::list_voices()?)
+}
+
+// Run an offline render in the background, reporting through render://progress and render://finished.
+// Only one render runs at a time.
+fn spawn_render<F>(app: tauri::AppHandle, state: &AudioController, output_path: String, work: F) -> Result<(), AppError>
+where
+    F: FnOnce(&tauri::AppHandle, &str, &std::sync::atomic::AtomicBool) -> Result<f64, String> + Send + 'static,
+{
+    let cancel = {
+        let mut job = state.render_job.lock();
+        if job.is_some() {
+            return Err(AppError::conflict("A render is already in progress"));
+        }
+        let cancel = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        *job = Some(cancel.clone());
+        cancel
+    };
+
+    thread::spawn(move || {
+        let result = work(&app, &output_path, &cancel);
+        let cancelled = cancel.load(std::sync::atomic::Ordering::Relaxed);
+        let controller = app.state::<Arc<AudioController>>();
+        if let Err(e) = &result {
+            // Don't leave a truncated file behind
+            let _ = fs::remove_file(&output_path);
+            if !cancelled {
+                controller.errors.report(errors::ErrorCode::Render, format!("Render failed: {}", e), Some(output_path.clone()));
+            }
+        }
+        *controller.render_job.lock() = None;
+        let _ = app.emit("render://finished", render::RenderFinished {
+            output_path,
+            duration_secs: *result.as_ref().unwrap_or(&0.0),
+            error: result.err().filter(|_| !cancelled),
+            cancelled,
+        });
+    });
+    Ok(())
 }
 
+// Render a whole schedule offline to a WAV file
 #[tauri::command]
-fn start_scheduler_playback(
+fn render_schedule(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>, id: String, output_path: String) -> Result<(), AppError> {
+    let schedule = load_schedule(app.clone(), id)?;
+    spawn_render(app, &state, output_path, move |app, path, cancel| {
+        render::render_schedule(app, &schedule, path, cancel)
+    })
+}
+
+// Bake a playing ambient sound, with its current settings, into a standalone WAV file
+#[tauri::command]
+fn export_processed_sound(
+    app: tauri::AppHandle,
     state: tauri::State<Arc<AudioController>>,
-    items: Vec<ScheduledItem>,
-    schedule_id: Option<String>,
-) -> Result<(), String> {
-    let mut sched = state.scheduler_state.lock();
-    if items.is_empty() {
-        return Err("No items to schedule".to_string());
+    id: String,
+    minutes: f64,
+    path: String,
+) -> Result<(), AppError> {
+    let info = state.active_ambients.lock().get(&id).cloned()
+        .ok_or_else(|| AppError::not_found(format!("Ambient sound '{}' is not playing", id)))?;
+    let title = std::path::Path::new(&id).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or(id);
+    let tags = encoder::Tags::new(title, playing_source_name(&app, &state));
+    spawn_render(app, &state, path, move |app, path, cancel| {
+        render::render_sound(app, info, minutes, path, &tags, cancel)
+    })
+}
+
+#[tauri::command]
+fn cancel_render(state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    if let Some(cancel) = state.render_job.lock().as_ref() {
+        cancel.store(true, std::sync::atomic::Ordering::Relaxed);
     }
-    
-    let first_item = &items[0];
-    let min = first_item.min_minutes.min(first_item.max_minutes);
-    let max = first_item.min_minutes.max(first_item.max_minutes);
-    let duration = if min == max {
-        min
-    } else {
-        min + (rand::random::<u32>() % (max - min + 1))
-    };
-    
-    sched.items = items;
-    sched.current_schedule_id = schedule_id;
-    sched.is_playing = true;
-    sched.current_item_index = 0;
-    sched.current_duration = duration;
-    sched.time_remaining = (duration * 60) as i32;
-    
     Ok(())
 }
 
+// Stops right away unless `fade_ms` is given
 #[tauri::command]
-fn stop_scheduler_playback(state: tauri::State<Arc<AudioController>>) -> Result<(), String> {
-    let mut sched = state.scheduler_state.lock();
-    sched.is_playing = false;
-    sched.current_item_index = 0;
-    sched.current_duration = 0;
-    sched.time_remaining = 0;
-    // Also stop all ambient sounds
-    state.send(AudioCommand::StopAllAmbient);
+fn stop_soundboard(state: tauri::State<Arc<AudioController>>, fade_ms: Option<u32>) -> Result<(), AppError> {
+    state.send(AudioCommand::StopSoundboard { fade_secs: stop_fade_secs(fade_ms)? });
     Ok(())
 }
 
-// Playlist management commands
 #[tauri::command]
-fn get_playlist_state(state: tauri::State<Arc<AudioController>>) -> Result<PlaylistState, String> {
-    Ok(state.get_playlist_state())
+fn set_duck_amount(state: tauri::State<Arc<AudioController>>, amount: f32) -> Result<(), AppError> {
+    let amount = settings_check::clamp("soundboard_duck_amount", amount as f64) as f32;
+    state.send(AudioCommand::SetDuckAmount(amount));
+    Ok(())
 }
 
 #[tauri::command]
-fn load_saved_playlists_and_favorites(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>) -> Result<(), String> {
-    // Load favorites from disk
-    let favorites = load_favorites_from_disk(&app)?;
-    state.playlist_state.lock().favorites = favorites;
-    
-    // Load playlists from disk
-    let playlists = load_playlists_from_disk(&app)?;
-    let mut playlist_map = state.playlists.lock();
-    for playlist in playlists {
-        playlist_map.insert(playlist.id.clone(), playlist);
-    }
-    
-    Ok(())
+fn is_soundboard_playing(state: tauri::State<Arc<AudioController>>) -> bool {
+    *state.soundboard_playing.lock()
 }
 
 #[tauri::command]
-fn set_playlist_shuffle(state: tauri::State<Arc<AudioController>>, shuffled: bool) -> Result<(), String> {
-    state.playlist_state.lock().is_shuffled = shuffled;
+fn set_music_volume(state: tauri::State<Arc<AudioController>>, volume: f32) -> Result<(), AppError> {
+    state.send(AudioCommand::SetVolume(volume.clamp(0.0, 1.0)));
     Ok(())
 }
 
 #[tauri::command]
-fn set_playlist_loop(state: tauri::State<Arc<AudioController>>, looping: bool) -> Result<(), String> {
-    state.playlist_state.lock().is_looping = looping;
+fn set_master_volume(state: tauri::State<Arc<AudioController>>, volume: f32) -> Result<(), AppError> {
+    state.send(AudioCommand::SetMasterVolume(volume.clamp(0.0, 1.0)));
     Ok(())
 }
 
 #[tauri::command]
-fn set_current_playlist(state: tauri::State<Arc<AudioController>>, playlist_id: Option<String>) -> Result<(), String> {
-    let mut ps = state.playlist_state.lock();
-    ps.current_playlist_id = playlist_id;
-    ps.current_index = 0;
-    ps.interrupted_index = None;
+fn set_music_muted(state: tauri::State<Arc<AudioController>>, muted: bool) -> Result<(), AppError> {
+    state.send(AudioCommand::SetMuted(muted));
     Ok(())
 }
 
 #[tauri::command]
-fn set_playlist_index(state: tauri::State<Arc<AudioController>>, index: i32) -> Result<(), String> {
-    state.playlist_state.lock().current_index = index;
+fn set_master_muted(state: tauri::State<Arc<AudioController>>, muted: bool) -> Result<(), AppError> {
+    state.send(AudioCommand::SetMasterMuted(muted));
     Ok(())
 }
 
+// Same as pressing the panic mute shortcut; returns whether everything is muted now
 #[tauri::command]
-fn play_next_track(state: tauri::State<Arc<AudioController>>) -> Result<bool, String> {
-    // Get current playlist state
-    let ps = state.playlist_state.lock().clone();
-    let all_tracks = state.all_tracks.lock().clone();
-    let playlists = state.playlists.lock().clone();
-    
-    // Determine which tracks to use
-    let tracks: Vec<PlaylistTrack> = if let Some(ref playlist_id) = ps.current_playlist_id {
-        if playlist_id == "all-music" {
-            all_tracks.clone()
-        } else if playlist_id == "favorites" {
-            all_tracks.iter().filter(|t| ps.favorites.contains(&t.id)).cloned().collect()
-        } else if playlist_id.starts_with("album-") {
-            // Filter tracks by album name
-            let album_name = playlist_id.strip_prefix("album-").unwrap_or("");
-            all_tracks.iter().filter(|t| t.album == album_name).cloned().collect()
-        } else if let Some(playlist) = playlists.get(playlist_id) {
-            playlist.tracks.clone()
-        } else {
-            // Unknown playlist, return empty
-            Vec::new()
+fn toggle_panic_mute(app: tauri::AppHandle) -> bool {
+    panic_mute::toggle(&app)
+}
+
+#[tauri::command]
+fn get_panic_mute(state: tauri::State<Arc<AudioController>>) -> bool {
+    state.panic_mute.is_muted()
+}
+
+// Microphone monitoring: hear the default input device in the mix. Volume and reverb changes
+// apply to the running input; turning it on opens the device.
+#[tauri::command]
+fn set_microphone(state: tauri::State<Arc<AudioController>>, config: microphone::MicrophoneConfig) -> Result<(), AppError> {
+    let mut input = state.microphone.lock();
+    if !config.enabled {
+        if input.take().is_some() {
+            log::info!("Microphone monitoring off");
         }
+    } else if let Some(running) = input.as_ref() {
+        running.set(&config);
     } else {
-        return Ok(false); // No playlist selected
-    };
-    
-    if tracks.is_empty() {
-        return Ok(false);
-    }
-    
-    // Calculate next index
-    let next_index: i32 = if ps.is_shuffled {
-        ((rand::random::<usize>()) % tracks.len()) as i32
-    } else {
-        let next = ps.current_index + 1;
-        if next >= tracks.len() as i32 {
-            if ps.is_looping {
-                0
-            } else {
-                return Ok(false); // Playlist finished, not looping
-            }
-        } else {
-            next
-        }
-    };
-    
-    // Update state
-    {
-        let mut ps_lock = state.playlist_state.lock();
-        ps_lock.current_index = next_index;
+        let (started, source) = microphone::MicrophoneInput::start(&config).map_err(AppError::unavailable)?;
+        state.send(AudioCommand::StartMicrophone(source));
+        *input = Some(started);
+        log::info!("Microphone monitoring on");
     }
-    
-    // Get the track and play it
-    let track = &tracks[next_index as usize];
-    let file_path = format!("{}/{}", track.album_path, track.file);
-    let track_info = CurrentTrackInfo {
-        id: track.id.clone(),
-        title: track.title.clone(),
-        artist: track.artist.clone(),
-        album: track.album.clone(),
-        file_path: file_path.clone(),
-    };
-    
-    state.send(AudioCommand::Play { file_path, track_info });
-    Ok(true)
+    Ok(())
 }
 
+// Gentle compression and a peak ceiling on everything, for late listening
 #[tauri::command]
-fn play_previous_track(state: tauri::State<Arc<AudioController>>) -> Result<bool, String> {
-    // Get current playlist state
-    let ps = state.playlist_state.lock().clone();
-    let all_tracks = state.all_tracks.lock().clone();
-    let playlists = state.playlists.lock().clone();
-    
-    // Determine which tracks to use
-    let tracks: Vec<PlaylistTrack> = if let Some(ref playlist_id) = ps.current_playlist_id {
-        if playlist_id == "all-music" {
-            all_tracks.clone()
-        } else if playlist_id == "favorites" {
-            all_tracks.iter().filter(|t| ps.favorites.contains(&t.id)).cloned().collect()
-        } else if playlist_id.starts_with("album-") {
-            // Filter tracks by album name
-            let album_name = playlist_id.strip_prefix("album-").unwrap_or("");
-            all_tracks.iter().filter(|t| t.album == album_name).cloned().collect()
-        } else if let Some(playlist) = playlists.get(playlist_id) {
-            playlist.tracks.clone()
-        } else {
-            Vec::new()
+fn set_night_mode(state: tauri::State<Arc<AudioController>>, enabled: bool) -> Result<(), AppError> {
+    state.night_mode.set(enabled);
+    log::info!("Night mode {}", if enabled { "on" } else { "off" });
+    Ok(())
+}
+
+// Keep playing, duck or pause while the app is in the background; applies at once if it already is
+#[tauri::command]
+fn set_background_audio(app: tauri::AppHandle, config: focus::BackgroundAudioConfig) -> Result<(), AppError> {
+    focus::configure(&app, config);
+    Ok(())
+}
+
+// Empty removes the shortcut
+#[tauri::command]
+fn set_panic_mute_shortcut(app: tauri::AppHandle, shortcut: String) -> Result<(), AppError> {
+    panic_mute::register(&app, &shortcut).map_err(AppError::invalid)
+}
+
+#[derive(Debug, Serialize)]
+struct MusicProgressResponse {
+    current_time: f64,
+    duration: f64,
+    is_playing: bool,
+    is_finished: bool,
+    loop_start: Option<f64>,
+    loop_end: Option<f64>,
+}
+
+#[tauri::command]
+fn get_music_progress(state: tauri::State<Arc<AudioController>>) -> Result<MusicProgressResponse, AppError> {
+    let progress = state.get_progress();
+    Ok(MusicProgressResponse {
+        current_time: progress.current_time,
+        duration: progress.duration,
+        is_playing: progress.is_playing,
+        is_finished: progress.is_finished,
+        loop_start: progress.loop_section.map(|(start, _)| start),
+        loop_end: progress.loop_section.map(|(_, end)| end),
+    })
+}
+
+#[tauri::command]
+fn set_frequency_scale(state: tauri::State<Arc<AudioController>>, scale: String) -> Result<(), AppError> {
+    let scale = FrequencyScale::from_name(&scale)
+        .ok_or_else(|| AppError::invalid(format!("Unknown frequency scale: {}", scale)))?;
+    state.send(AudioCommand::SetFrequencyScale(scale));
+    Ok(())
+}
+
+#[tauri::command]
+fn get_playback_state(state: tauri::State<Arc<AudioController>>) -> Result<PlaybackStateResponse, AppError> {
+    Ok(PlaybackStateResponse::from(state.get_playback_state()))
+}
+
+#[tauri::command]
+fn get_meters(state: tauri::State<Arc<AudioController>>) -> Result<MeterReadings, AppError> {
+    Ok(state.get_meters())
+}
+
+#[tauri::command]
+fn set_fft_smoothing(state: tauri::State<Arc<AudioController>>, attack: f32, decay: f32) -> Result<(), AppError> {
+    state.send(AudioCommand::SetFftSmoothing { attack, decay });
+    Ok(())
+}
+
+#[tauri::command]
+fn set_fft_triggers(state: tauri::State<Arc<AudioController>>, triggers: Vec<FftTrigger>) -> Result<(), AppError> {
+    for trigger in &triggers {
+        if trigger.name.trim().is_empty() {
+            return Err(AppError::invalid("Trigger name cannot be empty"));
+        }
+        if trigger.band_start > trigger.band_end || trigger.band_end >= FFT_SIZE {
+            return Err(AppError::invalid(format!("Invalid band {}-{} for trigger '{}'", trigger.band_start, trigger.band_end, trigger.name)));
         }
-    } else {
-        return Ok(false);
-    };
-    
-    if tracks.is_empty() {
-        return Ok(false);
-    }
-    
-    // Calculate previous index
-    let prev_index = if ps.current_index <= 0 {
-        (tracks.len() - 1) as i32
-    } else {
-        ps.current_index - 1
-    };
-    
-    // Update state
-    {
-        let mut ps_lock = state.playlist_state.lock();
-        ps_lock.current_index = prev_index;
     }
-    
-    // Get the track and play it
-    let track = &tracks[prev_index as usize];
-    let file_path = format!("{}/{}", track.album_path, track.file);
-    let track_info = CurrentTrackInfo {
-        id: track.id.clone(),
-        title: track.title.clone(),
-        artist: track.artist.clone(),
-        album: track.album.clone(),
-        file_path: file_path.clone(),
-    };
-    
-    state.send(AudioCommand::Play { file_path, track_info });
-    Ok(true)
+    state.send(AudioCommand::SetFftTriggers(triggers));
+    Ok(())
 }
 
 #[tauri::command]
-fn toggle_favorite(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>, track_id: String) -> Result<bool, String> {
-    let mut ps = state.playlist_state.lock();
-    let is_favorite = if let Some(pos) = ps.favorites.iter().position(|id| id == &track_id) {
-        ps.favorites.remove(pos);
-        false
-    } else {
-        ps.favorites.push(track_id);
-        true
-    };
-    
-    // Persist favorites to disk
-    save_favorites_to_disk(&app, &ps.favorites)?;
-    
-    Ok(is_favorite)
+fn set_fft_event_rate(state: tauri::State<Arc<AudioController>>, rate_hz: u32) -> Result<(), AppError> {
+    state.send(AudioCommand::SetFftEventRate(rate_hz));
+    Ok(())
+}
+
+// A window showing (or no longer showing) a visualizer; spectrum analysis only runs while at least
+// one is shown or lighting sync is on. Calls are counted per window.
+#[tauri::command]
+fn set_visualization_active(window: tauri::Window, state: tauri::State<Arc<AudioController>>, active: bool) -> Result<(), AppError> {
+    state.visualization.set_window(window.label(), active);
+    Ok(())
 }
 
+// Lighting sync commands
 #[tauri::command]
-fn set_crossfade_duration(state: tauri::State<Arc<AudioController>>, duration: f32) -> Result<(), String> {
-    state.send(AudioCommand::SetCrossfadeDuration(duration));
+fn set_lighting_enabled(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>, enabled: bool) -> Result<(), AppError> {
+    let mut lighting = state.lighting.lock();
+    // Dropping the running sync stops its thread; re-enabling reloads the mapping file
+    *lighting = None;
+    state.visualization.set_lighting(false);
+    if enabled {
+        let config = lighting::load_config(&get_lighting_config_path(&app)?)?;
+        if config.targets.is_empty() {
+            return Err(AppError::unavailable("No lights configured in lighting.json"));
+        }
+        *lighting = Some(lighting::LightingSync::start(config, state.playback_state.clone())?);
+        state.visualization.set_lighting(true);
+    }
     Ok(())
 }
 
 #[tauri::command]
-fn get_playlists(state: tauri::State<Arc<AudioController>>) -> Result<Vec<MusicPlaylist>, String> {
-    let playlists = state.playlists.lock();
-    Ok(playlists.values().cloned().collect())
+fn get_lighting_config_location(app: tauri::AppHandle) -> Result<String, AppError> {
+    Ok(get_lighting_config_path(&app)?.to_string_lossy().to_string())
 }
 
+// Remote control API commands
 #[tauri::command]
-fn save_playlist(
+fn set_remote_api(
     app: tauri::AppHandle,
     state: tauri::State<Arc<AudioController>>,
-    id: String,
-    name: String,
-    tracks: Vec<PlaylistTrack>,
-) -> Result<(), String> {
-    // Don't allow overwriting auto playlists
-    if id == "all-music" || id == "favorites" {
-        return Err("Cannot modify auto playlists".to_string());
+    enabled: bool,
+    port: u16,
+    token: String,
+    allow_lan: bool,
+) -> Result<(), AppError> {
+    let mut server = state.remote_server.lock();
+    // Dropping the running server shuts it down before rebinding
+    *server = None;
+    if enabled {
+        if token.trim().is_empty() {
+            return Err(AppError::invalid("Remote API token cannot be empty"));
+        }
+        *server = Some(remote::RemoteServer::start(app, port, token, allow_lan)?);
     }
-    
-    let playlist = MusicPlaylist {
-        id: id.clone(),
-        name,
-        is_auto: false,
-        tracks,
-    };
-    
-    // Persist to disk
-    save_playlist_to_disk(&app, &playlist)?;
-    
-    state.playlists.lock().insert(id, playlist);
     Ok(())
 }
 
+// OSC commands
 #[tauri::command]
-fn delete_playlist(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>, id: String) -> Result<(), String> {
-    // Don't allow deleting auto playlists
-    if id == "all-music" || id == "favorites" {
-        return Err("Cannot delete auto playlists".to_string());
+fn set_osc(
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AudioController>>,
+    enabled: bool,
+    port: u16,
+    feedback_host: String,
+    feedback_port: u16,
+    allow_lan: bool,
+) -> Result<(), AppError> {
+    let mut server = state.osc_server.lock();
+    // Dropping the running server releases the port before rebinding
+    *server = None;
+    if enabled {
+        let feedback_host = feedback_host.trim();
+        let feedback = (!feedback_host.is_empty()).then(|| (feedback_host.to_string(), feedback_port));
+        *server = Some(osc::OscServer::start(app, port, feedback, allow_lan)?);
     }
-    
-    // Delete from disk
-    delete_playlist_from_disk(&app, &id)?;
-    
-    state.playlists.lock().remove(&id);
     Ok(())
 }
 
+// Stream Deck plugin socket commands
 #[tauri::command]
-fn set_all_tracks(state: tauri::State<Arc<AudioController>>, tracks: Vec<PlaylistTrack>) -> Result<(), String> {
-    *state.all_tracks.lock() = tracks;
+fn set_streamdeck(
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AudioController>>,
+    enabled: bool,
+    port: u16,
+) -> Result<(), AppError> {
+    let mut server = state.streamdeck_server.lock();
+    *server = None;
+    if enabled {
+        *server = Some(streamdeck::StreamDeckServer::start(app, port)?);
+    }
     Ok(())
 }
 
+// OBS integration commands
 #[tauri::command]
-fn get_all_tracks(state: tauri::State<Arc<AudioController>>) -> Result<Vec<PlaylistTrack>, String> {
-    Ok(state.all_tracks.lock().clone())
+fn set_obs_integration(
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AudioController>>,
+    enabled: bool,
+    url: String,
+    password: String,
+    mappings: Vec<obs::ObsSceneMapping>,
+) -> Result<(), AppError> {
+    let mut client = state.obs_client.lock();
+    *client = None;
+    if enabled {
+        if !url.starts_with("ws://") {
+            return Err(AppError::invalid(format!("OBS address must start with ws://: {}", url)));
+        }
+        // The client keeps reconnecting in the background, so OBS doesn't need to be running yet
+        *client = Some(obs::ObsClient::start(app, url, password, mappings));
+    }
+    Ok(())
 }
 
+// Live streaming commands
 #[tauri::command]
-fn stop_music(state: tauri::State<Arc<AudioController>>) -> Result<(), String> {
-    state.send(AudioCommand::Stop);
+fn set_streaming(state: tauri::State<Arc<AudioController>>, config: streaming::StreamingConfig) -> Result<(), AppError> {
+    let mut streamer = state.streamer.lock();
+    *streamer = None;
+    if config.enabled {
+        *streamer = Some(streaming::Streamer::start(config, state.mix_tap.clone())?);
+    }
     Ok(())
 }
 
+// None while streaming is off
 #[tauri::command]
-fn pause_music(state: tauri::State<Arc<AudioController>>) -> Result<(), String> {
-    state.send(AudioCommand::Pause);
-    Ok(())
+fn get_streaming_status(state: tauri::State<Arc<AudioController>>) -> Option<streaming::StreamingStatus> {
+    state.streamer.lock().as_ref().map(|s| s.status())
 }
 
+// Recording commands
 #[tauri::command]
-fn resume_music(state: tauri::State<Arc<AudioController>>) -> Result<(), String> {
-    state.send(AudioCommand::Resume);
-    Ok(())
+fn start_recording(
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AudioController>>,
+    config: recording::RecordingConfig,
+) -> Result<recording::RecordingStatus, AppError> {
+    let mut recorder = state.recorder.lock();
+    if recorder.is_some() {
+        return Err(AppError::conflict("Already recording"));
+    }
+    let microphone = state.microphone.lock().is_some();
+    let started = recording::Recorder::start(config, state.mix_tap.clone(), playing_source_name(&app, &state), microphone, state.errors.clone())?;
+    let status = started.status();
+    *recorder = Some(started);
+    Ok(status)
 }
 
+// Returns the finished files, or None if nothing was recording
 #[tauri::command]
-fn seek_music(state: tauri::State<Arc<AudioController>>, position: f64) -> Result<(), String> {
-    state.send(AudioCommand::Seek(position));
-    Ok(())
+fn stop_recording(state: tauri::State<Arc<AudioController>>) -> Option<recording::RecordingStatus> {
+    let recorder = state.recorder.lock().take();
+    recorder.map(|r| r.stop())
 }
 
+// None while not recording
 #[tauri::command]
-fn play_soundboard(state: tauri::State<Arc<AudioController>>, file_path: String, volume: f32) -> Result<(), String> {
-    state.send(AudioCommand::PlaySoundboard { file_path, volume });
-    Ok(())
+fn get_recording_status(state: tauri::State<Arc<AudioController>>) -> Option<recording::RecordingStatus> {
+    state.recorder.lock().as_ref().map(|r| r.status())
 }
 
+// Logging commands
 #[tauri::command]
-fn stop_soundboard(state: tauri::State<Arc<AudioController>>) -> Result<(), String> {
-    state.send(AudioCommand::StopSoundboard);
-    Ok(())
+fn set_log_level(level: String) -> Result<(), AppError> {
+    logging::set_level(&level).map_err(AppError::invalid)
 }
 
+// The end of the log file, for bug reports
 #[tauri::command]
-fn set_duck_amount(state: tauri::State<Arc<AudioController>>, amount: f32) -> Result<(), String> {
-    state.send(AudioCommand::SetDuckAmount(amount));
-    Ok(())
+fn get_log_tail(app: tauri::AppHandle, lines: usize) -> Result<Vec<String>, AppError> {
+    Ok(logging::tail(&app, lines)?)
 }
 
+// Errors reported since startup (most recent last); new ones arrive as app://error events
 #[tauri::command]
-fn is_soundboard_playing(state: tauri::State<Arc<AudioController>>) -> bool {
-    *state.soundboard_playing.lock()
+fn get_recent_errors(state: tauri::State<Arc<AudioController>>) -> Vec<errors::ErrorEvent> {
+    state.errors.recent()
 }
 
+// Audio files in the configured recordings folder, newest first
 #[tauri::command]
-fn set_music_volume(state: tauri::State<Arc<AudioController>>, volume: f32) -> Result<(), String> {
-    state.send(AudioCommand::SetVolume(volume));
-    Ok(())
+fn list_recordings() -> Result<Vec<recording::RecordingFile>, AppError> {
+    Ok(recording::list_recordings(&get_settings()?.recording)?)
+}
+
+// Name of the schedule or preset that's playing, written into recording tags
+fn playing_source_name(app: &tauri::AppHandle, state: &AudioController) -> Option<String> {
+    let schedule_id = {
+        let sched = state.scheduler_state.lock();
+        sched.current_schedule_id.clone().filter(|_| sched.is_playing)
+    };
+    if let Some(schedule) = schedule_id.and_then(|id| load_schedule(app.clone(), id).ok()) {
+        return Some(schedule.name);
+    }
+    let preset_id = state.current_preset_id.lock().clone()?;
+    load_preset(app.clone(), preset_id).ok().map(|preset| preset.name)
 }
 
+// Replay buffer commands
 #[tauri::command]
-fn set_master_volume(state: tauri::State<Arc<AudioController>>, volume: f32) -> Result<(), String> {
-    state.send(AudioCommand::SetMasterVolume(volume));
+fn set_replay_buffer(state: tauri::State<Arc<AudioController>>, config: replay::ReplayConfig) -> Result<(), AppError> {
+    let mut buffer = state.replay_buffer.lock();
+    if !config.enabled {
+        *buffer = None;
+    } else if let Some(running) = buffer.as_ref() {
+        running.set_minutes(config.minutes);
+    } else {
+        *buffer = Some(replay::ReplayBuffer::start(config.minutes, state.mix_tap.clone()));
+    }
     Ok(())
 }
 
+// Seconds currently buffered, or None while the buffer is off
 #[tauri::command]
-fn set_music_muted(state: tauri::State<Arc<AudioController>>, muted: bool) -> Result<(), String> {
-    state.send(AudioCommand::SetMuted(muted));
+fn get_replay_buffer_status(state: tauri::State<Arc<AudioController>>) -> Option<f64> {
+    state.replay_buffer.lock().as_ref().map(|b| b.buffered_secs())
+}
+
+// Save the last `minutes` of the mix; the result arrives as a replay://saved event
+#[tauri::command]
+fn save_last(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>, minutes: f64, path: String) -> Result<(), AppError> {
+    let config = get_settings().map(|s| s.recording).unwrap_or_default();
+    let title = std::path::Path::new(&path).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let tags = encoder::Tags::new(title, playing_source_name(&app, &state));
+    let buffer = state.replay_buffer.lock();
+    let buffer = buffer.as_ref().ok_or_else(|| AppError::unavailable("The replay buffer is off"))?;
+    let saved_path = path.clone();
+    let reporter = state.errors.clone();
+    buffer.save_last(minutes, path, config, tags, move |result| {
+        if let Err(e) = &result {
+            reporter.report(errors::ErrorCode::Recording, format!("Saving the replay buffer failed: {}", e), Some(saved_path.clone()));
+        }
+        let _ = app.emit("replay://saved", replay::ReplaySaved {
+            path: saved_path,
+            duration_secs: *result.as_ref().unwrap_or(&0.0),
+            error: result.err(),
+        });
+    })?;
     Ok(())
 }
 
+// External player commands
 #[tauri::command]
-fn set_master_muted(state: tauri::State<Arc<AudioController>>, muted: bool) -> Result<(), String> {
-    state.send(AudioCommand::SetMasterMuted(muted));
+fn set_external_player(
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AudioController>>,
+    config: external_player::ExternalPlayerConfig,
+) -> Result<(), AppError> {
+    let mut player = state.external_player.lock();
+    if player.as_ref().map(|p| p.is_running_with(&config)).unwrap_or(false) {
+        return Ok(());
+    }
+    *player = None;
+    if config.enabled {
+        // Local music would play over the external player
+        state.send(AudioCommand::Stop { fade_secs: None });
+        *player = Some(external_player::ExternalPlayer::start(app, config, state.playback_state.clone())?);
+    }
     Ok(())
 }
 
-#[derive(Debug, Serialize)]
-struct MusicProgressResponse {
-    current_time: f64,
-    duration: f64,
-    is_playing: bool,
-    is_finished: bool,
+#[tauri::command]
+fn get_external_player_status(state: tauri::State<Arc<AudioController>>) -> Option<external_player::ExternalPlayerStatus> {
+    state.external_player.lock().as_ref().map(|p| p.status())
 }
 
+// What to do when a data file changed on another machine since it was loaded
 #[tauri::command]
-fn get_music_progress(state: tauri::State<Arc<AudioController>>) -> Result<MusicProgressResponse, String> {
-    let progress = state.get_progress();
-    Ok(MusicProgressResponse {
-        current_time: progress.current_time,
-        duration: progress.duration,
-        is_playing: progress.is_playing,
-        is_finished: progress.is_finished,
-    })
+fn set_data_sync(state: tauri::State<Arc<AudioController>>, config: data_sync::SyncConfig) {
+    state.data_sync.set_policy(config.on_conflict);
 }
 
-#[derive(Debug, Serialize)]
-struct PlaybackStateResponse {
-    music_playing: bool,
-    music_volume: f32,
-    ambient_count: u32,
-    ambient_volume: f32,
-    master_volume: f32,
-    is_muted: bool,
-    frequencies: Vec<f32>,
-    ambient_frequencies: Vec<f32>,
-}
-
-#[tauri::command]
-fn get_playback_state(state: tauri::State<Arc<AudioController>>) -> Result<PlaybackStateResponse, String> {
-    let ps = state.get_playback_state();
-    Ok(PlaybackStateResponse {
-        music_playing: ps.music_playing,
-        music_volume: ps.music_volume,
-        ambient_count: ps.ambient_count,
-        ambient_volume: ps.ambient_volume,
-        master_volume: ps.master_volume,
-        is_muted: ps.is_muted,
-        frequencies: ps.frequencies,
-        ambient_frequencies: ps.ambient_frequencies,
-    })
+// Webhook commands
+#[tauri::command]
+fn set_webhooks(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>, webhooks: Vec<webhooks::Webhook>) -> Result<(), AppError> {
+    let mut dispatcher = state.webhooks.lock();
+    *dispatcher = None;
+    if !webhooks.is_empty() {
+        *dispatcher = Some(webhooks::WebhookDispatcher::start(app, webhooks)?);
+    }
+    Ok(())
+}
+
+// In external-player mode the music transport goes to Spotify/MPD instead of the local sink.
+// Returns false when no external player is active.
+fn external_transport(state: &AudioController, command: external_player::Transport) -> bool {
+    match state.external_player.lock().as_ref() {
+        Some(player) => {
+            player.transport(command);
+            true
+        }
+        None => false,
+    }
 }
 
 // Ambient sound commands
 #[tauri::command]
 fn get_active_ambients(
     state: tauri::State<Arc<AudioController>>,
-) -> Result<Vec<ActiveAmbientInfo>, String> {
+) -> Result<Vec<ActiveAmbientInfo>, AppError> {
     let active = state.active_ambients.lock();
-    Ok(active.values().cloned().collect())
+    let phases = state.ambient_phases.lock();
+    Ok(active.values().cloned().map(|mut info| {
+        info.phase = phases.get(&info.id).copied();
+        info
+    }).collect())
+}
+
+// Level in dB of each playing ambient sound (after its own volume), keyed by id
+#[tauri::command]
+fn get_ambient_levels(state: tauri::State<Arc<AudioController>>) -> Result<HashMap<String, f32>, AppError> {
+    Ok(state.ambient_levels.lock().clone())
 }
 
 #[tauri::command]
 fn preload_ambient_sounds(
     state: tauri::State<Arc<AudioController>>,
     paths: Vec<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     state.send(AudioCommand::PreloadAmbient(paths));
     Ok(())
 }
@@ -3264,7 +2241,11 @@ fn play_ambient(
     pause_min: Option<u32>,
     pause_max: Option<u32>,
     volume_variation: Option<f32>,
-) -> Result<(), String> {
+    event_probability: Option<f32>,
+    sync_group: Option<String>,
+    random_start: Option<bool>,
+    duck_music: Option<bool>,
+) -> Result<(), AppError> {
     let settings = AmbientSettings {
         volume,
         pitch: pitch.unwrap_or(1.0),
@@ -3277,20 +2258,132 @@ fn play_ambient(
         pause_min: pause_min.unwrap_or(0),
         pause_max: pause_max.unwrap_or(0),
         volume_variation: volume_variation.unwrap_or(0.0),
+        event_probability: event_probability.unwrap_or(1.0),
+        sync_group: sync_group.filter(|g| !g.trim().is_empty()),
+        random_start: random_start.unwrap_or(false),
+        duck_music: duck_music.unwrap_or(false),
     };
     state.send(AudioCommand::PlayAmbient { id, file_a, file_b, settings });
     Ok(())
 }
 
+// Audition a file once, quietly and for at most `seconds`, without starting it as an ambient sound.
+// A new preview replaces the one playing.
+#[tauri::command]
+fn preview_ambient(state: tauri::State<Arc<AudioController>>, file_path: String, seconds: f32) -> Result<(), AppError> {
+    if !(seconds > 0.0 && seconds <= MAX_PREVIEW_SECS) {
+        return Err(AppError::invalid(format!("Preview length must be more than 0 and at most {} seconds", MAX_PREVIEW_SECS)));
+    }
+    if !Path::new(&file_path).is_file() {
+        return Err(AppError::not_found(format!("File not found: {}", file_path)));
+    }
+    state.send(AudioCommand::PreviewAmbient { file_path, seconds });
+    Ok(())
+}
+
+// Move a playing sound to other files, e.g. light rain to heavy rain. Its settings, cycle and any fade
+// stay as they are; the file it is on crossfades to the matching new one over `crossfade_ms`.
+#[tauri::command]
+fn swap_ambient_files(
+    state: tauri::State<Arc<AudioController>>,
+    id: String,
+    new_a: String,
+    new_b: String,
+    crossfade_ms: u32,
+) -> Result<(), AppError> {
+    if !state.active_ambients.lock().contains_key(&id) {
+        return Err(AppError::not_found(format!("Ambient sound isn't playing: {}", id)));
+    }
+    if crossfade_ms > MAX_SWAP_CROSSFADE_MS {
+        return Err(AppError::invalid(format!("Crossfade must be at most {} ms", MAX_SWAP_CROSSFADE_MS)));
+    }
+    for file in [&new_a, &new_b] {
+        if !Path::new(file).is_file() {
+            return Err(AppError::not_found(format!("File not found: {}", file)));
+        }
+    }
+    state.send(AudioCommand::SwapAmbientFiles { id, file_a: new_a, file_b: new_b, crossfade_secs: crossfade_ms as f32 / 1000.0 });
+    Ok(())
+}
+
+// Fades out over 200 ms unless `fade_ms` is given
+#[tauri::command]
+fn stop_ambient(state: tauri::State<Arc<AudioController>>, id: String, fade_ms: Option<u32>) -> Result<(), AppError> {
+    state.send(AudioCommand::StopAmbient { id, fade_secs: stop_fade_secs(fade_ms)? });
+    Ok(())
+}
+
+// Start a whole set of sounds (e.g. a preset) in one go instead of one play_ambient per sound
+#[tauri::command]
+fn apply_sounds(state: tauri::State<Arc<AudioController>>, sounds: Vec<ActiveAmbientInfo>) -> Result<(), AppError> {
+    for sound in &sounds {
+        if sound.settings.repeat_min > sound.settings.repeat_max || sound.settings.pause_min > sound.settings.pause_max {
+            return Err(AppError::invalid(format!("Invalid repeat or pause range for '{}'", sound.id)));
+        }
+    }
+    state.send(AudioCommand::ApplySounds(sounds));
+    Ok(())
+}
+
+#[tauri::command]
+fn stop_all_ambient(state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    state.send(AudioCommand::StopAllAmbient { fade_secs: None });
+    Ok(())
+}
+
+// Stop music, ambient sounds and the soundboard, remembering what was playing for restore_last_stopped
+#[tauri::command]
+fn stop_all(state: tauri::State<Arc<AudioController>>, fade_ms: Option<u32>) -> Result<(), AppError> {
+    let fade_secs = stop_fade_secs(fade_ms)?;
+    let ambients: Vec<ActiveAmbientInfo> = state.active_ambients.lock().values().cloned().collect();
+    let music = state.current_track.lock().clone().and_then(|track| {
+        let prog = state.progress.lock();
+        (!prog.is_finished).then(|| StoppedMusic { track, position: prog.current_time, paused: !prog.is_playing })
+    });
+    // Pressing it again with nothing playing mustn't throw away what the first press stopped
+    if !ambients.is_empty() || music.is_some() {
+        *state.last_stopped.lock() = Some(StoppedState { ambients, music });
+    }
+    if !external_transport(&state, external_player::Transport::Stop) {
+        state.send(AudioCommand::Stop { fade_secs });
+    }
+    state.send(AudioCommand::StopAllAmbient { fade_secs });
+    state.send(AudioCommand::StopSoundboard { fade_secs });
+    Ok(())
+}
+
+// Undo the last stop_all: the ambient sounds fade back in with their settings, and the track picks
+// up where it was. Soundboard clips are one-shots and aren't brought back.
+#[tauri::command]
+fn restore_last_stopped(state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    let stopped = state.last_stopped.lock().take()
+        .ok_or_else(|| AppError::not_found("Nothing was stopped"))?;
+    if !stopped.ambients.is_empty() {
+        state.send(AudioCommand::ApplySounds(stopped.ambients));
+    }
+    if let Some(music) = stopped.music {
+        let file_path = music.track.file_path.clone();
+        state.send(AudioCommand::Play { file_path, track_info: music.track });
+        if music.position > 0.0 {
+            state.send(AudioCommand::Seek(music.position));
+        }
+        if music.paused {
+            state.send(AudioCommand::Pause);
+        }
+    }
+    Ok(())
+}
+
+// Freeze every ambient sound mid-file, pauses and fades included, until resume_all_ambient
 #[tauri::command]
-fn stop_ambient(state: tauri::State<Arc<AudioController>>, id: String) -> Result<(), String> {
-    state.send(AudioCommand::StopAmbient(id));
+fn pause_all_ambient(state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    state.send(AudioCommand::PauseAllAmbient);
     Ok(())
 }
 
 #[tauri::command]
-fn stop_all_ambient(state: tauri::State<Arc<AudioController>>) -> Result<(), String> {
-    state.send(AudioCommand::StopAllAmbient);
+fn resume_all_ambient(state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    state.send(AudioCommand::ResumeAllAmbient);
     Ok(())
 }
 
@@ -3309,7 +2402,11 @@ fn update_ambient_settings(
     pause_min: Option<u32>,
     pause_max: Option<u32>,
     volume_variation: Option<f32>,
-) -> Result<(), String> {
+    event_probability: Option<f32>,
+    sync_group: Option<String>,
+    random_start: Option<bool>,
+    duck_music: Option<bool>,
+) -> Result<(), AppError> {
     let settings = AmbientSettings {
         volume,
         pitch: pitch.unwrap_or(1.0),
@@ -3322,31 +2419,123 @@ fn update_ambient_settings(
         pause_min: pause_min.unwrap_or(0),
         pause_max: pause_max.unwrap_or(0),
         volume_variation: volume_variation.unwrap_or(0.0),
+        event_probability: event_probability.unwrap_or(1.0),
+        sync_group: sync_group_for(&state, &id, sync_group),
+        random_start: random_start.unwrap_or_else(|| state.active_ambients.lock().get(&id).is_some_and(|info| info.settings.random_start)),
+        duck_music: duck_music.unwrap_or_else(|| state.active_ambients.lock().get(&id).is_some_and(|info| info.settings.duck_music)),
     };
     state.send(AudioCommand::UpdateAmbientSettings { id, settings });
     Ok(())
 }
 
+// A sound's sync group after a settings update: the one given (empty for none), or else the one it has
+fn sync_group_for(state: &AudioController, id: &str, sync_group: Option<String>) -> Option<String> {
+    match sync_group {
+        Some(group) => Some(group).filter(|g| !g.trim().is_empty()),
+        None => state.active_ambients.lock().get(id).and_then(|info| info.settings.sync_group.clone()),
+    }
+}
+
+// Put a playing sound in a sync group, or take it out with None or an empty name. Sounds in the same
+// group pause and start their A/B cycles together; the change applies when its current cycle ends.
+#[tauri::command]
+fn set_ambient_sync_group(state: tauri::State<Arc<AudioController>>, id: String, group: Option<String>) -> Result<(), AppError> {
+    if !state.active_ambients.lock().contains_key(&id) {
+        return Err(AppError::not_found(format!("Ambient sound isn't playing: {}", id)));
+    }
+    let group = group.map(|g| g.trim().to_string()).filter(|g| !g.is_empty());
+    state.send(AudioCommand::SetAmbientSyncGroup { id, group });
+    Ok(())
+}
+
+// Settings an intensity fader position maps to, keeping each range's max at or above its min
+fn apply_intensity(settings: &mut AmbientSettings, values: &IntensityValues) {
+    if let Some(volume) = values.volume {
+        settings.volume = volume / 100.0;
+    }
+    if let Some(event_probability) = values.event_probability {
+        settings.event_probability = event_probability / 100.0;
+    }
+    settings.repeat_min = values.repeat_range_min.unwrap_or(settings.repeat_min);
+    settings.repeat_max = values.repeat_range_max.unwrap_or(settings.repeat_max).max(settings.repeat_min);
+    settings.pause_min = values.pause_range_min.unwrap_or(settings.pause_min);
+    settings.pause_max = values.pause_range_max.unwrap_or(settings.pause_max).max(settings.pause_min);
+}
+
+// One fader for a playing sound: the sound's intensity macro (from its metadata, or the default one)
+// sets its volume, repeat and pause ranges and event probability. Returns the settings it ended up with.
+#[tauri::command]
+fn set_ambient_intensity(state: tauri::State<Arc<AudioController>>, id: String, intensity: u32) -> Result<AmbientSettings, AppError> {
+    let mut settings = state.active_ambients.lock().get(&id).map(|info| info.settings.clone())
+        .ok_or_else(|| AppError::not_found(format!("Ambient sound isn't playing: {}", id)))?;
+    let intensity_macro = state.library_cache.ambient_sound(&id)
+        .and_then(|sound| sound.defaults)
+        .and_then(|defaults| defaults.intensity_macro)
+        .unwrap_or_default();
+    apply_intensity(&mut settings, &intensity_macro.at(intensity.min(100)));
+    state.send(AudioCommand::UpdateAmbientSettings { id, settings: settings.clone() });
+    Ok(settings)
+}
+
 #[tauri::command]
-fn set_ambient_master_volume(state: tauri::State<Arc<AudioController>>, volume: f32) -> Result<(), String> {
-    state.send(AudioCommand::SetAmbientMasterVolume(volume));
+fn set_ambient_master_volume(state: tauri::State<Arc<AudioController>>, volume: f32) -> Result<(), AppError> {
+    state.send(AudioCommand::SetAmbientMasterVolume(volume.clamp(0.0, 1.0)));
     Ok(())
 }
 
 #[tauri::command]
-fn set_ambient_muted(state: tauri::State<Arc<AudioController>>, muted: bool) -> Result<(), String> {
+fn set_ambient_muted(state: tauri::State<Arc<AudioController>>, muted: bool) -> Result<(), AppError> {
     state.send(AudioCommand::SetAmbientMuted(muted));
     Ok(())
 }
 
+// Volume (0-1) of one ambient category, e.g. "weather", applied on top of the ambient master. The
+// category is the folder name; the change is saved to the settings.
+#[tauri::command]
+fn set_category_volume(state: tauri::State<Arc<AudioController>>, category_id: String, volume: f32) -> Result<(), AppError> {
+    update_category_volume(&state, category_id, |category| category.volume = volume.clamp(0.0, 1.0) * 100.0)
+}
+
+#[tauri::command]
+fn set_category_muted(state: tauri::State<Arc<AudioController>>, category_id: String, muted: bool) -> Result<(), AppError> {
+    update_category_volume(&state, category_id, |category| category.muted = muted)
+}
+
+// Every category's volume at once, as loaded from the settings; not saved
+#[tauri::command]
+fn set_category_volumes(state: tauri::State<Arc<AudioController>>, volumes: HashMap<String, CategoryVolume>) -> Result<(), AppError> {
+    *state.category_volumes.lock() = volumes.clone();
+    state.send(AudioCommand::SetCategoryVolumes(volumes));
+    Ok(())
+}
+
+fn update_category_volume(
+    state: &AudioController,
+    category_id: String,
+    update: impl FnOnce(&mut CategoryVolume),
+) -> Result<(), AppError> {
+    if category_id.trim().is_empty() {
+        return Err(AppError::invalid("Category id is empty"));
+    }
+    let volumes = {
+        let mut volumes = state.category_volumes.lock();
+        let category = volumes.entry(category_id.clone()).or_default();
+        update(category);
+        state.settings_writer.set_category_volume(&category_id, *category);
+        volumes.clone()
+    };
+    state.send(AudioCommand::SetCategoryVolumes(volumes));
+    Ok(())
+}
+
 #[tauri::command]
-fn set_soundboard_volume(state: tauri::State<Arc<AudioController>>, volume: f32) -> Result<(), String> {
-    state.send(AudioCommand::SetSoundboardVolume(volume));
+fn set_soundboard_volume(state: tauri::State<Arc<AudioController>>, volume: f32) -> Result<(), AppError> {
+    state.send(AudioCommand::SetSoundboardVolume(volume.clamp(0.0, 1.0)));
     Ok(())
 }
 
 #[tauri::command]
-fn set_soundboard_muted(state: tauri::State<Arc<AudioController>>, muted: bool) -> Result<(), String> {
+fn set_soundboard_muted(state: tauri::State<Arc<AudioController>>, muted: bool) -> Result<(), AppError> {
     state.send(AudioCommand::SetSoundboardMuted(muted));
     Ok(())
 }
@@ -3369,7 +2558,11 @@ fn play_ambient_scheduler(
     pause_min: Option<u32>,
     pause_max: Option<u32>,
     volume_variation: Option<f32>,
-) -> Result<(), String> {
+    event_probability: Option<f32>,
+    sync_group: Option<String>,
+    random_start: Option<bool>,
+    duck_music: Option<bool>,
+) -> Result<(), AppError> {
     let settings = AmbientSettings {
         volume,
         pitch: pitch.unwrap_or(1.0),
@@ -3382,13 +2575,17 @@ fn play_ambient_scheduler(
         pause_min: pause_min.unwrap_or(0),
         pause_max: pause_max.unwrap_or(0),
         volume_variation: volume_variation.unwrap_or(0.0),
+        event_probability: event_probability.unwrap_or(1.0),
+        sync_group: sync_group.filter(|g| !g.trim().is_empty()),
+        random_start: random_start.unwrap_or(false),
+        duck_music: duck_music.unwrap_or(false),
     };
     state.send(AudioCommand::PlayAmbientScheduler { id, file_a, file_b, settings });
     Ok(())
 }
 
 #[tauri::command]
-fn stop_ambient_scheduler(state: tauri::State<Arc<AudioController>>, id: String) -> Result<(), String> {
+fn stop_ambient_scheduler(state: tauri::State<Arc<AudioController>>, id: String) -> Result<(), AppError> {
     state.send(AudioCommand::StopAmbientScheduler(id));
     Ok(())
 }
@@ -3408,7 +2605,11 @@ fn update_ambient_settings_scheduler(
     pause_min: Option<u32>,
     pause_max: Option<u32>,
     volume_variation: Option<f32>,
-) -> Result<(), String> {
+    event_probability: Option<f32>,
+    sync_group: Option<String>,
+    random_start: Option<bool>,
+    duck_music: Option<bool>,
+) -> Result<(), AppError> {
     let settings = AmbientSettings {
         volume,
         pitch: pitch.unwrap_or(1.0),
@@ -3421,6 +2622,10 @@ fn update_ambient_settings_scheduler(
         pause_min: pause_min.unwrap_or(0),
         pause_max: pause_max.unwrap_or(0),
         volume_variation: volume_variation.unwrap_or(0.0),
+        event_probability: event_probability.unwrap_or(1.0),
+        sync_group: sync_group_for(&state, &id, sync_group),
+        random_start: random_start.unwrap_or_else(|| state.active_ambients.lock().get(&id).is_some_and(|info| info.settings.random_start)),
+        duck_music: duck_music.unwrap_or_else(|| state.active_ambients.lock().get(&id).is_some_and(|info| info.settings.duck_music)),
     };
     state.send(AudioCommand::UpdateAmbientSettingsScheduler { id, settings });
     Ok(())
@@ -3441,6 +2646,12 @@ fn get_playlists_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
     Ok(playlists_dir)
 }
 
+fn get_lighting_config_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data.join("lighting.json"))
+}
+
 fn get_favorites_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
     let app_data = app.path().app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
@@ -3457,8 +2668,7 @@ fn save_favorites_to_disk(app: &tauri::AppHandle, favorites: &[String]) -> Resul
     let path = get_favorites_path(app)?;
     let content = serde_json::to_string_pretty(favorites)
         .map_err(|e| format!("Failed to serialize favorites: {}", e))?;
-    fs::write(&path, content)
-        .map_err(|e| format!("Failed to write favorites file: {}", e))?;
+    app.state::<Arc<AudioController>>().data_sync.write(&path, &content)?;
     Ok(())
 }
 
@@ -3467,42 +2677,47 @@ fn load_favorites_from_disk(app: &tauri::AppHandle) -> Result<Vec<String>, Strin
     if !path.exists() {
         return Ok(Vec::new());
     }
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read favorites file: {}", e))?;
+    let content = app.state::<Arc<AudioController>>().data_sync.read(&path)?;
     let favorites: Vec<String> = serde_json::from_str(&content)
         .map_err(|e| format!("Failed to parse favorites: {}", e))?;
     Ok(favorites)
 }
 
-fn save_playlist_to_disk(app: &tauri::AppHandle, playlist: &MusicPlaylist) -> Result<(), String> {
+// Playlist writes and deletes are recorded for undo under `label`
+fn save_playlist_to_disk(app: &tauri::AppHandle, playlist: &MusicPlaylist, label: String) -> Result<(), String> {
     let playlists_dir = get_playlists_dir(app)?;
     let playlist_path = playlists_dir.join(format!("{}.playlist", &playlist.id));
     let content = serde_json::to_string_pretty(playlist)
         .map_err(|e| format!("Failed to serialize playlist: {}", e))?;
-    fs::write(&playlist_path, content)
-        .map_err(|e| format!("Failed to write playlist file: {}", e))?;
+    let controller = app.state::<Arc<AudioController>>();
+    let before = history::snapshot(&playlist_path);
+    controller.data_sync.write(&playlist_path, &content)?;
+    controller.history.record(history::EditKind::Playlist, label, &playlist_path, before, Some(content));
     Ok(())
 }
 
-fn delete_playlist_from_disk(app: &tauri::AppHandle, id: &str) -> Result<(), String> {
+fn delete_playlist_from_disk(app: &tauri::AppHandle, id: &str, label: String) -> Result<(), String> {
     let playlists_dir = get_playlists_dir(app)?;
     let playlist_path = playlists_dir.join(format!("{}.playlist", id));
     if playlist_path.exists() {
-        fs::remove_file(&playlist_path)
-            .map_err(|e| format!("Failed to delete playlist file: {}", e))?;
+        let controller = app.state::<Arc<AudioController>>();
+        let before = history::snapshot(&playlist_path);
+        controller.data_sync.remove(&playlist_path)?;
+        controller.history.record(history::EditKind::Playlist, label, &playlist_path, before, None);
     }
     Ok(())
 }
 
 fn load_playlists_from_disk(app: &tauri::AppHandle) -> Result<Vec<MusicPlaylist>, String> {
     let playlists_dir = get_playlists_dir(app)?;
+    let controller = app.state::<Arc<AudioController>>();
     let mut playlists = Vec::new();
     
     if let Ok(entries) = fs::read_dir(&playlists_dir) {
         for entry in entries.flatten() {
             let path = entry.path();
             if path.extension().and_then(|s| s.to_str()) == Some("playlist") {
-                if let Ok(content) = fs::read_to_string(&path) {
+                if let Ok(content) = controller.data_sync.read(&path) {
                     if let Ok(playlist) = serde_json::from_str::<MusicPlaylist>(&content) {
                         playlists.push(playlist);
                     }
@@ -3530,15 +2745,16 @@ fn get_presets_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
 }
 
 #[tauri::command]
-fn list_presets(app: tauri::AppHandle) -> Result<Vec<PresetInfo>, String> {
+fn list_presets(app: tauri::AppHandle) -> Result<Vec<PresetInfo>, AppError> {
     let presets_dir = get_presets_dir(&app)?;
+    let controller = app.state::<Arc<AudioController>>();
     let mut presets = Vec::new();
     
     if let Ok(entries) = fs::read_dir(&presets_dir) {
         for entry in entries.filter_map(|e| e.ok()) {
             let path = entry.path();
             if path.extension().map(|e| e == "soundscape").unwrap_or(false) {
-                if let Ok(content) = fs::read_to_string(&path) {
+                if let Ok(content) = controller.data_sync.read(&path) {
                     if let Ok(preset) = serde_json::from_str::<SoundscapePreset>(&content) {
                         presets.push(PresetInfo {
                             id: preset.id,
@@ -3546,6 +2762,7 @@ fn list_presets(app: tauri::AppHandle) -> Result<Vec<PresetInfo>, String> {
                             created: preset.created,
                             modified: preset.modified,
                             sound_count: preset.sounds.len(),
+                            etag: data_sync::etag(content.as_bytes()),
                         });
                     }
                 }
@@ -3560,7 +2777,7 @@ fn list_presets(app: tauri::AppHandle) -> Result<Vec<PresetInfo>, String> {
 }
 
 #[tauri::command]
-fn save_preset(app: tauri::AppHandle, name: String, sounds: Vec<PresetSound>) -> Result<PresetInfo, String> {
+fn save_preset(app: tauri::AppHandle, name: String, sounds: Vec<PresetSound>) -> Result<PresetInfo, AppError> {
     let presets_dir = get_presets_dir(&app)?;
     
     // Generate ID from name (sanitized filename)
@@ -3599,8 +2816,10 @@ fn save_preset(app: tauri::AppHandle, name: String, sounds: Vec<PresetSound>) ->
     let content = serde_json::to_string_pretty(&preset)
         .map_err(|e| format!("Failed to serialize preset: {}", e))?;
     
-    fs::write(&preset_path, content)
-        .map_err(|e| format!("Failed to write preset file: {}", e))?;
+    let controller = app.state::<Arc<AudioController>>();
+    let before = history::snapshot(&preset_path);
+    controller.data_sync.write(&preset_path, &content)?;
+    controller.history.record(history::EditKind::Preset, format!("Save preset \"{}\"", name), &preset_path, before, Some(content.clone()));
     
     Ok(PresetInfo {
         id: preset.id,
@@ -3608,20 +2827,20 @@ fn save_preset(app: tauri::AppHandle, name: String, sounds: Vec<PresetSound>) ->
         created: preset.created,
         modified: preset.modified,
         sound_count: preset.sounds.len(),
+        etag: data_sync::etag(content.as_bytes()),
     })
 }
 
 #[tauri::command]
-fn load_preset(app: tauri::AppHandle, id: String) -> Result<SoundscapePreset, String> {
+fn load_preset(app: tauri::AppHandle, id: String) -> Result<SoundscapePreset, AppError> {
     let presets_dir = get_presets_dir(&app)?;
     let preset_path = presets_dir.join(format!("{}.soundscape", &id));
     
     if !preset_path.exists() {
-        return Err(format!("Preset '{}' not found", id));
+        return Err(AppError::not_found(format!("Preset '{}' not found", id)));
     }
     
-    let content = fs::read_to_string(&preset_path)
-        .map_err(|e| format!("Failed to read preset file: {}", e))?;
+    let content = app.state::<Arc<AudioController>>().data_sync.read(&preset_path)?;
     
     let preset: SoundscapePreset = serde_json::from_str(&content)
         .map_err(|e| format!("Failed to parse preset: {}", e))?;
@@ -3630,20 +2849,128 @@ fn load_preset(app: tauri::AppHandle, id: String) -> Result<SoundscapePreset, St
 }
 
 #[tauri::command]
-fn delete_preset(app: tauri::AppHandle, id: String) -> Result<(), String> {
+fn delete_preset(app: tauri::AppHandle, id: String) -> Result<(), AppError> {
     let presets_dir = get_presets_dir(&app)?;
     let preset_path = presets_dir.join(format!("{}.soundscape", &id));
     
     if !preset_path.exists() {
-        return Err(format!("Preset '{}' not found", id));
+        return Err(AppError::not_found(format!("Preset '{}' not found", id)));
     }
     
-    fs::remove_file(&preset_path)
-        .map_err(|e| format!("Failed to delete preset: {}", e))?;
+    let controller = app.state::<Arc<AudioController>>();
+    let before = history::snapshot(&preset_path);
+    controller.data_sync.remove(&preset_path)?;
+    let name = before.as_deref()
+        .and_then(|content| serde_json::from_str::<SoundscapePreset>(content).ok())
+        .map(|preset| preset.name)
+        .unwrap_or(id);
+    controller.history.record(history::EditKind::Preset, format!("Delete preset \"{}\"", name), &preset_path, before, None);
     
     Ok(())
 }
 
+#[derive(Debug, Serialize)]
+struct ImportedContent {
+    presets: Vec<String>,   // ids
+    playlists: Vec<String>, // ids
+}
+
+// Download a shared .soundscape, .playlist or zip of them and install it into the presets and
+// playlists folders. Nothing is installed unless all of it is valid, and an id that's already taken
+// is a conflict unless `overwrite` is set. Each file is recorded for undo. Async so the download
+// runs on a blocking worker rather than the main thread.
+#[tauri::command]
+async fn import_from_url(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AudioController>>,
+    url: String,
+    overwrite: Option<bool>,
+) -> Result<ImportedContent, AppError> {
+    let download_url = url.clone();
+    let items = tauri::async_runtime::spawn_blocking(move || url_import::parse(&url_import::download(&download_url)?))
+        .await
+        .map_err(|e| AppError::unavailable(format!("Import failed: {}", e)))??;
+    let presets_dir = get_presets_dir(&app)?;
+    let playlists_dir = get_playlists_dir(&app)?;
+    let path_of = |item: &url_import::Shared| match item {
+        url_import::Shared::Preset(preset) => presets_dir.join(format!("{}.soundscape", preset.id)),
+        url_import::Shared::Playlist(playlist) => playlists_dir.join(format!("{}.playlist", playlist.id)),
+    };
+    if !overwrite.unwrap_or(false) {
+        let taken: Vec<String> = items.iter()
+            .filter(|item| path_of(item).exists())
+            .map(|item| match item {
+                url_import::Shared::Preset(preset) => format!("preset '{}'", preset.id),
+                url_import::Shared::Playlist(playlist) => format!("playlist '{}'", playlist.id),
+            })
+            .collect();
+        if !taken.is_empty() {
+            return Err(AppError::conflict(format!("Already installed: {}", taken.join(", "))));
+        }
+    }
+    
+    let mut imported = ImportedContent { presets: Vec::new(), playlists: Vec::new() };
+    for item in &items {
+        let path = path_of(item);
+        let (kind, label, content) = match item {
+            url_import::Shared::Preset(preset) => (
+                history::EditKind::Preset,
+                format!("Import preset \"{}\"", preset.name),
+                serde_json::to_string_pretty(preset).map_err(|e| format!("Failed to serialize preset: {}", e))?,
+            ),
+            url_import::Shared::Playlist(playlist) => (
+                history::EditKind::Playlist,
+                format!("Import playlist \"{}\"", playlist.name),
+                serde_json::to_string_pretty(playlist).map_err(|e| format!("Failed to serialize playlist: {}", e))?,
+            ),
+        };
+        let before = history::snapshot(&path);
+        state.data_sync.write(&path, &content)?;
+        state.history.record(kind, label, &path, before, Some(content));
+        let _ = app.emit(data_sync::CHANGED_EVENT, data_sync::DataChanged { kind: kind.data_kind(), path: path.to_string_lossy().to_string() });
+        match item {
+            url_import::Shared::Preset(preset) => imported.presets.push(preset.id.clone()),
+            url_import::Shared::Playlist(playlist) => imported.playlists.push(playlist.id.clone()),
+        }
+    }
+    if !imported.playlists.is_empty() {
+        load_saved_playlists_and_favorites(app.clone(), state.clone())?;
+    }
+    log::info!("Imported {} presets and {} playlists from {}", imported.presets.len(), imported.playlists.len(), url);
+    Ok(imported)
+}
+
+// Undo/redo of preset, playlist and soundboard edits
+#[tauri::command]
+fn undo(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>) -> Result<Option<history::UndoneEdit>, AppError> {
+    let edit = state.history.undo(&state.data_sync)?;
+    after_history_step(&app, &state, edit.as_ref())?;
+    Ok(edit)
+}
+
+#[tauri::command]
+fn redo(app: tauri::AppHandle, state: tauri::State<Arc<AudioController>>) -> Result<Option<history::UndoneEdit>, AppError> {
+    let edit = state.history.redo(&state.data_sync)?;
+    after_history_step(&app, &state, edit.as_ref())?;
+    Ok(edit)
+}
+
+#[tauri::command]
+fn get_history(state: tauri::State<Arc<AudioController>>) -> history::HistoryState {
+    state.history.state()
+}
+
+// Reload what the restored file feeds and tell the windows, as if it had changed on disk
+fn after_history_step(app: &tauri::AppHandle, state: &tauri::State<Arc<AudioController>>, edit: Option<&history::UndoneEdit>) -> Result<(), AppError> {
+    let Some(edit) = edit else { return Ok(()) };
+    log::info!("Restored: {}", edit.label);
+    if edit.kind == history::EditKind::Playlist {
+        load_saved_playlists_and_favorites(app.clone(), state.clone())?;
+    }
+    let _ = app.emit(data_sync::CHANGED_EVENT, data_sync::DataChanged { kind: edit.kind.data_kind(), path: edit.path.clone() });
+    Ok(())
+}
+
 #[tauri::command]
 fn get_current_preset_id(state: tauri::State<Arc<AudioController>>) -> Option<String> {
     state.current_preset_id.lock().clone()
@@ -3654,6 +2981,44 @@ fn set_current_preset_id(state: tauri::State<Arc<AudioController>>, id: Option<S
     *state.current_preset_id.lock() = id;
 }
 
+#[derive(Serialize)]
+struct RandomSoundscape {
+    sounds: Vec<PresetSound>,
+    preset: Option<PresetInfo>, // set when it was saved
+}
+
+// Build a soundscape of `target_layers` sounds from the library (see random_soundscape.rs), start
+// it in place of what's playing and, with `save_as`, keep it as a preset of that name
+#[tauri::command]
+fn generate_random_soundscape(
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AudioController>>,
+    category_filters: Vec<String>,
+    target_layers: u32,
+    save_as: Option<String>,
+) -> Result<RandomSoundscape, AppError> {
+    let layers = target_layers as usize;
+    if layers == 0 || layers > random_soundscape::MAX_LAYERS {
+        return Err(AppError::invalid(format!("Layers must be 1 - {}", random_soundscape::MAX_LAYERS)));
+    }
+    let save_as = save_as.map(|name| name.trim().to_string()).filter(|name| !name.is_empty());
+    let settings = get_settings()?;
+    let (categories, _) = state.library_cache.scan_ambient(Path::new(&settings.ambient_folder_path), true)?;
+    let sounds = random_soundscape::generate(&categories, &category_filters, layers).map_err(AppError::not_found)?;
+
+    let preset = match save_as {
+        Some(name) => Some(save_preset(app, name, sounds.clone())?),
+        None => None,
+    };
+    *state.current_preset_id.lock() = preset.as_ref().map(|p| p.id.clone());
+    let active = sounds.iter().map(|sound| {
+        let (settings, file_a, file_b) = render::preset_sound_settings(sound);
+        ActiveAmbientInfo { id: sound.sound_id.clone(), file_a, file_b, settings, phase: None }
+    }).collect();
+    state.send(AudioCommand::ApplySounds(active));
+    Ok(RandomSoundscape { sounds, preset })
+}
+
 // Schedule Preset Commands
 fn get_schedules_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
     let app_data = app.path().app_data_dir()
@@ -3669,15 +3034,16 @@ fn get_schedules_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
 }
 
 #[tauri::command]
-fn list_schedules(app: tauri::AppHandle) -> Result<Vec<SchedulePresetInfo>, String> {
+fn list_schedules(app: tauri::AppHandle) -> Result<Vec<SchedulePresetInfo>, AppError> {
     let schedules_dir = get_schedules_dir(&app)?;
+    let controller = app.state::<Arc<AudioController>>();
     let mut schedules = Vec::new();
     
     if let Ok(entries) = fs::read_dir(&schedules_dir) {
         for entry in entries.filter_map(|e| e.ok()) {
             let path = entry.path();
             if path.extension().map(|e| e == "schedule").unwrap_or(false) {
-                if let Ok(content) = fs::read_to_string(&path) {
+                if let Ok(content) = controller.data_sync.read(&path) {
                     if let Ok(schedule) = serde_json::from_str::<SchedulePreset>(&content) {
                         schedules.push(SchedulePresetInfo {
                             id: schedule.id,
@@ -3685,6 +3051,7 @@ fn list_schedules(app: tauri::AppHandle) -> Result<Vec<SchedulePresetInfo>, Stri
                             created: schedule.created,
                             modified: schedule.modified,
                             item_count: schedule.items.len(),
+                            etag: data_sync::etag(content.as_bytes()),
                         });
                     }
                 }
@@ -3699,7 +3066,16 @@ fn list_schedules(app: tauri::AppHandle) -> Result<Vec<SchedulePresetInfo>, Stri
 }
 
 #[tauri::command]
-fn save_schedule(app: tauri::AppHandle, name: String, items: Vec<ScheduledItem>) -> Result<SchedulePresetInfo, String> {
+fn save_schedule(
+    app: tauri::AppHandle,
+    name: String,
+    items: Vec<ScheduledItem>,
+    transition: Option<TransitionChime>,
+) -> Result<SchedulePresetInfo, AppError> {
+    if transition.as_ref().is_some_and(|chime| chime.volume > 100) {
+        return Err(AppError::invalid("Transition volume must be 0-100"));
+    }
+    check_event_volumes(&items)?;
     let schedules_dir = get_schedules_dir(&app)?;
     
     // Generate ID from name (sanitized filename)
@@ -3733,13 +3109,13 @@ fn save_schedule(app: tauri::AppHandle, name: String, items: Vec<ScheduledItem>)
         created,
         modified: now,
         items: items.clone(),
+        transition,
     };
     
     let content = serde_json::to_string_pretty(&schedule)
         .map_err(|e| format!("Failed to serialize schedule: {}", e))?;
     
-    fs::write(&schedule_path, content)
-        .map_err(|e| format!("Failed to write schedule file: {}", e))?;
+    app.state::<Arc<AudioController>>().data_sync.write(&schedule_path, &content)?;
     
     Ok(SchedulePresetInfo {
         id: schedule.id,
@@ -3747,20 +3123,20 @@ fn save_schedule(app: tauri::AppHandle, name: String, items: Vec<ScheduledItem>)
         created: schedule.created,
         modified: schedule.modified,
         item_count: schedule.items.len(),
+        etag: data_sync::etag(content.as_bytes()),
     })
 }
 
 #[tauri::command]
-fn load_schedule(app: tauri::AppHandle, id: String) -> Result<SchedulePreset, String> {
+fn load_schedule(app: tauri::AppHandle, id: String) -> Result<SchedulePreset, AppError> {
     let schedules_dir = get_schedules_dir(&app)?;
     let schedule_path = schedules_dir.join(format!("{}.schedule", &id));
     
     if !schedule_path.exists() {
-        return Err(format!("Schedule '{}' not found", id));
+        return Err(AppError::not_found(format!("Schedule '{}' not found", id)));
     }
     
-    let content = fs::read_to_string(&schedule_path)
-        .map_err(|e| format!("Failed to read schedule file: {}", e))?;
+    let content = app.state::<Arc<AudioController>>().data_sync.read(&schedule_path)?;
     
     let schedule: SchedulePreset = serde_json::from_str(&content)
         .map_err(|e| format!("Failed to parse schedule: {}", e))?;
@@ -3769,29 +3145,55 @@ fn load_schedule(app: tauri::AppHandle, id: String) -> Result<SchedulePreset, St
 }
 
 #[tauri::command]
-fn delete_schedule(app: tauri::AppHandle, id: String) -> Result<(), String> {
+fn delete_schedule(app: tauri::AppHandle, id: String) -> Result<(), AppError> {
     let schedules_dir = get_schedules_dir(&app)?;
     let schedule_path = schedules_dir.join(format!("{}.schedule", &id));
     
     if !schedule_path.exists() {
-        return Err(format!("Schedule '{}' not found", id));
+        return Err(AppError::not_found(format!("Schedule '{}' not found", id)));
     }
     
-    fs::remove_file(&schedule_path)
-        .map_err(|e| format!("Failed to delete schedule: {}", e))?;
+    app.state::<Arc<AudioController>>().data_sync.remove(&schedule_path)?;
     
     Ok(())
 }
 
+// Snapshot of the audio thread for bug reports about stuck fades or sounds that won't stop
+#[tauri::command]
+fn dump_debug_state(state: tauri::State<Arc<AudioController>>) -> Result<diagnostics::DebugState, AppError> {
+    let (reply, response) = channel();
+    state.send(AudioCommand::DumpDebugState(reply));
+    let engine = response.recv_timeout(std::time::Duration::from_secs(2))
+        .map_err(|_| AppError::unavailable("The audio thread did not respond"))?;
+    Ok(diagnostics::DebugState {
+        engine,
+        scheduler: state.scheduler_state.lock().clone(),
+        device: diagnostics::DeviceDebug::current(),
+    })
+}
+
+// Audio thread timing, queue depth, decode times and cache hit rate, for stutter reports
+#[tauri::command]
+fn get_engine_stats(state: tauri::State<Arc<AudioController>>) -> Result<diagnostics::EngineStatsView, AppError> {
+    Ok(state.engine_stats.snapshot())
+}
+
+#[tauri::command]
+fn reset_engine_stats(state: tauri::State<Arc<AudioController>>) -> Result<(), AppError> {
+    state.engine_stats.reset();
+    Ok(())
+}
+
 #[derive(Debug, Serialize, Clone)]
 struct AudioDevice {
     id: String,
     name: String,
     is_default: bool,
+    channels: u16,
 }
 
 #[tauri::command]
-fn get_output_devices() -> Result<Vec<AudioDevice>, String> {
+fn get_output_devices() -> Result<Vec<AudioDevice>, AppError> {
     let host = rodio::cpal::default_host();
     let default_device = host.default_output_device();
     let default_name = default_device.as_ref().and_then(|d| d.name().ok());
@@ -3803,6 +3205,7 @@ fn get_output_devices() -> Result<Vec<AudioDevice>, String> {
             let is_default = default_name.as_ref().map(|dn| dn == &name).unwrap_or(false);
             Some(AudioDevice {
                 id: name.clone(),
+                channels: test_tone::channels(&device).unwrap_or(2),
                 name,
                 is_default,
             })
@@ -3812,20 +3215,111 @@ fn get_output_devices() -> Result<Vec<AudioDevice>, String> {
     Ok(devices)
 }
 
+// Short sine on one channel (0-based) of an output device, to tell which physical output is which
+#[tauri::command]
+fn play_test_tone(device_id: String, channel: u16) -> Result<(), AppError> {
+    test_tone::play(&device_id, channel).map_err(AppError::unavailable)
+}
+
+// `soundscapes <command>` - control an already-running instance, returns the exit code
+pub fn run_cli(args: &[String]) -> i32 {
+    ipc::run_cli(args)
+}
+
+// Hands a soundscapes:// link to the running instance; false if none is running
+pub fn forward_deep_link(url: &str) -> bool {
+    ipc::forward_deep_link(url)
+}
+
+pub fn is_deep_link(arg: &str) -> bool {
+    deep_link::is_deep_link(arg)
+}
+
+// Shows the running instance's window; false if none is running
+pub fn focus_running_instance() -> bool {
+    ipc::focus_running_instance()
+}
+
+// `soundscapes --headless [options]`: run without windows; returns the exit code for bad options
+pub fn run_headless(args: &[String]) -> i32 {
+    if ipc::is_running() {
+        eprintln!("Soundscapes is already running");
+        return 1;
+    }
+    match headless::parse_args(args) {
+        Ok(options) => {
+            start(Some(options));
+            0
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            2
+        }
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let audio_controller = Arc::new(AudioController::new());
+    start(None)
+}
+
+fn start(headless: Option<headless::HeadlessOptions>) {
+    let audio_controller = Arc::new(AudioController::new(output::default_output()));
+    let mut context = tauri::generate_context!();
+    if headless.is_some() {
+        // Don't create the windows from tauri.conf.json
+        context.config_mut().app.windows.clear();
+    }
     
     tauri::Builder::default()
         .manage(audio_controller)
+        .setup(move |app| {
+            let log_level = get_settings().map(|s| s.log_level).unwrap_or_else(|_| default_log_level());
+            if let Err(e) = logging::init(app.handle(), &log_level) {
+                eprintln!("Logging disabled: {}", e);
+            }
+            ipc::start(app.handle().clone());
+            media_controls::start(app.handle().clone());
+            deep_link::start(app.handle());
+            data_sync::watch(app.handle().clone());
+            settings_watch::watch(app.handle().clone());
+            settings_writer::start(app.handle().clone());
+            state_events::start(app.handle().clone());
+            match &headless {
+                // A headless box may have no tray to put an icon in
+                Some(options) => headless::start(app.handle(), options)?,
+                None => {
+                    tray::start(app.handle())?;
+                    let shortcut = get_settings().map(|s| s.panic_mute_shortcut).unwrap_or_else(|_| default_panic_mute_shortcut());
+                    panic_mute::start(app.handle(), &shortcut)?;
+                }
+            }
+            Ok(())
+        })
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_deep_link::init())
         .on_window_event(|window, event| {
+            match event {
+                tauri::WindowEvent::Destroyed => {
+                    window.state::<Arc<AudioController>>().visualization.remove_window(window.label());
+                    focus::update(window.app_handle(), Some(window.label()));
+                }
+                // Minimizing and restoring come through as resizes
+                tauri::WindowEvent::Resized(_) | tauri::WindowEvent::Focused(_) => focus::update(window.app_handle(), None),
+                _ => {}
+            }
             if let tauri::WindowEvent::CloseRequested { .. } = event {
                 // If main window is closed, exit the entire app
                 if window.label() == "main" {
+                    let controller = window.state::<Arc<AudioController>>();
+                    // Finish an active recording so its file is playable
+                    if let Some(recorder) = controller.recorder.lock().take() {
+                        recorder.stop();
+                    }
+                    controller.settings_writer.flush(&controller);
                     std::process::exit(0);
                 }
             }
@@ -3833,28 +3327,58 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             get_settings,
             save_settings,
+            validate_settings,
+            validate_content_folders,
             save_volume_setting,
             scan_music_folder,
             scan_ambient_folder,
             scan_soundboard_folder,
+            rescan,
             update_soundboard_sound,
+            create_album_metadata,
+            import_from_url,
+            create_ambient_category,
+            add_ambient_sound,
+            update_ambient_sound,
+            delete_ambient_sound,
             init_audio,
             play_music,
             stop_music,
             pause_music,
             resume_music,
             seek_music,
+            restart_track,
+            set_loop_section,
+            clear_loop_section,
             play_soundboard,
             stop_soundboard,
+            announce,
+            get_tts_voices,
+            render_schedule,
+            export_processed_sound,
+            cancel_render,
             set_duck_amount,
             is_soundboard_playing,
             set_music_volume,
             set_master_volume,
             set_music_muted,
             set_master_muted,
+            toggle_panic_mute,
+            get_panic_mute,
+            set_panic_mute_shortcut,
+            set_night_mode,
+            set_background_audio,
+            set_microphone,
             get_music_progress,
             get_current_track,
             set_crossfade_duration,
+            set_skip_crossfade_duration,
+            set_preset_crossfade_duration,
+            set_loudness_matching,
+            set_skip_on_silence,
+            set_audio_tick_interval,
+            set_realtime_priority,
+            set_mmap_threshold,
             get_playlist_state,
             load_saved_playlists_and_favorites,
             set_playlist_shuffle,
@@ -3863,33 +3387,88 @@ pub fn run() {
             set_playlist_index,
             play_next_track,
             play_previous_track,
+            play_album,
+            start_auto_dj,
+            set_track_tags,
             toggle_favorite,
             get_playlists,
             save_playlist,
+            update_playlist_settings,
             delete_playlist,
             set_all_tracks,
             get_all_tracks,
             get_playback_state,
+            set_frequency_scale,
+            set_fft_event_rate,
+            set_fft_smoothing,
+            set_fft_triggers,
+            get_meters,
+            set_visualization_active,
+            set_lighting_enabled,
+            set_remote_api,
+            set_osc,
+            set_streamdeck,
+            set_obs_integration,
+            set_streaming,
+            get_streaming_status,
+            start_recording,
+            stop_recording,
+            get_recording_status,
+            list_recordings,
+            get_recent_errors,
+            set_log_level,
+            set_data_sync,
+            get_log_tail,
+            set_replay_buffer,
+            get_replay_buffer_status,
+            save_last,
+            set_external_player,
+            get_external_player_status,
+            set_webhooks,
+            get_lighting_config_location,
             get_active_ambients,
+            get_ambient_levels,
             preload_ambient_sounds,
             play_ambient,
             stop_ambient,
+            apply_sounds,
             stop_all_ambient,
+            stop_all,
+            restore_last_stopped,
+            pause_all_ambient,
+            resume_all_ambient,
             update_ambient_settings,
+            set_ambient_intensity,
+            set_ambient_sync_group,
+            preview_ambient,
+            swap_ambient_files,
             set_ambient_master_volume,
             set_ambient_muted,
+            set_category_volume,
+            set_category_muted,
+            set_category_volumes,
             set_soundboard_volume,
             set_soundboard_muted,
             play_ambient_scheduler,
             stop_ambient_scheduler,
             update_ambient_settings_scheduler,
             get_output_devices,
+            set_output_device,
+            set_soundboard_output_device,
+            play_test_tone,
+            dump_debug_state,
+            get_engine_stats,
+            undo,
+            redo,
+            get_history,
+            reset_engine_stats,
             list_presets,
             save_preset,
             load_preset,
             delete_preset,
             get_current_preset_id,
             set_current_preset_id,
+            generate_random_soundscape,
             list_schedules,
             save_schedule,
             load_schedule,
@@ -3898,6 +3477,6 @@ pub fn run() {
             start_scheduler_playback,
             stop_scheduler_playback
         ])
-        .run(tauri::generate_context!())
+        .run(context)
         .expect("error while running tauri application");
 }
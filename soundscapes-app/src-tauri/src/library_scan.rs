@@ -0,0 +1,154 @@
+// Library scanning - each music album and ambient category is a folder with a metadata.json. Large
+// libraries used to be read one folder at a time, from scratch, on every window open. Folders are
+// now read in parallel, and each folder's parsed metadata is kept with the file's modification time
+// and size, so a rescan only re-reads the ones that changed.
+use parking_lot::Mutex;
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+use crate::{AmbientCategory, AmbientMetadata, AmbientSoundDef, MusicAlbum, MusicMetadata, SoundboardData};
+
+// metadata.json modification time and size
+type Stamp = (Option<SystemTime>, u64);
+
+struct FolderCache<T> {
+    // Folder path -> metadata stamp and what was parsed from it
+    folders: Mutex<HashMap<PathBuf, (Stamp, T)>>,
+}
+
+impl<T> Default for FolderCache<T> {
+    fn default() -> Self {
+        Self { folders: Mutex::new(HashMap::new()) }
+    }
+}
+
+#[derive(Default)]
+pub struct LibraryCache {
+    albums: FolderCache<MusicAlbum>,
+    categories: FolderCache<AmbientCategory>,
+}
+
+// How much of a scan was served from the cache
+#[derive(Debug, Serialize, Clone, Copy, Default)]
+pub struct ScanStats {
+    pub folders: usize,
+    pub reread: usize,
+}
+
+// rescan result
+#[derive(Debug, Serialize)]
+pub struct LibraryScan {
+    pub albums: Vec<MusicAlbum>,
+    pub categories: Vec<AmbientCategory>,
+    pub soundboard: SoundboardData,
+    pub music_stats: ScanStats,
+    pub ambient_stats: ScanStats,
+}
+
+impl LibraryCache {
+    pub fn scan_music(&self, folder: &Path, incremental: bool) -> Result<(Vec<MusicAlbum>, ScanStats), String> {
+        scan(&self.albums, folder, incremental, |path, metadata: MusicMetadata| MusicAlbum {
+            name: metadata.name,
+            artist: metadata.artist,
+            tracks: metadata.tracks,
+            path: path.to_string_lossy().to_string(),
+        })
+    }
+
+    pub fn scan_ambient(&self, folder: &Path, incremental: bool) -> Result<(Vec<AmbientCategory>, ScanStats), String> {
+        scan(&self.categories, folder, incremental, |path, metadata: AmbientMetadata| AmbientCategory {
+            name: metadata.name,
+            icon: metadata.icon,
+            sounds: metadata.sounds,
+            path: path.to_string_lossy().to_string(),
+        })
+    }
+
+    // A scanned ambient sound by id (ids are unique across categories)
+    pub fn ambient_sound(&self, id: &str) -> Option<AmbientSoundDef> {
+        let folders = self.categories.folders.lock();
+        folders.values().flat_map(|(_, category)| &category.sounds).find(|sound| sound.id == id).cloned()
+    }
+}
+
+// Library folders are stored canonical, so the same folder reached through a symlink or a relative
+// path is one folder. dunce keeps Windows drive paths in their usual C:\... form rather than the
+// \\?\ form std returns, which the webview can't open. A path that can't be resolved (e.g. a
+// network share that's offline) is kept as given.
+pub fn canonical(path: &Path) -> PathBuf {
+    dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn stamp(path: &Path) -> Option<Stamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok(), metadata.len()))
+}
+
+// Subfolders of `folder` that have a metadata.json, parsed with `build`. Without `incremental`, every
+// folder is read again. Cached folders that are gone are dropped from the cache.
+fn scan<T, M>(
+    cache: &FolderCache<T>,
+    folder: &Path,
+    incremental: bool,
+    build: impl Fn(&Path, M) -> T + Send + Sync,
+) -> Result<(Vec<T>, ScanStats), String>
+where
+    T: Clone + Send + Sync,
+    M: DeserializeOwned,
+{
+    if !folder.exists() {
+        return Ok((Vec::new(), ScanStats::default()));
+    }
+    let folder = &canonical(folder);
+
+    let mut dirs = Vec::new();
+    for entry in WalkDir::new(folder).min_depth(1).max_depth(1) {
+        let entry = entry.map_err(|e| format!("Failed to read directory: {}", e))?;
+        if entry.file_type().is_dir() {
+            dirs.push(entry.into_path());
+        }
+    }
+
+    // What the cache holds for each folder, taken up front so the lock isn't held while reading files
+    let cached: Vec<Option<(Stamp, T)>> = {
+        let folders = cache.folders.lock();
+        dirs.iter().map(|dir| if incremental { folders.get(dir).cloned() } else { None }).collect()
+    };
+    let results: Vec<Option<(PathBuf, Stamp, T, bool)>> = dirs
+        .into_par_iter()
+        .zip(cached)
+        .map(|(dir, cached)| {
+            let metadata_path = dir.join("metadata.json");
+            let Some(stamp) = stamp(&metadata_path) else { return Ok(None) };
+            if let Some((cached_stamp, item)) = cached {
+                if cached_stamp == stamp {
+                    return Ok(Some((dir, stamp, item, false)));
+                }
+            }
+            let content = fs::read_to_string(&metadata_path).map_err(|e| format!("Failed to read metadata: {}", e))?;
+            let metadata: M = serde_json::from_str(&content).map_err(|e| format!("Failed to parse metadata: {}", e))?;
+            let item = build(&dir, metadata);
+            Ok(Some((dir, stamp, item, true)))
+        })
+        .collect::<Result<_, String>>()?;
+
+    let mut folders = cache.folders.lock();
+    folders.retain(|path, _| !path.starts_with(folder));
+    let mut items = Vec::new();
+    let mut stats = ScanStats::default();
+    for (dir, stamp, item, reread) in results.into_iter().flatten() {
+        stats.folders += 1;
+        if reread {
+            stats.reread += 1;
+        }
+        folders.insert(dir, (stamp, item.clone()));
+        items.push(item);
+    }
+    Ok((items, stats))
+}
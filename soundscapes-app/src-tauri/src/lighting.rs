@@ -0,0 +1,225 @@
+// Lighting sync - maps FFT bands and ambient intensity to Philips Hue or WLED lights on the local network
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+
+use crate::PlaybackState;
+
+const DEFAULT_FPS: u32 = 20;
+const MAX_FPS: u32 = 40;
+// The Hue bridge handles roughly 10 light updates per second, so each light is limited separately
+const HUE_MIN_INTERVAL: Duration = Duration::from_millis(100);
+const NETWORK_TIMEOUT: Duration = Duration::from_millis(300);
+const WLED_DEFAULT_PORT: u16 = 21324;
+// WLED realtime UDP protocol: DRGB (one RGB triplet per LED) and seconds before WLED returns to normal mode
+const WLED_PROTOCOL_DRGB: u8 = 2;
+const WLED_TIMEOUT_SECS: u8 = 2;
+
+// Contents of lighting.json
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LightingConfig {
+    #[serde(default = "default_fps")]
+    pub fps: u32,
+    #[serde(default)]
+    pub targets: Vec<LightTarget>,
+}
+
+fn default_fps() -> u32 {
+    DEFAULT_FPS
+}
+
+impl Default for LightingConfig {
+    fn default() -> Self {
+        Self { fps: DEFAULT_FPS, targets: Vec::new() }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum LightTarget {
+    Wled {
+        host: String,
+        #[serde(default = "default_wled_port")]
+        port: u16,
+        leds: usize,
+        #[serde(flatten)]
+        mapping: LightMapping,
+    },
+    Hue {
+        bridge: String,
+        username: String,
+        light: String,
+        #[serde(flatten)]
+        mapping: LightMapping,
+    },
+}
+
+fn default_wled_port() -> u16 {
+    WLED_DEFAULT_PORT
+}
+
+// Which part of the spectrum drives a light, and the color at full intensity
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LightMapping {
+    #[serde(default = "default_source")]
+    pub source: String, // "music", "ambient" or "soundboard"
+    #[serde(default = "default_band")]
+    pub band: [usize; 2], // first and last bucket (inclusive)
+    #[serde(default = "default_color")]
+    pub color: [u8; 3],
+    #[serde(default = "default_gain")]
+    pub gain: f32,
+}
+
+fn default_source() -> String {
+    "music".to_string()
+}
+
+fn default_band() -> [usize; 2] {
+    [0, 7]
+}
+
+fn default_color() -> [u8; 3] {
+    [255, 255, 255]
+}
+
+fn default_gain() -> f32 {
+    1.0
+}
+
+impl LightMapping {
+    // 0.0-1.0 intensity for this light from the current playback state
+    fn intensity(&self, state: &PlaybackState) -> f32 {
+        let (bins, volume) = match self.source.as_str() {
+            "ambient" => (&state.ambient_frequencies, state.ambient_volume),
+            "soundboard" => (&state.soundboard_frequencies, state.soundboard_volume),
+            _ => (&state.frequencies, state.music_volume),
+        };
+        if bins.is_empty() || state.is_muted {
+            return 0.0;
+        }
+        let end = self.band[1].min(bins.len() - 1);
+        let start = self.band[0].min(end);
+        let band = &bins[start..=end];
+        let level = band.iter().sum::<f32>() / band.len() as f32;
+        (level * volume.min(1.0) * self.gain).clamp(0.0, 1.0)
+    }
+}
+
+pub fn load_config(path: &Path) -> Result<LightingConfig, String> {
+    if !path.exists() {
+        return Ok(LightingConfig::default());
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read lighting config: {}", e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse lighting config: {}", e))
+}
+
+// Running lighting thread; dropping it stops the thread
+pub struct LightingSync {
+    stop: Arc<AtomicBool>,
+}
+
+impl LightingSync {
+    pub fn start(config: LightingConfig, playback_state: Arc<Mutex<PlaybackState>>) -> Result<Self, String> {
+        let socket = UdpSocket::bind("0.0.0.0:0")
+            .map_err(|e| format!("Failed to open lighting socket: {}", e))?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = stop.clone();
+        let frame_interval = Duration::from_secs_f32(1.0 / config.fps.clamp(1, MAX_FPS) as f32);
+
+        thread::spawn(move || {
+            let mut last_hue_update: Vec<Option<Instant>> = vec![None; config.targets.len()];
+
+            while !stop_clone.load(Ordering::Relaxed) {
+                let frame_start = Instant::now();
+                let state = playback_state.lock().clone();
+
+                for (i, target) in config.targets.iter().enumerate() {
+                    match target {
+                        LightTarget::Wled { host, port, leds, mapping } => {
+                            let intensity = mapping.intensity(&state);
+                            let _ = send_wled_frame(&socket, host, *port, *leds, scale_color(mapping.color, intensity));
+                        }
+                        LightTarget::Hue { bridge, username, light, mapping } => {
+                            if last_hue_update[i].map(|t| t.elapsed() < HUE_MIN_INTERVAL).unwrap_or(false) {
+                                continue;
+                            }
+                            last_hue_update[i] = Some(Instant::now());
+                            let intensity = mapping.intensity(&state);
+                            if let Err(e) = send_hue_state(bridge, username, light, intensity) {
+                                log::warn!("Hue update failed: {}", e);
+                            }
+                        }
+                    }
+                }
+
+                if let Some(remaining) = frame_interval.checked_sub(frame_start.elapsed()) {
+                    thread::sleep(remaining);
+                }
+            }
+        });
+
+        Ok(Self { stop })
+    }
+}
+
+impl Drop for LightingSync {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn scale_color(color: [u8; 3], intensity: f32) -> [u8; 3] {
+    color.map(|c| (c as f32 * intensity).round() as u8)
+}
+
+fn send_wled_frame(socket: &UdpSocket, host: &str, port: u16, leds: usize, color: [u8; 3]) -> Result<(), String> {
+    let mut packet = Vec::with_capacity(2 + leds * 3);
+    packet.push(WLED_PROTOCOL_DRGB);
+    packet.push(WLED_TIMEOUT_SECS);
+    for _ in 0..leds {
+        packet.extend_from_slice(&color);
+    }
+    socket.send_to(&packet, (host, port))
+        .map(|_| ())
+        .map_err(|e| format!("Failed to send to WLED {}: {}", host, e))
+}
+
+// Hue bridge v1 REST API over plain HTTP
+fn send_hue_state(bridge: &str, username: &str, light: &str, intensity: f32) -> Result<(), String> {
+    let body = if intensity <= 0.0 {
+        r#"{"on":false,"transitiontime":1}"#.to_string()
+    } else {
+        let bri = (intensity * 253.0).round() as u32 + 1;
+        format!(r#"{{"on":true,"bri":{},"transitiontime":1}}"#, bri)
+    };
+    let request = format!(
+        "PUT /api/{}/lights/{}/state HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        username, light, bridge, body.len(), body
+    );
+
+    let addr = if bridge.contains(':') { bridge.to_string() } else { format!("{}:80", bridge) };
+    let socket_addr = addr.to_socket_addrs()
+        .map_err(|e| format!("Invalid Hue bridge address {}: {}", bridge, e))?
+        .next()
+        .ok_or_else(|| format!("Could not resolve Hue bridge {}", bridge))?;
+    let mut stream = TcpStream::connect_timeout(&socket_addr, NETWORK_TIMEOUT)
+        .map_err(|e| format!("Failed to connect to Hue bridge {}: {}", bridge, e))?;
+    let _ = stream.set_read_timeout(Some(NETWORK_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(NETWORK_TIMEOUT));
+    stream.write_all(request.as_bytes())
+        .map_err(|e| format!("Failed to send Hue request: {}", e))?;
+
+    // Drain the response so the bridge doesn't see a reset connection
+    let mut response = [0u8; 512];
+    let _ = stream.read(&mut response);
+    Ok(())
+}
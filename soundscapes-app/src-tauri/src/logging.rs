@@ -0,0 +1,124 @@
+// Logging - the `log` macros write to a rotating file under app data as well as stderr, so a bug
+// report can include what the app was doing
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use parking_lot::Mutex;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tauri::Manager;
+
+const FILE_NAME: &str = "soundscapes.log";
+// Rotated at 5 MB, keeping soundscapes.log plus soundscapes.1.log .. soundscapes.4.log
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const KEEP_FILES: usize = 5;
+const MAX_TAIL_LINES: usize = 5000;
+// Our own records use this target prefix; dependencies are only kept at warn and above
+const OWN_TARGET: &str = env!("CARGO_CRATE_NAME");
+
+struct FileLogger {
+    dir: PathBuf,
+    // Open file and its current size
+    file: Mutex<Option<(File, u64)>>,
+}
+
+impl FileLogger {
+    fn open(&self) -> Option<(File, u64)> {
+        let path = self.dir.join(FILE_NAME);
+        let file = OpenOptions::new().create(true).append(true).open(&path).ok()?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Some((file, size))
+    }
+
+    fn rotate(&self) {
+        let _ = fs::remove_file(rotated_path(&self.dir, KEEP_FILES - 1));
+        for index in (1..KEEP_FILES - 1).rev() {
+            let _ = fs::rename(rotated_path(&self.dir, index), rotated_path(&self.dir, index + 1));
+        }
+        let _ = fs::rename(self.dir.join(FILE_NAME), rotated_path(&self.dir, 1));
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level() && (metadata.target().starts_with(OWN_TARGET) || metadata.level() <= Level::Warn)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} {:<5} [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        eprint!("{}", line);
+
+        let mut file = self.file.lock();
+        if file.as_ref().map(|(_, size)| *size >= MAX_FILE_BYTES).unwrap_or(false) {
+            *file = None;
+            self.rotate();
+        }
+        if file.is_none() {
+            *file = self.open();
+        }
+        if let Some((out, size)) = file.as_mut() {
+            if out.write_all(line.as_bytes()).is_ok() {
+                *size += line.len() as u64;
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some((file, _)) = self.file.lock().as_mut() {
+            let _ = file.flush();
+        }
+    }
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("soundscapes.{}.log", index))
+}
+
+pub fn log_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app.path().app_data_dir().map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data.join("logs"))
+}
+
+// Install the file logger; called once at startup
+pub fn init(app: &tauri::AppHandle, level: &str) -> Result<(), String> {
+    let dir = log_dir(app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    log::set_boxed_logger(Box::new(FileLogger { dir, file: Mutex::new(None) }))
+        .map_err(|e| format!("Failed to install logger: {}", e))?;
+    set_level(level)
+}
+
+// "off", "error", "warn", "info", "debug" or "trace"
+pub fn set_level(level: &str) -> Result<(), String> {
+    let filter = LevelFilter::from_str(level).map_err(|_| format!("Unknown log level: {}", level))?;
+    log::set_max_level(filter);
+    Ok(())
+}
+
+// The last `lines` lines, reaching into the previous file if the current one is shorter
+pub fn tail(app: &tauri::AppHandle, lines: usize) -> Result<Vec<String>, String> {
+    let dir = log_dir(app)?;
+    let lines = lines.min(MAX_TAIL_LINES);
+    let mut tail: Vec<String> = Vec::new();
+    for path in [dir.join(FILE_NAME), rotated_path(&dir, 1)] {
+        if tail.len() >= lines {
+            break;
+        }
+        let Ok(content) = fs::read_to_string(&path) else { continue };
+        let wanted = lines - tail.len();
+        let file_lines: Vec<&str> = content.lines().collect();
+        let mut older: Vec<String> = file_lines[file_lines.len().saturating_sub(wanted)..].iter().map(|l| l.to_string()).collect();
+        older.append(&mut tail);
+        tail = older;
+    }
+    Ok(tail)
+}
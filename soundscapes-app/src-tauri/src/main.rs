@@ -2,5 +2,28 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first() {
+        // A clicked soundscapes:// link goes to the running instance, or starts the app to handle it
+        Some(arg) if soundscapes_app_lib::is_deep_link(arg) => {
+            if soundscapes_app_lib::forward_deep_link(arg) {
+                return;
+            }
+        }
+        // Engine, scheduler and remote API only, without windows
+        Some(arg) if arg == "--headless" => {
+            std::process::exit(soundscapes_app_lib::run_headless(&args[1..]));
+        }
+        // Any other command is sent to the running instance instead of opening a window
+        // (older macOS passes -psn_... when launched from Finder, which is not a command)
+        Some(arg) if !arg.starts_with("-psn_") => {
+            std::process::exit(soundscapes_app_lib::run_cli(&args));
+        }
+        _ => {}
+    }
+    // Only one instance may own the audio device; a second launch focuses the first
+    if soundscapes_app_lib::focus_running_instance() {
+        return;
+    }
     soundscapes_app_lib::run()
 }
@@ -0,0 +1,138 @@
+// OS media controls - MPRIS on Linux, SMTC on Windows, Now Playing on macOS
+use souvlaki::{MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, MediaPosition, PlatformConfig, SeekDirection};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::{AudioCommand, AudioController};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const SEEK_STEP: Duration = Duration::from_secs(10);
+const COVER_FILE_NAMES: [&str; 4] = ["cover.jpg", "cover.png", "folder.jpg", "folder.png"];
+
+// Start the media controls thread; it mirrors the current track/playback state to the OS
+pub fn start(app: tauri::AppHandle) {
+    // SMTC needs the main window handle on Windows
+    #[cfg(target_os = "windows")]
+    let hwnd: Option<usize> = app.get_webview_window("main")
+        .and_then(|w| w.hwnd().ok())
+        .map(|h| h.0 as usize);
+    #[cfg(not(target_os = "windows"))]
+    let hwnd: Option<usize> = None;
+
+    thread::spawn(move || {
+        let config = PlatformConfig {
+            dbus_name: "soundscapes",
+            display_name: "Soundscapes",
+            hwnd: hwnd.map(|h| h as *mut std::ffi::c_void),
+        };
+        let mut controls = match MediaControls::new(config) {
+            Ok(c) => c,
+            Err(e) => {
+                log::warn!("Failed to create media controls: {:?}", e);
+                return;
+            }
+        };
+
+        let event_app = app.clone();
+        if let Err(e) = controls.attach(move |event| handle_event(&event_app, event)) {
+            log::warn!("Failed to attach media controls: {:?}", e);
+            return;
+        }
+
+        let mut last_track_id: Option<String> = None;
+        let mut last_status: Option<(bool, bool)> = None; // (is_playing, is_finished)
+        loop {
+            let controller = app.state::<Arc<AudioController>>();
+            let track = controller.get_current_track();
+            let progress = controller.get_progress();
+
+            let track_id = track.as_ref().map(|t| t.id.clone());
+            let track_changed = track_id != last_track_id;
+            if track_changed {
+                last_track_id = track_id;
+                let result = match track.as_ref() {
+                    Some(t) => {
+                        let cover_url = find_cover_url(&t.file_path);
+                        controls.set_metadata(MediaMetadata {
+                            title: Some(&t.title),
+                            artist: Some(&t.artist),
+                            album: Some(&t.album),
+                            cover_url: cover_url.as_deref(),
+                            duration: (progress.duration > 0.0).then(|| Duration::from_secs_f64(progress.duration)),
+                        })
+                    }
+                    None => controls.set_metadata(MediaMetadata::default()),
+                };
+                if let Err(e) = result {
+                    log::warn!("Failed to update media metadata: {:?}", e);
+                }
+            }
+
+            let status = (progress.is_playing, progress.is_finished);
+            if track_changed || last_status != Some(status) {
+                last_status = Some(status);
+                let position = Some(MediaPosition(Duration::from_secs_f64(progress.current_time.max(0.0))));
+                let playback = if track.is_none() || progress.is_finished {
+                    MediaPlayback::Stopped
+                } else if progress.is_playing {
+                    MediaPlayback::Playing { progress: position }
+                } else {
+                    MediaPlayback::Paused { progress: position }
+                };
+                let _ = controls.set_playback(playback);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+fn handle_event(app: &tauri::AppHandle, event: MediaControlEvent) {
+    let state = app.state::<Arc<AudioController>>();
+    match event {
+        MediaControlEvent::Play => state.send(AudioCommand::Resume),
+        MediaControlEvent::Pause => state.send(AudioCommand::Pause),
+        MediaControlEvent::Toggle => {
+            if state.get_progress().is_playing {
+                state.send(AudioCommand::Pause);
+            } else {
+                state.send(AudioCommand::Resume);
+            }
+        }
+        MediaControlEvent::Stop => state.send(AudioCommand::Stop { fade_secs: None }),
+        MediaControlEvent::Next => {
+            let _ = crate::play_next_track(state);
+        }
+        MediaControlEvent::Previous => {
+            let _ = crate::play_previous_track(state);
+        }
+        MediaControlEvent::SetPosition(MediaPosition(position)) => {
+            state.send(AudioCommand::Seek(position.as_secs_f64()));
+        }
+        MediaControlEvent::Seek(direction) => seek_relative(&state, direction, SEEK_STEP),
+        MediaControlEvent::SeekBy(direction, amount) => seek_relative(&state, direction, amount),
+        _ => {}
+    }
+}
+
+fn seek_relative(state: &AudioController, direction: SeekDirection, amount: Duration) {
+    let progress = state.get_progress();
+    let offset = amount.as_secs_f64();
+    let target = match direction {
+        SeekDirection::Forward => progress.current_time + offset,
+        SeekDirection::Backward => progress.current_time - offset,
+    };
+    state.send(AudioCommand::Seek(target.clamp(0.0, progress.duration.max(0.0))));
+}
+
+// Look for album art next to the track file
+fn find_cover_url(file_path: &str) -> Option<String> {
+    let dir = Path::new(file_path).parent()?;
+    COVER_FILE_NAMES.iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+        .map(|path| format!("file://{}", path.to_string_lossy()))
+}
@@ -0,0 +1,223 @@
+// Microphone monitoring - a GM narrating over the soundscape hears their own voice blended with
+// the ambience, and recordings and streams get the voice too. The default input device is captured
+// on a thread of its own into a short queue, and a source on the engine's output plays from it like
+// any other bus, with its own volume and reverb. The queue is kept short so the voice isn't late:
+// when the input runs ahead of the output the oldest samples are dropped, and when it falls behind
+// the source plays silence until a little has built up again.
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Sample, SampleFormat};
+use parking_lot::Mutex;
+use rodio::Source;
+use serde::{Deserialize, Serialize};
+use soundscapes_core::effects::{EffectParam, ReverbSource};
+use soundscapes_core::output::BoxedSource;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+// Most audio the queue holds before dropping the oldest, and how much it collects after running dry
+const MAX_LATENCY_SECS: f32 = 0.08;
+const REFILL_SECS: f32 = 0.02;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MicrophoneConfig {
+    pub enabled: bool,
+    pub volume: f32, // 0 - 100
+    pub reverb: f32, // 0 - 100, algorithmic reverb mix
+}
+
+impl Default for MicrophoneConfig {
+    fn default() -> Self {
+        Self { enabled: false, volume: 80.0, reverb: 0.0 }
+    }
+}
+
+// Captured samples, interleaved at the device's channel count and rate
+struct Queue {
+    samples: Mutex<VecDeque<f32>>,
+    channels: usize,
+    capacity: usize,
+    refill: usize,
+}
+
+impl Queue {
+    fn push(&self, data: impl Iterator<Item = f32>) {
+        let mut samples = self.samples.lock();
+        samples.extend(data);
+        // Whole frames, so the channels stay in place
+        let excess = samples.len().saturating_sub(self.capacity).next_multiple_of(self.channels);
+        samples.drain(..excess.min(samples.len()));
+    }
+}
+
+// Running capture; dropping it closes the input device and ends the source
+pub struct MicrophoneInput {
+    stop: Arc<AtomicBool>,
+    volume: EffectParam,
+    reverb: EffectParam,
+}
+
+impl MicrophoneInput {
+    // Open the default input device. The source plays what it captures until this is dropped.
+    pub fn start(config: &MicrophoneConfig) -> Result<(Self, BoxedSource), String> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = stop.clone();
+        let (opened_tx, opened) = mpsc::channel();
+        // The stream can't move between threads, so it is opened on the one that keeps it alive
+        thread::Builder::new()
+            .name("microphone".to_string())
+            .spawn(move || {
+                let stream = match open() {
+                    Ok((stream, queue)) => {
+                        let _ = opened_tx.send(Ok(queue));
+                        stream
+                    }
+                    Err(e) => {
+                        let _ = opened_tx.send(Err(e));
+                        return;
+                    }
+                };
+                while !stop_clone.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                drop(stream);
+            })
+            .map_err(|e| format!("Failed to start the microphone: {}", e))?;
+        let (queue, sample_rate) = opened.recv().map_err(|_| "Microphone thread stopped".to_string())??;
+
+        let input = Self {
+            stop: stop.clone(),
+            volume: EffectParam::new(config.volume / 100.0),
+            reverb: EffectParam::new(config.reverb / 100.0),
+        };
+        let source = MicrophoneSource {
+            channels: queue.channels as u16,
+            queue,
+            sample_rate,
+            stop,
+            volume: input.volume.clone(),
+            frame: Vec::new(),
+            position: 0,
+            refilling: true,
+        };
+        let source = ReverbSource::new(source, input.reverb.clone(), sample_rate);
+        Ok((input, Box::new(source)))
+    }
+
+    // Volume and reverb apply to the running source
+    pub fn set(&self, config: &MicrophoneConfig) {
+        self.volume.set(config.volume / 100.0);
+        self.reverb.set(config.reverb / 100.0);
+    }
+}
+
+impl Drop for MicrophoneInput {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn open() -> Result<(cpal::Stream, (Arc<Queue>, u32)), String> {
+    let device = cpal::default_host().default_input_device().ok_or("No microphone (input device) found")?;
+    let config = device
+        .default_input_config()
+        .map_err(|e| format!("Can't read the microphone configuration: {}", e))?;
+    let channels = config.channels().max(1) as usize;
+    let sample_rate = config.sample_rate().0;
+    let samples_for = |secs: f32| (secs * sample_rate as f32) as usize * channels;
+    let queue = Arc::new(Queue {
+        samples: Mutex::new(VecDeque::new()),
+        channels,
+        capacity: samples_for(MAX_LATENCY_SECS),
+        refill: samples_for(REFILL_SECS),
+    });
+
+    let error = |e: cpal::StreamError| log::warn!("Microphone: {}", e);
+    let stream_config = config.config();
+    let capture = queue.clone();
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => {
+            device.build_input_stream(&stream_config, move |data: &[f32], _: &_| capture.push(data.iter().copied()), error, None)
+        }
+        SampleFormat::I16 => device.build_input_stream(
+            &stream_config,
+            move |data: &[i16], _: &_| capture.push(data.iter().map(|s| s.to_sample::<f32>())),
+            error,
+            None,
+        ),
+        SampleFormat::U16 => device.build_input_stream(
+            &stream_config,
+            move |data: &[u16], _: &_| capture.push(data.iter().map(|s| s.to_sample::<f32>())),
+            error,
+            None,
+        ),
+        format => return Err(format!("Unsupported microphone sample format: {:?}", format)),
+    }
+    .map_err(|e| format!("Failed to open the microphone: {}", e))?;
+    stream.play().map_err(|e| format!("Failed to start the microphone: {}", e))?;
+    Ok((stream, (queue, sample_rate)))
+}
+
+struct MicrophoneSource {
+    queue: Arc<Queue>,
+    channels: u16,
+    sample_rate: u32,
+    stop: Arc<AtomicBool>,
+    volume: EffectParam,
+    frame: Vec<f32>,
+    position: usize,
+    // Playing silence until the queue has REFILL_SECS again
+    refilling: bool,
+}
+
+impl Iterator for MicrophoneSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        // A frame at a time, so a dry queue never leaves the channels out of step
+        if self.position >= self.frame.len() {
+            if self.stop.load(Ordering::Relaxed) {
+                return None;
+            }
+            let channels = self.queue.channels;
+            let mut samples = self.queue.samples.lock();
+            if self.refilling {
+                self.refilling = samples.len() < self.queue.refill;
+            } else if samples.len() < channels {
+                self.refilling = true;
+            }
+            self.frame.clear();
+            if self.refilling {
+                self.frame.resize(channels, 0.0);
+            } else {
+                let volume = self.volume.get();
+                self.frame.extend(samples.drain(..channels).map(|s| s * volume));
+            }
+            self.position = 0;
+        }
+        let sample = self.frame[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for MicrophoneSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,158 @@
+// Night mode - gentle compression with a peak ceiling, so a soundboard hit or a music swell at a
+// volume set for the quiet parts doesn't wake the household. Like the panic mute gate it wraps every
+// source the engine plays: each sound is compressed above THRESHOLD_DB and its peaks are held under
+// CEILING_DB, with the same gain on all channels of a frame so nothing moves in the stereo image.
+// Turning it off lets the gain recover at the release rate instead of jumping back.
+use rodio::Source;
+use soundscapes_core::output::{BoxedSource, OutputBackend};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const THRESHOLD_DB: f32 = -24.0;
+const RATIO: f32 = 3.0;
+const CEILING_DB: f32 = -6.0;
+const ATTACK_SECS: f32 = 0.01;
+const RELEASE_SECS: f32 = 0.4;
+
+#[derive(Default)]
+pub struct NightMode {
+    enabled: AtomicBool,
+}
+
+impl NightMode {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+}
+
+// Output backend that plays every source through the compressor
+pub struct NightModeOutput {
+    inner: Box<dyn OutputBackend>,
+    night_mode: Arc<NightMode>,
+}
+
+impl NightModeOutput {
+    pub fn new(inner: Box<dyn OutputBackend>, night_mode: Arc<NightMode>) -> Self {
+        Self { inner, night_mode }
+    }
+}
+
+impl OutputBackend for NightModeOutput {
+    fn play(&self, source: BoxedSource) -> Result<(), String> {
+        self.inner.play(Box::new(Compressor::new(source, self.night_mode.clone())))
+    }
+
+    fn set_device(&self, device: Option<&str>) -> Result<(), String> {
+        self.inner.set_device(device)
+    }
+
+    fn open_device(&self, device: Option<&str>) -> Result<Box<dyn OutputBackend>, String> {
+        Ok(Box::new(NightModeOutput::new(self.inner.open_device(device)?, self.night_mode.clone())))
+    }
+
+    fn plays_in_real_time(&self) -> bool {
+        self.inner.plays_in_real_time()
+    }
+}
+
+fn coefficient(seconds: f32, sample_rate: u32) -> f32 {
+    1.0 - (-1.0 / (seconds * sample_rate.max(1) as f32)).exp()
+}
+
+struct Compressor {
+    inner: BoxedSource,
+    night_mode: Arc<NightMode>,
+    // The current frame, read ahead so its peak sets the gain for all of it
+    frame: Vec<f32>,
+    position: usize,
+    // Samples into the current frame while passing samples straight through
+    channel: u16,
+    envelope: f32,
+    gain: f32,
+}
+
+impl Compressor {
+    fn new(inner: BoxedSource, night_mode: Arc<NightMode>) -> Self {
+        Self { inner, night_mode, frame: Vec::new(), position: 0, channel: 0, envelope: 0.0, gain: 1.0 }
+    }
+
+    fn read_frame(&mut self) {
+        self.frame.clear();
+        self.position = 0;
+        let channels = self.inner.channels().max(1);
+        let sample_rate = self.inner.sample_rate();
+        for _ in 0..channels {
+            match self.inner.next() {
+                Some(sample) => self.frame.push(sample),
+                None => break,
+            }
+        }
+
+        let peak = self.frame.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        let rate = if peak > self.envelope { ATTACK_SECS } else { RELEASE_SECS };
+        self.envelope += (peak - self.envelope) * coefficient(rate, sample_rate);
+
+        let target = if !self.night_mode.is_enabled() {
+            1.0
+        } else {
+            let over = 20.0 * self.envelope.max(1e-6).log10() - THRESHOLD_DB;
+            let compressed = if over > 0.0 { 10f32.powf(-over * (1.0 - 1.0 / RATIO) / 20.0) } else { 1.0 };
+            // The ceiling applies at once so a sudden hit never gets through; the compression
+            // follows the envelope
+            let ceiling = 10f32.powf(CEILING_DB / 20.0);
+            if peak * compressed > ceiling {
+                ceiling / peak
+            } else {
+                compressed
+            }
+        };
+        self.gain = if target < self.gain {
+            target
+        } else {
+            self.gain + (target - self.gain) * coefficient(RELEASE_SECS, sample_rate)
+        };
+    }
+}
+
+impl Iterator for Compressor {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position >= self.frame.len() {
+            // Nothing to do while it's off and fully recovered; it picks up again at a frame start
+            if (self.channel != 0 || !self.night_mode.is_enabled()) && self.gain >= 0.999 {
+                self.gain = 1.0;
+                self.channel = (self.channel + 1) % self.inner.channels().max(1);
+                return self.inner.next();
+            }
+            self.read_frame();
+        }
+        let sample = *self.frame.get(self.position)?;
+        self.position += 1;
+        Some(sample * self.gain)
+    }
+}
+
+impl Source for Compressor {
+    fn current_frame_len(&self) -> Option<usize> {
+        // Plus what's been read ahead
+        self.inner.current_frame_len().map(|len| len + self.frame.len() - self.position)
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
@@ -0,0 +1,182 @@
+// OBS integration - follows the program scene over obs-websocket (v5) and switches presets/playlists to match
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+use crate::remote::{self, RemoteAction};
+
+// Emitted when OBS switches to a mapped scene, with the scene name
+const OBS_SCENE_EVENT: &str = "obs://scene";
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+// obs-websocket op codes and the "Scenes" event subscription bit
+const OP_HELLO: u64 = 0;
+const OP_IDENTIFY: u64 = 1;
+const OP_IDENTIFIED: u64 = 2;
+const OP_EVENT: u64 = 5;
+const OP_REQUEST: u64 = 6;
+const OP_REQUEST_RESPONSE: u64 = 7;
+const EVENT_SUBSCRIPTION_SCENES: u64 = 1 << 2;
+
+// A scene name and what to start when OBS switches to it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ObsSceneMapping {
+    pub scene: String,
+    #[serde(default)]
+    pub preset_id: Option<String>,
+    #[serde(default)]
+    pub playlist_id: Option<String>,
+}
+
+type ObsSocket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+// Running OBS connection; dropping it disconnects and stops reconnecting
+pub struct ObsClient {
+    stop: Arc<AtomicBool>,
+}
+
+impl ObsClient {
+    pub fn start(app: tauri::AppHandle, url: String, password: String, mappings: Vec<ObsSceneMapping>) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = stop.clone();
+
+        thread::spawn(move || {
+            let mut current_scene: Option<String> = None;
+            while !stop_clone.load(Ordering::Relaxed) {
+                if let Err(e) = run_session(&app, &url, &password, &mappings, &mut current_scene, &stop_clone) {
+                    log::warn!("OBS connection: {}", e);
+                }
+                // Wait before reconnecting, but notice a stop request quickly
+                let retry_at = Instant::now() + RECONNECT_INTERVAL;
+                while Instant::now() < retry_at && !stop_clone.load(Ordering::Relaxed) {
+                    thread::sleep(READ_TIMEOUT);
+                }
+            }
+        });
+
+        Self { stop }
+    }
+}
+
+impl Drop for ObsClient {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// One connection to OBS, from handshake until it closes or we're stopped
+fn run_session(
+    app: &tauri::AppHandle,
+    url: &str,
+    password: &str,
+    mappings: &[ObsSceneMapping],
+    current_scene: &mut Option<String>,
+    stop: &AtomicBool,
+) -> Result<(), String> {
+    let (mut ws, _) = tungstenite::connect(url)
+        .map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+
+    // Handshake: Hello -> Identify -> Identified
+    let hello = read_message(&mut ws)?;
+    if hello["op"].as_u64() != Some(OP_HELLO) {
+        return Err("Expected Hello from OBS".to_string());
+    }
+    let mut identify = json!({ "rpcVersion": 1, "eventSubscriptions": EVENT_SUBSCRIPTION_SCENES });
+    if let Some(auth) = hello["d"].get("authentication") {
+        let challenge = auth["challenge"].as_str().unwrap_or_default();
+        let salt = auth["salt"].as_str().unwrap_or_default();
+        identify["authentication"] = json!(auth_response(password, salt, challenge));
+    }
+    send_message(&mut ws, OP_IDENTIFY, identify)?;
+    let identified = read_message(&mut ws)?;
+    if identified["op"].as_u64() != Some(OP_IDENTIFIED) {
+        return Err("OBS rejected the connection (check the password)".to_string());
+    }
+
+    // Apply the mapping for whatever scene is live right now
+    send_message(&mut ws, OP_REQUEST, json!({ "requestType": "GetCurrentProgramScene", "requestId": "current-scene" }))?;
+
+    if let MaybeTlsStream::Plain(stream) = ws.get_mut() {
+        let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    }
+
+    while !stop.load(Ordering::Relaxed) {
+        let message = match ws.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str::<Value>(&text) {
+                Ok(value) => value,
+                Err(_) => continue,
+            },
+            Ok(Message::Close(_)) => return Err("OBS closed the connection".to_string()),
+            Ok(_) => continue,
+            Err(tungstenite::Error::Io(ref e))
+                if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => continue,
+            Err(e) => return Err(format!("Connection lost: {}", e)),
+        };
+
+        let data = &message["d"];
+        let scene = match message["op"].as_u64() {
+            Some(OP_EVENT) if data["eventType"] == "CurrentProgramSceneChanged" => data["eventData"]["sceneName"].as_str(),
+            Some(OP_REQUEST_RESPONSE) if data["requestType"] == "GetCurrentProgramScene" => data["responseData"]["currentProgramSceneName"].as_str(),
+            _ => None,
+        };
+        if let Some(scene) = scene {
+            if current_scene.as_deref() != Some(scene) {
+                *current_scene = Some(scene.to_string());
+                apply_scene(app, mappings, scene);
+            }
+        }
+    }
+
+    let _ = ws.close(None);
+    Ok(())
+}
+
+fn apply_scene(app: &tauri::AppHandle, mappings: &[ObsSceneMapping], scene: &str) {
+    let Some(mapping) = mappings.iter().find(|m| m.scene == scene) else {
+        return;
+    };
+    if let Some(id) = &mapping.preset_id {
+        if let Err(e) = remote::apply_action(app, RemoteAction::ApplyPreset { id: id.clone() }) {
+            log::warn!("OBS scene {}: {}", scene, e);
+        }
+    }
+    if let Some(id) = &mapping.playlist_id {
+        if let Err(e) = remote::apply_action(app, RemoteAction::PlayPlaylist { id: id.clone() }) {
+            log::warn!("OBS scene {}: {}", scene, e);
+        }
+    }
+    let _ = app.emit(OBS_SCENE_EVENT, scene);
+}
+
+// base64(sha256(base64(sha256(password + salt)) + challenge)), per the obs-websocket spec
+fn auth_response(password: &str, salt: &str, challenge: &str) -> String {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let secret = b64.encode(Sha256::digest(format!("{}{}", password, salt)));
+    b64.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+fn read_message(ws: &mut ObsSocket) -> Result<Value, String> {
+    loop {
+        match ws.read().map_err(|e| format!("Handshake failed: {}", e))? {
+            Message::Text(text) => {
+                return serde_json::from_str(&text).map_err(|e| format!("Invalid message from OBS: {}", e));
+            }
+            Message::Close(_) => return Err("OBS closed the connection".to_string()),
+            _ => {}
+        }
+    }
+}
+
+fn send_message(ws: &mut ObsSocket, op: u64, data: Value) -> Result<(), String> {
+    let text = json!({ "op": op, "d": data }).to_string();
+    ws.send(Message::Text(text)).map_err(|e| format!("Failed to send to OBS: {}", e))
+}
@@ -0,0 +1,202 @@
+// OSC input/output - lets lighting desks, TouchOSC layouts and show-control software drive the app
+use rosc::{OscMessage, OscPacket, OscType};
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::remote::{self, RemoteAction};
+
+const ADDRESS_PREFIX: &str = "/soundscapes";
+const MAX_PACKET_BYTES: usize = 4096;
+// Receive timeout, so the thread notices when it should stop
+const RECV_TIMEOUT: Duration = Duration::from_millis(200);
+const FEEDBACK_INTERVAL: Duration = Duration::from_millis(250);
+const BUSES: [&str; 4] = ["master", "music", "ambient", "soundboard"];
+
+// Running OSC server (and feedback client); dropping it stops the thread and frees the port
+pub struct OscServer {
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl OscServer {
+    // feedback is an optional host:port that receives state updates (fader positions, track title, ...).
+    // OSC has no token, so other devices can only reach it when LAN access is allowed for the remote API.
+    pub fn start(app: tauri::AppHandle, port: u16, feedback: Option<(String, u16)>, allow_lan: bool) -> Result<Self, String> {
+        let host = if allow_lan { "0.0.0.0" } else { "127.0.0.1" };
+        let socket = UdpSocket::bind((host, port))
+            .map_err(|e| format!("Failed to start OSC server on port {}: {}", port, e))?;
+        socket.set_read_timeout(Some(RECV_TIMEOUT))
+            .map_err(|e| format!("Failed to configure OSC socket: {}", e))?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = stop.clone();
+
+        let handle = thread::spawn(move || {
+            let mut buf = [0u8; MAX_PACKET_BYTES];
+            let mut feedback_state = FeedbackState::default();
+            let mut last_feedback = Instant::now() - FEEDBACK_INTERVAL;
+
+            while !stop_clone.load(Ordering::Relaxed) {
+                match socket.recv_from(&mut buf) {
+                    Ok((size, _)) => match rosc::decoder::decode_udp(&buf[..size]) {
+                        Ok((_, packet)) => handle_packet(&app, packet),
+                        Err(e) => log::warn!("Invalid OSC packet: {:?}", e),
+                    },
+                    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => {}
+                    Err(e) => log::warn!("OSC receive failed: {}", e),
+                }
+
+                if let Some((host, port)) = &feedback {
+                    if last_feedback.elapsed() >= FEEDBACK_INTERVAL {
+                        last_feedback = Instant::now();
+                        for message in feedback_state.changes(&app) {
+                            if let Ok(data) = rosc::encoder::encode(&OscPacket::Message(message)) {
+                                let _ = socket.send_to(&data, (host.as_str(), *port));
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(Self { stop, thread: Some(handle) })
+    }
+}
+
+impl Drop for OscServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Wait (at most RECV_TIMEOUT) so the port can be rebound right away
+        if let Some(handle) = self.thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn handle_packet(app: &tauri::AppHandle, packet: OscPacket) {
+    match packet {
+        OscPacket::Message(message) => {
+            if let Some(action) = parse_message(&message) {
+                if let Err(e) = remote::apply_action(app, action) {
+                    log::warn!("OSC {} failed: {}", message.addr, e);
+                }
+            }
+        }
+        OscPacket::Bundle(bundle) => {
+            for packet in bundle.content {
+                handle_packet(app, packet);
+            }
+        }
+    }
+}
+
+// Maps /soundscapes/... addresses onto remote actions:
+//   /pause /resume /toggle /next /previous /stop   (ignored when the argument is 0, i.e. button release)
+//   /seek <seconds>
+//   /volume/<bus> <0.0-1.0>   /mute/<bus> <0|1>
+//   /preset/<id>  or  /preset <id>
+//   /soundboard/<id>  or  /soundboard/play <id>,  /soundboard/stop
+fn parse_message(message: &OscMessage) -> Option<RemoteAction> {
+    let path = message.addr.strip_prefix(ADDRESS_PREFIX)?;
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let first = message.args.first();
+    let pressed = first.and_then(arg_f32).map(|v| v != 0.0).unwrap_or(true);
+
+    let action = match segments.as_slice() {
+        ["pause"] => RemoteAction::Pause,
+        ["resume"] | ["play"] => RemoteAction::Resume,
+        ["toggle"] => RemoteAction::TogglePlayback,
+        ["next"] => RemoteAction::Next,
+        ["previous"] => RemoteAction::Previous,
+        ["stop"] => RemoteAction::Stop,
+        ["seek"] => return first.and_then(arg_f32).map(|position| RemoteAction::Seek { position: position as f64 }),
+        ["volume", bus] => {
+            return first.and_then(arg_f32).map(|v| RemoteAction::SetVolume {
+                bus: bus.to_string(),
+                volume: v.clamp(0.0, 1.0) * 100.0,
+            })
+        }
+        ["mute", bus] => return Some(RemoteAction::SetMuted { bus: bus.to_string(), muted: pressed }),
+        ["preset"] => return first.and_then(arg_string).map(|id| RemoteAction::ApplyPreset { id }),
+        ["preset", id] => RemoteAction::ApplyPreset { id: id.to_string() },
+        ["soundboard", "stop"] => RemoteAction::StopSoundboard,
+        ["soundboard", "play"] => return first.and_then(arg_string).map(|id| RemoteAction::PlaySoundboard { id }),
+        ["soundboard", id] => RemoteAction::PlaySoundboard { id: id.to_string() },
+        _ => return None,
+    };
+    pressed.then_some(action)
+}
+
+fn arg_f32(arg: &OscType) -> Option<f32> {
+    match arg {
+        OscType::Float(v) => Some(*v),
+        OscType::Double(v) => Some(*v as f32),
+        OscType::Int(v) => Some(*v as f32),
+        OscType::Long(v) => Some(*v as f32),
+        OscType::Bool(v) => Some(if *v { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+fn arg_string(arg: &OscType) -> Option<String> {
+    match arg {
+        OscType::String(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+// Last values sent to the feedback client, so only changes go out
+#[derive(Default)]
+struct FeedbackState {
+    volumes: Option<[f32; 4]>,
+    playing: Option<bool>,
+    track: Option<String>,
+    preset: Option<String>,
+}
+
+impl FeedbackState {
+    fn changes(&mut self, app: &tauri::AppHandle) -> Vec<OscMessage> {
+        let state = match remote::get_state(app) {
+            Ok(state) => state,
+            Err(_) => return Vec::new(),
+        };
+        let mut messages = Vec::new();
+
+        let volumes = [state.volumes.master, state.volumes.music, state.volumes.ambient, state.volumes.soundboard];
+        for (i, bus) in BUSES.iter().enumerate() {
+            if self.volumes.map(|v| v[i] != volumes[i]).unwrap_or(true) {
+                messages.push(message(&format!("volume/{}", bus), OscType::Float(volumes[i] / 100.0)));
+            }
+        }
+        self.volumes = Some(volumes);
+
+        if self.playing != Some(state.progress.is_playing) {
+            self.playing = Some(state.progress.is_playing);
+            messages.push(message("playing", OscType::Int(state.progress.is_playing as i32)));
+        }
+
+        let track = state.track.map(|t| t.title).unwrap_or_default();
+        if self.track.as_ref() != Some(&track) {
+            messages.push(message("track", OscType::String(track.clone())));
+            self.track = Some(track);
+        }
+
+        let preset = state.current_preset_id.unwrap_or_default();
+        if self.preset.as_ref() != Some(&preset) {
+            messages.push(message("preset", OscType::String(preset.clone())));
+            self.preset = Some(preset);
+        }
+
+        messages
+    }
+}
+
+fn message(address: &str, arg: OscType) -> OscMessage {
+    OscMessage {
+        addr: format!("{}/{}", ADDRESS_PREFIX, address),
+        args: vec![arg],
+    }
+}
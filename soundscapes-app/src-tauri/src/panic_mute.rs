@@ -0,0 +1,175 @@
+// Panic mute - one global shortcut silences everything at once, e.g. when a call comes in
+// mid-soundscape, and a second press brings it back. The sinks and their volumes are left alone: every source the
+// engine plays passes through a gate whose gain ramps to 0 over FADE_SECS, per sample so the cut
+// doesn't click, and back to 1 on the next press, so every bus returns exactly where it was.
+use parking_lot::Mutex;
+use rodio::Source;
+use serde::Serialize;
+use soundscapes_core::output::{BoxedSource, OutputBackend};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+use crate::AudioController;
+
+pub const EVENT: &str = "audio://panic-mute";
+const FADE_SECS: f32 = 0.05;
+
+// audio://panic-mute payload
+#[derive(Clone, Serialize)]
+pub struct PanicMuteChanged {
+    pub muted: bool,
+}
+
+#[derive(Default)]
+pub struct PanicMute {
+    muted: AtomicBool,
+    // The global shortcut plugin is installed (not in headless mode)
+    shortcuts: AtomicBool,
+    // Shortcut currently registered for it
+    shortcut: Mutex<Option<String>>,
+}
+
+impl PanicMute {
+    pub fn is_muted(&self) -> bool {
+        self.muted.load(Ordering::Relaxed)
+    }
+
+    // Returns whether it is muted now
+    pub fn toggle(&self) -> bool {
+        !self.muted.fetch_xor(true, Ordering::Relaxed)
+    }
+}
+
+// Toggle and tell the windows
+pub fn toggle(app: &tauri::AppHandle) -> bool {
+    let muted = app.state::<Arc<AudioController>>().panic_mute.toggle();
+    log::info!("Panic mute {}", if muted { "on" } else { "off" });
+    let _ = app.emit(EVENT, PanicMuteChanged { muted });
+    muted
+}
+
+// Install the global shortcut plugin and register the shortcut from the settings. Headless mode
+// skips this, since a box without a display may have no way to grab keys.
+pub fn start(app: &tauri::AppHandle, shortcut: &str) -> tauri::Result<()> {
+    app.plugin(tauri_plugin_global_shortcut::Builder::new().build())?;
+    app.state::<Arc<AudioController>>().panic_mute.shortcuts.store(true, Ordering::Relaxed);
+    if let Err(e) = register(app, shortcut) {
+        log::warn!("{}", e);
+    }
+    Ok(())
+}
+
+// Replace the registered shortcut; an empty one just removes it
+pub fn register(app: &tauri::AppHandle, shortcut: &str) -> Result<(), String> {
+    let controller = app.state::<Arc<AudioController>>();
+    if !controller.panic_mute.shortcuts.load(Ordering::Relaxed) {
+        return Err("Global shortcuts aren't available in headless mode".to_string());
+    }
+    let mut current = controller.panic_mute.shortcut.lock();
+    let shortcut = shortcut.trim();
+    if current.as_deref() == Some(shortcut) {
+        return Ok(());
+    }
+    if let Some(old) = current.take() {
+        let _ = app.global_shortcut().unregister(old.as_str());
+    }
+    if shortcut.is_empty() {
+        return Ok(());
+    }
+    app.global_shortcut()
+        .on_shortcut(shortcut, |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                toggle(app);
+            }
+        })
+        .map_err(|e| format!("Can't use {} as the panic mute shortcut: {}", shortcut, e))?;
+    *current = Some(shortcut.to_string());
+    Ok(())
+}
+
+// Output backend that plays every source through the gate
+pub struct GatedOutput {
+    inner: Box<dyn OutputBackend>,
+    mute: Arc<PanicMute>,
+}
+
+impl GatedOutput {
+    pub fn new(inner: Box<dyn OutputBackend>, mute: Arc<PanicMute>) -> Self {
+        Self { inner, mute }
+    }
+}
+
+impl OutputBackend for GatedOutput {
+    fn play(&self, source: BoxedSource) -> Result<(), String> {
+        self.inner.play(Box::new(Gate::new(source, self.mute.clone())))
+    }
+
+    fn set_device(&self, device: Option<&str>) -> Result<(), String> {
+        self.inner.set_device(device)
+    }
+
+    fn open_device(&self, device: Option<&str>) -> Result<Box<dyn OutputBackend>, String> {
+        Ok(Box::new(GatedOutput::new(self.inner.open_device(device)?, self.mute.clone())))
+    }
+
+    fn plays_in_real_time(&self) -> bool {
+        self.inner.plays_in_real_time()
+    }
+}
+
+struct Gate {
+    inner: BoxedSource,
+    mute: Arc<PanicMute>,
+    gain: f32,
+    channel: u16,
+}
+
+impl Gate {
+    fn new(inner: BoxedSource, mute: Arc<PanicMute>) -> Self {
+        // A sound started while muted stays silent
+        let gain = if mute.is_muted() { 0.0 } else { 1.0 };
+        Self { inner, mute, gain, channel: 0 }
+    }
+}
+
+impl Iterator for Gate {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        // Step the gain once per frame, so both channels get the same one
+        if self.channel == 0 {
+            let target = if self.mute.is_muted() { 0.0 } else { 1.0 };
+            if self.gain != target {
+                let step = 1.0 / (FADE_SECS * self.inner.sample_rate().max(1) as f32);
+                self.gain = if target > self.gain { (self.gain + step).min(target) } else { (self.gain - step).max(target) };
+            }
+        }
+        let sample = self.inner.next()?;
+        self.channel += 1;
+        if self.channel >= self.inner.channels().max(1) {
+            self.channel = 0;
+        }
+        Some(sample * self.gain)
+    }
+}
+
+impl Source for Gate {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
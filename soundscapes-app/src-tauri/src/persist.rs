@@ -0,0 +1,35 @@
+// Crash-safe writes for settings, presets, playlists and the other JSON files - the content goes to
+// a temporary file next to the target, is flushed to disk and then renamed over it, so a crash or
+// power loss leaves either the old file or the new one, never half of one
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> io::Result<()> {
+    let temp = temp_path(path);
+    let result = write_and_rename(&temp, path, content.as_ref());
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+fn write_and_rename(temp: &Path, path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut file = File::create(temp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(temp, path)?;
+    // Flush the directory entry too, so the rename itself survives a crash
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        let _ = File::open(dir).and_then(|d| d.sync_all());
+    }
+    Ok(())
+}
+
+// ".settings.json.tmp" beside "settings.json"; the data watcher and folder scans skip it by extension
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    path.with_file_name(format!(".{}.tmp", name))
+}
@@ -0,0 +1,110 @@
+// Random soundscapes - "surprise me" for when nothing in the preset list fits. A few sounds are
+// picked from the scanned library and given randomized settings that stay in a range that sounds
+// like a scene rather than noise: the first sound is a steady bed, the others sit quieter on top as
+// occasional events. Filters match a category name or one of a sound's tags, so "rain" finds the
+// Rain category as well as a sound tagged rain somewhere else. Higher rated sounds are picked more
+// often, and 1-star sounds only when nothing else matches.
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashSet;
+
+use crate::{AmbientCategory, AmbientSoundDef, PresetSound};
+
+pub const MAX_LAYERS: usize = 12;
+const DEFAULT_RATING: u32 = 3;
+
+fn rating(sound: &AmbientSoundDef) -> u32 {
+    sound.rating.unwrap_or(DEFAULT_RATING).clamp(1, 5)
+}
+
+fn matches(filters: &[String], category: &AmbientCategory, sound: &AmbientSoundDef) -> bool {
+    filters.is_empty()
+        || filters.iter().any(|filter| {
+            category.name.eq_ignore_ascii_case(filter) || sound.tags.iter().any(|tag| tag.trim().eq_ignore_ascii_case(filter))
+        })
+}
+
+// Up to `layers` different sounds matching any of `filters` (all sounds when there are none)
+pub fn generate(categories: &[AmbientCategory], filters: &[String], layers: usize) -> Result<Vec<PresetSound>, String> {
+    let filters: Vec<String> = filters.iter().map(|f| f.trim().to_string()).filter(|f| !f.is_empty()).collect();
+    let mut candidates: Vec<(&AmbientCategory, &AmbientSoundDef)> = categories
+        .iter()
+        .flat_map(|category| category.sounds.iter().map(move |sound| (category, sound)))
+        .filter(|(category, sound)| matches(&filters, category, sound))
+        .collect();
+    if candidates.is_empty() {
+        return Err(if filters.is_empty() {
+            "The ambient library has no sounds".to_string()
+        } else {
+            format!("No ambient sounds match {}", filters.join(", "))
+        });
+    }
+    if candidates.iter().filter(|(_, sound)| rating(sound) > 1).count() >= layers {
+        candidates.retain(|(_, sound)| rating(sound) > 1);
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut used_categories = HashSet::new();
+    let mut sounds = Vec::new();
+    while sounds.len() < layers && !candidates.is_empty() {
+        // Spread the picks over categories first, so a soundscape isn't five kinds of rain
+        let fresh: Vec<usize> = (0..candidates.len())
+            .filter(|&i| !used_categories.contains(candidates[i].0.path.as_str()))
+            .collect();
+        let pool = if fresh.is_empty() { (0..candidates.len()).collect() } else { fresh };
+        let index = *pool
+            .choose_weighted(&mut rng, |&i| rating(candidates[i].1).pow(2))
+            .map_err(|e| format!("Failed to pick a sound: {}", e))?;
+        let (category, sound) = candidates.swap_remove(index);
+        used_categories.insert(category.path.as_str());
+        sounds.push(layer(&mut rng, category, sound, sounds.is_empty()));
+    }
+    Ok(sounds)
+}
+
+// Settings for one sound. The repeat and pause ranges come from the sound's own defaults where it
+// has them, since those depend on what the file is (a loop or a single bird call).
+fn layer(rng: &mut impl Rng, category: &AmbientCategory, sound: &AmbientSoundDef, bed: bool) -> PresetSound {
+    let defaults = sound.defaults.as_ref();
+    let repeat_range_min = defaults.and_then(|d| d.repeat_range_min).unwrap_or(1);
+    let repeat_range_max = defaults
+        .and_then(|d| d.repeat_range_max)
+        .unwrap_or_else(|| if bed { 1 } else { rng.gen_range(1..=3) })
+        .max(repeat_range_min);
+    let (pause_range_min, pause_range_max) = match defaults.and_then(|d| d.pause_range_min.zip(d.pause_range_max)) {
+        Some((min, max)) => (min, max.max(min)),
+        None if bed => (0, 0),
+        None => {
+            let min = rng.gen_range(2..=10);
+            (min, min + rng.gen_range(2..=15))
+        }
+    };
+    // Round to the steps the sliders use, so the values look chosen rather than random
+    let low_pass_freq = if rng.gen_bool(0.3) { rng.gen_range(40..=120) * 100 } else { 22000 };
+
+    PresetSound {
+        category_id: category.name.clone(),
+        category_path: category.path.clone(),
+        sound_id: sound.id.clone(),
+        name: sound.name.clone(),
+        files_a: sound.files.a.clone(),
+        files_b: sound.files.b.clone(),
+        enabled: true,
+        volume: if bed { rng.gen_range(60..=80) } else { rng.gen_range(30..=60) },
+        pitch: (rng.gen_range(0.92f32..=1.08) * 100.0).round() / 100.0,
+        pan: if bed { rng.gen_range(-20..=20) } else { rng.gen_range(-60..=60) },
+        low_pass_freq,
+        algorithmic_reverb: rng.gen_range(0..=30),
+        repeat_range_min,
+        repeat_range_max,
+        pause_range_min,
+        pause_range_max,
+        volume_variation: if bed { rng.gen_range(0..=5) } else { rng.gen_range(0..=15) },
+        event_probability: if bed { 100 } else { rng.gen_range(12..=20) * 5 },
+        intensity: None,
+        sync_group: None,
+        // Layers of the same soundscape shouldn't all start on their first sample
+        random_start: true,
+        duck_music: false,
+    }
+}
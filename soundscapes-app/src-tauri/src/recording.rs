@@ -0,0 +1,307 @@
+// Session recording - writes the live mix, or each bus as a separate stem, from the shared mix tap
+// to WAV or a compressed format
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::encoder::{self, AudioFileWriter, Tags};
+use crate::errors::{ErrorCode, ErrorReporter};
+use crate::streaming::{Bus, MixTap, MIX_INTERVAL, TAP_CHANNELS, TAP_RATE};
+
+pub const DEFAULT_BITRATE: u32 = 192;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RecordingConfig {
+    pub folder: String, // empty uses Music/Soundscapes Recordings
+    pub stems: bool,    // one file per bus instead of the mix
+    pub format: String, // "wav", "mp3", "ogg" or "opus"
+    pub bitrate: u32,   // kbps, for the compressed formats
+    // Start a new numbered file after this many minutes / megabytes; 0 disables each
+    pub split_minutes: u32,
+    pub split_mb: u32,
+    // Normalize finished files to target_lufs (stems are left alone so they still sum to the mix)
+    pub normalize: bool,
+    pub target_lufs: f64,
+}
+
+impl RecordingConfig {
+    fn target(&self) -> Option<f64> {
+        self.normalize.then_some(self.target_lufs)
+    }
+
+    // Writer for a file the user named (renders, replay saves): the format follows the extension
+    pub fn create_export_writer(&self, path: &str, tags: &Tags) -> Result<AudioFileWriter, String> {
+        AudioFileWriter::create_normalized(path, encoder::format_for_path(path), self.bitrate, tags, self.target())
+    }
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            folder: String::new(),
+            stems: false,
+            format: "wav".to_string(),
+            bitrate: DEFAULT_BITRATE,
+            split_minutes: 0,
+            split_mb: 0,
+            normalize: false,
+            target_lufs: -16.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RecordingStatus {
+    pub files: Vec<String>,
+    pub elapsed_secs: f64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RecordingFile {
+    pub path: String,
+    pub name: String,
+    pub format: String,
+    pub size_bytes: u64,
+    pub modified: String, // RFC 3339
+}
+
+// Where the recorded audio goes: the full mix or a single bus
+struct Track {
+    bus: Option<Bus>,
+    path: String,
+    writer: AudioFileWriter,
+}
+
+// Opens the files of each recording part; with splitting on, parts are numbered 001, 002, ...
+struct Parts {
+    config: RecordingConfig,
+    folder: PathBuf,
+    stamp: String,
+    number: u32,
+    // Preset or schedule playing when the recording started, for the file tags
+    source: Option<String>,
+    // Microphone monitoring was on when the recording started, so it gets a stem too
+    microphone: bool,
+}
+
+impl Parts {
+    fn splits(&self) -> bool {
+        self.config.split_minutes > 0 || self.config.split_mb > 0
+    }
+
+    fn open_next(&mut self) -> Result<Vec<Track>, String> {
+        self.number += 1;
+        let base = if self.splits() {
+            format!("Soundscapes {} {:03}", self.stamp, self.number)
+        } else {
+            format!("Soundscapes {}", self.stamp)
+        };
+        let targets: Vec<Option<Bus>> = if self.config.stems {
+            Bus::ALL.iter().filter(|bus| self.microphone || **bus != Bus::Microphone).map(|bus| Some(*bus)).collect()
+        } else {
+            vec![None]
+        };
+
+        let mut tracks = Vec::new();
+        for bus in targets {
+            let title = match bus {
+                Some(bus) => format!("{} {}", base, bus.name()),
+                None => base.clone(),
+            };
+            let path = self.folder.join(format!("{}.{}", title, self.config.format)).to_string_lossy().to_string();
+            let tags = Tags::new(title, self.source.clone());
+            let target = if bus.is_none() { self.config.target() } else { None };
+            let writer = AudioFileWriter::create_normalized(&path, &self.config.format, self.config.bitrate, &tags, target)?;
+            tracks.push(Track { bus, path, writer });
+        }
+        Ok(tracks)
+    }
+
+    // Whether the current part has reached the split length or size (the largest stem counts)
+    fn is_full(&self, tracks: &[Track], frames: u64) -> bool {
+        let minutes = self.config.split_minutes as u64;
+        if minutes > 0 && frames >= minutes * 60 * TAP_RATE as u64 {
+            return true;
+        }
+        let max_bytes = self.config.split_mb as u64 * 1024 * 1024;
+        max_bytes > 0 && tracks.iter().any(|t| {
+            std::fs::metadata(t.writer.disk_path(&t.path)).map(|m| m.len() >= max_bytes).unwrap_or(false)
+        })
+    }
+}
+
+fn finish_tracks(tracks: Vec<Track>) -> Result<(), String> {
+    let mut result = Ok(());
+    for track in tracks {
+        if let Err(e) = track.writer.finish() {
+            result = Err(e);
+        }
+    }
+    result
+}
+
+// Running recording; stop() (or dropping it) finalizes the files
+pub struct Recorder {
+    stop: Arc<AtomicBool>,
+    status: Arc<Mutex<RecordingStatus>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Recorder {
+    pub fn start(
+        config: RecordingConfig,
+        tap: Arc<MixTap>,
+        source: Option<String>,
+        microphone: bool,
+        errors: ErrorReporter,
+    ) -> Result<Self, String> {
+        let mut parts = Parts {
+            folder: recordings_folder(&config)?,
+            stamp: chrono::Local::now().format("%Y-%m-%d %H-%M-%S").to_string(),
+            config,
+            number: 0,
+            source,
+            microphone,
+        };
+        let mut tracks = parts.open_next()?;
+        let files = tracks.iter().map(|t| t.path.clone()).collect();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let status = Arc::new(Mutex::new(RecordingStatus { files, elapsed_secs: 0.0, error: None }));
+        let stop_clone = stop.clone();
+        let status_clone = status.clone();
+        let subscription = tap.subscribe();
+
+        let worker = thread::spawn(move || {
+            let started = Instant::now();
+            let mut failed = false;
+            // Frames written to the current part
+            let mut part_frames: u64 = 0;
+            let mut checked_at = Instant::now();
+            loop {
+                // Read the stop flag first so the last blocks are still written
+                let stopping = stop_clone.load(Ordering::Relaxed);
+                for block in subscription.take() {
+                    if failed {
+                        continue;
+                    }
+                    for track in tracks.iter_mut() {
+                        let result = match track.bus {
+                            Some(bus) => track.writer.write(block.bus(bus)),
+                            None => track.writer.write(&block.mix()),
+                        };
+                        if let Err(e) = result {
+                            errors.report(ErrorCode::Recording, format!("Recording stopped: {}", e), Some(track.path.clone()));
+                            status_clone.lock().error = Some(e);
+                            failed = true;
+                            break;
+                        }
+                    }
+                    part_frames += (block.bus(Bus::Music).len() / TAP_CHANNELS) as u64;
+                }
+
+                // Roll over to the next part; file sizes are checked about once a second
+                if !failed && !stopping && parts.splits() && checked_at.elapsed() >= Duration::from_secs(1) {
+                    checked_at = Instant::now();
+                    if parts.is_full(&tracks, part_frames) {
+                        // If the next part can't be opened, keep writing to the current one
+                        let result = parts.open_next().and_then(|next| {
+                            status_clone.lock().files.extend(next.iter().map(|t| t.path.clone()));
+                            part_frames = 0;
+                            finish_tracks(std::mem::replace(&mut tracks, next))
+                        });
+                        if let Err(e) = result {
+                            errors.report(ErrorCode::Recording, format!("Could not start the next part: {}", e), None);
+                            status_clone.lock().error = Some(e);
+                        }
+                    }
+                }
+
+                status_clone.lock().elapsed_secs = started.elapsed().as_secs_f64();
+                if stopping {
+                    break;
+                }
+                thread::sleep(MIX_INTERVAL);
+            }
+            if let Err(e) = finish_tracks(tracks) {
+                status_clone.lock().error = Some(e);
+            }
+        });
+
+        Ok(Self { stop, status, worker: Some(worker) })
+    }
+
+    pub fn status(&self) -> RecordingStatus {
+        self.status.lock().clone()
+    }
+
+    // Stop and wait for the files to be finalized
+    pub fn stop(mut self) -> RecordingStatus {
+        self.finish();
+        self.status()
+    }
+
+    fn finish(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+fn recordings_folder(config: &RecordingConfig) -> Result<PathBuf, String> {
+    let folder = if config.folder.trim().is_empty() {
+        dirs::audio_dir()
+            .or_else(dirs::home_dir)
+            .ok_or("Could not find a folder for recordings")?
+            .join("Soundscapes Recordings")
+    } else {
+        PathBuf::from(&config.folder)
+    };
+    std::fs::create_dir_all(&folder).map_err(|e| format!("Failed to create {}: {}", folder.display(), e))?;
+    Ok(folder)
+}
+
+// Audio files in the recordings folder, newest first
+pub fn list_recordings(config: &RecordingConfig) -> Result<Vec<RecordingFile>, String> {
+    let folder = recordings_folder(config)?;
+    let entries = std::fs::read_dir(&folder).map_err(|e| format!("Failed to read {}: {}", folder.display(), e))?;
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        // Skip the temporary files of a recording that's still being normalized
+        if name.ends_with(".normalizing.wav") {
+            continue;
+        }
+        let path_str = path.to_string_lossy().to_string();
+        let format = encoder::format_for_path(&path_str);
+        let Ok(metadata) = entry.metadata() else { continue };
+        let has_audio_extension = path.extension().map(|e| e.to_string_lossy().eq_ignore_ascii_case(format)).unwrap_or(false);
+        if !metadata.is_file() || !has_audio_extension {
+            continue;
+        }
+        let modified = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+        files.push((modified, RecordingFile {
+            path: path_str,
+            name,
+            format: format.to_string(),
+            size_bytes: metadata.len(),
+            modified: chrono::DateTime::<chrono::Local>::from(modified).to_rfc3339(),
+        }));
+    }
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(files.into_iter().map(|(_, file)| file).collect())
+}
@@ -0,0 +1,509 @@
+// Remote control API - optional HTTP server so phones and scripts can control the running app
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Listener, Manager};
+use tungstenite::{Message, WebSocket};
+
+use crate::external_player::Transport;
+use crate::vtt;
+use crate::{ActiveAmbientInfo, AudioCommand, AudioController, CurrentTrackInfo, MeterReadings, MusicProgressResponse, PresetInfo, SchedulerState, SoundboardSound};
+
+// Emitted after a remote action so open windows can refresh their state
+const REMOTE_ACTION_EVENT: &str = "remote://action";
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+// WebSocket clients are polled on this interval for incoming messages and state changes
+const WS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const WS_LEVELS_INTERVAL: Duration = Duration::from_millis(200);
+// Mobile control page served at / - it reads the token from its own ?token= and talks to the API above
+const REMOTE_PAGE: &str = include_str!("remote.html");
+
+// Control actions shared by every remote surface, e.g. {"action": "set_volume", "bus": "ambient", "volume": 40}
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RemoteAction {
+    Pause,
+    Resume,
+    TogglePlayback,
+    Next,
+    Previous,
+    Stop,
+    Seek { position: f64 },
+    SetVolume { bus: String, volume: f32 }, // volume 0-100, like the settings
+    SetMuted { bus: String, muted: bool },
+    ApplyPreset { id: String },
+    PlayPlaylist { id: String },
+    PlayTrack { id: String }, // Track id, or its title
+    PlaySoundboard { id: String },
+    StopSoundboard,
+    // Spoken through the soundboard bus, with ducking
+    Announce {
+        text: String,
+        #[serde(default)]
+        voice: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct RemoteVolumes {
+    pub master: f32,
+    pub music: f32,
+    pub ambient: f32,
+    pub soundboard: f32,
+}
+
+#[derive(Serialize)]
+pub struct RemoteState {
+    pub track: Option<CurrentTrackInfo>,
+    pub progress: MusicProgressResponse,
+    pub volumes: RemoteVolumes,
+    pub active_ambients: Vec<ActiveAmbientInfo>,
+    pub current_preset_id: Option<String>,
+    pub scheduler: SchedulerState,
+}
+
+pub fn get_state(app: &tauri::AppHandle) -> Result<RemoteState, String> {
+    let controller = app.state::<Arc<AudioController>>();
+    let settings = crate::get_settings()?;
+    let active_ambients = controller.active_ambients.lock().values().cloned().collect();
+    Ok(RemoteState {
+        track: controller.get_current_track(),
+        progress: crate::get_music_progress(controller.clone())?,
+        volumes: RemoteVolumes {
+            master: settings.master_volume,
+            music: settings.music_volume,
+            ambient: settings.ambient_volume,
+            soundboard: settings.soundboard_volume,
+        },
+        active_ambients,
+        current_preset_id: controller.current_preset_id.lock().clone(),
+        scheduler: controller.scheduler_state.lock().clone(),
+    })
+}
+
+pub fn list_presets(app: &tauri::AppHandle) -> Result<Vec<PresetInfo>, String> {
+    Ok(crate::list_presets(app.clone())?)
+}
+
+pub fn list_soundboard(_app: &tauri::AppHandle) -> Result<Vec<SoundboardSound>, String> {
+    let settings = crate::get_settings()?;
+    Ok(crate::scan_soundboard_folder(settings.soundboard_folder_path)?.sounds)
+}
+
+pub fn apply_action(app: &tauri::AppHandle, action: RemoteAction) -> Result<(), String> {
+    let controller = app.state::<Arc<AudioController>>();
+    match &action {
+        RemoteAction::Pause => crate::pause_music(controller.clone())?,
+        RemoteAction::Resume => crate::resume_music(controller.clone())?,
+        RemoteAction::TogglePlayback if crate::external_transport(&controller, Transport::Toggle) => {}
+        RemoteAction::TogglePlayback => {
+            if controller.get_progress().is_playing {
+                controller.send(AudioCommand::Pause);
+            } else {
+                controller.send(AudioCommand::Resume);
+            }
+        }
+        RemoteAction::Next => {
+            crate::play_next_track(controller.clone())?;
+        }
+        RemoteAction::Previous => {
+            crate::play_previous_track(controller.clone())?;
+        }
+        RemoteAction::Stop => crate::stop_music(controller.clone(), None)?,
+        RemoteAction::Seek { position } => controller.send(AudioCommand::Seek(position.max(0.0))),
+        RemoteAction::SetVolume { bus, volume } => {
+            let volume = volume.clamp(0.0, 100.0);
+            let (command, key) = match bus.as_str() {
+                "master" => (AudioCommand::SetMasterVolume(volume / 100.0), "master_volume"),
+                "music" => (AudioCommand::SetVolume(volume / 100.0), "music_volume"),
+                "ambient" => (AudioCommand::SetAmbientMasterVolume(volume / 100.0), "ambient_volume"),
+                "soundboard" => (AudioCommand::SetSoundboardVolume(volume / 100.0), "soundboard_volume"),
+                _ => return Err(format!("Unknown bus: {}", bus)),
+            };
+            controller.send(command);
+            crate::save_volume_setting(controller.clone(), key.to_string(), volume)?;
+        }
+        RemoteAction::SetMuted { bus, muted } => {
+            let command = match bus.as_str() {
+                "master" => AudioCommand::SetMasterMuted(*muted),
+                "music" => AudioCommand::SetMuted(*muted),
+                "ambient" => AudioCommand::SetAmbientMuted(*muted),
+                "soundboard" => AudioCommand::SetSoundboardMuted(*muted),
+                _ => return Err(format!("Unknown bus: {}", bus)),
+            };
+            controller.send(command);
+        }
+        RemoteAction::ApplyPreset { id } => {
+            // Scripts and the CLI may pass the preset name instead of its id
+            let preset = list_presets(app)?
+                .into_iter()
+                .find(|p| &p.id == id || p.name.eq_ignore_ascii_case(id))
+                .ok_or_else(|| format!("Preset not found: {}", id))?;
+            *controller.current_preset_id.lock() = Some(preset.id.clone());
+            controller.send(AudioCommand::ApplyPreset(preset.id));
+        }
+        RemoteAction::PlayPlaylist { id } => {
+            crate::set_current_playlist(controller.clone(), Some(id.clone()))?;
+            // Start from the first track (or a random one when shuffled)
+            controller.playlist_state.lock().current_index = -1;
+            if !crate::play_next_track(controller.clone())? {
+                return Err(format!("Playlist is empty or not found: {}", id));
+            }
+        }
+        RemoteAction::PlayTrack { id } => {
+            let track = controller.all_tracks.lock()
+                .iter()
+                .find(|t| &t.id == id || t.title.eq_ignore_ascii_case(id))
+                .cloned()
+                .ok_or_else(|| format!("Track not found: {}", id))?;
+            let file_path = track.file_path();
+            let track_info = CurrentTrackInfo {
+                id: track.id,
+                title: track.title,
+                artist: track.artist,
+                album: track.album,
+                file_path: file_path.clone(),
+            };
+            controller.send(AudioCommand::Play { file_path, track_info });
+        }
+        RemoteAction::PlaySoundboard { id } => {
+            let settings = crate::get_settings()?;
+            let sound = crate::scan_soundboard_folder(settings.soundboard_folder_path.clone())?
+                .sounds
+                .into_iter()
+                .find(|s| &s.id == id || s.name.eq_ignore_ascii_case(id))
+                .ok_or_else(|| format!("Soundboard sound not found: {}", id))?;
+            let file_path = crate::content_path(&settings.soundboard_folder_path, &sound.file);
+            controller.send(AudioCommand::PlaySoundboard {
+                file_path: file_path.to_string_lossy().to_string(),
+                volume: sound.volume.unwrap_or(100) as f32 / 100.0,
+                trim: sound.trim,
+            });
+        }
+        RemoteAction::StopSoundboard => controller.send(AudioCommand::StopSoundboard { fade_secs: None }),
+        RemoteAction::Announce { text, voice } => crate::announce(controller.clone(), text.clone(), voice.clone())?,
+    }
+    let _ = app.emit(REMOTE_ACTION_EVENT, &action);
+    Ok(())
+}
+
+// Running HTTP server (port) and WebSocket server (port + 1); dropping it shuts both down
+pub struct RemoteServer {
+    server: Arc<tiny_http::Server>,
+    stop: Arc<AtomicBool>,
+}
+
+impl RemoteServer {
+    pub fn start(app: tauri::AppHandle, port: u16, token: String, allow_lan: bool) -> Result<Self, String> {
+        let host = if allow_lan { "0.0.0.0" } else { "127.0.0.1" };
+        let ws_port = port.checked_add(1)
+            .ok_or_else(|| "Remote API port must leave room for the WebSocket port".to_string())?;
+        let server = tiny_http::Server::http((host, port))
+            .map_err(|e| format!("Failed to start remote API on port {}: {}", port, e))?;
+        let listener = TcpListener::bind((host, ws_port))
+            .map_err(|e| format!("Failed to start WebSocket API on port {}: {}", ws_port, e))?;
+        listener.set_nonblocking(true)
+            .map_err(|e| format!("Failed to configure WebSocket listener: {}", e))?;
+
+        let server = Arc::new(server);
+        let server_clone = server.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let http_app = app.clone();
+        let http_token = token.clone();
+        thread::spawn(move || {
+            for request in server_clone.incoming_requests() {
+                handle_request(&http_app, &http_token, request);
+            }
+        });
+
+        let stop_clone = stop.clone();
+        thread::spawn(move || {
+            while !stop_clone.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let app = app.clone();
+                        let token = token.clone();
+                        let stop = stop_clone.clone();
+                        thread::spawn(move || handle_ws_client(app, token, stream, stop));
+                    }
+                    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => thread::sleep(WS_POLL_INTERVAL),
+                    Err(e) => {
+                        log::warn!("WebSocket accept failed: {}", e);
+                        thread::sleep(WS_POLL_INTERVAL);
+                    }
+                }
+            }
+        });
+
+        Ok(Self { server, stop })
+    }
+}
+
+impl Drop for RemoteServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// Accepts "Authorization: Bearer <token>" or a ?token= query parameter
+fn is_authorized(request: &tiny_http::Request, token: &str) -> bool {
+    let header_token = request.headers().iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer ").map(|t| t.trim().to_string()));
+    let query_token = request.url().split_once('?')
+        .and_then(|(_, query)| query.split('&').find_map(|pair| pair.strip_prefix("token=")))
+        .map(|t| t.to_string());
+    header_token.or(query_token).map(|t| t == token).unwrap_or(false)
+}
+
+fn json_response<T: Serialize>(status: u16, body: &T) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let data = serde_json::to_vec(body).unwrap_or_else(|_| b"{}".to_vec());
+    tiny_http::Response::from_data(data)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+        .with_header(header("Access-Control-Allow-Origin", "*"))
+}
+
+fn error_response(status: u16, message: &str) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    json_response(status, &serde_json::json!({ "error": message }))
+}
+
+fn header(name: &str, value: &str) -> tiny_http::Header {
+    tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("valid header")
+}
+
+fn read_json_body<T: serde::de::DeserializeOwned>(request: &mut tiny_http::Request) -> Result<T, String> {
+    let mut body = String::new();
+    request.as_reader().take(MAX_BODY_BYTES).read_to_string(&mut body)
+        .map_err(|e| format!("Failed to read request body: {}", e))?;
+    serde_json::from_str(&body).map_err(|e| format!("Invalid request body: {}", e))
+}
+
+#[derive(Deserialize)]
+struct VolumeBody {
+    bus: String,
+    volume: f32,
+}
+
+fn handle_request(app: &tauri::AppHandle, token: &str, mut request: tiny_http::Request) {
+    if *request.method() == tiny_http::Method::Options {
+        let response = tiny_http::Response::empty(tiny_http::StatusCode(204))
+            .with_header(header("Access-Control-Allow-Origin", "*"))
+            .with_header(header("Access-Control-Allow-Methods", "GET, POST, OPTIONS"))
+            .with_header(header("Access-Control-Allow-Headers", "Authorization, Content-Type"))
+            // Chrome asks before letting a hosted page (e.g. a Foundry server) call a local address
+            .with_header(header("Access-Control-Allow-Private-Network", "true"));
+        let _ = request.respond(response);
+        return;
+    }
+
+    // The page itself holds no data, so it is served without a token
+    let is_page = matches!(request.url().split('?').next().unwrap_or(""), "/" | "/remote" | "/remote/");
+    if is_page && *request.method() == tiny_http::Method::Get {
+        let response = tiny_http::Response::from_string(REMOTE_PAGE)
+            .with_header(header("Content-Type", "text/html; charset=utf-8"));
+        let _ = request.respond(response);
+        return;
+    }
+
+    if !is_authorized(&request, token) {
+        let _ = request.respond(error_response(401, "Invalid or missing token"));
+        return;
+    }
+
+    let path = request.url().split('?').next().unwrap_or("").trim_end_matches('/').to_string();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let is_post = *request.method() == tiny_http::Method::Post;
+
+    let result: Result<serde_json::Value, (u16, String)> = match (is_post, segments.as_slice()) {
+        (false, ["api", "state"]) => get_state(app).map(|s| serde_json::json!(s)).map_err(|e| (500, e)),
+        (false, ["api", "presets"]) => list_presets(app).map(|p| serde_json::json!(p)).map_err(|e| (500, e)),
+        (false, ["api", "soundboard"]) => list_soundboard(app).map(|s| serde_json::json!(s)).map_err(|e| (500, e)),
+        (true, ["api", "action"]) => read_json_body::<RemoteAction>(&mut request)
+            .map_err(|e| (400, e))
+            .and_then(|action| run_action(app, action)),
+        (true, ["api", "pause"]) => run_action(app, RemoteAction::Pause),
+        (true, ["api", "resume"]) => run_action(app, RemoteAction::Resume),
+        (true, ["api", "toggle"]) => run_action(app, RemoteAction::TogglePlayback),
+        (true, ["api", "next"]) => run_action(app, RemoteAction::Next),
+        (true, ["api", "previous"]) => run_action(app, RemoteAction::Previous),
+        (true, ["api", "stop"]) => run_action(app, RemoteAction::Stop),
+        (true, ["api", "volume"]) => read_json_body::<VolumeBody>(&mut request)
+            .map_err(|e| (400, e))
+            .and_then(|body| run_action(app, RemoteAction::SetVolume { bus: body.bus, volume: body.volume })),
+        (true, ["api", "presets", id, "apply"]) => run_action(app, RemoteAction::ApplyPreset { id: id.to_string() }),
+        (true, ["api", "soundboard", "stop"]) => run_action(app, RemoteAction::StopSoundboard),
+        (true, ["api", "soundboard", id, "play"]) => run_action(app, RemoteAction::PlaySoundboard { id: id.to_string() }),
+        (false, ["api", "vtt"]) => vtt::manifest(app).map(|m| serde_json::json!(m)).map_err(|e| (500, e)),
+        (true, ["api", "vtt", "scene"]) => read_json_body::<vtt::SceneActivation>(&mut request)
+            .map_err(|e| (400, e))
+            .and_then(|activation| vtt::activate_scene(app, activation).map(|r| serde_json::json!(r)).map_err(|e| (400, e))),
+        _ => Err((404, format!("Not found: {}", path))),
+    };
+
+    let response = match result {
+        Ok(body) => json_response(200, &body),
+        Err((status, message)) => error_response(status, &message),
+    };
+    let _ = request.respond(response);
+}
+
+fn run_action(app: &tauri::AppHandle, action: RemoteAction) -> Result<serde_json::Value, (u16, String)> {
+    apply_action(app, action)
+        .map(|_| serde_json::json!({ "ok": true }))
+        .map_err(|e| (400, e))
+}
+
+// Messages pushed to WebSocket clients
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WsEvent<'a> {
+    State(&'a RemoteState),
+    TrackChanged { track: Option<CurrentTrackInfo> },
+    TrackStarted { track: CurrentTrackInfo },
+    TrackEnded { track: CurrentTrackInfo },
+    SchedulerAdvanced { scheduler: SchedulerState },
+    Levels { meters: MeterReadings, ambient_levels: std::collections::HashMap<String, f32> },
+    Result { ok: bool, error: Option<String> },
+}
+
+// Cheap summary of the state used to decide when to push a full state message
+#[derive(PartialEq)]
+struct StateSignature {
+    track_id: Option<String>,
+    is_playing: bool,
+    is_finished: bool,
+    scheduler_playing: bool,
+    scheduler_index: usize,
+    preset_id: Option<String>,
+    ambient_ids: Vec<String>,
+}
+
+fn state_signature(controller: &AudioController) -> StateSignature {
+    let progress = controller.get_progress();
+    let scheduler = controller.scheduler_state.lock().clone();
+    let mut ambient_ids: Vec<String> = controller.active_ambients.lock().keys().cloned().collect();
+    ambient_ids.sort();
+    StateSignature {
+        track_id: controller.get_current_track().map(|t| t.id),
+        is_playing: progress.is_playing,
+        is_finished: progress.is_finished,
+        scheduler_playing: scheduler.is_playing,
+        scheduler_index: scheduler.current_item_index,
+        preset_id: controller.current_preset_id.lock().clone(),
+        ambient_ids,
+    }
+}
+
+fn ws_send(ws: &mut WebSocket<TcpStream>, event: &WsEvent) -> Result<(), tungstenite::Error> {
+    let text = serde_json::to_string(event).unwrap_or_default();
+    ws.send(Message::Text(text))
+}
+
+// Browsers can't set headers on WebSocket requests, so the token comes from ?token=
+fn handle_ws_client(app: tauri::AppHandle, token: String, stream: TcpStream, stop: Arc<AtomicBool>) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(WS_POLL_INTERVAL));
+
+    let authorize = |request: &tungstenite::handshake::server::Request, response: tungstenite::handshake::server::Response| {
+        let authorized = request.uri().query()
+            .map(|q| q.split('&').any(|pair| pair.strip_prefix("token=") == Some(token.as_str())))
+            .unwrap_or(false);
+        if authorized {
+            Ok(response)
+        } else {
+            Err(tungstenite::http::Response::builder()
+                .status(401)
+                .body(Some("Invalid or missing token".to_string()))
+                .expect("valid response"))
+        }
+    };
+    let mut ws = match tungstenite::accept_hdr(stream, authorize) {
+        Ok(ws) => ws,
+        Err(_) => return,
+    };
+
+    let controller = app.state::<Arc<AudioController>>();
+    let mut last_signature: Option<StateSignature> = None;
+    let mut last_levels = Instant::now();
+    // Track transitions are passed on as the audio thread sends them, not on the next state comparison
+    let (track_tx, track_rx) = std::sync::mpsc::channel();
+    let track_listeners = [crate::TRACK_STARTED_EVENT, crate::TRACK_ENDED_EVENT].map(|name| {
+        let track_tx = track_tx.clone();
+        app.listen_any(name, move |event| {
+            let _ = track_tx.send((name, event.payload().to_string()));
+        })
+    });
+
+    while !stop.load(Ordering::Relaxed) {
+        // Handle incoming control messages (the read times out after WS_POLL_INTERVAL)
+        match ws.read() {
+            Ok(Message::Text(text)) => {
+                let result = serde_json::from_str::<RemoteAction>(&text)
+                    .map_err(|e| format!("Invalid action: {}", e))
+                    .and_then(|action| apply_action(&app, action));
+                let event = WsEvent::Result { ok: result.is_ok(), error: result.err() };
+                if ws_send(&mut ws, &event).is_err() {
+                    break;
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(ref e))
+                if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(_) => break,
+        }
+
+        let mut transitions = track_rx.try_iter().filter_map(|(name, payload)| {
+            let track = serde_json::from_str::<CurrentTrackInfo>(&payload).ok()?;
+            Some(if name == crate::TRACK_STARTED_EVENT { WsEvent::TrackStarted { track } } else { WsEvent::TrackEnded { track } })
+        });
+        if transitions.any(|event| ws_send(&mut ws, &event).is_err()) {
+            break;
+        }
+
+        // Push state changes
+        let signature = state_signature(&controller);
+        if last_signature.as_ref() != Some(&signature) {
+            let state = match get_state(&app) {
+                Ok(state) => state,
+                Err(_) => continue,
+            };
+            let mut events = Vec::new();
+            if let Some(previous) = &last_signature {
+                if previous.track_id != signature.track_id {
+                    events.push(WsEvent::TrackChanged { track: controller.get_current_track() });
+                }
+                if signature.scheduler_playing && previous.scheduler_index != signature.scheduler_index {
+                    events.push(WsEvent::SchedulerAdvanced { scheduler: controller.scheduler_state.lock().clone() });
+                }
+            }
+            last_signature = Some(signature);
+            events.push(WsEvent::State(&state));
+            if events.iter().any(|event| ws_send(&mut ws, event).is_err()) {
+                break;
+            }
+        }
+
+        if last_levels.elapsed() >= WS_LEVELS_INTERVAL {
+            last_levels = Instant::now();
+            let event = WsEvent::Levels {
+                meters: controller.get_meters(),
+                ambient_levels: controller.ambient_levels.lock().clone(),
+            };
+            if ws_send(&mut ws, &event).is_err() {
+                break;
+            }
+        }
+    }
+    for id in track_listeners {
+        app.unlisten(id);
+    }
+    let _ = ws.close(None);
+}
@@ -0,0 +1,514 @@
+// Offline rendering - runs presets through the same ambient chain as live playback (A/B cycles,
+// pauses, pitch, pan, low-pass, reverb, volume variation) without an output device, so hours of
+// soundscape can be written to a file in minutes
+use rand::Rng;
+use rodio::source::UniformSourceIterator;
+use rodio::{Decoder, Source};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::Emitter;
+
+use crate::audio_cache::AudioBytes;
+use crate::encoder::{AudioFileWriter, Tags};
+use crate::{content_path, ActiveAmbientInfo, AmbientSettings, PresetSound, SchedulePreset, SoundscapePreset};
+use soundscapes_core::effects::EffectParams;
+
+// Rendered in the mix tap's format so recordings and renders share the same file writers
+const SAMPLE_RATE: u32 = crate::streaming::TAP_RATE;
+const CHANNELS: u16 = crate::streaming::TAP_CHANNELS as u16;
+// Frames rendered between progress/cancel checks (100ms)
+const BLOCK_FRAMES: usize = 4410;
+// Same length as the live scheduler's crossfade between presets
+const SCHEDULE_FADE_SECS: f32 = 2.0;
+// Matches the live engine's estimate of one pause loop
+const PAUSE_SECS_PER_LOOP: f64 = 5.0;
+// Tauri keeps app data in <data dir>/<identifier from tauri.conf.json>; the headless render reads
+// presets from there without starting the app
+const APP_IDENTIFIER: &str = "com.soundscapes.app";
+const CLI_USAGE: &str = "Usage: soundscapes --render <preset id or name> <minutes> <output file>
+
+Renders a preset to a WAV, MP3, Ogg or Opus file (chosen by extension) without opening a window.";
+
+#[derive(Clone, Serialize)]
+pub struct RenderProgress {
+    pub rendered_secs: f64,
+    pub total_secs: f64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RenderFinished {
+    pub output_path: String,
+    pub duration_secs: f64,
+    pub error: Option<String>,
+    pub cancelled: bool,
+}
+
+// Convert a saved preset sound into live settings plus full A/B file paths
+pub(crate) fn preset_sound_settings(sound: &PresetSound) -> (AmbientSettings, String, String) {
+    let settings = AmbientSettings {
+        volume: sound.volume as f32 / 100.0,
+        pitch: sound.pitch,
+        pan: sound.pan as f32 / 100.0,
+        low_pass_freq: sound.low_pass_freq as f32,
+        reverb_type: "off".to_string(),
+        algorithmic_reverb: sound.algorithmic_reverb as f32 / 100.0,
+        repeat_min: sound.repeat_range_min,
+        repeat_max: sound.repeat_range_max,
+        pause_min: sound.pause_range_min,
+        pause_max: sound.pause_range_max,
+        volume_variation: sound.volume_variation as f32 / 100.0,
+        event_probability: sound.event_probability as f32 / 100.0,
+        sync_group: sound.sync_group.clone(),
+        random_start: sound.random_start,
+        duck_music: sound.duck_music,
+    };
+    let join = |file: &str| if file.is_empty() {
+        String::new()
+    } else {
+        content_path(&sound.category_path, file).to_string_lossy().to_string()
+    };
+    (settings, join(&sound.files_a), join(&sound.files_b))
+}
+
+// File bytes shared by every voice, like the live engine's audio cache (large files are mapped)
+#[derive(Default)]
+pub struct FileCache {
+    files: HashMap<String, AudioBytes>,
+}
+
+impl FileCache {
+    fn get(&mut self, path: &str) -> Option<AudioBytes> {
+        if path.is_empty() {
+            return None;
+        }
+        if let Some(bytes) = self.files.get(path) {
+            return Some(bytes.clone());
+        }
+        let threshold = crate::default_mmap_threshold_mb() as u64 * 1024 * 1024;
+        let bytes = AudioBytes::read(path, threshold).ok()?;
+        self.files.insert(path.to_string(), bytes.clone());
+        Some(bytes)
+    }
+}
+
+type VoiceSource = Box<dyn Iterator<Item = f32> + Send>;
+
+// One ambient sound, following the live A/B state machine sample by sample
+pub struct AmbientVoice {
+    file_a: String,
+    file_b: String,
+    settings: AmbientSettings,
+    effects: EffectParams,
+    source: Option<VoiceSource>,
+    is_playing_a: bool,
+    loops_remaining: u32,
+    pause_frames: u64,
+    // Volume of the current file including variation
+    gain: f32,
+    fade: f32,
+    fade_target: f32,
+    fade_step: f32,
+    // Set when neither file can be decoded, so the voice doesn't spin
+    dead: bool,
+}
+
+impl AmbientVoice {
+    pub(crate) fn new(file_a: String, file_b: String, settings: AmbientSettings, cache: &mut FileCache) -> Self {
+        let mut voice = Self {
+            file_a,
+            file_b,
+            effects: EffectParams::new(settings.pan, settings.low_pass_freq, settings.algorithmic_reverb),
+            settings,
+            source: None,
+            is_playing_a: true,
+            loops_remaining: 0,
+            pause_frames: 0,
+            gain: 0.0,
+            fade: 1.0,
+            fade_target: 1.0,
+            fade_step: 0.0,
+            dead: false,
+        };
+        voice.start_cycle(cache);
+        voice
+    }
+
+    fn fade_to(&mut self, target: f32, secs: f32) {
+        self.fade_target = target;
+        self.fade_step = 1.0 / (secs * SAMPLE_RATE as f32).max(1.0);
+    }
+
+    fn fade_in(&mut self, secs: f32) {
+        self.fade = 0.0;
+        self.fade_to(1.0, secs);
+    }
+
+    fn faded_out(&self) -> bool {
+        self.fade_target == 0.0 && self.fade <= 0.0
+    }
+
+    fn start_cycle(&mut self, cache: &mut FileCache) {
+        let mut rng = rand::thread_rng();
+        self.loops_remaining = rng.gen_range(self.settings.repeat_min..=self.settings.repeat_max.max(self.settings.repeat_min));
+        self.is_playing_a = true;
+        self.start_file(cache);
+    }
+
+    // A cycle after a pause plays with the sound's event probability, otherwise it rests one more
+    // loop; returns whether it started
+    fn next_cycle(&mut self, cache: &mut FileCache) -> bool {
+        if rand::random::<f32>() >= self.settings.event_probability {
+            self.pause_frames = (PAUSE_SECS_PER_LOOP * SAMPLE_RATE as f64) as u64;
+            return false;
+        }
+        self.start_cycle(cache);
+        true
+    }
+
+    fn start_file(&mut self, cache: &mut FileCache) {
+        let path = if self.is_playing_a { &self.file_a } else { &self.file_b };
+        self.source = cache.get(path).and_then(|bytes| self.build_source(bytes));
+        let variation = if self.settings.volume_variation > 0.0 {
+            let var = (rand::random::<f32>() - 0.5) * 2.0 * self.settings.volume_variation;
+            (1.0 + var).clamp(0.0, 2.0)
+        } else {
+            1.0
+        };
+        self.gain = self.settings.volume * variation;
+    }
+
+    fn build_source(&self, bytes: AudioBytes) -> Option<VoiceSource> {
+        let source = Decoder::new(Cursor::new(bytes)).ok()?;
+        let sample_rate = source.sample_rate();
+        let source = source.speed(self.settings.pitch).convert_samples::<f32>();
+        let source = self.effects.apply(source, sample_rate);
+        let source: UniformSourceIterator<_, f32> = UniformSourceIterator::new(source, CHANNELS, SAMPLE_RATE);
+        Some(Box::new(source))
+    }
+
+    // Called when the current file has ended - mirrors the live engine's empty-sink handling
+    fn advance(&mut self, cache: &mut FileCache) {
+        let mut failed_starts = 0;
+        loop {
+            if self.is_playing_a {
+                self.is_playing_a = false;
+                self.start_file(cache);
+            } else {
+                self.loops_remaining = self.loops_remaining.saturating_sub(1);
+                if self.loops_remaining == 0 {
+                    let max = self.settings.pause_max.max(self.settings.pause_min);
+                    let pause_loops = rand::thread_rng().gen_range(self.settings.pause_min..=max);
+                    if pause_loops > 0 {
+                        self.pause_frames = (pause_loops as f64 * PAUSE_SECS_PER_LOOP * SAMPLE_RATE as f64) as u64;
+                        return;
+                    }
+                    if !self.next_cycle(cache) {
+                        return;
+                    }
+                } else {
+                    self.is_playing_a = true;
+                    self.start_file(cache);
+                }
+            }
+            if self.source.is_some() {
+                return;
+            }
+            failed_starts += 1;
+            if failed_starts >= 2 {
+                self.dead = true;
+                return;
+            }
+        }
+    }
+
+    // Add this voice into an interleaved stereo block
+    pub fn mix_into(&mut self, block: &mut [f32], cache: &mut FileCache) {
+        for frame in block.chunks_mut(CHANNELS as usize) {
+            if self.dead {
+                return;
+            }
+            if self.pause_frames > 0 {
+                self.pause_frames -= 1;
+                if self.pause_frames == 0 {
+                    self.next_cycle(cache);
+                }
+                self.step_fade();
+                continue;
+            }
+            let mut samples = [0.0f32; 2];
+            let mut ended = false;
+            match self.source.as_mut() {
+                Some(source) => {
+                    for sample in samples.iter_mut() {
+                        match source.next() {
+                            Some(s) => *sample = s,
+                            None => {
+                                ended = true;
+                                break;
+                            }
+                        }
+                    }
+                }
+                None => ended = true,
+            }
+            if ended {
+                self.source = None;
+                self.advance(cache);
+                continue;
+            }
+            let gain = self.gain * self.fade;
+            frame[0] += samples[0] * gain;
+            frame[1] += samples[1] * gain;
+            self.step_fade();
+        }
+    }
+
+    fn step_fade(&mut self) {
+        if self.fade < self.fade_target {
+            self.fade = (self.fade + self.fade_step).min(self.fade_target);
+        } else if self.fade > self.fade_target {
+            self.fade = (self.fade - self.fade_step).max(self.fade_target);
+        }
+    }
+}
+
+// Renders mixed blocks to a writer, reporting progress and checking for cancellation
+struct Renderer<'a> {
+    progress: &'a dyn Fn(RenderProgress),
+    writer: AudioFileWriter,
+    cancel: &'a AtomicBool,
+    rendered_frames: u64,
+    total_secs: f64,
+}
+
+impl Renderer<'_> {
+    fn run(&mut self, voices: &mut HashMap<String, AmbientVoice>, cache: &mut FileCache, frames: u64) -> Result<(), String> {
+        let mut block = vec![0.0f32; BLOCK_FRAMES * CHANNELS as usize];
+        let mut remaining = frames;
+        while remaining > 0 {
+            if self.cancel.load(Ordering::Relaxed) {
+                return Err("Render cancelled".to_string());
+            }
+            let count = remaining.min(BLOCK_FRAMES as u64) as usize;
+            let block = &mut block[..count * CHANNELS as usize];
+            block.fill(0.0);
+            for voice in voices.values_mut() {
+                voice.mix_into(block, cache);
+            }
+            voices.retain(|_, v| !v.faded_out());
+            self.writer.write(block)?;
+            remaining -= count as u64;
+            self.rendered_frames += count as u64;
+            // Report every 10 seconds of rendered audio
+            if self.rendered_frames % (SAMPLE_RATE as u64 * 10) < count as u64 {
+                (self.progress)(RenderProgress {
+                    rendered_secs: self.rendered_frames as f64 / SAMPLE_RATE as f64,
+                    total_secs: self.total_secs,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+// Bring the voice set in line with a preset the way the live scheduler does: removed sounds fade out,
+// new sounds fade in, sounds with a changed pitch restart, and the rest take the new volume and effects
+fn apply_preset(voices: &mut HashMap<String, AmbientVoice>, preset: &SoundscapePreset, cache: &mut FileCache, fade_secs: f32) {
+    let enabled: Vec<&PresetSound> = preset.sounds.iter().filter(|s| s.enabled).collect();
+    for (id, voice) in voices.iter_mut() {
+        if !enabled.iter().any(|s| &s.sound_id == id) {
+            voice.fade_to(0.0, fade_secs);
+        }
+    }
+    for sound in enabled {
+        let (settings, file_a, file_b) = preset_sound_settings(sound);
+        if let Some(voice) = voices.get_mut(&sound.sound_id) {
+            let pitch_changed = (voice.settings.pitch - settings.pitch).abs() > 0.001;
+            if !pitch_changed && voice.fade_target > 0.0 {
+                voice.effects.set(settings.pan, settings.low_pass_freq, settings.algorithmic_reverb);
+                voice.settings.volume = settings.volume;
+                voice.settings.volume_variation = settings.volume_variation;
+                voice.settings.pan = settings.pan;
+                voice.settings.low_pass_freq = settings.low_pass_freq;
+                voice.settings.algorithmic_reverb = settings.algorithmic_reverb;
+                continue;
+            }
+        }
+        let mut voice = AmbientVoice::new(file_a, file_b, settings, cache);
+        voice.fade_in(fade_secs);
+        voices.insert(sound.sound_id.clone(), voice);
+    }
+}
+
+// The format follows the file extension (WAV unless .mp3, .ogg or .opus); bitrate and normalization
+// come from the recording settings
+fn create_writer(output_path: &str, tags: &Tags) -> Result<AudioFileWriter, String> {
+    crate::get_settings().map(|s| s.recording).unwrap_or_default().create_export_writer(output_path, tags)
+}
+
+// Progress as render://progress events for the UI
+fn emit_progress(app: &tauri::AppHandle) -> impl Fn(RenderProgress) + '_ {
+    move |progress| {
+        let _ = app.emit("render://progress", progress);
+    }
+}
+
+// Render every item of a schedule once, in order, each for a random length within its range
+pub fn render_schedule(
+    app: &tauri::AppHandle,
+    schedule: &SchedulePreset,
+    output_path: &str,
+    cancel: &AtomicBool,
+) -> Result<f64, String> {
+    let mut items = schedule.items.clone();
+    items.sort_by_key(|item| item.order);
+    if items.is_empty() {
+        return Err("Schedule has no items".to_string());
+    }
+
+    // Load every preset and pick durations up front so progress has a known total
+    let mut rng = rand::thread_rng();
+    let mut steps = Vec::new();
+    for item in &items {
+        let preset = crate::load_preset(app.clone(), item.preset_id.clone())?;
+        let minutes = rng.gen_range(item.min_minutes..=item.max_minutes.max(item.min_minutes)).max(1);
+        steps.push((preset, minutes as u64 * 60));
+    }
+    let total_secs = steps.iter().map(|(_, secs)| *secs as f64).sum();
+
+    let mut renderer = Renderer {
+        progress: &emit_progress(app),
+        writer: create_writer(output_path, &Tags::new(schedule.name.clone(), Some(schedule.name.clone())))?,
+        cancel,
+        rendered_frames: 0,
+        total_secs,
+    };
+    let mut cache = FileCache::default();
+    let mut voices: HashMap<String, AmbientVoice> = HashMap::new();
+    for (preset, secs) in &steps {
+        // The first preset fades in from silence, later ones crossfade from the previous item
+        apply_preset(&mut voices, preset, &mut cache, SCHEDULE_FADE_SECS);
+        renderer.run(&mut voices, &mut cache, secs * SAMPLE_RATE as u64)?;
+    }
+    renderer.writer.finish()?;
+    Ok(total_secs)
+}
+
+// Render one ambient sound with its effects, A/B cycle and variation to a standalone file
+pub(crate) fn render_sound(
+    app: &tauri::AppHandle,
+    sound: ActiveAmbientInfo,
+    minutes: f64,
+    output_path: &str,
+    tags: &Tags,
+    cancel: &AtomicBool,
+) -> Result<f64, String> {
+    if !(minutes > 0.0) {
+        return Err("Length must be more than 0 minutes".to_string());
+    }
+    let total_secs = minutes * 60.0;
+    let mut cache = FileCache::default();
+    let decodable = [&sound.file_a, &sound.file_b].iter().any(|path| {
+        cache.get(path).map(|bytes| Decoder::new(Cursor::new(bytes)).is_ok()).unwrap_or(false)
+    });
+    if !decodable {
+        return Err("Could not decode the sound's audio files".to_string());
+    }
+    let mut renderer = Renderer {
+        progress: &emit_progress(app),
+        writer: create_writer(output_path, tags)?,
+        cancel,
+        rendered_frames: 0,
+        total_secs,
+    };
+    let voice = AmbientVoice::new(sound.file_a, sound.file_b, sound.settings, &mut cache);
+    let mut voices = HashMap::from([(String::new(), voice)]);
+    renderer.run(&mut voices, &mut cache, (total_secs * SAMPLE_RATE as f64) as u64)?;
+    renderer.writer.finish()?;
+    Ok(total_secs)
+}
+
+// Render a single preset for a fixed length, fading in like a preset applied live
+pub fn render_preset(
+    preset: &SoundscapePreset,
+    minutes: f64,
+    output_path: &str,
+    cancel: &AtomicBool,
+    progress: &dyn Fn(RenderProgress),
+) -> Result<f64, String> {
+    if !(minutes > 0.0) {
+        return Err("Length must be more than 0 minutes".to_string());
+    }
+    if !preset.sounds.iter().any(|s| s.enabled) {
+        return Err(format!("Preset '{}' has no enabled sounds", preset.name));
+    }
+    let total_secs = minutes * 60.0;
+    let mut renderer = Renderer {
+        progress,
+        writer: create_writer(output_path, &Tags::new(preset.name.clone(), Some(preset.name.clone())))?,
+        cancel,
+        rendered_frames: 0,
+        total_secs,
+    };
+    let mut cache = FileCache::default();
+    let mut voices = HashMap::new();
+    apply_preset(&mut voices, preset, &mut cache, SCHEDULE_FADE_SECS);
+    renderer.run(&mut voices, &mut cache, (total_secs * SAMPLE_RATE as f64) as u64)?;
+    renderer.writer.finish()?;
+    Ok(total_secs)
+}
+
+// `soundscapes --render <preset> <minutes> <output>`; returns the process exit code
+pub fn run_cli(args: &[String]) -> i32 {
+    let [preset, minutes, output_path] = args else {
+        eprintln!("{}", CLI_USAGE);
+        return 2;
+    };
+    let minutes = match minutes.parse::<f64>() {
+        Ok(minutes) if minutes > 0.0 => minutes,
+        _ => {
+            eprintln!("Minutes must be a number greater than 0\n\n{}", CLI_USAGE);
+            return 2;
+        }
+    };
+    let preset = match find_preset(preset) {
+        Ok(preset) => preset,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return 1;
+        }
+    };
+
+    let cancel = AtomicBool::new(false);
+    let progress = |p: RenderProgress| eprint!("\rRendered {:.0}/{:.0}s", p.rendered_secs, p.total_secs);
+    let result = render_preset(&preset, minutes, output_path, &cancel, &progress);
+    eprintln!();
+    match result {
+        Ok(secs) => {
+            println!("Rendered {:.0}s of '{}' to {}", secs, preset.name, output_path);
+            0
+        }
+        Err(e) => {
+            // Don't leave a truncated file behind
+            let _ = std::fs::remove_file(output_path);
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
+
+// Look a preset up by id or name in the app's presets folder
+fn find_preset(id_or_name: &str) -> Result<SoundscapePreset, String> {
+    let dir = dirs::data_dir().ok_or("Could not find the app data folder")?.join(APP_IDENTIFIER).join("presets");
+    let entries = std::fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map(|e| e == "soundscape").unwrap_or(false))
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|content| serde_json::from_str::<SoundscapePreset>(&content).ok())
+        .find(|preset| preset.id == id_or_name || preset.name.eq_ignore_ascii_case(id_or_name))
+        .ok_or_else(|| format!("Preset not found: {}", id_or_name))
+}
@@ -0,0 +1,122 @@
+// Replay buffer - keeps the last few minutes of the mix in memory so a good moment can be saved
+// after it happened, like a game capture replay buffer
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use crate::encoder::Tags;
+use crate::recording::RecordingConfig;
+use crate::streaming::{MixTap, MIX_INTERVAL, TAP_CHANNELS, TAP_RATE};
+
+// Samples are kept as 16-bit, about 10 MB per minute
+pub const MAX_MINUTES: u32 = 60;
+const SAMPLES_PER_MINUTE: usize = TAP_RATE as usize * TAP_CHANNELS * 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ReplayConfig {
+    pub enabled: bool,
+    pub minutes: u32, // how much to keep, 1 - MAX_MINUTES
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self { enabled: false, minutes: 10 }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct ReplaySaved {
+    pub path: String,
+    pub duration_secs: f64,
+    pub error: Option<String>,
+}
+
+// Running buffer; dropping it stops capturing and frees the memory
+pub struct ReplayBuffer {
+    samples: Arc<Mutex<VecDeque<i16>>>,
+    capacity: Arc<AtomicUsize>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ReplayBuffer {
+    pub fn start(minutes: u32, tap: Arc<MixTap>) -> Self {
+        let samples = Arc::new(Mutex::new(VecDeque::new()));
+        let capacity = Arc::new(AtomicUsize::new(capacity_for(minutes)));
+        let stop = Arc::new(AtomicBool::new(false));
+        let samples_clone = samples.clone();
+        let capacity_clone = capacity.clone();
+        let stop_clone = stop.clone();
+        let subscription = tap.subscribe();
+
+        thread::spawn(move || {
+            while !stop_clone.load(Ordering::Relaxed) {
+                thread::sleep(MIX_INTERVAL);
+                let blocks = subscription.take();
+                let mut samples = samples_clone.lock();
+                for block in blocks {
+                    samples.extend(block.mix().into_iter().map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16));
+                }
+                let excess = samples.len().saturating_sub(capacity_clone.load(Ordering::Relaxed));
+                samples.drain(..excess);
+            }
+        });
+
+        Self { samples, capacity, stop }
+    }
+
+    // Changing the length keeps what's already buffered (trimmed if it's now too long)
+    pub fn set_minutes(&self, minutes: u32) {
+        self.capacity.store(capacity_for(minutes), Ordering::Relaxed);
+    }
+
+    pub fn buffered_secs(&self) -> f64 {
+        self.samples.lock().len() as f64 / (TAP_RATE as usize * TAP_CHANNELS) as f64
+    }
+
+    // Write the last `minutes` (or everything buffered, if less) to `path`; the format follows the
+    // extension. Runs on a background thread; `done` gets the saved length in seconds.
+    pub fn save_last<F>(&self, minutes: f64, path: String, config: RecordingConfig, tags: Tags, done: F) -> Result<(), String>
+    where
+        F: FnOnce(Result<f64, String>) + Send + 'static,
+    {
+        if !(minutes > 0.0) {
+            return Err("Length must be more than 0 minutes".to_string());
+        }
+        // Copy out under the lock, then encode without holding it
+        let snapshot: Vec<i16> = {
+            let samples = self.samples.lock();
+            let wanted = ((minutes * SAMPLES_PER_MINUTE as f64) as usize / TAP_CHANNELS) * TAP_CHANNELS;
+            samples.range(samples.len().saturating_sub(wanted)..).copied().collect()
+        };
+        if snapshot.is_empty() {
+            return Err("Nothing has been buffered yet".to_string());
+        }
+
+        thread::spawn(move || {
+            let result = config.create_export_writer(&path, &tags).and_then(|mut writer| {
+                for chunk in snapshot.chunks(TAP_RATE as usize * TAP_CHANNELS) {
+                    let block: Vec<f32> = chunk.iter().map(|s| *s as f32 / i16::MAX as f32).collect();
+                    writer.write(&block)?;
+                }
+                writer.finish()?;
+                Ok(snapshot.len() as f64 / (TAP_RATE as usize * TAP_CHANNELS) as f64)
+            });
+            done(result);
+        });
+        Ok(())
+    }
+}
+
+impl Drop for ReplayBuffer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn capacity_for(minutes: u32) -> usize {
+    minutes.clamp(1, MAX_MINUTES) as usize * SAMPLES_PER_MINUTE
+}
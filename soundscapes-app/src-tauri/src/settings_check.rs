@@ -0,0 +1,166 @@
+// Settings validation - settings.json can be edited by hand, and a value the UI would never send
+// (a negative crossfade, a volume of 5000, an unknown log level) used to reach the audio thread as
+// is. Settings are checked against the catalogue below whenever they're loaded or saved: numbers
+// are clamped into range, unknown choices go back to their default, and missing folders are
+// reported but left alone since they may be on a drive that isn't mounted yet.
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::AppSettings;
+use soundscapes_core::spectrum::FrequencyScale;
+
+struct NumberOption {
+    key: &'static str,
+    min: f64,
+    max: f64,
+    integer: bool,
+}
+
+const fn number(key: &'static str, min: f64, max: f64) -> NumberOption {
+    NumberOption { key, min, max, integer: false }
+}
+
+const fn integer(key: &'static str, min: f64, max: f64) -> NumberOption {
+    NumberOption { key, min, max, integer: true }
+}
+
+// Ranges match the settings UI; volumes are stored 0-100
+const NUMBER_OPTIONS: &[NumberOption] = &[
+    number("master_volume", 0.0, 100.0),
+    number("music_volume", 0.0, 100.0),
+    number("ambient_volume", 0.0, 100.0),
+    number("soundboard_volume", 0.0, 100.0),
+    number("music_crossfade_duration", 0.0, 10.0),
+    number("music_skip_crossfade_duration", 0.0, 2.0),
+    number("ambient_preset_crossfade_duration", 0.2, 10.0),
+    number("soundboard_duck_amount", 0.0, 1.0),
+    number("visualization_attack", 0.01, 1.0),
+    number("visualization_decay", 0.01, 1.0),
+    integer("visualization_event_rate", 0.0, crate::MAX_FFT_EVENT_RATE as f64),
+    integer("audio_tick_ms", crate::MIN_AUDIO_TICK_MS as f64, crate::MAX_AUDIO_TICK_MS as f64),
+    integer("ambient_mmap_threshold_mb", 0.0, 4096.0),
+    integer("remote_api_port", 1024.0, 65535.0),
+    integer("osc_port", 1024.0, 65535.0),
+    integer("osc_feedback_port", 1.0, 65535.0),
+    integer("streamdeck_port", 1024.0, 65535.0),
+];
+
+const FOLDER_OPTIONS: &[&str] = &["music_folder_path", "ambient_folder_path", "soundboard_folder_path", "presets_folder_path"];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SettingIssue {
+    pub key: String,
+    pub message: String,
+    // The value was replaced; otherwise it is only a warning
+    pub fixed: bool,
+}
+
+// validate_settings result
+#[derive(Debug, Serialize)]
+pub struct SettingsValidation {
+    pub settings: AppSettings,
+    pub issues: Vec<SettingIssue>,
+}
+
+// Clamp a value for one of the catalogue's numeric settings, for commands that set them directly
+pub fn clamp(key: &str, value: f64) -> f64 {
+    match NUMBER_OPTIONS.iter().find(|o| o.key == key) {
+        Some(option) => value.clamp(option.min, option.max),
+        None => value,
+    }
+}
+
+// Fix up settings.json content before it is deserialized, so an out-of-range port doesn't make the
+// whole file unreadable
+pub fn parse(content: &str) -> Result<(AppSettings, Vec<SettingIssue>), String> {
+    let mut value: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let mut issues = fix_value(&mut value);
+    let settings: AppSettings = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    issues.extend(check_folders(&settings));
+    Ok((settings, issues))
+}
+
+pub fn validate(settings: AppSettings) -> SettingsValidation {
+    let Ok(mut value) = serde_json::to_value(&settings) else {
+        return SettingsValidation { settings, issues: Vec::new() };
+    };
+    let mut issues = fix_value(&mut value);
+    let settings = serde_json::from_value(value).unwrap_or(settings);
+    issues.extend(check_folders(&settings));
+    SettingsValidation { settings, issues }
+}
+
+pub fn log_fixed(issues: &[SettingIssue]) {
+    for issue in issues.iter().filter(|i| i.fixed) {
+        log::warn!("Settings: {}: {}", issue.key, issue.message);
+    }
+}
+
+fn fix_value(value: &mut Value) -> Vec<SettingIssue> {
+    let mut issues = Vec::new();
+    let Value::Object(map) = value else { return issues };
+
+    for option in NUMBER_OPTIONS {
+        let Some(number) = map.get(option.key).and_then(Value::as_f64) else { continue };
+        let mut fixed = number.clamp(option.min, option.max);
+        if option.integer {
+            fixed = fixed.round();
+        }
+        if fixed != number {
+            issues.push(SettingIssue {
+                key: option.key.to_string(),
+                message: format!("{} is outside {}-{}, using {}", number, option.min, option.max, fixed),
+                fixed: true,
+            });
+            map.insert(option.key.to_string(), number_value(fixed, option.integer));
+        }
+    }
+
+    let mut fix_choice = |key: &str, valid: fn(&str) -> bool, default: String| {
+        let Some(current) = map.get(key).and_then(Value::as_str) else { return };
+        if !valid(current) {
+            issues.push(SettingIssue {
+                key: key.to_string(),
+                message: format!("Unknown value \"{}\", using \"{}\"", current, default),
+                fixed: true,
+            });
+            map.insert(key.to_string(), Value::String(default));
+        }
+    };
+    fix_choice("log_level", |level| log::LevelFilter::from_str(level).is_ok(), crate::default_log_level());
+    fix_choice(
+        "visualization_frequency_scale",
+        |scale| FrequencyScale::from_name(scale).is_some(),
+        crate::default_frequency_scale(),
+    );
+    issues
+}
+
+fn number_value(number: f64, integer: bool) -> Value {
+    if integer {
+        Value::from(number as u64)
+    } else {
+        serde_json::Number::from_f64(number).map(Value::Number).unwrap_or(Value::Null)
+    }
+}
+
+fn check_folders(settings: &AppSettings) -> Vec<SettingIssue> {
+    let folders = [
+        &settings.music_folder_path,
+        &settings.ambient_folder_path,
+        &settings.soundboard_folder_path,
+        &settings.presets_folder_path,
+    ];
+    FOLDER_OPTIONS
+        .iter()
+        .zip(folders)
+        .filter(|(_, path)| !Path::new(path.as_str()).is_dir())
+        .map(|(key, path)| SettingIssue {
+            key: key.to_string(),
+            message: format!("Folder not found: {}", path),
+            fixed: false,
+        })
+        .collect()
+}
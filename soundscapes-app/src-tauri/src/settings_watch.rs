@@ -0,0 +1,157 @@
+// Live settings reload - settings.json may be edited by hand or synced from another machine while
+// the app runs. Engine settings (volumes, crossfade, ducking, ...) are applied right away and the
+// windows get settings://changed so they can pick up the rest, e.g. rescan a folder that moved.
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::{Emitter, Manager};
+
+use crate::data_sync::etag;
+use crate::{AppSettings, AudioCommand, AudioController};
+
+pub const CHANGED_EVENT: &str = "settings://changed";
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// settings://changed payload; changed lists the top-level keys that differ from before
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SettingsChanged {
+    pub settings: AppSettings,
+    pub changed: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct SettingsWatch {
+    // ETag of the content this app last wrote, so its own saves aren't reported back
+    written: Mutex<Option<String>>,
+}
+
+impl SettingsWatch {
+    pub fn wrote(&self, content: &str) {
+        *self.written.lock() = Some(etag(content.as_bytes()));
+    }
+
+    fn is_own(&self, content: &str) -> bool {
+        self.written.lock().as_deref() == Some(etag(content.as_bytes()).as_str())
+    }
+}
+
+// Top-level keys whose values differ
+fn changed_keys(old: &AppSettings, new: &AppSettings) -> Vec<String> {
+    let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+    new.iter()
+        .filter(|(key, value)| old.get(*key) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+// The settings the engine holds itself; everything else is applied by the windows (or the headless
+// daemon) when they see the event
+fn apply(app: &tauri::AppHandle, controller: &AudioController, settings: &AppSettings, changed: &[String]) {
+    for key in changed {
+        match key.as_str() {
+            "master_volume" => controller.send(AudioCommand::SetMasterVolume(settings.master_volume / 100.0)),
+            "music_volume" => controller.send(AudioCommand::SetVolume(settings.music_volume / 100.0)),
+            "ambient_volume" => controller.send(AudioCommand::SetAmbientMasterVolume(settings.ambient_volume / 100.0)),
+            "soundboard_volume" => controller.send(AudioCommand::SetSoundboardVolume(settings.soundboard_volume / 100.0)),
+            "music_crossfade_duration" => controller.send(AudioCommand::SetCrossfadeDuration(settings.music_crossfade_duration)),
+            "music_skip_crossfade_duration" => {
+                controller.send(AudioCommand::SetSkipCrossfadeDuration(settings.music_skip_crossfade_duration))
+            }
+            "ambient_preset_crossfade_duration" => {
+                controller.send(AudioCommand::SetPresetCrossfadeDuration(settings.ambient_preset_crossfade_duration))
+            }
+            "soundboard_duck_amount" => controller.send(AudioCommand::SetDuckAmount(settings.soundboard_duck_amount)),
+            "music_loudness_matching" => controller.send(AudioCommand::SetLoudnessMatching(settings.music_loudness_matching)),
+            "music_skip_on_silence" => controller.send(AudioCommand::SetSkipOnSilence(settings.music_skip_on_silence)),
+            "audio_tick_ms" => controller.send(AudioCommand::SetTickInterval(settings.audio_tick_ms)),
+            "ambient_mmap_threshold_mb" => {
+                controller.send(AudioCommand::SetMmapThreshold(settings.ambient_mmap_threshold_mb as u64 * 1024 * 1024))
+            }
+            "audio_realtime_priority" => {
+                // The audio thread logs whether it worked
+                let (reply, _) = std::sync::mpsc::channel();
+                controller.send(AudioCommand::SetRealtimePriority(settings.audio_realtime_priority, reply));
+            }
+            "output_device" => {
+                // The audio thread logs whether it worked
+                let (reply, _) = std::sync::mpsc::channel();
+                let device = Some(settings.output_device.clone()).filter(|id| !id.is_empty());
+                controller.send(AudioCommand::SetOutputDevice(device, reply));
+            }
+            "soundboard_output_device" => {
+                // The audio thread logs whether it worked
+                let (reply, _) = std::sync::mpsc::channel();
+                let device = Some(settings.soundboard_output_device.clone()).filter(|id| !id.is_empty());
+                controller.send(AudioCommand::SetSoundboardOutputDevice(device, reply));
+            }
+            "panic_mute_shortcut" => {
+                if let Err(e) = crate::panic_mute::register(app, &settings.panic_mute_shortcut) {
+                    log::warn!("Ignoring panic mute shortcut from settings.json: {}", e);
+                }
+            }
+            "night_mode" => controller.night_mode.set(settings.night_mode),
+            "log_level" => {
+                if let Err(e) = crate::logging::set_level(&settings.log_level) {
+                    log::warn!("Ignoring log level from settings.json: {}", e);
+                }
+            }
+            "data_sync" => controller.data_sync.set_policy(settings.data_sync.on_conflict),
+            "ambient_category_volumes" => {
+                *controller.category_volumes.lock() = settings.ambient_category_volumes.clone();
+                controller.send(AudioCommand::SetCategoryVolumes(settings.ambient_category_volumes.clone()));
+            }
+            _ => {}
+        }
+    }
+}
+
+// Poll settings.json and apply changes made by anything other than this app. A file that doesn't
+// parse (e.g. half-way through a manual edit) is skipped until it does.
+pub fn watch(app: tauri::AppHandle) {
+    let path = crate::get_settings_path();
+    thread::spawn(move || {
+        let stamp = |path: &Path| fs::metadata(path).ok().map(|m| (m.modified().ok(), m.len()));
+        let mut seen: Option<(Option<SystemTime>, u64)> = stamp(&path);
+        let mut current = crate::get_settings().unwrap_or_else(|_| crate::get_default_settings());
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let now = stamp(&path);
+            if now == seen {
+                continue;
+            }
+            seen = now;
+            let Ok(content) = fs::read_to_string(&path) else { continue };
+            let settings = match crate::settings_check::parse(&content) {
+                Ok((settings, issues)) => {
+                    crate::settings_check::log_fixed(&issues);
+                    settings
+                }
+                Err(e) => {
+                    log::warn!("Ignoring settings.json until it parses: {}", e);
+                    continue;
+                }
+            };
+            let controller = app.state::<Arc<AudioController>>();
+            if controller.settings_watch.is_own(&content) {
+                current = settings;
+                continue;
+            }
+            let changed = changed_keys(&current, &settings);
+            if changed.is_empty() {
+                continue;
+            }
+            log::info!("settings.json changed outside the app: {}", changed.join(", "));
+            apply(&app, &controller, &settings, &changed);
+            let _ = app.emit(CHANGED_EVENT, SettingsChanged { settings: settings.clone(), changed });
+            current = settings;
+        }
+    });
+}
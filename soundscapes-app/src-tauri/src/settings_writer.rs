@@ -0,0 +1,125 @@
+// Debounced settings writes - dragging a volume slider calls save_volume_setting dozens of times a
+// second, and each call used to read and rewrite settings.json. Updates are now collected here and
+// a writer thread saves them together once they stop arriving for a moment. Ambient category
+// volumes go the same way.
+use parking_lot::{Condvar, Mutex};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use crate::{AppSettings, AudioController, CategoryVolume};
+
+// Quiet time before pending updates are written
+const DEBOUNCE: Duration = Duration::from_millis(500);
+// Longest an update waits while more keep arriving
+const MAX_DELAY: Duration = Duration::from_secs(3);
+
+pub const VOLUME_KEYS: &[&str] = &["master_volume", "music_volume", "ambient_volume", "soundboard_volume"];
+
+#[derive(Default)]
+pub struct SettingsWriter {
+    pending: Mutex<Pending>,
+    wake: Condvar,
+}
+
+#[derive(Default)]
+struct Pending {
+    volumes: HashMap<String, f32>,
+    categories: HashMap<String, CategoryVolume>,
+    // First and latest update since the last write
+    first: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl SettingsWriter {
+    pub fn set_volume(&self, key: &str, value: f32) {
+        let now = Instant::now();
+        let mut pending = self.pending.lock();
+        pending.volumes.insert(key.to_string(), value);
+        pending.first.get_or_insert(now);
+        pending.last = Some(now);
+        self.wake.notify_one();
+    }
+
+    pub fn set_category_volume(&self, category: &str, volume: CategoryVolume) {
+        let now = Instant::now();
+        let mut pending = self.pending.lock();
+        pending.categories.insert(category.to_string(), volume);
+        pending.first.get_or_insert(now);
+        pending.last = Some(now);
+        self.wake.notify_one();
+    }
+
+    // Move pending updates into settings that are about to be written anyway
+    pub fn take_into(&self, settings: &mut AppSettings) {
+        let pending = std::mem::take(&mut *self.pending.lock());
+        for (key, value) in pending.volumes {
+            set_volume(settings, &key, value);
+        }
+        settings.ambient_category_volumes.extend(pending.categories);
+    }
+
+    // Write pending updates now, e.g. before exiting
+    pub fn flush(&self, controller: &AudioController) {
+        let pending = std::mem::take(&mut *self.pending.lock());
+        write(controller, pending);
+    }
+}
+
+fn set_volume(settings: &mut AppSettings, key: &str, value: f32) {
+    match key {
+        "master_volume" => settings.master_volume = value,
+        "music_volume" => settings.music_volume = value,
+        "ambient_volume" => settings.ambient_volume = value,
+        "soundboard_volume" => settings.soundboard_volume = value,
+        _ => {}
+    }
+}
+
+// Re-read settings.json at write time, so edits made since (by hand or by the watcher) are kept
+fn write(controller: &AudioController, pending: Pending) {
+    if pending.volumes.is_empty() && pending.categories.is_empty() {
+        return;
+    }
+    let mut settings = match crate::get_settings() {
+        Ok(settings) => settings,
+        Err(e) => {
+            log::warn!("Dropping volume changes, settings.json can't be read: {}", e);
+            return;
+        }
+    };
+    for (key, value) in pending.volumes {
+        set_volume(&mut settings, &key, value);
+    }
+    settings.ambient_category_volumes.extend(pending.categories);
+    if let Err(e) = crate::write_settings(controller, &settings) {
+        log::warn!("Failed to save volume changes: {}", e);
+    }
+}
+
+pub fn start(app: tauri::AppHandle) {
+    thread::spawn(move || {
+        let controller = app.state::<Arc<AudioController>>().inner().clone();
+        let writer = &controller.settings_writer;
+        loop {
+            let mut pending = writer.pending.lock();
+            while pending.first.is_none() {
+                writer.wake.wait(&mut pending);
+            }
+            // Wait until updates pause, or until the first one has waited long enough
+            while let (Some(first), Some(last)) = (pending.first, pending.last) {
+                let due = (last + DEBOUNCE).min(first + MAX_DELAY);
+                let now = Instant::now();
+                if now >= due {
+                    break;
+                }
+                writer.wake.wait_for(&mut pending, due - now);
+            }
+            let taken = std::mem::take(&mut *pending);
+            drop(pending);
+            write(&controller, taken);
+        }
+    });
+}
@@ -0,0 +1,68 @@
+// State broadcast - the current track, playlist state and active ambients change from commands,
+// the audio thread, the scheduler and every remote surface. Rather than have each window poll for
+// them, one thread compares them against what it last sent and emits an event when they differ.
+use serde::Serialize;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::{ActiveAmbientInfo, AudioController, CurrentTrackInfo, PlaylistState};
+
+pub const TRACK_CHANGED_EVENT: &str = "state://track-changed";
+pub const PLAYLIST_CHANGED_EVENT: &str = "state://playlist-changed";
+pub const AMBIENT_CHANGED_EVENT: &str = "state://ambient-changed";
+// Fast enough that a click in one window shows up in the others straight away
+const CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Serialize)]
+struct TrackChanged {
+    track: Option<CurrentTrackInfo>,
+    is_playing: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct AmbientChanged {
+    // Sorted by id
+    ambients: Vec<ActiveAmbientInfo>,
+    current_preset_id: Option<String>,
+}
+
+pub fn start(app: tauri::AppHandle) {
+    thread::spawn(move || {
+        let mut last_track: Option<serde_json::Value> = None;
+        let mut last_playlist: Option<serde_json::Value> = None;
+        let mut last_ambient: Option<serde_json::Value> = None;
+        loop {
+            let controller = app.state::<Arc<AudioController>>();
+            let track = TrackChanged {
+                track: controller.get_current_track(),
+                is_playing: controller.get_progress().is_playing,
+            };
+            emit_if_changed(&app, TRACK_CHANGED_EVENT, &track, &mut last_track);
+
+            let playlist: PlaylistState = controller.get_playlist_state();
+            emit_if_changed(&app, PLAYLIST_CHANGED_EVENT, &playlist, &mut last_playlist);
+
+            let mut ambients: Vec<ActiveAmbientInfo> = controller.active_ambients.lock().values().cloned().collect();
+            ambients.sort_by(|a, b| a.id.cmp(&b.id));
+            let ambient = AmbientChanged { ambients, current_preset_id: controller.current_preset_id.lock().clone() };
+            emit_if_changed(&app, AMBIENT_CHANGED_EVENT, &ambient, &mut last_ambient);
+
+            thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
+
+// Compared as JSON, which is also exactly what the windows see
+fn emit_if_changed<T: Serialize>(app: &tauri::AppHandle, event: &str, payload: &T, last: &mut Option<serde_json::Value>) {
+    let Ok(value) = serde_json::to_value(payload) else { return };
+    if last.as_ref() == Some(&value) {
+        return;
+    }
+    // Nothing to compare the first snapshot with; windows read the initial state themselves
+    if last.is_some() {
+        let _ = app.emit(event, &value);
+    }
+    *last = Some(value);
+}
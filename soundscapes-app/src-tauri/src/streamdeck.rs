@@ -0,0 +1,205 @@
+// Stream Deck protocol - newline-delimited JSON over a localhost TCP socket, backing the Stream Deck plugin
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use crate::remote::{self, RemoteAction};
+use crate::AudioController;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const STATUS_INTERVAL: Duration = Duration::from_millis(200);
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+// Lines sent by the plugin: either a remote action ({"action": "apply_preset", "id": "rain"})
+// or a query ({"type": "get_buttons"})
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DeckRequest {
+    Action(RemoteAction),
+    Query(DeckQuery),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum DeckQuery {
+    GetButtons,
+    GetStatus,
+}
+
+#[derive(Serialize)]
+struct DeckPresetButton {
+    id: String,
+    name: String,
+}
+
+#[derive(Serialize)]
+struct DeckSoundButton {
+    id: String,
+    name: String,
+    color: Option<String>,
+}
+
+// Feedback for button images and titles
+#[derive(Serialize, PartialEq, Clone)]
+struct DeckStatus {
+    now_playing: String, // "Title - Artist", empty when nothing is loaded
+    is_playing: bool,
+    current_preset_id: Option<String>,
+    soundboard_playing: bool,
+    active_ambients: usize,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum DeckEvent {
+    Buttons { presets: Vec<DeckPresetButton>, sounds: Vec<DeckSoundButton> },
+    Status(DeckStatus),
+    Result { ok: bool, error: Option<String> },
+}
+
+// Running Stream Deck socket; dropping it stops accepting and disconnects clients
+pub struct StreamDeckServer {
+    stop: Arc<AtomicBool>,
+}
+
+impl StreamDeckServer {
+    pub fn start(app: tauri::AppHandle, port: u16) -> Result<Self, String> {
+        // The plugin runs on the same machine, so the socket is never exposed to the network
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .map_err(|e| format!("Failed to start Stream Deck server on port {}: {}", port, e))?;
+        listener.set_nonblocking(true)
+            .map_err(|e| format!("Failed to configure Stream Deck listener: {}", e))?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = stop.clone();
+        thread::spawn(move || {
+            while !stop_clone.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let app = app.clone();
+                        let stop = stop_clone.clone();
+                        thread::spawn(move || handle_client(app, stream, stop));
+                    }
+                    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                    Err(e) => {
+                        log::warn!("Stream Deck accept failed: {}", e);
+                        thread::sleep(POLL_INTERVAL);
+                    }
+                }
+            }
+        });
+
+        Ok(Self { stop })
+    }
+}
+
+impl Drop for StreamDeckServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn get_buttons(app: &tauri::AppHandle) -> Result<DeckEvent, String> {
+    let presets = remote::list_presets(app)?
+        .into_iter()
+        .map(|p| DeckPresetButton { id: p.id, name: p.name })
+        .collect();
+    let sounds = remote::list_soundboard(app)?
+        .into_iter()
+        .map(|s| DeckSoundButton { id: s.id, name: s.name, color: s.color })
+        .collect();
+    Ok(DeckEvent::Buttons { presets, sounds })
+}
+
+fn get_status(controller: &AudioController) -> DeckStatus {
+    let progress = controller.get_progress();
+    let now_playing = controller.get_current_track()
+        .map(|t| if t.artist.is_empty() { t.title } else { format!("{} - {}", t.title, t.artist) })
+        .unwrap_or_default();
+    DeckStatus {
+        now_playing,
+        is_playing: progress.is_playing,
+        current_preset_id: controller.current_preset_id.lock().clone(),
+        soundboard_playing: controller.playback_state.lock().soundboard_playing,
+        active_ambients: controller.active_ambients.lock().len(),
+    }
+}
+
+fn send_event(stream: &mut TcpStream, event: &DeckEvent) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(event).unwrap_or_default();
+    line.push(b'\n');
+    stream.write_all(&line)
+}
+
+fn handle_request(app: &tauri::AppHandle, controller: &AudioController, line: &[u8]) -> DeckEvent {
+    match serde_json::from_slice::<DeckRequest>(line) {
+        Ok(DeckRequest::Action(action)) => {
+            let result = remote::apply_action(app, action);
+            DeckEvent::Result { ok: result.is_ok(), error: result.err() }
+        }
+        Ok(DeckRequest::Query(DeckQuery::GetButtons)) => get_buttons(app)
+            .unwrap_or_else(|e| DeckEvent::Result { ok: false, error: Some(e) }),
+        Ok(DeckRequest::Query(DeckQuery::GetStatus)) => DeckEvent::Status(get_status(controller)),
+        Err(e) => DeckEvent::Result { ok: false, error: Some(format!("Invalid message: {}", e)) },
+    }
+}
+
+fn handle_client(app: tauri::AppHandle, stream: TcpStream, stop: Arc<AtomicBool>) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(POLL_INTERVAL));
+    let mut writer = match stream.try_clone() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+    let mut reader = BufReader::new(stream);
+    let controller = app.state::<Arc<AudioController>>();
+
+    // Send the button list and current status straight away so the plugin can draw its keys
+    if let Ok(buttons) = get_buttons(&app) {
+        if send_event(&mut writer, &buttons).is_err() {
+            return;
+        }
+    }
+    let mut last_status: Option<DeckStatus> = None;
+    let mut last_status_check = Instant::now() - STATUS_INTERVAL;
+    let mut line = Vec::new();
+
+    while !stop.load(Ordering::Relaxed) {
+        // read_until keeps partial lines in `line` when the read times out
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => break,
+            Ok(_) if line.ends_with(b"\n") => {
+                let is_blank = line.iter().all(u8::is_ascii_whitespace);
+                let event = (!is_blank).then(|| handle_request(&app, &controller, &line));
+                line.clear();
+                if let Some(event) = event {
+                    if send_event(&mut writer, &event).is_err() {
+                        break;
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(_) => break,
+        }
+        if line.len() > MAX_LINE_BYTES {
+            break;
+        }
+
+        if last_status_check.elapsed() >= STATUS_INTERVAL {
+            last_status_check = Instant::now();
+            let status = get_status(&controller);
+            if last_status.as_ref() != Some(&status) {
+                last_status = Some(status.clone());
+                if send_event(&mut writer, &DeckEvent::Status(status)).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// Live streaming - subscribes to the engine's mix tap and pushes the mix to an Icecast/Shoutcast server as MP3.
+// The tap lives in soundscapes-core; it's re-exported here for the recorder, the replay buffer and the encoders.
+use base64::Engine;
+use mp3lame_encoder::{max_required_buffer_size, Bitrate, Builder, InterleavedPcm, Quality};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub use soundscapes_core::mix_tap::{Bus, MixTap, Subscription, MIX_INTERVAL, TAP_CHANNELS, TAP_RATE};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct StreamingConfig {
+    pub enabled: bool,
+    pub server_type: String, // "icecast" or "shoutcast" (v1 source protocol)
+    pub host: String,
+    pub port: u16,
+    pub mount: String, // Icecast only
+    pub username: String, // Icecast only, usually "source"
+    pub password: String,
+    pub bitrate: u32, // kbps
+    pub stream_name: String,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            server_type: "icecast".to_string(),
+            host: String::new(),
+            port: 8000,
+            mount: "/soundscapes".to_string(),
+            username: "source".to_string(),
+            password: String::new(),
+            bitrate: 128,
+            stream_name: "Soundscapes".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StreamingStatus {
+    pub state: String, // "connecting", "live" or "error"
+    pub message: Option<String>,
+}
+
+
+// Running stream; dropping it disconnects from the server
+pub struct Streamer {
+    stop: Arc<AtomicBool>,
+    status: Arc<Mutex<StreamingStatus>>,
+}
+
+impl Streamer {
+    pub fn start(config: StreamingConfig, tap: Arc<MixTap>) -> Result<Self, String> {
+        if config.host.trim().is_empty() {
+            return Err("Streaming server host is required".to_string());
+        }
+        bitrate_for(config.bitrate)?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let status = Arc::new(Mutex::new(StreamingStatus { state: "connecting".to_string(), message: None }));
+        let stop_clone = stop.clone();
+        let status_clone = status.clone();
+
+        let subscription = tap.subscribe();
+        thread::spawn(move || {
+            while !stop_clone.load(Ordering::Relaxed) {
+                *status_clone.lock() = StreamingStatus { state: "connecting".to_string(), message: None };
+                let result = connect(&config).and_then(|stream| {
+                    *status_clone.lock() = StreamingStatus { state: "live".to_string(), message: None };
+                    stream_mix(stream, &config, &subscription, &stop_clone)
+                });
+                if let Err(e) = result {
+                    log::warn!("Streaming: {}", e);
+                    *status_clone.lock() = StreamingStatus { state: "error".to_string(), message: Some(e) };
+                }
+                let retry_at = Instant::now() + RECONNECT_INTERVAL;
+                while Instant::now() < retry_at && !stop_clone.load(Ordering::Relaxed) {
+                    thread::sleep(MIX_INTERVAL);
+                }
+            }
+        });
+
+        Ok(Self { stop, status })
+    }
+
+    pub fn status(&self) -> StreamingStatus {
+        self.status.lock().clone()
+    }
+}
+
+impl Drop for Streamer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+pub fn bitrate_for(kbps: u32) -> Result<Bitrate, String> {
+    match kbps {
+        64 => Ok(Bitrate::Kbps64),
+        96 => Ok(Bitrate::Kbps96),
+        128 => Ok(Bitrate::Kbps128),
+        160 => Ok(Bitrate::Kbps160),
+        192 => Ok(Bitrate::Kbps192),
+        256 => Ok(Bitrate::Kbps256),
+        320 => Ok(Bitrate::Kbps320),
+        _ => Err(format!("Unsupported bitrate: {} kbps", kbps)),
+    }
+}
+
+fn connect(config: &StreamingConfig) -> Result<TcpStream, String> {
+    // Shoutcast v1 sources connect to the port above the listener port
+    let port = if config.server_type == "shoutcast" { config.port.saturating_add(1) } else { config.port };
+    let addr = (config.host.as_str(), port).to_socket_addrs()
+        .map_err(|e| format!("Invalid server address {}: {}", config.host, e))?
+        .next()
+        .ok_or_else(|| format!("Could not resolve {}", config.host))?;
+    let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+        .map_err(|e| format!("Failed to connect to {}:{}: {}", config.host, port, e))?;
+    let _ = stream.set_read_timeout(Some(CONNECT_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(CONNECT_TIMEOUT));
+
+    if config.server_type == "shoutcast" {
+        write_all(&mut stream, format!("{}\r\n", config.password).as_bytes())?;
+        let reply = read_head(&mut stream, b"\r\n")?;
+        if !reply.starts_with("OK") {
+            return Err(format!("Server rejected the password: {}", reply.trim()));
+        }
+        let headers = format!(
+            "icy-name:{}\r\nicy-br:{}\r\nicy-pub:0\r\ncontent-type:audio/mpeg\r\n\r\n",
+            config.stream_name, config.bitrate
+        );
+        write_all(&mut stream, headers.as_bytes())?;
+    } else {
+        let mount = if config.mount.starts_with('/') { config.mount.clone() } else { format!("/{}", config.mount) };
+        let credentials = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", config.username, config.password));
+        let request = format!(
+            "PUT {} HTTP/1.1\r\nHost: {}:{}\r\nAuthorization: Basic {}\r\nUser-Agent: Soundscapes\r\nContent-Type: audio/mpeg\r\nIce-Name: {}\r\nIce-Public: 0\r\nIce-Audio-Info: bitrate={}\r\nExpect: 100-continue\r\n\r\n",
+            mount, config.host, config.port, credentials, config.stream_name, config.bitrate
+        );
+        write_all(&mut stream, request.as_bytes())?;
+        let reply = read_head(&mut stream, b"\r\n\r\n")?;
+        let status_line = reply.lines().next().unwrap_or("");
+        if !(status_line.contains(" 100 ") || status_line.contains(" 200 ")) {
+            return Err(format!("Server refused the stream: {}", status_line));
+        }
+    }
+    Ok(stream)
+}
+
+fn write_all(stream: &mut TcpStream, data: &[u8]) -> Result<(), String> {
+    stream.write_all(data).map_err(|e| format!("Connection lost: {}", e))
+}
+
+// Read a server reply up to and including `terminator`
+fn read_head(stream: &mut TcpStream, terminator: &[u8]) -> Result<String, String> {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(terminator) && head.len() < 4096 {
+        match stream.read(&mut byte) {
+            Ok(0) => break,
+            Ok(_) => head.push(byte[0]),
+            Err(e) => return Err(format!("No reply from server: {}", e)),
+        }
+    }
+    Ok(String::from_utf8_lossy(&head).to_string())
+}
+
+// Encode the mix as the pump delivers it and send it until stopped or disconnected
+fn stream_mix(mut stream: TcpStream, config: &StreamingConfig, subscription: &Subscription, stop: &AtomicBool) -> Result<(), String> {
+    let mut builder = Builder::new().ok_or_else(|| "Failed to create MP3 encoder".to_string())?;
+    builder.set_num_channels(TAP_CHANNELS as u8).map_err(|e| format!("MP3 encoder: {:?}", e))?;
+    builder.set_sample_rate(TAP_RATE).map_err(|e| format!("MP3 encoder: {:?}", e))?;
+    builder.set_brate(bitrate_for(config.bitrate)?).map_err(|e| format!("MP3 encoder: {:?}", e))?;
+    builder.set_quality(Quality::Good).map_err(|e| format!("MP3 encoder: {:?}", e))?;
+    let mut encoder = builder.build().map_err(|e| format!("MP3 encoder: {:?}", e))?;
+
+    // Blocks queued while reconnecting would arrive as a burst; start from now
+    subscription.take();
+    while !stop.load(Ordering::Relaxed) {
+        thread::sleep(MIX_INTERVAL);
+        for block in subscription.take() {
+            let pcm: Vec<i16> = block.mix()
+                .into_iter()
+                .map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+                .collect();
+            let mut mp3 = Vec::with_capacity(max_required_buffer_size(pcm.len()));
+            let size = encoder.encode(InterleavedPcm(&pcm), mp3.spare_capacity_mut())
+                .map_err(|e| format!("MP3 encoding failed: {:?}", e))?;
+            // SAFETY: encode() initialized the first `size` bytes of the spare capacity
+            unsafe {
+                mp3.set_len(size);
+            }
+            write_all(&mut stream, &mp3)?;
+        }
+    }
+    Ok(())
+}
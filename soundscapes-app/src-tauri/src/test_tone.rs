@@ -0,0 +1,116 @@
+// Test tone - with several outputs connected it isn't always clear which device (or which of its
+// speakers) a name in the device list is. play_test_tone plays a short sine on one channel of one
+// device, opened on its own stream so the engine's output keeps playing as it was.
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::{OutputStream, Source};
+use std::f32::consts::TAU;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+const FREQUENCY: f32 = 440.0;
+const SECONDS: f32 = 1.0;
+const AMPLITUDE: f32 = 0.25;
+// Ramp at each end so the tone doesn't click
+const RAMP_SECS: f32 = 0.01;
+const SAMPLE_RATE: u32 = 48000;
+
+// Sine on one channel, silence on the others
+struct Tone {
+    channels: u16,
+    channel: u16,
+    frame: u32,
+    frames: u32,
+    current: u16,
+}
+
+impl Iterator for Tone {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.frame >= self.frames {
+            return None;
+        }
+        let sample = if self.current == self.channel {
+            let t = self.frame as f32 / SAMPLE_RATE as f32;
+            let ramp = (t / RAMP_SECS).min((SECONDS - t) / RAMP_SECS).clamp(0.0, 1.0);
+            (t * FREQUENCY * TAU).sin() * AMPLITUDE * ramp
+        } else {
+            0.0
+        };
+        self.current += 1;
+        if self.current >= self.channels {
+            self.current = 0;
+            self.frame += 1;
+        }
+        Some(sample)
+    }
+}
+
+impl Source for Tone {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(SECONDS))
+    }
+}
+
+// Channel count of an output device in its default configuration
+pub fn channels(device: &rodio::cpal::Device) -> Option<u16> {
+    device.default_output_config().ok().map(|config| config.channels())
+}
+
+// Play the tone on `channel` (0 is the first) of the device named `device_id`. Returns once the
+// stream is open; the tone plays out on its own thread.
+pub fn play(device_id: &str, channel: u16) -> Result<(), String> {
+    let device_id = device_id.to_string();
+    let (opened_tx, opened) = mpsc::channel();
+    // The stream can't move between threads, so it is opened on the one that keeps it alive
+    thread::Builder::new()
+        .name("test-tone".to_string())
+        .spawn(move || {
+            let stream = match open(&device_id, channel) {
+                Ok(stream) => {
+                    let _ = opened_tx.send(Ok(()));
+                    stream
+                }
+                Err(e) => {
+                    let _ = opened_tx.send(Err(e));
+                    return;
+                }
+            };
+            thread::sleep(Duration::from_secs_f32(SECONDS + 0.2));
+            drop(stream);
+        })
+        .map_err(|e| format!("Failed to start test tone: {}", e))?;
+    opened.recv().map_err(|_| "Test tone thread stopped".to_string())?
+}
+
+fn open(device_id: &str, channel: u16) -> Result<OutputStream, String> {
+    let host = rodio::cpal::default_host();
+    let device = host
+        .output_devices()
+        .map_err(|e| format!("Failed to enumerate devices: {}", e))?
+        .find(|device| device.name().is_ok_and(|name| name == device_id))
+        .ok_or_else(|| format!("Output device not found: {}", device_id))?;
+    let channels = channels(&device).ok_or_else(|| format!("Can't read the configuration of {}", device_id))?;
+    if channel >= channels {
+        return Err(format!("{} has {} channels, there is no channel {}", device_id, channels, channel + 1));
+    }
+    let (stream, handle) = OutputStream::try_from_device(&device).map_err(|e| e.to_string())?;
+    let frames = (SECONDS * SAMPLE_RATE as f32) as u32;
+    handle
+        .play_raw(Tone { channels, channel, frame: 0, frames, current: 0 })
+        .map_err(|e| e.to_string())?;
+    Ok(stream)
+}
@@ -0,0 +1,179 @@
+// System tray icon - playback controls, mute and recent presets while the window is minimized
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::remote::{self, RemoteAction};
+use crate::{AudioCommand, AudioController};
+
+const TRAY_ID: &str = "main";
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const RECENT_PRESET_COUNT: usize = 5;
+const PRESET_ID_PREFIX: &str = "preset:";
+// Quit fades the master bus out over QUIT_FADE_STEPS * QUIT_FADE_STEP
+const QUIT_FADE_STEPS: u32 = 30;
+const QUIT_FADE_STEP: Duration = Duration::from_millis(50);
+
+// Menu items whose text/check state follows playback
+struct TrayItems {
+    play_pause: MenuItem<Wry>,
+    mute: CheckMenuItem<Wry>,
+}
+
+pub fn start(app: &AppHandle) -> tauri::Result<()> {
+    let mut recent = initial_recent_presets(app);
+    let (menu, mut items) = build_menu(app, &recent, false, false)?;
+
+    let tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Soundscapes")
+        .menu(&menu)
+        .menu_on_left_click(false)
+        .on_menu_event(handle_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = event {
+                show_main_window(tray.app_handle());
+            }
+        });
+    match app.default_window_icon() {
+        Some(icon) => tray.icon(icon.clone()),
+        None => tray,
+    }
+    .build(app)?;
+
+    // Keep the play/pause label, mute check and recent presets in sync with the backend
+    let app = app.clone();
+    thread::spawn(move || {
+        let mut last_preset: Option<String> = None;
+        let mut last_status: Option<(bool, bool)> = None;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let controller = app.state::<Arc<AudioController>>();
+            let is_playing = controller.get_progress().is_playing;
+            let is_muted = controller.playback_state.lock().is_muted;
+            let preset = controller.current_preset_id.lock().clone();
+
+            if preset.is_some() && preset != last_preset {
+                last_preset = preset.clone();
+                if let Some(id) = preset {
+                    recent.retain(|(recent_id, _)| recent_id != &id);
+                    if let Some(info) = remote::list_presets(&app).ok().and_then(|p| p.into_iter().find(|p| p.id == id)) {
+                        recent.insert(0, (info.id, info.name));
+                        recent.truncate(RECENT_PRESET_COUNT);
+                    }
+                }
+                if let Ok((menu, new_items)) = build_menu(&app, &recent, is_playing, is_muted) {
+                    items = new_items;
+                    set_menu(&app, menu);
+                }
+                last_status = Some((is_playing, is_muted));
+                continue;
+            }
+
+            if last_status != Some((is_playing, is_muted)) {
+                last_status = Some((is_playing, is_muted));
+                let _ = items.play_pause.set_text(if is_playing { "Pause" } else { "Play" });
+                let _ = items.mute.set_checked(is_muted);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn set_menu(app: &AppHandle, menu: Menu<Wry>) {
+    // Menus must be swapped on the main thread
+    let handle = app.clone();
+    let _ = app.run_on_main_thread(move || {
+        if let Some(tray) = handle.tray_by_id(TRAY_ID) {
+            let _ = tray.set_menu(Some(menu));
+        }
+    });
+}
+
+// Seed the submenu with the most recently modified presets until the user applies some
+fn initial_recent_presets(app: &AppHandle) -> Vec<(String, String)> {
+    let mut presets = remote::list_presets(app).unwrap_or_default();
+    presets.sort_by(|a, b| b.modified.cmp(&a.modified));
+    presets.into_iter()
+        .take(RECENT_PRESET_COUNT)
+        .map(|p| (p.id, p.name))
+        .collect()
+}
+
+fn build_menu(app: &AppHandle, recent: &[(String, String)], is_playing: bool, is_muted: bool) -> tauri::Result<(Menu<Wry>, TrayItems)> {
+    let play_pause = MenuItem::with_id(app, "play_pause", if is_playing { "Pause" } else { "Play" }, true, None::<&str>)?;
+    let next = MenuItem::with_id(app, "next", "Next Track", true, None::<&str>)?;
+    let mute = CheckMenuItem::with_id(app, "mute", "Mute", true, is_muted, None::<&str>)?;
+
+    let preset_items = recent.iter()
+        .map(|(id, name)| MenuItem::with_id(app, format!("{}{}", PRESET_ID_PREFIX, id), name, true, None::<&str>))
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let preset_refs: Vec<&dyn IsMenuItem<Wry>> = preset_items.iter().map(|item| item as &dyn IsMenuItem<Wry>).collect();
+    let presets = Submenu::with_items(app, "Recent Presets", !preset_items.is_empty(), &preset_refs)?;
+
+    let show = MenuItem::with_id(app, "show", "Show Soundscapes", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    let menu = Menu::with_items(app, &[
+        &play_pause,
+        &next,
+        &mute,
+        &PredefinedMenuItem::separator(app)?,
+        &presets,
+        &PredefinedMenuItem::separator(app)?,
+        &show,
+        &quit,
+    ])?;
+    Ok((menu, TrayItems { play_pause, mute }))
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let id = event.id().as_ref();
+    let action = match id {
+        "play_pause" => Some(RemoteAction::TogglePlayback),
+        "next" => Some(RemoteAction::Next),
+        "mute" => {
+            let muted = app.state::<Arc<AudioController>>().playback_state.lock().is_muted;
+            Some(RemoteAction::SetMuted { bus: "master".to_string(), muted: !muted })
+        }
+        "show" => {
+            show_main_window(app);
+            None
+        }
+        "quit" => {
+            quit_with_fade(app.clone());
+            None
+        }
+        _ => id.strip_prefix(PRESET_ID_PREFIX).map(|preset_id| RemoteAction::ApplyPreset { id: preset_id.to_string() }),
+    };
+    if let Some(action) = action {
+        if let Err(e) = remote::apply_action(app, action) {
+            log::warn!("Tray action failed: {}", e);
+        }
+    }
+}
+
+pub(crate) fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+// Ramp the master volume down before exiting so everything doesn't cut off at once
+fn quit_with_fade(app: AppHandle) {
+    thread::spawn(move || {
+        let controller = app.state::<Arc<AudioController>>();
+        let start_volume = controller.playback_state.lock().master_volume;
+        for step in (0..QUIT_FADE_STEPS).rev() {
+            controller.send(AudioCommand::SetMasterVolume(start_volume * step as f32 / QUIT_FADE_STEPS as f32));
+            thread::sleep(QUIT_FADE_STEP);
+        }
+        controller.settings_writer.flush(&controller);
+        app.exit(0);
+    });
+}
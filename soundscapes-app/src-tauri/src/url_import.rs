@@ -0,0 +1,142 @@
+// Import from a URL - presets and playlists are shared as their .soundscape / .playlist files, or as
+// a zip of several. download fetches one, and parse checks everything in it before any of it is
+// installed, so a bad file in an archive doesn't leave half of it behind.
+use std::io::{Cursor, Read};
+use std::time::Duration;
+
+use crate::errors::AppError;
+use crate::{MusicPlaylist, SoundscapePreset};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+// Presets and playlists are small JSON files; anything bigger than this isn't one
+const MAX_DOWNLOAD_BYTES: u64 = 20 * 1024 * 1024;
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+const MAX_ARCHIVE_ENTRIES: usize = 500;
+const AUTO_PLAYLIST_IDS: &[&str] = &["all-music", "favorites"];
+
+pub enum Shared {
+    Preset(SoundscapePreset),
+    Playlist(MusicPlaylist),
+}
+
+pub struct Download {
+    pub file_name: String, // last segment of the URL path, used when the content doesn't say what it is
+    pub bytes: Vec<u8>,
+}
+
+pub fn download(url: &str) -> Result<Download, AppError> {
+    let lower = url.to_ascii_lowercase();
+    if !lower.starts_with("https://") && !lower.starts_with("http://") {
+        return Err(AppError::invalid("Only http and https URLs can be imported"));
+    }
+    let response = ureq::get(url).timeout(REQUEST_TIMEOUT).call().map_err(|e| match e {
+        ureq::Error::Status(404, _) => AppError::not_found(format!("Nothing to import at {}", url)),
+        e => AppError::unavailable(format!("Failed to download {}: {}", url, e)),
+    })?;
+    let mut bytes = Vec::new();
+    response.into_reader().take(MAX_DOWNLOAD_BYTES + 1).read_to_end(&mut bytes)
+        .map_err(|e| AppError::unavailable(format!("Failed to download {}: {}", url, e)))?;
+    if bytes.len() as u64 > MAX_DOWNLOAD_BYTES {
+        return Err(AppError::invalid(format!("The download is over {} MB", MAX_DOWNLOAD_BYTES / (1024 * 1024))));
+    }
+    let path = url.split(['?', '#']).next().unwrap_or("");
+    let file_name = path.rsplit('/').next().unwrap_or("").to_string();
+    Ok(Download { file_name, bytes })
+}
+
+// Everything in the download, checked; an archive with any invalid file is rejected as a whole
+pub fn parse(download: &Download) -> Result<Vec<Shared>, AppError> {
+    if download.bytes.starts_with(b"PK\x03\x04") {
+        return parse_archive(&download.bytes);
+    }
+    let item = match extension(&download.file_name) {
+        Some(ext) => parse_file(&download.file_name, ext, &download.bytes)?,
+        // No extension to go by, so it's whichever kind it parses as
+        None => parse_file(&download.file_name, "soundscape", &download.bytes)
+            .or_else(|_| parse_file(&download.file_name, "playlist", &download.bytes))
+            .map_err(|_| AppError::invalid("The download isn't a preset, playlist or zip archive"))?,
+    };
+    Ok(vec![item])
+}
+
+fn parse_archive(bytes: &[u8]) -> Result<Vec<Shared>, AppError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| AppError::invalid(format!("Failed to read the archive: {}", e)))?;
+    if archive.len() > MAX_ARCHIVE_ENTRIES {
+        return Err(AppError::invalid(format!("The archive has more than {} files", MAX_ARCHIVE_ENTRIES)));
+    }
+    let mut items = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)
+            .map_err(|e| AppError::invalid(format!("Failed to read the archive: {}", e)))?;
+        if entry.is_dir() {
+            continue;
+        }
+        // Files are installed by their id, so the folders they sit in don't matter
+        let name = entry.name().rsplit('/').next().unwrap_or("").to_string();
+        let Some(ext) = extension(&name) else {
+            log::debug!("Skipping {} in the archive", entry.name());
+            continue;
+        };
+        let mut content = Vec::new();
+        entry.by_ref().take(MAX_FILE_BYTES + 1).read_to_end(&mut content)
+            .map_err(|e| AppError::invalid(format!("Failed to read {} from the archive: {}", name, e)))?;
+        if content.len() as u64 > MAX_FILE_BYTES {
+            return Err(AppError::invalid(format!("{} is too large to be a {}", name, ext)));
+        }
+        items.push(parse_file(&name, ext, &content)?);
+    }
+    if items.is_empty() {
+        return Err(AppError::invalid("The archive has no .soundscape or .playlist files"));
+    }
+    Ok(items)
+}
+
+fn extension(file_name: &str) -> Option<&'static str> {
+    let lower = file_name.to_ascii_lowercase();
+    if lower.ends_with(".soundscape") {
+        Some("soundscape")
+    } else if lower.ends_with(".playlist") {
+        Some("playlist")
+    } else {
+        None
+    }
+}
+
+fn parse_file(name: &str, ext: &str, content: &[u8]) -> Result<Shared, AppError> {
+    let invalid = |reason: String| AppError::invalid(format!("{} isn't a valid {}: {}", name, ext, reason));
+    if ext == "playlist" {
+        let playlist: MusicPlaylist = serde_json::from_slice(content).map_err(|e| invalid(e.to_string()))?;
+        check_id(&playlist.id).map_err(invalid)?;
+        if playlist.is_auto || AUTO_PLAYLIST_IDS.contains(&playlist.id.as_str()) {
+            return Err(invalid("auto playlists can't be imported".to_string()));
+        }
+        if playlist.name.trim().is_empty() {
+            return Err(invalid("it has no name".to_string()));
+        }
+        Ok(Shared::Playlist(playlist))
+    } else {
+        let preset: SoundscapePreset = serde_json::from_slice(content).map_err(|e| invalid(e.to_string()))?;
+        check_id(&preset.id).map_err(invalid)?;
+        if preset.name.trim().is_empty() {
+            return Err(invalid("it has no name".to_string()));
+        }
+        for sound in &preset.sounds {
+            if sound.volume > 100
+                || sound.repeat_range_min > sound.repeat_range_max
+                || sound.pause_range_min > sound.pause_range_max
+            {
+                return Err(invalid(format!("sound '{}' has settings out of range", sound.name)));
+            }
+        }
+        Ok(Shared::Preset(preset))
+    }
+}
+
+// Ids become file names, so they must not reach outside the data folder
+fn check_id(id: &str) -> Result<(), String> {
+    if id.is_empty() || !id.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("'{}' isn't a valid id", id));
+    }
+    Ok(())
+}
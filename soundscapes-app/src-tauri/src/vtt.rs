@@ -0,0 +1,109 @@
+// Virtual tabletop endpoints on the remote API - lets a FoundryVTT module (or a Roll20 userscript)
+// discover what's available and switch the soundscape when the GM activates a scene
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::Manager;
+
+use crate::remote::{self, RemoteAction, RemoteState};
+use crate::{AudioController, PresetInfo, SoundboardSound};
+
+// Bumped when the /api/vtt endpoints change incompatibly, so modules can warn about old app versions
+const VTT_API_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+pub struct VttPlaylist {
+    pub id: String,
+    pub name: String,
+}
+
+// Everything a module's settings screen needs in one request
+#[derive(Serialize)]
+pub struct VttManifest {
+    pub app: &'static str,
+    pub app_version: &'static str,
+    pub api_version: u32,
+    pub presets: Vec<PresetInfo>,
+    pub playlists: Vec<VttPlaylist>,
+    pub sounds: Vec<SoundboardSound>,
+    pub state: RemoteState,
+}
+
+// Sent when a scene is activated. Preset, playlist and sound accept an id or a name; with no preset
+// given, a preset named like the scene is used if there is one.
+#[derive(Deserialize)]
+pub struct SceneActivation {
+    pub scene: String,
+    #[serde(default)]
+    pub preset: Option<String>,
+    #[serde(default)]
+    pub playlist: Option<String>,
+    #[serde(default)]
+    pub sound: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SceneResult {
+    pub ok: bool,
+    pub preset_id: Option<String>,
+    pub playlist_id: Option<String>,
+    pub sound: Option<String>,
+}
+
+pub fn manifest(app: &tauri::AppHandle) -> Result<VttManifest, String> {
+    Ok(VttManifest {
+        app: "soundscapes",
+        app_version: env!("CARGO_PKG_VERSION"),
+        api_version: VTT_API_VERSION,
+        presets: remote::list_presets(app)?,
+        playlists: list_playlists(app),
+        sounds: remote::list_soundboard(app)?,
+        state: remote::get_state(app)?,
+    })
+}
+
+pub fn activate_scene(app: &tauri::AppHandle, activation: SceneActivation) -> Result<SceneResult, String> {
+    let preset_id = match &activation.preset {
+        Some(preset) => Some(find_preset(app, preset)?.ok_or_else(|| format!("Preset not found: {}", preset))?),
+        None => find_preset(app, &activation.scene)?,
+    };
+    let playlist_id = match &activation.playlist {
+        Some(playlist) => Some(find_playlist(app, playlist).ok_or_else(|| format!("Playlist not found: {}", playlist))?),
+        None => None,
+    };
+
+    if let Some(id) = &preset_id {
+        remote::apply_action(app, RemoteAction::ApplyPreset { id: id.clone() })?;
+    }
+    if let Some(id) = &playlist_id {
+        remote::apply_action(app, RemoteAction::PlayPlaylist { id: id.clone() })?;
+    }
+    if let Some(sound) = &activation.sound {
+        remote::apply_action(app, RemoteAction::PlaySoundboard { id: sound.clone() })?;
+    }
+
+    Ok(SceneResult { ok: true, preset_id, playlist_id, sound: activation.sound })
+}
+
+fn find_preset(app: &tauri::AppHandle, id_or_name: &str) -> Result<Option<String>, String> {
+    Ok(remote::list_presets(app)?
+        .into_iter()
+        .find(|p| p.id == id_or_name || p.name.eq_ignore_ascii_case(id_or_name))
+        .map(|p| p.id))
+}
+
+fn find_playlist(app: &tauri::AppHandle, id_or_name: &str) -> Option<String> {
+    list_playlists(app)
+        .into_iter()
+        .find(|p| p.id == id_or_name || p.name.eq_ignore_ascii_case(id_or_name))
+        .map(|p| p.id)
+}
+
+fn list_playlists(app: &tauri::AppHandle) -> Vec<VttPlaylist> {
+    let controller = app.state::<Arc<AudioController>>();
+    let mut playlists: Vec<VttPlaylist> = controller.playlists.lock()
+        .values()
+        .map(|p| VttPlaylist { id: p.id.clone(), name: p.name.clone() })
+        .collect();
+    playlists.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    playlists
+}
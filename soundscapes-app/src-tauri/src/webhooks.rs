@@ -0,0 +1,155 @@
+// Outgoing webhooks - POSTs a JSON payload when the preset changes, a track starts or the
+// schedule advances, so Node-RED, IFTTT or Home Assistant automations can follow the soundscape
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::remote;
+use crate::AudioController;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const EVENTS: [&str; 3] = ["preset_changed", "track_started", "schedule_advanced"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Webhook {
+    pub url: String,
+    // Events to send; empty sends all of them
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+impl Webhook {
+    fn wants(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event)
+    }
+}
+
+// Running webhook watcher; dropping it stops watching and delivering
+pub struct WebhookDispatcher {
+    stop: Arc<AtomicBool>,
+}
+
+impl WebhookDispatcher {
+    pub fn start(app: tauri::AppHandle, hooks: Vec<Webhook>) -> Result<Self, String> {
+        for hook in &hooks {
+            if !hook.url.starts_with("http://") && !hook.url.starts_with("https://") {
+                return Err(format!("Webhook URL must start with http:// or https://: {}", hook.url));
+            }
+            if let Some(event) = hook.events.iter().find(|e| !EVENTS.contains(&e.as_str())) {
+                return Err(format!("Unknown webhook event: {}", event));
+            }
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = stop.clone();
+        let (events_tx, events_rx) = channel::<(String, Value)>();
+
+        // Deliver on a separate thread so a slow endpoint doesn't delay noticing the next change
+        thread::spawn(move || {
+            for (event, payload) in events_rx {
+                for hook in hooks.iter().filter(|h| h.wants(&event)) {
+                    if let Err(e) = ureq::post(&hook.url).timeout(REQUEST_TIMEOUT).send_json(&payload) {
+                        log::warn!("Webhook {} failed: {}", hook.url, e);
+                    }
+                }
+            }
+        });
+
+        thread::spawn(move || {
+            // Start from the current state so enabling webhooks doesn't fire for what's already playing
+            let mut watched = WatchedState::read(&app);
+            while !stop_clone.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+                let current = WatchedState::read(&app);
+                for (event, data) in watched.changes(&app, &current) {
+                    let payload = json!({
+                        "event": event,
+                        "timestamp": chrono::Local::now().to_rfc3339(),
+                        "data": data,
+                    });
+                    if events_tx.send((event.to_string(), payload)).is_err() {
+                        return;
+                    }
+                }
+                watched = current;
+            }
+        });
+
+        Ok(Self { stop })
+    }
+}
+
+impl Drop for WebhookDispatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+struct WatchedState {
+    preset_id: Option<String>,
+    track_id: Option<String>,
+    // Schedule id and item index while a schedule is running
+    schedule_step: Option<(Option<String>, usize)>,
+}
+
+impl WatchedState {
+    fn read(app: &tauri::AppHandle) -> Self {
+        let controller = app.state::<Arc<AudioController>>();
+        let schedule_step = {
+            let scheduler = controller.scheduler_state.lock();
+            scheduler.is_playing.then(|| (scheduler.current_schedule_id.clone(), scheduler.current_item_index))
+        };
+        Self {
+            preset_id: controller.current_preset_id.lock().clone(),
+            track_id: controller.get_current_track().map(|t| t.id),
+            schedule_step,
+        }
+    }
+
+    fn changes(&self, app: &tauri::AppHandle, current: &WatchedState) -> Vec<(&'static str, Value)> {
+        let controller = app.state::<Arc<AudioController>>();
+        let mut events = Vec::new();
+
+        if current.preset_id != self.preset_id {
+            if let Some(id) = &current.preset_id {
+                let name = remote::list_presets(app).ok()
+                    .and_then(|presets| presets.into_iter().find(|p| &p.id == id))
+                    .map(|p| p.name);
+                events.push(("preset_changed", json!({ "id": id, "name": name })));
+            }
+        }
+
+        if current.track_id != self.track_id {
+            if let Some(track) = controller.get_current_track() {
+                events.push(("track_started", json!({
+                    "id": track.id,
+                    "title": track.title,
+                    "artist": track.artist,
+                    "album": track.album,
+                })));
+            }
+        }
+
+        if current.schedule_step != self.schedule_step {
+            if let Some((schedule_id, index)) = &current.schedule_step {
+                let scheduler = controller.scheduler_state.lock();
+                let item = scheduler.items.get(*index);
+                events.push(("schedule_advanced", json!({
+                    "schedule_id": schedule_id,
+                    "index": index,
+                    "preset_id": item.map(|i| &i.preset_id),
+                    "preset_name": item.map(|i| &i.preset_name),
+                    "duration_minutes": scheduler.current_duration,
+                })));
+            }
+        }
+
+        events
+    }
+}
@@ -1,30 +1,188 @@
-import { useEffect } from 'react';
+import { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { invoke } from '@tauri-apps/api/core';
 import './App.css';
 import { Sidebar } from './components/Sidebar/Sidebar';
 import { MainWindow } from './components/MainWindow/MainWindow';
 import { Visualizer } from './components/MainWindow/Visualizer';
 import { useSettingsStore } from './stores/settingsStore';
 import { useAudioStore } from './stores/audioStore';
+import { usePresetStore } from './stores/presetStore';
+import { useSchedulerStore } from './stores/schedulerStore';
+import { usePlaylistStore } from './stores/playlistStore';
 import { usePersistentPlayback } from './hooks/usePersistentPlayback';
+import { useSettingsReload } from './hooks/useSettingsReload';
+import { useStateEvents } from './hooks/useStateEvents';
+import { useAmbientStore } from './stores/ambientStore';
+import { useSoundboardStore } from './stores/soundboardStore';
+import { AppErrorEvent, DataChanged, UndoneEdit } from './types';
+import { errorMessage } from './utils/errors';
 
 function App() {
   const { loadSettings } = useSettingsStore();
   const { initAudio, loadVolumesFromSettings } = useAudioStore();
+  const [lastError, setLastError] = useState<AppErrorEvent | null>(null);
+  const [historyNotice, setHistoryNotice] = useState<string | null>(null);
+  const [panicMuted, setPanicMuted] = useState(false);
 
   // Persistent playback hooks - always running regardless of which windows are open
   usePersistentPlayback();
+  useStateEvents();
+
+  // Rescan folders moved by an outside edit of settings.json
+  useSettingsReload(({ settings, changed }) => {
+    if (changed.includes('music_folder_path')) usePlaylistStore.getState().loadAlbums(settings.music_folder_path);
+    if (changed.includes('ambient_folder_path')) useAmbientStore.getState().loadCategories(settings.ambient_folder_path);
+    if (changed.includes('soundboard_folder_path')) useSoundboardStore.getState().loadSounds(settings.soundboard_folder_path);
+  });
 
   useEffect(() => {
     const init = async () => {
       const settings = await loadSettings();
       if (settings) {
         loadVolumesFromSettings(settings);
+        // The chosen device may be unplugged; playback then stays on the system default
+        if (settings.output_device) {
+          invoke('set_output_device', { deviceId: settings.output_device }).catch(console.error);
+        }
+        if (settings.soundboard_output_device) {
+          invoke('set_soundboard_output_device', { deviceId: settings.soundboard_output_device }).catch(console.error);
+        }
       }
       await initAudio();
     };
     init();
   }, []);
 
+  // Mirror mute/volume changes made from the tray or remote APIs into the mixer UI
+  useEffect(() => {
+    const muteKeys = {
+      master: 'isMasterMuted',
+      music: 'isMusicMuted',
+      ambient: 'isAmbientMuted',
+      soundboard: 'isSoundboardMuted',
+    } as const;
+    const volumeKeys = {
+      master: 'masterVolume',
+      music: 'musicVolume',
+      ambient: 'ambientVolume',
+      soundboard: 'soundboardVolume',
+    } as const;
+    type Bus = keyof typeof muteKeys;
+
+    const unlistenPromise = listen<{ action: string; bus?: Bus; muted?: boolean; volume?: number }>('remote://action', (event) => {
+      const { action, bus, muted, volume } = event.payload;
+      if (!bus || !(bus in muteKeys)) return;
+      if (action === 'set_muted' && muted !== undefined) {
+        useAudioStore.setState({ [muteKeys[bus]]: muted });
+      } else if (action === 'set_volume' && volume !== undefined) {
+        useAudioStore.setState({ [volumeKeys[bus]]: Math.max(0, Math.min(100, volume)) });
+      }
+    });
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  // Spotify can rotate the refresh token; keep the saved one current so the next launch can sign in
+  useEffect(() => {
+    const unlistenPromise = listen<string>('external-player://spotify-token', (event) => {
+      const { settings, updateSetting } = useSettingsStore.getState();
+      if (!settings) return;
+      updateSetting('external_player', { ...settings.external_player, spotify_refresh_token: event.payload });
+    });
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  // Panic mute is toggled by a global shortcut the backend handles; show while everything is muted
+  useEffect(() => {
+    invoke<boolean>('get_panic_mute').then(setPanicMuted).catch(() => {});
+    const unlistenPromise = listen<{ muted: boolean }>('audio://panic-mute', (event) => {
+      setPanicMuted(event.payload.muted);
+    });
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  // Show backend failures (missing files, undecodable audio, ...) instead of a button that does nothing
+  useEffect(() => {
+    let timeout: ReturnType<typeof setTimeout> | undefined;
+    const unlistenPromise = listen<AppErrorEvent>('app://error', (event) => {
+      setLastError(event.payload);
+      clearTimeout(timeout);
+      timeout = setTimeout(() => setLastError(null), 5000);
+    });
+    return () => {
+      clearTimeout(timeout);
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  // Ctrl+Z / Ctrl+Shift+Z (or Ctrl+Y) undo and redo preset, playlist and soundboard edits;
+  // text fields keep their own undo
+  useEffect(() => {
+    let timeout: ReturnType<typeof setTimeout> | undefined;
+    const handleKeyDown = async (e: KeyboardEvent) => {
+      const target = e.target as HTMLElement | null;
+      if (target?.closest('input, textarea, select, [contenteditable="true"]')) return;
+      if (!(e.ctrlKey || e.metaKey) || e.altKey) return;
+      const key = e.key.toLowerCase();
+      const command = key === 'z' ? (e.shiftKey ? 'redo' : 'undo') : key === 'y' ? 'redo' : null;
+      if (!command) return;
+      e.preventDefault();
+      try {
+        const edit = await invoke<UndoneEdit | null>(command);
+        if (!edit) return;
+        setHistoryNotice(`${command === 'undo' ? 'Undid' : 'Redid'}: ${edit.label}`);
+      } catch (error) {
+        setHistoryNotice(`Couldn't ${command}: ${errorMessage(error)}`);
+      }
+      clearTimeout(timeout);
+      timeout = setTimeout(() => setHistoryNotice(null), 3000);
+    };
+    window.addEventListener('keydown', handleKeyDown);
+    return () => {
+      clearTimeout(timeout);
+      window.removeEventListener('keydown', handleKeyDown);
+    };
+  }, []);
+
+  // Reload data files changed by another machine syncing the app data folder (or by undo/redo)
+  useEffect(() => {
+    const unlistenPromise = listen<DataChanged>('data://changed', (event) => {
+      switch (event.payload.kind) {
+        case 'presets':
+          usePresetStore.getState().loadPresets();
+          break;
+        case 'schedules':
+          useSchedulerStore.getState().loadSchedules();
+          break;
+        case 'playlists':
+        case 'favorites': {
+          const folder = useSettingsStore.getState().settings?.music_folder_path;
+          if (folder) usePlaylistStore.getState().loadAlbums(folder);
+          break;
+        }
+        case 'soundboard': {
+          const folder = useSettingsStore.getState().settings?.soundboard_folder_path;
+          if (folder) useSoundboardStore.getState().loadSounds(folder);
+          break;
+        }
+        case 'ambient': {
+          const folder = useSettingsStore.getState().settings?.ambient_folder_path;
+          if (folder) useAmbientStore.getState().loadCategories(folder);
+          break;
+        }
+      }
+    });
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
   return (
     <div className="relative h-screen overflow-hidden bg-black">
       {/* Full-window shader background */}
@@ -39,6 +197,28 @@ function App() {
           <MainWindow />
         </div>
       </div>
+
+      {lastError && (
+        <div className="fixed bottom-4 left-1/2 transform -translate-x-1/2 max-w-lg bg-bg-secondary border border-border rounded-lg px-4 py-2 shadow-lg z-50">
+          <p className="text-sm text-accent-red">{lastError.message}</p>
+          {lastError.context && <p className="text-xs text-text-secondary truncate">{lastError.context}</p>}
+        </div>
+      )}
+
+      {panicMuted && (
+        <button
+          onClick={() => invoke<boolean>('toggle_panic_mute').then(setPanicMuted)}
+          className="fixed top-4 left-1/2 transform -translate-x-1/2 bg-bg-secondary border border-accent-red rounded-lg px-4 py-2 shadow-lg z-50 text-sm text-accent-red"
+        >
+          All audio muted - click or press the shortcut again to restore
+        </button>
+      )}
+
+      {historyNotice && !lastError && (
+        <div className="fixed bottom-4 left-1/2 transform -translate-x-1/2 max-w-lg bg-bg-secondary border border-border rounded-lg px-4 py-2 shadow-lg z-50">
+          <p className="text-sm text-text-primary">{historyNotice}</p>
+        </div>
+      )}
     </div>
   );
 }
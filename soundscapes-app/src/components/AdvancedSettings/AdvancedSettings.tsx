@@ -1,19 +1,30 @@
 import React, { useEffect, useState } from 'react';
-import { FolderOpen, RefreshCw, RotateCcw } from 'lucide-react';
+import { Circle, FolderOpen, Plus, RefreshCw, RotateCcw, Square, X } from 'lucide-react';
 import { useSettingsStore } from '../../stores/settingsStore';
 import { usePlaylistStore } from '../../stores/playlistStore';
 import { useAmbientStore } from '../../stores/ambientStore';
 import { useSoundboardStore } from '../../stores/soundboardStore';
-import { open } from '@tauri-apps/plugin-dialog';
+import { usePresetStore } from '../../stores/presetStore';
+import { open, save } from '@tauri-apps/plugin-dialog';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { getVisualizationList } from '../../visualizations';
+import { errorMessage } from '../../utils/errors';
+import { AppSettings, BackgroundAudioConfig, ExternalPlayerConfig, ExternalPlayerStatus, MicrophoneConfig, ObsSceneMapping, RecordingConfig, RecordingFile, RecordingStatus, ReplayConfig, ReplaySaved, StreamingConfig, StreamingStatus, SyncConfig, Webhook, WebhookEvent } from '../../types';
 
 interface AudioDevice {
   id: string;
   name: string;
   is_default: boolean;
+  channels: number;
 }
 
+// Speaker names for the common layouts, by channel index
+const channelName = (channels: number, channel: number) => {
+  const names = channels === 2 ? ['Left', 'Right'] : channels >= 6 ? ['Front Left', 'Front Right', 'Center', 'LFE', 'Rear Left', 'Rear Right', 'Side Left', 'Side Right'] : [];
+  return names[channel] ?? `Channel ${channel + 1}`;
+};
+
 interface FolderSettingProps {
   label: string;
   path: string;
@@ -59,13 +70,38 @@ const FolderSetting: React.FC<FolderSettingProps> = ({ label, path, onChangePath
   );
 };
 
+// Recording length as h:mm:ss
+const formatElapsed = (secs: number): string => {
+  const total = Math.floor(secs);
+  const h = Math.floor(total / 3600);
+  const m = Math.floor((total % 3600) / 60);
+  const s = total % 60;
+  return `${h}:${m.toString().padStart(2, '0')}:${s.toString().padStart(2, '0')}`;
+};
+
 export const AdvancedSettings: React.FC = () => {
   const { settings, updateSetting, loadSettings } = useSettingsStore();
-  const { loadAlbums } = usePlaylistStore();
+  const { loadAlbums, playlists } = usePlaylistStore();
+  const { presets, loadPresets } = usePresetStore();
   const { loadCategories } = useAmbientStore();
   const { loadSounds } = useSoundboardStore();
   const [outputDevices, setOutputDevices] = useState<AudioDevice[]>([]);
   const [selectedDevice, setSelectedDevice] = useState<string>('');
+  const [testChannel, setTestChannel] = useState(0);
+  const [testToneError, setTestToneError] = useState<string | null>(null);
+  const [streamingStatus, setStreamingStatus] = useState<StreamingStatus | null>(null);
+  const [recordingStatus, setRecordingStatus] = useState<RecordingStatus | null>(null);
+  const [lastRecording, setLastRecording] = useState<RecordingStatus | null>(null);
+  const [recordings, setRecordings] = useState<RecordingFile[]>([]);
+  const [logTail, setLogTail] = useState<string[] | null>(null);
+  const [replayBuffered, setReplayBuffered] = useState<number | null>(null);
+  const [replayMessage, setReplayMessage] = useState<string | null>(null);
+  const [externalStatus, setExternalStatus] = useState<ExternalPlayerStatus | null>(null);
+  const [realtimeError, setRealtimeError] = useState<string | null>(null);
+  const [outputDeviceError, setOutputDeviceError] = useState<string | null>(null);
+  const [soundboardDeviceError, setSoundboardDeviceError] = useState<string | null>(null);
+  const [shortcutError, setShortcutError] = useState<string | null>(null);
+  const [microphoneError, setMicrophoneError] = useState<string | null>(null);
 
   useEffect(() => {
     const loadDevices = async () => {
@@ -73,7 +109,10 @@ export const AdvancedSettings: React.FC = () => {
         const devices = await invoke<AudioDevice[]>('get_output_devices');
         setOutputDevices(devices);
         const defaultDevice = devices.find(d => d.is_default);
-        if (defaultDevice) {
+        const savedDevice = useSettingsStore.getState().settings?.output_device;
+        if (savedDevice && devices.some(d => d.id === savedDevice)) {
+          setSelectedDevice(savedDevice);
+        } else if (defaultDevice) {
           setSelectedDevice(defaultDevice.id);
         }
       } catch (error) {
@@ -90,6 +129,384 @@ export const AdvancedSettings: React.FC = () => {
     }
   }, [settings?.music_crossfade_duration]);
 
+  // Sync manual-skip crossfade duration to backend when settings load
+  useEffect(() => {
+    if (settings?.music_skip_crossfade_duration !== undefined) {
+      invoke('set_skip_crossfade_duration', { duration: settings.music_skip_crossfade_duration });
+    }
+  }, [settings?.music_skip_crossfade_duration]);
+
+  // Sync preset crossfade duration to backend when settings load
+  useEffect(() => {
+    if (settings?.ambient_preset_crossfade_duration !== undefined) {
+      invoke('set_preset_crossfade_duration', { duration: settings.ambient_preset_crossfade_duration });
+    }
+  }, [settings?.ambient_preset_crossfade_duration]);
+
+  // Sync loudness matching toggle to backend when settings load
+  useEffect(() => {
+    if (settings?.music_loudness_matching !== undefined) {
+      invoke('set_loudness_matching', { enabled: settings.music_loudness_matching });
+    }
+  }, [settings?.music_loudness_matching]);
+
+  // Sync skip-on-silence to backend when settings load
+  useEffect(() => {
+    if (settings?.music_skip_on_silence !== undefined) {
+      invoke('set_skip_on_silence', { enabled: settings.music_skip_on_silence });
+    }
+  }, [settings?.music_skip_on_silence]);
+
+  // Sync audio thread tick interval to backend when settings load
+  useEffect(() => {
+    if (settings?.audio_tick_ms !== undefined) {
+      invoke('set_audio_tick_interval', { ms: settings.audio_tick_ms });
+    }
+  }, [settings?.audio_tick_ms]);
+
+  // Sync the size above which ambient files are memory-mapped to backend when settings load
+  useEffect(() => {
+    if (settings?.ambient_mmap_threshold_mb !== undefined) {
+      invoke('set_mmap_threshold', { mb: settings.ambient_mmap_threshold_mb });
+    }
+  }, [settings?.ambient_mmap_threshold_mb]);
+
+  // Sync audio thread real-time priority to backend when settings load; the OS may refuse it
+  useEffect(() => {
+    if (settings?.audio_realtime_priority !== undefined) {
+      invoke('set_realtime_priority', { enabled: settings.audio_realtime_priority })
+        .then(() => setRealtimeError(null))
+        .catch((error) => setRealtimeError(errorMessage(error)));
+    }
+  }, [settings?.audio_realtime_priority]);
+
+  // Sync the output device to backend when settings load; everything playing moves to it
+  useEffect(() => {
+    if (settings?.output_device !== undefined) {
+      invoke('set_output_device', { deviceId: settings.output_device })
+        .then(() => setOutputDeviceError(null))
+        .catch((error) => setOutputDeviceError(errorMessage(error)));
+    }
+  }, [settings?.output_device]);
+
+  // Sync the soundboard's own output device to backend when settings load
+  useEffect(() => {
+    if (settings?.soundboard_output_device !== undefined) {
+      invoke('set_soundboard_output_device', { deviceId: settings.soundboard_output_device })
+        .then(() => setSoundboardDeviceError(null))
+        .catch((error) => setSoundboardDeviceError(errorMessage(error)));
+    }
+  }, [settings?.soundboard_output_device]);
+
+  // Sync night mode to backend when settings load
+  useEffect(() => {
+    if (settings?.night_mode !== undefined) {
+      invoke('set_night_mode', { enabled: settings.night_mode });
+    }
+  }, [settings?.night_mode]);
+
+  // Sync the background audio policy to backend, which follows the window events
+  useEffect(() => {
+    if (settings?.background_audio) {
+      invoke('set_background_audio', { config: settings.background_audio });
+    }
+  }, [settings?.background_audio]);
+
+  const updateBackgroundAudio = (changes: Partial<BackgroundAudioConfig>) => {
+    if (!settings) return;
+    updateSetting('background_audio', { ...settings.background_audio, ...changes });
+  };
+
+  // Sync the panic mute shortcut to backend, which registers it globally; a taken or malformed
+  // shortcut is refused
+  useEffect(() => {
+    if (settings?.panic_mute_shortcut !== undefined) {
+      invoke('set_panic_mute_shortcut', { shortcut: settings.panic_mute_shortcut })
+        .then(() => setShortcutError(null))
+        .catch((error) => setShortcutError(errorMessage(error)));
+    }
+  }, [settings?.panic_mute_shortcut]);
+
+  // Sync visualizer frequency scale to backend when settings load
+  useEffect(() => {
+    if (settings?.visualization_frequency_scale !== undefined) {
+      invoke('set_frequency_scale', { scale: settings.visualization_frequency_scale });
+    }
+  }, [settings?.visualization_frequency_scale]);
+
+  // Sync visualizer event rate to backend when settings load
+  useEffect(() => {
+    if (settings?.visualization_event_rate !== undefined) {
+      invoke('set_fft_event_rate', { rateHz: settings.visualization_event_rate });
+    }
+  }, [settings?.visualization_event_rate]);
+
+  // Sync visualizer smoothing to backend when settings load
+  useEffect(() => {
+    if (settings?.visualization_attack !== undefined && settings?.visualization_decay !== undefined) {
+      invoke('set_fft_smoothing', { attack: settings.visualization_attack, decay: settings.visualization_decay });
+    }
+  }, [settings?.visualization_attack, settings?.visualization_decay]);
+
+  // Sync frequency band triggers to backend when settings load
+  useEffect(() => {
+    if (settings?.fft_triggers !== undefined) {
+      invoke('set_fft_triggers', { triggers: settings.fft_triggers });
+    }
+  }, [settings?.fft_triggers]);
+
+  // Start/stop the remote control API when its settings change
+  useEffect(() => {
+    if (settings?.remote_api_enabled === undefined) return;
+    invoke('set_remote_api', {
+      enabled: settings.remote_api_enabled,
+      port: settings.remote_api_port,
+      token: settings.remote_api_token,
+      allowLan: settings.remote_api_allow_lan,
+    }).catch(console.error);
+  }, [settings?.remote_api_enabled, settings?.remote_api_port, settings?.remote_api_token, settings?.remote_api_allow_lan]);
+
+  // Start/stop the OSC server when its settings change
+  useEffect(() => {
+    if (settings?.osc_enabled === undefined) return;
+    invoke('set_osc', {
+      enabled: settings.osc_enabled,
+      port: settings.osc_port,
+      feedbackHost: settings.osc_feedback_host,
+      feedbackPort: settings.osc_feedback_port,
+      allowLan: settings.remote_api_allow_lan,
+    }).catch(console.error);
+  }, [settings?.osc_enabled, settings?.osc_port, settings?.osc_feedback_host, settings?.osc_feedback_port, settings?.remote_api_allow_lan]);
+
+  // Start/stop the Stream Deck plugin socket when its settings change
+  useEffect(() => {
+    if (settings?.streamdeck_enabled === undefined) return;
+    invoke('set_streamdeck', {
+      enabled: settings.streamdeck_enabled,
+      port: settings.streamdeck_port,
+    }).catch(console.error);
+  }, [settings?.streamdeck_enabled, settings?.streamdeck_port]);
+
+  // Connect to / disconnect from OBS when its settings change
+  useEffect(() => {
+    if (settings?.obs_enabled === undefined) return;
+    invoke('set_obs_integration', {
+      enabled: settings.obs_enabled,
+      url: settings.obs_url,
+      password: settings.obs_password,
+      mappings: settings.obs_scene_mappings,
+    }).catch(console.error);
+  }, [settings?.obs_enabled, settings?.obs_url, settings?.obs_password, settings?.obs_scene_mappings]);
+
+  useEffect(() => {
+    loadPresets();
+  }, [loadPresets]);
+
+  // Start/stop the Icecast/Shoutcast stream when its settings change
+  useEffect(() => {
+    if (!settings?.streaming) return;
+    invoke('set_streaming', { config: settings.streaming }).catch(console.error);
+  }, [settings?.streaming]);
+
+  // Poll the stream's connection state while it's enabled
+  useEffect(() => {
+    if (!settings?.streaming?.enabled) {
+      setStreamingStatus(null);
+      return;
+    }
+    const poll = () => invoke<StreamingStatus | null>('get_streaming_status').then(setStreamingStatus).catch(console.error);
+    poll();
+    const interval = setInterval(poll, 2000);
+    return () => clearInterval(interval);
+  }, [settings?.streaming?.enabled]);
+
+  const updateStreaming = (changes: Partial<StreamingConfig>) => {
+    if (!settings) return;
+    updateSetting('streaming', { ...settings.streaming, ...changes });
+  };
+
+  // Poll recording progress (a recording may also have been started before this window opened)
+  useEffect(() => {
+    const poll = () => invoke<RecordingStatus | null>('get_recording_status').then(setRecordingStatus).catch(console.error);
+    poll();
+    const interval = setInterval(poll, 2000);
+    return () => clearInterval(interval);
+  }, []);
+
+  const loadRecordings = () => {
+    invoke<RecordingFile[]>('list_recordings').then(setRecordings).catch(console.error);
+  };
+
+  useEffect(() => {
+    loadRecordings();
+  }, [settings?.recording?.folder]);
+
+  const updateRecording = (changes: Partial<RecordingConfig>) => {
+    if (!settings) return;
+    updateSetting('recording', { ...settings.recording, ...changes });
+  };
+
+  const toggleRecording = async () => {
+    if (!settings) return;
+    try {
+      if (recordingStatus) {
+        setLastRecording(await invoke<RecordingStatus | null>('stop_recording'));
+        setRecordingStatus(null);
+        loadRecordings();
+      } else {
+        setLastRecording(null);
+        setRecordingStatus(await invoke<RecordingStatus>('start_recording', { config: settings.recording }));
+      }
+    } catch (error) {
+      console.error('Recording failed:', error);
+      setLastRecording({ files: [], elapsed_secs: 0, error: errorMessage(error) });
+    }
+  };
+
+  // Keep the replay buffer in line with its settings
+  useEffect(() => {
+    if (!settings?.replay_buffer) return;
+    invoke('set_replay_buffer', { config: settings.replay_buffer }).catch(console.error);
+  }, [settings?.replay_buffer]);
+
+  useEffect(() => {
+    if (!settings?.replay_buffer?.enabled) {
+      setReplayBuffered(null);
+      return;
+    }
+    const poll = () => invoke<number | null>('get_replay_buffer_status').then(setReplayBuffered).catch(console.error);
+    poll();
+    const interval = setInterval(poll, 2000);
+    return () => clearInterval(interval);
+  }, [settings?.replay_buffer?.enabled]);
+
+  useEffect(() => {
+    const unlistenPromise = listen<ReplaySaved>('replay://saved', (event) => {
+      const { path, duration_secs, error } = event.payload;
+      setReplayMessage(error ? `Save failed: ${error}` : `Saved ${formatElapsed(duration_secs)} to ${path}`);
+    });
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  // Keep microphone monitoring in line with its settings; no input device is reported here
+  useEffect(() => {
+    if (!settings?.microphone) return;
+    invoke('set_microphone', { config: settings.microphone })
+      .then(() => setMicrophoneError(null))
+      .catch((error) => setMicrophoneError(errorMessage(error)));
+  }, [settings?.microphone]);
+
+  const updateMicrophone = (changes: Partial<MicrophoneConfig>) => {
+    if (!settings) return;
+    updateSetting('microphone', { ...settings.microphone, ...changes });
+  };
+
+  const updateReplay = (changes: Partial<ReplayConfig>) => {
+    if (!settings) return;
+    updateSetting('replay_buffer', { ...settings.replay_buffer, ...changes });
+  };
+
+  const saveReplay = async () => {
+    if (!settings) return;
+    const path = await save({
+      defaultPath: `Soundscapes replay.${settings.recording.format}`,
+      filters: [{ name: 'Audio', extensions: ['wav', 'mp3', 'ogg', 'opus'] }],
+    });
+    if (!path) return;
+    try {
+      setReplayMessage('Saving...');
+      await invoke('save_last', { minutes: settings.replay_buffer.minutes, path });
+    } catch (error) {
+      setReplayMessage(`Save failed: ${errorMessage(error)}`);
+    }
+  };
+
+  // Hand the music bus to Spotify/MPD (or take it back) when its settings change
+  useEffect(() => {
+    if (!settings?.external_player) return;
+    invoke('set_external_player', { config: settings.external_player }).catch(console.error);
+  }, [settings?.external_player]);
+
+  // Poll the external player's connection and now-playing info while it's enabled
+  useEffect(() => {
+    if (!settings?.external_player?.enabled) {
+      setExternalStatus(null);
+      return;
+    }
+    const poll = () => invoke<ExternalPlayerStatus | null>('get_external_player_status').then(setExternalStatus).catch(console.error);
+    poll();
+    const interval = setInterval(poll, 2000);
+    return () => clearInterval(interval);
+  }, [settings?.external_player?.enabled]);
+
+  const updateExternalPlayer = (changes: Partial<ExternalPlayerConfig>) => {
+    if (!settings) return;
+    updateSetting('external_player', { ...settings.external_player, ...changes });
+  };
+
+  // Restart the webhook watcher when the hook list changes (rows still being filled in are skipped)
+  useEffect(() => {
+    if (!settings?.webhooks) return;
+    invoke('set_webhooks', { webhooks: settings.webhooks.filter((w) => w.url.trim()) }).catch(console.error);
+  }, [settings?.webhooks]);
+
+  useEffect(() => {
+    if (!settings?.data_sync) return;
+    invoke('set_data_sync', { config: settings.data_sync }).catch(console.error);
+  }, [settings?.data_sync]);
+
+  useEffect(() => {
+    if (!settings?.log_level) return;
+    invoke('set_log_level', { level: settings.log_level }).catch(console.error);
+  }, [settings?.log_level]);
+
+  const showLog = async () => {
+    try {
+      setLogTail(await invoke<string[]>('get_log_tail', { lines: 200 }));
+    } catch (error) {
+      setLogTail([`Failed to read the log: ${errorMessage(error)}`]);
+    }
+  };
+
+  // Snapshot of the audio thread (sinks, fades, cache, scheduler) for stuck fades and ghost sounds
+  const copyEngineState = async () => {
+    try {
+      const state = await invoke<unknown>('dump_debug_state');
+      await navigator.clipboard.writeText(JSON.stringify(state, null, 2));
+    } catch (error) {
+      console.error('Failed to dump the engine state:', errorMessage(error));
+    }
+  };
+
+  // Loop timing, queue depth, decode times and cache hit rate since launch (or the last reset)
+  const copyEngineStats = async () => {
+    try {
+      const stats = await invoke<unknown>('get_engine_stats');
+      await navigator.clipboard.writeText(JSON.stringify(stats, null, 2));
+    } catch (error) {
+      console.error('Failed to read the engine stats:', errorMessage(error));
+    }
+  };
+
+  const updateWebhook = (index: number, changes: Partial<Webhook>) => {
+    if (!settings) return;
+    updateSetting('webhooks', settings.webhooks.map((w, i) => (i === index ? { ...w, ...changes } : w)));
+  };
+
+  const toggleWebhookEvent = (index: number, event: WebhookEvent) => {
+    if (!settings) return;
+    const events = settings.webhooks[index].events;
+    updateWebhook(index, { events: events.includes(event) ? events.filter((e) => e !== event) : [...events, event] });
+  };
+
+  const updateObsMapping = (index: number, changes: Partial<ObsSceneMapping>) => {
+    if (!settings) return;
+    const mappings = settings.obs_scene_mappings.map((m, i) => (i === index ? { ...m, ...changes } : m));
+    updateSetting('obs_scene_mappings', mappings);
+  };
+
   if (!settings) {
     return (
       <div className="flex items-center justify-center h-full">
@@ -163,6 +580,18 @@ export const AdvancedSettings: React.FC = () => {
                 {getVisualizationList().find(v => v.id === (settings.visualization_type || 'orb'))?.description}
               </p>
             </div>
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Frequency Scale</label>
+              <select
+                value={settings.visualization_frequency_scale || 'linear'}
+                onChange={(e) => updateSetting('visualization_frequency_scale', e.target.value as AppSettings['visualization_frequency_scale'])}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+              >
+                <option value="linear">Linear</option>
+                <option value="log">Logarithmic</option>
+                <option value="mel">Mel</option>
+              </select>
+            </div>
           </div>
         </div>
 
@@ -173,7 +602,13 @@ export const AdvancedSettings: React.FC = () => {
               <label className="text-sm text-text-secondary">Output Device</label>
               <select
                 value={selectedDevice}
-                onChange={(e) => setSelectedDevice(e.target.value)}
+                onChange={(e) => {
+                  const device = outputDevices.find((d) => d.id === e.target.value);
+                  setSelectedDevice(e.target.value);
+                  setTestChannel(0);
+                  // The default device is saved as '' so the app follows the system default
+                  updateSetting('output_device', device?.is_default ? '' : e.target.value);
+                }}
                 className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
               >
                 {outputDevices.map((device) => (
@@ -182,6 +617,55 @@ export const AdvancedSettings: React.FC = () => {
                   </option>
                 ))}
               </select>
+              <div className="flex gap-2">
+                <select
+                  value={testChannel}
+                  onChange={(e) => setTestChannel(Number(e.target.value))}
+                  className="flex-1 px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                >
+                  {Array.from({ length: outputDevices.find((d) => d.id === selectedDevice)?.channels ?? 2 }, (_, channel) => (
+                    <option key={channel} value={channel}>
+                      {channelName(outputDevices.find((d) => d.id === selectedDevice)?.channels ?? 2, channel)}
+                    </option>
+                  ))}
+                </select>
+                <button
+                  onClick={async () => {
+                    try {
+                      await invoke('play_test_tone', { deviceId: selectedDevice, channel: testChannel });
+                      setTestToneError(null);
+                    } catch (error) {
+                      setTestToneError(errorMessage(error));
+                    }
+                  }}
+                  disabled={!selectedDevice}
+                  className="px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border hover:border-accent-purple disabled:opacity-50"
+                >
+                  Test Tone
+                </button>
+              </div>
+              {outputDeviceError && <p className="text-xs text-accent-red">{outputDeviceError}</p>}
+              {testToneError && <p className="text-xs text-accent-red">{testToneError}</p>}
+            </div>
+            
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Soundboard Output Device</label>
+              <select
+                value={settings.soundboard_output_device ?? ''}
+                onChange={(e) => updateSetting('soundboard_output_device', e.target.value)}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+              >
+                <option value="">Same as Output Device</option>
+                {outputDevices.map((device) => (
+                  <option key={device.id} value={device.id}>
+                    {device.name}{device.is_default ? ' (Default)' : ''}
+                  </option>
+                ))}
+              </select>
+              <p className="text-xs text-text-secondary">
+                Sends soundboard sounds to another device, like a virtual cable for a stream, while music and ambient sounds stay on the output device.
+              </p>
+              {soundboardDeviceError && <p className="text-xs text-accent-red">{soundboardDeviceError}</p>}
             </div>
             
             <div>
@@ -208,6 +692,54 @@ export const AdvancedSettings: React.FC = () => {
               </div>
             </div>
             
+            <div>
+              <div className="flex justify-between text-sm mb-2">
+                <span className="text-text-secondary">Skip Crossfade Duration</span>
+                <span className="text-text-primary">{settings.music_skip_crossfade_duration}s</span>
+              </div>
+              <div style={{ position: 'relative', height: '24px' }}>
+                <div style={{ position: 'absolute', top: '8px', left: 0, right: 0, height: '8px', borderRadius: '4px', backgroundColor: '#313131' }} />
+                <div style={{ position: 'absolute', top: '8px', left: 0, height: '8px', borderRadius: '4px', background: 'linear-gradient(to right, #12e6c8, #a287f4)', width: `${(settings.music_skip_crossfade_duration / 2) * 100}%` }} />
+                <input
+                  type="range"
+                  min="0"
+                  max="2"
+                  step="0.1"
+                  value={settings.music_skip_crossfade_duration}
+                  onChange={(e) => {
+                    const duration = Number(e.target.value);
+                    updateSetting('music_skip_crossfade_duration', duration);
+                    invoke('set_skip_crossfade_duration', { duration });
+                  }}
+                  style={{ position: 'relative', width: '100%', height: '24px', background: 'transparent', cursor: 'pointer' }}
+                />
+              </div>
+            </div>
+            
+            <div>
+              <div className="flex justify-between text-sm mb-2">
+                <span className="text-text-secondary">Preset Crossfade Duration</span>
+                <span className="text-text-primary">{settings.ambient_preset_crossfade_duration}s</span>
+              </div>
+              <div style={{ position: 'relative', height: '24px' }}>
+                <div style={{ position: 'absolute', top: '8px', left: 0, right: 0, height: '8px', borderRadius: '4px', backgroundColor: '#313131' }} />
+                <div style={{ position: 'absolute', top: '8px', left: 0, height: '8px', borderRadius: '4px', background: 'linear-gradient(to right, #12e6c8, #a287f4)', width: `${(settings.ambient_preset_crossfade_duration / 10) * 100}%` }} />
+                <input
+                  type="range"
+                  min="0.2"
+                  max="10"
+                  step="0.1"
+                  value={settings.ambient_preset_crossfade_duration}
+                  onChange={(e) => {
+                    const duration = Number(e.target.value);
+                    updateSetting('ambient_preset_crossfade_duration', duration);
+                    invoke('set_preset_crossfade_duration', { duration });
+                  }}
+                  style={{ position: 'relative', width: '100%', height: '24px', background: 'transparent', cursor: 'pointer' }}
+                />
+              </div>
+            </div>
+            
             <div>
               <div className="flex justify-between text-sm mb-2">
                 <span className="text-text-secondary">Soundboard Duck Amount</span>
@@ -226,6 +758,800 @@ export const AdvancedSettings: React.FC = () => {
                 />
               </div>
             </div>
+            
+            <div className="space-y-1">
+              <label className="flex items-center gap-2 text-sm text-text-secondary">
+                <input
+                  type="checkbox"
+                  checked={settings.audio_realtime_priority}
+                  onChange={(e) => updateSetting('audio_realtime_priority', e.target.checked)}
+                />
+                Real-time priority for the audio engine (fewer dropouts under load)
+              </label>
+              {realtimeError && <p className="text-xs text-accent-red">{realtimeError}</p>}
+            </div>
+            
+            <label className="flex items-center gap-2 text-sm text-text-secondary">
+              <input
+                type="checkbox"
+                checked={settings.night_mode}
+                onChange={(e) => updateSetting('night_mode', e.target.checked)}
+              />
+              Night mode (compresses loud moments and caps peaks)
+            </label>
+            
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">In the background (any open window, like the visualizer, keeps it in the foreground)</label>
+              <div className="flex gap-2">
+                <select
+                  value={settings.background_audio.policy}
+                  onChange={(e) => updateBackgroundAudio({ policy: e.target.value as BackgroundAudioConfig['policy'] })}
+                  className="flex-1 px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                >
+                  <option value="keep_playing">Keep playing</option>
+                  <option value="duck">Turn down</option>
+                  <option value="pause">Silence and pause music</option>
+                </select>
+                <select
+                  value={settings.background_audio.trigger}
+                  onChange={(e) => updateBackgroundAudio({ trigger: e.target.value as BackgroundAudioConfig['trigger'] })}
+                  disabled={settings.background_audio.policy === 'keep_playing'}
+                  className="flex-1 px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple disabled:opacity-50"
+                >
+                  <option value="minimized">When every window is minimized</option>
+                  <option value="unfocused">When no window has focus</option>
+                </select>
+              </div>
+            </div>
+            
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Panic mute shortcut (mutes everything until pressed again)</label>
+              <input
+                type="text"
+                value={settings.panic_mute_shortcut}
+                placeholder="e.g. CmdOrCtrl+Alt+M, empty for none"
+                onChange={(e) => updateSetting('panic_mute_shortcut', e.target.value)}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+              />
+              {shortcutError && <p className="text-xs text-accent-red">{shortcutError}</p>}
+            </div>
+          </div>
+        </div>
+
+        <div>
+          <h3 className="text-sm font-medium text-text-primary mb-4">Remote Control</h3>
+          <div className="space-y-4">
+            <label className="flex items-center gap-2 text-sm text-text-secondary">
+              <input
+                type="checkbox"
+                checked={settings.remote_api_enabled}
+                onChange={(e) => updateSetting('remote_api_enabled', e.target.checked)}
+              />
+              Enable remote control API
+            </label>
+            <label className="flex items-center gap-2 text-sm text-text-secondary">
+              <input
+                type="checkbox"
+                checked={settings.remote_api_allow_lan}
+                onChange={(e) => updateSetting('remote_api_allow_lan', e.target.checked)}
+              />
+              Allow devices on the local network
+            </label>
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Port</label>
+              <input
+                type="number"
+                min="1024"
+                max="65535"
+                value={settings.remote_api_port}
+                onChange={(e) => updateSetting('remote_api_port', Number(e.target.value))}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+              />
+            </div>
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Access Token</label>
+              <input
+                type="text"
+                readOnly
+                value={settings.remote_api_token}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm font-mono border border-border focus:outline-none"
+              />
+            </div>
+          </div>
+        </div>
+
+        <div>
+          <h3 className="text-sm font-medium text-text-primary mb-4">OSC</h3>
+          <div className="space-y-4">
+            <label className="flex items-center gap-2 text-sm text-text-secondary">
+              <input
+                type="checkbox"
+                checked={settings.osc_enabled}
+                onChange={(e) => updateSetting('osc_enabled', e.target.checked)}
+              />
+              Listen for OSC messages (/soundscapes/...)
+            </label>
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Listen Port</label>
+              <input
+                type="number"
+                min="1024"
+                max="65535"
+                value={settings.osc_port}
+                onChange={(e) => updateSetting('osc_port', Number(e.target.value))}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+              />
+            </div>
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Feedback Host (optional)</label>
+              <input
+                type="text"
+                placeholder="e.g. 192.168.1.20"
+                value={settings.osc_feedback_host}
+                onChange={(e) => updateSetting('osc_feedback_host', e.target.value)}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+              />
+            </div>
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Feedback Port</label>
+              <input
+                type="number"
+                min="1"
+                max="65535"
+                value={settings.osc_feedback_port}
+                onChange={(e) => updateSetting('osc_feedback_port', Number(e.target.value))}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+              />
+            </div>
+          </div>
+        </div>
+
+        <div>
+          <h3 className="text-sm font-medium text-text-primary mb-4">Stream Deck</h3>
+          <div className="space-y-4">
+            <label className="flex items-center gap-2 text-sm text-text-secondary">
+              <input
+                type="checkbox"
+                checked={settings.streamdeck_enabled}
+                onChange={(e) => updateSetting('streamdeck_enabled', e.target.checked)}
+              />
+              Enable Stream Deck plugin connection
+            </label>
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Port</label>
+              <input
+                type="number"
+                min="1024"
+                max="65535"
+                value={settings.streamdeck_port}
+                onChange={(e) => updateSetting('streamdeck_port', Number(e.target.value))}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+              />
+            </div>
+          </div>
+        </div>
+
+        <div>
+          <h3 className="text-sm font-medium text-text-primary mb-4">OBS</h3>
+          <div className="space-y-4">
+            <label className="flex items-center gap-2 text-sm text-text-secondary">
+              <input
+                type="checkbox"
+                checked={settings.obs_enabled}
+                onChange={(e) => updateSetting('obs_enabled', e.target.checked)}
+              />
+              Switch presets when the OBS scene changes
+            </label>
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">obs-websocket Address</label>
+              <input
+                type="text"
+                value={settings.obs_url}
+                onChange={(e) => updateSetting('obs_url', e.target.value)}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+              />
+            </div>
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Password</label>
+              <input
+                type="password"
+                value={settings.obs_password}
+                onChange={(e) => updateSetting('obs_password', e.target.value)}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+              />
+            </div>
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Scene Mappings</label>
+              {settings.obs_scene_mappings.map((mapping, index) => (
+                <div key={index} className="flex gap-2">
+                  <input
+                    type="text"
+                    placeholder="Scene name"
+                    value={mapping.scene}
+                    onChange={(e) => updateObsMapping(index, { scene: e.target.value })}
+                    className="flex-1 px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                  />
+                  <select
+                    value={mapping.preset_id ?? ''}
+                    onChange={(e) => updateObsMapping(index, { preset_id: e.target.value || null })}
+                    className="flex-1 px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                  >
+                    <option value="">No preset</option>
+                    {presets.map((p) => (
+                      <option key={p.id} value={p.id}>{p.name}</option>
+                    ))}
+                  </select>
+                  <select
+                    value={mapping.playlist_id ?? ''}
+                    onChange={(e) => updateObsMapping(index, { playlist_id: e.target.value || null })}
+                    className="flex-1 px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                  >
+                    <option value="">No playlist</option>
+                    {playlists.map((p) => (
+                      <option key={p.id} value={p.id}>{p.name}</option>
+                    ))}
+                  </select>
+                  <button
+                    onClick={() => updateSetting('obs_scene_mappings', settings.obs_scene_mappings.filter((_, i) => i !== index))}
+                    className="px-2 text-text-secondary hover:text-text-primary"
+                    title="Remove mapping"
+                  >
+                    <X size={16} />
+                  </button>
+                </div>
+              ))}
+              <button
+                onClick={() => updateSetting('obs_scene_mappings', [...settings.obs_scene_mappings, { scene: '', preset_id: null, playlist_id: null }])}
+                className="flex items-center gap-2 text-sm text-text-secondary hover:text-text-primary"
+              >
+                <Plus size={16} />
+                <span>Add scene</span>
+              </button>
+            </div>
+          </div>
+        </div>
+
+        <div>
+          <h3 className="text-sm font-medium text-text-primary mb-4">Live Streaming</h3>
+          <div className="space-y-4">
+            <label className="flex items-center gap-2 text-sm text-text-secondary">
+              <input
+                type="checkbox"
+                checked={settings.streaming.enabled}
+                onChange={(e) => updateStreaming({ enabled: e.target.checked })}
+              />
+              Stream the master mix (MP3)
+            </label>
+            {streamingStatus && (
+              <p className="text-xs text-text-secondary">
+                Status: {streamingStatus.state}{streamingStatus.message ? ` - ${streamingStatus.message}` : ''}
+              </p>
+            )}
+            <div className="grid grid-cols-2 gap-3">
+              <div className="space-y-2">
+                <label className="text-sm text-text-secondary">Server Type</label>
+                <select
+                  value={settings.streaming.server_type}
+                  onChange={(e) => updateStreaming({ server_type: e.target.value as StreamingConfig['server_type'] })}
+                  className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                >
+                  <option value="icecast">Icecast</option>
+                  <option value="shoutcast">Shoutcast (v1)</option>
+                </select>
+              </div>
+              <div className="space-y-2">
+                <label className="text-sm text-text-secondary">Bitrate</label>
+                <select
+                  value={settings.streaming.bitrate}
+                  onChange={(e) => updateStreaming({ bitrate: Number(e.target.value) })}
+                  className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                >
+                  {[64, 96, 128, 160, 192, 256, 320].map((kbps) => (
+                    <option key={kbps} value={kbps}>{kbps} kbps</option>
+                  ))}
+                </select>
+              </div>
+              <div className="space-y-2">
+                <label className="text-sm text-text-secondary">Host</label>
+                <input
+                  type="text"
+                  value={settings.streaming.host}
+                  onChange={(e) => updateStreaming({ host: e.target.value })}
+                  className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                />
+              </div>
+              <div className="space-y-2">
+                <label className="text-sm text-text-secondary">Port</label>
+                <input
+                  type="number"
+                  min="1"
+                  max="65535"
+                  value={settings.streaming.port}
+                  onChange={(e) => updateStreaming({ port: Number(e.target.value) })}
+                  className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                />
+              </div>
+              {settings.streaming.server_type === 'icecast' && (
+                <>
+                  <div className="space-y-2">
+                    <label className="text-sm text-text-secondary">Mount</label>
+                    <input
+                      type="text"
+                      value={settings.streaming.mount}
+                      onChange={(e) => updateStreaming({ mount: e.target.value })}
+                      className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                    />
+                  </div>
+                  <div className="space-y-2">
+                    <label className="text-sm text-text-secondary">Username</label>
+                    <input
+                      type="text"
+                      value={settings.streaming.username}
+                      onChange={(e) => updateStreaming({ username: e.target.value })}
+                      className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                    />
+                  </div>
+                </>
+              )}
+              <div className="space-y-2">
+                <label className="text-sm text-text-secondary">Password</label>
+                <input
+                  type="password"
+                  value={settings.streaming.password}
+                  onChange={(e) => updateStreaming({ password: e.target.value })}
+                  className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                />
+              </div>
+              <div className="space-y-2">
+                <label className="text-sm text-text-secondary">Stream Name</label>
+                <input
+                  type="text"
+                  value={settings.streaming.stream_name}
+                  onChange={(e) => updateStreaming({ stream_name: e.target.value })}
+                  className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                />
+              </div>
+            </div>
+          </div>
+        </div>
+
+        <div>
+          <h3 className="text-sm font-medium text-text-primary mb-4">Recording</h3>
+          <div className="space-y-4">
+            <FolderSetting
+              label="Recordings"
+              path={settings.recording.folder}
+              onChangePath={(folder) => updateRecording({ folder })}
+            />
+            <label className="flex items-center gap-2 text-sm text-text-secondary">
+              <input
+                type="checkbox"
+                checked={settings.recording.stems}
+                disabled={recordingStatus !== null}
+                onChange={(e) => updateRecording({ stems: e.target.checked })}
+              />
+              Record music, ambient and soundboard to separate files (stems)
+            </label>
+            <div className="grid grid-cols-2 gap-3">
+              <div className="space-y-2">
+                <label className="text-sm text-text-secondary">Format</label>
+                <select
+                  value={settings.recording.format}
+                  disabled={recordingStatus !== null}
+                  onChange={(e) => updateRecording({ format: e.target.value as RecordingConfig['format'] })}
+                  className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                >
+                  <option value="wav">WAV (uncompressed)</option>
+                  <option value="mp3">MP3</option>
+                  <option value="ogg">Ogg Vorbis</option>
+                  <option value="opus">Opus</option>
+                </select>
+              </div>
+              <div className="space-y-2">
+                <label className="text-sm text-text-secondary">Bitrate</label>
+                <select
+                  value={settings.recording.bitrate}
+                  disabled={recordingStatus !== null || settings.recording.format === 'wav'}
+                  onChange={(e) => updateRecording({ bitrate: Number(e.target.value) })}
+                  className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                >
+                  {[64, 96, 128, 160, 192, 256, 320].map((kbps) => (
+                    <option key={kbps} value={kbps}>{kbps} kbps</option>
+                  ))}
+                </select>
+              </div>
+              <div className="space-y-2">
+                <label className="text-sm text-text-secondary">Split every (minutes, 0 = off)</label>
+                <input
+                  type="number"
+                  min="0"
+                  value={settings.recording.split_minutes}
+                  disabled={recordingStatus !== null}
+                  onChange={(e) => updateRecording({ split_minutes: Math.max(0, Number(e.target.value)) })}
+                  className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                />
+              </div>
+              <div className="space-y-2">
+                <label className="text-sm text-text-secondary">Split at size (MB, 0 = off)</label>
+                <input
+                  type="number"
+                  min="0"
+                  value={settings.recording.split_mb}
+                  disabled={recordingStatus !== null}
+                  onChange={(e) => updateRecording({ split_mb: Math.max(0, Number(e.target.value)) })}
+                  className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                />
+              </div>
+            </div>
+            <label className="flex items-center gap-2 text-sm text-text-secondary">
+              <input
+                type="checkbox"
+                checked={settings.recording.normalize}
+                disabled={recordingStatus !== null}
+                onChange={(e) => updateRecording({ normalize: e.target.checked })}
+              />
+              Normalize loudness of recordings and exports
+            </label>
+            {settings.recording.normalize && (
+              <div className="space-y-2">
+                <label className="text-sm text-text-secondary">Target loudness (LUFS)</label>
+                <input
+                  type="number"
+                  min="-36"
+                  max="-6"
+                  step="1"
+                  value={settings.recording.target_lufs}
+                  disabled={recordingStatus !== null}
+                  onChange={(e) => updateRecording({ target_lufs: Math.min(-6, Math.max(-36, Number(e.target.value))) })}
+                  className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                />
+              </div>
+            )}
+            <button
+              onClick={toggleRecording}
+              className="flex items-center gap-2 px-3 py-2 bg-bg-secondary rounded-lg text-sm text-text-secondary hover:text-text-primary hover:bg-bg-secondary/80 transition-colors"
+            >
+              {recordingStatus ? <Square size={14} /> : <Circle size={14} className="text-accent-red" />}
+              {recordingStatus ? `Stop recording (${formatElapsed(recordingStatus.elapsed_secs)})` : 'Start recording'}
+            </button>
+            {(recordingStatus || lastRecording) && (
+              <div className="text-xs text-text-secondary space-y-1">
+                {(recordingStatus || lastRecording)?.error && (
+                  <p className="text-accent-red">{(recordingStatus || lastRecording)?.error}</p>
+                )}
+                {(recordingStatus || lastRecording)?.files.map((file) => (
+                  <p key={file} className="truncate">{file}</p>
+                ))}
+              </div>
+            )}
+            <div className="space-y-2">
+              <div className="flex items-center justify-between">
+                <label className="text-sm text-text-secondary">Recordings ({recordings.length})</label>
+                <button
+                  onClick={loadRecordings}
+                  className="p-1 text-text-secondary hover:text-text-primary transition-colors"
+                  title="Refresh"
+                >
+                  <RefreshCw size={14} />
+                </button>
+              </div>
+              {recordings.length > 0 && (
+                <div className="max-h-40 overflow-y-auto text-xs text-text-secondary space-y-1">
+                  {recordings.map((file) => (
+                    <div key={file.path} className="flex justify-between gap-2" title={file.path}>
+                      <span className="truncate">{file.name}</span>
+                      <span className="shrink-0">
+                        {(file.size_bytes / (1024 * 1024)).toFixed(1)} MB · {new Date(file.modified).toLocaleString()}
+                      </span>
+                    </div>
+                  ))}
+                </div>
+              )}
+            </div>
+          </div>
+        </div>
+
+        <div>
+          <h3 className="text-sm font-medium text-text-primary mb-4">Replay Buffer</h3>
+          <div className="space-y-4">
+            <label className="flex items-center gap-2 text-sm text-text-secondary">
+              <input
+                type="checkbox"
+                checked={settings.replay_buffer.enabled}
+                onChange={(e) => updateReplay({ enabled: e.target.checked })}
+              />
+              Keep the last minutes of the mix so they can be saved afterwards (about 10 MB of memory per minute)
+            </label>
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Minutes to keep</label>
+              <input
+                type="number"
+                min="1"
+                max="60"
+                value={settings.replay_buffer.minutes}
+                onChange={(e) => updateReplay({ minutes: Math.max(1, Math.min(60, Number(e.target.value))) })}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+              />
+            </div>
+            {settings.replay_buffer.enabled && (
+              <button
+                onClick={saveReplay}
+                disabled={!replayBuffered}
+                className="flex items-center gap-2 px-3 py-2 bg-bg-secondary rounded-lg text-sm text-text-secondary hover:text-text-primary hover:bg-bg-secondary/80 transition-colors disabled:opacity-50"
+              >
+                Save last {settings.replay_buffer.minutes} min{replayBuffered !== null ? ` (${formatElapsed(replayBuffered)} buffered)` : ''}
+              </button>
+            )}
+            {replayMessage && <p className="text-xs text-text-secondary truncate">{replayMessage}</p>}
+          </div>
+        </div>
+
+        <div>
+          <h3 className="text-sm font-medium text-text-primary mb-4">Microphone</h3>
+          <div className="space-y-4">
+            <label className="flex items-center gap-2 text-sm text-text-secondary">
+              <input
+                type="checkbox"
+                checked={settings.microphone.enabled}
+                onChange={(e) => updateMicrophone({ enabled: e.target.checked })}
+              />
+              Hear the default microphone mixed with the soundscape, and record it (use headphones to avoid feedback)
+            </label>
+            {microphoneError && <p className="text-xs text-accent-red">{microphoneError}</p>}
+            <div>
+              <div className="flex justify-between text-sm mb-2">
+                <span className="text-text-secondary">Microphone Volume</span>
+                <span className="text-text-primary">{settings.microphone.volume}%</span>
+              </div>
+              <div style={{ position: 'relative', height: '24px' }}>
+                <div style={{ position: 'absolute', top: '8px', left: 0, right: 0, height: '8px', borderRadius: '4px', backgroundColor: '#313131' }} />
+                <div style={{ position: 'absolute', top: '8px', left: 0, height: '8px', borderRadius: '4px', background: 'linear-gradient(to right, #12e6c8, #a287f4)', width: `${settings.microphone.volume}%` }} />
+                <input
+                  type="range"
+                  min="0"
+                  max="100"
+                  step="1"
+                  value={settings.microphone.volume}
+                  onChange={(e) => updateMicrophone({ volume: Number(e.target.value) })}
+                  style={{ position: 'relative', width: '100%', height: '24px', background: 'transparent', cursor: 'pointer' }}
+                />
+              </div>
+            </div>
+            <div>
+              <div className="flex justify-between text-sm mb-2">
+                <span className="text-text-secondary">Microphone Reverb</span>
+                <span className="text-text-primary">{settings.microphone.reverb}%</span>
+              </div>
+              <div style={{ position: 'relative', height: '24px' }}>
+                <div style={{ position: 'absolute', top: '8px', left: 0, right: 0, height: '8px', borderRadius: '4px', backgroundColor: '#313131' }} />
+                <div style={{ position: 'absolute', top: '8px', left: 0, height: '8px', borderRadius: '4px', background: 'linear-gradient(to right, #12e6c8, #a287f4)', width: `${settings.microphone.reverb}%` }} />
+                <input
+                  type="range"
+                  min="0"
+                  max="100"
+                  step="1"
+                  value={settings.microphone.reverb}
+                  onChange={(e) => updateMicrophone({ reverb: Number(e.target.value) })}
+                  style={{ position: 'relative', width: '100%', height: '24px', background: 'transparent', cursor: 'pointer' }}
+                />
+              </div>
+            </div>
+          </div>
+        </div>
+
+        <div>
+          <h3 className="text-sm font-medium text-text-primary mb-4">External Music Player</h3>
+          <div className="space-y-4">
+            <label className="flex items-center gap-2 text-sm text-text-secondary">
+              <input
+                type="checkbox"
+                checked={settings.external_player.enabled}
+                onChange={(e) => updateExternalPlayer({ enabled: e.target.checked })}
+              />
+              Control music in another player (ambient and soundboard stay local)
+            </label>
+            {externalStatus && (
+              <p className="text-xs text-text-secondary">
+                {externalStatus.error
+                  ? `Error: ${externalStatus.error}`
+                  : externalStatus.title
+                    ? `${externalStatus.is_playing ? 'Playing' : 'Paused'}: ${externalStatus.title}${externalStatus.artist ? ` - ${externalStatus.artist}` : ''}`
+                    : externalStatus.connected ? 'Connected' : 'Connecting...'}
+              </p>
+            )}
+            <div className="grid grid-cols-2 gap-3">
+              <div className="space-y-2">
+                <label className="text-sm text-text-secondary">Player</label>
+                <select
+                  value={settings.external_player.player}
+                  onChange={(e) => updateExternalPlayer({ player: e.target.value as ExternalPlayerConfig['player'] })}
+                  className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                >
+                  <option value="mpd">MPD</option>
+                  <option value="spotify">Spotify (Premium)</option>
+                </select>
+              </div>
+              {settings.external_player.player === 'mpd' ? (
+                <>
+                  <div className="space-y-2">
+                    <label className="text-sm text-text-secondary">Host</label>
+                    <input
+                      type="text"
+                      value={settings.external_player.mpd_host}
+                      onChange={(e) => updateExternalPlayer({ mpd_host: e.target.value })}
+                      className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                    />
+                  </div>
+                  <div className="space-y-2">
+                    <label className="text-sm text-text-secondary">Port</label>
+                    <input
+                      type="number"
+                      min="1"
+                      max="65535"
+                      value={settings.external_player.mpd_port}
+                      onChange={(e) => updateExternalPlayer({ mpd_port: Number(e.target.value) })}
+                      className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                    />
+                  </div>
+                  <div className="space-y-2">
+                    <label className="text-sm text-text-secondary">Password</label>
+                    <input
+                      type="password"
+                      value={settings.external_player.mpd_password}
+                      onChange={(e) => updateExternalPlayer({ mpd_password: e.target.value })}
+                      className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                    />
+                  </div>
+                </>
+              ) : (
+                <>
+                  <div className="space-y-2">
+                    <label className="text-sm text-text-secondary">Client ID</label>
+                    <input
+                      type="text"
+                      value={settings.external_player.spotify_client_id}
+                      onChange={(e) => updateExternalPlayer({ spotify_client_id: e.target.value })}
+                      className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                    />
+                  </div>
+                  <div className="space-y-2">
+                    <label className="text-sm text-text-secondary">Refresh Token</label>
+                    <input
+                      type="password"
+                      value={settings.external_player.spotify_refresh_token}
+                      onChange={(e) => updateExternalPlayer({ spotify_refresh_token: e.target.value })}
+                      className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                    />
+                  </div>
+                </>
+              )}
+            </div>
+          </div>
+        </div>
+
+        <div>
+          <h3 className="text-sm font-medium text-text-primary mb-4">Webhooks</h3>
+          <div className="space-y-2">
+            <p className="text-xs text-text-secondary">
+              POSTs a JSON payload to each URL when the selected events happen. Leave every event unchecked to receive all of them.
+            </p>
+            {settings.webhooks.map((hook, index) => (
+              <div key={index} className="space-y-2">
+                <div className="flex gap-2">
+                  <input
+                    type="text"
+                    placeholder="https://example.com/hook"
+                    value={hook.url}
+                    onChange={(e) => updateWebhook(index, { url: e.target.value })}
+                    className="flex-1 px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+                  />
+                  <button
+                    onClick={() => updateSetting('webhooks', settings.webhooks.filter((_, i) => i !== index))}
+                    className="px-2 text-text-secondary hover:text-text-primary"
+                    title="Remove webhook"
+                  >
+                    <X size={16} />
+                  </button>
+                </div>
+                <div className="flex gap-4">
+                  {([
+                    ['preset_changed', 'Preset changed'],
+                    ['track_started', 'Track started'],
+                    ['schedule_advanced', 'Schedule advanced'],
+                  ] as [WebhookEvent, string][]).map(([event, label]) => (
+                    <label key={event} className="flex items-center gap-2 text-xs text-text-secondary">
+                      <input
+                        type="checkbox"
+                        checked={hook.events.includes(event)}
+                        onChange={() => toggleWebhookEvent(index, event)}
+                      />
+                      {label}
+                    </label>
+                  ))}
+                </div>
+              </div>
+            ))}
+            <button
+              onClick={() => updateSetting('webhooks', [...settings.webhooks, { url: '', events: [] }])}
+              className="flex items-center gap-2 text-sm text-text-secondary hover:text-text-primary"
+            >
+              <Plus size={16} />
+              <span>Add webhook</span>
+            </button>
+          </div>
+        </div>
+
+        <div>
+          <h3 className="text-sm font-medium text-text-primary mb-4">Synced Data Folder</h3>
+          <div className="space-y-2">
+            <label className="text-sm text-text-secondary">When a preset, schedule or playlist was changed on another device</label>
+            <select
+              value={settings.data_sync.on_conflict}
+              onChange={(e) => updateSetting('data_sync', { on_conflict: e.target.value as SyncConfig['on_conflict'] })}
+              className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+            >
+              <option value="reload">Don't save; reload the other version</option>
+              <option value="overwrite">Save over it (last writer wins)</option>
+              <option value="duplicate">Save, keeping the other version as a conflict copy</option>
+            </select>
+          </div>
+        </div>
+
+        <div>
+          <h3 className="text-sm font-medium text-text-primary mb-4">Diagnostics</h3>
+          <div className="space-y-4">
+            <div className="space-y-2">
+              <label className="text-sm text-text-secondary">Log level</label>
+              <select
+                value={settings.log_level}
+                onChange={(e) => updateSetting('log_level', e.target.value as AppSettings['log_level'])}
+                className="w-full px-3 py-2 bg-bg-secondary rounded-lg text-text-primary text-sm border border-border focus:outline-none focus:border-accent-purple"
+              >
+                {['off', 'error', 'warn', 'info', 'debug', 'trace'].map((level) => (
+                  <option key={level} value={level}>{level}</option>
+                ))}
+              </select>
+            </div>
+            <div className="flex gap-2">
+              <button
+                onClick={showLog}
+                className="flex items-center gap-2 px-3 py-2 bg-bg-secondary rounded-lg text-sm text-text-secondary hover:text-text-primary hover:bg-bg-secondary/80 transition-colors"
+              >
+                <RefreshCw size={14} />
+                {logTail ? 'Reload log' : 'Show recent log'}
+              </button>
+              {logTail && (
+                <button
+                  onClick={() => navigator.clipboard.writeText(logTail.join('\n')).catch(console.error)}
+                  className="px-3 py-2 bg-bg-secondary rounded-lg text-sm text-text-secondary hover:text-text-primary hover:bg-bg-secondary/80 transition-colors"
+                >
+                  Copy for bug report
+                </button>
+              )}
+              <button
+                onClick={copyEngineState}
+                className="px-3 py-2 bg-bg-secondary rounded-lg text-sm text-text-secondary hover:text-text-primary hover:bg-bg-secondary/80 transition-colors"
+              >
+                Copy engine state
+              </button>
+              <button
+                onClick={copyEngineStats}
+                className="px-3 py-2 bg-bg-secondary rounded-lg text-sm text-text-secondary hover:text-text-primary hover:bg-bg-secondary/80 transition-colors"
+              >
+                Copy performance stats
+              </button>
+              <button
+                onClick={() => invoke('reset_engine_stats').catch(console.error)}
+                className="px-3 py-2 bg-bg-secondary rounded-lg text-sm text-text-secondary hover:text-text-primary hover:bg-bg-secondary/80 transition-colors"
+              >
+                Reset stats
+              </button>
+            </div>
+            {logTail && (
+              <pre className="max-h-64 overflow-auto p-2 bg-bg-secondary rounded-lg text-xs text-text-secondary whitespace-pre-wrap">
+                {logTail.length > 0 ? logTail.join('\n') : 'The log is empty'}
+              </pre>
+            )}
           </div>
         </div>
 
@@ -1,11 +1,20 @@
 import React, { useState, useCallback, useEffect, useRef } from 'react';
-import { ChevronDown, ChevronRight, ChevronsUpDown, Check, Square, Volume2, Eye, EyeOff, Trash2, Info, RotateCcw, Save, XCircle, FilePlus, Calendar } from 'lucide-react';
+import { ChevronDown, ChevronRight, ChevronsUpDown, Check, Square, Volume2, Eye, EyeOff, Trash2, Info, RotateCcw, Save, XCircle, FilePlus, Calendar, Download, Dices, Headphones, Pause, Play, VolumeX } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { save } from '@tauri-apps/plugin-dialog';
 import { getCurrentWindow, LogicalSize } from '@tauri-apps/api/window';
-import { useAmbientStore } from '../../stores/ambientStore';
+import { AmbientPhase, useAmbientStore } from '../../stores/ambientStore';
 import { usePresetStore } from '../../stores/presetStore';
 import { useSchedulerStore } from '../../stores/schedulerStore';
 import { Scheduler } from './Scheduler';
 import { AmbientSoundDef, AmbientSound, DEFAULT_AMBIENT_SETTINGS } from '../../types';
+import { baseName, joinPath } from '../../utils/path';
+
+// Sounds in a soundscape from the dice button
+const RANDOM_SOUNDSCAPE_LAYERS = 4;
+
+// How much of a sound the preview button plays
+const PREVIEW_SECONDS = 5;
 
 // Info descriptions for each setting
 const settingInfo: Record<string, string> = {
@@ -14,10 +23,22 @@ const settingInfo: Record<string, string> = {
   pan: 'Left/Right stereo balance. Negative values pan left, positive values pan right, 0 is centered.',
   lowPassFreq: 'Filters out high frequencies above this value. Lower values create a muffled effect. 22kHz = no filtering.',
   volumeVariation: 'Adds random volume changes each loop. Higher values create more dynamic variation.',
+  eventProbability: 'Chance that each cycle plays when it comes up. Lower values make the sound rarer and less predictable.',
+  intensity: 'One fader for the whole sound: sets volume, repeat and pause ranges and event probability together, following the sound\'s intensity curve.',
   repeatRange: 'Number of A/B file cycles before pausing. Random value chosen between min and max each cycle.',
   pauseRange: 'Number of pause cycles between repeats. 0 means no pause. Random value chosen between min and max.',
+  randomStart: 'Starts the sound at a random point in its file instead of the beginning, so sounds started together don\'t play in lockstep.',
+  duckMusic: 'Lowers the music by the ducking amount while this sound is audible, for spoken or story layers like a weather report.',
+  morphInto: 'Crossfades this sound to another one\'s files while it keeps playing with the same settings, e.g. light rain into heavy rain.',
+  syncGroup: 'Sounds in the same group pause and start their cycles together, so layers like waves and gulls stay in step. The first sound in the group sets the repeat and pause ranges.',
 };
 
+// Sync groups offered per sound; the backend takes any name
+const SYNC_GROUPS = ['A', 'B', 'C', 'D'];
+
+// Crossfade when a sound morphs into another one's files
+const MORPH_CROSSFADE_MS = 3000;
+
 // Tooltip component
 const InfoTooltip: React.FC<{ text: string }> = ({ text }) => {
   const [show, setShow] = useState(false);
@@ -161,10 +182,15 @@ interface SoundItemProps {
   categoryName: string;
   isActive: boolean;
   onToggle: () => void;
-  onUpdateSettings: (settings: Record<string, number | string>) => void;
+  onUpdateSettings: (settings: Record<string, number | string | boolean>) => void;
+  onSetIntensity: (intensity: number) => void;
+  onSetSyncGroup: (group: string | null) => void;
+  morphTargets: AmbientSoundDef[];
+  onMorphInto: (target: AmbientSoundDef) => void;
   onResetToDefaults: () => void;
   isExpanded: boolean;
   onToggleExpanded: () => void;
+  phase?: AmbientPhase;
   activeSettings?: {
     volume: number;
     pitch: number;
@@ -177,19 +203,57 @@ interface SoundItemProps {
     pauseRangeMin: number;
     pauseRangeMax: number;
     volumeVariation: number;
+    eventProbability: number;
+    intensity?: number;
+    syncGroup?: string;
+    randomStart?: boolean;
+    duckMusic?: boolean;
   };
 }
 
 const SoundItem: React.FC<SoundItemProps> = ({
   sound,
+  categoryPath,
   isActive,
   onToggle,
   onUpdateSettings,
+  onSetIntensity,
+  onSetSyncGroup,
+  morphTargets,
+  onMorphInto,
   onResetToDefaults,
   isExpanded,
   onToggleExpanded,
+  phase,
   activeSettings,
 }) => {
+  const [exportMinutes, setExportMinutes] = useState(5);
+
+  // Play the first file quietly without adding the sound to the soundscape
+  const handlePreview = async () => {
+    try {
+      await invoke('preview_ambient', { filePath: joinPath(categoryPath, sound.files.a), seconds: PREVIEW_SECONDS });
+    } catch (error) {
+      console.error('Error previewing sound:', error);
+    }
+  };
+
+  // Bake the sound with its current settings into a loop file (progress shows in the Scheduler)
+  const handleExport = async () => {
+    const path = await save({
+      defaultPath: `${sound.name}.wav`,
+      filters: [
+        { name: 'WAV Audio', extensions: ['wav'] },
+        { name: 'Compressed Audio', extensions: ['mp3', 'ogg', 'opus'] },
+      ],
+    });
+    if (!path) return;
+    try {
+      await invoke('export_processed_sound', { id: sound.id, minutes: exportMinutes, path });
+    } catch (error) {
+      console.error('Error exporting sound:', error);
+    }
+  };
 
   return (
     <div className="rounded-lg bg-bg-secondary/30 overflow-hidden">
@@ -205,6 +269,23 @@ const SoundItem: React.FC<SoundItemProps> = ({
         
         <span className="flex-1 text-sm text-text-primary">{sound.name}</span>
         
+        {phase?.state === 'paused' && (
+          <span
+            className="text-xs text-text-secondary"
+            title="Resting between cycles (pause range and event probability); it plays again when the pause ends"
+          >
+            Resting{phase.next_cycle_secs != null ? ` ${Math.ceil(phase.next_cycle_secs)}s` : ''}
+          </span>
+        )}
+        
+        <button
+          onClick={handlePreview}
+          className="p-1 text-text-secondary hover:text-accent-cyan transition-colors"
+          title="Preview"
+        >
+          <Headphones size={14} />
+        </button>
+        
         {isActive && (
           <div className="flex items-center gap-1">
             <button
@@ -226,6 +307,16 @@ const SoundItem: React.FC<SoundItemProps> = ({
       
       {isActive && isExpanded && activeSettings && (
         <div style={{ padding: '8px 12px 8px 12px' }} className="space-y-4">
+          <Slider
+            label="Intensity"
+            value={activeSettings.intensity ?? 50}
+            min={0}
+            max={100}
+            onChange={onSetIntensity}
+            formatValue={(v) => activeSettings.intensity === undefined ? '-' : `${v}`}
+            info={settingInfo.intensity}
+          />
+          
           <Slider
             label="Volume"
             value={activeSettings.volume}
@@ -287,6 +378,16 @@ const SoundItem: React.FC<SoundItemProps> = ({
             info={settingInfo.volumeVariation}
           />
           
+          <Slider
+            label="Event Probability"
+            value={activeSettings.eventProbability}
+            min={0}
+            max={100}
+            onChange={(v) => onUpdateSettings({ eventProbability: v })}
+            formatValue={(v) => `${v}%`}
+            info={settingInfo.eventProbability}
+          />
+          
           <DualRangeSlider
             label="Repeat Range"
             min={1}
@@ -308,6 +409,89 @@ const SoundItem: React.FC<SoundItemProps> = ({
             onMaxChange={(value) => onUpdateSettings({ pauseRangeMax: value })}
             info={settingInfo.pauseRange}
           />
+
+          <div className="flex items-center justify-between text-xs">
+            <div className="flex items-center gap-1">
+              <span className="text-text-secondary">Sync Group</span>
+              <InfoTooltip text={settingInfo.syncGroup} />
+            </div>
+            <select
+              value={activeSettings.syncGroup ?? ''}
+              onChange={(e) => onSetSyncGroup(e.target.value || null)}
+              className="px-2 py-1 bg-bg-secondary border border-border rounded text-xs text-text-primary focus:outline-none focus:border-accent-purple"
+            >
+              <option value="">None</option>
+              {[...new Set([...SYNC_GROUPS, ...(activeSettings.syncGroup ? [activeSettings.syncGroup] : [])])].map((group) => (
+                <option key={group} value={group}>Group {group}</option>
+              ))}
+            </select>
+          </div>
+
+          <label className="flex items-center justify-between text-xs">
+            <div className="flex items-center gap-1">
+              <span className="text-text-secondary">Random Start</span>
+              <InfoTooltip text={settingInfo.randomStart} />
+            </div>
+            <input
+              type="checkbox"
+              checked={activeSettings.randomStart ?? false}
+              onChange={(e) => onUpdateSettings({ randomStart: e.target.checked })}
+            />
+          </label>
+
+          <label className="flex items-center justify-between text-xs">
+            <div className="flex items-center gap-1">
+              <span className="text-text-secondary">Duck Music</span>
+              <InfoTooltip text={settingInfo.duckMusic} />
+            </div>
+            <input
+              type="checkbox"
+              checked={activeSettings.duckMusic ?? false}
+              onChange={(e) => onUpdateSettings({ duckMusic: e.target.checked })}
+            />
+          </label>
+
+          {morphTargets.length > 0 && (
+            <div className="flex items-center justify-between text-xs">
+              <div className="flex items-center gap-1">
+                <span className="text-text-secondary">Morph Into</span>
+                <InfoTooltip text={settingInfo.morphInto} />
+              </div>
+              <select
+                value=""
+                onChange={(e) => {
+                  const target = morphTargets.find((s) => s.id === e.target.value);
+                  if (target) onMorphInto(target);
+                }}
+                className="px-2 py-1 bg-bg-secondary border border-border rounded text-xs text-text-primary focus:outline-none focus:border-accent-purple"
+              >
+                <option value="">Choose...</option>
+                {morphTargets.map((target) => (
+                  <option key={target.id} value={target.id}>{target.name}</option>
+                ))}
+              </select>
+            </div>
+          )}
+
+          <div className="flex items-center gap-2">
+            <select
+              value={exportMinutes}
+              onChange={(e) => setExportMinutes(Number(e.target.value))}
+              className="px-2 py-1 bg-bg-secondary border border-border rounded text-xs text-text-primary focus:outline-none focus:border-accent-purple"
+            >
+              {[1, 5, 10, 30, 60].map((minutes) => (
+                <option key={minutes} value={minutes}>{minutes} min</option>
+              ))}
+            </select>
+            <button
+              onClick={handleExport}
+              className="flex items-center gap-1 px-2 py-1 text-xs text-text-secondary hover:text-accent-cyan transition-colors"
+              title="Render this sound with its effects to a WAV file"
+            >
+              <Download size={12} />
+              Export with effects
+            </button>
+          </div>
         </div>
       )}
     </div>
@@ -321,15 +505,25 @@ export const AmbientSoundscapes: React.FC = () => {
     resetSoundToDefaults,
     expandedCategories,
     hideUnselected,
+    isPaused,
+    categoryVolumes,
     toggleSound,
-    loadSoundWithSettings,
+    applySounds,
     updateSoundSettings,
+    setSoundIntensity,
+    setSoundSyncGroup,
+    swapSoundFiles,
     toggleCategory,
     selectAllInCategory,
     deselectAllInCategory,
     setHideUnselected,
     clearAll,
+    togglePaused,
+    setCategoryVolume,
+    toggleCategoryMute,
     syncActiveFromBackend,
+    phases,
+    loadPhases,
     transitionToSounds,
     prepareFadeOut,
   } = useAmbientStore();
@@ -473,17 +667,18 @@ export const AmbientSoundscapes: React.FC = () => {
     }
   }, [categories, syncActiveFromBackend]);
 
-  // Poll backend for active sounds and preset ID to stay in sync with main window changes
+  // Poll cycle phases while sounds play, so resting sounds show their pause instead of looking broken
   useEffect(() => {
-    if (categories.length === 0) return;
-    
-    const interval = setInterval(() => {
-      syncActiveFromBackend();
-      syncCurrentPresetId();
-    }, 500); // Poll every 500ms for responsive UI updates
-    
+    if (activeSounds.size === 0) return;
+    loadPhases();
+    const interval = setInterval(loadPhases, 1000);
     return () => clearInterval(interval);
-  }, [categories.length, syncActiveFromBackend, syncCurrentPresetId]);
+  }, [activeSounds.size, loadPhases]);
+
+  // Changes from the main window arrive as state://ambient-changed (see useStateEvents)
+  useEffect(() => {
+    syncCurrentPresetId();
+  }, [syncCurrentPresetId]);
 
   // Check if preset name already exists
   const getExistingPresetByName = (name: string) => {
@@ -557,49 +752,39 @@ export const AmbientSoundscapes: React.FC = () => {
     try {
       const preset = await loadPreset(presetId);
       
-      // Clear existing sounds first
-      clearAll();
-      
-      // Small delay to let clearAll complete
-      await new Promise(resolve => setTimeout(resolve, 100));
+      // Create the sound objects with preset settings
+      const sounds: AmbientSound[] = preset.sounds.map(presetSound => ({
+        id: presetSound.soundId,
+        name: presetSound.name,
+        categoryId: presetSound.categoryId,
+        categoryPath: presetSound.categoryPath,
+        filesA: presetSound.filesA,
+        filesB: presetSound.filesB,
+        enabled: presetSound.enabled,
+        volume: presetSound.volume,
+        pitch: presetSound.pitch,
+        pan: presetSound.pan,
+        lowPassFreq: presetSound.lowPassFreq,
+        reverbType: DEFAULT_AMBIENT_SETTINGS.reverbType,
+        algorithmicReverb: presetSound.algorithmicReverb,
+        repeatRangeMin: presetSound.repeatRangeMin,
+        repeatRangeMax: presetSound.repeatRangeMax,
+        pauseRangeMin: presetSound.pauseRangeMin,
+        pauseRangeMax: presetSound.pauseRangeMax,
+        volumeVariation: presetSound.volumeVariation,
+        eventProbability: presetSound.eventProbability ?? DEFAULT_AMBIENT_SETTINGS.eventProbability,
+        intensity: presetSound.intensity,
+        syncGroup: presetSound.syncGroup,
+        randomStart: presetSound.randomStart,
+        duckMusic: presetSound.duckMusic,
+      }));
       
-      // Load each sound from the preset sequentially with small delays to prevent audio glitches
-      for (let i = 0; i < preset.sounds.length; i++) {
-        const presetSound = preset.sounds[i];
-        // Create the sound object with preset settings
-        const sound: AmbientSound = {
-          id: presetSound.soundId,
-          name: presetSound.name,
-          categoryId: presetSound.categoryId,
-          categoryPath: presetSound.categoryPath,
-          filesA: presetSound.filesA,
-          filesB: presetSound.filesB,
-          enabled: presetSound.enabled,
-          volume: presetSound.volume,
-          pitch: presetSound.pitch,
-          pan: presetSound.pan,
-          lowPassFreq: presetSound.lowPassFreq,
-          reverbType: DEFAULT_AMBIENT_SETTINGS.reverbType,
-          algorithmicReverb: presetSound.algorithmicReverb,
-          repeatRangeMin: presetSound.repeatRangeMin,
-          repeatRangeMax: presetSound.repeatRangeMax,
-          pauseRangeMin: presetSound.pauseRangeMin,
-          pauseRangeMax: presetSound.pauseRangeMax,
-          volumeVariation: presetSound.volumeVariation,
-        };
-        
-        // Load the sound with all its settings in one operation
-        await loadSoundWithSettings(sound);
-        
-        // Small delay between sounds to prevent audio buffer overload
-        if (i < preset.sounds.length - 1) {
-          await new Promise(resolve => setTimeout(resolve, 150));
-        }
-      }
+      // Replace the current sounds in one call so they all start together
+      await applySounds(sounds);
     } catch (error) {
       console.error('Error loading preset:', error);
     }
-  }, [loadPreset, clearAll, loadSoundWithSettings]);
+  }, [loadPreset, applySounds]);
 
   // Handle deleting a preset
   const handleDeletePreset = (presetId: string) => {
@@ -634,6 +819,17 @@ export const AmbientSoundscapes: React.FC = () => {
     setCurrentPresetId(null);
   }, [clearAll, setCurrentPresetId]);
 
+  // Replace what's playing with a few random sounds from the library; the backend's ambient-changed
+  // event brings them into the list
+  const handleRandomSoundscape = useCallback(async () => {
+    stopSchedule();
+    try {
+      await invoke('generate_random_soundscape', { categoryFilters: [], targetLayers: RANDOM_SOUNDSCAPE_LAYERS });
+    } catch (error) {
+      console.error('Error generating random soundscape:', error);
+    }
+  }, [stopSchedule]);
+
   // Handle loading a preset (stops scheduler) - for manual preset selection on left panel
   const handleLoadPresetWithSchedulerStop = useCallback(async (presetId: string) => {
     stopSchedule();
@@ -666,6 +862,11 @@ export const AmbientSoundscapes: React.FC = () => {
         pauseRangeMin: presetSound.pauseRangeMin,
         pauseRangeMax: presetSound.pauseRangeMax,
         volumeVariation: presetSound.volumeVariation,
+        eventProbability: presetSound.eventProbability ?? DEFAULT_AMBIENT_SETTINGS.eventProbability,
+        intensity: presetSound.intensity,
+        syncGroup: presetSound.syncGroup,
+        randomStart: presetSound.randomStart,
+        duckMusic: presetSound.duckMusic,
       }));
       
       // Use smart transition - only stop/start what's needed
@@ -712,6 +913,15 @@ export const AmbientSoundscapes: React.FC = () => {
             >
               <ChevronsUpDown size={20} />
             </button>
+            <button
+              onClick={togglePaused}
+              className={`p-2 rounded-lg transition-colors ${
+                isPaused ? 'text-accent-purple' : 'text-text-secondary hover:text-text-primary hover:bg-bg-secondary'
+              }`}
+              title={isPaused ? 'Resume all sounds' : 'Pause all sounds'}
+            >
+              {isPaused ? <Play size={20} /> : <Pause size={20} />}
+            </button>
             <button
               onClick={handleClearAll}
               className="p-2 rounded-lg text-text-secondary hover:text-accent-red hover:bg-bg-secondary transition-colors"
@@ -776,6 +986,15 @@ export const AmbientSoundscapes: React.FC = () => {
         >
           <FilePlus size={16} />
         </button>
+
+        <button
+          onClick={handleRandomSoundscape}
+          disabled={categories.length === 0}
+          className="p-1.5 rounded-lg text-text-secondary hover:text-accent-cyan hover:bg-bg-secondary transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+          title="Random soundscape"
+        >
+          <Dices size={18} />
+        </button>
         
         {currentPresetId && (
           <button
@@ -828,6 +1047,8 @@ export const AmbientSoundscapes: React.FC = () => {
         {categories.map((category) => {
           const isExpanded = expandedCategories.has(category.name);
           const activeCount = category.sounds.filter(s => activeSounds.has(s.id)).length;
+          const categoryId = baseName(category.path);
+          const categoryVolume = categoryVolumes[categoryId] ?? { volume: 100, muted: false };
           
           return (
             <div key={category.name} className="mb-2">
@@ -875,6 +1096,27 @@ export const AmbientSoundscapes: React.FC = () => {
                     >
                       <ChevronsUpDown size={14} className="inline" />
                     </button>
+                    <div className="ml-auto flex items-center gap-1">
+                      <button
+                        onClick={() => toggleCategoryMute(categoryId)}
+                        className={`p-1 rounded transition-colors ${
+                          categoryVolume.muted ? 'text-accent-red' : 'text-text-secondary hover:text-text-primary'
+                        }`}
+                        title={`${categoryVolume.muted ? 'Unmute' : 'Mute'} ${category.name}`}
+                      >
+                        {categoryVolume.muted ? <VolumeX size={14} /> : <Volume2 size={14} />}
+                      </button>
+                      <input
+                        type="range"
+                        min={0}
+                        max={100}
+                        value={categoryVolume.volume}
+                        onChange={(e) => setCategoryVolume(categoryId, Number(e.target.value))}
+                        className="w-20"
+                        style={{ opacity: categoryVolume.muted ? 0.4 : 1 }}
+                        title={`${category.name} volume: ${Math.round(categoryVolume.volume)}%`}
+                      />
+                    </div>
                   </div>
                   
                   <div className="space-y-1">
@@ -892,9 +1134,14 @@ export const AmbientSoundscapes: React.FC = () => {
                             isActive={!!activeSound}
                             onToggle={() => toggleSound(category.path, sound, category.name)}
                             onUpdateSettings={(settings) => updateSoundSettings(sound.id, settings)}
+                            onSetIntensity={(intensity) => setSoundIntensity(sound.id, intensity)}
+                            onSetSyncGroup={(group) => setSoundSyncGroup(sound.id, group)}
+                            morphTargets={category.sounds.filter(s => s.id !== sound.id)}
+                            onMorphInto={(target) => swapSoundFiles(sound.id, category.path, target, MORPH_CROSSFADE_MS)}
                             onResetToDefaults={() => resetSoundToDefaults(sound.id, sound)}
                             isExpanded={expandedSounds.has(sound.id)}
                             onToggleExpanded={() => toggleSoundExpanded(sound.id)}
+                            phase={activeSound ? phases[sound.id] : undefined}
                             activeSettings={activeSound ? {
                               volume: activeSound.volume,
                               pitch: activeSound.pitch,
@@ -907,6 +1154,11 @@ export const AmbientSoundscapes: React.FC = () => {
                               pauseRangeMin: activeSound.pauseRangeMin,
                               pauseRangeMax: activeSound.pauseRangeMax,
                               volumeVariation: activeSound.volumeVariation,
+                              eventProbability: activeSound.eventProbability,
+                              intensity: activeSound.intensity,
+                              syncGroup: activeSound.syncGroup,
+                              randomStart: activeSound.randomStart,
+                              duckMusic: activeSound.duckMusic,
                             } : undefined}
                           />
                         );
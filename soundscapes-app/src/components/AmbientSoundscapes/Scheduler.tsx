@@ -1,8 +1,13 @@
 import React, { useState, useEffect, useCallback, useRef } from 'react';
-import { Trash2, ChevronUp, ChevronDown, FilePlus, Save, XCircle, Play, Square, Clock } from 'lucide-react';
+import { Trash2, ChevronUp, ChevronDown, FilePlus, Save, XCircle, Play, Square, Clock, Megaphone, Download, Bell, Plus, X } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { save } from '@tauri-apps/plugin-dialog';
 import { useSchedulerStore } from '../../stores/schedulerStore';
 import { usePresetStore } from '../../stores/presetStore';
-import { ScheduledItem } from '../../types';
+import { useSoundboardStore } from '../../stores/soundboardStore';
+import { AppSettings, ScheduledItem, ScheduledEvent, SoundboardSound, RenderProgress, RenderFinished } from '../../types';
+import { errorMessage } from '../../utils/errors';
 
 // Dual range slider for minutes
 interface DualRangeSliderProps {
@@ -70,6 +75,9 @@ interface ScheduledItemRowProps {
   isActive: boolean;
   onRemove: () => void;
   onUpdateTiming: (min: number, max: number) => void;
+  onUpdateAnnouncement: (announcement: string) => void;
+  soundboardSounds: SoundboardSound[];
+  onUpdateEvents: (events: ScheduledEvent[]) => void;
   onMoveUp: () => void;
   onMoveDown: () => void;
 }
@@ -81,9 +89,16 @@ const ScheduledItemRow: React.FC<ScheduledItemRowProps> = ({
   isActive,
   onRemove,
   onUpdateTiming,
+  onUpdateAnnouncement,
+  soundboardSounds,
+  onUpdateEvents,
   onMoveUp,
   onMoveDown,
 }) => {
+  const events = item.events ?? [];
+  const updateEvent = (index: number, changes: Partial<ScheduledEvent>) =>
+    onUpdateEvents(events.map((event, i) => (i === index ? { ...event, ...changes } : event)));
+
   return (
     <div
       className={`rounded-lg bg-bg-secondary/50 border transition-all ${
@@ -142,6 +157,69 @@ const ScheduledItemRow: React.FC<ScheduledItemRowProps> = ({
           />
         </div>
       </div>
+
+      <div className="flex items-center gap-2 mt-2">
+        <Megaphone size={12} className="text-text-secondary" />
+        <input
+          type="text"
+          placeholder="Announcement (optional)"
+          value={item.announcement ?? ''}
+          onChange={(e) => onUpdateAnnouncement(e.target.value)}
+          className="flex-1 px-2 py-1 bg-bg-secondary rounded text-text-primary text-xs border border-border focus:outline-none focus:border-accent-purple"
+        />
+      </div>
+
+      {/* One-shot sounds during the item */}
+      {events.map((event, eventIndex) => (
+        <div key={eventIndex} className="flex items-center gap-2 mt-2">
+          <Bell size={12} className="text-text-secondary" />
+          <select
+            value={event.soundId}
+            onChange={(e) => updateEvent(eventIndex, { soundId: e.target.value })}
+            className="flex-1 min-w-0 px-2 py-1 bg-bg-secondary rounded text-text-primary text-xs border border-border focus:outline-none focus:border-accent-purple"
+          >
+            {soundboardSounds.map((sound) => (
+              <option key={sound.id} value={sound.id}>
+                {sound.name}
+              </option>
+            ))}
+          </select>
+          <input
+            type="number"
+            min={0}
+            max={100}
+            value={event.volume}
+            onChange={(e) => updateEvent(eventIndex, { volume: Math.min(100, Math.max(0, Number(e.target.value))) })}
+            className="w-12 px-2 py-1 bg-bg-secondary rounded text-text-primary text-xs border border-border focus:outline-none focus:border-accent-purple"
+            title="Volume (%)"
+          />
+          <input
+            type="number"
+            min={0}
+            placeholder="Random"
+            value={event.offsetSecs ?? ''}
+            onChange={(e) => updateEvent(eventIndex, { offsetSecs: e.target.value === '' ? null : Math.max(0, Math.round(Number(e.target.value))) })}
+            className="w-16 px-2 py-1 bg-bg-secondary rounded text-text-primary text-xs border border-border focus:outline-none focus:border-accent-purple"
+            title="Seconds into the item; empty plays it at a random time"
+          />
+          <button
+            onClick={() => onUpdateEvents(events.filter((_, i) => i !== eventIndex))}
+            className="p-0.5 text-text-secondary hover:text-accent-red transition-colors"
+            title="Remove sound"
+          >
+            <X size={12} />
+          </button>
+        </div>
+      ))}
+      <button
+        onClick={() => onUpdateEvents([...events, { soundId: soundboardSounds[0].id, volume: 100, offsetSecs: null }])}
+        disabled={soundboardSounds.length === 0}
+        className="flex items-center gap-1 mt-2 text-xs text-text-secondary hover:text-text-primary disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+        title="Play a soundboard sound once during this item"
+      >
+        <Plus size={12} />
+        Add sound
+      </button>
     </div>
   );
 };
@@ -164,6 +242,7 @@ export const Scheduler: React.FC<SchedulerProps> = ({ onLoadPreset: _onLoadPrese
     schedules,
     currentScheduleId,
     editingItems,
+    editingTransition,
     isPlaying,
     currentItemIndex,
     timeRemaining,
@@ -174,6 +253,9 @@ export const Scheduler: React.FC<SchedulerProps> = ({ onLoadPreset: _onLoadPrese
     addItem,
     removeItem,
     updateItemTiming,
+    updateItemAnnouncement,
+    updateItemEvents,
+    updateTransition,
     reorderItems,
     clearItems,
     startSchedule,
@@ -182,10 +264,14 @@ export const Scheduler: React.FC<SchedulerProps> = ({ onLoadPreset: _onLoadPrese
   } = useSchedulerStore();
 
   const { presets, loadPresets } = usePresetStore();
+  const { sounds: soundboardSounds, loadSounds } = useSoundboardStore();
 
   const [showSaveDialog, setShowSaveDialog] = useState(false);
   const [scheduleName, setScheduleName] = useState('');
   const [showDeleteDialog, setShowDeleteDialog] = useState(false);
+  // Percentage while a schedule is being rendered to a file
+  const [renderPercent, setRenderPercent] = useState<number | null>(null);
+  const [renderMessage, setRenderMessage] = useState<string | null>(null);
 
   // Load schedules and presets on mount
   useEffect(() => {
@@ -193,6 +279,29 @@ export const Scheduler: React.FC<SchedulerProps> = ({ onLoadPreset: _onLoadPrese
     loadPresets();
   }, [loadSchedules, loadPresets]);
 
+  // Soundboard sounds to pick the transition and event sounds from
+  useEffect(() => {
+    invoke<AppSettings>('get_settings')
+      .then((settings) => loadSounds(settings.soundboard_folder_path))
+      .catch(console.error);
+  }, [loadSounds]);
+
+  useEffect(() => {
+    const progressPromise = listen<RenderProgress>('render://progress', (event) => {
+      const { rendered_secs, total_secs } = event.payload;
+      setRenderPercent(total_secs > 0 ? Math.round((rendered_secs / total_secs) * 100) : 0);
+    });
+    const finishedPromise = listen<RenderFinished>('render://finished', (event) => {
+      const { output_path, error, cancelled } = event.payload;
+      setRenderPercent(null);
+      setRenderMessage(cancelled ? 'Export cancelled' : error ? `Export failed: ${error}` : `Exported to ${output_path}`);
+    });
+    return () => {
+      progressPromise.then((unlisten) => unlisten());
+      finishedPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
   // Track last loaded preset to avoid reloading
   const lastLoadedRef = useRef<{ index: number; playing: boolean } | null>(null);
   // Track if we've already prepared fade out for current transition
@@ -278,6 +387,28 @@ export const Scheduler: React.FC<SchedulerProps> = ({ onLoadPreset: _onLoadPrese
     }
   };
 
+  // Render the saved schedule offline to a WAV file
+  const handleExport = async () => {
+    if (!currentScheduleId) return;
+    const name = schedules.find(s => s.id === currentScheduleId)?.name || 'schedule';
+    const outputPath = await save({
+      defaultPath: `${name}.wav`,
+      filters: [
+        { name: 'WAV Audio', extensions: ['wav'] },
+        { name: 'Compressed Audio', extensions: ['mp3', 'ogg', 'opus'] },
+      ],
+    });
+    if (!outputPath) return;
+    try {
+      setRenderMessage(null);
+      setRenderPercent(0);
+      await invoke('render_schedule', { id: currentScheduleId, outputPath });
+    } catch (error) {
+      setRenderPercent(null);
+      setRenderMessage(`Export failed: ${errorMessage(error)}`);
+    }
+  };
+
   // Get current schedule name
   const currentScheduleName = currentScheduleId
     ? schedules.find(s => s.id === currentScheduleId)?.name
@@ -349,6 +480,18 @@ export const Scheduler: React.FC<SchedulerProps> = ({ onLoadPreset: _onLoadPrese
           <FilePlus size={16} />
         </button>
         
+        {/* Export */}
+        {currentScheduleId && (
+          <button
+            onClick={handleExport}
+            disabled={renderPercent !== null}
+            className="p-1.5 rounded-lg text-text-secondary hover:text-accent-cyan hover:bg-bg-secondary transition-colors disabled:opacity-50 disabled:cursor-not-allowed border border-border"
+            title="Export schedule to audio file"
+          >
+            <Download size={16} />
+          </button>
+        )}
+        
         {/* Delete */}
         {currentScheduleId && (
           <button
@@ -361,6 +504,32 @@ export const Scheduler: React.FC<SchedulerProps> = ({ onLoadPreset: _onLoadPrese
         )}
       </div>
 
+      {/* Export Progress */}
+      {(renderPercent !== null || renderMessage) && (
+        <div className="flex items-center gap-2 text-xs text-text-secondary" style={{ marginBottom: '6px' }}>
+          <span className="flex-1 truncate">
+            {renderPercent !== null ? `Exporting... ${renderPercent}%` : renderMessage}
+          </span>
+          {renderPercent !== null ? (
+            <button
+              onClick={() => invoke('cancel_render').catch(console.error)}
+              className="text-text-secondary hover:text-accent-red transition-colors"
+              title="Cancel export"
+            >
+              <XCircle size={14} />
+            </button>
+          ) : (
+            <button
+              onClick={() => setRenderMessage(null)}
+              className="text-text-secondary hover:text-text-primary transition-colors"
+              title="Dismiss"
+            >
+              <XCircle size={14} />
+            </button>
+          )}
+        </div>
+      )}
+
       {/* Save Dialog */}
       {showSaveDialog && (
         <div className="mb-3 p-3 bg-bg-secondary rounded-lg border border-border">
@@ -421,6 +590,48 @@ export const Scheduler: React.FC<SchedulerProps> = ({ onLoadPreset: _onLoadPrese
         </select>
       </div>
 
+      {/* Transition Sound */}
+      <div className="flex items-center gap-2 mb-3">
+        <Bell size={14} className="text-text-secondary" />
+        <select
+          value={editingTransition?.soundId ?? ''}
+          onChange={(e) => updateTransition(e.target.value
+            ? { volume: 100, leadInSecs: 0, ...editingTransition, soundId: e.target.value }
+            : null)}
+          className="flex-1 px-2 py-1.5 bg-bg-secondary border border-border rounded-lg text-sm text-text-primary focus:outline-none focus:border-accent-purple"
+          title="Soundboard sound played at each change"
+        >
+          <option value="">No transition sound</option>
+          {soundboardSounds.map((sound) => (
+            <option key={sound.id} value={sound.id}>
+              {sound.name}
+            </option>
+          ))}
+        </select>
+        {editingTransition && (
+          <>
+            <input
+              type="number"
+              min={0}
+              max={100}
+              value={editingTransition.volume}
+              onChange={(e) => updateTransition({ ...editingTransition, volume: Math.min(100, Math.max(0, Number(e.target.value))) })}
+              className="w-14 px-2 py-1.5 bg-bg-secondary border border-border rounded-lg text-sm text-text-primary focus:outline-none focus:border-accent-purple"
+              title="Volume (%)"
+            />
+            <input
+              type="number"
+              min={0}
+              max={300}
+              value={editingTransition.leadInSecs}
+              onChange={(e) => updateTransition({ ...editingTransition, leadInSecs: Math.max(0, Number(e.target.value)) })}
+              className="w-14 px-2 py-1.5 bg-bg-secondary border border-border rounded-lg text-sm text-text-primary focus:outline-none focus:border-accent-purple"
+              title="Seconds before the change"
+            />
+          </>
+        )}
+      </div>
+
       {/* Scheduled Items List */}
       <div className="flex-1 overflow-y-auto space-y-2" style={{ paddingRight: '4px' }}>
         {editingItems.length > 0 ? (
@@ -433,6 +644,9 @@ export const Scheduler: React.FC<SchedulerProps> = ({ onLoadPreset: _onLoadPrese
               isActive={isPlaying && currentItemIndex === index}
               onRemove={() => removeItem(item.id)}
               onUpdateTiming={(min, max) => updateItemTiming(item.id, min, max)}
+              onUpdateAnnouncement={(text) => updateItemAnnouncement(item.id, text)}
+              soundboardSounds={soundboardSounds}
+              onUpdateEvents={(events) => updateItemEvents(item.id, events)}
               onMoveUp={() => reorderItems(index, index - 1)}
               onMoveDown={() => reorderItems(index, index + 1)}
             />
@@ -8,7 +8,7 @@ import { useSettingsStore } from '../../stores/settingsStore';
 import { usePresetStore } from '../../stores/presetStore';
 import { useSchedulerStore } from '../../stores/schedulerStore';
 import { useAmbientStore } from '../../stores/ambientStore';
-import { AmbientSound } from '../../types';
+import { AmbientSound, DEFAULT_AMBIENT_SETTINGS } from '../../types';
 
 const DEFAULT_REVERB_TYPE: 'off' | 'small-room' | 'large-hall' | 'cathedral' = 'off';
 
@@ -16,7 +16,7 @@ export const MainWindow: React.FC = () => {
   const { settings, updateSetting } = useSettingsStore();
   const { loadPreset, setCurrentPresetId } = usePresetStore();
   const { loadSchedule, startSchedule, setCurrentScheduleId } = useSchedulerStore();
-  const { clearAll, loadSoundWithSettings, transitionToSounds } = useAmbientStore();
+  const { applySounds, transitionToSounds } = useAmbientStore();
   
   const vizList = getVisualizationList();
   const currentVizId = settings?.visualization_type || 'orb';
@@ -34,46 +34,35 @@ export const MainWindow: React.FC = () => {
       const preset = await loadPreset(presetId);
       setCurrentPresetId(presetId);
       
-      // Clear existing sounds first
-      await clearAll();
+      const sounds: AmbientSound[] = preset.sounds.map(presetSound => ({
+        id: presetSound.soundId,
+        name: presetSound.name,
+        categoryId: presetSound.categoryId,
+        categoryPath: presetSound.categoryPath,
+        filesA: presetSound.filesA,
+        filesB: presetSound.filesB,
+        enabled: presetSound.enabled,
+        volume: presetSound.volume,
+        pitch: presetSound.pitch,
+        pan: presetSound.pan,
+        lowPassFreq: presetSound.lowPassFreq,
+        reverbType: DEFAULT_REVERB_TYPE,
+        algorithmicReverb: presetSound.algorithmicReverb,
+        repeatRangeMin: presetSound.repeatRangeMin,
+        repeatRangeMax: presetSound.repeatRangeMax,
+        pauseRangeMin: presetSound.pauseRangeMin,
+        pauseRangeMax: presetSound.pauseRangeMax,
+        volumeVariation: presetSound.volumeVariation,
+        eventProbability: presetSound.eventProbability ?? DEFAULT_AMBIENT_SETTINGS.eventProbability,
+        intensity: presetSound.intensity,
+      }));
       
-      // Small delay to let clearAll complete
-      await new Promise(resolve => setTimeout(resolve, 100));
-      
-      // Load each sound from the preset
-      for (let i = 0; i < preset.sounds.length; i++) {
-        const presetSound = preset.sounds[i];
-        const sound: AmbientSound = {
-          id: presetSound.soundId,
-          name: presetSound.name,
-          categoryId: presetSound.categoryId,
-          categoryPath: presetSound.categoryPath,
-          filesA: presetSound.filesA,
-          filesB: presetSound.filesB,
-          enabled: presetSound.enabled,
-          volume: presetSound.volume,
-          pitch: presetSound.pitch,
-          pan: presetSound.pan,
-          lowPassFreq: presetSound.lowPassFreq,
-          reverbType: DEFAULT_REVERB_TYPE,
-          algorithmicReverb: presetSound.algorithmicReverb,
-          repeatRangeMin: presetSound.repeatRangeMin,
-          repeatRangeMax: presetSound.repeatRangeMax,
-          pauseRangeMin: presetSound.pauseRangeMin,
-          pauseRangeMax: presetSound.pauseRangeMax,
-          volumeVariation: presetSound.volumeVariation,
-        };
-        
-        await loadSoundWithSettings(sound);
-        
-        if (i < preset.sounds.length - 1) {
-          await new Promise(resolve => setTimeout(resolve, 150));
-        }
-      }
+      // Replace the current sounds in one call so they all start together
+      await applySounds(sounds);
     } catch (error) {
       console.error('Error loading preset:', error);
     }
-  }, [loadPreset, setCurrentPresetId, clearAll, loadSoundWithSettings]);
+  }, [loadPreset, setCurrentPresetId, applySounds]);
 
   // Handle starting a schedule from quick controls
   const handleStartSchedule = useCallback(async (scheduleId: string) => {
@@ -106,6 +95,8 @@ export const MainWindow: React.FC = () => {
           pauseRangeMin: presetSound.pauseRangeMin,
           pauseRangeMax: presetSound.pauseRangeMax,
           volumeVariation: presetSound.volumeVariation,
+          eventProbability: presetSound.eventProbability ?? DEFAULT_AMBIENT_SETTINGS.eventProbability,
+          intensity: presetSound.intensity,
         }));
         
         await transitionToSounds(newSounds);
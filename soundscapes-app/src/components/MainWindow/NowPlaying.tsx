@@ -2,6 +2,7 @@ import React, { useState, useEffect, useRef } from 'react';
 import { Play, Pause, SkipBack, SkipForward } from 'lucide-react';
 import { useAudioStore } from '../../stores/audioStore';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 
 interface MusicProgress {
   current_time: number;
@@ -18,6 +19,15 @@ interface CurrentTrackInfo {
   file_path: string;
 }
 
+// Going back later than this into a track restarts it instead of playing the previous one
+const RESTART_THRESHOLD_SECS = 3;
+
+// state://track-changed payload
+interface TrackChanged {
+  track: CurrentTrackInfo | null;
+  is_playing: boolean;
+}
+
 export const NowPlaying: React.FC = () => {
   const { isMusicMuted } = useAudioStore();
   const [currentTrack, setCurrentTrack] = useState<CurrentTrackInfo | null>(null);
@@ -27,20 +37,36 @@ export const NowPlaying: React.FC = () => {
   const [dragPosition, setDragPosition] = useState(0);
   const progressBarRef = useRef<HTMLDivElement>(null);
 
-  // Poll for current track and progress from backend
+  // The track comes from state://track-changed after the first read; music://track-started comes
+  // straight from the audio thread, so the progress bar starts over without waiting for the next poll
+  useEffect(() => {
+    invoke<CurrentTrackInfo | null>('get_current_track')
+      .then(setCurrentTrack)
+      .catch(() => {});
+    const unlistenPromise = listen<TrackChanged>('state://track-changed', (event) => {
+      setCurrentTrack(event.payload.track);
+      setIsPlaying(event.payload.is_playing);
+    });
+    const unlistenStartedPromise = listen<CurrentTrackInfo>('music://track-started', (event) => {
+      setCurrentTrack(event.payload);
+      setProgress((p) => ({ ...p, currentTime: 0 }));
+    });
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+      unlistenStartedPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  // Poll the playback position, which changes continuously
   useEffect(() => {
     let mounted = true;
     
     const poll = async () => {
       if (!mounted) return;
       try {
-        const [trackInfo, prog] = await Promise.all([
-          invoke<CurrentTrackInfo | null>('get_current_track'),
-          invoke<MusicProgress>('get_music_progress'),
-        ]);
+        const prog = await invoke<MusicProgress>('get_music_progress');
         
         if (mounted) {
-          setCurrentTrack(trackInfo);
           setIsPlaying(prog.is_playing);
           setProgress({ currentTime: prog.current_time, duration: prog.duration });
         }
@@ -78,20 +104,18 @@ export const NowPlaying: React.FC = () => {
     }
   };
 
-  const restartTrack = async () => {
-    // Restart current track from beginning by re-playing it
-    if (currentTrack) {
-      try {
-        await invoke('play_music', {
-          filePath: currentTrack.file_path,
-          id: currentTrack.id,
-          title: currentTrack.title,
-          artist: currentTrack.artist,
-          album: currentTrack.album,
-        });
-      } catch (err) {
-        console.error('Error restarting track:', err);
+  // Like most players: back to the start of the track once it has played a few seconds, otherwise
+  // to the previous track
+  const playPrevious = async () => {
+    if (!currentTrack) return;
+    try {
+      if (progress.currentTime > RESTART_THRESHOLD_SECS) {
+        await invoke('restart_track');
+      } else {
+        await invoke('play_previous_track');
       }
+    } catch (err) {
+      console.error('Error going back:', err);
     }
   };
 
@@ -184,10 +208,10 @@ export const NowPlaying: React.FC = () => {
         {/* Playback controls */}
         <div className="flex items-center gap-1">
           <button
-            onClick={restartTrack}
+            onClick={playPrevious}
             disabled={!currentTrack}
             className="p-2 rounded-lg text-text-secondary hover:text-text-primary transition-colors disabled:opacity-30"
-            title="Restart track"
+            title="Restart track (previous track in the first few seconds)"
           >
             <SkipBack size={18} />
           </button>
@@ -1,6 +1,8 @@
 import React, { useRef, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { useAudioStore } from '../../stores/audioStore';
+import { useVisualizationActive } from '../../hooks/useVisualizationActive';
 
 interface PlaybackState {
   music_playing: boolean;
@@ -145,27 +147,29 @@ export const Orb: React.FC = () => {
   const smoothedFreqRef = useRef<Float32Array>(new Float32Array(64));
   const { isMasterMuted } = useAudioStore();
 
-  // Poll playback state from backend
+  // Spectrum analysis runs in the backend only while a visualizer is showing
+  useVisualizationActive();
+
+  // Receive playback state pushed from the audio thread
   useEffect(() => {
     let mounted = true;
     
-    const pollState = async () => {
-      if (!mounted) return;
-      try {
-        const state = await invoke<PlaybackState>('get_playback_state');
-        stateRef.current = state;
-      } catch {
-        // Ignore errors during polling
-      }
-      if (mounted) {
-        setTimeout(pollState, 100);
-      }
-    };
+    // Fetch once so we have data before the first event arrives
+    invoke<PlaybackState>('get_playback_state')
+      .then((state) => {
+        if (mounted) stateRef.current = state;
+      })
+      .catch(() => {
+        // Ignore errors - events will fill in the state
+      });
     
-    pollState();
+    const unlistenPromise = listen<PlaybackState>('playback://fft', (event) => {
+      stateRef.current = event.payload;
+    });
     
     return () => {
       mounted = false;
+      unlistenPromise.then((unlisten) => unlisten());
     };
   }, []);
 
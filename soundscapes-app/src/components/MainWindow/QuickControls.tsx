@@ -6,6 +6,7 @@ import { usePresetStore } from '../../stores/presetStore';
 import { useSchedulerStore } from '../../stores/schedulerStore';
 import { useAmbientStore } from '../../stores/ambientStore';
 import { useSettingsStore } from '../../stores/settingsStore';
+import { SLOW_STOP_FADE_MS } from '../../types';
 
 interface QuickControlsProps {
   onLoadAmbientPreset: (presetId: string) => Promise<void>;
@@ -26,6 +27,7 @@ export const QuickControls: React.FC<QuickControlsProps> = ({
     toggleLoop,
     setCurrentPlaylist,
     playTrackFromPlaylist,
+    playAlbum,
     loadAlbums,
   } = usePlaylistStore();
 
@@ -43,13 +45,10 @@ export const QuickControls: React.FC<QuickControlsProps> = ({
     loadSchedules();
   }, [loadPresets, loadSchedules]);
 
-  // Poll for currentPresetId and active sounds from other windows
+  // Read currentPresetId and active sounds once; changes from other windows arrive as events
   useEffect(() => {
-    const interval = setInterval(() => {
-      syncCurrentPresetId();
-      syncActiveFromBackend();
-    }, 500);
-    return () => clearInterval(interval);
+    syncCurrentPresetId();
+    syncActiveFromBackend();
   }, [syncCurrentPresetId, syncActiveFromBackend]);
 
   // Load albums if not already loaded
@@ -107,12 +106,8 @@ export const QuickControls: React.FC<QuickControlsProps> = ({
   const handleMusicSelect = async (type: 'playlist' | 'album', id: string) => {
     setMusicDropdownOpen(false);
     if (type === 'album') {
-      await setCurrentPlaylist(`album-${id}`);
-      // Start playing the first track
-      const album = albums.find(a => a.name === id);
-      if (album && album.tracks.length > 0) {
-        await playTrackFromPlaylist(`album-${id}`, 0);
-      }
+      // Backend sets the album as current and starts the first track
+      await playAlbum(id);
     } else {
       await setCurrentPlaylist(id);
       await playTrackFromPlaylist(id, 0);
@@ -130,9 +125,9 @@ export const QuickControls: React.FC<QuickControlsProps> = ({
   };
 
   // Stop music playback only
-  const handleStopMusic = async () => {
+  const handleStopMusic = async (e: React.MouseEvent) => {
     try {
-      await invoke('stop_music');
+      await invoke('stop_music', { fadeMs: e.shiftKey ? SLOW_STOP_FADE_MS : undefined });
       await setCurrentPlaylist(null);
     } catch (error) {
       console.error('Error stopping music:', error);
@@ -210,7 +205,7 @@ export const QuickControls: React.FC<QuickControlsProps> = ({
       <button
         onClick={handleStopMusic}
         className="p-1.5 rounded-lg text-text-secondary/60 hover:text-accent-red hover:bg-bg-secondary/50 transition-colors"
-        title="Stop music"
+        title="Stop music (Shift-click to fade out)"
       >
         <Square size={14} />
       </button>
@@ -1,6 +1,8 @@
 import React, { useRef, useEffect, useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { useAudioStore } from '../../stores/audioStore';
+import { useVisualizationActive } from '../../hooks/useVisualizationActive';
 import { getVisualization, DEFAULT_VISUALIZATION } from '../../visualizations';
 import { Visualization } from '../../visualizations/types';
 
@@ -12,7 +14,13 @@ interface PlaybackState {
   master_volume: number;
   is_muted: boolean;
   frequencies: number[];
+  left_frequencies: number[];
+  right_frequencies: number[];
   ambient_frequencies: number[];
+  soundboard_playing: boolean;
+  soundboard_volume: number;
+  soundboard_level: number;
+  soundboard_frequencies: number[];
 }
 
 interface BufferTarget {
@@ -158,7 +166,13 @@ export const Visualizer: React.FC = () => {
     master_volume: 1,
     is_muted: false,
     frequencies: new Array(64).fill(0),
+    left_frequencies: new Array(64).fill(0),
+    right_frequencies: new Array(64).fill(0),
     ambient_frequencies: new Array(64).fill(0),
+    soundboard_playing: false,
+    soundboard_volume: 0,
+    soundboard_level: 0,
+    soundboard_frequencies: new Array(64).fill(0),
   });
   const frequencyTextureRef = useRef<WebGLTexture | null>(null);
   const smoothedFreqRef = useRef<Float32Array>(new Float32Array(64));
@@ -194,27 +208,29 @@ export const Visualizer: React.FC = () => {
     };
   }, [currentVizId]);
 
-  // Poll playback state from backend
+  // Spectrum analysis runs in the backend only while a visualizer is showing
+  useVisualizationActive();
+
+  // Receive playback state pushed from the audio thread
   useEffect(() => {
     let mounted = true;
     
-    const pollState = async () => {
-      if (!mounted) return;
-      try {
-        const state = await invoke<PlaybackState>('get_playback_state');
-        stateRef.current = state;
-      } catch {
-        // Ignore errors during polling
-      }
-      if (mounted) {
-        setTimeout(pollState, 100);
-      }
-    };
+    // Fetch once so we have data before the first event arrives
+    invoke<PlaybackState>('get_playback_state')
+      .then((state) => {
+        if (mounted) stateRef.current = state;
+      })
+      .catch(() => {
+        // Ignore errors - events will fill in the state
+      });
     
-    pollState();
+    const unlistenPromise = listen<PlaybackState>('playback://fft', (event) => {
+      stateRef.current = event.payload;
+    });
     
     return () => {
       mounted = false;
+      unlistenPromise.then((unlisten) => unlisten());
     };
   }, []);
 
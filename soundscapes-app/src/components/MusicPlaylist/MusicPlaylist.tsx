@@ -2,6 +2,8 @@ import React, { useState, useEffect, useRef } from 'react';
 import { ChevronDown, ChevronRight, Star, Play, ListPlus, ListStart, Shuffle, Repeat, Plus, Music, Trash2, Square, Pencil } from 'lucide-react';
 import { usePlaylistStore } from '../../stores/playlistStore';
 import { invoke } from '@tauri-apps/api/core';
+import { errorMessage } from '../../utils/errors';
+import { SLOW_STOP_FADE_MS } from '../../types';
 
 interface MusicProgress {
   current_time: number;
@@ -91,8 +93,8 @@ export const MusicPlaylist: React.FC = () => {
     isLooping,
     favorites,
     playNextQueue,
-    playTrack,
     playTrackFromPlaylist,
+    playAlbum,
     addToPlayNextQueue,
     playNow,
     playNext,
@@ -211,8 +213,7 @@ export const MusicPlaylist: React.FC = () => {
       setShowCreateDialog(false);
       setIsSelectMode(false);
     } catch (error) {
-      const message = error instanceof Error ? error.message : 'Error creating playlist';
-      showToast(message);
+      showToast(errorMessage(error));
     }
   };
 
@@ -331,12 +332,12 @@ export const MusicPlaylist: React.FC = () => {
         <h2 className="text-lg font-semibold text-text-primary">Music</h2>
         <div className="flex items-center gap-2">
           <button
-            onClick={async () => {
-              await invoke('stop_music');
+            onClick={async (e) => {
+              await invoke('stop_music', { fadeMs: e.shiftKey ? SLOW_STOP_FADE_MS : undefined });
               setCurrentPlaylist(null);
             }}
             className="p-2 rounded-lg transition-colors text-text-secondary hover:text-accent-red hover:bg-bg-secondary"
-            title="Stop playback"
+            title="Stop playback (Shift-click to fade out)"
           >
             <Square size={18} />
           </button>
@@ -460,9 +461,8 @@ export const MusicPlaylist: React.FC = () => {
                   <button
                     onClick={async () => {
                       if (selectedPlaylistId.startsWith('album-')) {
-                        // For albums, set the album as current and play the first track
-                        await setCurrentPlaylist(selectedPlaylistId);
-                        await playTrack(selectedPlaylist.tracks[0]);
+                        // For albums, the backend sets the album as current and plays the first track
+                        await playAlbum(selectedPlaylistId.replace('album-', ''));
                       } else {
                         // For playlists, set the playlist and play from index 0
                         await playTrackFromPlaylist(selectedPlaylistId, 0);
@@ -507,11 +507,8 @@ export const MusicPlaylist: React.FC = () => {
                     isFavorite={favorites.has(track.id)}
                     onPlay={async () => {
                       if (selectedPlaylistId?.startsWith('album-')) {
-                        // For albums, set the album as current with correct index and play the track
-                        await setCurrentPlaylist(selectedPlaylistId);
-                        await invoke('set_playlist_index', { index });
-                        usePlaylistStore.setState({ currentIndex: index });
-                        await playTrack(track);
+                        // For albums, the backend starts the album from this track
+                        await playAlbum(selectedPlaylistId.replace('album-', ''), track.id);
                       } else {
                         playTrackFromPlaylist(selectedPlaylistId, index);
                       }
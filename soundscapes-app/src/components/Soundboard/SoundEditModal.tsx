@@ -2,12 +2,27 @@ import React, { useState, useEffect, useRef } from 'react';
 import { X } from 'lucide-react';
 import { SoundboardSound } from '../../types';
 
+export interface SoundEditUpdates {
+  name: string;
+  hotkey: string | null;
+  color: string;
+  start_ms: number | undefined;
+  end_ms: number | undefined;
+}
+
 interface SoundEditModalProps {
   sound: SoundboardSound;
   onClose: () => void;
-  onSave: (updates: { name: string; hotkey: string | null; color: string }) => void;
+  onSave: (updates: SoundEditUpdates) => void;
 }
 
+// Trim fields are in seconds; empty means the start or end of the file
+const toSeconds = (ms: number | undefined) => (ms === undefined ? '' : String(ms / 1000));
+const toMs = (seconds: string) => {
+  const value = parseFloat(seconds);
+  return seconds.trim() === '' || isNaN(value) ? undefined : Math.max(0, Math.round(value * 1000));
+};
+
 const PRESET_COLORS = [
   '#a287f4', // Purple (default)
   '#f472b6', // Pink
@@ -29,6 +44,8 @@ export const SoundEditModal: React.FC<SoundEditModalProps> = ({ sound, onClose,
   const [name, setName] = useState(sound.name);
   const [hotkey, setHotkey] = useState(sound.hotkey || '');
   const [color, setColor] = useState(sound.color);
+  const [trimStart, setTrimStart] = useState(toSeconds(sound.start_ms));
+  const [trimEnd, setTrimEnd] = useState(toSeconds(sound.end_ms));
   const [isCapturingHotkey, setIsCapturingHotkey] = useState(false);
   const hotkeyInputRef = useRef<HTMLInputElement>(null);
 
@@ -69,11 +86,18 @@ export const SoundEditModal: React.FC<SoundEditModalProps> = ({ sound, onClose,
     return () => window.removeEventListener('keydown', handleKeyDown);
   }, [isCapturingHotkey, onClose]);
 
+  const startMs = toMs(trimStart);
+  const endMs = toMs(trimEnd);
+  const trimError = endMs !== undefined && endMs <= (startMs ?? 0) ? 'End must be after the start' : null;
+
   const handleSave = () => {
+    if (trimError) return;
     onSave({
       name: name.trim() || sound.name,
       hotkey: hotkey.trim() || null,
       color,
+      start_ms: startMs || undefined,
+      end_ms: endMs,
     });
     onClose();
   };
@@ -138,6 +162,34 @@ export const SoundEditModal: React.FC<SoundEditModalProps> = ({ sound, onClose,
           <p className="text-xs text-text-secondary" style={{ margin: '4px' }}>Click the field and press any key combination</p>
         </div>
 
+        {/* Trim */}
+        <div style={{ marginBottom: '20px' }}>
+          <label className="block text-sm text-text-secondary" style={{ marginBottom: '6px' }}>Trim (seconds)</label>
+          <div className="flex gap-2">
+            <input
+              type="number"
+              min={0}
+              step={0.1}
+              value={trimStart}
+              onChange={(e) => setTrimStart(e.target.value)}
+              className="flex-1 min-w-0 px-3 py-2 bg-bg-tertiary border border-border rounded-lg text-text-primary focus:outline-none focus:ring-2 focus:ring-accent-purple"
+              placeholder="Start"
+            />
+            <input
+              type="number"
+              min={0}
+              step={0.1}
+              value={trimEnd}
+              onChange={(e) => setTrimEnd(e.target.value)}
+              className="flex-1 min-w-0 px-3 py-2 bg-bg-tertiary border border-border rounded-lg text-text-primary focus:outline-none focus:ring-2 focus:ring-accent-purple"
+              placeholder="End"
+            />
+          </div>
+          <p className={`text-xs ${trimError ? 'text-accent-red' : 'text-text-secondary'}`} style={{ margin: '4px' }}>
+            {trimError ?? 'Play only part of the file; leave empty for the whole file'}
+          </p>
+        </div>
+
         {/* Color Picker */}
         <div style={{ marginBottom: '24px' }}>
           <label className="block text-sm text-text-secondary" style={{ marginBottom: '6px' }}>Color</label>
@@ -180,7 +232,8 @@ export const SoundEditModal: React.FC<SoundEditModalProps> = ({ sound, onClose,
           </button>
           <button
             onClick={handleSave}
-            className="flex-1 px-4 py-2 bg-accent-purple rounded-lg text-white font-medium hover:brightness-110 transition-all"
+            disabled={trimError !== null}
+            className="flex-1 px-4 py-2 bg-accent-purple rounded-lg text-white font-medium hover:brightness-110 transition-all disabled:opacity-50 disabled:cursor-not-allowed"
           >
             Save
           </button>
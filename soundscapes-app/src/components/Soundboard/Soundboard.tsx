@@ -1,7 +1,7 @@
 import React, { useState, useEffect } from 'react';
 import { Volume2, Settings } from 'lucide-react';
 import { useSoundboardStore } from '../../stores/soundboardStore';
-import { SoundEditModal } from './SoundEditModal';
+import { SoundEditModal, SoundEditUpdates } from './SoundEditModal';
 import { SoundboardSound } from '../../types';
 
 export const Soundboard: React.FC = () => {
@@ -42,7 +42,7 @@ export const Soundboard: React.FC = () => {
     return () => window.removeEventListener('keydown', handleKeyDown);
   }, [sounds, playSoundByHotkey, editingSound]);
 
-  const handleEditSave = (updates: { name: string; hotkey: string | null; color: string }) => {
+  const handleEditSave = (updates: SoundEditUpdates) => {
     if (editingSound) {
       updateSound(editingSound.id, updates);
     }
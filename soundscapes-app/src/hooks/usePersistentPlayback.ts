@@ -5,32 +5,15 @@ import { useSchedulerStore } from '../stores/schedulerStore';
 /**
  * Hook that syncs frontend state with backend for both music playlists and ambient schedules.
  * All playback logic (auto-advance, scheduler tick, preset loading) is handled by the Rust backend.
- * This hook only polls the backend to keep the UI in sync.
+ * Playlist changes arrive as events (see useStateEvents); the scheduler is still polled.
  */
 export function usePersistentPlayback() {
   const { syncWithBackend: syncPlaylist } = usePlaylistStore();
   const { syncWithBackend: syncScheduler } = useSchedulerStore();
 
-  // Sync playlist state with backend periodically (for current track info updates)
+  // Read the playlist state once; state://playlist-changed keeps it current from then on
   useEffect(() => {
-    let mounted = true;
-    
-    const poll = async () => {
-      if (!mounted) return;
-      
-      try {
-        await syncPlaylist();
-      } catch {
-        // Ignore errors during polling
-      }
-      
-      if (mounted) {
-        setTimeout(poll, 500);
-      }
-    };
-    
-    poll();
-    return () => { mounted = false; };
+    syncPlaylist();
   }, [syncPlaylist]);
 
   // Sync scheduler state with backend every second
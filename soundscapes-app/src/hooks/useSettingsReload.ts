@@ -0,0 +1,28 @@
+import { useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { useSettingsStore } from '../stores/settingsStore';
+import { useAudioStore } from '../stores/audioStore';
+import { SettingsChanged } from '../types';
+
+const VOLUME_KEYS = ['master_volume', 'music_volume', 'ambient_volume', 'soundboard_volume'];
+
+/**
+ * Hook that picks up settings.json edits made outside the app (by hand or by a sync tool).
+ * The backend has already applied the engine settings; this updates the window's settings
+ * and volume sliders, and effects that depend on settings re-run as usual.
+ */
+export function useSettingsReload(onChange?: (change: SettingsChanged) => void) {
+  useEffect(() => {
+    const unlistenPromise = listen<SettingsChanged>('settings://changed', (event) => {
+      const { settings, changed } = event.payload;
+      useSettingsStore.setState({ settings });
+      if (changed.some((key) => VOLUME_KEYS.includes(key))) {
+        useAudioStore.getState().loadVolumesFromSettings(settings);
+      }
+      onChange?.(event.payload);
+    });
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+}
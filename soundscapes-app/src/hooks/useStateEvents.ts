@@ -0,0 +1,27 @@
+import { useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { usePlaylistStore, BackendPlaylistState } from '../stores/playlistStore';
+import { useAmbientStore, AmbientChanged } from '../stores/ambientStore';
+import { usePresetStore } from '../stores/presetStore';
+
+/**
+ * Hook that keeps this window's playlist, ambient and preset state in step with the backend.
+ * The backend emits an event whenever that state changes, from any window, the scheduler or a
+ * remote control, so windows only need to read it once when they open.
+ */
+export function useStateEvents() {
+  useEffect(() => {
+    const unlistenPromises = [
+      listen<BackendPlaylistState>('state://playlist-changed', (event) => {
+        usePlaylistStore.getState().applyBackendState(event.payload);
+      }),
+      listen<AmbientChanged>('state://ambient-changed', (event) => {
+        useAmbientStore.getState().applyActiveAmbients(event.payload.ambients);
+        usePresetStore.setState({ currentPresetId: event.payload.current_preset_id });
+      }),
+    ];
+    return () => {
+      unlistenPromises.forEach((promise) => promise.then((unlisten) => unlisten()));
+    };
+  }, []);
+}
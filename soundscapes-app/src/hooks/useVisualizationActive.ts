@@ -0,0 +1,28 @@
+import { useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * Hook for components that draw the spectrum. The backend only runs spectrum analysis while some
+ * window reports an active visualizer, so this reports one while the component is mounted and its
+ * window is visible (a minimized or hidden window doesn't need the data).
+ */
+export function useVisualizationActive() {
+  useEffect(() => {
+    let active = false;
+    const report = (next: boolean) => {
+      if (next === active) return;
+      active = next;
+      invoke('set_visualization_active', { active: next }).catch(() => {
+        // Ignore errors - analysis just keeps its current state
+      });
+    };
+    const onVisibilityChange = () => report(document.visibilityState === 'visible');
+
+    onVisibilityChange();
+    document.addEventListener('visibilitychange', onVisibilityChange);
+    return () => {
+      document.removeEventListener('visibilitychange', onVisibilityChange);
+      report(false);
+    };
+  }, []);
+}
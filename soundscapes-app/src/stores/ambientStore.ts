@@ -1,9 +1,10 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
-import { AmbientCategory, AmbientSound, AmbientSoundDef, DEFAULT_AMBIENT_SETTINGS } from '../types';
+import { AmbientCategory, AmbientSound, AmbientSoundDef, CategoryVolume, DEFAULT_AMBIENT_SETTINGS } from '../types';
+import { joinPath } from '../utils/path';
 
-// Backend response for active ambient info
-interface ActiveAmbientInfo {
+// Backend response for active ambient info (also the state://ambient-changed payload)
+export interface ActiveAmbientInfo {
   id: string;
   file_a: string;
   file_b: string;
@@ -19,7 +20,24 @@ interface ActiveAmbientInfo {
     pause_min: number;
     pause_max: number;
     volume_variation: number;
+    event_probability: number;
+    sync_group?: string | null;
+    random_start?: boolean;
+    duck_music?: boolean;
   };
+  phase?: AmbientPhase; // only from get_active_ambients
+}
+
+// Where a sound is in its A/B cycle
+export interface AmbientPhase {
+  state: 'playing_a' | 'playing_b' | 'paused';
+  next_cycle_secs?: number | null; // pause left while paused; none while its sync group waits
+}
+
+// state://ambient-changed payload
+export interface AmbientChanged {
+  ambients: ActiveAmbientInfo[];
+  current_preset_id: string | null;
 }
 
 interface AmbientState {
@@ -28,18 +46,31 @@ interface AmbientState {
   isLoading: boolean;
   expandedCategories: Set<string>;
   hideUnselected: boolean;
+  isPaused: boolean; // every ambient sound frozen where it was
+  categoryVolumes: Record<string, CategoryVolume>; // by category folder name
+  phases: Record<string, AmbientPhase>; // by sound id, refreshed by loadPhases
   
   loadCategories: (folderPath: string) => Promise<void>;
   syncActiveFromBackend: () => Promise<void>;
+  loadPhases: () => Promise<void>;
+  applyActiveAmbients: (activeInfos: ActiveAmbientInfo[]) => void;
   toggleSound: (categoryPath: string, sound: AmbientSoundDef, categoryName: string) => void;
   loadSoundWithSettings: (sound: AmbientSound) => Promise<void>;
+  applySounds: (sounds: AmbientSound[]) => Promise<void>;
   updateSoundSettings: (soundId: string, settings: Partial<AmbientSound>) => void;
+  setSoundIntensity: (soundId: string, intensity: number) => Promise<void>;
+  setSoundSyncGroup: (soundId: string, group: string | null) => Promise<void>;
+  swapSoundFiles: (soundId: string, categoryPath: string, target: AmbientSoundDef, crossfadeMs: number) => Promise<void>;
   resetSoundToDefaults: (soundId: string, soundDef: AmbientSoundDef) => void;
   toggleCategory: (categoryName: string) => void;
   selectAllInCategory: (categoryPath: string, sounds: AmbientSoundDef[], categoryName: string) => void;
   deselectAllInCategory: (categoryName: string) => void;
   setHideUnselected: (hide: boolean) => void;
   clearAll: () => void;
+  togglePaused: () => Promise<void>;
+  loadCategoryVolumes: (volumes: Record<string, CategoryVolume>) => void;
+  setCategoryVolume: (categoryId: string, volume: number) => void;
+  toggleCategoryMute: (categoryId: string) => void;
   transitionToSounds: (newSounds: AmbientSound[]) => Promise<void>;
   prepareFadeOut: (nextSoundIds: Set<string>) => Promise<void>;
 }
@@ -50,6 +81,9 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
   isLoading: false,
   expandedCategories: new Set(),
   hideUnselected: false,
+  isPaused: false,
+  categoryVolumes: {},
+  phases: {},
   
   loadCategories: async (folderPath: string) => {
     set({ isLoading: true });
@@ -61,8 +95,8 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
       const allPaths: string[] = [];
       for (const category of categories) {
         for (const sound of category.sounds) {
-          allPaths.push(`${category.path}/${sound.files.a}`);
-          allPaths.push(`${category.path}/${sound.files.b}`);
+          allPaths.push(joinPath(category.path, sound.files.a));
+          allPaths.push(joinPath(category.path, sound.files.b));
         }
       }
       if (allPaths.length > 0) {
@@ -80,56 +114,78 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
     // Query backend for currently playing ambient sounds and restore UI state
     try {
       const activeInfos = await invoke<ActiveAmbientInfo[]>('get_active_ambients');
-      const { categories } = get();
-      const newActiveSounds = new Map<string, AmbientSound>();
-      
+      get().applyActiveAmbients(activeInfos);
+    } catch (error) {
+      console.warn('Failed to sync active ambients from backend:', error);
+    }
+  },
+  
+  // Only the cycle phases; the sounds themselves come from state://ambient-changed
+  loadPhases: async () => {
+    try {
+      const activeInfos = await invoke<ActiveAmbientInfo[]>('get_active_ambients');
+      const phases: Record<string, AmbientPhase> = {};
       for (const info of activeInfos) {
-        // Find the sound definition in categories to get name and category info
-        let soundName = info.id;
-        let categoryId = '';
-        let categoryPath = '';
-        let filesA = '';
-        let filesB = '';
-        
-        for (const category of categories) {
-          const soundDef = category.sounds.find(s => s.id === info.id);
-          if (soundDef) {
-            soundName = soundDef.name;
-            categoryId = category.name;
-            categoryPath = category.path;
-            filesA = soundDef.files.a;
-            filesB = soundDef.files.b;
-            break;
-          }
+        if (info.phase) phases[info.id] = info.phase;
+      }
+      set({ phases });
+    } catch (error) {
+      console.warn('Failed to load ambient phases:', error);
+    }
+  },
+
+  applyActiveAmbients: (activeInfos: ActiveAmbientInfo[]) => {
+    const { categories } = get();
+    const newActiveSounds = new Map<string, AmbientSound>();
+    
+    for (const info of activeInfos) {
+      // Find the sound definition in categories to get name and category info
+      let soundName = info.id;
+      let categoryId = '';
+      let categoryPath = '';
+      let filesA = '';
+      let filesB = '';
+      
+      for (const category of categories) {
+        const soundDef = category.sounds.find(s => s.id === info.id);
+        if (soundDef) {
+          soundName = soundDef.name;
+          categoryId = category.name;
+          categoryPath = category.path;
+          filesA = soundDef.files.a;
+          filesB = soundDef.files.b;
+          break;
         }
-        
-        newActiveSounds.set(info.id, {
-          id: info.id,
-          name: soundName,
-          categoryId,
-          categoryPath,
-          filesA,
-          filesB,
-          enabled: true,
-          volume: Math.round(info.settings.volume * 100), // Convert 0-1 to 0-100
-          pitch: info.settings.pitch,
-          pan: Math.round(info.settings.pan * 100), // Convert -1..1 to -100..100
-          lowPassFreq: info.settings.low_pass_freq,
-          reverbType: info.settings.reverb_type as AmbientSound['reverbType'],
-          algorithmicReverb: Math.round(info.settings.algorithmic_reverb * 100), // Convert 0-1 to 0-100
-          repeatRangeMin: info.settings.repeat_min,
-          repeatRangeMax: info.settings.repeat_max,
-          pauseRangeMin: info.settings.pause_min,
-          pauseRangeMax: info.settings.pause_max,
-          volumeVariation: Math.round(info.settings.volume_variation * 100), // Convert 0-0.5 to 0-50
-        });
       }
       
-      // Always update - this handles both adding sounds AND clearing when empty
-      set({ activeSounds: newActiveSounds });
-    } catch (error) {
-      console.warn('Failed to sync active ambients from backend:', error);
+      newActiveSounds.set(info.id, {
+        id: info.id,
+        name: soundName,
+        categoryId,
+        categoryPath,
+        filesA,
+        filesB,
+        enabled: true,
+        volume: Math.round(info.settings.volume * 100), // Convert 0-1 to 0-100
+        pitch: info.settings.pitch,
+        pan: Math.round(info.settings.pan * 100), // Convert -1..1 to -100..100
+        lowPassFreq: info.settings.low_pass_freq,
+        reverbType: info.settings.reverb_type as AmbientSound['reverbType'],
+        algorithmicReverb: Math.round(info.settings.algorithmic_reverb * 100), // Convert 0-1 to 0-100
+        repeatRangeMin: info.settings.repeat_min,
+        repeatRangeMax: info.settings.repeat_max,
+        pauseRangeMin: info.settings.pause_min,
+        pauseRangeMax: info.settings.pause_max,
+        volumeVariation: Math.round(info.settings.volume_variation * 100), // Convert 0-0.5 to 0-50
+        eventProbability: Math.round(info.settings.event_probability * 100), // Convert 0-1 to 0-100
+        syncGroup: info.settings.sync_group ?? undefined,
+        randomStart: info.settings.random_start,
+        duckMusic: info.settings.duck_music,
+      });
     }
+    
+    // Always update - this handles both adding sounds AND clearing when empty
+    set({ activeSounds: newActiveSounds });
   },
   
   toggleSound: async (categoryPath: string, soundDef: AmbientSoundDef, categoryName: string) => {
@@ -159,12 +215,13 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
         pauseRangeMin: soundDef.defaults?.pauseRangeMin ?? DEFAULT_AMBIENT_SETTINGS.pauseRangeMin,
         pauseRangeMax: soundDef.defaults?.pauseRangeMax ?? DEFAULT_AMBIENT_SETTINGS.pauseRangeMax,
         volumeVariation: soundDef.defaults?.volumeVariation ?? DEFAULT_AMBIENT_SETTINGS.volumeVariation,
+        eventProbability: soundDef.defaults?.eventProbability ?? DEFAULT_AMBIENT_SETTINGS.eventProbability,
       };
       
       newActiveSounds.set(soundDef.id, sound);
       
-      const fileA = `${categoryPath}/${soundDef.files.a}`;
-      const fileB = `${categoryPath}/${soundDef.files.b}`;
+      const fileA = joinPath(categoryPath, soundDef.files.a);
+      const fileB = joinPath(categoryPath, soundDef.files.b);
       await invoke('play_ambient', {
         id: soundDef.id,
         fileA,
@@ -180,6 +237,7 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
         pauseMin: sound.pauseRangeMin,
         pauseMax: sound.pauseRangeMax,
         volumeVariation: sound.volumeVariation / 100, // Convert 0-50 to 0-0.5
+        eventProbability: sound.eventProbability / 100, // Convert 0-100 to 0-1
       });
     }
     
@@ -196,8 +254,8 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
     const newActiveSounds = new Map(activeSounds);
     newActiveSounds.set(sound.id, sound);
     
-    const fileA = `${sound.categoryPath}/${sound.filesA}`;
-    const fileB = `${sound.categoryPath}/${sound.filesB}`;
+    const fileA = joinPath(sound.categoryPath, sound.filesA);
+    const fileB = joinPath(sound.categoryPath, sound.filesB);
     
     await invoke('play_ambient', {
       id: sound.id,
@@ -214,11 +272,44 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
       pauseMin: sound.pauseRangeMin,
       pauseMax: sound.pauseRangeMax,
       volumeVariation: sound.volumeVariation / 100,
+      eventProbability: sound.eventProbability / 100,
+      syncGroup: sound.syncGroup ?? null,
+      randomStart: sound.randomStart ?? false,
+      duckMusic: sound.duckMusic ?? false,
     });
     
     set({ activeSounds: newActiveSounds });
   },
   
+  // Replace the active sounds with a whole set (used for preset loading). All sounds go to the backend
+  // in one call and crossfade in together; shared sounds keep playing and sounds not in the set fade out.
+  applySounds: async (sounds: AmbientSound[]) => {
+    const infos: ActiveAmbientInfo[] = sounds.map(sound => ({
+      id: sound.id,
+      file_a: joinPath(sound.categoryPath, sound.filesA),
+      file_b: joinPath(sound.categoryPath, sound.filesB),
+      settings: {
+        volume: sound.volume / 100,
+        pitch: sound.pitch,
+        pan: sound.pan / 100,
+        low_pass_freq: sound.lowPassFreq,
+        reverb_type: sound.reverbType,
+        algorithmic_reverb: sound.algorithmicReverb / 100,
+        repeat_min: sound.repeatRangeMin,
+        repeat_max: sound.repeatRangeMax,
+        pause_min: sound.pauseRangeMin,
+        pause_max: sound.pauseRangeMax,
+        volume_variation: sound.volumeVariation / 100,
+        event_probability: sound.eventProbability / 100,
+        sync_group: sound.syncGroup ?? null,
+        random_start: sound.randomStart ?? false,
+        duck_music: sound.duckMusic ?? false,
+      },
+    }));
+    await invoke('apply_sounds', { sounds: infos });
+    set({ activeSounds: new Map(sounds.map(sound => [sound.id, sound])) });
+  },
+  
   updateSoundSettings: async (soundId: string, settings: Partial<AmbientSound>) => {
     const { activeSounds } = get();
     const sound = activeSounds.get(soundId);
@@ -242,12 +333,71 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
         pauseMin: updatedSound.pauseRangeMin,
         pauseMax: updatedSound.pauseRangeMax,
         volumeVariation: updatedSound.volumeVariation / 100,
+        eventProbability: updatedSound.eventProbability / 100,
+        randomStart: updatedSound.randomStart ?? false,
+        duckMusic: updatedSound.duckMusic ?? false,
       });
       
       set({ activeSounds: newActiveSounds });
     }
   },
   
+  // The backend maps the fader through the sound's intensity macro and returns the settings it set,
+  // so the other sliders follow
+  setSoundIntensity: async (soundId: string, intensity: number) => {
+    try {
+      const settings = await invoke<ActiveAmbientInfo['settings']>('set_ambient_intensity', { id: soundId, intensity });
+      const sound = get().activeSounds.get(soundId);
+      if (!sound) return;
+      const newActiveSounds = new Map(get().activeSounds);
+      newActiveSounds.set(soundId, {
+        ...sound,
+        intensity,
+        volume: Math.round(settings.volume * 100),
+        repeatRangeMin: settings.repeat_min,
+        repeatRangeMax: settings.repeat_max,
+        pauseRangeMin: settings.pause_min,
+        pauseRangeMax: settings.pause_max,
+        eventProbability: Math.round(settings.event_probability * 100),
+      });
+      set({ activeSounds: newActiveSounds });
+    } catch (error) {
+      console.error('Failed to set sound intensity:', error);
+    }
+  },
+
+  setSoundSyncGroup: async (soundId: string, group: string | null) => {
+    const sound = get().activeSounds.get(soundId);
+    if (!sound) return;
+    try {
+      await invoke('set_ambient_sync_group', { id: soundId, group });
+      const newActiveSounds = new Map(get().activeSounds);
+      newActiveSounds.set(soundId, { ...sound, syncGroup: group ?? undefined });
+      set({ activeSounds: newActiveSounds });
+    } catch (error) {
+      console.error('Failed to set sound sync group:', error);
+    }
+  },
+  
+  // Play another sound's files in this sound's place, keeping its settings
+  swapSoundFiles: async (soundId: string, categoryPath: string, target: AmbientSoundDef, crossfadeMs: number) => {
+    const sound = get().activeSounds.get(soundId);
+    if (!sound) return;
+    try {
+      await invoke('swap_ambient_files', {
+        id: soundId,
+        newA: joinPath(categoryPath, target.files.a),
+        newB: joinPath(categoryPath, target.files.b),
+        crossfadeMs,
+      });
+      const newActiveSounds = new Map(get().activeSounds);
+      newActiveSounds.set(soundId, { ...sound, categoryPath, filesA: target.files.a, filesB: target.files.b });
+      set({ activeSounds: newActiveSounds });
+    } catch (error) {
+      console.error('Failed to swap sound files:', error);
+    }
+  },
+  
   resetSoundToDefaults: async (soundId: string, soundDef: AmbientSoundDef) => {
     const { activeSounds } = get();
     const sound = activeSounds.get(soundId);
@@ -265,9 +415,10 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
         pauseRangeMin: soundDef.defaults?.pauseRangeMin ?? DEFAULT_AMBIENT_SETTINGS.pauseRangeMin,
         pauseRangeMax: soundDef.defaults?.pauseRangeMax ?? DEFAULT_AMBIENT_SETTINGS.pauseRangeMax,
         volumeVariation: soundDef.defaults?.volumeVariation ?? DEFAULT_AMBIENT_SETTINGS.volumeVariation,
+        eventProbability: soundDef.defaults?.eventProbability ?? DEFAULT_AMBIENT_SETTINGS.eventProbability,
       };
       
-      const updatedSound = { ...sound, ...defaultSettings };
+      const updatedSound = { ...sound, ...defaultSettings, intensity: undefined };
       const newActiveSounds = new Map(activeSounds);
       newActiveSounds.set(soundId, updatedSound);
       
@@ -285,6 +436,9 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
         pauseMin: updatedSound.pauseRangeMin,
         pauseMax: updatedSound.pauseRangeMax,
         volumeVariation: updatedSound.volumeVariation / 100,
+        eventProbability: updatedSound.eventProbability / 100,
+        randomStart: updatedSound.randomStart ?? false,
+        duckMusic: updatedSound.duckMusic ?? false,
       });
       
       set({ activeSounds: newActiveSounds });
@@ -332,12 +486,13 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
         pauseRangeMin: soundDef.defaults?.pauseRangeMin ?? DEFAULT_AMBIENT_SETTINGS.pauseRangeMin,
         pauseRangeMax: soundDef.defaults?.pauseRangeMax ?? DEFAULT_AMBIENT_SETTINGS.pauseRangeMax,
         volumeVariation: soundDef.defaults?.volumeVariation ?? DEFAULT_AMBIENT_SETTINGS.volumeVariation,
+        eventProbability: soundDef.defaults?.eventProbability ?? DEFAULT_AMBIENT_SETTINGS.eventProbability,
       };
       
       newActiveSounds.set(soundDef.id, sound);
       
-      const fileA = `${categoryPath}/${soundDef.files.a}`;
-      const fileB = `${categoryPath}/${soundDef.files.b}`;
+      const fileA = joinPath(categoryPath, soundDef.files.a);
+      const fileB = joinPath(categoryPath, soundDef.files.b);
       await invoke('play_ambient', {
         id: soundDef.id,
         fileA,
@@ -353,6 +508,7 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
         pauseMin: sound.pauseRangeMin,
         pauseMax: sound.pauseRangeMax,
         volumeVariation: sound.volumeVariation / 100,
+        eventProbability: sound.eventProbability / 100,
       });
       
       // Small delay between sounds to prevent audio buffer overload
@@ -388,6 +544,34 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
     set({ activeSounds: new Map() });
   },
   
+  togglePaused: async () => {
+    const isPaused = !get().isPaused;
+    try {
+      await invoke(isPaused ? 'pause_all_ambient' : 'resume_all_ambient');
+      set({ isPaused });
+    } catch (error) {
+      console.error('Failed to pause or resume ambient sounds:', error);
+    }
+  },
+  
+  loadCategoryVolumes: (volumes: Record<string, CategoryVolume>) => {
+    set({ categoryVolumes: volumes });
+  },
+  
+  // The backend saves category volumes to the settings itself
+  setCategoryVolume: (categoryId: string, volume: number) => {
+    const current = get().categoryVolumes[categoryId] ?? { volume: 100, muted: false };
+    set({ categoryVolumes: { ...get().categoryVolumes, [categoryId]: { ...current, volume } } });
+    invoke('set_category_volume', { categoryId, volume: volume / 100 }).catch(console.error);
+  },
+  
+  toggleCategoryMute: (categoryId: string) => {
+    const current = get().categoryVolumes[categoryId] ?? { volume: 100, muted: false };
+    const muted = !current.muted;
+    set({ categoryVolumes: { ...get().categoryVolumes, [categoryId]: { ...current, muted } } });
+    invoke('set_category_muted', { categoryId, muted }).catch(console.error);
+  },
+  
   // Smart transition for SCHEDULER: uses 2000ms fades for smooth preset transitions
   // stop sounds not in new preset, start sounds not already playing, 
   // update settings for shared sounds, keep common sounds playing continuously
@@ -441,6 +625,10 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
         pauseMin: sound.pauseRangeMin,
         pauseMax: sound.pauseRangeMax,
         volumeVariation: sound.volumeVariation / 100,
+        eventProbability: sound.eventProbability / 100,
+        syncGroup: sound.syncGroup ?? '',
+        randomStart: sound.randomStart ?? false,
+        duckMusic: sound.duckMusic ?? false,
       });
     }
     
@@ -449,8 +637,8 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
       const sound = soundsToStart[i];
       newActiveSounds.set(sound.id, sound);
       
-      const fileA = `${sound.categoryPath}/${sound.filesA}`;
-      const fileB = `${sound.categoryPath}/${sound.filesB}`;
+      const fileA = joinPath(sound.categoryPath, sound.filesA);
+      const fileB = joinPath(sound.categoryPath, sound.filesB);
       
       await invoke('play_ambient_scheduler', {
         id: sound.id,
@@ -467,6 +655,10 @@ export const useAmbientStore = create<AmbientState>((set, get) => ({
         pauseMin: sound.pauseRangeMin,
         pauseMax: sound.pauseRangeMax,
         volumeVariation: sound.volumeVariation / 100,
+        eventProbability: sound.eventProbability / 100,
+        syncGroup: sound.syncGroup ?? null,
+        randomStart: sound.randomStart ?? false,
+        duckMusic: sound.duckMusic ?? false,
       });
       
       // Small delay between sounds to prevent audio buffer overload
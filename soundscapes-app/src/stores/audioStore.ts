@@ -63,6 +63,7 @@ export const useAudioStore = create<AudioState>((set, get) => ({
     });
     // Sync soundboard volume to backend
     invoke('set_soundboard_volume', { volume: soundboardVol / 100 }).catch(console.error);
+    invoke('set_category_volumes', { volumes: settings.ambient_category_volumes ?? {} }).catch(console.error);
   },
   
   setMasterVolume: (volume: number, save = true) => {
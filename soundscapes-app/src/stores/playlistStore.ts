@@ -1,6 +1,7 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
 import { MusicAlbum } from '../types';
+import { joinPath } from '../utils/path';
 
 // Types matching the Rust backend
 interface PlaylistTrack {
@@ -10,6 +11,8 @@ interface PlaylistTrack {
   artist: string;
   album: string;
   albumPath: string;
+  mood?: string | null;
+  energy?: number | null;
 }
 
 interface MusicPlaylist {
@@ -17,9 +20,13 @@ interface MusicPlaylist {
   name: string;
   isAuto: boolean;
   tracks: PlaylistTrack[];
+  crossfadeDuration?: number | null;
+  shuffleDefault?: boolean | null;
+  volumeOffset?: number | null;
 }
 
-interface BackendPlaylistState {
+// Backend playlist state (also the state://playlist-changed payload)
+export interface BackendPlaylistState {
   currentPlaylistId: string | null;
   currentIndex: number;
   isShuffled: boolean;
@@ -47,8 +54,10 @@ interface PlaylistState {
   // Actions
   loadAlbums: (folderPath: string) => Promise<void>;
   syncWithBackend: () => Promise<void>;
+  applyBackendState: (backendState: BackendPlaylistState) => void;
   playTrack: (track: PlaylistTrack) => Promise<void>;
   playTrackFromPlaylist: (playlistId: string, index: number) => Promise<void>;
+  playAlbum: (albumName: string, startTrackId?: string) => Promise<void>;
   playNext: () => Promise<void>;
   playPrevious: () => Promise<void>;
   setCurrentPlaylist: (playlistId: string | null) => Promise<void>;
@@ -103,6 +112,8 @@ export const usePlaylistStore = create<PlaylistState>((set, get) => ({
             artist: track.artist,
             album: album.name,
             albumPath: album.path,
+            mood: track.mood,
+            energy: track.energy,
           });
         });
       });
@@ -154,29 +165,33 @@ export const usePlaylistStore = create<PlaylistState>((set, get) => ({
   syncWithBackend: async () => {
     try {
       const backendState = await invoke<BackendPlaylistState>('get_playlist_state');
-      const { allTracks } = get();
-      
-      // Rebuild favorites playlist
-      const favoriteTracks = allTracks.filter(t => backendState.favorites.includes(t.id));
-      
-      set(state => ({
-        currentPlaylistId: backendState.currentPlaylistId,
-        currentIndex: backendState.currentIndex,
-        isShuffled: backendState.isShuffled,
-        isLooping: backendState.isLooping,
-        favorites: new Set(backendState.favorites),
-        interruptedIndex: backendState.interruptedIndex,
-        playlists: state.playlists.map(p => 
-          p.id === 'favorites' ? { ...p, tracks: favoriteTracks } : p
-        ),
-      }));
+      get().applyBackendState(backendState);
     } catch (error) {
       console.error('Error syncing with backend:', error);
     }
   },
+
+  applyBackendState: (backendState: BackendPlaylistState) => {
+    const { allTracks } = get();
+    
+    // Rebuild favorites playlist
+    const favoriteTracks = allTracks.filter(t => backendState.favorites.includes(t.id));
+    
+    set(state => ({
+      currentPlaylistId: backendState.currentPlaylistId,
+      currentIndex: backendState.currentIndex,
+      isShuffled: backendState.isShuffled,
+      isLooping: backendState.isLooping,
+      favorites: new Set(backendState.favorites),
+      interruptedIndex: backendState.interruptedIndex,
+      playlists: state.playlists.map(p => 
+        p.id === 'favorites' ? { ...p, tracks: favoriteTracks } : p
+      ),
+    }));
+  },
   
   playTrack: async (track: PlaylistTrack) => {
-    const filePath = `${track.albumPath}/${track.file}`;
+    const filePath = joinPath(track.albumPath, track.file);
     
     try {
       await invoke('play_music', { 
@@ -194,28 +209,18 @@ export const usePlaylistStore = create<PlaylistState>((set, get) => ({
   playTrackFromPlaylist: async (playlistId: string, index: number) => {
     const { playlists, albums } = get();
     
-    // Get tracks from playlist or album
-    let tracks: PlaylistTrack[] = [];
+    // Albums are resolved by the backend
     if (playlistId.startsWith('album-')) {
       const albumName = playlistId.replace('album-', '');
-      const album = albums.find(a => a.name === albumName);
-      if (album) {
-        tracks = album.tracks.map(t => ({
-          id: t.id,
-          file: t.file,
-          title: t.title,
-          artist: t.artist,
-          album: album.name,
-          albumPath: album.path,
-        }));
-      }
-    } else {
-      const playlist = playlists.find(p => p.id === playlistId);
-      if (playlist) {
-        tracks = playlist.tracks;
+      const track = albums.find(a => a.name === albumName)?.tracks[index];
+      if (track) {
+        await get().playAlbum(albumName, track.id);
       }
+      return;
     }
     
+    const tracks: PlaylistTrack[] = playlists.find(p => p.id === playlistId)?.tracks ?? [];
+    
     if (tracks.length === 0 || index < 0 || index >= tracks.length) return;
     
     await invoke('set_current_playlist', { playlistId });
@@ -226,6 +231,16 @@ export const usePlaylistStore = create<PlaylistState>((set, get) => ({
     await get().playTrack(tracks[index]);
   },
   
+  playAlbum: async (albumName: string, startTrackId?: string) => {
+    try {
+      await invoke('play_album', { albumName, startTrackId: startTrackId ?? null });
+      // Backend sets the album as current playlist and index
+      await get().syncWithBackend();
+    } catch (error) {
+      console.error('Error playing album:', error);
+    }
+  },
+  
   playNext: async () => {
     const { playNextQueue } = get();
     
@@ -261,6 +276,8 @@ export const usePlaylistStore = create<PlaylistState>((set, get) => ({
   setCurrentPlaylist: async (playlistId: string | null) => {
     await invoke('set_current_playlist', { playlistId });
     set({ currentPlaylistId: playlistId, currentIndex: 0, interruptedIndex: null });
+    // Playlist may carry its own shuffle default
+    await get().syncWithBackend();
   },
   
   toggleShuffle: async () => {
@@ -1,6 +1,7 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
 import { AmbientSound } from '../types';
+import { isAppError } from '../utils/errors';
 
 export interface PresetInfo {
   id: string;
@@ -8,6 +9,7 @@ export interface PresetInfo {
   created: string;
   modified: string;
   soundCount: number;
+  etag: string; // changes with every write of the file
 }
 
 export interface PresetSound {
@@ -28,6 +30,11 @@ export interface PresetSound {
   pauseRangeMin: number;
   pauseRangeMax: number;
   volumeVariation: number;
+  eventProbability?: number;
+  intensity?: number;
+  syncGroup?: string;
+  randomStart?: boolean;
+  duckMusic?: boolean;
 }
 
 export interface SoundscapePreset {
@@ -71,6 +78,11 @@ function ambientToPresetSound(sound: AmbientSound): PresetSound {
     pauseRangeMin: sound.pauseRangeMin,
     pauseRangeMax: sound.pauseRangeMax,
     volumeVariation: sound.volumeVariation,
+    eventProbability: sound.eventProbability,
+    intensity: sound.intensity,
+    syncGroup: sound.syncGroup,
+    randomStart: sound.randomStart,
+    duckMusic: sound.duckMusic,
   };
 }
 
@@ -92,7 +104,11 @@ export const usePresetStore = create<PresetState>((set, get) => ({
   
   savePreset: async (name: string, sounds: Map<string, AmbientSound>) => {
     const presetSounds = Array.from(sounds.values()).map(ambientToPresetSound);
-    const result = await invoke<PresetInfo>('save_preset', { name, sounds: presetSounds });
+    const result = await invoke<PresetInfo>('save_preset', { name, sounds: presetSounds }).catch(async (error) => {
+      // Changed on another device: reload the list so the other version shows
+      if (isAppError(error) && error.kind === 'conflict') await get().loadPresets();
+      throw error;
+    });
     
     // Refresh the preset list
     await get().loadPresets();
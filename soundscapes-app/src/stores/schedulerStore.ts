@@ -1,6 +1,7 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
-import { ScheduledItem, SchedulePreset, SchedulePresetInfo } from '../types';
+import { ScheduledEvent, ScheduledItem, SchedulePreset, SchedulePresetInfo, TransitionChime } from '../types';
+import { isAppError } from '../utils/errors';
 
 interface SchedulerState {
   // Schedule presets management
@@ -10,6 +11,7 @@ interface SchedulerState {
   
   // Current editing state
   editingItems: ScheduledItem[];
+  editingTransition: TransitionChime | null;
   hasUnsavedChanges: boolean;
   
   // Playback state (synced from backend)
@@ -28,6 +30,9 @@ interface SchedulerState {
   addItem: (presetId: string, presetName: string) => void;
   removeItem: (itemId: string) => void;
   updateItemTiming: (itemId: string, minMinutes: number, maxMinutes: number) => void;
+  updateItemAnnouncement: (itemId: string, announcement: string) => void;
+  updateItemEvents: (itemId: string, events: ScheduledEvent[]) => void;
+  updateTransition: (transition: TransitionChime | null) => void;
   reorderItems: (fromIndex: number, toIndex: number) => void;
   clearItems: () => void;
   setCurrentScheduleId: (id: string | null) => void;
@@ -53,6 +58,7 @@ export const useSchedulerStore = create<SchedulerState>((set, get) => ({
   isLoading: false,
   
   editingItems: [],
+  editingTransition: null,
   hasUnsavedChanges: false,
   
   isPlaying: false,
@@ -72,10 +78,15 @@ export const useSchedulerStore = create<SchedulerState>((set, get) => ({
   },
   
   saveSchedule: async (name: string) => {
-    const { editingItems } = get();
+    const { editingItems, editingTransition } = get();
     const result = await invoke<SchedulePresetInfo>('save_schedule', { 
       name, 
-      items: editingItems 
+      items: editingItems,
+      transition: editingTransition,
+    }).catch(async (error) => {
+      // Changed on another device: show that version in the list, keep the edits unsaved
+      if (isAppError(error) && error.kind === 'conflict') await get().loadSchedules();
+      throw error;
     });
     
     // Refresh the list
@@ -90,6 +101,7 @@ export const useSchedulerStore = create<SchedulerState>((set, get) => ({
     set({ 
       currentScheduleId: id,
       editingItems: schedule.items,
+      editingTransition: schedule.transition ?? null,
       hasUnsavedChanges: false,
     });
     return schedule;
@@ -103,6 +115,7 @@ export const useSchedulerStore = create<SchedulerState>((set, get) => ({
       set({ 
         currentScheduleId: null,
         editingItems: [],
+        editingTransition: null,
         hasUnsavedChanges: false,
         isPlaying: false,
       });
@@ -139,6 +152,32 @@ export const useSchedulerStore = create<SchedulerState>((set, get) => ({
     });
   },
   
+  updateItemAnnouncement: (itemId: string, announcement: string) => {
+    const { editingItems } = get();
+    set({
+      editingItems: editingItems.map(item =>
+        item.id === itemId
+          ? { ...item, announcement: announcement || null }
+          : item
+      ),
+      hasUnsavedChanges: true,
+    });
+  },
+
+  updateItemEvents: (itemId: string, events: ScheduledEvent[]) => {
+    const { editingItems } = get();
+    set({
+      editingItems: editingItems.map(item =>
+        item.id === itemId ? { ...item, events } : item
+      ),
+      hasUnsavedChanges: true,
+    });
+  },
+
+  updateTransition: (transition: TransitionChime | null) => {
+    set({ editingTransition: transition, hasUnsavedChanges: true });
+  },
+
   updateItemTiming: (itemId: string, minMinutes: number, maxMinutes: number) => {
     const { editingItems } = get();
     set({
@@ -167,6 +206,7 @@ export const useSchedulerStore = create<SchedulerState>((set, get) => ({
   clearItems: () => {
     set({
       editingItems: [],
+      editingTransition: null,
       currentScheduleId: null,
       hasUnsavedChanges: false,
       isPlaying: false,
@@ -180,7 +220,7 @@ export const useSchedulerStore = create<SchedulerState>((set, get) => ({
   },
   
   startSchedule: async () => {
-    const { editingItems, currentScheduleId } = get();
+    const { editingItems, editingTransition, currentScheduleId } = get();
     if (editingItems.length === 0) return;
     
     try {
@@ -188,6 +228,7 @@ export const useSchedulerStore = create<SchedulerState>((set, get) => ({
       await invoke('start_scheduler_playback', {
         items: editingItems,
         scheduleId: currentScheduleId,
+        transition: editingTransition,
       });
       
       // Sync state from backend
@@ -221,6 +262,7 @@ export const useSchedulerStore = create<SchedulerState>((set, get) => ({
         timeRemaining: number;
         items: ScheduledItem[];
         currentScheduleId: string | null;
+        transition: TransitionChime | null;
       }>('get_scheduler_state');
       
       set({
@@ -231,6 +273,7 @@ export const useSchedulerStore = create<SchedulerState>((set, get) => ({
         // Only update editingItems if backend has items and we don't have local changes
         ...(backendState.items.length > 0 && !get().hasUnsavedChanges ? {
           editingItems: backendState.items,
+          editingTransition: backendState.transition,
           currentScheduleId: backendState.currentScheduleId,
         } : {}),
       });
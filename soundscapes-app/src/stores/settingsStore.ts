@@ -1,6 +1,7 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
 import { AppSettings, ActivePanel } from '../types';
+import { errorMessage } from '../utils/errors';
 
 interface SettingsState {
   settings: AppSettings | null;
@@ -34,7 +35,7 @@ export const useSettingsStore = create<SettingsState>((set, get) => ({
       // Return settings so caller can use them for volume initialization
       return settings;
     } catch (error) {
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
       return null;
     }
   },
@@ -44,7 +45,7 @@ export const useSettingsStore = create<SettingsState>((set, get) => ({
       await invoke('save_settings', { settings });
       set({ settings });
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
   
@@ -1,6 +1,7 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
 import { SoundboardSound, SoundboardData } from '../types';
+import { joinPath } from '../utils/path';
 
 interface SoundboardState {
   sounds: SoundboardSound[];
@@ -12,7 +13,7 @@ interface SoundboardState {
   playSound: (soundId: string) => void;
   playSoundByHotkey: (hotkey: string) => void;
   updateSoundVolume: (soundId: string, volume: number) => void;
-  updateSound: (soundId: string, updates: Partial<Pick<SoundboardSound, 'name' | 'hotkey' | 'color'>>) => Promise<void>;
+  updateSound: (soundId: string, updates: Partial<Pick<SoundboardSound, 'name' | 'hotkey' | 'color' | 'start_ms' | 'end_ms'>>) => Promise<void>;
 }
 
 export const useSoundboardStore = create<SoundboardState>((set, get) => ({
@@ -30,10 +31,12 @@ export const useSoundboardStore = create<SoundboardState>((set, get) => ({
         id: s.id,
         name: s.name,
         file: s.file,
-        filePath: `${folderPath}/${s.file}`,
+        filePath: joinPath(folderPath, s.file),
         volume: s.volume ?? 80,
         hotkey: s.hotkey ?? null,
         color: s.color ?? '#a287f4',
+        start_ms: s.start_ms ?? undefined,
+        end_ms: s.end_ms ?? undefined,
       }));
       
       set({ sounds, folderPath, isLoading: false });
@@ -63,7 +66,8 @@ export const useSoundboardStore = create<SoundboardState>((set, get) => ({
         // Play the soundboard sound (volume is 0-100, convert to 0-1)
         await invoke('play_soundboard', { 
           filePath: sound.filePath, 
-          volume: sound.volume / 100 
+          volume: sound.volume / 100,
+          trim: { start_ms: sound.start_ms, end_ms: sound.end_ms },
         });
         set({ currentlyPlaying: soundId });
         
@@ -115,7 +119,7 @@ export const useSoundboardStore = create<SoundboardState>((set, get) => ({
     }
   },
   
-  updateSound: async (soundId: string, updates: Partial<Pick<SoundboardSound, 'name' | 'hotkey' | 'color'>>) => {
+  updateSound: async (soundId: string, updates: Partial<Pick<SoundboardSound, 'name' | 'hotkey' | 'color' | 'start_ms' | 'end_ms'>>) => {
     const { sounds, folderPath } = get();
     const sound = sounds.find(s => s.id === soundId);
     if (!sound) return;
@@ -135,6 +139,10 @@ export const useSoundboardStore = create<SoundboardState>((set, get) => ({
         name: updates.name,
         hotkey: updates.hotkey,
         color: updates.color,
+        // The trim is replaced as a whole, so send both ends
+        trim: 'start_ms' in updates || 'end_ms' in updates
+          ? { start_ms: updates.start_ms, end_ms: updates.end_ms }
+          : undefined,
       });
     } catch (error) {
       console.error('Error saving soundboard sound:', error);
@@ -7,6 +7,8 @@ export interface MusicTrack {
   albumPath: string;
   duration?: number;
   favorite: boolean;
+  mood?: string | null;
+  energy?: number | null;
 }
 
 export interface MusicAlbum {
@@ -28,6 +30,24 @@ export interface AmbientSoundDefaults {
   pauseRangeMin: number;
   pauseRangeMax: number;
   volumeVariation: number;
+  eventProbability: number;
+}
+
+// One setting's value at intensity 0 and at intensity 100
+export interface IntensityRange {
+  low: number;
+  high: number;
+}
+
+// How a sound's intensity fader maps onto its settings; settings left out aren't touched
+export interface IntensityMacro {
+  curve?: number;
+  volume?: IntensityRange;
+  repeatRangeMin?: IntensityRange;
+  repeatRangeMax?: IntensityRange;
+  pauseRangeMin?: IntensityRange;
+  pauseRangeMax?: IntensityRange;
+  eventProbability?: IntensityRange;
 }
 
 export interface AmbientSoundFiles {
@@ -39,7 +59,9 @@ export interface AmbientSoundDef {
   id: string;
   name: string;
   files: AmbientSoundFiles;
-  defaults?: Partial<AmbientSoundDefaults>;
+  defaults?: Partial<AmbientSoundDefaults> & { intensityMacro?: IntensityMacro };
+  tags?: string[];
+  rating?: number; // 1 - 5 stars
 }
 
 export interface AmbientCategory {
@@ -57,6 +79,10 @@ export interface AmbientSound extends AmbientSoundDefaults {
   filesA: string;
   filesB: string;
   enabled: boolean;
+  intensity?: number; // Intensity fader position, once it has been moved
+  syncGroup?: string; // sounds in the same group pause and start their cycles together
+  randomStart?: boolean; // first file starts at a random point
+  duckMusic?: boolean; // lowers the music while audible
 }
 
 export interface SoundboardSound {
@@ -67,6 +93,8 @@ export interface SoundboardSound {
   volume: number;
   hotkey: string | null;
   color: string;
+  start_ms?: number; // Trim, as in metadata.json; unset plays from the start
+  end_ms?: number;   // unset plays to the end
 }
 
 export interface SoundboardData {
@@ -89,6 +117,204 @@ export interface SoundscapePreset {
   sounds: AmbientSound[];
 }
 
+export interface StreamingConfig {
+  enabled: boolean;
+  server_type: 'icecast' | 'shoutcast';
+  host: string;
+  port: number;
+  mount: string;
+  username: string;
+  password: string;
+  bitrate: number;
+  stream_name: string;
+}
+
+export interface ExternalPlayerConfig {
+  enabled: boolean;
+  player: 'mpd' | 'spotify';
+  mpd_host: string;
+  mpd_port: number;
+  mpd_password: string;
+  spotify_client_id: string;
+  spotify_refresh_token: string;
+}
+
+export interface ExternalPlayerStatus {
+  connected: boolean;
+  is_playing: boolean;
+  title: string | null;
+  artist: string | null;
+  volume: number | null;
+  error: string | null;
+}
+
+export interface StreamingStatus {
+  state: 'connecting' | 'live' | 'error';
+  message: string | null;
+}
+
+export interface RecordingConfig {
+  folder: string; // empty uses Music/Soundscapes Recordings
+  stems: boolean; // one file per bus (music, ambient, soundboard) instead of the mix
+  format: 'wav' | 'mp3' | 'ogg' | 'opus';
+  bitrate: number; // kbps, for the compressed formats
+  split_minutes: number; // start a new numbered file after this long; 0 = never
+  split_mb: number; // ...or once a file reaches this size; 0 = never
+  normalize: boolean; // normalize finished files (not stems), renders and replay saves
+  target_lufs: number;
+}
+
+export interface RecordingFile {
+  path: string;
+  name: string;
+  format: 'wav' | 'mp3' | 'ogg' | 'opus';
+  size_bytes: number;
+  modified: string; // RFC 3339
+}
+
+export interface ReplayConfig {
+  enabled: boolean;
+  minutes: number; // how much of the mix to keep, 1-60
+}
+
+export interface MicrophoneConfig {
+  enabled: boolean;
+  volume: number; // 0-100
+  reverb: number; // 0-100
+}
+
+// What the audio does while the app is in the background
+export interface BackgroundAudioConfig {
+  policy: 'keep_playing' | 'duck' | 'pause';
+  trigger: 'minimized' | 'unfocused';
+}
+
+// app://error payload; code says what kind of failure it was, context is usually the file involved
+export interface AppErrorEvent {
+  code: 'audio_output' | 'file_open' | 'decode' | 'sink' | 'announcement' | 'recording' | 'render';
+  message: string;
+  context: string | null;
+  timestamp: string; // RFC 3339
+}
+
+// Volume of an ambient category (its folder name) on top of the ambient master
+export interface CategoryVolume {
+  volume: number; // 0-100
+  muted: boolean;
+}
+
+// What to do when a preset, schedule, playlist or favorites file changed on another machine
+// since this app loaded it
+export interface SyncConfig {
+  on_conflict: 'reload' | 'overwrite' | 'duplicate';
+}
+
+// data://changed payload; 'soundboard' and 'ambient' only come from undo/redo
+export interface DataChanged {
+  kind: 'presets' | 'schedules' | 'playlists' | 'favorites' | 'soundboard' | 'ambient';
+  path: string;
+}
+
+// What undo/redo restored
+export interface UndoneEdit {
+  kind: 'preset' | 'playlist' | 'soundboard' | 'ambient';
+  label: string;
+  path: string;
+}
+
+// Labels of the edits undo/redo would restore next
+export interface HistoryState {
+  undo: string | null;
+  redo: string | null;
+}
+
+// settings://changed payload; changed lists the top-level keys edited outside the app
+export interface SettingsChanged {
+  settings: AppSettings;
+  changed: string[];
+}
+
+// rescan result; stats count the folders found and how many of them were read again
+export interface ScanStats {
+  folders: number;
+  reread: number;
+}
+
+export interface LibraryScan {
+  albums: MusicAlbum[];
+  categories: AmbientCategory[];
+  soundboard: SoundboardData;
+  music_stats: ScanStats;
+  ambient_stats: ScanStats;
+}
+
+// validate_settings result; fixed issues were corrected in settings, the rest are warnings
+export interface SettingIssue {
+  key: string;
+  message: string;
+  fixed: boolean;
+}
+
+export interface SettingsValidation {
+  settings: AppSettings;
+  issues: SettingIssue[];
+}
+
+// validate_content_folders result; path is the metadata.json, id the track or sound if any
+export interface ContentIssue {
+  kind: 'unreadable' | 'schema' | 'missing_file' | 'duplicate_id';
+  library: 'music' | 'ambient' | 'soundboard';
+  path: string;
+  id: string | null;
+  message: string;
+}
+
+export interface ContentValidation {
+  files: number;
+  issues: ContentIssue[];
+}
+
+// What a failed invoke() rejects with; path is set for decode_error and io_error
+export interface AppError {
+  kind: 'not_found' | 'decode_error' | 'io_error' | 'invalid_argument' | 'conflict' | 'unavailable' | 'other';
+  message: string;
+  path?: string;
+}
+
+export interface ReplaySaved {
+  path: string;
+  duration_secs: number;
+  error: string | null;
+}
+
+export interface RecordingStatus {
+  files: string[];
+  elapsed_secs: number;
+  error: string | null;
+}
+
+export type WebhookEvent = 'preset_changed' | 'track_started' | 'schedule_advanced';
+
+export interface Webhook {
+  url: string;
+  events: WebhookEvent[]; // empty sends every event
+}
+
+export interface ObsSceneMapping {
+  scene: string;
+  preset_id: string | null;
+  playlist_id: string | null;
+}
+
+export interface FftTrigger {
+  name: string;
+  source: 'music' | 'ambient' | 'soundboard';
+  band_start: number;
+  band_end: number;
+  threshold: number;
+  hold_ms: number;
+}
+
 export interface AppSettings {
   music_folder_path: string;
   ambient_folder_path: string;
@@ -101,6 +327,46 @@ export interface AppSettings {
   music_volume: number;
   ambient_volume: number;
   soundboard_volume: number;
+  music_skip_crossfade_duration: number;
+  ambient_preset_crossfade_duration: number;
+  music_loudness_matching: boolean;
+  music_skip_on_silence: boolean;
+  visualization_frequency_scale: 'linear' | 'log' | 'mel';
+  visualization_event_rate: number;
+  visualization_attack: number;
+  visualization_decay: number;
+  fft_triggers: FftTrigger[];
+  remote_api_enabled: boolean;
+  remote_api_port: number;
+  remote_api_token: string;
+  remote_api_allow_lan: boolean;
+  osc_enabled: boolean;
+  osc_port: number;
+  osc_feedback_host: string;
+  osc_feedback_port: number;
+  streamdeck_enabled: boolean;
+  streamdeck_port: number;
+  obs_enabled: boolean;
+  obs_url: string;
+  obs_password: string;
+  obs_scene_mappings: ObsSceneMapping[];
+  streaming: StreamingConfig;
+  recording: RecordingConfig;
+  replay_buffer: ReplayConfig;
+  external_player: ExternalPlayerConfig;
+  microphone: MicrophoneConfig;
+  background_audio: BackgroundAudioConfig;
+  webhooks: Webhook[];
+  audio_tick_ms: number;
+  audio_realtime_priority: boolean;
+  output_device: string; // device id from get_output_devices; '' = system default
+  soundboard_output_device: string; // device id for the soundboard alone; '' = same as output_device
+  panic_mute_shortcut: string;
+  night_mode: boolean;
+  ambient_mmap_threshold_mb: number;
+  log_level: 'off' | 'error' | 'warn' | 'info' | 'debug' | 'trace';
+  data_sync: SyncConfig;
+  ambient_category_volumes: Record<string, CategoryVolume>;
 }
 
 export type ActivePanel = 'music' | 'ambient' | 'soundboard' | 'settings' | null;
@@ -113,6 +379,22 @@ export interface ScheduledItem {
   minMinutes: number;
   maxMinutes: number;
   order: number;
+  announcement?: string | null; // spoken when the schedule reaches this item
+  events?: ScheduledEvent[]; // one-shot soundboard sounds during the item
+}
+
+// Soundboard sound played once during a scheduled item
+export interface ScheduledEvent {
+  soundId: string;
+  volume: number; // 0-100
+  offsetSecs?: number | null; // seconds after the item starts; unset = a random time during the item
+}
+
+// Soundboard sound played as a schedule moves on to the next item
+export interface TransitionChime {
+  soundId: string;
+  volume: number; // 0-100
+  leadInSecs: number; // how long before the switch it plays; 0 = at the switch
 }
 
 export interface SchedulePreset {
@@ -121,6 +403,20 @@ export interface SchedulePreset {
   created: string;
   modified: string;
   items: ScheduledItem[];
+  transition?: TransitionChime;
+}
+
+// Offline schedule render events (render://progress, render://finished)
+export interface RenderProgress {
+  rendered_secs: number;
+  total_secs: number;
+}
+
+export interface RenderFinished {
+  output_path: string;
+  duration_secs: number;
+  error: string | null;
+  cancelled: boolean;
 }
 
 export interface SchedulePresetInfo {
@@ -129,8 +425,12 @@ export interface SchedulePresetInfo {
   created: string;
   modified: string;
   itemCount: number;
+  etag: string;
 }
 
+// Shift-click on a stop button tails the sound off over this long instead of cutting it
+export const SLOW_STOP_FADE_MS = 4000;
+
 export const DEFAULT_AMBIENT_SETTINGS: AmbientSoundDefaults = {
   volume: 50,
   pitch: 1.0,
@@ -143,4 +443,5 @@ export const DEFAULT_AMBIENT_SETTINGS: AmbientSoundDefaults = {
   pauseRangeMin: 0,
   pauseRangeMax: 0,
   volumeVariation: 0,
+  eventProbability: 100,
 };
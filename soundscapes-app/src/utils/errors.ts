@@ -0,0 +1,16 @@
+import { AppError } from '../types';
+
+export function isAppError(error: unknown): error is AppError {
+  return typeof error === 'object' && error !== null && 'kind' in error && 'message' in error;
+}
+
+// Text to show for an error thrown by invoke() or anything else
+export function errorMessage(error: unknown): string {
+  if (isAppError(error)) {
+    return error.path ? `${error.message} (${error.path})` : error.message;
+  }
+  if (error instanceof Error) {
+    return error.message;
+  }
+  return String(error);
+}
@@ -0,0 +1,15 @@
+// A file named in a metadata.json, inside its folder. Uses the separator the folder already has, so
+// Windows drive paths and network shares (\\server\share) keep backslashes, and the file's own
+// slashes are turned to match.
+export function joinPath(folder: string, file: string): string {
+  const separator = folder.includes('\\') && !folder.includes('/') ? '\\' : '/';
+  const base = folder.replace(/[\\/]+$/, '');
+  const relative = file.replace(/^[\\/]+/, '').replace(/[\\/]+/g, separator);
+  return `${base}${separator}${relative}`;
+}
+
+// Last part of a path, e.g. the folder name of an ambient category
+export function baseName(path: string): string {
+  const parts = path.split(/[\\/]+/).filter(Boolean);
+  return parts[parts.length - 1] ?? '';
+}
@@ -5,12 +5,17 @@ import { AmbientSoundscapes } from '../components/AmbientSoundscapes/AmbientSoun
 import { useSettingsStore } from '../stores/settingsStore';
 import { useAmbientStore } from '../stores/ambientStore';
 import { useAudioStore } from '../stores/audioStore';
+import { useSettingsReload } from '../hooks/useSettingsReload';
+import { useStateEvents } from '../hooks/useStateEvents';
 
 function AmbientWindow() {
   const { settings, loadSettings } = useSettingsStore();
-  const { loadCategories } = useAmbientStore();
+  const { loadCategories, loadCategoryVolumes } = useAmbientStore();
   const { initAudio } = useAudioStore();
 
+  useSettingsReload();
+  useStateEvents();
+
   useEffect(() => {
     const init = async () => {
       await loadSettings();
@@ -22,6 +27,7 @@ function AmbientWindow() {
   useEffect(() => {
     if (settings) {
       loadCategories(settings.ambient_folder_path);
+      loadCategoryVolumes(settings.ambient_category_volumes ?? {});
     }
   }, [settings]);
 
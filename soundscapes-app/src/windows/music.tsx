@@ -5,12 +5,17 @@ import { MusicPlaylist } from '../components/MusicPlaylist/MusicPlaylist';
 import { useSettingsStore } from '../stores/settingsStore';
 import { usePlaylistStore } from '../stores/playlistStore';
 import { useAudioStore } from '../stores/audioStore';
+import { useSettingsReload } from '../hooks/useSettingsReload';
+import { useStateEvents } from '../hooks/useStateEvents';
 
 function MusicWindow() {
   const { settings, loadSettings } = useSettingsStore();
   const { loadAlbums } = usePlaylistStore();
   const { initAudio } = useAudioStore();
 
+  useSettingsReload();
+  useStateEvents();
+
   useEffect(() => {
     const init = async () => {
       await loadSettings();
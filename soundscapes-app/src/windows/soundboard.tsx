@@ -5,12 +5,15 @@ import { Soundboard } from '../components/Soundboard/Soundboard';
 import { useSettingsStore } from '../stores/settingsStore';
 import { useSoundboardStore } from '../stores/soundboardStore';
 import { useAudioStore } from '../stores/audioStore';
+import { useSettingsReload } from '../hooks/useSettingsReload';
 
 function SoundboardWindow() {
   const { settings, loadSettings } = useSettingsStore();
   const { loadSounds } = useSoundboardStore();
   const { initAudio } = useAudioStore();
 
+  useSettingsReload();
+
   useEffect(() => {
     const init = async () => {
       await loadSettings();